@@ -52,6 +52,28 @@ impl error::Error for ConfigError {
 }
 
 
+/// One step of the breadcrumb path attached to a [`MessageParsingError::WithPath`], pinpointing
+/// where within the paragraph/fragment tree a parsing failure occurred.
+#[derive(Debug)]
+pub(crate) enum PathSegment {
+    Paragraph(usize),
+    Fragment(usize),
+    Field(&'static str),
+}
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Paragraph(index)
+                => write!(f, "paragraph {}", index),
+            PathSegment::Fragment(index)
+                => write!(f, "fragment {}", index),
+            PathSegment::Field(name)
+                => write!(f, "field {:?}", name),
+        }
+    }
+}
+
+
 #[derive(Debug)]
 pub(crate) enum MessageParsingError {
     UnexpectedFragment(String, String),
@@ -65,8 +87,24 @@ pub(crate) enum MessageParsingError {
     LinkValuePlainTextNotString,
     TargetValueNotSinglePlainText(String),
     InnerValueNotList,
+    /// Wraps another [`MessageParsingError`] with the breadcrumb path (outermost segment first)
+    /// leading to the value that failed to parse, so the error points at the exact location
+    /// instead of a bare type name.
+    WithPath(Vec<PathSegment>, Box<MessageParsingError>),
+}
+impl MessageParsingError {
+    /// Prepends `segment` to this error's breadcrumb path, wrapping it in [`Self::WithPath`] if it
+    /// is not one already.
+    pub(crate) fn with_path(self, segment: PathSegment) -> Self {
+        match self {
+            MessageParsingError::WithPath(mut segments, inner) => {
+                segments.insert(0, segment);
+                MessageParsingError::WithPath(segments, inner)
+            },
+            other => MessageParsingError::WithPath(vec![segment], Box::new(other)),
+        }
+    }
 }
-
 impl fmt::Display for MessageParsingError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -92,6 +130,12 @@ impl fmt::Display for MessageParsingError {
                 => write!(f, "{} value is not a single plaintext entry", value_type),
             MessageParsingError::InnerValueNotList
                 => write!(f, "inner value is not a list"),
+            MessageParsingError::WithPath(segments, inner) => {
+                for segment in segments {
+                    write!(f, "{} > ", segment)?;
+                }
+                write!(f, "{}", inner)
+            },
         }
     }
 }
@@ -1,24 +1,40 @@
 use rocketbot_interface::JsonValueExtensions;
 use rocketbot_interface::message::{Checkbox, Emoji, InlineFragment, ListItem, MessageFragment};
 use serde_json;
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::errors::MessageParsingError;
+use crate::errors::{MessageParsingError, PathSegment};
 
 
-fn parse_inline_fragment(inline: &serde_json::Value) -> Result<InlineFragment, MessageParsingError> {
-    let inline_type = inline["type"].as_str()
-        .ok_or(MessageParsingError::TypeNotString)?;
+fn in_field<T>(result: Result<T, MessageParsingError>, name: &'static str) -> Result<T, MessageParsingError> {
+    result.map_err(|e| e.with_path(PathSegment::Field(name)))
+}
+
+fn parse_inline_fragment(inline: &serde_json::Value, lenient: bool) -> Result<InlineFragment, MessageParsingError> {
+    let inline_type = match inline["type"].as_str() {
+        Some(t) => t,
+        None => {
+            if lenient {
+                return Ok(InlineFragment::Unknown(inline.clone()));
+            }
+            return Err(MessageParsingError::TypeNotString);
+        },
+    };
     match inline_type {
         "PLAIN_TEXT" => {
-            let value = inline["value"].as_str()
-                .ok_or(MessageParsingError::PlainTextValueNotString)?
-                .to_owned();
+            let value = in_field(
+                inline["value"].as_str().ok_or(MessageParsingError::PlainTextValueNotString),
+                "value",
+            )?.to_owned();
             Ok(InlineFragment::PlainText(value))
         },
         "BOLD"|"STRIKE"|"ITALIC" => {
             let mut fragments: Vec<InlineFragment> = Vec::new();
-            for fragment in inline["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                fragments.push(parse_inline_fragment(fragment)?);
+            let members = in_field(inline["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, fragment) in members.enumerate() {
+                let parsed = parse_inline_fragment(fragment, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
+                fragments.push(parsed);
             }
             let result = match inline_type {
                 "BOLD" => InlineFragment::Bold(fragments),
@@ -29,58 +45,75 @@ fn parse_inline_fragment(inline: &serde_json::Value) -> Result<InlineFragment, M
             Ok(result)
         },
         "LINK" => {
-            let value_type = inline["value"]["src"]["type"].as_str()
-                .ok_or(MessageParsingError::LinkValueNotSinglePlainText)?;
+            let value_type = in_field(
+                inline["value"]["src"]["type"].as_str().ok_or(MessageParsingError::LinkValueNotSinglePlainText),
+                "src",
+            )?;
             if value_type != "PLAIN_TEXT" {
-                return Err(MessageParsingError::LinkValueNotSinglePlainText);
+                return Err(MessageParsingError::LinkValueNotSinglePlainText.with_path(PathSegment::Field("src")));
             }
-            let url = inline["value"]["src"]["value"].as_str()
-                .ok_or(MessageParsingError::LinkValuePlainTextNotString)?
-                .to_owned();
+            let url = in_field(
+                inline["value"]["src"]["value"].as_str().ok_or(MessageParsingError::LinkValuePlainTextNotString),
+                "src",
+            )?.to_owned();
 
             let mut label_fragments: Vec<InlineFragment> = Vec::new();
-            for fragment in inline["value"]["label"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                label_fragments.push(parse_inline_fragment(fragment)?);
+            let members = in_field(inline["value"]["label"].members().ok_or(MessageParsingError::InnerValueNotList), "label")?;
+            for (i, fragment) in members.enumerate() {
+                let parsed = parse_inline_fragment(fragment, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
+                label_fragments.push(parsed);
             }
 
             Ok(InlineFragment::Link(url, label_fragments))
         },
-        "MENTION_CHANNEL"|"MENTION_USER"|"EMOJI"|"INLINE_CODE" => {
+        "MENTION_CHANNEL"|"MENTION_USER"|"EMOJI"|"INLINE_CODE"|"KATEX" => {
             if inline_type == "EMOJI" && inline["unicode"].is_string() {
                 // special case: Unicode emoji
                 return Ok(InlineFragment::Emoji(Emoji::Unicode(
                     inline["unicode"].as_str().unwrap().to_owned()
                 )));
             }
-            let value_type = inline["value"]["type"].as_str()
-                .ok_or(MessageParsingError::TargetValueNotSinglePlainText(inline_type.into()))?;
+            let value_type = in_field(
+                inline["value"]["type"].as_str().ok_or(MessageParsingError::TargetValueNotSinglePlainText(inline_type.into())),
+                "value",
+            )?;
             if value_type != "PLAIN_TEXT" {
-                return Err(MessageParsingError::TargetValueNotSinglePlainText(inline_type.into()));
+                return Err(MessageParsingError::TargetValueNotSinglePlainText(inline_type.into()).with_path(PathSegment::Field("value")));
             }
-            let target = inline["value"]["value"].as_str()
-                .ok_or(MessageParsingError::TargetValueNotSinglePlainText(inline_type.into()))?
-                .to_owned();
+            let target = in_field(
+                inline["value"]["value"].as_str().ok_or(MessageParsingError::TargetValueNotSinglePlainText(inline_type.into())),
+                "value",
+            )?.to_owned();
             let result = match inline_type {
                 "MENTION_CHANNEL" => InlineFragment::MentionChannel(target),
                 "MENTION_USER" => InlineFragment::MentionUser(target),
                 "EMOJI" => InlineFragment::Emoji(Emoji::Code(target)),
                 "INLINE_CODE" => InlineFragment::InlineCode(target),
+                "KATEX" => InlineFragment::InlineMath(target),
                 _ => panic!("type does not match pre-filtered types; assume bug"),
             };
             Ok(result)
         },
         other => {
-            Err(MessageParsingError::UnexpectedFragment(other.into(), "inline fragment".into()))
+            if lenient {
+                Ok(InlineFragment::Unknown(inline.clone()))
+            } else {
+                Err(MessageParsingError::UnexpectedFragment(other.into(), "inline fragment".into()))
+            }
         },
     }
 }
 
-fn parse_list_item(item: &serde_json::Value) -> Result<ListItem, MessageParsingError> {
+fn parse_list_item(item: &serde_json::Value, lenient: bool) -> Result<ListItem, MessageParsingError> {
     match item["type"].as_str().ok_or(MessageParsingError::TypeNotString)? {
         "LIST_ITEM" => {
             let mut fragments: Vec<InlineFragment> = Vec::new();
-            for fragment in item["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                fragments.push(parse_inline_fragment(fragment)?);
+            let members = in_field(item["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, fragment) in members.enumerate() {
+                let parsed = parse_inline_fragment(fragment, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
+                fragments.push(parsed);
             }
             Ok(ListItem {
                 label: fragments,
@@ -92,15 +125,17 @@ fn parse_list_item(item: &serde_json::Value) -> Result<ListItem, MessageParsingE
     }
 }
 
-fn parse_checkbox(item: &serde_json::Value) -> Result<Checkbox, MessageParsingError> {
+fn parse_checkbox(item: &serde_json::Value, lenient: bool) -> Result<Checkbox, MessageParsingError> {
     match item["type"].as_str().ok_or(MessageParsingError::TypeNotString)? {
         "TASK" => {
-            let checked = item["status"].as_bool()
-                .ok_or(MessageParsingError::TaskStatusNotBool)?;
+            let checked = in_field(item["status"].as_bool().ok_or(MessageParsingError::TaskStatusNotBool), "status")?;
 
             let mut fragments: Vec<InlineFragment> = Vec::new();
-            for fragment in item["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                fragments.push(parse_inline_fragment(fragment)?);
+            let members = in_field(item["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, fragment) in members.enumerate() {
+                let parsed = parse_inline_fragment(fragment, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
+                fragments.push(parsed);
             }
             Ok(Checkbox {
                 checked,
@@ -113,10 +148,10 @@ fn parse_checkbox(item: &serde_json::Value) -> Result<Checkbox, MessageParsingEr
     }
 }
 
-fn parse_code_line(item: &serde_json::Value) -> Result<InlineFragment, MessageParsingError> {
+fn parse_code_line(item: &serde_json::Value, lenient: bool) -> Result<InlineFragment, MessageParsingError> {
     match item["type"].as_str().ok_or(MessageParsingError::TypeNotString)? {
         "CODE_LINE" => {
-            parse_inline_fragment(&item["value"])
+            in_field(parse_inline_fragment(&item["value"], lenient), "value")
         },
         other => {
             Err(MessageParsingError::UnexpectedFragment(other.into(), "code line".into()))
@@ -124,106 +159,636 @@ fn parse_code_line(item: &serde_json::Value) -> Result<InlineFragment, MessagePa
     }
 }
 
-fn parse_paragraph_fragment(paragraph: &serde_json::Value) -> Result<MessageFragment, MessageParsingError> {
-    match paragraph["type"].as_str().ok_or(MessageParsingError::TypeNotString)? {
+fn parse_paragraph_fragment(paragraph: &serde_json::Value, lenient: bool) -> Result<MessageFragment, MessageParsingError> {
+    let paragraph_type = match paragraph["type"].as_str() {
+        Some(t) => t,
+        None => {
+            if lenient {
+                return Ok(MessageFragment::Unknown(paragraph.clone()));
+            }
+            return Err(MessageParsingError::TypeNotString);
+        },
+    };
+    match paragraph_type {
         "BIG_EMOJI" => {
             let mut emoji: Vec<Emoji> = Vec::new();
-            for big_emoji in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let inline_emoji = parse_inline_fragment(big_emoji)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, big_emoji) in members.enumerate() {
+                let inline_emoji = parse_inline_fragment(big_emoji, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 if let InlineFragment::Emoji(e) = inline_emoji {
                     emoji.push(e);
                 } else {
-                    return Err(MessageParsingError::BigEmojiValueNotEmoji);
+                    return Err(MessageParsingError::BigEmojiValueNotEmoji.with_path(PathSegment::Fragment(i)));
                 }
             }
             Ok(MessageFragment::BigEmoji(emoji))
         },
         "UNORDERED_LIST" => {
             let mut items: Vec<ListItem> = Vec::new();
-            for item in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let list_item = parse_list_item(item)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, item) in members.enumerate() {
+                let list_item = parse_list_item(item, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 items.push(list_item);
             }
             Ok(MessageFragment::UnorderedList(items))
         },
         "QUOTE" => {
             let mut items: Vec<MessageFragment> = Vec::new();
-            for item in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let fragment = parse_paragraph_fragment(item)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, item) in members.enumerate() {
+                let fragment = parse_paragraph_fragment(item, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 items.push(fragment);
             }
             Ok(MessageFragment::Quote(items))
         },
         "TASKS" => {
             let mut tasks: Vec<Checkbox> = Vec::new();
-            for item in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let task = parse_checkbox(item)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, item) in members.enumerate() {
+                let task = parse_checkbox(item, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 tasks.push(task);
             }
             Ok(MessageFragment::Tasks(tasks))
         },
         "ORDERED_LIST" => {
             let mut items: Vec<ListItem> = Vec::new();
-            for item in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let list_item = parse_list_item(item)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, item) in members.enumerate() {
+                let list_item = parse_list_item(item, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 items.push(list_item);
             }
             Ok(MessageFragment::OrderedList(items))
         },
         "PARAGRAPH" => {
             let mut fragments: Vec<InlineFragment> = Vec::new();
-            for frag in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let fragment = parse_inline_fragment(frag)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, frag) in members.enumerate() {
+                let fragment = parse_inline_fragment(frag, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 fragments.push(fragment);
             }
             Ok(MessageFragment::Paragraph(fragments))
         },
         "CODE" => {
-            let language = paragraph["language"].as_str()
-                .ok_or(MessageParsingError::CodeLanguageNotString)?
-                .to_owned();
+            let language = in_field(
+                paragraph["language"].as_str().ok_or(MessageParsingError::CodeLanguageNotString),
+                "language",
+            )?.to_owned();
 
             let mut lines: Vec<InlineFragment> = Vec::new();
-            for line in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let parsed_line = parse_code_line(line)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, line) in members.enumerate() {
+                let parsed_line = parse_code_line(line, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 lines.push(parsed_line);
             }
             Ok(MessageFragment::Code(language, lines))
         },
         "HEADING" => {
-            let level = paragraph["level"].as_u32()
-                .ok_or(MessageParsingError::HeadingLevelNotU32)?;
+            let level = in_field(
+                paragraph["level"].as_u32().ok_or(MessageParsingError::HeadingLevelNotU32),
+                "level",
+            )?;
 
             let mut fragments: Vec<InlineFragment> = Vec::new();
-            for fragment in paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList)? {
-                let parsed_line = parse_inline_fragment(fragment)?;
+            let members = in_field(paragraph["value"].members().ok_or(MessageParsingError::InnerValueNotList), "value")?;
+            for (i, fragment) in members.enumerate() {
+                let parsed_line = parse_inline_fragment(fragment, lenient)
+                    .map_err(|e| e.with_path(PathSegment::Fragment(i)))?;
                 fragments.push(parsed_line);
             }
             Ok(MessageFragment::Heading(level, fragments))
         },
         "LINE_BREAK" => Ok(MessageFragment::LineBreak),
+        "KATEX" => {
+            let value_type = in_field(
+                paragraph["value"]["type"].as_str().ok_or(MessageParsingError::TargetValueNotSinglePlainText("KATEX".into())),
+                "value",
+            )?;
+            if value_type != "PLAIN_TEXT" {
+                return Err(MessageParsingError::TargetValueNotSinglePlainText("KATEX".into()).with_path(PathSegment::Field("value")));
+            }
+            let source = in_field(
+                paragraph["value"]["value"].as_str().ok_or(MessageParsingError::TargetValueNotSinglePlainText("KATEX".into())),
+                "value",
+            )?.to_owned();
+            Ok(MessageFragment::Math(source))
+        },
         other => {
-            Err(MessageParsingError::UnexpectedFragment(other.into(), "message fragment".into()))
+            if lenient {
+                Ok(MessageFragment::Unknown(paragraph.clone()))
+            } else {
+                Err(MessageParsingError::UnexpectedFragment(other.into(), "message fragment".into()))
+            }
         },
     }
 }
 
-pub(crate) fn parse_message(paragraphs: &serde_json::Value) -> Result<Vec<MessageFragment>, MessageParsingError> {
+fn parse_message_inner(paragraphs: &serde_json::Value, lenient: bool) -> Result<Vec<MessageFragment>, MessageParsingError> {
     let mut ret: Vec<MessageFragment> = Vec::new();
-    for pm in paragraphs.members().ok_or(MessageParsingError::InnerValueNotList)? {
-        let fragment = parse_paragraph_fragment(pm)?;
+    let members = paragraphs.members().ok_or(MessageParsingError::InnerValueNotList)?;
+    for (i, pm) in members.enumerate() {
+        let fragment = parse_paragraph_fragment(pm, lenient)
+            .map_err(|e| e.with_path(PathSegment::Paragraph(i)))?;
         ret.push(fragment);
     }
     Ok(ret)
 }
 
+/// Parses a Rocket.Chat message AST strictly: an unrecognized fragment type anywhere in the tree
+/// aborts the whole parse with [`MessageParsingError::UnexpectedFragment`] (wrapped in a
+/// [`MessageParsingError::WithPath`] breadcrumb pinpointing where it occurred). Callers that would
+/// rather keep parsing the rest of the message should use [`parse_message_lenient`] instead.
+pub(crate) fn parse_message(paragraphs: &serde_json::Value) -> Result<Vec<MessageFragment>, MessageParsingError> {
+    parse_message_inner(paragraphs, false)
+}
+
+/// Parses a Rocket.Chat message AST leniently: a fragment of an unrecognized type is wrapped in
+/// [`InlineFragment::Unknown`]/[`MessageFragment::Unknown`] (holding the raw JSON node verbatim)
+/// instead of aborting the parse, so a single new Rocket.Chat node type doesn't break message
+/// handling for the whole tree. The `Unknown` nodes can be re-emitted losslessly by [`emit_message`].
+pub(crate) fn parse_message_lenient(paragraphs: &serde_json::Value) -> Result<Vec<MessageFragment>, MessageParsingError> {
+    parse_message_inner(paragraphs, true)
+}
+
+
+pub(crate) fn emit_inline_fragment(inline: &InlineFragment) -> serde_json::Value {
+    match inline {
+        InlineFragment::PlainText(value) => serde_json::json!({
+            "type": "PLAIN_TEXT",
+            "value": value,
+        }),
+        InlineFragment::Bold(fragments) => serde_json::json!({
+            "type": "BOLD",
+            "value": fragments.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+        }),
+        InlineFragment::Strike(fragments) => serde_json::json!({
+            "type": "STRIKE",
+            "value": fragments.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+        }),
+        InlineFragment::Italic(fragments) => serde_json::json!({
+            "type": "ITALIC",
+            "value": fragments.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+        }),
+        InlineFragment::Link(target, label_fragments) => serde_json::json!({
+            "type": "LINK",
+            "value": {
+                "src": {
+                    "type": "PLAIN_TEXT",
+                    "value": target,
+                },
+                "label": label_fragments.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+            },
+        }),
+        InlineFragment::MentionChannel(target) => serde_json::json!({
+            "type": "MENTION_CHANNEL",
+            "value": {
+                "type": "PLAIN_TEXT",
+                "value": target,
+            },
+        }),
+        InlineFragment::MentionUser(target) => serde_json::json!({
+            "type": "MENTION_USER",
+            "value": {
+                "type": "PLAIN_TEXT",
+                "value": target,
+            },
+        }),
+        InlineFragment::Emoji(Emoji::Code(code)) => serde_json::json!({
+            "type": "EMOJI",
+            "value": {
+                "type": "PLAIN_TEXT",
+                "value": code,
+            },
+            "shortCode": code,
+        }),
+        InlineFragment::Emoji(Emoji::Unicode(unicode)) => serde_json::json!({
+            "type": "EMOJI",
+            "unicode": unicode,
+        }),
+        InlineFragment::InlineCode(code) => serde_json::json!({
+            "type": "INLINE_CODE",
+            "value": {
+                "type": "PLAIN_TEXT",
+                "value": code,
+            },
+        }),
+        InlineFragment::InlineMath(source) => serde_json::json!({
+            "type": "KATEX",
+            "value": {
+                "type": "PLAIN_TEXT",
+                "value": source,
+            },
+        }),
+        InlineFragment::Unknown(value) => value.clone(),
+    }
+}
+
+fn emit_list_item(item: &ListItem) -> serde_json::Value {
+    serde_json::json!({
+        "type": "LIST_ITEM",
+        "value": item.label.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+    })
+}
+
+fn emit_checkbox(checkbox: &Checkbox) -> serde_json::Value {
+    serde_json::json!({
+        "type": "TASK",
+        "status": checkbox.checked,
+        "value": checkbox.label.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+    })
+}
+
+fn emit_code_line(line: &InlineFragment) -> serde_json::Value {
+    serde_json::json!({
+        "type": "CODE_LINE",
+        "value": emit_inline_fragment(line),
+    })
+}
+
+pub(crate) fn emit_paragraph_fragment(fragment: &MessageFragment) -> serde_json::Value {
+    match fragment {
+        MessageFragment::BigEmoji(emoji) => serde_json::json!({
+            "type": "BIG_EMOJI",
+            "value": emoji.iter().map(|e| emit_inline_fragment(&InlineFragment::Emoji(e.clone()))).collect::<Vec<_>>(),
+        }),
+        MessageFragment::UnorderedList(items) => serde_json::json!({
+            "type": "UNORDERED_LIST",
+            "value": items.iter().map(emit_list_item).collect::<Vec<_>>(),
+        }),
+        MessageFragment::Quote(fragments) => serde_json::json!({
+            "type": "QUOTE",
+            "value": fragments.iter().map(emit_paragraph_fragment).collect::<Vec<_>>(),
+        }),
+        MessageFragment::Tasks(checkboxes) => serde_json::json!({
+            "type": "TASKS",
+            "value": checkboxes.iter().map(emit_checkbox).collect::<Vec<_>>(),
+        }),
+        MessageFragment::OrderedList(items) => serde_json::json!({
+            "type": "ORDERED_LIST",
+            "value": items.iter().map(emit_list_item).collect::<Vec<_>>(),
+        }),
+        MessageFragment::Paragraph(fragments) => serde_json::json!({
+            "type": "PARAGRAPH",
+            "value": fragments.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+        }),
+        MessageFragment::Code(language, lines) => serde_json::json!({
+            "type": "CODE",
+            "language": language,
+            "value": lines.iter().map(emit_code_line).collect::<Vec<_>>(),
+        }),
+        MessageFragment::Heading(level, fragments) => serde_json::json!({
+            "type": "HEADING",
+            "level": level,
+            "value": fragments.iter().map(emit_inline_fragment).collect::<Vec<_>>(),
+        }),
+        MessageFragment::Math(source) => serde_json::json!({
+            "type": "KATEX",
+            "value": {
+                "type": "PLAIN_TEXT",
+                "value": source,
+            },
+        }),
+        MessageFragment::Unknown(value) => value.clone(),
+    }
+}
+
+pub(crate) fn emit_message(fragments: &[MessageFragment]) -> serde_json::Value {
+    serde_json::Value::Array(
+        fragments.iter().map(emit_paragraph_fragment).collect()
+    )
+}
+
+
+fn starts_with_at(graphemes: &[&str], index: usize, token: &str) -> bool {
+    let token_graphemes: Vec<&str> = token.graphemes(true).collect();
+    if index + token_graphemes.len() > graphemes.len() {
+        return false;
+    }
+    token_graphemes.iter().enumerate().all(|(offset, tg)| graphemes[index + offset] == *tg)
+}
+
+fn flush_plain_text(plain: &mut String, fragments: &mut Vec<InlineFragment>) {
+    if !plain.is_empty() {
+        fragments.push(InlineFragment::PlainText(std::mem::take(plain)));
+    }
+}
+
+fn scan_until(graphemes: &[&str], start: usize, terminator: &str) -> (String, usize) {
+    let mut content = String::new();
+    let mut i = start;
+    while i < graphemes.len() && !starts_with_at(graphemes, i, terminator) {
+        content.push_str(graphemes[i]);
+        i += 1;
+    }
+    let terminator_len = terminator.graphemes(true).count();
+    let next_i = if starts_with_at(graphemes, i, terminator) { i + terminator_len } else { i };
+    (content, next_i)
+}
+
+fn try_parse_link(graphemes: &[&str], start: usize) -> Option<(usize, usize, String, usize)> {
+    // `start` points at the opening '['
+    let mut i = start + 1;
+    let label_start = i;
+    while i < graphemes.len() && graphemes[i] != "]" {
+        i += 1;
+    }
+    if i >= graphemes.len() {
+        return None;
+    }
+    let label_end = i;
+    i += 1; // skip ']'
+    if graphemes.get(i) != Some(&"(") {
+        return None;
+    }
+    i += 1; // skip '('
+    let url_start = i;
+    while i < graphemes.len() && graphemes[i] != ")" {
+        i += 1;
+    }
+    if i >= graphemes.len() {
+        return None;
+    }
+    let url = graphemes[url_start..i].concat();
+    Some((label_start, label_end, url, i + 1))
+}
+
+fn is_shortcode_char(grapheme: &str) -> bool {
+    grapheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '+' || c == '-')
+}
+
+fn try_parse_shortcode(graphemes: &[&str], start: usize) -> Option<(String, usize)> {
+    // `start` points at the opening ':'
+    let content_start = start + 1;
+    let mut i = content_start;
+    while i < graphemes.len() {
+        if graphemes[i] == ":" {
+            if i == content_start {
+                return None;
+            }
+            return Some((graphemes[content_start..i].concat(), i + 1));
+        }
+        if !is_shortcode_char(graphemes[i]) {
+            return None;
+        }
+        i += 1;
+    }
+    None
+}
+
+fn is_identifier_char(grapheme: &str) -> bool {
+    grapheme.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
+
+fn try_parse_identifier(graphemes: &[&str], start: usize) -> Option<(String, usize)> {
+    let mut i = start;
+    while i < graphemes.len() && is_identifier_char(graphemes[i]) {
+        i += 1;
+    }
+    if i == start {
+        return None;
+    }
+    Some((graphemes[start..i].concat(), i))
+}
+
+/// Parses a run of inline Markdown-ish text into [`InlineFragment`]s, stopping early (without
+/// consuming `terminator`) if `terminator` is encountered. Returns the parsed fragments and the
+/// grapheme index just past the last one consumed (which points at `terminator` if one was found).
+fn parse_inline_run(graphemes: &[&str], start: usize, terminator: Option<&str>) -> (Vec<InlineFragment>, usize) {
+    let mut fragments = Vec::new();
+    let mut plain = String::new();
+    let mut i = start;
+
+    while i < graphemes.len() {
+        if let Some(term) = terminator {
+            if starts_with_at(graphemes, i, term) {
+                break;
+            }
+        }
+
+        if starts_with_at(graphemes, i, "**") || starts_with_at(graphemes, i, "__") {
+            let marker = if starts_with_at(graphemes, i, "**") { "**" } else { "__" };
+            flush_plain_text(&mut plain, &mut fragments);
+            let (inner, next_i) = parse_inline_run(graphemes, i + marker.len(), Some(marker));
+            fragments.push(InlineFragment::Bold(inner));
+            i = if starts_with_at(graphemes, next_i, marker) { next_i + marker.len() } else { next_i };
+            continue;
+        }
+        if starts_with_at(graphemes, i, "~~") {
+            flush_plain_text(&mut plain, &mut fragments);
+            let (inner, next_i) = parse_inline_run(graphemes, i + 2, Some("~~"));
+            fragments.push(InlineFragment::Strike(inner));
+            i = if starts_with_at(graphemes, next_i, "~~") { next_i + 2 } else { next_i };
+            continue;
+        }
+        if starts_with_at(graphemes, i, "`") {
+            flush_plain_text(&mut plain, &mut fragments);
+            let (code, next_i) = scan_until(graphemes, i + 1, "`");
+            fragments.push(InlineFragment::InlineCode(code));
+            i = next_i;
+            continue;
+        }
+        if starts_with_at(graphemes, i, "[") {
+            if let Some((label_start, label_end, url, next_i)) = try_parse_link(graphemes, i) {
+                flush_plain_text(&mut plain, &mut fragments);
+                let (label_fragments, _) = parse_inline_run(&graphemes[label_start..label_end], 0, None);
+                fragments.push(InlineFragment::Link(url, label_fragments));
+                i = next_i;
+                continue;
+            }
+        }
+        if starts_with_at(graphemes, i, ":") {
+            if let Some((code, next_i)) = try_parse_shortcode(graphemes, i) {
+                flush_plain_text(&mut plain, &mut fragments);
+                fragments.push(InlineFragment::Emoji(Emoji::Code(code)));
+                i = next_i;
+                continue;
+            }
+        }
+        if starts_with_at(graphemes, i, "@") {
+            if let Some((name, next_i)) = try_parse_identifier(graphemes, i + 1) {
+                flush_plain_text(&mut plain, &mut fragments);
+                fragments.push(InlineFragment::MentionUser(name));
+                i = next_i;
+                continue;
+            }
+        }
+        if starts_with_at(graphemes, i, "#") {
+            if let Some((name, next_i)) = try_parse_identifier(graphemes, i + 1) {
+                flush_plain_text(&mut plain, &mut fragments);
+                fragments.push(InlineFragment::MentionChannel(name));
+                i = next_i;
+                continue;
+            }
+        }
+        if starts_with_at(graphemes, i, "*") || starts_with_at(graphemes, i, "_") {
+            let marker = if starts_with_at(graphemes, i, "*") { "*" } else { "_" };
+            flush_plain_text(&mut plain, &mut fragments);
+            let (inner, next_i) = parse_inline_run(graphemes, i + 1, Some(marker));
+            fragments.push(InlineFragment::Italic(inner));
+            i = if starts_with_at(graphemes, next_i, marker) { next_i + 1 } else { next_i };
+            continue;
+        }
+
+        plain.push_str(graphemes[i]);
+        i += 1;
+    }
+
+    flush_plain_text(&mut plain, &mut fragments);
+    (fragments, i)
+}
+
+fn parse_inline_sequence(text: &str) -> Result<Vec<InlineFragment>, MessageParsingError> {
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+    let (fragments, _) = parse_inline_run(&graphemes, 0, None);
+    Ok(fragments)
+}
+
+fn parse_task_line(line: &str) -> Option<(bool, &str)> {
+    let trimmed = line.trim_start();
+    trimmed.strip_prefix("- [x] ")
+        .or_else(|| trimmed.strip_prefix("- [X] "))
+        .map(|rest| (true, rest))
+        .or_else(|| trimmed.strip_prefix("- [ ] ").map(|rest| (false, rest)))
+}
+
+fn parse_ordered_item(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let digits_end = trimmed.find(|c: char| !c.is_ascii_digit())?;
+    if digits_end == 0 {
+        return None;
+    }
+    trimmed[digits_end..].strip_prefix(". ")
+}
+
+fn parse_unordered_item(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* "))
+}
+
+/// Parses human-typed Markdown-ish source text into the same [`MessageFragment`] tree the JSON
+/// parser ([`parse_message`]) produces, so bots can accept free-form text instead of pre-parsed
+/// ASTs. Block structure is recognized line-by-line (fenced code, `#` headings, `>` quotes,
+/// `- [ ]`/`- [x]` tasks, `1.` ordered lists, `-`/`*` unordered lists, falling back to a plain
+/// paragraph), while inline structure within each line is recognized via [`parse_inline_run`],
+/// which walks the line grapheme-by-grapheme so multi-codepoint emoji and combining marks are
+/// never split mid-fragment.
+pub(crate) fn parse_markdown(source: &str) -> Result<Vec<MessageFragment>, MessageParsingError> {
+    let mut fragments = Vec::new();
+    let mut lines = source.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("```") {
+            let language = rest.trim().to_owned();
+            let mut code_lines = Vec::new();
+            for code_line in lines.by_ref() {
+                if code_line.trim_end() == "```" {
+                    break;
+                }
+                code_lines.push(InlineFragment::PlainText(code_line.to_owned()));
+            }
+            fragments.push(MessageFragment::Code(language, code_lines));
+            continue;
+        }
+
+        if line.starts_with('#') {
+            let level = line.chars().take_while(|&c| c == '#').count() as u32;
+            let rest = line[level as usize..].trim_start();
+            let inline = parse_inline_sequence(rest)?;
+            fragments.push(MessageFragment::Heading(level, inline));
+            continue;
+        }
+
+        if line.trim_start().starts_with('>') {
+            let mut quote_source = line.trim_start().trim_start_matches('>').trim_start().to_owned();
+            while let Some(next_line) = lines.peek() {
+                if !next_line.trim_start().starts_with('>') {
+                    break;
+                }
+                quote_source.push('\n');
+                quote_source.push_str(next_line.trim_start().trim_start_matches('>').trim_start());
+                lines.next();
+            }
+            let inner = parse_markdown(&quote_source)?;
+            fragments.push(MessageFragment::Quote(inner));
+            continue;
+        }
+
+        if let Some((checked, rest)) = parse_task_line(line) {
+            let mut tasks = vec![Checkbox { checked, label: parse_inline_sequence(rest)? }];
+            while let Some(next_line) = lines.peek() {
+                match parse_task_line(next_line) {
+                    Some((checked, rest)) => {
+                        tasks.push(Checkbox { checked, label: parse_inline_sequence(rest)? });
+                        lines.next();
+                    },
+                    None => break,
+                }
+            }
+            fragments.push(MessageFragment::Tasks(tasks));
+            continue;
+        }
+
+        if let Some(rest) = parse_ordered_item(line) {
+            let mut items = vec![ListItem { label: parse_inline_sequence(rest)? }];
+            while let Some(next_line) = lines.peek() {
+                match parse_ordered_item(next_line) {
+                    Some(rest) => {
+                        items.push(ListItem { label: parse_inline_sequence(rest)? });
+                        lines.next();
+                    },
+                    None => break,
+                }
+            }
+            fragments.push(MessageFragment::OrderedList(items));
+            continue;
+        }
+
+        if let Some(rest) = parse_unordered_item(line) {
+            let mut items = vec![ListItem { label: parse_inline_sequence(rest)? }];
+            while let Some(next_line) = lines.peek() {
+                match parse_unordered_item(next_line) {
+                    Some(rest) => {
+                        items.push(ListItem { label: parse_inline_sequence(rest)? });
+                        lines.next();
+                    },
+                    None => break,
+                }
+            }
+            fragments.push(MessageFragment::UnorderedList(items));
+            continue;
+        }
+
+        let inline = parse_inline_sequence(line)?;
+        fragments.push(MessageFragment::Paragraph(inline));
+    }
+
+    Ok(fragments)
+}
+
 
 #[cfg(test)]
 mod tests {
-    use super::parse_message;
+    use super::{emit_message, parse_message, parse_message_lenient};
     use rocketbot_interface::message::{Emoji, InlineFragment, MessageFragment};
     use serde_json::json;
 
+    fn assert_round_trips(msg: &serde_json::Value) {
+        let parsed = parse_message(msg).unwrap();
+        let emitted = emit_message(&parsed);
+        let reparsed = parse_message(&emitted).unwrap();
+        assert_eq!(parsed, reparsed);
+    }
+
     #[test]
     fn parse_plain() {
         let msg = json!([
@@ -238,6 +803,7 @@ mod tests {
             }
         ]);
         let mut parsed = parse_message(&msg).unwrap();
+        assert_round_trips(&msg);
         assert_eq!(parsed.len(), 1);
         let mut inlines = match parsed.remove(0) {
             MessageFragment::Paragraph(inlines) => inlines,
@@ -280,6 +846,7 @@ mod tests {
             }
         ]);
         let mut parsed = parse_message(&msg).unwrap();
+        assert_round_trips(&msg);
         assert_eq!(parsed.len(), 1);
         let mut inlines = match parsed.remove(0) {
             MessageFragment::Paragraph(inlines) => inlines,
@@ -324,6 +891,7 @@ mod tests {
             }
         ]);
         let mut parsed = parse_message(&msg).unwrap();
+        assert_round_trips(&msg);
         assert_eq!(parsed.len(), 1);
         let mut emoji = match parsed.remove(0) {
             MessageFragment::BigEmoji(emoji) => emoji,
@@ -351,6 +919,7 @@ mod tests {
             }
         ]);
         let mut parsed = parse_message(&msg).unwrap();
+        assert_round_trips(&msg);
         assert_eq!(parsed.len(), 1);
         let mut emoji = match parsed.remove(0) {
             MessageFragment::BigEmoji(emoji) => emoji,
@@ -398,6 +967,7 @@ mod tests {
             },
         ]);
         let mut parsed = parse_message(&msg).unwrap();
+        assert_round_trips(&msg);
         assert_eq!(parsed.len(), 1);
         let mut fragments = match parsed.remove(0) {
             MessageFragment::Paragraph(frags) => frags,
@@ -426,4 +996,224 @@ mod tests {
         };
         assert_eq!(suffix, ")&action=history");
     }
+
+    #[test]
+    fn markdown_bold_and_link() {
+        use super::parse_markdown;
+
+        let mut parsed = parse_markdown("**fett** und [hier](https://example.com/)").unwrap();
+        assert_eq!(parsed.len(), 1);
+        let mut inlines = match parsed.remove(0) {
+            MessageFragment::Paragraph(inlines) => inlines,
+            _ => panic!("not a paragraph"),
+        };
+        assert_eq!(inlines.len(), 3);
+
+        let bold = match inlines.remove(0) {
+            InlineFragment::Bold(inner) => inner,
+            _ => panic!("not bold"),
+        };
+        assert_eq!(bold.len(), 1);
+        assert_eq!(bold[0], InlineFragment::PlainText("fett".to_owned()));
+
+        let middle = match inlines.remove(0) {
+            InlineFragment::PlainText(pt) => pt,
+            _ => panic!("not plain"),
+        };
+        assert_eq!(middle, " und ");
+
+        let (url, mut label) = match inlines.remove(0) {
+            InlineFragment::Link(url, label) => (url, label),
+            _ => panic!("not a link"),
+        };
+        assert_eq!(url, "https://example.com/");
+        assert_eq!(label.len(), 1);
+        assert_eq!(label.remove(0), InlineFragment::PlainText("hier".to_owned()));
+    }
+
+    #[test]
+    fn markdown_mention_and_shortcode() {
+        use super::parse_markdown;
+
+        let mut parsed = parse_markdown("Hallo @ravu in #general :eggplant:").unwrap();
+        assert_eq!(parsed.len(), 1);
+        let inlines = match parsed.remove(0) {
+            MessageFragment::Paragraph(inlines) => inlines,
+            _ => panic!("not a paragraph"),
+        };
+        assert_eq!(inlines.len(), 5);
+        assert_eq!(inlines[1], InlineFragment::MentionUser("ravu".to_owned()));
+        assert_eq!(inlines[3], InlineFragment::MentionChannel("general".to_owned()));
+        assert_eq!(inlines[4], InlineFragment::Emoji(Emoji::Code("eggplant".to_owned())));
+    }
+
+    #[test]
+    fn markdown_task_list() {
+        use super::parse_markdown;
+
+        let mut parsed = parse_markdown("- [x] erledigt\n- [ ] offen").unwrap();
+        assert_eq!(parsed.len(), 1);
+        let mut tasks = match parsed.remove(0) {
+            MessageFragment::Tasks(tasks) => tasks,
+            _ => panic!("not tasks"),
+        };
+        assert_eq!(tasks.len(), 2);
+        let second = tasks.remove(1);
+        assert_eq!(second.checked, false);
+        let first = tasks.remove(0);
+        assert_eq!(first.checked, true);
+    }
+
+    #[test]
+    fn markdown_heading_and_code_fence() {
+        use super::parse_markdown;
+
+        let mut parsed = parse_markdown("## Titel\n```rust\nlet x = 1;\n```").unwrap();
+        assert_eq!(parsed.len(), 2);
+
+        let (level, inlines) = match parsed.remove(0) {
+            MessageFragment::Heading(level, inlines) => (level, inlines),
+            _ => panic!("not a heading"),
+        };
+        assert_eq!(level, 2);
+        assert_eq!(inlines, vec![InlineFragment::PlainText("Titel".to_owned())]);
+
+        let (language, lines) = match parsed.remove(0) {
+            MessageFragment::Code(language, lines) => (language, lines),
+            _ => panic!("not code"),
+        };
+        assert_eq!(language, "rust");
+        assert_eq!(lines, vec![InlineFragment::PlainText("let x = 1;".to_owned())]);
+    }
+
+    #[test]
+    fn parse_inline_katex() {
+        let msg = json!([
+            {
+                "type": "PARAGRAPH",
+                "value": [
+                    {
+                        "type": "KATEX",
+                        "value": {
+                            "type": "PLAIN_TEXT",
+                            "value": "E = mc^2"
+                        }
+                    }
+                ]
+            }
+        ]);
+        let mut parsed = parse_message(&msg).unwrap();
+        assert_round_trips(&msg);
+        assert_eq!(parsed.len(), 1);
+        let mut inlines = match parsed.remove(0) {
+            MessageFragment::Paragraph(inlines) => inlines,
+            _ => panic!("not a paragraph"),
+        };
+        assert_eq!(inlines.len(), 1);
+        let source = match inlines.remove(0) {
+            InlineFragment::InlineMath(source) => source,
+            _ => panic!("not inline math"),
+        };
+        assert_eq!(source, "E = mc^2");
+    }
+
+    #[test]
+    fn parse_block_katex() {
+        let msg = json!([
+            {
+                "type": "KATEX",
+                "value": {
+                    "type": "PLAIN_TEXT",
+                    "value": "\\int_0^1 x \\, dx"
+                }
+            }
+        ]);
+        let mut parsed = parse_message(&msg).unwrap();
+        assert_round_trips(&msg);
+        assert_eq!(parsed.len(), 1);
+        let source = match parsed.remove(0) {
+            MessageFragment::Math(source) => source,
+            _ => panic!("not block math"),
+        };
+        assert_eq!(source, "\\int_0^1 x \\, dx");
+    }
+
+    #[test]
+    fn parse_strict_rejects_unknown_fragment() {
+        use crate::errors::MessageParsingError;
+
+        let msg = json!([
+            {
+                "type": "PARAGRAPH",
+                "value": [
+                    {
+                        "type": "PLAIN_TEXT",
+                        "value": "vorher ",
+                    },
+                    {
+                        "type": "SOME_FUTURE_NODE_TYPE",
+                        "value": "wer weiß",
+                    },
+                ],
+            },
+        ]);
+        let err = parse_message(&msg).unwrap_err();
+        let rendered = err.to_string();
+        assert!(rendered.contains("paragraph 0"), "{}", rendered);
+        assert!(rendered.contains("fragment 1"), "{}", rendered);
+        assert!(matches!(err, MessageParsingError::WithPath(_, _)));
+    }
+
+    #[test]
+    fn parse_lenient_wraps_unknown_fragment() {
+        let msg = json!([
+            {
+                "type": "PARAGRAPH",
+                "value": [
+                    {
+                        "type": "PLAIN_TEXT",
+                        "value": "vorher ",
+                    },
+                    {
+                        "type": "SOME_FUTURE_NODE_TYPE",
+                        "value": "wer weiß",
+                    },
+                ],
+            },
+        ]);
+        let mut parsed = parse_message_lenient(&msg).unwrap();
+        assert_eq!(parsed.len(), 1);
+        let mut inlines = match parsed.remove(0) {
+            MessageFragment::Paragraph(inlines) => inlines,
+            _ => panic!("not a paragraph"),
+        };
+        assert_eq!(inlines.len(), 2);
+        inlines.remove(0);
+        let unknown = match inlines.remove(0) {
+            InlineFragment::Unknown(value) => value,
+            _ => panic!("not unknown"),
+        };
+        assert_eq!(unknown, msg[0]["value"][1]);
+
+        // re-emitting reproduces the original node verbatim
+        let emitted = emit_message(&parse_message_lenient(&msg).unwrap());
+        assert_eq!(emitted, msg);
+    }
+
+    #[test]
+    fn parse_lenient_wraps_unknown_paragraph() {
+        let msg = json!([
+            {
+                "type": "SOME_FUTURE_BLOCK_TYPE",
+                "value": "wer weiß",
+            },
+        ]);
+        let mut parsed = parse_message_lenient(&msg).unwrap();
+        assert_eq!(parsed.len(), 1);
+        let unknown = match parsed.remove(0) {
+            MessageFragment::Unknown(value) => value,
+            _ => panic!("not unknown"),
+        };
+        assert_eq!(unknown, msg[0]);
+    }
 }
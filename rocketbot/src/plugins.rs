@@ -67,8 +67,6 @@ pub(crate) async fn load_plugins(iface: Weak<dyn RocketBotInterface>) -> Vec<Plu
                 Box::new(rocketbot_plugin_grammargen::GrammarGenPlugin::new(iface_weak, inner_config).await)
             } else if plugin_config.name == "group_pressure" {
                 Box::new(rocketbot_plugin_group_pressure::GroupPressurePlugin::new(iface_weak, inner_config).await)
-            } else if plugin_config.name == "hackernews" {
-                Box::new(rocketbot_plugin_hackernews::HackernewsPlugin::new(iface_weak, inner_config).await)
             } else if plugin_config.name == "help" {
                 Box::new(rocketbot_plugin_help::HelpPlugin::new(iface_weak, inner_config).await)
             } else if plugin_config.name == "logger" {
@@ -123,6 +121,8 @@ pub(crate) async fn load_plugins(iface: Weak<dyn RocketBotInterface>) -> Vec<Plu
                 Box::new(rocketbot_plugin_url::UrlPlugin::new(iface_weak, inner_config).await)
             } else if plugin_config.name == "url_commands" {
                 Box::new(rocketbot_plugin_url_commands::UrlCommandsPlugin::new(iface_weak, inner_config).await)
+            } else if plugin_config.name == "url_watcher" {
+                Box::new(rocketbot_plugin_url_watcher::UrlWatcherPlugin::new(iface_weak, inner_config).await)
             } else if plugin_config.name == "user_list_message" {
                 Box::new(rocketbot_plugin_user_list_message::UserListMessagePlugin::new(iface_weak, inner_config).await)
             } else if plugin_config.name == "vaccine" {
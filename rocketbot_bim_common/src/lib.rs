@@ -1,4 +1,8 @@
 pub mod achievements;
+pub mod partial_date;
+pub mod ride_ical;
+pub mod ride_format;
+pub mod ride_stats;
 pub mod ride_table;
 
 
@@ -10,6 +14,8 @@ use indexmap::IndexSet;
 use rocketbot_string::NatSortedString;
 use serde::{Deserialize, Serialize};
 
+use crate::partial_date::{parse_partial_date, PartialDate};
+
 
 pub type VehicleNumber = NatSortedString;
 
@@ -159,7 +165,9 @@ pub struct VehicleInfo {
     #[serde(default)] pub power_sources: BTreeSet<PowerSource>,
     pub type_code: String,
     pub in_service_since: Option<String>,
+    #[serde(default)] pub in_service_since_date: Option<PartialDate>,
     pub out_of_service_since: Option<String>,
+    #[serde(default)] pub out_of_service_since_date: Option<PartialDate>,
     pub manufacturer: Option<String>,
     #[serde(default)] pub depot: Option<String>,
     pub other_data: BTreeMap<String, String>,
@@ -173,13 +181,29 @@ impl VehicleInfo {
             power_sources: BTreeSet::new(),
             type_code,
             in_service_since: None,
+            in_service_since_date: None,
             out_of_service_since: None,
+            out_of_service_since_date: None,
             manufacturer: None,
             depot: None,
             other_data: BTreeMap::new(),
             fixed_coupling: IndexSet::new(),
         }
     }
+
+    /// Sets `in_service_since` to `raw` and, if it can be parsed, `in_service_since_date` to the
+    /// corresponding [`PartialDate`].
+    pub fn set_in_service_since(&mut self, raw: String) {
+        self.in_service_since_date = parse_partial_date(&raw);
+        self.in_service_since = Some(raw);
+    }
+
+    /// Sets `out_of_service_since` to `raw` and, if it can be parsed, `out_of_service_since_date`
+    /// to the corresponding [`PartialDate`].
+    pub fn set_out_of_service_since(&mut self, raw: String) {
+        self.out_of_service_since_date = parse_partial_date(&raw);
+        self.out_of_service_since = Some(raw);
+    }
 }
 
 
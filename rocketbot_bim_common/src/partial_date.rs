@@ -0,0 +1,148 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+
+/// A calendar date (proleptic Gregorian, as per ISO 8601) that may only be known to the precision
+/// of a year, a year and month, or a full year-month-day. Used for in-service/out-of-service dates
+/// scraped from wiki pages, which are rarely given with full precision.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct PartialDate {
+    pub year: i32,
+    pub month: Option<u8>,
+    pub day: Option<u8>,
+}
+impl PartialDate {
+    pub fn new(year: i32, month: Option<u8>, day: Option<u8>) -> Self {
+        Self { year, month, day }
+    }
+}
+impl fmt::Display for PartialDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.month, self.day) {
+            (Some(month), Some(day)) => write!(f, "{:04}-{:02}-{:02}", self.year, month, day),
+            (Some(month), None) => write!(f, "{:04}-{:02}", self.year, month),
+            (None, _) => write!(f, "{:04}", self.year),
+        }
+    }
+}
+
+
+fn is_valid_month(month: u8) -> bool {
+    (1..=12).contains(&month)
+}
+
+fn is_valid_day_for_month(year: i32, month: u8, day: u8) -> bool {
+    NaiveDate::from_ymd_opt(year, month.into(), day.into()).is_some()
+}
+
+/// Parses a run of exactly four ASCII digits, rejecting shorter (ambiguous two-digit year) or
+/// longer digit runs.
+fn parse_four_digit_year(s: &str) -> Option<i32> {
+    if s.len() == 4 && s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn try_parse_iso_ymd(s: &str) -> Option<PartialDate> {
+    let parts: Vec<&str> = s.split('-').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let year = parse_four_digit_year(parts[0].trim())?;
+    let month: u8 = parts[1].trim().parse().ok()?;
+    let day: u8 = parts[2].trim().parse().ok()?;
+    Some(narrow_to_valid(year, month, day))
+}
+
+/// Parses the German-style dotted day-month-year form, e.g. `"15. 3. 1995"` or `"15.3.1995"`.
+fn try_parse_dotted_dmy(s: &str) -> Option<PartialDate> {
+    let parts: Vec<&str> = s.split('.').map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let day: u8 = parts[0].parse().ok()?;
+    let month: u8 = parts[1].parse().ok()?;
+    let year = parse_four_digit_year(parts[2])?;
+    Some(narrow_to_valid(year, month, day))
+}
+
+/// Parses a month/year or month.year form, e.g. `"03/1995"` or `"3.1995"`.
+fn try_parse_my(s: &str) -> Option<PartialDate> {
+    let sep = if s.contains('/') {
+        '/'
+    } else if s.contains('.') {
+        '.'
+    } else {
+        return None;
+    };
+    let parts: Vec<&str> = s.split(sep).map(|p| p.trim()).collect();
+    if parts.len() != 2 {
+        return None;
+    }
+    let month: u8 = parts[0].parse().ok()?;
+    let year = parse_four_digit_year(parts[1])?;
+    if !is_valid_month(month) {
+        return None;
+    }
+    Some(PartialDate::new(year, Some(month), None))
+}
+
+/// Finds the first run of exactly four ASCII digits in `s`, e.g. the `2001` in `"seit 2001"`.
+fn extract_bare_year(s: &str) -> Option<i32> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        if let Some(year) = parse_four_digit_year(&s[start..i]) {
+            return Some(year);
+        }
+    }
+    None
+}
+
+/// Narrows `(year, month, day)` to the highest precision that is actually valid, falling back to
+/// a lower-precision component on an impossible day/month combination.
+fn narrow_to_valid(year: i32, month: u8, day: u8) -> PartialDate {
+    if !is_valid_month(month) {
+        return PartialDate::new(year, None, None);
+    }
+    if !is_valid_day_for_month(year, month, day) {
+        return PartialDate::new(year, Some(month), None);
+    }
+    PartialDate::new(year, Some(month), Some(day))
+}
+
+/// Attempts to parse a free-text in-service/out-of-service date string (as commonly found on wiki
+/// vehicle list pages) into a calendar-aware [`PartialDate`]. Recognizes bare years (`"1995"`),
+/// month/year (`"03/1995"`, `"3.1995"`), full day-month-year (`"15. 3. 1995"`, `"1995-03-15"`) and
+/// open-ended fields (`"seit 2001"`). Returns `None` if no recognizable date could be extracted,
+/// including bare two-digit years, which are deliberately left ambiguous rather than guessed at.
+pub fn parse_partial_date(raw: &str) -> Option<PartialDate> {
+    let trimmed = raw.trim();
+
+    if let Some(date) = try_parse_iso_ymd(trimmed) {
+        return Some(date);
+    }
+    if let Some(date) = try_parse_dotted_dmy(trimmed) {
+        return Some(date);
+    }
+    if let Some(date) = try_parse_my(trimmed) {
+        return Some(date);
+    }
+    if let Some(year) = extract_bare_year(trimmed) {
+        return Some(PartialDate::new(year, None, None));
+    }
+
+    None
+}
@@ -0,0 +1,277 @@
+//! Pluggable (de)serialization of [`RideTableData`] into archival formats.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Local, SecondsFormat};
+
+use crate::CouplingMode;
+use crate::ride_table::{Ride, RideTableData, RideTableVehicle, UserRide};
+
+
+#[derive(Debug)]
+pub enum RideFormatError {
+    Decode(String),
+}
+impl fmt::Display for RideFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "failed to decode ride table data: {}", e),
+        }
+    }
+}
+impl std::error::Error for RideFormatError {
+}
+
+
+/// A (de)serialization target for a batch of [`RideTableData`].
+pub trait RideFormat {
+    /// A short, stable name for this format, used as the key for [`format_by_name`].
+    fn name(&self) -> &'static str;
+
+    /// Encodes the given rides into this format.
+    fn encode(&self, rides: &[RideTableData]) -> Vec<u8>;
+
+    /// Decodes a batch of rides previously produced by [`RideFormat::encode`].
+    fn decode(&self, bytes: &[u8]) -> Result<Vec<RideTableData>, RideFormatError>;
+}
+
+
+/// Looks up a [`RideFormat`] by its [`RideFormat::name`], e.g. `"json-lines"`, `"msgpack"` or
+/// `"csv"`.
+pub fn format_by_name(name: &str) -> Option<Box<dyn RideFormat>> {
+    match name {
+        "json-lines" => Some(Box::new(JsonLinesFormat)),
+        "msgpack" => Some(Box::new(MessagePackFormat)),
+        "csv" => Some(Box::new(CsvFormat)),
+        _ => None,
+    }
+}
+
+
+/// One ride per line, each line a self-contained JSON document.
+pub struct JsonLinesFormat;
+impl RideFormat for JsonLinesFormat {
+    fn name(&self) -> &'static str { "json-lines" }
+
+    fn encode(&self, rides: &[RideTableData]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for ride in rides {
+            serde_json::to_writer(&mut buf, ride)
+                .expect("RideTableData is always JSON-serializable");
+            buf.push(b'\n');
+        }
+        buf
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Vec<RideTableData>, RideFormatError> {
+        let text = std::str::from_utf8(bytes)
+            .map_err(|e| RideFormatError::Decode(e.to_string()))?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line)
+                .map_err(|e| RideFormatError::Decode(e.to_string()))
+            )
+            .collect()
+    }
+}
+
+
+/// All rides encoded as a single compact MessagePack array.
+pub struct MessagePackFormat;
+impl RideFormat for MessagePackFormat {
+    fn name(&self) -> &'static str { "msgpack" }
+
+    fn encode(&self, rides: &[RideTableData]) -> Vec<u8> {
+        rmp_serde::to_vec(rides)
+            .expect("RideTableData is always MessagePack-serializable")
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Vec<RideTableData>, RideFormatError> {
+        rmp_serde::from_slice(bytes)
+            .map_err(|e| RideFormatError::Decode(e.to_string()))
+    }
+}
+
+
+/// One row per [`RideTableVehicle`], with the nested streak/count/last columns expanded and
+/// timestamps rendered as ISO-8601.
+///
+/// `relative_time` and `physical_modes` are purely presentational (they only affect how a ride
+/// table is rendered on the spot) and are not represented in the flattened CSV.
+pub struct CsvFormat;
+impl CsvFormat {
+    const HEADER: &'static [&'static str] = &[
+        "ride_id", "line", "rider_username",
+        "vehicle_number", "vehicle_type", "coupling_mode", "highlight_coupled_rides",
+        "my_same_count_streak", "my_same_count", "my_same_last_timestamp", "my_same_last_line",
+        "my_coupled_count_streak", "my_coupled_count", "my_coupled_last_timestamp", "my_coupled_last_line",
+        "other_same_count_streak", "other_same_count", "other_same_last_rider", "other_same_last_timestamp", "other_same_last_line",
+        "other_coupled_count_streak", "other_coupled_count", "other_coupled_last_rider", "other_coupled_last_timestamp", "other_coupled_last_line",
+    ];
+}
+impl RideFormat for CsvFormat {
+    fn name(&self) -> &'static str { "csv" }
+
+    fn encode(&self, rides: &[RideTableData]) -> Vec<u8> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(Self::HEADER)
+            .expect("writing the CSV header never fails");
+
+        for ride in rides {
+            for vehicle in &ride.vehicles {
+                let (my_same_ts, my_same_line) = ride_columns(vehicle.my_same_last.as_ref());
+                let (my_coupled_ts, my_coupled_line) = ride_columns(vehicle.my_coupled_last.as_ref());
+                let (other_same_rider, other_same_ts, other_same_line) = user_ride_columns(vehicle.other_same_last.as_ref());
+                let (other_coupled_rider, other_coupled_ts, other_coupled_line) = user_ride_columns(vehicle.other_coupled_last.as_ref());
+
+                writer.write_record(&[
+                    ride.ride_id.to_string(),
+                    ride.line.clone().unwrap_or_default(),
+                    ride.rider_username.clone(),
+                    vehicle.vehicle_number.clone(),
+                    vehicle.vehicle_type.clone().unwrap_or_default(),
+                    vehicle.coupling_mode.as_db_str().to_owned(),
+                    vehicle.highlight_coupled_rides.to_string(),
+                    vehicle.my_same_count_streak.to_string(),
+                    vehicle.my_same_count.to_string(),
+                    my_same_ts,
+                    my_same_line,
+                    vehicle.my_coupled_count_streak.to_string(),
+                    vehicle.my_coupled_count.to_string(),
+                    my_coupled_ts,
+                    my_coupled_line,
+                    vehicle.other_same_count_streak.to_string(),
+                    vehicle.other_same_count.to_string(),
+                    other_same_rider,
+                    other_same_ts,
+                    other_same_line,
+                    vehicle.other_coupled_count_streak.to_string(),
+                    vehicle.other_coupled_count.to_string(),
+                    other_coupled_rider,
+                    other_coupled_ts,
+                    other_coupled_line,
+                ]).expect("writing a CSV record never fails");
+            }
+        }
+
+        writer.into_inner()
+            .expect("flushing an in-memory CSV writer never fails")
+    }
+
+    fn decode(&self, bytes: &[u8]) -> Result<Vec<RideTableData>, RideFormatError> {
+        let mut reader = csv::Reader::from_reader(bytes);
+
+        // group the flattened rows back into rides, keeping first-seen order
+        let mut order: Vec<i64> = Vec::new();
+        let mut rides: BTreeMap<i64, RideTableData> = BTreeMap::new();
+
+        for result in reader.records() {
+            let record = result.map_err(|e| RideFormatError::Decode(e.to_string()))?;
+            let get = |i: usize| record.get(i)
+                .ok_or_else(|| RideFormatError::Decode(format!("missing CSV column {}", i)));
+
+            let ride_id: i64 = get(0)?.parse()
+                .map_err(|_| RideFormatError::Decode("invalid ride_id".to_owned()))?;
+            let line = non_empty(get(1)?);
+            let rider_username = get(2)?.to_owned();
+
+            let coupling_mode = CouplingMode::try_from_db_str(get(5)?)
+                .ok_or_else(|| RideFormatError::Decode(format!("unknown coupling mode {:?}", get(5)?)))?;
+            let highlight_coupled_rides: bool = get(6)?.parse()
+                .map_err(|_| RideFormatError::Decode("invalid highlight_coupled_rides".to_owned()))?;
+
+            let vehicle = RideTableVehicle {
+                vehicle_number: get(3)?.to_owned(),
+                vehicle_type: non_empty(get(4)?),
+                my_same_count_streak: parse_i64(get(7)?)?,
+                my_same_count: parse_i64(get(8)?)?,
+                my_same_last: parse_ride(get(9)?, get(10)?)?,
+                my_coupled_count_streak: parse_i64(get(11)?)?,
+                my_coupled_count: parse_i64(get(12)?)?,
+                my_coupled_last: parse_ride(get(13)?, get(14)?)?,
+                other_same_count_streak: parse_i64(get(15)?)?,
+                other_same_count: parse_i64(get(16)?)?,
+                other_same_last: parse_user_ride(get(17)?, get(18)?, get(19)?)?,
+                other_coupled_count_streak: parse_i64(get(20)?)?,
+                other_coupled_count: parse_i64(get(21)?)?,
+                other_coupled_last: parse_user_ride(get(22)?, get(23)?, get(24)?)?,
+                highlight_coupled_rides,
+                coupling_mode,
+            };
+
+            let ride_data = rides.entry(ride_id).or_insert_with(|| {
+                order.push(ride_id);
+                RideTableData {
+                    ride_id,
+                    line: line.clone(),
+                    rider_username: rider_username.clone(),
+                    vehicles: Vec::new(),
+                    relative_time: None,
+                    physical_modes: Vec::new(),
+                }
+            });
+            ride_data.vehicles.push(vehicle);
+        }
+
+        Ok(order.into_iter().map(|ride_id| rides.remove(&ride_id).unwrap()).collect())
+    }
+}
+
+fn non_empty(s: &str) -> Option<String> {
+    if s.is_empty() { None } else { Some(s.to_owned()) }
+}
+
+fn parse_i64(s: &str) -> Result<i64, RideFormatError> {
+    s.parse().map_err(|_| RideFormatError::Decode(format!("invalid integer {:?}", s)))
+}
+
+fn parse_timestamp(s: &str) -> Result<DateTime<Local>, RideFormatError> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Local))
+        .map_err(|e| RideFormatError::Decode(format!("invalid timestamp {:?}: {}", s, e)))
+}
+
+fn parse_ride(timestamp: &str, line: &str) -> Result<Option<Ride>, RideFormatError> {
+    if timestamp.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Ride {
+        timestamp: parse_timestamp(timestamp)?,
+        line: non_empty(line),
+    }))
+}
+
+fn parse_user_ride(rider_username: &str, timestamp: &str, line: &str) -> Result<Option<UserRide>, RideFormatError> {
+    if timestamp.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(UserRide {
+        rider_username: rider_username.to_owned(),
+        ride: Ride {
+            timestamp: parse_timestamp(timestamp)?,
+            line: non_empty(line),
+        },
+    }))
+}
+
+fn ride_columns(ride: Option<&Ride>) -> (String, String) {
+    match ride {
+        Some(r) => (
+            r.timestamp.to_rfc3339_opts(SecondsFormat::Secs, false),
+            r.line.clone().unwrap_or_default(),
+        ),
+        None => (String::new(), String::new()),
+    }
+}
+
+fn user_ride_columns(user_ride: Option<&UserRide>) -> (String, String, String) {
+    match user_ride {
+        Some(ur) => (
+            ur.rider_username.clone(),
+            ur.ride.timestamp.to_rfc3339_opts(SecondsFormat::Secs, false),
+            ur.ride.line.clone().unwrap_or_default(),
+        ),
+        None => (String::new(), String::new(), String::new()),
+    }
+}
@@ -0,0 +1,104 @@
+//! Exports a rider's ride history as an RFC 5545 `VCALENDAR`, suitable for subscribing to as a
+//! calendar feed.
+
+use std::fmt::Write;
+
+use chrono::{Duration, Utc};
+
+use crate::ride_table::{Ride, RideTableData, RideTableVehicle};
+
+
+/// How long a ride's calendar event is shown as lasting. Rides are effectively instantaneous, so
+/// this is purely cosmetic (a zero-duration event renders poorly in most calendar clients).
+const EVENT_DURATION_MINUTES: i64 = 1;
+
+/// The maximum number of octets per content line before RFC 5545 folding kicks in.
+const FOLD_LIMIT_OCTETS: usize = 75;
+
+/// Escapes `text` for use inside an iCalendar `TEXT` value, per RFC 5545 section 3.3.11.
+fn escape_ical_text(text: &str) -> String {
+    text
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a single already-escaped content line at [`FOLD_LIMIT_OCTETS`] octets, inserting
+/// `CRLF` followed by a single space before each continuation, without splitting a multi-byte
+/// UTF-8 character across the boundary.
+fn fold_ical_line(line: &str) -> String {
+    let bytes = line.as_bytes();
+    if bytes.len() <= FOLD_LIMIT_OCTETS {
+        return line.to_owned();
+    }
+
+    let mut folded = String::new();
+    let mut start = 0;
+    let mut first = true;
+    while start < bytes.len() {
+        // the leading space of a continuation line counts toward its own 75-octet budget
+        let budget = if first { FOLD_LIMIT_OCTETS } else { FOLD_LIMIT_OCTETS - 1 };
+        let mut end = (start + budget).min(bytes.len());
+        while end > start && (bytes[end] & 0b1100_0000) == 0b1000_0000 {
+            end -= 1;
+        }
+
+        if !first {
+            folded.push_str("\r\n ");
+        }
+        folded.push_str(&line[start..end]);
+
+        start = end;
+        first = false;
+    }
+    folded
+}
+
+/// Appends `line`, folded and `CRLF`-terminated, to `ics`.
+fn write_ical_line(ics: &mut String, line: &str) {
+    ics.push_str(&fold_ical_line(line));
+    ics.push_str("\r\n");
+}
+
+/// Renders the rider's own same-vehicle ride history (`RideTableVehicle::my_same_last`) as a
+/// `VCALENDAR` text. Each vehicle contributes at most one `VEVENT`, keyed by `ride_id` and
+/// vehicle number so that re-exporting the same ride table never produces duplicate events.
+pub fn rides_to_icalendar(rides: &[RideTableData]) -> String {
+    let mut ics = String::new();
+    write_ical_line(&mut ics, "BEGIN:VCALENDAR");
+    write_ical_line(&mut ics, "VERSION:2.0");
+    write_ical_line(&mut ics, "PRODID:-//rocketbot//bim-rides//EN");
+
+    for ride in rides {
+        for vehicle in &ride.vehicles {
+            if let Some(my_same_last) = vehicle.my_same_last.as_ref() {
+                write_ride_event(&mut ics, ride, vehicle, my_same_last);
+            }
+        }
+    }
+
+    write_ical_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+fn write_ride_event(ics: &mut String, ride: &RideTableData, vehicle: &RideTableVehicle, occurrence: &Ride) {
+    let dtstart = occurrence.timestamp.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ");
+    let dtend = (occurrence.timestamp + Duration::minutes(EVENT_DURATION_MINUTES))
+        .with_timezone(&Utc).format("%Y%m%dT%H%M%SZ");
+
+    let mut summary = vehicle.vehicle_number.clone();
+    if let Some(vehicle_type) = vehicle.vehicle_type.as_ref() {
+        write!(&mut summary, " ({})", vehicle_type).unwrap();
+    }
+    if let Some(line) = occurrence.line.as_ref().or(ride.line.as_ref()) {
+        write!(&mut summary, " on {}", line).unwrap();
+    }
+
+    write_ical_line(ics, "BEGIN:VEVENT");
+    write_ical_line(ics, &format!("UID:bim-ride-{}-{}@rocketbot", ride.ride_id, vehicle.vehicle_number));
+    write_ical_line(ics, &format!("DTSTART:{}", dtstart));
+    write_ical_line(ics, &format!("DTEND:{}", dtend));
+    write_ical_line(ics, &format!("SUMMARY:{}", escape_ical_text(&summary)));
+    write_ical_line(ics, "END:VEVENT");
+}
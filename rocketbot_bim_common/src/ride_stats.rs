@@ -0,0 +1,177 @@
+//! Aggregate frequency-analysis reports folded from ride table data.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Timelike};
+use rocketbot_string::NatSortedString;
+use serde::{Deserialize, Serialize};
+
+use crate::ride_table::{Ride, RideTableData, RideTableVehicle};
+
+
+/// A single vehicle's share of a leaderboard.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct VehicleTally {
+    pub vehicle_number: String,
+    pub count: i64,
+}
+
+/// A single rider's share of a leaderboard.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct RiderTally {
+    pub rider_username: String,
+    pub count: i64,
+}
+
+/// A single line's share of a leaderboard.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct LineTally {
+    pub line: Option<String>,
+    pub count: i64,
+}
+
+/// A ride-count histogram bucketed by weekday (`counts[weekday]`, `0` = Monday) and hour of day
+/// (`counts[weekday][hour]`, `0..24`).
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HourWeekdayHistogram {
+    pub counts: [[u64; 24]; 7],
+}
+impl Default for HourWeekdayHistogram {
+    fn default() -> Self {
+        Self { counts: [[0; 24]; 7] }
+    }
+}
+impl HourWeekdayHistogram {
+    fn record(&mut self, ride: &Ride) {
+        let weekday = ride.timestamp.weekday().num_days_from_monday() as usize;
+        let hour = ride.timestamp.hour() as usize;
+        self.counts[weekday][hour] += 1;
+    }
+}
+
+/// The aggregate report emitted by [`RideStats::finish`]. Plain data, renderable as a chat
+/// message or fed into the PDF renderer.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct RideStatsReport {
+    /// Vehicles ridden most often by the same rider in a row, highest `my_same_count` first.
+    pub top_vehicles_by_same_count: Vec<VehicleTally>,
+
+    /// Vehicles with the longest same-vehicle ride streak (`my_same_count_streak`), longest first.
+    pub longest_same_count_streaks: Vec<VehicleTally>,
+
+    /// Vehicles with the longest coupled-vehicle ride streak (`my_coupled_count_streak`), longest
+    /// first.
+    pub longest_coupled_count_streaks: Vec<VehicleTally>,
+
+    /// Total rides per rider, highest first.
+    pub rider_totals: Vec<RiderTally>,
+
+    /// Total rides per line, highest first. Rides without a known line are tallied under `None`.
+    pub line_totals: Vec<LineTally>,
+
+    /// Ride counts bucketed by weekday and hour of day, derived from the timestamp of the most
+    /// recent same-vehicle and coupled-vehicle ride of each vehicle.
+    pub hour_weekday_histogram: HourWeekdayHistogram,
+}
+
+/// Folds a collection of [`RideTableData`]/[`RideTableVehicle`] into a [`RideStatsReport`].
+#[derive(Clone, Debug, Default)]
+pub struct RideStats {
+    same_count_by_vehicle: BTreeMap<String, i64>,
+    same_count_streak_by_vehicle: BTreeMap<String, i64>,
+    coupled_count_streak_by_vehicle: BTreeMap<String, i64>,
+    total_by_rider: BTreeMap<String, i64>,
+    total_by_line: BTreeMap<Option<String>, i64>,
+    histogram: HourWeekdayHistogram,
+}
+impl RideStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Folds in a single ride and all the vehicles it covers.
+    pub fn add_ride(&mut self, ride: &RideTableData) {
+        *self.total_by_rider.entry(ride.rider_username.clone()).or_insert(0) += 1;
+        *self.total_by_line.entry(ride.line.clone()).or_insert(0) += 1;
+
+        for vehicle in &ride.vehicles {
+            self.add_vehicle(vehicle);
+        }
+    }
+
+    /// Folds in every ride in `rides`.
+    pub fn add_rides<'r, I: IntoIterator<Item = &'r RideTableData>>(&mut self, rides: I) {
+        for ride in rides {
+            self.add_ride(ride);
+        }
+    }
+
+    fn add_vehicle(&mut self, vehicle: &RideTableVehicle) {
+        let number = &vehicle.vehicle_number;
+
+        *self.same_count_by_vehicle.entry(number.clone()).or_insert(0) += vehicle.my_same_count;
+
+        let same_streak = self.same_count_streak_by_vehicle.entry(number.clone()).or_insert(0);
+        if vehicle.my_same_count_streak > *same_streak {
+            *same_streak = vehicle.my_same_count_streak;
+        }
+
+        let coupled_streak = self.coupled_count_streak_by_vehicle.entry(number.clone()).or_insert(0);
+        if vehicle.my_coupled_count_streak > *coupled_streak {
+            *coupled_streak = vehicle.my_coupled_count_streak;
+        }
+
+        if let Some(my_same_last) = vehicle.my_same_last.as_ref() {
+            self.histogram.record(my_same_last);
+        }
+        if let Some(my_coupled_last) = vehicle.my_coupled_last.as_ref() {
+            self.histogram.record(my_coupled_last);
+        }
+    }
+
+    /// Finalizes the fold into a [`RideStatsReport`], sorting each leaderboard by descending
+    /// count and breaking ties by ascending (naturally-sorted) vehicle number or rider/line name.
+    pub fn finish(self) -> RideStatsReport {
+        RideStatsReport {
+            top_vehicles_by_same_count: vehicle_tallies(self.same_count_by_vehicle),
+            longest_same_count_streaks: vehicle_tallies(self.same_count_streak_by_vehicle),
+            longest_coupled_count_streaks: vehicle_tallies(self.coupled_count_streak_by_vehicle),
+            rider_totals: rider_tallies(self.total_by_rider),
+            line_totals: line_tallies(self.total_by_line),
+            hour_weekday_histogram: self.histogram,
+        }
+    }
+}
+
+fn vehicle_tallies(counts: BTreeMap<String, i64>) -> Vec<VehicleTally> {
+    let mut tallies: Vec<VehicleTally> = counts.into_iter()
+        .map(|(vehicle_number, count)| VehicleTally { vehicle_number, count })
+        .collect();
+    tallies.sort_by(|a, b| {
+        b.count.cmp(&a.count)
+            .then_with(|| NatSortedString::from(a.vehicle_number.clone()).cmp(&NatSortedString::from(b.vehicle_number.clone())))
+    });
+    tallies
+}
+
+fn rider_tallies(counts: BTreeMap<String, i64>) -> Vec<RiderTally> {
+    let mut tallies: Vec<RiderTally> = counts.into_iter()
+        .map(|(rider_username, count)| RiderTally { rider_username, count })
+        .collect();
+    tallies.sort_by(|a, b| {
+        b.count.cmp(&a.count)
+            .then_with(|| a.rider_username.cmp(&b.rider_username))
+    });
+    tallies
+}
+
+fn line_tallies(counts: BTreeMap<Option<String>, i64>) -> Vec<LineTally> {
+    let mut tallies: Vec<LineTally> = counts.into_iter()
+        .map(|(line, count)| LineTally { line, count })
+        .collect();
+    tallies.sort_by(|a, b| {
+        b.count.cmp(&a.count)
+            .then_with(|| a.line.cmp(&b.line))
+    });
+    tallies
+}
@@ -2,7 +2,7 @@ use chrono::{DateTime, Local, TimeZone};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 
-use crate::{CouplingMode, LastRider, format_timestamp};
+use crate::{CouplingMode, LastRider, VehicleClass, format_timestamp};
 
 
 #[derive(Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -23,6 +23,12 @@ pub struct RideTableData {
     /// the ride happened within the past 24 hours. `None` causes the full timestamp to always be
     /// shown.
     pub relative_time: Option<DateTime<Local>>,
+
+    /// The physical modes (tram/bus/metro/etc.) that serve this ride's line, as resolved against
+    /// an imported GTFS feed. Empty if no GTFS feed is configured for the company, the ride has no
+    /// line, or the line could not be resolved.
+    #[serde(default)]
+    pub physical_modes: Vec<VehicleClass>,
 }
 
 
@@ -1,7 +1,8 @@
-use crate::graph_drawing::{Canvas, ChartColor, GRAPH_COLORS};
+use crate::{AxisScale, Canvas, ChartColor, ChartTheme, GRAPH_COLORS};
 
 
 const BAR_SPACING_FROM_CHUNK_EDGE: usize = 2;
+const AXIS_TICK_COUNT: usize = 5;
 
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -15,7 +16,7 @@ impl BarGraph {
     pub fn canvas(&self) -> &Canvas { &self.canvas }
     pub fn canvas_mut(&mut self) -> &mut Canvas { &mut self.canvas }
 
-    fn calculate_image_size(bar_thickness: usize, bars_per_chunk: usize, chunk_count: usize, max_y_value: usize) -> (usize, usize) {
+    fn calculate_image_size(bar_thickness: usize, bars_per_chunk: usize, chunk_count: usize, axis_scale: &AxisScale) -> (usize, usize) {
         // 2 = frame width on both edges
         let width =
             2 // left frame + right frame
@@ -23,7 +24,7 @@ impl BarGraph {
             + 2*BAR_SPACING_FROM_CHUNK_EDGE*chunk_count // space between chunk separator and outermost bar (left + right)
             + bar_thickness*bars_per_chunk*chunk_count // the bars themselves
         ;
-        let height = 2 + Canvas::data_height_with_headroom(max_y_value);
+        let height = 2 + (axis_scale.max.round() as usize);
 
         // crash early if the dimensions are too large
         u32::try_from(width).expect("width too large");
@@ -32,14 +33,15 @@ impl BarGraph {
         (width, height)
     }
 
-    pub fn new_for_ranges(bar_thickness: usize, bars_per_chunk: usize, chunk_count: usize, max_y_value: usize) -> Self {
+    pub fn new_for_ranges(bar_thickness: usize, bars_per_chunk: usize, chunk_count: usize, max_y_value: usize, theme: ChartTheme) -> Self {
+        let axis_scale = Canvas::nice_axis_scale(0.0, max_y_value as f64, AXIS_TICK_COUNT);
         let (width, height) = Self::calculate_image_size(
             bar_thickness,
             bars_per_chunk,
             chunk_count,
-            max_y_value,
+            &axis_scale,
         );
-        let canvas = Canvas::new(width, height);
+        let canvas = Canvas::new(width, height, theme);
         let mut image = Self {
             bar_thickness,
             bars_per_chunk,
@@ -48,8 +50,11 @@ impl BarGraph {
         };
 
         // draw horizontal ticks
-        const HORIZONTAL_TICK_STEP: usize = 100;
-        for graph_y in (0..height).step_by(HORIZONTAL_TICK_STEP) {
+        for &tick in &axis_scale.ticks {
+            let graph_y = tick.round() as usize;
+            if graph_y >= height {
+                continue;
+            }
             let y = height - (1 + graph_y);
             for x in 1..(width-1) {
                 image.canvas.set_pixel(x, y, ChartColor::Tick);
@@ -0,0 +1,190 @@
+use crate::{AxisScale, Canvas, ChartColor, ChartTheme, GRAPH_COLORS};
+
+
+const BOX_SPACING_FROM_CHUNK_EDGE: usize = 2;
+const AXIS_TICK_COUNT: usize = 5;
+
+/// The multiplier applied to the interquartile range (IQR) by Tukey's rule to decide whether a
+/// sample is a whisker end or an outlier.
+const OUTLIER_IQR_FACTOR: f64 = 1.5;
+
+
+/// The five-number summary (plus outliers) of a group of samples, as drawn by [`BoxPlot`].
+#[derive(Clone, Debug, PartialEq)]
+struct BoxSummary {
+    pub whisker_low: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub whisker_high: f64,
+    pub outliers: Vec<f64>,
+}
+
+/// Returns the value at quantile `q` (0.0..=1.0) of `sorted`, a non-empty, ascending-sorted slice,
+/// linearly interpolating between the two closest ranks.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.len() == 1 {
+        return sorted[0];
+    }
+
+    let pos = q * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    if lower == upper {
+        sorted[lower]
+    } else {
+        let fraction = pos - lower as f64;
+        sorted[lower] * (1.0 - fraction) + sorted[upper] * fraction
+    }
+}
+
+/// Computes the five-number summary and outliers of `samples` (which must be non-empty).
+fn summarize(samples: &[usize]) -> BoxSummary {
+    let mut sorted: Vec<f64> = samples.iter().map(|&s| s as f64).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let q1 = quantile(&sorted, 0.25);
+    let median = quantile(&sorted, 0.5);
+    let q3 = quantile(&sorted, 0.75);
+    let iqr = q3 - q1;
+    let low_fence = q1 - OUTLIER_IQR_FACTOR * iqr;
+    let high_fence = q3 + OUTLIER_IQR_FACTOR * iqr;
+
+    // whiskers extend to the furthest sample still within the fences
+    let whisker_low = sorted.iter().copied()
+        .find(|&v| v >= low_fence)
+        .unwrap_or(q1);
+    let whisker_high = sorted.iter().copied().rev()
+        .find(|&v| v <= high_fence)
+        .unwrap_or(q3);
+
+    let outliers = sorted.iter().copied()
+        .filter(|&v| v < whisker_low || v > whisker_high)
+        .collect();
+
+    BoxSummary {
+        whisker_low,
+        q1,
+        median,
+        q3,
+        whisker_high,
+        outliers,
+    }
+}
+
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BoxPlot {
+    canvas: Canvas,
+}
+impl BoxPlot {
+    pub fn canvas(&self) -> &Canvas { &self.canvas }
+    pub fn canvas_mut(&mut self) -> &mut Canvas { &mut self.canvas }
+
+    /// Renders one box-and-whiskers per group of `groups` (each a non-empty list of samples),
+    /// placed side by side. Each box spans Q1 to Q3 with a median line; whiskers reach to the
+    /// furthest sample within 1.5×IQR of the box, and samples beyond that are drawn as individual
+    /// outlier pixels. The value axis uses the same "nice numbers" tick subsystem as `bar`/`line`.
+    pub fn new_for_groups(box_thickness: usize, groups: &[Vec<usize>], theme: ChartTheme) -> Self {
+        let chunk_count = groups.len();
+        let summaries: Vec<BoxSummary> = groups.iter()
+            .map(|samples| summarize(samples))
+            .collect();
+
+        let max_value = groups.iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0);
+        let axis_scale = Canvas::nice_axis_scale(0.0, max_value as f64, AXIS_TICK_COUNT);
+
+        // 2 = frame width on both edges
+        let width =
+            2 // left frame + right frame
+            + chunk_count.saturating_sub(1) // chunk separators
+            + 2*BOX_SPACING_FROM_CHUNK_EDGE*chunk_count // space between chunk separator and box (left + right)
+            + box_thickness*chunk_count // the boxes themselves
+        ;
+        let height = 2 + (axis_scale.max.round() as usize);
+
+        // crash early if the dimensions are too large
+        u32::try_from(width).expect("width too large");
+        u32::try_from(height).expect("height too large");
+
+        let mut canvas = Canvas::new(width, height, theme);
+
+        // draw horizontal ticks
+        for &tick in &axis_scale.ticks {
+            let graph_y = tick.round() as usize;
+            if graph_y >= height {
+                continue;
+            }
+            let y = height - (1 + graph_y);
+            for x in 1..(width-1) {
+                canvas.set_pixel(x, y, ChartColor::Tick);
+            }
+        }
+
+        // draw frame
+        for y in 0..height {
+            canvas.set_pixel(0, y, ChartColor::Border);
+            canvas.set_pixel(width - 1, y, ChartColor::Border);
+        }
+        for x in 0..width {
+            canvas.set_pixel(x, 0, ChartColor::Border);
+            canvas.set_pixel(x, height - 1, ChartColor::Border);
+        }
+
+        let value_to_y = |value: f64| -> usize {
+            let graph_y = value.round().clamp(0.0, (height - 2) as f64) as usize;
+            height - (1 + graph_y)
+        };
+
+        for (chunk_index, summary) in summaries.iter().enumerate() {
+            let box_x =
+                chunk_index * (
+                    1 // chunk-separator or frame
+                    + 2*BOX_SPACING_FROM_CHUNK_EDGE // left + right space between chunk separator and box
+                    + box_thickness // box
+                )
+                + 1 // chunk-separator or frame
+                + BOX_SPACING_FROM_CHUNK_EDGE // left space from chunk separator
+            ;
+            let center_x = box_x + box_thickness / 2;
+            let color = u8::try_from(chunk_index % GRAPH_COLORS.len()).unwrap();
+
+            // whisker: a vertical tick from whisker_low to whisker_high through the box
+            let whisker_low_y = value_to_y(summary.whisker_low);
+            let whisker_high_y = value_to_y(summary.whisker_high);
+            for y in whisker_high_y..=whisker_low_y {
+                canvas.set_pixel(center_x, y, ChartColor::Data(color));
+            }
+
+            // box: a bordered rectangle from Q1 to Q3
+            let q1_y = value_to_y(summary.q1);
+            let q3_y = value_to_y(summary.q3);
+            for y in q3_y..=q1_y {
+                canvas.set_pixel(box_x, y, ChartColor::Data(color));
+                canvas.set_pixel(box_x + box_thickness - 1, y, ChartColor::Data(color));
+            }
+            for x in box_x..box_x+box_thickness {
+                canvas.set_pixel(x, q1_y, ChartColor::Data(color));
+                canvas.set_pixel(x, q3_y, ChartColor::Data(color));
+            }
+
+            // median line
+            let median_y = value_to_y(summary.median);
+            for x in box_x..box_x+box_thickness {
+                canvas.set_pixel(x, median_y, ChartColor::Data(color));
+            }
+
+            // outliers as individual pixels
+            for &outlier in &summary.outliers {
+                let y = value_to_y(outlier);
+                canvas.set_pixel(center_x, y, ChartColor::Data(color));
+            }
+        }
+
+        Self { canvas }
+    }
+}
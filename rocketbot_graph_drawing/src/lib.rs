@@ -0,0 +1,469 @@
+pub mod bar;
+pub mod boxplot;
+pub mod line;
+pub mod pie;
+
+
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ChartColor {
+    Background,
+    Border,
+    Tick,
+    TimeSubdivision,
+    Text,
+    /// The row marking where a value axis crosses zero, drawn by [`crate::line::LineGraph`] when
+    /// its domain straddles zero.
+    ZeroLine,
+    Data(u8),
+}
+impl ChartColor {
+    #[inline]
+    pub fn palette_index(&self) -> u8 {
+        match self {
+            Self::Background => 0,
+            Self::Border => 1,
+            Self::Tick => 2,
+            Self::TimeSubdivision => 3,
+            Self::Text => 4,
+            Self::ZeroLine => 5,
+            Self::Data(d) => d.checked_add(6).unwrap(),
+        }
+    }
+}
+
+/// The RGB palette a [`Canvas`] is rendered with: one triple per non-data [`ChartColor`] variant
+/// plus the cycling palette used for [`ChartColor::Data`].
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChartTheme {
+    pub background: [u8; 3],
+    pub border: [u8; 3],
+    pub tick: [u8; 3],
+    pub time_subdivision: [u8; 3],
+    pub text: [u8; 3],
+    pub zero_line: [u8; 3],
+    pub data: Vec<[u8; 3]>,
+}
+impl ChartTheme {
+    /// The original light theme: a white background with dark gray chrome.
+    pub fn light() -> Self {
+        Self {
+            background: GRAPH_BACKGROUND_COLOR_LIGHT,
+            border: GRAPH_BORDER_COLOR_LIGHT,
+            tick: GRAPH_TICK_COLOR_LIGHT,
+            time_subdivision: GRAPH_TIME_SUBDIVISION_COLOR_LIGHT,
+            text: GRAPH_TEXT_COLOR_LIGHT,
+            zero_line: GRAPH_BORDER_COLOR_LIGHT,
+            data: GRAPH_COLORS.into_iter().collect(),
+        }
+    }
+
+    /// A dark theme with light-on-dark chrome, suited for chat clients with a dark background.
+    pub fn dark() -> Self {
+        Self {
+            background: GRAPH_BACKGROUND_COLOR_DARK,
+            border: GRAPH_BORDER_COLOR_DARK,
+            tick: GRAPH_TICK_COLOR_DARK,
+            time_subdivision: GRAPH_TIME_SUBDIVISION_COLOR_DARK,
+            text: GRAPH_TEXT_COLOR_DARK,
+            zero_line: GRAPH_BORDER_COLOR_DARK,
+            data: GRAPH_COLORS.into_iter().collect(),
+        }
+    }
+}
+impl Default for ChartTheme {
+    fn default() -> Self { Self::light() }
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Canvas {
+    width: usize,
+    pixels: Vec<ChartColor>,
+    theme: ChartTheme,
+}
+impl Canvas {
+    pub fn new(width: usize, height: usize, theme: ChartTheme) -> Self {
+        let pixel_count = width * height;
+        let pixels = vec![ChartColor::Background; pixel_count];
+
+        Self {
+            width,
+            pixels,
+            theme,
+        }
+    }
+
+    pub fn theme(&self) -> &ChartTheme { &self.theme }
+
+    pub fn width(&self) -> usize { self.width }
+    pub fn height(&self) -> usize {
+        debug_assert_eq!(self.pixels.len() % self.width, 0);
+        self.pixels.len() / self.width
+    }
+    #[allow(unused)]
+    pub fn pixels(&self) -> &[ChartColor] { self.pixels.as_slice() }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: ChartColor) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    pub fn set_pixel_if_in_range(&mut self, x: usize, y: usize, color: ChartColor) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        self.set_pixel(x, y, color);
+    }
+
+    pub fn draw_string(&mut self, mut x: usize, y: usize, text: &str) {
+        for c in text.chars() {
+            let pixel_slice = FONT
+                .get(&c).unwrap_or(&FONT_REPLACEMENT_CHARACTER);
+            for pixel in *pixel_slice {
+                if x >= self.width() {
+                    // enough
+                    break;
+                }
+
+                for y_offset in 0..8 {
+                    if *pixel & (1 << y_offset) != 0 {
+                        self.set_pixel_if_in_range(x, y + y_offset, ChartColor::Text);
+                    }
+                }
+
+                x += 1;
+            }
+        }
+    }
+
+    /// Draws a legend: a colored swatch followed by its label, one `(label, color)` entry per
+    /// line, starting at `(x, y)` and growing downward. Intended for labeling the data series of
+    /// a `bar`/`line` chart with their `ChartColor::Data` colors.
+    pub fn draw_legend(&mut self, x: usize, y: usize, entries: &[(String, ChartColor)]) {
+        const SWATCH_SIZE: usize = 6;
+        const SWATCH_TEXT_GAP: usize = 2;
+        const ENTRY_LINE_HEIGHT: usize = 10;
+
+        for (i, (label, color)) in entries.iter().enumerate() {
+            let entry_y = y + i * ENTRY_LINE_HEIGHT;
+
+            for swatch_y in 0..SWATCH_SIZE {
+                for swatch_x in 0..SWATCH_SIZE {
+                    self.set_pixel_if_in_range(x + swatch_x, entry_y + swatch_y, *color);
+                }
+            }
+
+            self.draw_string(x + SWATCH_SIZE + SWATCH_TEXT_GAP, entry_y, label);
+        }
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let palette: Vec<u8> = self.theme.background.into_iter()
+            .chain(self.theme.border.into_iter())
+            .chain(self.theme.tick.into_iter())
+            .chain(self.theme.time_subdivision.into_iter())
+            .chain(self.theme.text.into_iter())
+            .chain(self.theme.zero_line.into_iter())
+            .chain(self.theme.data.iter().flat_map(|cs| *cs))
+            .collect();
+        let mut png_bytes: Vec<u8> = Vec::new();
+
+        let width_u32 = self.width().try_into().unwrap();
+        let height_u32 = self.height().try_into().unwrap();
+
+        {
+            let mut png_encoder = png::Encoder::new(&mut png_bytes, width_u32, height_u32);
+            png_encoder.set_color(png::ColorType::Indexed);
+            png_encoder.set_palette(palette);
+
+            let mut png_writer = png_encoder.write_header().expect("failed to write PNG header");
+            let mut png_data = Vec::with_capacity(self.pixels.len());
+            png_data.extend(self.pixels.iter().map(|p| p.palette_index()));
+            png_writer.write_image_data(&png_data).expect("failed to write image data");
+        }
+
+        png_bytes
+    }
+
+    /// Renders the canvas as a compact monospaced string using Unicode braille characters, one
+    /// line per four pixel rows. Each braille character encodes a 2×4 block of pixels; a pixel is
+    /// considered "set" (its dot is drawn) if it is anything other than [`ChartColor::Background`].
+    ///
+    /// Useful where a chat message can't carry a PNG attachment.
+    pub fn to_braille(&self) -> String {
+        // braille dot bits, indexed by [row][column] within the 4x2 block; note the nonlinear
+        // numbering (dots 7 and 8, the bottom row, don't continue the 0x01..0x20 progression)
+        const DOT_BITS: [[u32; 2]; 4] = [
+            [0x01, 0x08],
+            [0x02, 0x10],
+            [0x04, 0x20],
+            [0x40, 0x80],
+        ];
+        const BRAILLE_BASE: u32 = 0x2800;
+
+        let width = self.width();
+        let height = self.height();
+        let char_rows = (height + 3) / 4;
+        let char_columns = (width + 1) / 2;
+
+        let mut ret = String::with_capacity((char_columns + 1) * char_rows);
+        for char_row in 0..char_rows {
+            for char_column in 0..char_columns {
+                let mut dots: u32 = 0;
+                for (row_offset, bits_per_column) in DOT_BITS.iter().enumerate() {
+                    let y = char_row*4 + row_offset;
+                    if y >= height {
+                        continue;
+                    }
+                    for (column_offset, &bit) in bits_per_column.iter().enumerate() {
+                        let x = char_column*2 + column_offset;
+                        if x >= width {
+                            continue;
+                        }
+                        if self.pixels[y * self.width + x] != ChartColor::Background {
+                            dots |= bit;
+                        }
+                    }
+                }
+
+                let codepoint = BRAILLE_BASE + dots;
+                ret.push(char::from_u32(codepoint).unwrap());
+            }
+            ret.push('\n');
+        }
+
+        ret
+    }
+
+    /// Computes tidy axis bounds and evenly spaced tick values for the range `[min, max]`, aiming
+    /// for roughly `tick_count` ticks, using Heckbert's "nice numbers" algorithm.
+    pub fn nice_axis_scale(min: f64, max: f64, tick_count: usize) -> AxisScale {
+        let (min, max) = if min == 0.0 && max == 0.0 {
+            // all-zero data; fall back to a [0, 1] axis
+            (0.0, 1.0)
+        } else if min == max {
+            // pad a single-valued range by ±1 so it isn't degenerate
+            (min - 1.0, max + 1.0)
+        } else {
+            (min, max)
+        };
+
+        let tick_count = tick_count.max(2);
+        let range = nice_num(max - min, false);
+        let tick_spacing = nice_num(range / (tick_count - 1) as f64, true);
+        let graph_min = (min / tick_spacing).floor() * tick_spacing;
+        let graph_max = (max / tick_spacing).ceil() * tick_spacing;
+
+        let mut ticks = Vec::new();
+        let mut tick = graph_min;
+        while tick < graph_max + tick_spacing / 2.0 {
+            ticks.push(tick);
+            tick += tick_spacing;
+        }
+
+        AxisScale {
+            min: graph_min,
+            max: graph_max,
+            ticks,
+        }
+    }
+}
+
+
+/// The result of [`Canvas::nice_axis_scale`]: the graph's rounded axis bounds plus the tick values
+/// to label within them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AxisScale {
+    pub min: f64,
+    pub max: f64,
+    pub ticks: Vec<f64>,
+}
+
+
+/// Rounds `x` to a "nice" number of the form `{1, 2, 5, 10} * 10^exp` (Heckbert's "nice numbers"
+/// algorithm). If `round` is `true`, rounds to the nearest nice number; otherwise rounds up, which
+/// is used to obtain a nice number that is at least as large as `x`.
+fn nice_num(x: f64, round: bool) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+
+    let exp = x.log10().floor();
+    let f = x / 10f64.powf(exp);
+
+    let nice_fraction = if round {
+        if f < 1.5 { 1.0 }
+        else if f < 3.0 { 2.0 }
+        else if f < 7.0 { 5.0 }
+        else { 10.0 }
+    } else {
+        if f <= 1.0 { 1.0 }
+        else if f <= 2.0 { 2.0 }
+        else if f <= 5.0 { 5.0 }
+        else { 10.0 }
+    };
+
+    nice_fraction * 10f64.powf(exp)
+}
+
+
+pub(crate) const GRAPH_COLORS: [[u8; 3]; 30] = [
+    // DawnBringer DB32 palette without black and white
+    [0x63, 0x9b, 0xff], // #639bff
+    [0xac, 0x32, 0x32], // #ac3232
+    [0xdf, 0x71, 0x26], // #df7126
+    [0xfb, 0xf2, 0x36], // #fbf236
+    [0x99, 0xe5, 0x50], // #99e550
+    [0x76, 0x42, 0x8a], // #76428a
+
+    [0x5b, 0x6e, 0xe1], // #5b6ee1
+    [0xd9, 0x57, 0x63], // #d95763
+    [0xd9, 0xa0, 0x66], // #d9a066
+    [0x8f, 0x97, 0x4a], // #8f974a
+    [0x6a, 0xbe, 0x30], // #6abe30
+    [0x3f, 0x3f, 0x74], // #3f3f74
+
+    [0x30, 0x60, 0x82], // #306082
+    [0x8f, 0x56, 0x3b], // #8f563b
+    [0xee, 0xc3, 0x9a], // #eec39a
+    [0x8a, 0x6f, 0x30], // #8a6f30
+    [0x37, 0x94, 0x6e], // #37946e
+    [0xd7, 0x7b, 0xba], // #d77bba
+
+    [0x5f, 0xcd, 0xe4], // #5fcde4
+    [0x66, 0x39, 0x31], // #663931
+    [0x52, 0x4b, 0x24], // #524b24
+    [0xcb, 0xdb, 0xfc], // #cbdbfc
+    [0x4b, 0x69, 0x2f], // #4b692f
+    [0x45, 0x28, 0x3c], // #45283c
+
+    [0x22, 0x20, 0x34], // #222034
+    [0x59, 0x56, 0x52], // #595652
+    [0x84, 0x7e, 0x87], // #847e87
+    [0x9b, 0xad, 0xb7], // #9badb7
+    [0x32, 0x3c, 0x39], // #323c39
+    [0x69, 0x6a, 0x6a], // #696a6a
+];
+pub(crate) const GRAPH_BORDER_COLOR_LIGHT: [u8; 3] = [0, 0, 0]; // #000000
+pub(crate) const GRAPH_BACKGROUND_COLOR_LIGHT: [u8; 3] = [255, 255, 255]; // #ffffff
+pub(crate) const GRAPH_TICK_COLOR_LIGHT: [u8; 3] = [221, 221, 221]; // #dddddd
+pub(crate) const GRAPH_TIME_SUBDIVISION_COLOR_LIGHT: [u8; 3] = [136, 136, 136]; // #888888
+pub(crate) const GRAPH_TEXT_COLOR_LIGHT: [u8; 3] = [136, 136, 136]; // #888888
+
+pub(crate) const GRAPH_BORDER_COLOR_DARK: [u8; 3] = [255, 255, 255]; // #ffffff
+pub(crate) const GRAPH_BACKGROUND_COLOR_DARK: [u8; 3] = [0x22, 0x20, 0x34]; // #222034 (DB32 darkest)
+pub(crate) const GRAPH_TICK_COLOR_DARK: [u8; 3] = [0x59, 0x56, 0x52]; // #595652
+pub(crate) const GRAPH_TIME_SUBDIVISION_COLOR_DARK: [u8; 3] = [0x9b, 0xad, 0xb7]; // #9badb7
+pub(crate) const GRAPH_TEXT_COLOR_DARK: [u8; 3] = [0xcb, 0xdb, 0xfc]; // #cbdbfc
+
+
+pub(crate) static FONT: LazyLock<BTreeMap<char, &'static [u8]>> = LazyLock::new(|| {
+    let mut font: BTreeMap<char, &'static [u8]> = BTreeMap::new();
+
+    // encoding is column by column; each byte represents one column
+    // LSB is the topmost pixel, LSB-but-one is the pixel below it, etc.
+    // if a bit is 1, the font has a pixel there; if it is 0, there is none
+    //
+    // covers printable ASCII (space through tilde); lowercase letters reuse their uppercase
+    // glyph, as this 5x7 grid isn't tall enough to draw distinct ascenders/descenders legibly
+
+    font.insert(' ', &[0b0000000]);
+    font.insert('0', &[0b0111110, 0b1010001, 0b1001001, 0b1000101, 0b0111110, 0b0000000]);
+    font.insert('1', &[0b0000000, 0b1000010, 0b1111111, 0b1000000, 0b0000000]);
+    font.insert('2', &[0b1000010, 0b1100001, 0b1010001, 0b1001001, 0b1000110, 0b0000000]);
+    font.insert('3', &[0b0100010, 0b1000001, 0b1001001, 0b1001001, 0b0110110, 0b0000000]);
+    font.insert('4', &[0b0011000, 0b0010100, 0b0010010, 0b1111111, 0b0010000, 0b0000000]);
+    font.insert('5', &[0b0100111, 0b1000101, 0b1000101, 0b1000101, 0b0111001, 0b0000000]);
+    font.insert('6', &[0b0111100, 0b1001010, 0b1001001, 0b1001001, 0b0110000, 0b0000000]);
+    font.insert('7', &[0b0000001, 0b1110001, 0b0001001, 0b0000101, 0b0000011, 0b0000000]);
+    font.insert('8', &[0b0110110, 0b1001001, 0b1001001, 0b1001001, 0b0110110, 0b0000000]);
+    font.insert('9', &[0b0000110, 0b1001001, 0b1001001, 0b0101001, 0b0011110, 0b0000000]);
+    font.insert('A', &[0b1111100, 0b0010010, 0b0010001, 0b0010010, 0b1111100, 0b0000000]);
+    font.insert('B', &[0b1111111, 0b1001001, 0b1001001, 0b1001001, 0b0110110, 0b0000000]);
+    font.insert('C', &[0b0111110, 0b1000001, 0b1000001, 0b1000001, 0b0100010, 0b0000000]);
+    font.insert('D', &[0b1111111, 0b1000001, 0b1000001, 0b1000001, 0b0111110, 0b0000000]);
+    font.insert('E', &[0b1111111, 0b1001001, 0b1001001, 0b1001001, 0b1000001, 0b0000000]);
+    font.insert('F', &[0b1111111, 0b0001001, 0b0001001, 0b0001001, 0b0000001, 0b0000000]);
+    font.insert('G', &[0b0111110, 0b1000001, 0b1001001, 0b1001001, 0b0111010, 0b0000000]);
+    font.insert('H', &[0b1111111, 0b0001000, 0b0001000, 0b0001000, 0b1111111, 0b0000000]);
+    font.insert('I', &[0b0000000, 0b1000001, 0b1111111, 0b1000001, 0b0000000]);
+    font.insert('J', &[0b0110000, 0b1000000, 0b1000000, 0b1000000, 0b0111111, 0b0000000]);
+    font.insert('K', &[0b1111111, 0b0001000, 0b0010100, 0b0100010, 0b1000001, 0b0000000]);
+    font.insert('L', &[0b1111111, 0b1000000, 0b1000000, 0b1000000, 0b1000000, 0b0000000]);
+    font.insert('M', &[0b1111111, 0b0000010, 0b0000100, 0b0000010, 0b1111111, 0b0000000]);
+    font.insert('N', &[0b1111111, 0b0000010, 0b0001100, 0b0010000, 0b1111111, 0b0000000]);
+    font.insert('O', &[0b0111110, 0b1000001, 0b1000001, 0b1000001, 0b0111110, 0b0000000]);
+    font.insert('P', &[0b1111111, 0b0001001, 0b0001001, 0b0001001, 0b0000110, 0b0000000]);
+    font.insert('Q', &[0b0111110, 0b1000001, 0b1010001, 0b0100001, 0b1011110, 0b0000000]);
+    font.insert('R', &[0b1111111, 0b0001001, 0b0011001, 0b0101001, 0b1000110, 0b0000000]);
+    font.insert('S', &[0b1000110, 0b1001001, 0b1001001, 0b1001001, 0b0110001, 0b0000000]);
+    font.insert('T', &[0b0000001, 0b0000001, 0b1111111, 0b0000001, 0b0000001, 0b0000000]);
+    font.insert('U', &[0b0111111, 0b1000000, 0b1000000, 0b1000000, 0b0111111, 0b0000000]);
+    font.insert('V', &[0b0011111, 0b0100000, 0b1000000, 0b0100000, 0b0011111, 0b0000000]);
+    font.insert('W', &[0b1111111, 0b0100000, 0b0010000, 0b0100000, 0b1111111, 0b0000000]);
+    font.insert('X', &[0b1100011, 0b0010100, 0b0001000, 0b0010100, 0b1100011, 0b0000000]);
+    font.insert('Y', &[0b0000011, 0b0000100, 0b1111000, 0b0000100, 0b0000011, 0b0000000]);
+    font.insert('Z', &[0b1100001, 0b1010001, 0b1001001, 0b1000101, 0b1000011, 0b0000000]);
+    font.insert('.', &[0b0000000, 0b1100000, 0b1100000, 0b0000000]);
+    font.insert(',', &[0b0000000, 0b0100000, 0b1100000, 0b0000000]);
+    font.insert(':', &[0b0000000, 0b0110110, 0b0110110, 0b0000000]);
+    font.insert(';', &[0b0000000, 0b1110110, 0b0110110, 0b0000000]);
+    font.insert('!', &[0b0000000, 0b0000000, 0b1011111, 0b0000000]);
+    font.insert('?', &[0b0000010, 0b0000001, 0b1011001, 0b0001001, 0b0000110, 0b0000000]);
+    font.insert('-', &[0b0001000, 0b0001000, 0b0001000, 0b0001000, 0b0001000, 0b0000000]);
+    font.insert('+', &[0b0001000, 0b0001000, 0b0111110, 0b0001000, 0b0001000, 0b0000000]);
+    font.insert('=', &[0b0010100, 0b0010100, 0b0010100, 0b0010100, 0b0010100, 0b0000000]);
+    font.insert('(', &[0b0000000, 0b0011100, 0b0100010, 0b1000001, 0b0000000]);
+    font.insert(')', &[0b0000000, 0b1000001, 0b0100010, 0b0011100, 0b0000000]);
+    font.insert('[', &[0b0000000, 0b1111111, 0b1000001, 0b0000000]);
+    font.insert(']', &[0b0000000, 0b0000000, 0b1000001, 0b1111111, 0b0000000]);
+    font.insert('\'', &[0b0000000, 0b0000000, 0b0000011, 0b0000000]);
+    font.insert('"', &[0b0000000, 0b0000011, 0b0000000, 0b0000011, 0b0000000]);
+    font.insert('<', &[0b0001000, 0b0010100, 0b0100010, 0b1000001, 0b0000000]);
+    font.insert('>', &[0b0000000, 0b1000001, 0b0100010, 0b0010100, 0b0001000, 0b0000000]);
+    font.insert('@', &[0b0111110, 0b1000001, 0b1011101, 0b1010101, 0b0001110, 0b0000000]);
+    font.insert('#', &[0b0001010, 0b0111111, 0b0001010, 0b0111111, 0b0001010, 0b0000000]);
+    font.insert('$', &[0b0100100, 0b0101010, 0b1111111, 0b0101010, 0b0010010, 0b0000000]);
+    font.insert('%', &[0b1100011, 0b0010011, 0b0101000, 0b1100110, 0b1000001, 0b0000000]);
+    font.insert('&', &[0b0110110, 0b1001001, 0b1010101, 0b0100010, 0b1010000, 0b0000000]);
+    font.insert('*', &[0b0101010, 0b0011100, 0b0111110, 0b0011100, 0b0101010, 0b0000000]);
+    font.insert('_', &[0b1000000, 0b1000000, 0b1000000, 0b1000000, 0b1000000, 0b0000000]);
+    font.insert('`', &[0b0000000, 0b0000001, 0b0000010, 0b0000000]);
+    font.insert('~', &[0b0001000, 0b0000100, 0b0001000, 0b0010000, 0b0001000, 0b0000000]);
+    font.insert('^', &[0b0000100, 0b0000010, 0b0000001, 0b0000010, 0b0000100, 0b0000000]);
+    font.insert('|', &[0b0000000, 0b0000000, 0b1111111, 0b0000000]);
+    font.insert('\\', &[0b0000001, 0b0000010, 0b0000100, 0b0001000, 0b0010000, 0b0000000]);
+    font.insert('/', &[0b0010000, 0b0001000, 0b0000100, 0b0000010, 0b0000001, 0b0000000]);
+
+    font.insert('a', font[&'A']);
+    font.insert('b', font[&'B']);
+    font.insert('c', font[&'C']);
+    font.insert('d', font[&'D']);
+    font.insert('e', font[&'E']);
+    font.insert('f', font[&'F']);
+    font.insert('g', font[&'G']);
+    font.insert('h', font[&'H']);
+    font.insert('i', font[&'I']);
+    font.insert('j', font[&'J']);
+    font.insert('k', font[&'K']);
+    font.insert('l', font[&'L']);
+    font.insert('m', font[&'M']);
+    font.insert('n', font[&'N']);
+    font.insert('o', font[&'O']);
+    font.insert('p', font[&'P']);
+    font.insert('q', font[&'Q']);
+    font.insert('r', font[&'R']);
+    font.insert('s', font[&'S']);
+    font.insert('t', font[&'T']);
+    font.insert('u', font[&'U']);
+    font.insert('v', font[&'V']);
+    font.insert('w', font[&'W']);
+    font.insert('x', font[&'X']);
+    font.insert('y', font[&'Y']);
+    font.insert('z', font[&'Z']);
+
+    font
+});
+pub(crate) const FONT_REPLACEMENT_CHARACTER: &'static [u8] = &[0b1111111, 0b1010101, 0b1100011, 0b1111111, 0b0000000];
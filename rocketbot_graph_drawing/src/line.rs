@@ -0,0 +1,192 @@
+use crate::{AxisScale, Canvas, ChartColor, ChartTheme};
+
+
+const AXIS_TICK_COUNT: usize = 5;
+
+/// The length, in pixels, of the short tick marks drawn along the right-hand (secondary) axis.
+const RIGHT_AXIS_TICK_MARK_LENGTH: usize = 3;
+
+/// The approximate on-canvas width, in pixels, of one character drawn with [`Canvas::draw_string`].
+/// Used to right-align right-axis tick labels against the border; the font is proportional, so
+/// this is a conservative estimate rather than an exact value.
+const APPROXIMATE_CHAR_WIDTH: usize = 6;
+
+
+/// Which of a [`LineGraph`]'s two Y-axes a data series is plotted against.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum AxisSide {
+    Left,
+    Right,
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LineGraph {
+    thicken: usize,
+    canvas: Canvas,
+    left_scale: AxisScale,
+    right_scale: Option<AxisScale>,
+}
+impl LineGraph {
+    pub fn canvas(&self) -> &Canvas { &self.canvas }
+    pub fn canvas_mut(&mut self) -> &mut Canvas { &mut self.canvas }
+
+    fn calculate_image_size(x_positions: usize, left_scale: &AxisScale) -> (usize, usize) {
+        // 2 = frame width on both edges
+        let width = 2 + x_positions;
+        let height = 2 + (left_scale.max - left_scale.min).round() as usize;
+
+        // crash early if the dimensions are too large
+        u32::try_from(width).expect("width too large");
+        u32::try_from(height).expect("height too large");
+
+        (width, height)
+    }
+
+    /// Draws the tick marks and labels of one axis. The left axis gets a full-width gridline per
+    /// tick; the right axis gets only a short tick mark next to its border, to avoid cluttering
+    /// the chart with two overlapping sets of gridlines.
+    fn draw_axis_ticks(canvas: &mut Canvas, width: usize, height: usize, scale: &AxisScale, side: AxisSide) {
+        for &tick in &scale.ticks {
+            let y = Self::value_to_y(height, scale, tick);
+            let label = format!("{}", tick.round() as i64);
+
+            match side {
+                AxisSide::Left => {
+                    for x in 1..(width-1) {
+                        canvas.set_pixel(x, y, ChartColor::Tick);
+                    }
+                    canvas.draw_string(1, y, &label);
+                },
+                AxisSide::Right => {
+                    let tick_mark_start = (width - 1).saturating_sub(RIGHT_AXIS_TICK_MARK_LENGTH);
+                    for x in tick_mark_start..(width - 1) {
+                        canvas.set_pixel(x, y, ChartColor::Tick);
+                    }
+
+                    let label_width = label.chars().count() * APPROXIMATE_CHAR_WIDTH;
+                    let label_x = tick_mark_start.saturating_sub(1 + label_width);
+                    canvas.draw_string(label_x, y, &label);
+                },
+            }
+        }
+    }
+
+    /// Maps `value` to a pixel row, clamping it into `scale`'s domain first so out-of-range points
+    /// are drawn at the nearest edge rather than wrapping or panicking.
+    fn value_to_y(height: usize, scale: &AxisScale, value: f64) -> usize {
+        let span = scale.max - scale.min;
+        let clamped = value.clamp(scale.min, scale.max);
+        let fraction = if span > 0.0 { (clamped - scale.min) / span } else { 0.0 };
+        let graph_y = (fraction * (height - 2) as f64).round() as usize;
+
+        height - (1 + graph_y)
+    }
+
+    /// Draws the row where `scale`'s domain crosses zero, if it does at all (a domain that's
+    /// entirely non-negative or entirely non-positive has no interior zero crossing to mark).
+    fn draw_zero_line(canvas: &mut Canvas, width: usize, height: usize, scale: &AxisScale) {
+        if scale.min >= 0.0 || scale.max <= 0.0 {
+            return;
+        }
+
+        let y = Self::value_to_y(height, scale, 0.0);
+        for x in 1..(width - 1) {
+            canvas.set_pixel(x, y, ChartColor::ZeroLine);
+        }
+    }
+
+    /// Creates a single-Y-axis line graph whose value domain is `[min_value, max_value]`.
+    pub fn new_for_ranges(x_positions: usize, min_value: f64, max_value: f64, thicken: usize, theme: ChartTheme) -> Self {
+        let left_scale = Canvas::nice_axis_scale(min_value, max_value, AXIS_TICK_COUNT);
+        let (width, height) = Self::calculate_image_size(x_positions, &left_scale);
+        let canvas = Canvas::new(width, height, theme);
+        let mut image = Self {
+            thicken,
+            canvas,
+            left_scale,
+            right_scale: None,
+        };
+
+        Self::draw_axis_ticks(&mut image.canvas, width, height, &image.left_scale, AxisSide::Left);
+        Self::draw_zero_line(&mut image.canvas, width, height, &image.left_scale);
+        image.draw_vertical_ticks_and_frame(width, height);
+
+        image
+    }
+
+    /// Creates a dual-Y-axis line graph: series plotted against [`AxisSide::Left`] share the
+    /// chart's overall pixel height with series plotted against [`AxisSide::Right`], but each axis
+    /// gets its own independent "nice numbers" range and tick labels, drawn on its own border.
+    pub fn new_for_dual_ranges(x_positions: usize, min_left_value: f64, max_left_value: f64, min_right_value: f64, max_right_value: f64, thicken: usize, theme: ChartTheme) -> Self {
+        let left_scale = Canvas::nice_axis_scale(min_left_value, max_left_value, AXIS_TICK_COUNT);
+        let right_scale = Canvas::nice_axis_scale(min_right_value, max_right_value, AXIS_TICK_COUNT);
+        let (width, height) = Self::calculate_image_size(x_positions, &left_scale);
+        let canvas = Canvas::new(width, height, theme);
+        let mut image = Self {
+            thicken,
+            canvas,
+            left_scale,
+            right_scale: Some(right_scale),
+        };
+
+        Self::draw_axis_ticks(&mut image.canvas, width, height, &image.left_scale, AxisSide::Left);
+        Self::draw_axis_ticks(&mut image.canvas, width, height, image.right_scale.as_ref().unwrap(), AxisSide::Right);
+        Self::draw_zero_line(&mut image.canvas, width, height, &image.left_scale);
+        image.draw_vertical_ticks_and_frame(width, height);
+
+        image
+    }
+
+    fn draw_vertical_ticks_and_frame(&mut self, width: usize, height: usize) {
+        const VERTICAL_TICK_STEP: usize = 100;
+        for graph_x in (0..width).step_by(VERTICAL_TICK_STEP) {
+            let x = 1 + graph_x;
+            for y in 1..(height-1) {
+                self.canvas.set_pixel(x, y, ChartColor::Tick);
+            }
+        }
+
+        // draw frame
+        for y in 0..height {
+            self.canvas.set_pixel(0, y, ChartColor::Border);
+            self.canvas.set_pixel(width - 1, y, ChartColor::Border);
+        }
+        for x in 0..width {
+            self.canvas.set_pixel(x, 0, ChartColor::Border);
+            self.canvas.set_pixel(x, height - 1, ChartColor::Border);
+        }
+    }
+
+    pub fn draw_data_point(&mut self, graph_x: usize, value: f64, color: u8, axis: AxisSide) {
+        let scale = match axis {
+            AxisSide::Left => &self.left_scale,
+            AxisSide::Right => self.right_scale.as_ref()
+                .expect("draw_data_point called with AxisSide::Right on a single-axis LineGraph"),
+        };
+
+        let x = 1 + graph_x;
+        let y = Self::value_to_y(self.canvas.height(), scale, value);
+        let pixel_value = ChartColor::Data(color);
+
+        self.canvas.set_pixel(x, y, pixel_value);
+
+        for graph_thicker_y in 0..self.thicken {
+            let thicker_y_down = y + 1 + graph_thicker_y;
+            if thicker_y_down < self.canvas.height() {
+                self.canvas.set_pixel(x, thicker_y_down, pixel_value);
+            }
+
+            if let Some(thicker_y_up) = y.checked_sub(1 + graph_thicker_y) {
+                self.canvas.set_pixel(x, thicker_y_up, pixel_value);
+            }
+        }
+    }
+
+    pub fn draw_time_subdivision(&mut self, graph_x: usize) {
+        let x = 1 + graph_x;
+        for y in 1..(self.canvas.height()-1) {
+            self.canvas.set_pixel(x, y, ChartColor::TimeSubdivision);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{Canvas, ChartColor, ChartTheme, GRAPH_COLORS};
+
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PieChart {
+    canvas: Canvas,
+}
+impl PieChart {
+    pub fn canvas(&self) -> &Canvas { &self.canvas }
+    pub fn canvas_mut(&mut self) -> &mut Canvas { &mut self.canvas }
+
+    /// Renders `slices` (label, value pairs) as a pie chart of the given `radius`, cycling through
+    /// `GRAPH_COLORS` for each slice in turn. If `inner_radius` is greater than zero, the center of
+    /// the pie is left unfilled, turning it into a donut chart.
+    pub fn new_for_slices(slices: &[(String, usize)], radius: usize, inner_radius: usize, theme: ChartTheme) -> Self {
+        // 2 = frame width on both edges
+        let width = 2 * radius + 2;
+        let height = 2 * radius + 2;
+
+        // crash early if the dimensions are too large
+        u32::try_from(width).expect("width too large");
+        u32::try_from(height).expect("height too large");
+
+        let mut canvas = Canvas::new(width, height, theme);
+
+        // draw frame
+        for y in 0..height {
+            canvas.set_pixel(0, y, ChartColor::Border);
+            canvas.set_pixel(width - 1, y, ChartColor::Border);
+        }
+        for x in 0..width {
+            canvas.set_pixel(x, 0, ChartColor::Border);
+            canvas.set_pixel(x, height - 1, ChartColor::Border);
+        }
+
+        let total: usize = slices.iter().map(|(_label, value)| *value).sum();
+        if total == 0 {
+            return Self { canvas };
+        }
+
+        // cumulative slice boundary angles (radians), measured clockwise from straight up
+        let mut cumulative_value = 0;
+        let boundary_angles: Vec<f64> = slices.iter()
+            .map(|(_label, value)| {
+                cumulative_value += *value;
+                (cumulative_value as f64 / total as f64) * std::f64::consts::TAU
+            })
+            .collect();
+
+        let center_x = 1.0 + radius as f64;
+        let center_y = 1.0 + radius as f64;
+        let radius_f = radius as f64;
+        let inner_radius_f = inner_radius as f64;
+
+        // rasterize the slices by testing each pixel's angle and radius against the boundaries
+        for y in 1..(height - 1) {
+            for x in 1..(width - 1) {
+                let dx = (x as f64 + 0.5) - center_x;
+                let dy = (y as f64 + 0.5) - center_y;
+                let distance = (dx*dx + dy*dy).sqrt();
+                if distance > radius_f || distance < inner_radius_f {
+                    continue;
+                }
+
+                let mut angle = dx.atan2(-dy);
+                if angle < 0.0 {
+                    angle += std::f64::consts::TAU;
+                }
+
+                let slice_index = boundary_angles.iter()
+                    .position(|&boundary| angle < boundary)
+                    .unwrap_or(slices.len() - 1);
+                let color = u8::try_from(slice_index % GRAPH_COLORS.len()).unwrap();
+                canvas.set_pixel(x, y, ChartColor::Data(color));
+            }
+        }
+
+        // label each slice with its name and percentage, placed halfway between the two radii
+        // at the slice's midpoint angle
+        let label_radius = (radius_f + inner_radius_f) / 2.0;
+        let mut previous_boundary = 0.0;
+        for (i, (label, value)) in slices.iter().enumerate() {
+            let boundary = boundary_angles[i];
+            let mid_angle = (previous_boundary + boundary) / 2.0;
+            previous_boundary = boundary;
+
+            let percentage = (100.0 * *value as f64 / total as f64).round() as u32;
+            let text = format!("{}/{}%", label, percentage);
+
+            let label_x = (center_x + mid_angle.sin() * label_radius).round().max(0.0) as usize;
+            let label_y = (center_y - mid_angle.cos() * label_radius).round().max(0.0) as usize;
+            canvas.draw_string(label_x, label_y, &text);
+        }
+
+        Self { canvas }
+    }
+}
@@ -38,8 +38,11 @@ impl fmt::Display for ChannelTypeParseError {
 pub enum HttpError {
     MissingUserId,
     MissingAuthToken,
-    ObtainingResponse(hyper_util::client::legacy::Error),
-    ObtainingResponseBody(hyper::Error),
+    /// The underlying transport (e.g. `hyper_util`'s legacy client or `reqwest`) failed to obtain
+    /// a response at all, e.g. due to a connection, TLS, or DNS resolution failure.
+    ObtainingResponse(Box<dyn Error + Send + Sync>),
+    /// A response was obtained, but reading its body failed (e.g. the connection was cut short).
+    ObtainingResponseBody(Box<dyn Error + Send + Sync>),
     DecodingAsGzip(std::io::Error),
     DecodingAsUtf8(FromUtf8Error),
     StatusNotOk(StatusCode),
@@ -0,0 +1,217 @@
+//! A small, reusable mechanism for gating command dispatch on shared, configurable
+//! preconditions (e.g. restricting a command to certain channels, requiring a sender to be on
+//! an allowlist, or imposing a per-user cooldown) without having to reimplement the same checks
+//! in every plugin that dispatches commands.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde_json;
+
+use crate::JsonValueExtensions;
+use crate::sync::Mutex;
+
+
+/// The circumstances under which a [`CommandHook`] is evaluated.
+#[derive(Clone, Debug)]
+pub struct HookContext {
+    pub command_name: String,
+    pub channel_name: Option<String>,
+    pub sender_username: String,
+}
+
+/// The result of evaluating a [`CommandHook`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HookVerdict {
+    /// The command may proceed.
+    Allow,
+
+    /// The command must not proceed. If given, `feedback` should be shown to whoever issued the
+    /// command.
+    Deny { feedback: Option<String> },
+}
+
+/// A single, named precondition that must hold before a command is dispatched.
+#[async_trait]
+pub trait CommandHook: Send + Sync {
+    async fn check(&self, context: &HookContext) -> HookVerdict;
+}
+
+/// A collection of hooks, looked up by the name under which a plugin's configuration refers to
+/// them.
+#[derive(Clone, Default)]
+pub struct HookRegistry {
+    hooks: HashMap<String, Arc<dyn CommandHook>>,
+}
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self { hooks: HashMap::new() }
+    }
+
+    pub fn register<N: Into<String>>(&mut self, name: N, hook: Arc<dyn CommandHook>) {
+        self.hooks.insert(name.into(), hook);
+    }
+
+    /// Evaluates `hook_names`, in order, against `context`, stopping and returning the verdict as
+    /// soon as one of them denies. Hook names that are not registered are silently treated as
+    /// allowing (a misconfigured hook list should not itself prevent a command from running).
+    pub async fn evaluate(&self, hook_names: &[String], context: &HookContext) -> HookVerdict {
+        for hook_name in hook_names {
+            if let Some(hook) = self.hooks.get(hook_name) {
+                let verdict = hook.check(context).await;
+                if verdict != HookVerdict::Allow {
+                    return verdict;
+                }
+            }
+        }
+        HookVerdict::Allow
+    }
+}
+
+
+/// A hook that only allows the command in a predefined set of channels. Always allows private
+/// (channel-less) commands.
+pub struct ChannelAllowlistHook {
+    pub allowed_channels: HashSet<String>,
+}
+#[async_trait]
+impl CommandHook for ChannelAllowlistHook {
+    async fn check(&self, context: &HookContext) -> HookVerdict {
+        match &context.channel_name {
+            Some(channel_name) if !self.allowed_channels.contains(channel_name) => HookVerdict::Deny {
+                feedback: Some("This command is not allowed in this channel.".to_owned()),
+            },
+            _ => HookVerdict::Allow,
+        }
+    }
+}
+
+/// A hook that only allows the command to be run by a predefined set of users.
+pub struct UserAllowlistHook {
+    pub allowed_usernames: HashSet<String>,
+}
+#[async_trait]
+impl CommandHook for UserAllowlistHook {
+    async fn check(&self, context: &HookContext) -> HookVerdict {
+        if self.allowed_usernames.contains(&context.sender_username) {
+            HookVerdict::Allow
+        } else {
+            HookVerdict::Deny {
+                feedback: Some("You are not permitted to do that.".to_owned()),
+            }
+        }
+    }
+}
+
+/// A hook that only allows one invocation per user per `cooldown` duration.
+pub struct CooldownHook {
+    cooldown: Duration,
+    last_use_per_user: Mutex<HashMap<String, Instant>>,
+}
+impl CooldownHook {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last_use_per_user: Mutex::new("CooldownHook::last_use_per_user", HashMap::new()),
+        }
+    }
+}
+#[async_trait]
+impl CommandHook for CooldownHook {
+    async fn check(&self, context: &HookContext) -> HookVerdict {
+        let now = Instant::now();
+        let mut last_use_guard = self.last_use_per_user.lock().await;
+        if let Some(last_use) = last_use_guard.get(&context.sender_username) {
+            if now.duration_since(*last_use) < self.cooldown {
+                return HookVerdict::Deny {
+                    feedback: Some("You must wait a bit before doing that again.".to_owned()),
+                };
+            }
+        }
+        last_use_guard.insert(context.sender_username.clone(), now);
+        HookVerdict::Allow
+    }
+}
+
+
+/// Builds a [`HookRegistry`] from a JSON configuration value shaped as an object mapping hook
+/// name to a hook definition `{"type": "channel_allowlist"|"user_allowlist"|"cooldown", ...}`
+/// (the remaining fields depending on `type`). Unrecognized `type` values are rejected.
+pub fn hooks_from_config(hooks_value: &serde_json::Value) -> Result<HookRegistry, &'static str> {
+    let mut registry = HookRegistry::new();
+
+    for (hook_name, hook_def) in hooks_value.entries_or_empty() {
+        let hook_type = hook_def["type"].as_str()
+            .ok_or("hook definition has no \"type\"")?;
+
+        let hook: Arc<dyn CommandHook> = match hook_type {
+            "channel_allowlist" => {
+                let mut allowed_channels = HashSet::new();
+                for channel_value in hook_def["channels"].members().ok_or("channels is not a list")? {
+                    let channel_name = channel_value.as_str().ok_or("channel is not a string")?;
+                    allowed_channels.insert(channel_name.to_owned());
+                }
+                Arc::new(ChannelAllowlistHook { allowed_channels })
+            },
+            "user_allowlist" => {
+                let mut allowed_usernames = HashSet::new();
+                for username_value in hook_def["usernames"].members().ok_or("usernames is not a list")? {
+                    let username = username_value.as_str().ok_or("username is not a string")?;
+                    allowed_usernames.insert(username.to_owned());
+                }
+                Arc::new(UserAllowlistHook { allowed_usernames })
+            },
+            "cooldown" => {
+                let seconds = hook_def["seconds"].as_f64()
+                    .ok_or("seconds is not a number")?;
+                Arc::new(CooldownHook::new(Duration::from_secs_f64(seconds)))
+            },
+            _ => return Err("unknown hook type"),
+        };
+
+        registry.register(hook_name.clone(), hook);
+    }
+
+    Ok(registry)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct AlwaysDenyHook;
+    #[async_trait]
+    impl CommandHook for AlwaysDenyHook {
+        async fn check(&self, _context: &HookContext) -> HookVerdict {
+            HookVerdict::Deny { feedback: Some("nope".to_owned()) }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_short_circuit_on_first_denial() {
+        let mut registry = HookRegistry::new();
+        registry.register("always_allow", Arc::new(UserAllowlistHook { allowed_usernames: {
+            let mut s = HashSet::new();
+            s.insert("alice".to_owned());
+            s
+        } }));
+        registry.register("always_deny", Arc::new(AlwaysDenyHook));
+
+        let context = HookContext {
+            command_name: "test".to_owned(),
+            channel_name: None,
+            sender_username: "alice".to_owned(),
+        };
+
+        let hook_names = vec!["always_allow".to_owned(), "always_deny".to_owned()];
+        let verdict = registry.evaluate(&hook_names, &context).await;
+        assert_eq!(verdict, HookVerdict::Deny { feedback: Some("nope".to_owned()) });
+
+        let allow_only = vec!["always_allow".to_owned()];
+        let verdict = registry.evaluate(&allow_only, &context).await;
+        assert_eq!(verdict, HookVerdict::Allow);
+    }
+}
@@ -0,0 +1,296 @@
+use std::error::Error;
+use std::io::Read;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use flate2::read::GzDecoder;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use serde::de::DeserializeOwned;
+
+use crate::errors::HttpError;
+
+
+/// Resolves a hostname to the set of IP addresses it points at. Implemented by default by
+/// [`SystemDnsResolver`]; operators that need to pin a specific (e.g. internal, split-horizon)
+/// upstream instead of the operating system's configured resolver can supply their own
+/// implementation to [`HttpClient::new`].
+#[async_trait]
+pub trait DnsResolver: Send + Sync {
+    async fn resolve(&self, host: &str) -> std::io::Result<Vec<IpAddr>>;
+}
+
+
+/// The default [`DnsResolver`]: resolves using the operating system's standard resolution
+/// mechanism, the same one used for any other outgoing connection.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemDnsResolver;
+#[async_trait]
+impl DnsResolver for SystemDnsResolver {
+    async fn resolve(&self, host: &str) -> std::io::Result<Vec<IpAddr>> {
+        let addrs = tokio::net::lookup_host((host, 0)).await?;
+        Ok(addrs.map(|socket_addr| socket_addr.ip()).collect())
+    }
+}
+
+
+fn is_unique_local(addr: &Ipv6Addr) -> bool {
+    (addr.segments()[0] & 0xfe00) == 0xfc00
+}
+
+/// Decides whether a resolved IP address may be connected to. The default policy (see
+/// [`IpPolicy::default`]) rejects loopback, link-local, RFC 1918 private, and IPv6 unique-local
+/// addresses (including IPv4-mapped IPv6 addresses falling into one of those ranges), closing off
+/// the most common SSRF vector of a user-influenced URL resolving to an address internal to the
+/// deployment.
+#[derive(Clone, Copy, Debug)]
+pub struct IpPolicy {
+    pub allow_loopback: bool,
+    pub allow_link_local: bool,
+    pub allow_private: bool,
+    pub allow_unique_local: bool,
+}
+impl Default for IpPolicy {
+    fn default() -> Self {
+        Self {
+            allow_loopback: false,
+            allow_link_local: false,
+            allow_private: false,
+            allow_unique_local: false,
+        }
+    }
+}
+impl IpPolicy {
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        match ip {
+            IpAddr::V4(v4) => {
+                // routed to localhost on Linux despite being neither loopback, private nor
+                // link-local by Rust's classification; a classic SSRF bypass if left unchecked
+                if v4.is_unspecified() { return false; }
+                if v4.is_multicast() || v4.is_broadcast() { return false; }
+                if !self.allow_loopback && v4.is_loopback() { return false; }
+                if !self.allow_link_local && v4.is_link_local() { return false; }
+                if !self.allow_private && v4.is_private() { return false; }
+                true
+            },
+            IpAddr::V6(v6) => {
+                if let Some(mapped_v4) = v6.to_ipv4_mapped() {
+                    return self.is_allowed(&IpAddr::V4(mapped_v4));
+                }
+                if v6.is_unspecified() { return false; }
+                if v6.is_multicast() { return false; }
+                if !self.allow_loopback && v6.is_loopback() { return false; }
+                if !self.allow_link_local && v6.is_unicast_link_local() { return false; }
+                if !self.allow_unique_local && is_unique_local(v6) { return false; }
+                true
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_unique_local() {
+        assert!(is_unique_local(&"fc00::1".parse().unwrap()));
+        assert!(is_unique_local(&"fd12:3456:789a::1".parse().unwrap()));
+        assert!(!is_unique_local(&"2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_rejects_loopback() {
+        let policy = IpPolicy::default();
+        assert!(!policy.is_allowed(&"127.0.0.1".parse().unwrap()));
+        assert!(!policy.is_allowed(&"::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_rejects_link_local() {
+        let policy = IpPolicy::default();
+        assert!(!policy.is_allowed(&"169.254.1.1".parse().unwrap()));
+        assert!(!policy.is_allowed(&"fe80::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_rejects_private() {
+        let policy = IpPolicy::default();
+        assert!(!policy.is_allowed(&"10.0.0.1".parse().unwrap()));
+        assert!(!policy.is_allowed(&"192.168.1.1".parse().unwrap()));
+        assert!(!policy.is_allowed(&"172.16.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_rejects_unique_local() {
+        let policy = IpPolicy::default();
+        assert!(!policy.is_allowed(&"fc00::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_rejects_unspecified() {
+        let policy = IpPolicy::default();
+        assert!(!policy.is_allowed(&"0.0.0.0".parse().unwrap()));
+        assert!(!policy.is_allowed(&"::".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_rejects_multicast_and_broadcast() {
+        let policy = IpPolicy::default();
+        assert!(!policy.is_allowed(&"224.0.0.1".parse().unwrap()));
+        assert!(!policy.is_allowed(&"255.255.255.255".parse().unwrap()));
+        assert!(!policy.is_allowed(&"ff02::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_rejects_ipv4_mapped() {
+        let policy = IpPolicy::default();
+        assert!(!policy.is_allowed(&"::ffff:127.0.0.1".parse().unwrap()));
+        assert!(!policy.is_allowed(&"::ffff:10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_accepts_public() {
+        let policy = IpPolicy::default();
+        assert!(policy.is_allowed(&"93.184.216.34".parse().unwrap()));
+        assert!(policy.is_allowed(&"2001:db8::1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_is_allowed_respects_overrides() {
+        let policy = IpPolicy { allow_loopback: true, ..IpPolicy::default() };
+        assert!(policy.is_allowed(&"127.0.0.1".parse().unwrap()));
+        assert!(!policy.is_allowed(&"10.0.0.1".parse().unwrap()));
+    }
+}
+
+
+/// Adapts a [`DnsResolver`] and an [`IpPolicy`] into a `reqwest` DNS resolver, so that every
+/// connection `reqwest` makes is filtered by the policy before it is ever dialed.
+struct PolicyEnforcingResolver {
+    inner: Arc<dyn DnsResolver>,
+    policy: IpPolicy,
+}
+impl Resolve for PolicyEnforcingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let inner = Arc::clone(&self.inner);
+        let policy = self.policy;
+        let host = name.as_str().to_owned();
+        Box::pin(async move {
+            let resolved = inner.resolve(&host).await
+                .map_err(|e| -> Box<dyn Error + Send + Sync> { Box::new(e) })?;
+            let allowed: Vec<SocketAddr> = resolved.into_iter()
+                .filter(|ip| policy.is_allowed(ip))
+                .map(|ip| SocketAddr::new(ip, 0))
+                .collect();
+            if allowed.is_empty() {
+                return Err(format!("no permitted addresses for {:?} (SSRF policy)", host).into());
+            }
+            let addrs: Addrs = Box::new(allowed.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+
+/// The unprocessed result of [`HttpClient::fetch`]: the response status and headers, plus its raw
+/// (possibly still gzip-encoded) body. Exposed for callers such as a conditional-GET poller that
+/// need to inspect the status (e.g. for `304 Not Modified`) or headers (e.g. `ETag`) themselves
+/// instead of going through [`HttpClient::get_bytes`]'s all-or-nothing status check.
+pub struct RawResponse {
+    pub status: reqwest::StatusCode,
+    pub headers: reqwest::header::HeaderMap,
+    pub body: Vec<u8>,
+}
+
+fn decode_if_gzip(headers: &reqwest::header::HeaderMap, body: Vec<u8>) -> Result<Vec<u8>, HttpError> {
+    let is_gzip = headers
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.eq_ignore_ascii_case("gzip"))
+        .unwrap_or(false);
+    if !is_gzip {
+        return Ok(body);
+    }
+
+    let mut decoder = GzDecoder::new(&body[..]);
+    let mut decoded = Vec::new();
+    decoder.read_to_end(&mut decoded)
+        .map_err(HttpError::DecodingAsGzip)?;
+    Ok(decoded)
+}
+
+
+/// An SSRF-hardened HTTP client shared by plugins that need to fetch user- or operator-supplied
+/// URLs. Every connection is resolved through a pluggable [`DnsResolver`] and filtered by an
+/// [`IpPolicy`] before being dialed, and every failure mode of the fetch pipeline (obtaining the
+/// response, reading its body, decoding gzip, decoding UTF-8, a non-OK status, parsing JSON) is
+/// reported uniformly as an [`HttpError`], the same type [`crate::interfaces::RocketBotInterface::obtain_http_resource`]
+/// uses for fetches against the chat server itself.
+pub struct HttpClient {
+    client: reqwest::Client,
+}
+impl HttpClient {
+    pub fn new(resolver: Arc<dyn DnsResolver>, policy: IpPolicy) -> Self {
+        let policy_resolver = PolicyEnforcingResolver { inner: resolver, policy };
+        let client = reqwest::Client::builder()
+            .dns_resolver(Arc::new(policy_resolver))
+            .build()
+            .expect("failed to build HTTP client");
+        Self { client }
+    }
+
+    /// Builds an [`HttpClient`] using [`SystemDnsResolver`] and the default, most restrictive
+    /// [`IpPolicy`].
+    pub fn with_default_resolver() -> Self {
+        Self::new(Arc::new(SystemDnsResolver), IpPolicy::default())
+    }
+
+    /// Performs a GET request against `url` with the given extra headers (e.g. `If-None-Match`)
+    /// and returns the response as-is: no status check and no gzip decoding. Most callers want
+    /// [`HttpClient::get_bytes`]/[`HttpClient::get_text`]/[`HttpClient::get_json`] instead; this
+    /// is the low-level primitive for callers (such as a conditional-GET poller) that need the
+    /// raw status and headers.
+    pub async fn fetch(&self, url: &str, extra_headers: &[(reqwest::header::HeaderName, &str)]) -> Result<RawResponse, HttpError> {
+        let mut request = self.client.get(url);
+        for (name, value) in extra_headers {
+            request = request.header(name.clone(), *value);
+        }
+
+        let response = request.send().await
+            .map_err(|e| HttpError::ObtainingResponse(Box::new(e)))?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await
+            .map_err(|e| HttpError::ObtainingResponseBody(Box::new(e)))?
+            .to_vec();
+
+        Ok(RawResponse { status, headers, body })
+    }
+
+    /// Fetches `url` and returns its response body, transparently decoding it if the response
+    /// carries a `Content-Encoding: gzip` header. Fails with [`HttpError::StatusNotOk`] if the
+    /// response status is not successful.
+    pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>, HttpError> {
+        let response = self.fetch(url, &[]).await?;
+        if !response.status.is_success() {
+            return Err(HttpError::StatusNotOk(response.status));
+        }
+        decode_if_gzip(&response.headers, response.body)
+    }
+
+    /// Fetches `url` and decodes its response body as UTF-8 text.
+    pub async fn get_text(&self, url: &str) -> Result<String, HttpError> {
+        let bytes = self.get_bytes(url).await?;
+        String::from_utf8(bytes)
+            .map_err(HttpError::DecodingAsUtf8)
+    }
+
+    /// Fetches `url` and parses its response body as JSON.
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, HttpError> {
+        let bytes = self.get_bytes(url).await?;
+        serde_json::from_slice(&bytes)
+            .map_err(HttpError::ParsingJson)
+    }
+}
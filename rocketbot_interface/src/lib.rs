@@ -1,6 +1,8 @@
 pub mod clown;
 pub mod commands;
 pub mod errors;
+pub mod hooks;
+pub mod http_client;
 pub mod interfaces;
 pub mod macros;
 pub mod message;
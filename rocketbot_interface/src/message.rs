@@ -1,11 +1,15 @@
 use std::fmt;
+use std::fmt::Write as _;
 
+use serde_json;
 
-fn write_joined_mapped<T, I, F>(f: &mut fmt::Formatter<'_>, pieces: I, glue: &str, mut format_item: F) -> fmt::Result
+
+fn write_joined_mapped<W, T, I, F>(f: &mut W, pieces: I, glue: &str, mut format_item: F) -> fmt::Result
     where
+        W: fmt::Write,
         T: fmt::Display,
         I: IntoIterator<Item = T>,
-        F: FnMut(&mut fmt::Formatter<'_>, &T) -> fmt::Result {
+        F: FnMut(&mut W, &T) -> fmt::Result {
     let mut first = true;
     for piece in pieces.into_iter() {
         if first {
@@ -17,15 +21,42 @@ fn write_joined_mapped<T, I, F>(f: &mut fmt::Formatter<'_>, pieces: I, glue: &st
     }
     Ok(())
 }
-fn write_joined<T: fmt::Display, I: IntoIterator<Item = T>>(f: &mut fmt::Formatter<'_>, pieces: I, glue: &str) -> fmt::Result {
+fn write_joined<W: fmt::Write, T: fmt::Display, I: IntoIterator<Item = T>>(f: &mut W, pieces: I, glue: &str) -> fmt::Result {
     write_joined_mapped(f, pieces, glue, |f, piece| write!(f, "{}", piece))
 }
-fn write_concatenated<T: fmt::Display, I: IntoIterator<Item = T>>(f: &mut fmt::Formatter<'_>, pieces: I) -> fmt::Result {
+fn write_concatenated<W: fmt::Write, T: fmt::Display, I: IntoIterator<Item = T>>(f: &mut W, pieces: I) -> fmt::Result {
     write_joined(f, pieces, "")
 }
 
+fn write_plain_text_fragments(fragments: &[InlineFragment], buf: &mut String) {
+    for fragment in fragments {
+        fragment.write_plain_text(buf);
+    }
+}
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+fn write_markdown_fragments(fragments: &[InlineFragment], buf: &mut String) {
+    for fragment in fragments {
+        fragment.write_markdown(buf);
+    }
+}
+
+/// Appends `s` to `buf` as a double-quoted s-expression atom, escaping backslashes and quotes.
+fn write_sexpr_string(buf: &mut String, s: &str) {
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            _ => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+
+/// Inline fragments carry an [`Unknown`][Self::Unknown] variant (holding the raw, unrecognized
+/// JSON node verbatim), which precludes deriving `Eq`/`Hash` for this type.
+#[derive(Clone, Debug, PartialEq)]
 pub enum InlineFragment {
     PlainText(String),
     Bold(Vec<InlineFragment>),
@@ -36,6 +67,191 @@ pub enum InlineFragment {
     MentionUser(String),
     Emoji(Emoji),
     InlineCode(String),
+    /// Inline KaTeX math, holding the raw LaTeX source.
+    InlineMath(String),
+    /// An inline fragment of a type not recognized by the strict parser, holding the raw JSON
+    /// node verbatim so it can be re-emitted losslessly. Produced only by the lenient parsing
+    /// path (see `rocketbot::jsonage::parse_message_lenient`).
+    Unknown(serde_json::Value),
+}
+impl InlineFragment {
+    /// Flattens this fragment (and its children) into plain text: formatting fragments
+    /// ([`Bold`][Self::Bold], [`Strike`][Self::Strike], [`Italic`][Self::Italic]) are stripped down
+    /// to their contained text, a [`Link`][Self::Link] contributes its label, and mentions/emoji are
+    /// rendered as their literal name. Intended for command matching, logging, and search indexing.
+    pub fn to_plain_text(&self) -> String {
+        let mut buf = String::new();
+        self.write_plain_text(&mut buf);
+        buf
+    }
+
+    fn write_plain_text(&self, buf: &mut String) {
+        match self {
+            InlineFragment::PlainText(pt) => buf.push_str(pt),
+            InlineFragment::Bold(fragments)
+            | InlineFragment::Strike(fragments)
+            | InlineFragment::Italic(fragments) => {
+                for fragment in fragments {
+                    fragment.write_plain_text(buf);
+                }
+            },
+            InlineFragment::Link(_target, label_fragments) => {
+                for fragment in label_fragments {
+                    fragment.write_plain_text(buf);
+                }
+            },
+            InlineFragment::MentionChannel(target) => {
+                buf.push('#');
+                buf.push_str(target);
+            },
+            InlineFragment::MentionUser(target) => {
+                buf.push('@');
+                buf.push_str(target);
+            },
+            InlineFragment::Emoji(emoji) => emoji.write_plain_text(buf),
+            InlineFragment::InlineCode(code) => buf.push_str(code),
+            InlineFragment::InlineMath(source) => buf.push_str(source),
+            InlineFragment::Unknown(value) => write!(buf, "{}", value).unwrap(),
+        }
+    }
+
+    /// Renders this fragment (and its children) as Markdown source text.
+    pub fn to_markdown(&self) -> String {
+        let mut buf = String::new();
+        self.write_markdown(&mut buf);
+        buf
+    }
+
+    fn write_markdown(&self, buf: &mut String) {
+        match self {
+            InlineFragment::PlainText(pt) => buf.push_str(pt),
+            InlineFragment::Bold(fragments) => {
+                buf.push_str("**");
+                for fragment in fragments {
+                    fragment.write_markdown(buf);
+                }
+                buf.push_str("**");
+            },
+            InlineFragment::Strike(fragments) => {
+                buf.push_str("~~");
+                for fragment in fragments {
+                    fragment.write_markdown(buf);
+                }
+                buf.push_str("~~");
+            },
+            InlineFragment::Italic(fragments) => {
+                buf.push('_');
+                for fragment in fragments {
+                    fragment.write_markdown(buf);
+                }
+                buf.push('_');
+            },
+            InlineFragment::Link(target, label_fragments) => {
+                buf.push('[');
+                for fragment in label_fragments {
+                    fragment.write_markdown(buf);
+                }
+                buf.push_str("](");
+                buf.push_str(target);
+                buf.push(')');
+            },
+            InlineFragment::MentionChannel(target) => {
+                buf.push('#');
+                buf.push_str(target);
+            },
+            InlineFragment::MentionUser(target) => {
+                buf.push('@');
+                buf.push_str(target);
+            },
+            InlineFragment::Emoji(emoji) => write!(buf, "{}", emoji).unwrap(),
+            InlineFragment::InlineCode(code) => {
+                buf.push('`');
+                buf.push_str(code);
+                buf.push('`');
+            },
+            InlineFragment::InlineMath(source) => {
+                buf.push('$');
+                buf.push_str(source);
+                buf.push('$');
+            },
+            InlineFragment::Unknown(value) => write!(buf, "{}", value).unwrap(),
+        }
+    }
+
+    /// Renders this fragment (and its children) as a nested parenthesized s-expression, naming
+    /// this node after its variant (in kebab-case) and recursing into its children.
+    fn write_sexpr(&self, buf: &mut String) {
+        match self {
+            InlineFragment::PlainText(pt) => {
+                buf.push_str("(plain ");
+                write_sexpr_string(buf, pt);
+                buf.push(')');
+            },
+            InlineFragment::Bold(fragments) => {
+                buf.push_str("(bold");
+                for fragment in fragments {
+                    buf.push(' ');
+                    fragment.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            InlineFragment::Strike(fragments) => {
+                buf.push_str("(strike");
+                for fragment in fragments {
+                    buf.push(' ');
+                    fragment.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            InlineFragment::Italic(fragments) => {
+                buf.push_str("(italic");
+                for fragment in fragments {
+                    buf.push(' ');
+                    fragment.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            InlineFragment::Link(target, label_fragments) => {
+                buf.push_str("(link ");
+                write_sexpr_string(buf, target);
+                for fragment in label_fragments {
+                    buf.push(' ');
+                    fragment.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            InlineFragment::MentionChannel(target) => {
+                buf.push_str("(mention-channel ");
+                write_sexpr_string(buf, target);
+                buf.push(')');
+            },
+            InlineFragment::MentionUser(target) => {
+                buf.push_str("(mention-user ");
+                write_sexpr_string(buf, target);
+                buf.push(')');
+            },
+            InlineFragment::Emoji(emoji) => {
+                buf.push_str("(emoji ");
+                write_sexpr_string(buf, &emoji.to_string());
+                buf.push(')');
+            },
+            InlineFragment::InlineCode(code) => {
+                buf.push_str("(inline-code ");
+                write_sexpr_string(buf, code);
+                buf.push(')');
+            },
+            InlineFragment::InlineMath(source) => {
+                buf.push_str("(inline-math ");
+                write_sexpr_string(buf, source);
+                buf.push(')');
+            },
+            InlineFragment::Unknown(value) => {
+                buf.push_str("(unknown ");
+                write_sexpr_string(buf, &value.to_string());
+                buf.push(')');
+            },
+        }
+    }
 }
 impl fmt::Display for InlineFragment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -70,6 +286,10 @@ impl fmt::Display for InlineFragment {
                 => write!(f, "{}", tgt),
             InlineFragment::InlineCode(tgt)
                 => write!(f, "`{}`", tgt),
+            InlineFragment::InlineMath(source)
+                => write!(f, "${}$", source),
+            InlineFragment::Unknown(value)
+                => write!(f, "{}", value),
         }
     }
 }
@@ -97,7 +317,9 @@ impl fmt::Display for ListItem {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// Message fragments carry an [`Unknown`][Self::Unknown] variant (holding the raw, unrecognized
+/// JSON node verbatim), which precludes deriving `Eq`/`Hash` for this type.
+#[derive(Clone, Debug, PartialEq)]
 pub enum MessageFragment {
     BigEmoji(Vec<Emoji>),
     UnorderedList(Vec<ListItem>),
@@ -107,6 +329,218 @@ pub enum MessageFragment {
     Paragraph(Vec<InlineFragment>),
     Code(String, Vec<InlineFragment>),
     Heading(u32, Vec<InlineFragment>),
+    /// Block KaTeX math, holding the raw LaTeX source.
+    Math(String),
+    /// A paragraph of a type not recognized by the strict parser, holding the raw JSON node
+    /// verbatim so it can be re-emitted losslessly. Produced only by the lenient parsing path
+    /// (see `rocketbot::jsonage::parse_message_lenient`).
+    Unknown(serde_json::Value),
+}
+impl MessageFragment {
+    /// Flattens this fragment (and its children) into plain text, mirroring
+    /// [`InlineFragment::to_plain_text`] at the paragraph level. Intended for command matching,
+    /// logging, and search indexing where callers currently have to re-walk the enum themselves.
+    pub fn to_plain_text(&self) -> String {
+        let mut buf = String::new();
+        self.write_plain_text(&mut buf);
+        buf
+    }
+
+    fn write_plain_text(&self, buf: &mut String) {
+        match self {
+            MessageFragment::BigEmoji(emoji) => {
+                write_joined_mapped(buf, emoji, " ", |buf, e| Ok(e.write_plain_text(buf))).unwrap()
+            },
+            MessageFragment::UnorderedList(items)
+            | MessageFragment::OrderedList(items) => {
+                write_joined_mapped(buf, items, "\n", |buf, item| Ok(write_plain_text_fragments(&item.label, buf))).unwrap()
+            },
+            MessageFragment::Quote(fragments) => {
+                write_joined_mapped(buf, fragments, "\n", |buf, frag| Ok(frag.write_plain_text(buf))).unwrap()
+            },
+            MessageFragment::Tasks(tasks) => {
+                write_joined_mapped(buf, tasks, "\n", |buf, task| Ok(write_plain_text_fragments(&task.label, buf))).unwrap()
+            },
+            MessageFragment::Paragraph(fragments)
+            | MessageFragment::Heading(_, fragments) => {
+                write_plain_text_fragments(fragments, buf);
+            },
+            MessageFragment::Code(_language, lines) => {
+                write_joined_mapped(buf, lines, "\n", |buf, line| Ok(line.write_plain_text(buf))).unwrap()
+            },
+            MessageFragment::Math(source) => buf.push_str(source),
+            MessageFragment::Unknown(value) => write!(buf, "{}", value).unwrap(),
+        }
+    }
+
+    /// Renders this fragment (and its children) as Markdown source text: `**bold**`, `~~strike~~`,
+    /// `[label](url)`, fenced code blocks with the stored language, `>`-prefixed quotes, and
+    /// ordered/unordered list markers.
+    pub fn to_markdown(&self) -> String {
+        let mut buf = String::new();
+        self.write_markdown(&mut buf);
+        buf
+    }
+
+    fn write_markdown(&self, buf: &mut String) {
+        match self {
+            MessageFragment::BigEmoji(emoji)
+                => write_joined(buf, emoji, " ").unwrap(),
+            MessageFragment::UnorderedList(items) => {
+                write_joined_mapped(buf, items, "\n", |buf, item| {
+                    buf.push_str("- ");
+                    Ok(write_markdown_fragments(&item.label, buf))
+                }).unwrap()
+            },
+            MessageFragment::OrderedList(items) => {
+                let mut i = 0usize;
+                write_joined_mapped(buf, items, "\n", |buf, item| {
+                    i += 1;
+                    write!(buf, "{}. ", i)?;
+                    Ok(write_markdown_fragments(&item.label, buf))
+                }).unwrap()
+            },
+            MessageFragment::Quote(fragments) => {
+                write_joined_mapped(buf, fragments, "\n", |buf, frag| {
+                    let rendered = frag.to_markdown();
+                    write_joined_mapped(buf, rendered.lines(), "\n", |buf, line| write!(buf, "> {}", line))
+                }).unwrap()
+            },
+            MessageFragment::Tasks(tasks) => {
+                write_joined_mapped(buf, tasks, "\n", |buf, task| {
+                    let checkmark = if task.checked { 'x' } else { ' ' };
+                    write!(buf, "- [{}] ", checkmark)?;
+                    Ok(write_markdown_fragments(&task.label, buf))
+                }).unwrap()
+            },
+            MessageFragment::Paragraph(fragments) => {
+                for fragment in fragments {
+                    fragment.write_markdown(buf);
+                }
+            },
+            MessageFragment::Code(language, lines) => {
+                writeln!(buf, "```{}", language).unwrap();
+                for line in lines {
+                    line.write_markdown(buf);
+                    buf.push('\n');
+                }
+                buf.push_str("```");
+            },
+            MessageFragment::Heading(level, fragments) => {
+                for _ in 0..*level {
+                    buf.push('#');
+                }
+                buf.push(' ');
+                for fragment in fragments {
+                    fragment.write_markdown(buf);
+                }
+            },
+            MessageFragment::Math(source) => {
+                buf.push_str("$$\n");
+                buf.push_str(source);
+                buf.push_str("\n$$");
+            },
+            MessageFragment::Unknown(value) => write!(buf, "{}", value).unwrap(),
+        }
+    }
+
+    /// Renders this fragment (and its children) as a nested parenthesized s-expression, naming
+    /// this node after its variant (in kebab-case) and recursing into its children, e.g.
+    /// `(paragraph (bold (plain "hi")) (link "url" (plain "label")))`. Intended as a compact,
+    /// diffable debug dump of parsed message trees, for golden tests and manual inspection of
+    /// parser output (see [`to_sexpr`]).
+    fn write_sexpr(&self, buf: &mut String) {
+        match self {
+            MessageFragment::BigEmoji(emoji) => {
+                buf.push_str("(big-emoji");
+                for e in emoji {
+                    buf.push_str(" (emoji ");
+                    write_sexpr_string(buf, &e.to_string());
+                    buf.push(')');
+                }
+                buf.push(')');
+            },
+            MessageFragment::UnorderedList(items) => {
+                buf.push_str("(unordered-list");
+                for item in items {
+                    buf.push_str(" (item");
+                    for fragment in &item.label {
+                        buf.push(' ');
+                        fragment.write_sexpr(buf);
+                    }
+                    buf.push(')');
+                }
+                buf.push(')');
+            },
+            MessageFragment::OrderedList(items) => {
+                buf.push_str("(ordered-list");
+                for item in items {
+                    buf.push_str(" (item");
+                    for fragment in &item.label {
+                        buf.push(' ');
+                        fragment.write_sexpr(buf);
+                    }
+                    buf.push(')');
+                }
+                buf.push(')');
+            },
+            MessageFragment::Quote(fragments) => {
+                buf.push_str("(quote");
+                for fragment in fragments {
+                    buf.push(' ');
+                    fragment.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            MessageFragment::Tasks(tasks) => {
+                buf.push_str("(tasks");
+                for task in tasks {
+                    buf.push_str(if task.checked { " (task-checked" } else { " (task-unchecked" });
+                    for fragment in &task.label {
+                        buf.push(' ');
+                        fragment.write_sexpr(buf);
+                    }
+                    buf.push(')');
+                }
+                buf.push(')');
+            },
+            MessageFragment::Paragraph(fragments) => {
+                buf.push_str("(paragraph");
+                for fragment in fragments {
+                    buf.push(' ');
+                    fragment.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            MessageFragment::Code(language, lines) => {
+                buf.push_str("(code ");
+                write_sexpr_string(buf, language);
+                for line in lines {
+                    buf.push(' ');
+                    line.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            MessageFragment::Heading(level, fragments) => {
+                write!(buf, "(heading {}", level).unwrap();
+                for fragment in fragments {
+                    buf.push(' ');
+                    fragment.write_sexpr(buf);
+                }
+                buf.push(')');
+            },
+            MessageFragment::Math(source) => {
+                buf.push_str("(math ");
+                write_sexpr_string(buf, source);
+                buf.push(')');
+            },
+            MessageFragment::Unknown(value) => {
+                buf.push_str("(unknown ");
+                write_sexpr_string(buf, &value.to_string());
+                buf.push(')');
+            },
+        }
+    }
 }
 impl fmt::Display for MessageFragment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -153,6 +587,10 @@ impl fmt::Display for MessageFragment {
                 write_concatenated(f, pieces)?;
                 write!(f, "\n")
             },
+            MessageFragment::Math(source)
+                => write!(f, "$$\n{}\n$$\n", source),
+            MessageFragment::Unknown(value)
+                => write!(f, "{}\n", value),
         }
     }
 }
@@ -163,6 +601,14 @@ pub enum Emoji {
     Code(String),
     Unicode(String),
 }
+impl Emoji {
+    fn write_plain_text(&self, buf: &mut String) {
+        match self {
+            Self::Code(c) => buf.push_str(c),
+            Self::Unicode(s) => buf.push_str(s),
+        }
+    }
+}
 impl fmt::Display for Emoji {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -202,6 +648,8 @@ pub fn collect_inline_urls<'a, I: Iterator<Item = &'a InlineFragment>>(fragments
                 let mut inline_urls = collect_inline_urls(ilfs.iter());
                 urls.append(&mut inline_urls);
             },
+            InlineFragment::InlineMath(_source) => {},
+            InlineFragment::Unknown(_value) => {},
         }
     }
     urls
@@ -243,7 +691,60 @@ pub fn collect_urls<'a, I: Iterator<Item = &'a MessageFragment>>(fragments: I) -
                     urls.append(&mut inline_urls);
                 }
             },
+            MessageFragment::Math(_source) => {},
+            MessageFragment::Unknown(_value) => {},
         }
     }
     urls
 }
+
+/// Renders `fragments` as a nested parenthesized s-expression, one top-level node per fragment
+/// separated by a space, e.g. `(paragraph (bold (plain "hi")) (link "url" (plain "label")))`.
+/// Gives maintainers a compact, diffable snapshot of a parsed message tree, for debugging parser
+/// output and golden tests over deeply nested messages.
+pub fn to_sexpr(fragments: &[MessageFragment]) -> String {
+    let mut buf = String::new();
+    let mut first = true;
+    for fragment in fragments {
+        if !first {
+            buf.push(' ');
+        }
+        first = false;
+        fragment.write_sexpr(&mut buf);
+    }
+    buf
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_sexpr() {
+        let fragments = vec![
+            MessageFragment::Paragraph(vec![
+                InlineFragment::Bold(vec![InlineFragment::PlainText("hi".to_owned())]),
+                InlineFragment::Link(
+                    "url".to_owned(),
+                    vec![InlineFragment::PlainText("label".to_owned())],
+                ),
+            ]),
+        ];
+        assert_eq!(
+            to_sexpr(&fragments),
+            "(paragraph (bold (plain \"hi\")) (link \"url\" (plain \"label\")))",
+        );
+    }
+
+    #[test]
+    fn test_to_sexpr_escapes_quotes() {
+        let fragments = vec![
+            MessageFragment::Paragraph(vec![InlineFragment::PlainText("say \"hi\"".to_owned())]),
+        ];
+        assert_eq!(
+            to_sexpr(&fragments),
+            "(paragraph (plain \"say \\\"hi\\\"\"))",
+        );
+    }
+}
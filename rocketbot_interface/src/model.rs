@@ -119,7 +119,9 @@ impl EditInfo {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// Carries a parsed [`MessageFragment`] tree, which (due to its `Unknown` variant holding a raw
+/// [`serde_json::Value`]) does not implement `Eq`/`Hash`, so neither does this type.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Message {
     pub id: String,
     pub timestamp: DateTime<Utc>,
@@ -183,7 +185,9 @@ impl MessageAttachment {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// Embeds a [`Message`], so (transitively, due to `MessageFragment::Unknown`) this type does not
+/// implement `Eq`/`Hash` either.
+#[derive(Clone, Debug, PartialEq)]
 pub struct ChannelMessage {
     pub message: Message,
     pub channel: Channel,
@@ -200,7 +204,9 @@ impl ChannelMessage {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// Embeds a [`Message`], so (transitively, due to `MessageFragment::Unknown`) this type does not
+/// implement `Eq`/`Hash` either.
+#[derive(Clone, Debug, PartialEq)]
 pub struct PrivateMessage {
     pub message: Message,
     pub conversation: PrivateConversation,
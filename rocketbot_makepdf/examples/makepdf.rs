@@ -3,27 +3,39 @@ use std::ffi::OsString;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 
+use rocketbot_bim_common::ride_table::RideTableData;
 use rocketbot_makepdf::render_description;
 use rocketbot_makepdf::model::PdfDescription;
+use rocketbot_makepdf::ride_card::ride_table_to_pdf;
 use serde_json;
 
 
 fn main() {
     let args: Vec<OsString> = env::args_os().collect();
     if args.len() < 3 || args.len() > 4 {
-        eprintln!("Usage: makepdf [--bd] DEFINITION.json OUTPUT.pdf");
+        eprintln!("Usage: makepdf [--bd|--ride-table] DEFINITION.json OUTPUT.pdf");
         std::process::exit(1);
     }
 
     let mut read_base_description = false;
+    let mut read_ride_table = false;
     let first_file_index = if args[1] == "--bd" {
         read_base_description = true;
         2
+    } else if args[1] == "--ride-table" {
+        read_ride_table = true;
+        2
     } else {
         1
     };
 
-    let defn: PdfDescription = {
+    let defn: PdfDescription = if read_ride_table {
+        let ride_file = File::open(&args[first_file_index])
+            .expect("failed to open ride table file");
+        let ride: RideTableData = serde_json::from_reader(ride_file)
+            .expect("failed to deserialize ride table file");
+        ride_table_to_pdf(&ride)
+    } else {
         let defn_file = File::open(&args[first_file_index])
             .expect("failed to open definition file");
         let val: serde_json::Value = serde_json::from_reader(defn_file)
@@ -1,4 +1,5 @@
 pub mod model;
+pub mod ride_card;
 
 
 use std::collections::HashMap;
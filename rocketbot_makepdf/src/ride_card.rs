@@ -0,0 +1,113 @@
+//! Renders a [`RideTableData`] directly into a [`PdfDescription`], turning the ride-table output
+//! format and the PDF renderer into a single pipeline.
+
+use std::collections::HashMap;
+
+use rocketbot_bim_common::LastRider;
+use rocketbot_bim_common::ride_table::RideTableData;
+use rocketbot_render_text::{DEFAULT_FONT_DATA, DEFAULT_ITALIC_FONT_DATA};
+
+use crate::model::{
+    PdfBinaryDataDescription, PdfColorDescription, PdfDescription, PdfElementDescription,
+    PdfPageDescription, PdfPathDescription, PdfPoint, PdfTextDescription, TextAlignmentDescription,
+};
+
+
+const REGULAR_FONT: &str = "regular";
+const ITALIC_FONT: &str = "italic";
+
+const PAGE_WIDTH_MM: f32 = 210.0;
+const PAGE_HEIGHT_MM: f32 = 297.0;
+const MARGIN_MM: f32 = 10.0;
+const ROW_HEIGHT_MM: f32 = 8.0;
+const TITLE_SIZE_PT: f32 = 14.0;
+const TEXT_SIZE_PT: f32 = 10.0;
+
+/// Builds a single-page ride card for `ride`: a title row followed by one row per vehicle,
+/// shading the row whose most recently highlighted ride belongs to the rider who registered
+/// this ride (reusing [`RideTableVehicle::belongs_to_rider_highlighted`]).
+pub fn ride_table_to_pdf(ride: &RideTableData) -> PdfDescription {
+    let mut fonts = HashMap::new();
+    fonts.insert(REGULAR_FONT.to_owned(), PdfBinaryDataDescription(DEFAULT_FONT_DATA.to_vec()));
+    fonts.insert(ITALIC_FONT.to_owned(), PdfBinaryDataDescription(DEFAULT_ITALIC_FONT_DATA.to_vec()));
+
+    let mut elements = Vec::new();
+    let mut y = MARGIN_MM;
+
+    elements.push(text_element(MARGIN_MM, y, REGULAR_FONT, TITLE_SIZE_PT, format!("Ride #{}", ride.ride_id)));
+    y += ROW_HEIGHT_MM;
+
+    if let Some(line) = &ride.line {
+        elements.push(text_element(MARGIN_MM, y, REGULAR_FONT, TEXT_SIZE_PT, format!("Line {}", line)));
+        y += ROW_HEIGHT_MM;
+    }
+    y += ROW_HEIGHT_MM / 2.0;
+
+    for vehicle in &ride.vehicles {
+        if vehicle.belongs_to_rider_highlighted() {
+            elements.push(highlight_box(MARGIN_MM, y, PAGE_WIDTH_MM - 2.0 * MARGIN_MM, ROW_HEIGHT_MM));
+        }
+
+        let font = if vehicle.has_changed_hands_highlighted() { ITALIC_FONT } else { REGULAR_FONT };
+
+        let rider_label = match vehicle.last_highlighted_rider() {
+            LastRider::Me => ride.rider_username.clone(),
+            LastRider::SomebodyElse(name) => name.to_owned(),
+            LastRider::Nobody => "nobody yet".to_owned(),
+        };
+
+        let last_ride_text = vehicle.my_highlighted_last()
+            .map(|r| r.stringify(ride.relative_time))
+            .unwrap_or_else(|| "-".to_owned());
+
+        let type_suffix = vehicle.vehicle_type.as_ref()
+            .map(|vt| format!(" ({})", vt))
+            .unwrap_or_default();
+
+        let label = format!(
+            "{}{} \u{2014} {} \u{2014} {}",
+            vehicle.vehicle_number, type_suffix, rider_label, last_ride_text,
+        );
+        elements.push(text_element(MARGIN_MM + 1.0, y + ROW_HEIGHT_MM - 2.0, font, TEXT_SIZE_PT, label));
+
+        y += ROW_HEIGHT_MM;
+    }
+
+    PdfDescription {
+        title: format!("Ride #{}", ride.ride_id),
+        pages: vec![
+            PdfPageDescription {
+                width_mm: PAGE_WIDTH_MM,
+                height_mm: PAGE_HEIGHT_MM,
+                elements,
+            },
+        ],
+        fonts,
+    }
+}
+
+fn text_element(x: f32, y: f32, font: &str, size_pt: f32, text: String) -> PdfElementDescription {
+    PdfElementDescription::Text(PdfTextDescription {
+        x,
+        y,
+        font: font.to_owned(),
+        size_pt,
+        text,
+        alignment: TextAlignmentDescription::Left,
+    })
+}
+
+fn highlight_box(x: f32, y: f32, width_mm: f32, height_mm: f32) -> PdfElementDescription {
+    PdfElementDescription::Path(PdfPathDescription {
+        stroke: None,
+        stroke_width: None,
+        fill: Some(PdfColorDescription::Grayscale { white: 0.85 }),
+        close: true,
+        points: vec![
+            PdfPoint { x, y },
+            PdfPoint { x: x + width_mm, y },
+            PdfPoint { x: x + width_mm, y: y + height_mm },
+            PdfPoint { x, y: y + height_mm },
+        ],
+    })
+}
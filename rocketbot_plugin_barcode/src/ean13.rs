@@ -190,9 +190,133 @@ pub fn encode_ean_13(digits: [Digit; 13]) -> [bool; 95] {
     ret
 }
 
+
+/// The start/end guard pattern (3 areas): a single bar.
+const GUARD_START_END: [bool; 3] = [true, false, true];
+
+/// The center guard pattern (5 areas): two bars bracketing a space.
+const GUARD_CENTER: [bool; 5] = [false, true, false, true, false];
+
+
+/// An error that can occur while decoding an EAN-13 barcode.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodeError {
+    /// The start, center or end guard pattern, or the left-group digit encoding, did not match.
+    GuardMismatch,
+
+    /// A 7-module window did not correspond to any known digit encoding.
+    UnknownSymbol,
+
+    /// The check digit recomputed from the first twelve digits does not match the thirteenth.
+    ChecksumFailure,
+}
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GuardMismatch
+                => write!(f, "guard pattern did not match"),
+            Self::UnknownSymbol
+                => write!(f, "a digit window did not match any known encoding"),
+            Self::ChecksumFailure
+                => write!(f, "recomputed check digit does not match the encoded one"),
+        }
+    }
+}
+impl std::error::Error for DecodeError {}
+
+/// Looks up `window` (a 7-module slice) in `table`, returning the digit it represents if found.
+fn lookup_digit(window: &[bool], table: &[[bool; 7]; 10]) -> Option<Digit> {
+    table.iter()
+        .position(|pattern| window == &pattern[..])
+        .map(|index| Digit::try_from_u8(index as u8).unwrap())
+}
+
+/// Checks the start, center and end guard patterns, then decodes the left group of six digits
+/// (areas 3..=44), recovering the leading (lone) digit from the pattern of L/G encodings used.
+///
+/// This is the part of decoding that a barcode scanned upside-down gets wrong (it reads the
+/// R-group where the L/G-group is expected), so callers retry it on the reversed-and-inverted
+/// array before giving up.
+fn decode_guards_and_left(bars: &[bool; 95]) -> Result<(Digit, [Digit; 6]), DecodeError> {
+    if &bars[0..3] != &GUARD_START_END[..]
+            || &bars[45..50] != &GUARD_CENTER[..]
+            || &bars[92..95] != &GUARD_START_END[..] {
+        return Err(DecodeError::GuardMismatch);
+    }
+
+    let mut left_digits = [Digit::default(); 6];
+    let mut use_g_pattern = [false; 6];
+    for i in 0..6 {
+        let offset = 3 + i * 7;
+        let window = &bars[offset..offset + 7];
+        if let Some(digit) = lookup_digit(window, &L_DIGITS) {
+            left_digits[i] = digit;
+            use_g_pattern[i] = false;
+        } else if let Some(digit) = lookup_digit(window, &G_DIGITS) {
+            left_digits[i] = digit;
+            use_g_pattern[i] = true;
+        } else {
+            return Err(DecodeError::UnknownSymbol);
+        }
+    }
+
+    let first_digit_value = FIRST_DIGIT_USE_G.iter()
+        .position(|row| *row == use_g_pattern)
+        .ok_or(DecodeError::UnknownSymbol)?;
+    let first_digit = Digit::try_from_u8(first_digit_value as u8).unwrap();
+
+    Ok((first_digit, left_digits))
+}
+
+/// Reverses `bars` and inverts each area, as produced by scanning a barcode upside-down.
+fn reverse_and_invert(bars: &[bool; 95]) -> [bool; 95] {
+    let mut reversed = [false; 95];
+    for (dest, &bar) in reversed.iter_mut().zip(bars.iter().rev()) {
+        *dest = !bar;
+    }
+    reversed
+}
+
+/// Decodes an EAN-13 bar code, the inverse of [`encode_ean_13`].
+///
+/// If the guard patterns or the left-group digit encoding don't match, retries once on the
+/// reversed-and-inverted array (see [`decode_guards_and_left`]) before giving up.
+pub fn decode_ean_13(bars: [bool; 95]) -> Result<[Digit; 13], DecodeError> {
+    let (actual_bars, first_digit, left_digits) = match decode_guards_and_left(&bars) {
+        Ok((first_digit, left_digits)) => (bars, first_digit, left_digits),
+        Err(_) => {
+            let flipped = reverse_and_invert(&bars);
+            let (first_digit, left_digits) = decode_guards_and_left(&flipped)?;
+            (flipped, first_digit, left_digits)
+        },
+    };
+
+    // decode right section
+    let mut right_digits = [Digit::default(); 6];
+    for (i, right_digit) in right_digits.iter_mut().enumerate() {
+        let offset = 50 + i * 7;
+        let window = &actual_bars[offset..offset + 7];
+        *right_digit = lookup_digit(window, &R_DIGITS).ok_or(DecodeError::UnknownSymbol)?;
+    }
+
+    let mut digits = [Digit::default(); 13];
+    digits[0] = first_digit;
+    digits[1..7].copy_from_slice(&left_digits);
+    digits[7..13].copy_from_slice(&right_digits);
+
+    // verify the check digit
+    let mut first_twelve = [Digit::default(); 12];
+    first_twelve.copy_from_slice(&digits[0..12]);
+    if calculate_check_digit(first_twelve) != digits[12] {
+        return Err(DecodeError::ChecksumFailure);
+    }
+
+    Ok(digits)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{Digit, encode_ean_13};
+    use super::{DecodeError, Digit, decode_ean_13, encode_ean_13};
 
     #[test]
     fn test_encode_ean_13() {
@@ -239,4 +363,53 @@ mod tests {
 
         assert_eq!(encoded, raw_expected);
     }
+
+    #[test]
+    fn test_decode_ean_13() {
+        // Wikipedia example
+        let raw_digits: [u8; 13] = [
+            4,
+            0, 0, 3, 9, 9, 4,
+            1, 5, 5, 4, 8, 6,
+        ];
+        let mut digits = [Digit::default(); 13];
+        for (digit, raw_digit) in digits.iter_mut().zip(raw_digits.iter()) {
+            *digit = Digit::try_from_u8(*raw_digit).unwrap();
+        }
+
+        let encoded = encode_ean_13(digits);
+        let decoded = decode_ean_13(encoded).unwrap();
+        assert_eq!(decoded, digits);
+
+        // scanned upside-down, it is reversed and every bar/space is flipped
+        let mut flipped = encoded;
+        flipped.reverse();
+        for bar in flipped.iter_mut() {
+            *bar = !*bar;
+        }
+        let decoded_flipped = decode_ean_13(flipped).unwrap();
+        assert_eq!(decoded_flipped, digits);
+    }
+
+    #[test]
+    fn test_decode_ean_13_checksum_failure() {
+        let raw_digits: [u8; 13] = [
+            4,
+            0, 0, 3, 9, 9, 4,
+            1, 5, 5, 4, 8, 7, // wrong check digit (should be 6)
+        ];
+        let mut digits = [Digit::default(); 13];
+        for (digit, raw_digit) in digits.iter_mut().zip(raw_digits.iter()) {
+            *digit = Digit::try_from_u8(*raw_digit).unwrap();
+        }
+
+        let encoded = encode_ean_13(digits);
+        assert_eq!(decode_ean_13(encoded), Err(DecodeError::ChecksumFailure));
+    }
+
+    #[test]
+    fn test_decode_ean_13_guard_mismatch() {
+        let bars = [false; 95];
+        assert_eq!(decode_ean_13(bars), Err(DecodeError::GuardMismatch));
+    }
 }
@@ -3,15 +3,81 @@
 
 use std::collections::BTreeMap;
 use std::env::args_os;
+use std::fmt;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
+use reqwest::header;
 use rocketbot_bim_common::LineOperatorInfo;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::Error as _;
+use sha2::{Digest, Sha256};
 use sxd_document;
 use sxd_document::dom::Element;
+use unicode_normalization::char::{decompose_compatible, is_combining_mark};
+
+
+#[derive(Debug)]
+enum FetchError {
+    Http(reqwest::Error),
+    XmlParse { page: String, parse_error: sxd_document::parser::Error },
+    MissingElement { what: &'static str, page: String },
+    NoTable { page: String },
+    NoLineColumn { page: String },
+}
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(e)
+                => write!(f, "HTTP error: {}", e),
+            Self::XmlParse { page, parse_error }
+                => write!(f, "failed to parse page {:?} as XML: {}", page, parse_error),
+            Self::MissingElement { what, page }
+                => write!(f, "page {:?} is missing its {} element", page, what),
+            Self::NoTable { page }
+                => write!(f, "no table element found in any matching section of page {:?}", page),
+            Self::NoLineColumn { page }
+                => write!(f, "page {:?}'s table has no heading row identifying the line column", page),
+        }
+    }
+}
+impl std::error::Error for FetchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::XmlParse { parse_error, .. } => Some(parse_error),
+            Self::MissingElement { .. } => None,
+            Self::NoTable { .. } => None,
+            Self::NoLineColumn { .. } => None,
+        }
+    }
+}
+
+
+/// The file format of the configuration file, detected from its extension or an explicit
+/// `--format` flag.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ConfigFormat {
+    Json,
+    Toml,
+}
+impl ConfigFormat {
+    fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            _ => None,
+        }
+    }
+
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::Toml,
+            _ => Self::Json,
+        }
+    }
+}
 
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -28,12 +94,22 @@ struct PageSource {
     pub operator_name_to_abbrev: BTreeMap<String, String>,
 }
 
+/// The field name (within [`PageConfig::fields`]) that supplies [`LineOperatorInfo::canonical_line`].
+const LINE_FIELD: &str = "line";
+
+/// The field name (within [`PageConfig::fields`]) that supplies [`LineOperatorInfo::operator_name`].
+const OPERATOR_FIELD: &str = "operator";
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 pub(crate) struct PageConfig {
     pub title: String,
     pub region: String,
-    pub line_column: String,
-    pub operator_spec: Spec,
+
+    /// Maps output field names to the table column (or fixed string) they are read from.
+    /// [`LINE_FIELD`] and [`OPERATOR_FIELD`] feed the well-known `LineOperatorInfo` fields; any
+    /// other key is emitted alongside them as an extra field.
+    pub fields: BTreeMap<String, Spec>,
+
     pub section: Option<String>,
 }
 
@@ -145,23 +221,96 @@ impl<'d> ElementExt<'d> for sxd_document::dom::Element<'d> {
 }
 
 
+/// A [`LineOperatorInfo`] plus whatever additional fields a page's [`PageConfig::fields`]
+/// requested beyond [`LINE_FIELD`] and [`OPERATOR_FIELD`], flattened into the same JSON object.
+#[derive(Clone, Debug, Serialize)]
+struct LineOperatorInfoWithExtra {
+    #[serde(flatten)]
+    info: LineOperatorInfo,
+
+    #[serde(flatten)]
+    extra: BTreeMap<String, String>,
+}
+
+
+fn sha256_hexdigest(data: &[u8]) -> String {
+    let mut sha256 = Sha256::new();
+    sha256.update(data);
+    let digest = sha256.finalize();
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+
+/// A cached page body plus the validators needed to conditionally re-fetch it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CacheEntry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+
 async fn obtain_xhtml(
     reqwest_client: &mut Client,
     url: &str,
     authorization_token: Option<&str>,
-) -> String {
+    cache_dir: Option<&Path>,
+) -> Result<String, FetchError> {
     eprintln!("fetching URL {:?}", url);
+
+    let cache_path = cache_dir
+        .map(|dir| dir.join(format!("{}.json", sha256_hexdigest(url.as_bytes()))));
+    let cached_entry: Option<CacheEntry> = cache_path.as_ref().and_then(|path| {
+        let file = File::open(path).ok()?;
+        serde_json::from_reader(file).ok()
+    });
+
     let mut builder = reqwest_client.get(url);
     if let Some(token) = authorization_token {
         builder = builder.bearer_auth(token);
     }
-    let page_html_bytes = builder
-        .send().await.expect("sending request failed")
-        .error_for_status().expect("response is an error")
-        .bytes().await.expect("obtaining response bytes failed");
+    if let Some(entry) = &cached_entry {
+        if let Some(etag) = &entry.etag {
+            builder = builder.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            builder = builder.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = builder.send().await.map_err(FetchError::Http)?;
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(entry) = cached_entry {
+            eprintln!("not modified, reusing cached body for {:?}", url);
+            return Ok(entry.body);
+        }
+    }
+
+    let response = response.error_for_status().map_err(FetchError::Http)?;
+    let etag = response.headers().get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_owned());
+    let last_modified = response.headers().get(header::LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_owned());
+
+    let page_html_bytes = response.bytes().await.map_err(FetchError::Http)?;
     let page_html_string = String::from_utf8(page_html_bytes.to_vec())
         .expect("article is not UTF-8");
-    page_html_string
+
+    if let Some(path) = &cache_path {
+        let entry = CacheEntry {
+            etag,
+            last_modified,
+            body: page_html_string.clone(),
+        };
+        if let Ok(file) = File::create(path) {
+            let _ = serde_json::to_writer(file, &entry);
+        }
+    }
+
+    Ok(page_html_string)
 }
 
 
@@ -313,9 +462,9 @@ impl Table {
 }
 
 
-fn reduce_table(table: Element) -> Vec<Vec<String>> {
+fn reduce_table(table: Element, page: &str) -> Result<Vec<Vec<String>>, FetchError> {
     let tbody = table.first_child_element_named("tbody")
-        .expect("no tbody element");
+        .ok_or_else(|| FetchError::MissingElement { what: "tbody", page: page.to_owned() })?;
     let rows = tbody.child_elements_named("tr");
     let mut ret = Table::new();
 
@@ -362,7 +511,145 @@ fn reduce_table(table: Element) -> Vec<Vec<String>> {
         }
     }
 
-    ret.into_rows_some()
+    Ok(ret.into_rows_some())
+}
+
+
+/// Decomposes `s` into NFKD form and strips combining marks, so that diacritics no longer affect
+/// comparison (e.g. "Wien" and "Wién" become the same string).
+fn unicode_compatible_without_combining(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        decompose_compatible(c, |dc| {
+            if !is_combining_mark(dc) {
+                ret.push(dc);
+            }
+        })
+    }
+    ret
+}
+
+/// Normalizes an operator name for fuzzy comparison: case-, diacritic- and
+/// internal-whitespace-insensitive.
+fn normalize_operator_name(name: &str) -> String {
+    let uncombined_lowercase = unicode_compatible_without_combining(name).to_lowercase();
+    uncombined_lowercase.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The Levenshtein edit distance between `a` and `b`, operating on `char`s rather than bytes so
+/// that multi-byte UTF-8 sequences count as a single edit.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+
+    let mut previous_row: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, &a_char) in a_chars.iter().enumerate() {
+        let mut current_row = vec![i + 1];
+        for (j, &b_char) in b_chars.iter().enumerate() {
+            let substitution_cost = if a_char == b_char { 0 } else { 1 };
+            let value = (previous_row[j + 1] + 1)
+                .min(current_row[j] + 1)
+                .min(previous_row[j] + substitution_cost);
+            current_row.push(value);
+        }
+        previous_row = current_row;
+    }
+
+    previous_row[b_chars.len()]
+}
+
+/// The maximum Levenshtein distance (on normalized names) still considered a typo rather than a
+/// different operator, scaled by name length (roughly MeiliSearch's typo budget).
+fn typo_budget(char_count: usize) -> usize {
+    if char_count <= 4 {
+        0
+    } else if char_count <= 8 {
+        1
+    } else {
+        2
+    }
+}
+
+
+/// Collects operator-name-matching decisions across a whole run, so that a summary can be
+/// reported to stderr once all pages have been processed instead of interleaving it with the
+/// per-page fetch progress.
+#[derive(Default)]
+struct OperatorMatchLog {
+    fuzzy_matches: Vec<(String, String)>,
+    unmatched: Vec<String>,
+}
+impl OperatorMatchLog {
+    fn print_summary(&self) {
+        if !self.fuzzy_matches.is_empty() {
+            eprintln!("operators matched only fuzzily:");
+            for (scraped, key) in &self.fuzzy_matches {
+                eprintln!("  {:?} -> {:?}", scraped, key);
+            }
+        }
+        if !self.unmatched.is_empty() {
+            eprintln!("operators with no abbreviation match:");
+            for scraped in &self.unmatched {
+                eprintln!("  {:?}", scraped);
+            }
+        }
+    }
+}
+
+/// Looks up `operator_name`'s abbreviation in `operator_name_to_abbrev`, falling back from an
+/// exact match to a normalized-exact match to the best Levenshtein-fuzzy match within
+/// [`typo_budget`]. A tie among equally-close fuzzy candidates is treated as ambiguous (`None`)
+/// rather than guessed at. Every non-exact outcome is recorded in `log`.
+fn resolve_operator_abbrev(
+    operator_name: &str,
+    operator_name_to_abbrev: &BTreeMap<String, String>,
+    log: &mut OperatorMatchLog,
+) -> Option<String> {
+    if let Some(abbrev) = operator_name_to_abbrev.get(operator_name) {
+        return Some(abbrev.clone());
+    }
+
+    let normalized_name = normalize_operator_name(operator_name);
+
+    for (key, abbrev) in operator_name_to_abbrev {
+        if normalize_operator_name(key) == normalized_name {
+            log.fuzzy_matches.push((operator_name.to_owned(), key.clone()));
+            return Some(abbrev.clone());
+        }
+    }
+
+    let budget = typo_budget(normalized_name.chars().count());
+    let mut best: Option<(&str, &str, usize)> = None;
+    let mut tied = false;
+    for (key, abbrev) in operator_name_to_abbrev {
+        let distance = levenshtein_distance(&normalized_name, &normalize_operator_name(key));
+        if distance > budget {
+            continue;
+        }
+
+        match best {
+            None => best = Some((key, abbrev, distance)),
+            Some((_, _, best_distance)) => {
+                if distance < best_distance {
+                    best = Some((key, abbrev, distance));
+                    tied = false;
+                } else if distance == best_distance {
+                    tied = true;
+                }
+            },
+        }
+    }
+
+    match best {
+        Some((key, abbrev, _)) if !tied => {
+            log.fuzzy_matches.push((operator_name.to_owned(), key.to_owned()));
+            Some(abbrev.to_owned())
+        },
+        _ => {
+            log.unmatched.push(operator_name.to_owned());
+            None
+        },
+    }
 }
 
 
@@ -372,20 +659,24 @@ async fn process_page(
     page_config: &PageConfig,
     operator_name_to_abbrev: &BTreeMap<String, String>,
     authorization_token: Option<&str>,
-) -> BTreeMap<String, BTreeMap<String, LineOperatorInfo>> {
+    operator_match_log: &mut OperatorMatchLog,
+    cache_dir: Option<&Path>,
+) -> Result<BTreeMap<String, BTreeMap<String, LineOperatorInfoWithExtra>>, FetchError> {
     // return value is: region -> line -> operator_info
 
     let url = url_pattern.replace("{TITLE}", &page_config.title);
 
-    let page_xml_notag = obtain_xhtml(reqwest_client, &url, authorization_token).await;
+    let page_xml_notag = obtain_xhtml(reqwest_client, &url, authorization_token, cache_dir).await?;
     let page_xml = format!("<?xml version=\"1.0\"?>{}", page_xml_notag);
 
     let page_package = sxd_document::parser::parse(&page_xml)
-        .expect("parsing XML failed");
+        .map_err(|parse_error| FetchError::XmlParse { page: page_config.title.clone(), parse_error })?;
     let page = page_package.as_document();
 
-    let html = page.document_element().expect("no document element");
-    let body = html.first_child_element_named("body").expect("no body element");
+    let html = page.document_element()
+        .ok_or_else(|| FetchError::MissingElement { what: "document", page: page_config.title.clone() })?;
+    let body = html.first_child_element_named("body")
+        .ok_or_else(|| FetchError::MissingElement { what: "body", page: page_config.title.clone() })?;
 
     let mut sections = body.child_elements_named("section");
     if let Some(section_name) = page_config.section.as_ref() {
@@ -417,64 +708,69 @@ async fn process_page(
         .into_iter()
         .flat_map(|section| section.child_elements_named("table"))
         .nth(0)
-        .expect("no table element in any section");
-    let reduced_table = reduce_table(table);
-
-    let mut line_column_index_opt = None;
-    let mut operator_column_index_opt = None;
+        .ok_or_else(|| FetchError::NoTable { page: page_config.title.clone() })?;
+    let reduced_table = reduce_table(table, &page_config.title)?;
 
+    let mut field_to_column_index: BTreeMap<String, usize> = BTreeMap::new();
     let mut ret = BTreeMap::new();
 
     for (r, row) in reduced_table.iter().enumerate() {
-        let mut line_opt = None;
-        let mut operator_opt = None;
-        for (c, first_text) in row.iter().enumerate() {
-            if r == 0 {
-                // heading row
-                if first_text == &page_config.line_column {
-                    line_column_index_opt = Some(c);
-                }
-                if let Spec::Column(operator_column) = &page_config.operator_spec {
-                    if first_text == operator_column {
-                        operator_column_index_opt = Some(c);
+        if r == 0 {
+            // heading row: resolve each column-backed field to its column index
+            for (c, first_text) in row.iter().enumerate() {
+                for (field_name, spec) in &page_config.fields {
+                    if let Spec::Column(column_name) = spec {
+                        if first_text == column_name {
+                            field_to_column_index.insert(field_name.clone(), c);
+                        }
                     }
                 }
-            } else {
-                // data row
-                let line_column_index = line_column_index_opt
-                    .expect("no line column index known");
+            }
 
-                if c == line_column_index {
-                    line_opt = Some(first_text.clone());
-                }
-                if let Some(operator_column_index) = operator_column_index_opt {
-                    if c == operator_column_index {
-                        operator_opt = Some(first_text.clone());
-                    }
-                }
+            let line_field_is_column = match page_config.fields.get(LINE_FIELD) {
+                Some(Spec::Column(_)) | None => true,
+                Some(Spec::Fixed(_)) => false,
+            };
+            if line_field_is_column && !field_to_column_index.contains_key(LINE_FIELD) {
+                return Err(FetchError::NoLineColumn { page: page_config.title.clone() });
             }
+
+            continue;
         }
 
-        if let Spec::Fixed(operator_fixed) = &page_config.operator_spec {
-            operator_opt = Some(operator_fixed.clone());
+        // data row: resolve every configured field, column-backed or fixed
+        let mut field_values: BTreeMap<String, String> = BTreeMap::new();
+        for (field_name, spec) in &page_config.fields {
+            match spec {
+                Spec::Fixed(fixed_value) => {
+                    field_values.insert(field_name.clone(), fixed_value.clone());
+                },
+                Spec::Column(_) => {
+                    if let Some(&column_index) = field_to_column_index.get(field_name) {
+                        if let Some(value) = row.get(column_index) {
+                            field_values.insert(field_name.clone(), value.clone());
+                        }
+                    }
+                },
+            }
         }
 
-        let line = match line_opt {
+        let line = match field_values.remove(LINE_FIELD) {
             Some(l) => l,
             None => continue,
         };
-        let operator_name = match operator_opt {
+        let operator_name = match field_values.remove(OPERATOR_FIELD) {
             Some(o) => o,
             None => continue,
         };
-        let operator_abbrev = operator_name_to_abbrev
-            .get(&operator_name)
-            .map(|oa| oa.clone());
-        let operator_info = LineOperatorInfo {
+        let operator_abbrev = resolve_operator_abbrev(&operator_name, operator_name_to_abbrev, operator_match_log);
+        let info = LineOperatorInfo {
             canonical_line: line.clone(),
             operator_name,
             operator_abbrev,
+            regular_type: None,
         };
+        let operator_info = LineOperatorInfoWithExtra { info, extra: field_values };
 
         ret
             .entry(page_config.region.clone())
@@ -482,37 +778,83 @@ async fn process_page(
             .insert(line.to_lowercase(), operator_info);
     }
 
-    ret
+    Ok(ret)
 }
 
 
 #[tokio::main]
 async fn main() {
+    let mut config_path = PathBuf::from("fetch_ops_mw.json");
+    let mut format_override = None;
+    let mut cache_dir: Option<PathBuf> = None;
+    let mut positional_seen = false;
+
+    let mut args_iter = args_os().skip(1);
+    while let Some(arg) = args_iter.next() {
+        if arg == "--format" {
+            let value = args_iter.next()
+                .expect("--format requires a value");
+            let value_str = value.to_string_lossy();
+            format_override = Some(
+                ConfigFormat::from_str(&value_str)
+                    .expect("unknown format (known: \"json\", \"toml\")")
+            );
+        } else if arg == "--cache-dir" {
+            let value = args_iter.next()
+                .expect("--cache-dir requires a value");
+            cache_dir = Some(PathBuf::from(value));
+        } else if !positional_seen {
+            config_path = PathBuf::from(arg);
+            positional_seen = true;
+        }
+    }
+
+    if let Some(dir) = &cache_dir {
+        std::fs::create_dir_all(dir)
+            .expect("failed to create cache directory");
+    }
+
     // load config
     let config: Config = {
-        let config_path = match args_os().nth(1) {
-            Some(cp) => PathBuf::from(cp),
-            None => PathBuf::from("fetch_ops_mw.json"),
-        };
-        let f = File::open(config_path)
-            .expect("failed to open config file");
-        serde_json::from_reader(f)
-            .expect("failed to parse config file")
+        let format = format_override.unwrap_or_else(|| ConfigFormat::from_path(&config_path));
+        match format {
+            ConfigFormat::Json => {
+                let f = File::open(&config_path)
+                    .expect("failed to open config file");
+                serde_json::from_reader(f)
+                    .expect("failed to parse config file")
+            },
+            ConfigFormat::Toml => {
+                let contents = std::fs::read_to_string(&config_path)
+                    .expect("failed to read config file");
+                toml::from_str(&contents)
+                    .expect("failed to parse config file")
+            },
+        }
     };
 
     let mut region_to_line_to_operator = BTreeMap::new();
+    let mut operator_match_log = OperatorMatchLog::default();
 
     let mut reqwest_client = reqwest::Client::new();
 
     for page_source in &config.page_sources {
         for page in &page_source.pages {
-            let this_region_to_line_to_operator = process_page(
+            let this_region_to_line_to_operator = match process_page(
                 &mut reqwest_client,
                 &page_source.page_url_pattern,
                 &page,
                 &page_source.operator_name_to_abbrev,
                 page_source.authorization_token.as_deref(),
-            ).await;
+                &mut operator_match_log,
+                cache_dir.as_deref(),
+            ).await {
+                Ok(rtlto) => rtlto,
+                Err(e) => {
+                    eprintln!("skipping page {:?}: {}", page.title, e);
+                    continue;
+                },
+            };
             for (this_region, this_line_to_operator) in this_region_to_line_to_operator {
                 let line_to_operator = region_to_line_to_operator
                     .entry(this_region)
@@ -524,6 +866,8 @@ async fn main() {
         }
     }
 
+    operator_match_log.print_summary();
+
     // output
     {
         let f = File::create(config.output_path)
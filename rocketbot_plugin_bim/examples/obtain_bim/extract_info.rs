@@ -1,51 +1,317 @@
-use std::io::Read;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufReader, Read};
 use std::fs::File;
+use std::iter::FusedIterator;
+use std::time::Duration;
 
+use flate2::read::{GzDecoder, ZlibDecoder};
 use form_urlencoded;
 use indexmap::IndexSet;
+use quick_xml::events::Event;
+use quick_xml::Reader as XmlReader;
+use rand::{Rng, thread_rng};
 use regex::Regex;
 use rocketbot_plugin_bim::{VehicleInfo, VehicleNumber};
 use sxd_document;
 use sxd_document::dom::Element;
 use sxd_xpath::{self, XPath};
 
-use crate::{MatcherTransformerConfig, PageConfig};
+use crate::{FetchConfig, MatcherTransformerConfig, PageConfig};
 use crate::wiki_parsing::WikiParser;
 
 
-async fn obtain_content(page_url_pattern: &str, page_title: &str) -> String {
+/// An error occurring while scraping vehicle data from a wiki page.
+///
+/// Collecting these as values (instead of panicking via `.expect()`) allows the caller to skip
+/// and log a single broken page instead of aborting the whole database refresh.
+#[derive(Debug)]
+pub(crate) enum ScrapeError {
+    Io(io::Error),
+    Http(reqwest::Error),
+    Decode(std::string::FromUtf8Error),
+    JsonParse(serde_json::Error),
+    JsonShape { field: String },
+    XPathBuild { expr: String },
+    XPathEval { expr: String },
+    XmlParse,
+    WikitextParse { title: String },
+    TooManyRetries { url: String, attempts: u32, status: reqwest::StatusCode },
+    XmlDump(quick_xml::Error),
+}
+impl fmt::Display for ScrapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e)
+                => write!(f, "input/output error: {}", e),
+            Self::Http(e)
+                => write!(f, "HTTP error: {}", e),
+            Self::Decode(e)
+                => write!(f, "failed to decode page content as UTF-8: {}", e),
+            Self::JsonParse(e)
+                => write!(f, "failed to parse page JSON: {}", e),
+            Self::JsonShape { field }
+                => write!(f, "page JSON has unexpected shape at {:?}", field),
+            Self::XPathBuild { expr }
+                => write!(f, "failed to compile XPath expression {:?}", expr),
+            Self::XPathEval { expr }
+                => write!(f, "failed to evaluate XPath expression {:?}", expr),
+            Self::XmlParse
+                => write!(f, "failed to parse processed wikitext as XML"),
+            Self::WikitextParse { title }
+                => write!(f, "failed to parse wikitext of page {:?}", title),
+            Self::TooManyRetries { url, attempts, status }
+                => write!(f, "giving up on {:?} after {} attempt(s); last status was {}", url, attempts, status),
+            Self::XmlDump(e)
+                => write!(f, "failed to read MediaWiki XML dump: {}", e),
+        }
+    }
+}
+impl std::error::Error for ScrapeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Http(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            Self::JsonParse(e) => Some(e),
+            Self::JsonShape { .. } => None,
+            Self::XPathBuild { .. } => None,
+            Self::XPathEval { .. } => None,
+            Self::XmlParse => None,
+            Self::WikitextParse { .. } => None,
+            Self::TooManyRetries { .. } => None,
+            Self::XmlDump(e) => Some(e),
+        }
+    }
+}
+impl From<io::Error> for ScrapeError {
+    fn from(e: io::Error) -> Self { Self::Io(e) }
+}
+impl From<reqwest::Error> for ScrapeError {
+    fn from(e: reqwest::Error) -> Self { Self::Http(e) }
+}
+impl From<std::string::FromUtf8Error> for ScrapeError {
+    fn from(e: std::string::FromUtf8Error) -> Self { Self::Decode(e) }
+}
+impl From<quick_xml::Error> for ScrapeError {
+    fn from(e: quick_xml::Error) -> Self { Self::XmlDump(e) }
+}
+
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns whether `bytes` starts with a valid zlib stream header (RFC 1950: a CMF byte whose low
+/// nibble is the "deflate" compression method, forming a 16-bit big-endian value divisible by 31).
+fn looks_like_zlib(bytes: &[u8]) -> bool {
+    if bytes.len() < 2 {
+        return false;
+    }
+    let cmf = bytes[0];
+    let flg = bytes[1];
+    (cmf & 0x0f) == 8 && (((cmf as u16) << 8) | (flg as u16)) % 31 == 0
+}
+
+/// Decompresses `bytes` if they are gzip- or zlib-compressed, sniffed from the magic bytes or
+/// forced by a `.gz`/`.zz` suffix on `path_hint`; otherwise returns them unchanged.
+fn decompress_if_needed(bytes: Vec<u8>, path_hint: Option<&str>) -> Result<Vec<u8>, ScrapeError> {
+    let forced_gzip = path_hint.map(|p| p.ends_with(".gz")).unwrap_or(false);
+    let forced_zlib = path_hint.map(|p| p.ends_with(".zz")).unwrap_or(false);
+
+    let mut decompressed = Vec::new();
+    if forced_gzip || bytes.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+        Ok(decompressed)
+    } else if forced_zlib || looks_like_zlib(&bytes) {
+        ZlibDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+        Ok(decompressed)
+    } else {
+        Ok(bytes)
+    }
+}
+
+
+/// Returns how long to wait before the `attempt`th retry (1-based), honoring a `Retry-After`
+/// header if the server provided one, falling back to exponential backoff with jitter otherwise.
+fn retry_delay(fetch_config: &FetchConfig, attempt: u32, retry_after: Option<Duration>) -> Duration {
+    if let Some(ra) = retry_after {
+        return ra;
+    }
+
+    let exponential_ms = fetch_config.base_delay_ms.saturating_mul(1u64 << (attempt - 1));
+    let jittered_ms = thread_rng().gen_range(exponential_ms / 2..=exponential_ms);
+    Duration::from_millis(jittered_ms)
+}
+
+/// Parses a `Retry-After` header value, which is either a number of seconds or an HTTP date. Only
+/// the former is supported; the latter is rare enough in practice that falling back to the
+/// regular backoff delay is an acceptable trade-off.
+fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?;
+    let seconds: u64 = value.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+async fn obtain_content(client: &reqwest::Client, fetch_config: &FetchConfig, page_url_pattern: &str, page_title: &str) -> Result<String, ScrapeError> {
     if page_url_pattern.starts_with("file://") {
         let page_title_no_slashes = page_title.replace("/", "");
         let path = page_url_pattern
             .strip_prefix("file://").unwrap()
             .replace("{TITLE}", &page_title_no_slashes);
-        let mut f = File::open(path)
-            .expect("failed to open file");
+        let mut f = File::open(&path)?;
         let mut bytes = Vec::new();
-        f.read_to_end(&mut bytes)
-            .expect("failed to read bytes");
-        String::from_utf8(bytes)
-            .expect("failed to decode as UTF-8")
+        f.read_to_end(&mut bytes)?;
+        let bytes = decompress_if_needed(bytes, Some(&path))?;
+        let content = String::from_utf8(bytes)?;
+        Ok(content)
     } else {
         let page_title_encoded: String = form_urlencoded::byte_serialize(page_title.as_bytes())
             .collect();
         let url = page_url_pattern.replace("{TITLE}", &page_title_encoded);
-        let response = reqwest::get(url).await
-            .expect("failed to obtain response");
-        let response_bytes = response.bytes().await
-            .expect("failed to obtain response bytes");
-        let response_bytes_vec = response_bytes.to_vec();
-        String::from_utf8(response_bytes_vec)
-            .expect("failed to decode response as UTF-8")
+
+        let max_attempts = fetch_config.max_attempts.max(1);
+        for attempt in 1..=max_attempts {
+            let response = client
+                .get(&url)
+                .header("Accept-Encoding", "gzip, deflate")
+                .send().await?;
+            let status = response.status();
+
+            if status.is_success() {
+                let response_bytes = response.bytes().await?;
+                let bytes = decompress_if_needed(response_bytes.to_vec(), None)?;
+                let content = String::from_utf8(bytes)?;
+                return Ok(content);
+            }
+
+            if attempt == max_attempts || !fetch_config.retryable_status_codes.contains(&status.as_u16()) {
+                return Err(ScrapeError::TooManyRetries { url, attempts: attempt, status });
+            }
+
+            let retry_after = parse_retry_after(&response);
+            let delay = retry_delay(fetch_config, attempt, retry_after);
+            eprintln!("  request for {:?} failed with status {}; retrying in {:?}", page_title, status, delay);
+            tokio::time::sleep(delay).await;
+        }
+
+        // the loop above always returns on its last iteration (attempt == max_attempts)
+        unreachable!()
     }
 }
 
 
-fn compile_xpath(factory: &sxd_xpath::Factory, xpath_str: &str) -> XPath {
+fn compile_xpath(factory: &sxd_xpath::Factory, xpath_str: &str) -> Result<XPath, ScrapeError> {
     factory.build(xpath_str)
-        .expect("failed to parse XPath")
-        .expect("XPath is None")
+        .map_err(|_| ScrapeError::XPathBuild { expr: xpath_str.to_owned() })?
+        .ok_or_else(|| ScrapeError::XPathBuild { expr: xpath_str.to_owned() })
+}
+
+
+/// Which element [`MwDumpPages`] is currently collecting character data for.
+enum DumpCapture {
+    Title,
+    Text,
+}
+
+/// Streams `(title, wikitext)` pairs out of a MediaWiki `pages-articles` XML export (as produced
+/// by `dumpBackup.php`/`mwdumper`), reading it in a single forward pass so the whole multi-gigabyte
+/// export never needs to live in memory at once. Works over anything implementing [`Read`] -- a
+/// plain [`File`] or a [`GzDecoder`] wrapping one -- so both uncompressed and gzip-compressed
+/// (`.xml.gz`) dumps can be iterated identically.
+///
+/// Pages without both a `<title>` and revision `<text>` (e.g. a dump entry for a deleted revision)
+/// are silently skipped, exactly like a page absent from the dump entirely.
+pub(crate) struct MwDumpPages<R: Read> {
+    reader: XmlReader<BufReader<R>>,
+    buf: Vec<u8>,
+    done: bool,
+}
+impl<R: Read> MwDumpPages<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        let reader = XmlReader::from_reader(BufReader::new(inner));
+        Self {
+            reader,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
 }
+impl<R: Read> Iterator for MwDumpPages<R> {
+    type Item = Result<(String, String), ScrapeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // each loop iteration processes (and usually discards) one <page>; only a page with both
+        // a title and revision text yields an item, so we keep going until one does (or EOF)
+        'pages: loop {
+            if self.done {
+                return None;
+            }
+
+            let mut in_page = false;
+            let mut in_revision = false;
+            let mut capture = None;
+            let mut title = None;
+            let mut text = None;
+
+            loop {
+                self.buf.clear();
+                let event = match self.reader.read_event_into(&mut self.buf) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        self.done = true;
+                        return Some(Err(e.into()));
+                    },
+                };
+
+                match event {
+                    Event::Eof => {
+                        self.done = true;
+                        return None;
+                    },
+                    Event::Start(e) => {
+                        match e.name().as_ref() {
+                            b"page" => in_page = true,
+                            b"revision" => in_revision = true,
+                            b"title" if in_page && !in_revision => capture = Some(DumpCapture::Title),
+                            b"text" if in_revision => capture = Some(DumpCapture::Text),
+                            _ => {},
+                        }
+                    },
+                    Event::Text(e) => {
+                        let decoded = match e.unescape() {
+                            Ok(d) => d.into_owned(),
+                            Err(err) => {
+                                self.done = true;
+                                return Some(Err(err.into()));
+                            },
+                        };
+                        match capture {
+                            Some(DumpCapture::Title) => title = Some(decoded),
+                            Some(DumpCapture::Text) => text = Some(decoded),
+                            None => {},
+                        }
+                    },
+                    Event::End(e) => {
+                        match e.name().as_ref() {
+                            b"title" | b"text" => capture = None,
+                            b"revision" => in_revision = false,
+                            b"page" => {
+                                in_page = false;
+                                if let (Some(t), Some(x)) = (title, text) {
+                                    return Some(Ok((t, x)));
+                                }
+                                // no title/revision text (e.g. a deleted revision): skip this page
+                                continue 'pages;
+                            },
+                            _ => {},
+                        }
+                    },
+                    _ => {},
+                }
+            }
+        }
+    }
+}
+impl<R: Read> FusedIterator for MwDumpPages<R> {}
 
 
 fn value_match(matcher: &MatcherTransformerConfig, key: &str, value: &str) -> Option<String> {
@@ -174,19 +440,19 @@ pub(crate) fn row_data_to_trams(page_config: &PageConfig, row_data: Vec<(String,
 }
 
 
-pub(crate) fn process_table<F>(vehicles: &mut Vec<VehicleInfo>, table: Element, page_config: &PageConfig, mut row_data_to_vehicles: F)
+pub(crate) fn process_table<F>(vehicles: &mut Vec<VehicleInfo>, table: Element, page_config: &PageConfig, mut row_data_to_vehicles: F) -> Result<(), ScrapeError>
     where F: FnMut(&PageConfig, Vec<(String, String)>) -> Vec<VehicleInfo>
 {
     let xpath_factory = sxd_xpath::Factory::new();
-    let table_head_xpath = compile_xpath(&xpath_factory, ".//th");
-    let table_row_xpath = compile_xpath(&xpath_factory, ".//tr");
-    let table_data_xpath = compile_xpath(&xpath_factory, ".//td");
+    let table_head_xpath = compile_xpath(&xpath_factory, ".//th")?;
+    let table_row_xpath = compile_xpath(&xpath_factory, ".//tr")?;
+    let table_data_xpath = compile_xpath(&xpath_factory, ".//td")?;
     let context = sxd_xpath::Context::new();
 
     // find table headers
     let mut keys = Vec::new();
     let heads_value = table_head_xpath.evaluate(&context, table)
-        .expect("failed to execute table head XPath");
+        .map_err(|_| ScrapeError::XPathEval { expr: ".//th".to_owned() })?;
     if let sxd_xpath::Value::Nodeset(heads) = heads_value {
         for head in heads.document_order() {
             keys.push(head.string_value());
@@ -195,14 +461,14 @@ pub(crate) fn process_table<F>(vehicles: &mut Vec<VehicleInfo>, table: Element,
 
     // find table rows
     let rows_value = table_row_xpath.evaluate(&context, table)
-        .expect("failed to execute table row XPath");
+        .map_err(|_| ScrapeError::XPathEval { expr: ".//tr".to_owned() })?;
     if let sxd_xpath::Value::Nodeset(rows) = rows_value {
         for row in rows.document_order() {
             // find data
             let mut row_data = Vec::new();
 
             let cells_value = table_data_xpath.evaluate(&context, row)
-                .expect("failed to execute table data XPath");
+                .map_err(|_| ScrapeError::XPathEval { expr: ".//td".to_owned() })?;
             if let sxd_xpath::Value::Nodeset(cells) = cells_value {
                 let cells_doc_order = cells.document_order();
                 for (key, cell) in keys.iter().zip(cells_doc_order.iter()) {
@@ -215,53 +481,98 @@ pub(crate) fn process_table<F>(vehicles: &mut Vec<VehicleInfo>, table: Element,
             vehicles.append(&mut these_vehicles);
         }
     }
+
+    Ok(())
 }
 
 
-pub(crate) async fn process_page<F, G>(page_url_pattern: &str, page_config: &PageConfig, parser: &mut WikiParser, mut process_table: F, row_data_to_vehicles: G) -> Vec<VehicleInfo>
+/// Parses `body_wikitext` (the wikitext of the page named `actual_title`) and runs `process_table`
+/// over every table it contains. Shared by [`process_page`] (one page fetched live from the Action
+/// API) and [`process_dump`] (many pages read from an offline XML export), since both ultimately
+/// need to do the same thing with a page's wikitext once they have obtained it.
+fn process_wikitext<F, G>(actual_title: &str, body_wikitext: &str, page_config: &PageConfig, parser: &mut WikiParser, mut process_table: F, row_data_to_vehicles: G) -> Result<Vec<VehicleInfo>, ScrapeError>
     where
-        F : FnMut(&mut Vec<VehicleInfo>, Element, &PageConfig, G),
+        F : FnMut(&mut Vec<VehicleInfo>, Element, &PageConfig, G) -> Result<(), ScrapeError>,
         G : FnMut(&PageConfig, Vec<(String, String)>) -> Vec<VehicleInfo> + Copy,
 {
-    let page_json = obtain_content(page_url_pattern, &page_config.title).await;
-
-    // deserialize
-    let page: serde_json::Value = serde_json::from_str(&page_json)
-        .expect("failed to parse page JSON");
-
-    // get title and body
-    let page_dict = page["query"]["pages"].as_object()
-        .expect("failed to get page dict")
-        .values()
-        .nth(0).expect("page dict empty");
-    let actual_title = page_dict["title"].as_str().expect("page title not a string");
-    let body_wikitext = page_dict["revisions"][0]["*"].as_str().expect("page body not a string");
-
     // parse wikitext
     let parsed = parser.parse_article(actual_title, body_wikitext)
-        .expect("failed to parse article");
+        .map_err(|_| ScrapeError::WikitextParse { title: actual_title.to_owned() })?;
     let parsed_no_doctype = parsed.strip_prefix("<!DOCTYPE html>\n").unwrap_or(&parsed);
 
     // load as XML
     let xml_package = sxd_document::parser::parse(&parsed_no_doctype)
-        .expect("failed to parse processed wikitext as XML");
+        .map_err(|_| ScrapeError::XmlParse)?;
     let xml = xml_package.as_document();
 
     // find tables
-    let tables_xpath = sxd_xpath::Factory::new().build(".//table")
-        .expect("failed to parse tables XPath")
-        .expect("failed to obtain XPath");
+    let tables_xpath = compile_xpath(&sxd_xpath::Factory::new(), ".//table")?;
     let context = sxd_xpath::Context::new();
     let tables = tables_xpath.evaluate(&context, xml.root())
-        .expect("failed to execute tables XPath");
+        .map_err(|_| ScrapeError::XPathEval { expr: ".//table".to_owned() })?;
 
     let mut vehicles = Vec::new();
     if let sxd_xpath::Value::Nodeset(table_nodes) = tables {
         for table_node in table_nodes {
             let table_elem = table_node.element().expect("table node is not an element");
-            process_table(&mut vehicles, table_elem, &page_config, row_data_to_vehicles);
+            process_table(&mut vehicles, table_elem, &page_config, row_data_to_vehicles)?;
         }
     }
 
-    vehicles
+    Ok(vehicles)
+}
+
+pub(crate) async fn process_page<F, G>(client: &reqwest::Client, fetch_config: &FetchConfig, page_url_pattern: &str, page_config: &PageConfig, parser: &mut WikiParser, process_table: F, row_data_to_vehicles: G) -> Result<Vec<VehicleInfo>, ScrapeError>
+    where
+        F : FnMut(&mut Vec<VehicleInfo>, Element, &PageConfig, G) -> Result<(), ScrapeError>,
+        G : FnMut(&PageConfig, Vec<(String, String)>) -> Vec<VehicleInfo> + Copy,
+{
+    let page_json = obtain_content(client, fetch_config, page_url_pattern, &page_config.title).await?;
+
+    // deserialize
+    let page: serde_json::Value = serde_json::from_str(&page_json)
+        .map_err(ScrapeError::JsonParse)?;
+
+    // get title and body
+    let page_dict = page["query"]["pages"].as_object()
+        .ok_or_else(|| ScrapeError::JsonShape { field: "query.pages".to_owned() })?
+        .values()
+        .nth(0).ok_or_else(|| ScrapeError::JsonShape { field: "query.pages[0]".to_owned() })?;
+    let actual_title = page_dict["title"].as_str()
+        .ok_or_else(|| ScrapeError::JsonShape { field: "query.pages[0].title".to_owned() })?;
+    let body_wikitext = page_dict["revisions"][0]["*"].as_str()
+        .ok_or_else(|| ScrapeError::JsonShape { field: "query.pages[0].revisions[0].*".to_owned() })?;
+
+    process_wikitext(actual_title, body_wikitext, page_config, parser, process_table, row_data_to_vehicles)
+}
+
+/// Drives the same table-extraction pipeline as [`process_page`], but sources wikitext from an
+/// offline `pages-articles` XML dump (via [`MwDumpPages`]) instead of live Action API calls. Reads
+/// the whole dump in a single forward pass, matching each dump page's title against `page_configs`
+/// and running the corresponding configuration's pipeline on a match. Pages with a title not
+/// present in `page_configs` are skipped without being parsed as wikitext at all.
+pub(crate) fn process_dump<R, F, G>(dump_reader: R, page_configs: &[PageConfig], parser: &mut WikiParser, mut process_table: F, row_data_to_vehicles: G) -> Result<Vec<VehicleInfo>, ScrapeError>
+    where
+        R : Read,
+        F : FnMut(&mut Vec<VehicleInfo>, Element, &PageConfig, G) -> Result<(), ScrapeError>,
+        G : FnMut(&PageConfig, Vec<(String, String)>) -> Vec<VehicleInfo> + Copy,
+{
+    let page_configs_by_title: HashMap<&str, &PageConfig> = page_configs.iter()
+        .map(|pc| (pc.title.as_str(), pc))
+        .collect();
+
+    let mut all_vehicles = Vec::new();
+    for page_result in MwDumpPages::new(dump_reader) {
+        let (title, wikitext) = page_result?;
+
+        let Some(page_config) = page_configs_by_title.get(title.as_str()) else {
+            continue;
+        };
+
+        eprintln!("processing dump page {:?}", title);
+        let mut vehicles = process_wikitext(&title, &wikitext, page_config, parser, &mut process_table, row_data_to_vehicles)?;
+        all_vehicles.append(&mut vehicles);
+    }
+
+    Ok(all_vehicles)
 }
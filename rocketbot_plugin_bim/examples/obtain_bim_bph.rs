@@ -5,20 +5,31 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::env::args_os;
 use std::fs::File;
 use std::path::PathBuf;
-use std::sync::{LazyLock, Mutex};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
 
 use indexmap::IndexSet;
 use regex::Regex;
 use reqwest::header::{HeaderMap, HeaderValue};
 use rocketbot_bim_common::{PowerSource, VehicleClass, VehicleInfo, VehicleNumber};
+use rocketbot_bim_common::partial_date::parse_partial_date;
 use rocketbot_string::regex::EnjoyableRegex;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex as AsyncMutex, Semaphore};
+use tokio::time::Instant;
 use url::Url;
 
 
 static WHITESPACE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new("\\s+").expect("failed to compile whitespace regex"));
 static REGEX_CACHE: Mutex<BTreeMap<String, Regex>> = Mutex::new(BTreeMap::new());
+static CONTENT_TYPE_CHARSET_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new("(?i)charset=\"?([A-Za-z0-9_-]+)\"?").expect("failed to compile Content-Type charset regex"));
+static META_CHARSET_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new("(?i)<meta\\s[^>]*charset=[\"']?([A-Za-z0-9_-]+)").expect("failed to compile meta charset regex"));
+
+/// The number of leading bytes of a response body scanned for a `<meta charset>`/
+/// `<meta http-equiv="Content-Type">` tag, mirroring the prefix-scanning behavior of browsers
+/// (the declaration is required by the HTML spec to appear within the first 1024 bytes).
+const META_CHARSET_SCAN_LIMIT: usize = 1024;
 
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -35,7 +46,26 @@ struct Config {
     pub values_to_ignore: BTreeSet<String>,
     #[serde(default)] pub number_splitter: Option<String>,
     #[serde(default)] pub number_evaluators: BTreeMap<String, String>,
+    /// Pins the charset label (e.g. `"windows-1251"`) used to decode every response, overriding
+    /// both the `Content-Type` header and any `<meta charset>` tag. Use this when a site declares
+    /// the wrong charset.
+    #[serde(default)] pub force_encoding: Option<String>,
+    /// The number of start-URL page chains crawled in parallel.
+    #[serde(default = "default_max_concurrent_requests")] pub max_concurrent_requests: usize,
+    /// The minimum delay, in milliseconds, kept between two requests to the same host.
+    #[serde(default)] pub min_host_delay_ms: u64,
+    /// How many times a failed request (5xx status, timeout, or connection error) is retried
+    /// before the page is logged and skipped.
+    #[serde(default = "default_max_retries")] pub max_retries: u32,
+    /// The delay, in milliseconds, before the first retry; doubled after each subsequent retry.
+    #[serde(default = "default_initial_backoff_ms")] pub initial_backoff_ms: u64,
+    /// If `true`, the existing contents of `output_path` (if any) are loaded and merged with the
+    /// freshly-scraped vehicles instead of being overwritten outright.
+    #[serde(default)] pub merge: bool,
 }
+fn default_max_concurrent_requests() -> usize { 1 }
+fn default_max_retries() -> u32 { 3 }
+fn default_initial_backoff_ms() -> u64 { 500 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
 struct VehicleTypeConfig {
@@ -58,6 +88,144 @@ struct ColumnKeyConfig {
 }
 
 
+/// Detects the charset a response body is encoded in, preferring (in order) `force_encoding`, the
+/// `Content-Type` response header's `charset` parameter, a `<meta charset>`/
+/// `<meta http-equiv="Content-Type">` tag within the first [`META_CHARSET_SCAN_LIMIT`] bytes of the
+/// body, and finally UTF-8.
+fn detect_encoding(
+    content_type_header: Option<&str>,
+    body: &[u8],
+    force_encoding: Option<&str>,
+) -> &'static encoding_rs::Encoding {
+    if let Some(label) = force_encoding {
+        if let Some(encoding) = encoding_rs::Encoding::for_label(label.as_bytes()) {
+            return encoding;
+        }
+        eprintln!("unknown forced encoding label {:?}; falling back to detection", label);
+    }
+
+    if let Some(content_type) = content_type_header {
+        if let Some(captures) = CONTENT_TYPE_CHARSET_RE.captures(content_type) {
+            if let Some(encoding) = encoding_rs::Encoding::for_label(captures[1].as_bytes()) {
+                return encoding;
+            }
+        }
+    }
+
+    let scan_len = body.len().min(META_CHARSET_SCAN_LIMIT);
+    let prefix_latin1: String = body[..scan_len].iter().map(|&b| b as char).collect();
+    if let Some(captures) = META_CHARSET_RE.captures(&prefix_latin1) {
+        if let Some(encoding) = encoding_rs::Encoding::for_label(captures[1].as_bytes()) {
+            return encoding;
+        }
+    }
+
+    encoding_rs::UTF_8
+}
+
+
+/// Tracks, per host, the instant of the most recently issued request, so that
+/// [`wait_for_host_turn`] can space out requests to the same host by at least a configured delay.
+type HostLastRequest = Arc<AsyncMutex<BTreeMap<String, Instant>>>;
+
+/// Sleeps, if necessary, until at least `min_delay` has passed since the last request to `host`,
+/// then records the current instant as that host's most recent request.
+async fn wait_for_host_turn(host_last_request: &HostLastRequest, host: &str, min_delay: Duration) {
+    if min_delay.is_zero() {
+        return;
+    }
+
+    let wait_until = {
+        let mut guard = host_last_request.lock().await;
+        let now = Instant::now();
+        let wait_until = guard.get(host)
+            .map(|&last| last + min_delay)
+            .filter(|&wu| wu > now)
+            .unwrap_or(now);
+        guard.insert(host.to_owned(), wait_until);
+        wait_until
+    };
+
+    tokio::time::sleep_until(wait_until).await;
+}
+
+/// Downloads `url`, retrying with exponential backoff on 5xx responses, timeouts, and connection
+/// errors (up to `config.max_retries` times), and returns the response's `Content-Type` header
+/// (if any) alongside its raw body. Any other error, or exhaustion of retries, is returned as
+/// `Err` describing the failure instead of panicking, so the caller can log and skip the page.
+async fn fetch_with_retry(
+    client: &reqwest::Client,
+    url: &str,
+    config: &Config,
+    host_last_request: &HostLastRequest,
+) -> Result<(Option<String>, bytes::Bytes), String> {
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_owned()))
+        .unwrap_or_else(|| url.to_owned());
+    let min_delay = Duration::from_millis(config.min_host_delay_ms);
+
+    let mut backoff = Duration::from_millis(config.initial_backoff_ms);
+    for attempt in 0..=config.max_retries {
+        wait_for_host_turn(host_last_request, &host, min_delay).await;
+
+        let outcome = async {
+            let response = client.get(url).send().await
+                .map_err(|e| format!("failed to send request to {:?}: {}", url, e))?;
+            let status = response.status();
+            if status.is_server_error() {
+                return Err(format!("server error {} from {:?}", status, url));
+            }
+            let response = response.error_for_status()
+                .map_err(|e| format!("non-success status from {:?}: {}", url, e))?;
+            let content_type = response.headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned());
+            let body = response.bytes().await
+                .map_err(|e| format!("failed to obtain bytes for {:?}: {}", url, e))?;
+            Ok((content_type, body))
+        }.await;
+
+        match outcome {
+            Ok(ok) => return Ok(ok),
+            Err(e) => {
+                if attempt == config.max_retries {
+                    return Err(format!("giving up on {:?} after {} attempts: {}", url, attempt + 1, e));
+                }
+                eprintln!("attempt {} on {:?} failed ({}); retrying in {:?}", attempt + 1, url, e, backoff);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            },
+        }
+    }
+    unreachable!("loop always returns")
+}
+
+
+/// Merges a freshly-scraped `new` vehicle record with the `old` record loaded from a previous run,
+/// preferring the newly-scraped `depot` and `other_data` (falling back to the old values for
+/// anything the new scrape did not find) while never clearing an already-recorded
+/// `out_of_service_since`.
+fn merge_vehicle(old: &VehicleInfo, new: &VehicleInfo) -> VehicleInfo {
+    let mut merged = new.clone();
+
+    if merged.out_of_service_since.is_none() {
+        merged.out_of_service_since = old.out_of_service_since.clone();
+        merged.out_of_service_since_date = old.out_of_service_since_date.clone();
+    }
+    if merged.depot.is_none() {
+        merged.depot = old.depot.clone();
+    }
+
+    let mut merged_other_data = old.other_data.clone();
+    merged_other_data.extend(new.other_data.clone());
+    merged.other_data = merged_other_data;
+
+    merged
+}
+
+
 fn string_matches_regex(string: &str, regex_str: &str) -> Result<bool, Box<rhai::EvalAltResult>> {
     // do we know this regex already?
     let mut regex_cache_guard = REGEX_CACHE.lock()
@@ -80,8 +248,9 @@ async fn obtain_vehicles(
     client: &reqwest::Client,
     url: &str,
     config: &Config,
-    number_to_vehicle: &mut BTreeMap<VehicleNumber, VehicleInfo>,
-) -> Option<String> {
+    number_to_vehicle: &Mutex<BTreeMap<VehicleNumber, VehicleInfo>>,
+    host_last_request: &HostLastRequest,
+) -> Result<Option<String>, String> {
     // compile a few selectors
     let table_selector = match Selector::parse(&config.table_css_selector) {
         Ok(ts) => ts,
@@ -105,21 +274,15 @@ async fn obtain_vehicles(
         name_to_evaluator.insert(key.clone(), compiled);
     }
 
-    // download the page
-    let response_res = client.get(url)
-        .send().await.and_then(|r| r.error_for_status());
-    let response = match response_res {
-        Ok(r) => r,
-        Err(e) => panic!("failed to download {:?}: {}", url, e),
-    };
-    let response_bytes = match response.bytes().await {
-        Ok(b) => b,
-        Err(e) => panic!("failed to obtain bytes for {:?}: {}", url, e),
-    };
-    let response_string = match String::from_utf8(response_bytes.to_vec()) {
-        Ok(rs) => rs,
-        Err(e) => panic!("failed to decode bytes for {:?} as UTF-8: {}", url, e),
-    };
+    // download the page, retrying politely on transient failures
+    let (content_type_header, response_bytes) = fetch_with_retry(client, url, config, host_last_request).await?;
+    let encoding = detect_encoding(
+        content_type_header.as_deref(),
+        &response_bytes,
+        config.force_encoding.as_deref(),
+    );
+    let (response_string, _actual_encoding, _had_errors) = encoding.decode(&response_bytes);
+    let response_string = response_string.into_owned();
 
     let html = Html::parse_document(&response_string);
 
@@ -270,7 +433,9 @@ async fn obtain_vehicles(
             };
 
             for individual_vehicle_number in &vehicle_numbers {
-                if number_to_vehicle.contains_key(individual_vehicle_number) {
+                let mut number_to_vehicle_guard = number_to_vehicle.lock()
+                    .expect("number_to_vehicle poisoned?!");
+                if number_to_vehicle_guard.contains_key(individual_vehicle_number) {
                     eprintln!("skipping duplicate vehicle {:?} of type {:?}", individual_vehicle_number, raw_type);
                     continue;
                 }
@@ -289,13 +454,15 @@ async fn obtain_vehicles(
                     power_sources: type_info.power_sources.clone(),
                     type_code: type_info.vehicle_type.clone(),
                     in_service_since: in_service_since.clone(),
+                    in_service_since_date: in_service_since.as_deref().and_then(parse_partial_date),
                     out_of_service_since: out_of_service_since.clone(),
+                    out_of_service_since_date: out_of_service_since.as_deref().and_then(parse_partial_date),
                     manufacturer: type_info.manufacturer.clone(),
                     depot: depot.clone(),
                     other_data: other_data.clone(),
                     fixed_coupling,
                 };
-                number_to_vehicle.insert(individual_vehicle_number.clone(), vehicle);
+                number_to_vehicle_guard.insert(individual_vehicle_number.clone(), vehicle);
             }
         }
     }
@@ -310,7 +477,7 @@ async fn obtain_vehicles(
                 .as_str()
                 .to_owned()
         );
-    next_page_link
+    Ok(next_page_link)
 }
 
 
@@ -335,16 +502,85 @@ async fn main() {
         .user_agent(&config.user_agent)
         .build().expect("failed to build HTTP client");
 
-    let mut number_to_vehicle = BTreeMap::new();
+    let number_to_vehicle = Arc::new(Mutex::new(BTreeMap::new()));
+    let host_last_request: HostLastRequest = Arc::new(AsyncMutex::new(BTreeMap::new()));
+    let concurrency_limit = Arc::new(Semaphore::new(config.max_concurrent_requests.max(1)));
+
+    let mut chain_tasks = Vec::new();
     for start_url in &config.urls {
-        let mut url = start_url.clone();
-        while let Some(next_url) = obtain_vehicles(&http_client, &url, &config, &mut number_to_vehicle).await {
-            url = next_url;
-        }
+        let http_client = http_client.clone();
+        let config = config.clone();
+        let number_to_vehicle = Arc::clone(&number_to_vehicle);
+        let host_last_request = Arc::clone(&host_last_request);
+        let concurrency_limit = Arc::clone(&concurrency_limit);
+        let start_url = start_url.clone();
+
+        chain_tasks.push(tokio::spawn(async move {
+            let _permit = concurrency_limit.acquire().await
+                .expect("concurrency semaphore closed?!");
+
+            let mut url = start_url;
+            loop {
+                match obtain_vehicles(&http_client, &url, &config, &number_to_vehicle, &host_last_request).await {
+                    Ok(Some(next_url)) => url = next_url,
+                    Ok(None) => break,
+                    Err(e) => {
+                        eprintln!("skipping rest of chain starting at page {:?}: {}", url, e);
+                        break;
+                    },
+                }
+            }
+        }));
+    }
+    for chain_task in chain_tasks {
+        chain_task.await
+            .expect("crawl task panicked");
     }
 
     // derive list of references
-    let vehicles: Vec<&VehicleInfo> = number_to_vehicle.values().collect();
+    let number_to_vehicle = Arc::try_unwrap(number_to_vehicle)
+        .expect("number_to_vehicle still shared after all crawl tasks finished?!")
+        .into_inner()
+        .expect("number_to_vehicle poisoned?!");
+
+    let vehicles: Vec<VehicleInfo> = if config.merge {
+        let old_vehicles: Vec<VehicleInfo> = match File::open(&config.output_path) {
+            Ok(f) => ciborium::from_reader(f)
+                .expect("failed to parse existing output file"),
+            Err(_) => Vec::new(),
+        };
+        let mut old_number_to_vehicle: BTreeMap<VehicleNumber, VehicleInfo> = old_vehicles
+            .into_iter()
+            .map(|v| (v.number.clone(), v))
+            .collect();
+
+        let (mut added, mut updated, mut unchanged) = (0usize, 0usize, 0usize);
+        let mut merged_vehicles = Vec::with_capacity(number_to_vehicle.len() + old_number_to_vehicle.len());
+        for (number, new_vehicle) in &number_to_vehicle {
+            match old_number_to_vehicle.remove(number) {
+                Some(old_vehicle) => {
+                    let merged = merge_vehicle(&old_vehicle, new_vehicle);
+                    if merged == old_vehicle {
+                        unchanged += 1;
+                    } else {
+                        updated += 1;
+                    }
+                    merged_vehicles.push(merged);
+                },
+                None => {
+                    added += 1;
+                    merged_vehicles.push(new_vehicle.clone());
+                },
+            }
+        }
+        // whatever is left in old_number_to_vehicle was not rediscovered this run; keep it
+        merged_vehicles.extend(old_number_to_vehicle.into_values());
+
+        eprintln!("merge summary: {} added, {} updated, {} unchanged", added, updated, unchanged);
+        merged_vehicles
+    } else {
+        number_to_vehicle.into_values().collect()
+    };
 
     // output
     {
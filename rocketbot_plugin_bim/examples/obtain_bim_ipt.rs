@@ -14,6 +14,7 @@ use ego_tree::NodeRef;
 use indexmap::IndexSet;
 use regex::Regex;
 use rocketbot_bim_common::{PowerSource, VehicleClass, VehicleInfo, VehicleNumber};
+use rocketbot_bim_common::partial_date::parse_partial_date;
 use rocketbot_string::regex::EnjoyableRegex;
 use scraper::{ElementRef, Html, Node, Selector};
 use serde::{Deserialize, Serialize};
@@ -359,7 +360,9 @@ async fn main() -> ExitCode {
                     power_sources: vehicle_page_config.power_sources.clone(),
                     type_code: vehicle_page_config.type_code.clone(),
                     in_service_since: in_service_since.clone(),
+                    in_service_since_date: in_service_since.as_deref().and_then(parse_partial_date),
                     out_of_service_since: out_of_service_since.clone(),
+                    out_of_service_since_date: out_of_service_since.as_deref().and_then(parse_partial_date),
                     manufacturer: vehicle_page_config.manufacturer.clone(),
                     depot: None,
                     other_data,
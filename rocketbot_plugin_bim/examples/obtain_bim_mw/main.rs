@@ -4,13 +4,14 @@
 mod extract_info;
 
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env::args_os;
 use std::fs::File;
 use std::path::PathBuf;
 use std::time::Duration;
 
 use ciborium;
+use flate2::read::GzDecoder;
 use indexmap::IndexMap;
 use regex::Regex;
 use rocketbot_bim_common::{VehicleClass, VehicleInfo};
@@ -19,7 +20,7 @@ use rocketbot_mediawiki_parsing::WikiParser;
 use serde::{Deserialize, Serialize};
 use serde_json;
 
-use crate::extract_info::{process_page, process_table, row_data_to_trams};
+use crate::extract_info::{process_dump, process_page, process_table, row_data_to_trams};
 
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -28,7 +29,49 @@ struct Config {
     pub php_path: Option<String>,
     pub wiki_parse_server_dir: String,
     pub parser_already_running: bool,
-    pub page_sources: Vec<PageSource>,
+    #[serde(default)] pub fetch: FetchConfig,
+    #[serde(default)] pub page_sources: Vec<PageSource>,
+    #[serde(default)] pub dump_sources: Vec<DumpSource>,
+}
+
+/// A local MediaWiki `pages-articles` XML export to read page data from offline, together with
+/// the page configurations to match its pages against. Gzip-compressed dumps (conventionally
+/// named with a `.xml.gz` suffix) are decompressed transparently.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct DumpSource {
+    pub dump_path: String,
+    pub pages: Vec<PageConfig>,
+}
+
+/// Policy governing how `obtain_content` retries a failed HTTP fetch.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct FetchConfig {
+    /// The total number of attempts made to fetch a page, including the first one, before giving
+    /// up and returning an error.
+    #[serde(default = "FetchConfig::default_max_attempts")] pub max_attempts: u32,
+
+    /// The delay before the first retry, in milliseconds. Each subsequent retry doubles the delay
+    /// of the previous one (exponential backoff) before jitter is applied.
+    #[serde(default = "FetchConfig::default_base_delay_ms")] pub base_delay_ms: u64,
+
+    /// HTTP status codes that are considered transient and therefore worth retrying.
+    #[serde(default = "FetchConfig::default_retryable_status_codes")] pub retryable_status_codes: BTreeSet<u16>,
+}
+impl FetchConfig {
+    fn default_max_attempts() -> u32 { 3 }
+    fn default_base_delay_ms() -> u64 { 500 }
+    fn default_retryable_status_codes() -> BTreeSet<u16> {
+        [429, 500, 502, 503, 504].into_iter().collect()
+    }
+}
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: Self::default_max_attempts(),
+            base_delay_ms: Self::default_base_delay_ms(),
+            retryable_status_codes: Self::default_retryable_status_codes(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -107,21 +150,48 @@ async fn main() {
             parser
         };
 
+        let http_client = reqwest::Client::new();
         let mut page_cache = HashMap::new();
         for page_source in &config.page_sources {
             for page in &page_source.pages {
-                let mut vehicles = process_page(
+                let mut vehicles = match process_page(
+                    &http_client,
+                    &config.fetch,
                     &page_source.page_url_pattern,
                     &page,
                     &mut parser,
                     &mut page_cache,
                     process_table,
                     row_data_to_trams,
-                ).await;
+                ).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        eprintln!("skipping page {:?}: {}", page.title, e);
+                        continue;
+                    },
+                };
                 all_vehicles.append(&mut vehicles);
             }
         }
 
+        for dump_source in &config.dump_sources {
+            let dump_file = File::open(&dump_source.dump_path)
+                .expect("failed to open dump file");
+            let result = if dump_source.dump_path.ends_with(".gz") {
+                process_dump(GzDecoder::new(dump_file), &dump_source.pages, &mut parser, process_table, row_data_to_trams)
+            } else {
+                process_dump(dump_file, &dump_source.pages, &mut parser, process_table, row_data_to_trams)
+            };
+            let mut vehicles = match result {
+                Ok(v) => v,
+                Err(e) => {
+                    eprintln!("skipping dump {:?}: {}", dump_source.dump_path, e);
+                    continue;
+                },
+            };
+            all_vehicles.append(&mut vehicles);
+        }
+
         parser.parsing_done()
             .expect("error signalling end of parsing");
     }
@@ -6,25 +6,70 @@ use std::env::args_os;
 use std::fs::File;
 use std::io::{Cursor, Read};
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
+use chrono::{DateTime, Utc};
 use ciborium;
 use csv;
+use hmac::{Hmac, Mac};
 use indexmap::{IndexMap, IndexSet};
 use reqwest;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
 use rocketbot_bim_common::{PowerSource, VehicleClass, VehicleInfo, VehicleNumber};
+use rocketbot_bim_common::partial_date::{parse_partial_date, PartialDate};
 use serde::{Deserialize, Serialize};
 use serde_json;
+use sha2::{Digest, Sha256};
 
 
 #[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
 struct Config {
     pub pages: Vec<PageInfo>,
     pub output_path: String,
+
+    /// Credentials and endpoint for an S3-compatible object store. Required if `output_path` or
+    /// any `csv_url` is an `s3://bucket/key` URL.
+    #[serde(default)] pub s3: Option<S3Config>,
+
+    /// If set, load the previously-exported vehicle list (from `output_path`) and reconcile it
+    /// with this run's scrape using [`reconcile_vehicle`] instead of blindly overwriting it. The
+    /// per-source, per-vehicle causal history used for reconciliation is kept in a sidecar file
+    /// next to `output_path` (see [`version_state_path`]).
+    #[serde(default)] pub incremental: bool,
+
+    /// The number of seconds to sleep between scrapes in `--watch` mode. Required if `--watch` is
+    /// passed on the command line.
+    #[serde(default)] pub watch_interval_secs: Option<u64>,
+}
+
+/// Connection details for an S3-compatible object store, used when `output_path` or a `csv_url`
+/// is an `s3://` URL rather than a local path or an `http(s)://`/`file://` URL.
+#[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
+struct S3Config {
+    /// The base URL of the S3-compatible endpoint, e.g. `https://s3.example.com`.
+    pub endpoint: String,
+
+    /// The region to sign requests for.
+    #[serde(default = "S3Config::default_region")] pub region: String,
+
+    pub access_key: String,
+    pub secret_key: String,
+
+    /// Whether to address the bucket via `endpoint/bucket/key` (path-style) instead of
+    /// `bucket.endpoint/key` (virtual-hosted-style). Most S3-compatible stores that are not AWS
+    /// itself require path-style addressing.
+    #[serde(default)] pub path_style: bool,
+}
+impl S3Config {
+    fn default_region() -> String { "us-east-1".to_owned() }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
 struct PageInfo {
+    /// Identifies this page as the source of a dot ([`version_state`]) in incremental-merge mode.
+    /// Must be stable across runs and unique among a config's pages.
+    pub source_id: String,
+
     pub csv_url: String,
     pub subsets: BTreeSet<String>,
     pub timeout_ms: Option<u64>,
@@ -61,7 +106,274 @@ impl EmptyNoneElseCloned for Option<&String> {
 }
 
 
-async fn obtain_page_bytes(url: &str, timeout: Option<Duration>) -> Vec<u8> {
+/// Splits an `s3://bucket/key` URL into its bucket and key parts.
+fn parse_s3_url(url: &str) -> Option<(&str, &str)> {
+    let rest = url.strip_prefix("s3://")?;
+    rest.split_once('/')
+}
+
+fn sha256_hexdigest(data: &[u8]) -> String {
+    let mut sha256 = Sha256::new();
+    sha256.update(data);
+    let digest = sha256.finalize();
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Builds the `Authorization` header value for an AWS SigV4-signed S3 request, as well as the
+/// `x-amz-date` and `x-amz-content-sha256` header values that must accompany it.
+///
+/// See <https://docs.aws.amazon.com/general/latest/gr/sigv4-signing.html> for the algorithm.
+fn sigv4_sign(
+    s3_config: &S3Config,
+    now: DateTime<Utc>,
+    method: &str,
+    host: &str,
+    canonical_uri: &str,
+    payload: &[u8],
+) -> (String, String, String) {
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date_stamp = now.format("%Y%m%d").to_string();
+    let payload_hash = sha256_hexdigest(payload);
+
+    let canonical_headers = format!(
+        "host:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
+        host, payload_hash, amz_date,
+    );
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method, canonical_uri, canonical_headers, signed_headers, payload_hash,
+    );
+    let canonical_request_hash = sha256_hexdigest(canonical_request.as_bytes());
+
+    let credential_scope = format!("{}/{}/s3/aws4_request", date_stamp, s3_config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date, credential_scope, canonical_request_hash,
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", s3_config.secret_key).as_bytes(), date_stamp.as_bytes());
+    let k_region = hmac_sha256(&k_date, s3_config.region.as_bytes());
+    let k_service = hmac_sha256(&k_region, b"s3");
+    let k_signing = hmac_sha256(&k_service, b"aws4_request");
+    let signature_bytes = hmac_sha256(&k_signing, string_to_sign.as_bytes());
+    let signature: String = signature_bytes.iter().map(|b| format!("{:02x}", b)).collect();
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        s3_config.access_key, credential_scope, signed_headers, signature,
+    );
+
+    (authorization, amz_date, payload_hash)
+}
+
+/// Returns the `(host, canonical_uri, request_url)` for addressing `bucket`/`key` against
+/// `s3_config`'s endpoint, honoring `s3_config.path_style`.
+fn s3_request_parts(s3_config: &S3Config, bucket: &str, key: &str) -> (String, String, String) {
+    let endpoint_host = s3_config.endpoint
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+
+    if s3_config.path_style {
+        let canonical_uri = format!("/{}/{}", bucket, key);
+        let url = format!("{}{}", s3_config.endpoint.trim_end_matches('/'), canonical_uri);
+        (endpoint_host.to_owned(), canonical_uri, url)
+    } else {
+        let host = format!("{}.{}", bucket, endpoint_host);
+        let canonical_uri = format!("/{}", key);
+        let url = format!("https://{}{}", host, canonical_uri);
+        (host, canonical_uri, url)
+    }
+}
+
+async fn s3_get_object(s3_config: &S3Config, bucket: &str, key: &str) -> Vec<u8> {
+    let (host, canonical_uri, url) = s3_request_parts(s3_config, bucket, key);
+    let now = SystemTime::now().into();
+    let (authorization, amz_date, payload_hash) = sigv4_sign(s3_config, now, "GET", &host, &canonical_uri, b"");
+
+    let client = reqwest::Client::new();
+    let response = client.get(&url)
+        .header("host", host)
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", payload_hash)
+        .header("authorization", authorization)
+        .send().await
+        .expect("failed to obtain S3 response");
+    let response = response.error_for_status()
+        .expect("S3 GetObject request failed");
+    response.bytes().await
+        .expect("failed to obtain S3 response bytes")
+        .to_vec()
+}
+
+async fn s3_put_object(s3_config: &S3Config, bucket: &str, key: &str, body: &[u8]) {
+    let (host, canonical_uri, url) = s3_request_parts(s3_config, bucket, key);
+    let now = SystemTime::now().into();
+    let (authorization, amz_date, payload_hash) = sigv4_sign(s3_config, now, "PUT", &host, &canonical_uri, body);
+
+    let client = reqwest::Client::new();
+    let response = client.put(&url)
+        .header("host", host)
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", payload_hash)
+        .header("authorization", authorization)
+        .body(body.to_vec())
+        .send().await
+        .expect("failed to obtain S3 response");
+    response.error_for_status()
+        .expect("S3 PutObject request failed");
+}
+
+/// A single update event: the `counter`th run of the page identified by `source_id`.
+type Dot = (String, u64);
+
+/// The sidecar state that makes incremental merging possible: for each source, the counter of its
+/// most recent run; for each vehicle, the set of dots whose data it currently reflects.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct VersionState {
+    #[serde(default)] pub source_counters: HashMap<String, u64>,
+    #[serde(default)] pub vehicle_dots: HashMap<VehicleNumber, BTreeSet<Dot>>,
+}
+
+/// The sidecar file sits next to `output_path`, so an incremental merge always has its causal
+/// history available even though the CBOR output itself doesn't carry any of it.
+fn version_state_path(output_path: &str) -> String {
+    format!("{}.versions.json", output_path)
+}
+
+/// Loads the previously-exported vehicle list from `output_path` (in incremental mode, this is
+/// always a local CBOR file, never an `s3://` URL), returning an empty list on the first run.
+fn load_existing_vehicles_cbor(output_path: &str) -> Vec<VehicleInfo> {
+    let f = match File::open(output_path) {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+    ciborium::from_reader(f)
+        .expect("failed to parse existing output file")
+}
+
+fn load_version_state(output_path: &str) -> VersionState {
+    let f = match File::open(version_state_path(output_path)) {
+        Ok(f) => f,
+        Err(_) => return VersionState::default(),
+    };
+    serde_json::from_reader(f)
+        .expect("failed to parse version state file")
+}
+
+fn save_version_state(output_path: &str, state: &VersionState) {
+    let f = File::create(version_state_path(output_path))
+        .expect("failed to create version state file");
+    serde_json::to_writer_pretty(f, state)
+        .expect("failed to write version state");
+}
+
+/// Whether `left`'s dots are a superset of `right`'s (in which case `left` dominates, having seen
+/// everything `right` has and more).
+fn dominates(left: &BTreeSet<Dot>, right: &BTreeSet<Dot>) -> bool {
+    right.is_subset(left)
+}
+
+/// Merges two concurrently-updated copies of the same vehicle field-by-field, preferring `new`'s
+/// value on a conflict but logging it so the disagreement is auditable instead of silently lost.
+fn merge_conflicting_vehicles(old: &VehicleInfo, new: &VehicleInfo) -> VehicleInfo {
+    let mut merged = new.clone();
+
+    macro_rules! log_conflict {
+        ($field:ident) => {
+            if old.$field != new.$field {
+                eprintln!(
+                    "conflict on {}: {} is {:?} vs. {:?}; keeping {:?}",
+                    new.number, stringify!($field), old.$field, new.$field, new.$field,
+                );
+            }
+        };
+    }
+    log_conflict!(vehicle_class);
+    log_conflict!(power_sources);
+    log_conflict!(type_code);
+    log_conflict!(in_service_since);
+    log_conflict!(out_of_service_since);
+    log_conflict!(manufacturer);
+    log_conflict!(depot);
+    log_conflict!(fixed_coupling);
+
+    let mut other_data = old.other_data.clone();
+    other_data.extend(new.other_data.clone());
+    merged.other_data = other_data;
+
+    merged
+}
+
+/// Reconciles a freshly-scraped vehicle against the previously-recorded state of the same
+/// `VehicleNumber`, following a dotted-version-vector-set scheme: if one side's dots are a
+/// superset of the other's, it is authoritative and wins outright; if the dot sets are concurrent
+/// (neither is a superset), the two are a genuine conflict and are merged field-by-field, with
+/// conflicting fields logged rather than silently dropped.
+fn reconcile_vehicle(
+    existing: Option<(BTreeSet<Dot>, VehicleInfo)>,
+    new_dots: BTreeSet<Dot>,
+    new_vehicle: VehicleInfo,
+) -> (BTreeSet<Dot>, VehicleInfo) {
+    let (old_dots, old_vehicle) = match existing {
+        None => return (new_dots, new_vehicle),
+        Some(e) => e,
+    };
+
+    if dominates(&new_dots, &old_dots) {
+        (new_dots, new_vehicle)
+    } else if dominates(&old_dots, &new_dots) {
+        (old_dots, old_vehicle)
+    } else {
+        let merged_vehicle = merge_conflicting_vehicles(&old_vehicle, &new_vehicle);
+        let merged_dots: BTreeSet<Dot> = old_dots.union(&new_dots).cloned().collect();
+        (merged_dots, merged_vehicle)
+    }
+}
+
+/// The on-disk sidecar recording a cached page's `ETag`/`Last-Modified` validators, so `--watch`
+/// mode's subsequent polls can make a conditional request instead of blindly re-downloading.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct HttpCacheMetadata {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Where per-URL HTTP cache entries (body + validators) live for `--watch` mode's conditional
+/// fetches, namespaced by `output_path` so multiple configs don't collide.
+fn http_cache_dir(output_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.http-cache", output_path))
+}
+
+fn http_cache_paths(cache_dir: &Path, url: &str) -> (PathBuf, PathBuf) {
+    let key = sha256_hexdigest(url.as_bytes());
+    (cache_dir.join(format!("{}.body", key)), cache_dir.join(format!("{}.meta.json", key)))
+}
+
+fn load_http_cache_metadata(meta_path: &Path) -> Option<HttpCacheMetadata> {
+    let f = File::open(meta_path).ok()?;
+    serde_json::from_reader(f).ok()
+}
+
+/// Fetches `url`, returning its bytes together with whether the content actually changed since
+/// the last fetch. `file://` and `s3://` URLs are always considered changed, since there is no
+/// cheap way to validate them conditionally; `http(s)://` URLs are cached in `cache_dir` and
+/// validated via `If-None-Match`/`If-Modified-Since`, with a `304 Not Modified` response
+/// reporting no change and returning the previously-cached body.
+async fn obtain_page_bytes(
+    url: &str,
+    timeout: Option<Duration>,
+    s3_config: Option<&S3Config>,
+    cache_dir: &Path,
+) -> (Vec<u8>, bool) {
     if let Some(file_path) = url.strip_prefix("file://") {
         // it's a local file
         let mut f = File::open(file_path)
@@ -69,46 +381,169 @@ async fn obtain_page_bytes(url: &str, timeout: Option<Duration>) -> Vec<u8> {
         let mut buf = Vec::new();
         f.read_to_end(&mut buf)
             .expect("failed to read local page");
-        buf
-    } else {
-        let mut client_builder = reqwest::Client::builder();
-        if let Some(to) = timeout {
-            client_builder = client_builder.timeout(to);
+        return (buf, true);
+    }
+    if let Some((bucket, key)) = parse_s3_url(url) {
+        let s3_config = s3_config
+            .expect("csv_url is an s3:// URL but no [s3] configuration was provided");
+        let bytes = s3_get_object(s3_config, bucket, key).await;
+        return (bytes, true);
+    }
+
+    let (body_path, meta_path) = http_cache_paths(cache_dir, url);
+    let cached_metadata = load_http_cache_metadata(&meta_path);
+
+    let mut client_builder = reqwest::Client::builder();
+    if let Some(to) = timeout {
+        client_builder = client_builder.timeout(to);
+    }
+    let client = client_builder.build()
+        .expect("failed to build client");
+
+    let mut request = client.get(url);
+    if let Some(metadata) = &cached_metadata {
+        if let Some(etag) = &metadata.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &metadata.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
         }
-        let client = client_builder.build()
-            .expect("failed to build client");
-        let request = client.get(url)
-            .build().expect("failed to build request");
-        let response = client.execute(request).await
-            .expect("failed to obtain response");
-        let response_bytes = response.bytes().await
-            .expect("failed to obtain response bytes");
-        response_bytes.to_vec()
     }
+
+    let response = request.send().await
+        .expect("failed to obtain response");
+    let status = response.status();
+
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        let cached_body = std::fs::read(&body_path)
+            .expect("server returned 304 Not Modified but no cached body is available");
+        return (cached_body, false);
+    }
+
+    let new_etag = response.headers().get(ETAG)
+        .and_then(|v| v.to_str().ok()).map(|v| v.to_owned());
+    let new_last_modified = response.headers().get(LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok()).map(|v| v.to_owned());
+    let response_bytes = response.bytes().await
+        .expect("failed to obtain response bytes").to_vec();
+
+    std::fs::create_dir_all(cache_dir)
+        .expect("failed to create HTTP cache directory");
+    std::fs::write(&body_path, &response_bytes)
+        .expect("failed to write cached body");
+    let metadata = HttpCacheMetadata { etag: new_etag, last_modified: new_last_modified };
+    let meta_file = File::create(&meta_path)
+        .expect("failed to create cache metadata file");
+    serde_json::to_writer(meta_file, &metadata)
+        .expect("failed to write cache metadata");
+
+    (response_bytes, true)
 }
 
 
-#[tokio::main]
-async fn main() {
-    // load config
-    let config: Config = {
-        let config_path = match args_os().nth(1) {
-            Some(cp) => PathBuf::from(cp),
-            None => PathBuf::from("obtain_bim_sl.json"),
-        };
-        let f = File::open(config_path)
-            .expect("failed to open config file");
-        serde_json::from_reader(f)
-            .expect("failed to parse config file")
+/// A compact summary of a vehicle database, written to `<output_path>.manifest.json` alongside the
+/// CBOR blob so that dashboards and import-validation scripts can learn what's in it without
+/// deserializing and scanning the (potentially large) vehicle list themselves.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct Manifest {
+    pub vehicle_count: usize,
+    pub count_by_type_code: BTreeMap<String, usize>,
+    pub count_by_vehicle_class: BTreeMap<String, usize>,
+    pub count_by_depot: BTreeMap<String, usize>,
+    pub active_count: usize,
+    pub withdrawn_count: usize,
+    pub min_in_service_since: Option<PartialDate>,
+    pub max_in_service_since: Option<PartialDate>,
+    pub sources: BTreeMap<String, String>,
+}
+
+fn build_manifest(vehicles: &[VehicleInfo], source_fetch_times: &BTreeMap<String, String>) -> Manifest {
+    let mut manifest = Manifest::default();
+    manifest.vehicle_count = vehicles.len();
+    manifest.sources = source_fetch_times.clone();
+
+    for vehicle in vehicles {
+        *manifest.count_by_type_code.entry(vehicle.type_code.clone()).or_insert(0) += 1;
+        *manifest.count_by_vehicle_class.entry(vehicle.vehicle_class.to_string()).or_insert(0) += 1;
+        if let Some(depot) = &vehicle.depot {
+            *manifest.count_by_depot.entry(depot.clone()).or_insert(0) += 1;
+        }
+
+        if vehicle.out_of_service_since.is_some() {
+            manifest.withdrawn_count += 1;
+        } else {
+            manifest.active_count += 1;
+        }
+
+        if let Some(since) = vehicle.in_service_since_date {
+            manifest.min_in_service_since = Some(match manifest.min_in_service_since {
+                Some(current) => current.min(since),
+                None => since,
+            });
+            manifest.max_in_service_since = Some(match manifest.max_in_service_since {
+                Some(current) => current.max(since),
+                None => since,
+            });
+        }
+    }
+
+    manifest
+}
+
+fn manifest_path(output_path: &str) -> String {
+    format!("{}.manifest.json", output_path)
+}
+
+fn write_manifest(output_path: &str, manifest: &Manifest) {
+    let f = File::create(manifest_path(output_path))
+        .expect("failed to create manifest file");
+    serde_json::to_writer_pretty(f, manifest)
+        .expect("failed to write manifest");
+}
+
+/// Writes `vehicles` to `output_path` as CBOR, via a temp file that is then renamed into place so
+/// a reader never observes a partially-written file.
+fn write_vehicles_atomically(output_path: &str, vehicles: &[VehicleInfo]) {
+    let tmp_path = format!("{}.tmp", output_path);
+    {
+        let f = File::create(&tmp_path)
+            .expect("failed to create temp output file");
+        ciborium::into_writer(vehicles, f)
+            .expect("failed to write vehicles");
+    }
+    std::fs::rename(&tmp_path, output_path)
+        .expect("failed to rename temp output file into place");
+}
+
+/// Runs a single scrape-and-merge pass, rewriting `config.output_path` only if at least one page's
+/// content actually changed since the last pass (per [`obtain_page_bytes`]'s conditional fetch).
+/// Returns whether the output was rewritten.
+async fn run_once(config: &Config, cache_dir: &Path) -> bool {
+    let mut version_state = if config.incremental {
+        load_version_state(&config.output_path)
+    } else {
+        VersionState::default()
     };
+    let mut page_counters: HashMap<String, u64> = HashMap::new();
+    if config.incremental {
+        for page in &config.pages {
+            let counter = version_state.source_counters.get(&page.source_id).copied().unwrap_or(0) + 1;
+            page_counters.insert(page.source_id.clone(), counter);
+        }
+    }
 
     let mut vehicles: Vec<VehicleInfo> = Vec::new();
+    let mut vehicle_dots: Vec<BTreeSet<Dot>> = Vec::new();
+    let mut any_changed = false;
+    let mut source_fetch_times: BTreeMap<String, String> = BTreeMap::new();
     for page in &config.pages {
         eprintln!("fetching {}", page.csv_url);
 
         let timeout = page.timeout_ms.map(|ms| Duration::from_millis(ms));
 
-        let page_bytes_utf16le = obtain_page_bytes(&page.csv_url, timeout).await;
+        let (page_bytes_utf16le, page_changed) = obtain_page_bytes(&page.csv_url, timeout, config.s3.as_ref(), cache_dir).await;
+        any_changed |= page_changed;
+        source_fetch_times.insert(page.csv_url.clone(), Utc::now().to_rfc3339());
         let page_words: Vec<u16> = page_bytes_utf16le
             .chunks(2)
             .map(|ch| u16::from_le_bytes(ch.try_into().unwrap()))
@@ -244,43 +679,145 @@ async fn main() {
                     power_sources: class_def.power_sources.clone(),
                     type_code: class_def.type_code.clone(),
                     in_service_since: in_service_since.clone(),
+                    in_service_since_date: in_service_since.as_deref().and_then(parse_partial_date),
                     out_of_service_since: out_of_service_since.clone(),
+                    out_of_service_since_date: out_of_service_since.as_deref().and_then(parse_partial_date),
                     manufacturer: class_def.manufacturer.clone(),
                     depot: depot.clone(),
                     other_data: other_data.clone(),
                     fixed_coupling: fixed_coupling.clone(),
                 };
+                if config.incremental {
+                    let counter = *page_counters.get(&page.source_id)
+                        .expect("page_counters was precomputed for every page");
+                    let dots: BTreeSet<Dot> = [(page.source_id.clone(), counter)].into_iter().collect();
+                    vehicle_dots.push(dots);
+                }
                 vehicles.push(vehicle);
             }
         }
     }
 
-    vehicles.sort_unstable_by_key(|v| v.number.clone());
+    if !any_changed {
+        eprintln!("no pages changed; skipping merge and rewrite");
+        return false;
+    }
+
+    if config.incremental {
+        // reconcile same-run duplicates (e.g. two sources describing the same vehicle) using the
+        // dotted-version-vector-set scheme, then fold in the previously-recorded state
+        let previous_vehicles = load_existing_vehicles_cbor(&config.output_path);
+        let mut previous_by_number: HashMap<VehicleNumber, VehicleInfo> = previous_vehicles
+            .into_iter()
+            .map(|v| (v.number.clone(), v))
+            .collect();
 
-    // clear out duplicates
-    let mut i = 1;
-    while i < vehicles.len() {
-        let left = &vehicles[i-1];
-        let right = &vehicles[i];
-        if left == right {
-            vehicles.remove(i);
-            continue;
+        let mut merged_by_number: BTreeMap<VehicleNumber, (BTreeSet<Dot>, VehicleInfo)> = BTreeMap::new();
+        for (vehicle, dots) in vehicles.into_iter().zip(vehicle_dots.into_iter()) {
+            let number = vehicle.number.clone();
+            let existing = merged_by_number.remove(&number);
+            merged_by_number.insert(number, reconcile_vehicle(existing, dots, vehicle));
         }
 
-        if left.number == right.number {
-            println!("dupe! {:?} vs. {:?}", left, right);
-            // remove the older one (assume it's the one that came first)
-            vehicles.remove(i - 1);
-        } else {
-            i += 1;
+        for (number, slot) in merged_by_number.iter_mut() {
+            let old_dots = version_state.vehicle_dots.get(number).cloned();
+            let old_vehicle = previous_by_number.remove(number);
+            if let (Some(old_dots), Some(old_vehicle)) = (old_dots, old_vehicle) {
+                let (new_dots, new_vehicle) = slot.clone();
+                *slot = reconcile_vehicle(Some((old_dots, old_vehicle)), new_dots, new_vehicle);
+            }
+        }
+
+        // vehicles that used to be scraped but no longer are: keep them as-is, history intact
+        for (number, old_vehicle) in previous_by_number {
+            let old_dots = version_state.vehicle_dots.get(&number).cloned().unwrap_or_default();
+            merged_by_number.insert(number, (old_dots, old_vehicle));
+        }
+
+        version_state.vehicle_dots = merged_by_number.iter()
+            .map(|(number, (dots, _))| (number.clone(), dots.clone()))
+            .collect();
+        for (source_id, counter) in page_counters {
+            version_state.source_counters.insert(source_id, counter);
+        }
+        save_version_state(&config.output_path, &version_state);
+
+        vehicles = merged_by_number.into_values().map(|(_, vehicle)| vehicle).collect();
+    } else {
+        vehicles.sort_unstable_by_key(|v| v.number.clone());
+
+        // clear out duplicates
+        let mut i = 1;
+        while i < vehicles.len() {
+            let left = &vehicles[i-1];
+            let right = &vehicles[i];
+            if left == right {
+                vehicles.remove(i);
+                continue;
+            }
+
+            if left.number == right.number {
+                println!("dupe! {:?} vs. {:?}", left, right);
+                // remove the older one (assume it's the one that came first)
+                vehicles.remove(i - 1);
+            } else {
+                i += 1;
+            }
         }
     }
 
     // output
-    {
-        let f = File::create(config.output_path)
-            .expect("failed to open output file");
-        ciborium::into_writer(&vehicles, f)
-            .expect("failed to write vehicles");
+    if let Some((bucket, key)) = parse_s3_url(&config.output_path) {
+        let s3_config = config.s3.as_ref()
+            .expect("output_path is an s3:// URL but no [s3] configuration was provided");
+        let mut buf = Vec::new();
+        ciborium::into_writer(&vehicles, &mut buf)
+            .expect("failed to serialize vehicles");
+        s3_put_object(s3_config, bucket, key, &buf).await;
+    } else {
+        write_vehicles_atomically(&config.output_path, &vehicles);
+    }
+
+    let manifest = build_manifest(&vehicles, &source_fetch_times);
+    write_manifest(&config.output_path, &manifest);
+
+    true
+}
+
+
+#[tokio::main]
+async fn main() {
+    // load config
+    let mut watch = false;
+    let mut config_path: Option<PathBuf> = None;
+    for arg in args_os().skip(1) {
+        if arg == "--watch" {
+            watch = true;
+        } else {
+            config_path = Some(PathBuf::from(arg));
+        }
+    }
+
+    let config: Config = {
+        let config_path = config_path
+            .unwrap_or_else(|| PathBuf::from("obtain_bim_sl.json"));
+        let f = File::open(config_path)
+            .expect("failed to open config file");
+        serde_json::from_reader(f)
+            .expect("failed to parse config file")
+    };
+
+    let cache_dir = http_cache_dir(&config.output_path);
+
+    if watch {
+        let interval_secs = config.watch_interval_secs
+            .expect("--watch requires watch_interval_secs to be set in the config");
+        loop {
+            let changed = run_once(&config, &cache_dir).await;
+            eprintln!("{}", if changed { "output rewritten" } else { "no changes" });
+            tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+        }
+    } else {
+        run_once(&config, &cache_dir).await;
     }
 }
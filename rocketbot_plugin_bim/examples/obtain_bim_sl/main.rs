@@ -1,20 +1,27 @@
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::env::args_os;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use indexmap::IndexSet;
+use md5::{Digest, Md5};
 use once_cell::sync::Lazy;
+use rand::{Rng, thread_rng};
 use regex::Regex;
 use reqwest;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, USER_AGENT};
 use rocketbot_plugin_bim::{VehicleClass, VehicleInfo, VehicleNumber};
 use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
 use serde_json;
 
 
+const DEFAULT_USER_AGENT: &str = "rocketbot-obtain-bim-sl/1.0";
+
+
 static DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(concat!(
     "(?P<first>[0-9]+)",
     "(?:",
@@ -33,6 +40,55 @@ static DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(concat!(
 struct Config {
     pub pages: Vec<PageInfo>,
     pub output_path: String,
+    #[serde(default)] pub merge: bool,
+    #[serde(default)] pub fetch: FetchConfig,
+}
+
+/// Policy governing how [`obtain_page_bytes`] retries a failed HTTP fetch and caches successful
+/// ones on disk.
+#[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
+struct FetchConfig {
+    /// The total number of attempts made to fetch a page, including the first one, before giving
+    /// up and panicking.
+    #[serde(default = "FetchConfig::default_max_attempts")] pub max_attempts: u32,
+
+    /// The delay before the first retry, in milliseconds. Each subsequent retry doubles the delay
+    /// of the previous one (exponential backoff) before jitter is applied.
+    #[serde(default = "FetchConfig::default_base_delay_ms")] pub base_delay_ms: u64,
+
+    /// HTTP status codes that are considered transient and therefore worth retrying.
+    #[serde(default = "FetchConfig::default_retryable_status_codes")] pub retryable_status_codes: BTreeSet<u16>,
+
+    /// If set, fetched page bodies are cached on disk in this directory, keyed by URL, together
+    /// with their `ETag`/`Last-Modified` response headers. Subsequent runs send those back as
+    /// `If-None-Match`/`If-Modified-Since`, and a `304 Not Modified` response reuses the cached
+    /// body instead of re-downloading it.
+    #[serde(default)] pub cache_dir: Option<String>,
+}
+impl FetchConfig {
+    fn default_max_attempts() -> u32 { 3 }
+    fn default_base_delay_ms() -> u64 { 500 }
+    fn default_retryable_status_codes() -> BTreeSet<u16> {
+        [429, 500, 502, 503, 504].into_iter().collect()
+    }
+}
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: Self::default_max_attempts(),
+            base_delay_ms: Self::default_base_delay_ms(),
+            retryable_status_codes: Self::default_retryable_status_codes(),
+            cache_dir: None,
+        }
+    }
+}
+
+/// The on-disk sidecar recording the validators of a cached page body, so a later run can make a
+/// conditional request instead of blindly re-fetching.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct CacheMetadata {
+    etag: Option<String>,
+    last_modified: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
@@ -41,6 +97,72 @@ struct PageInfo {
     pub type_code: String,
     pub vehicle_class: VehicleClass,
     #[serde(default)] pub other_data: BTreeMap<String, String>,
+    #[serde(default)] pub selectors: SelectorConfig,
+    #[serde(default)] pub column_map: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
+struct SelectorConfig {
+    #[serde(default = "SelectorConfig::default_class_data_table")] pub class_data_table: String,
+    #[serde(default = "SelectorConfig::default_class_data_row")] pub class_data_row: String,
+    #[serde(default = "SelectorConfig::default_vehicle_table")] pub vehicle_table: String,
+    #[serde(default = "SelectorConfig::default_header_field")] pub header_field: String,
+    #[serde(default = "SelectorConfig::default_vehicle_row")] pub vehicle_row: String,
+    #[serde(default = "SelectorConfig::default_cell")] pub cell: String,
+}
+impl SelectorConfig {
+    fn default_class_data_table() -> String { "div#classdata > table".to_owned() }
+    fn default_class_data_row() -> String { "tr".to_owned() }
+    fn default_vehicle_table() -> String { "table#ClassMembersTable".to_owned() }
+    fn default_header_field() -> String { "thead th".to_owned() }
+    fn default_vehicle_row() -> String { "tbody tr".to_owned() }
+    fn default_cell() -> String { "td".to_owned() }
+}
+impl Default for SelectorConfig {
+    fn default() -> Self {
+        Self {
+            class_data_table: Self::default_class_data_table(),
+            class_data_row: Self::default_class_data_row(),
+            vehicle_table: Self::default_vehicle_table(),
+            header_field: Self::default_header_field(),
+            vehicle_row: Self::default_vehicle_row(),
+            cell: Self::default_cell(),
+        }
+    }
+}
+
+
+/// The builder field (or `other_data` key) that a scraped column header is mapped to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum ColumnTarget {
+    Number,
+    FixedCoupling,
+    InServiceSince,
+    OutOfServiceSince,
+    Manufacturer,
+    Other(String),
+}
+impl ColumnTarget {
+    pub fn for_header(header: &str, column_map: &HashMap<String, String>) -> Self {
+        if let Some(mapped) = column_map.get(header) {
+            return match mapped.as_str() {
+                "number" => Self::Number,
+                "fixed_coupling" => Self::FixedCoupling,
+                "in_service_since" => Self::InServiceSince,
+                "out_of_service_since" => Self::OutOfServiceSince,
+                "manufacturer" => Self::Manufacturer,
+                other => Self::Other(other.to_owned()),
+            };
+        }
+
+        // fall back to the site's traditional column names
+        match header {
+            "Number" => Self::Number,
+            "Formation" => Self::FixedCoupling,
+            "Builder" => Self::Manufacturer,
+            other => Self::Other(other.to_owned()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -135,7 +257,53 @@ impl VehicleInfoBuilder {
 }
 
 
-async fn obtain_page_bytes(url: &str) -> Vec<u8> {
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        hex.push_str(&format!("{:02x}", b));
+    }
+    hex
+}
+
+/// Returns the paths of the cached body and metadata sidecar for `url` within `cache_dir`, keyed
+/// by the MD5 hash of the URL (so that arbitrary URLs always turn into safe filenames).
+fn cache_paths(cache_dir: &str, url: &str) -> (PathBuf, PathBuf) {
+    let mut hasher = Md5::new();
+    hasher.update(url.as_bytes());
+    let key = hex_encode(&hasher.finalize());
+    (
+        Path::new(cache_dir).join(format!("{}.body", key)),
+        Path::new(cache_dir).join(format!("{}.meta.json", key)),
+    )
+}
+
+fn load_cache_metadata(meta_path: &Path) -> Option<CacheMetadata> {
+    let f = File::open(meta_path).ok()?;
+    serde_json::from_reader(f).ok()
+}
+
+/// Returns how long to wait before the `attempt`th retry (1-based), honoring a `Retry-After`
+/// header if the server provided one, falling back to exponential backoff with jitter otherwise.
+fn retry_delay(fetch_config: &FetchConfig, attempt: u32, retry_after: Option<Duration>) -> Duration {
+    if let Some(ra) = retry_after {
+        return ra;
+    }
+
+    let exponential_ms = fetch_config.base_delay_ms.saturating_mul(1u64 << (attempt - 1));
+    let jittered_ms = thread_rng().gen_range(exponential_ms / 2..=exponential_ms);
+    Duration::from_millis(jittered_ms)
+}
+
+/// Parses a `Retry-After` header value, which is either a number of seconds or an HTTP date. Only
+/// the former is supported; the latter is rare enough in practice that falling back to the
+/// regular backoff delay is an acceptable trade-off.
+fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?;
+    let seconds: u64 = value.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
+
+async fn obtain_page_bytes(client: &reqwest::Client, fetch_config: &FetchConfig, url: &str) -> Vec<u8> {
     if let Some(file_path) = url.strip_prefix("file://") {
         // it's a local file
         let mut f = File::open(file_path)
@@ -143,14 +311,75 @@ async fn obtain_page_bytes(url: &str) -> Vec<u8> {
         let mut buf = Vec::new();
         f.read_to_end(&mut buf)
             .expect("failed to read local page");
-        buf
-    } else {
-        let response = reqwest::get(url).await
+        return buf;
+    }
+
+    let paths = fetch_config.cache_dir.as_ref()
+        .map(|dir| cache_paths(dir, url));
+    let cached_metadata = paths.as_ref()
+        .and_then(|(_, meta_path)| load_cache_metadata(meta_path));
+
+    let max_attempts = fetch_config.max_attempts.max(1);
+    for attempt in 1..=max_attempts {
+        let mut request = client.get(url)
+            .header(USER_AGENT, DEFAULT_USER_AGENT);
+        if let Some(metadata) = &cached_metadata {
+            if let Some(etag) = &metadata.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &metadata.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let response = request.send().await
             .expect("failed to obtain response");
-        let response_bytes = response.bytes().await
-            .expect("failed to obtain response bytes");
-        response_bytes.to_vec()
+        let status = response.status();
+
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some((body_path, _)) = &paths {
+                if let Ok(cached_body) = std::fs::read(body_path) {
+                    return cached_body;
+                }
+            }
+            panic!("server returned 304 Not Modified for {:?} but no cached body is available", url);
+        }
+
+        if status.is_success() {
+            let new_etag = response.headers().get(ETAG)
+                .and_then(|v| v.to_str().ok()).map(|v| v.to_owned());
+            let new_last_modified = response.headers().get(LAST_MODIFIED)
+                .and_then(|v| v.to_str().ok()).map(|v| v.to_owned());
+            let response_bytes = response.bytes().await
+                .expect("failed to obtain response bytes").to_vec();
+
+            if let Some((body_path, meta_path)) = &paths {
+                std::fs::create_dir_all(body_path.parent().expect("body path has no parent"))
+                    .expect("failed to create cache directory");
+                std::fs::write(body_path, &response_bytes)
+                    .expect("failed to write cached body");
+                let metadata = CacheMetadata { etag: new_etag, last_modified: new_last_modified };
+                let meta_file = File::create(meta_path)
+                    .expect("failed to create cache metadata file");
+                serde_json::to_writer(meta_file, &metadata)
+                    .expect("failed to write cache metadata");
+            }
+
+            return response_bytes;
+        }
+
+        if attempt == max_attempts || !fetch_config.retryable_status_codes.contains(&status.as_u16()) {
+            panic!("giving up on {:?} after {} attempt(s); last status was {}", url, attempt, status);
+        }
+
+        let retry_after = parse_retry_after(&response);
+        let delay = retry_delay(fetch_config, attempt, retry_after);
+        eprintln!("  request for {:?} failed with status {}; retrying in {:?}", url, status, delay);
+        tokio::time::sleep(delay).await;
     }
+
+    // the loop above always returns on its last iteration (attempt == max_attempts)
+    unreachable!()
 }
 
 
@@ -238,38 +467,108 @@ fn compare_age(left: &VehicleInfo, right: &VehicleInfo) -> Ordering {
 }
 
 
+/// Merges a freshly-scraped vehicle record on top of a previously-recorded one: the newer record's
+/// fields win overall (per [`compare_age`]), but `other_data` is merged key-by-key, with the newly
+/// scraped values taking precedence and keys found only in the old record being retained. This way,
+/// manually-curated `other_data` entries survive re-scrapes instead of being discarded wholesale.
+fn merge_vehicle(old: VehicleInfo, new: VehicleInfo) -> VehicleInfo {
+    let mut merged = match compare_age(&old, &new) {
+        Ordering::Less | Ordering::Equal => new.clone(),
+        Ordering::Greater => old.clone(),
+    };
+
+    let mut other_data = old.other_data;
+    other_data.extend(new.other_data);
+    merged.other_data = other_data;
+
+    merged
+}
+
+
+/// Loads the previously-scraped vehicle list from `output_path`, returning an empty list if the
+/// file does not exist yet (e.g. on the very first run of a merge-enabled scrape).
+fn load_existing_vehicles(output_path: &str) -> Vec<VehicleInfo> {
+    let f = match File::open(output_path) {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+    serde_json::from_reader(f)
+        .expect("failed to parse existing output file")
+}
+
+
+/// Merges the old vehicle list (loaded from a previous run) with the freshly-scraped one,
+/// reconciling entries that share a number with [`merge_vehicle`] and keeping entries that are
+/// unique to either side (e.g. a vehicle that has since vanished from the scraped page).
+fn merge_old_and_new(old_vehicles: Vec<VehicleInfo>, new_vehicles: Vec<VehicleInfo>) -> Vec<VehicleInfo> {
+    let mut number_to_old: HashMap<VehicleNumber, VehicleInfo> = old_vehicles.into_iter()
+        .map(|v| (v.number.clone(), v))
+        .collect();
+
+    let mut merged: Vec<VehicleInfo> = Vec::new();
+    for new_vehicle in new_vehicles {
+        match number_to_old.remove(&new_vehicle.number) {
+            Some(old_vehicle) => merged.push(merge_vehicle(old_vehicle, new_vehicle)),
+            None => merged.push(new_vehicle),
+        }
+    }
+
+    // whatever is left in number_to_old no longer appears on the scraped page; keep it anyway
+    merged.extend(number_to_old.into_values());
+
+    merged
+}
+
+
 #[tokio::main]
 async fn main() {
     // load config
-    let config: Config = {
-        let config_path = match args_os().nth(1) {
-            Some(cp) => PathBuf::from(cp),
-            None => PathBuf::from("obtain_bim_sl.json"),
-        };
+    let mut cli_merge = false;
+    let mut config_path: Option<PathBuf> = None;
+    for arg in args_os().skip(1) {
+        if arg == "--merge" {
+            cli_merge = true;
+        } else {
+            config_path = Some(PathBuf::from(arg));
+        }
+    }
+
+    let mut config: Config = {
+        let config_path = config_path
+            .unwrap_or_else(|| PathBuf::from("obtain_bim_sl.json"));
         let f = File::open(config_path)
             .expect("failed to open config file");
         serde_json::from_reader(f)
             .expect("failed to parse config file")
     };
+    config.merge = config.merge || cli_merge;
 
-    let class_data_table_sel = Selector::parse("div#classdata > table")
-        .expect("failed to parse class-data-table selector");
-    let class_data_row_sel = Selector::parse("tr")
-        .expect("failed to parse class-data-row selector");
-    let table_sel = Selector::parse("table#ClassMembersTable")
-        .expect("failed to parse vehicle-table selector");
-    let header_field_sel = Selector::parse("thead th")
-        .expect("failed to parse header field selector");
-    let vehicle_row_sel = Selector::parse("tbody tr")
-        .expect("failed to parse data row selector");
-    let td_sel = Selector::parse("td")
-        .expect("failed to parse td selector");
+    let old_vehicles = if config.merge {
+        load_existing_vehicles(&config.output_path)
+    } else {
+        Vec::new()
+    };
+
+    let http_client = reqwest::Client::new();
 
     let mut vehicles: Vec<VehicleInfo> = Vec::new();
     for page_info in &config.pages {
         eprintln!("fetching {}", page_info.url);
 
-        let page_bytes = obtain_page_bytes(&page_info.url).await;
+        let class_data_table_sel = Selector::parse(&page_info.selectors.class_data_table)
+            .expect("failed to parse class-data-table selector");
+        let class_data_row_sel = Selector::parse(&page_info.selectors.class_data_row)
+            .expect("failed to parse class-data-row selector");
+        let table_sel = Selector::parse(&page_info.selectors.vehicle_table)
+            .expect("failed to parse vehicle-table selector");
+        let header_field_sel = Selector::parse(&page_info.selectors.header_field)
+            .expect("failed to parse header field selector");
+        let vehicle_row_sel = Selector::parse(&page_info.selectors.vehicle_row)
+            .expect("failed to parse data row selector");
+        let td_sel = Selector::parse(&page_info.selectors.cell)
+            .expect("failed to parse td selector");
+
+        let page_bytes = obtain_page_bytes(&http_client, &config.fetch, &page_info.url).await;
         let page_string = String::from_utf8(page_bytes)
             .expect("failed to decode page as UTF-8");
         let html = Html::parse_document(&page_string);
@@ -293,53 +592,51 @@ async fn main() {
         let table = html.root_element().select(&table_sel)
             .nth(0).expect("table not found");
 
-        // find the header fields
-        let mut headers: Vec<String> = Vec::new();
+        // find the header fields and map them to builder fields
+        let mut header_targets: Vec<(String, ColumnTarget)> = Vec::new();
         for header_field in table.select(&header_field_sel) {
-            headers.push(trimmed_text_to_string(header_field.text()));
+            let header = trimmed_text_to_string(header_field.text());
+            let target = ColumnTarget::for_header(&header, &page_info.column_map);
+            header_targets.push((header, target));
         }
 
         // find the vehicles
         for vehicle_row in table.select(&vehicle_row_sel) {
-            let mut kvps: HashMap<String, String> = HashMap::new();
-            for (header, data_field) in headers.iter().zip(vehicle_row.select(&td_sel)) {
+            let mut raw_number: Option<String> = None;
+            let mut formation_numbers: Vec<String> = Vec::new();
+            let mut in_service_since: Option<String> = None;
+            let mut out_of_service_since: Option<String> = None;
+            let mut manufacturer: Option<String> = None;
+            let mut vehicle_props: BTreeMap<String, String> = common_props.clone();
+
+            for ((_header, target), data_field) in header_targets.iter().zip(vehicle_row.select(&td_sel)) {
                 let data_string = trimmed_text_to_string(data_field.text());
-                if data_string.len() > 0 {
-                    kvps.insert(header.clone(), data_string);
+                if data_string.len() == 0 {
+                    continue;
                 }
-            }
 
-            // do we have a formation?
-            let mut numbers: Vec<String> = Vec::new();
-            if let Some(formation) = kvps.get("Formation") {
-                if formation.len() > 0 {
-                    numbers.extend(
-                        formation.split(',')
+                match target {
+                    ColumnTarget::Number => raw_number = Some(data_string),
+                    ColumnTarget::FixedCoupling => {
+                        formation_numbers = data_string.split(',')
                             .map(|n| n.trim().to_owned())
-                    );
-                }
-            }
-
-            if numbers.len() == 0 {
-                // no; get the "raw" number
-                if let Some(num) = kvps.get("Number") {
-                    numbers.push(num.trim().to_owned());
+                            .collect();
+                    },
+                    ColumnTarget::InServiceSince => in_service_since = Some(data_string),
+                    ColumnTarget::OutOfServiceSince => out_of_service_since = Some(data_string),
+                    ColumnTarget::Manufacturer => manufacturer = Some(data_string),
+                    ColumnTarget::Other(key) => { vehicle_props.insert(key.clone(), data_string); },
                 }
             }
 
-            // collect all properties
-            let mut vehicle_props = BTreeMap::new();
-            vehicle_props.extend(
-                common_props.iter()
-                    .map(|(k, v)| (k.clone(), v.clone()))
-            );
-            vehicle_props.extend(
-                kvps.iter()
-                    .filter(|(k, _v)| *k != "Formation" && *k != "Number")
-                    .map(|(k, v)| (k.clone(), v.clone()))
-            );
-
-            let builder_opt = vehicle_props.remove("Builder");
+            // prefer the formation (fixed-coupling) numbers; fall back to the "raw" number
+            let numbers: Vec<String> = if formation_numbers.len() > 0 {
+                formation_numbers
+            } else if let Some(num) = raw_number {
+                vec![num]
+            } else {
+                Vec::new()
+            };
 
             // insert
             for number in &numbers {
@@ -351,8 +648,14 @@ async fn main() {
                 if numbers.len() > 1 {
                     vehicle.fixed_coupling(numbers.iter().map(|n| n.clone().into()));
                 }
-                if let Some(builder) = &builder_opt {
-                    vehicle.manufacturer(builder);
+                if let Some(since) = &in_service_since {
+                    vehicle.in_service_since(since);
+                }
+                if let Some(since) = &out_of_service_since {
+                    vehicle.out_of_service_since(since);
+                }
+                if let Some(manuf) = &manufacturer {
+                    vehicle.manufacturer(manuf);
                 }
 
                 for (k, v) in &vehicle_props {
@@ -394,6 +697,11 @@ async fn main() {
         }
     }
 
+    if config.merge {
+        vehicles = merge_old_and_new(old_vehicles, vehicles);
+        vehicles.sort_unstable_by_key(|v| v.number.clone());
+    }
+
     // output
     {
         let f = File::create(config.output_path)
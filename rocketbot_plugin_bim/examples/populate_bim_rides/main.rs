@@ -74,6 +74,7 @@ async fn main() {
         increment_rides_by_spec(
             &mut db_client,
             bim_database_opt.as_ref(),
+            None,
             company,
             &placeholder_company,
             &message.username,
@@ -35,3 +35,45 @@ pub(crate) async fn recalculate_achievements(db_conn: &tokio_postgres::Client) -
     db_conn.execute("CALL bim.refresh_achievements()", &[]).await?;
     Ok(())
 }
+
+
+/// One rider's position in the unlock order of a single achievement, as computed by
+/// [`achievement_unlock_ranks`].
+pub(crate) struct AchievementUnlock {
+    pub rank: i64,
+    pub rider_username: String,
+    pub achieved_on: DateTime<Local>,
+}
+
+/// Groups every unlocked `bim.rider_achievements` row by achievement ID, numbering each
+/// achievement's unlockers by unlock order (1st, 2nd, ...) via a
+/// `ROW_NUMBER() OVER (PARTITION BY achievement_id ORDER BY achieved_on)` window function. Used by
+/// `bimachievers` to show who unlocked an achievement (and in what order), as well as how rare it
+/// is relative to the others.
+pub(crate) async fn achievement_unlock_ranks(db_conn: &tokio_postgres::Client) -> Result<BTreeMap<i64, Vec<AchievementUnlock>>, tokio_postgres::Error> {
+    let rows = db_conn.query(
+        "
+            SELECT
+                ra.achievement_id, ra.rider_username, ra.achieved_on,
+                ROW_NUMBER() OVER (PARTITION BY ra.achievement_id ORDER BY ra.achieved_on)
+            FROM bim.rider_achievements ra
+            WHERE ra.achieved_on IS NOT NULL
+            ORDER BY ra.achievement_id, 4
+        ",
+        &[],
+    ).await?;
+
+    let mut ach_id_to_unlocks: BTreeMap<i64, Vec<AchievementUnlock>> = BTreeMap::new();
+    for row in rows {
+        let achievement_id: i64 = row.get(0);
+        let rider_username: String = row.get(1);
+        let achieved_on: DateTime<Local> = row.get(2);
+        let rank: i64 = row.get(3);
+
+        ach_id_to_unlocks
+            .entry(achievement_id)
+            .or_insert_with(Vec::new)
+            .push(AchievementUnlock { rank, rider_username, achieved_on });
+    }
+    Ok(ach_id_to_unlocks)
+}
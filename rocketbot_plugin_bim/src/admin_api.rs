@@ -0,0 +1,1420 @@
+use std::collections::{hash_map, HashMap};
+use std::convert::Infallible;
+use std::sync::Weak;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Local};
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, Response, StatusCode};
+use hyper::body::{Bytes, Incoming};
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use rocketbot_bim_common::{CouplingMode, VehicleNumber};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_postgres::types::ToSql;
+use tracing::{error, info};
+
+use crate::{
+    bim_type_stats_for_company, Config, connect_ride_db, enqueue_achievement_update,
+    fixed_coupling_monopolies, lookback_range_from_query_params, rider_cost_savings, RwLock,
+    spec_to_vehicles, StatsFilter, top_ridden_vehicle_counts, top_rider_ride_and_vehicle_counts,
+    UpdateAchievementsData,
+};
+use crate::clocks::SystemClocks;
+use crate::query_result::{BimQueryRide, BimQueryResult, serialize_bim_query_result};
+use crate::ride_store::{PostgresRideStore, RideStore, RideUpdate, StoredRide, StoredRideVehicle, VehicleAudience, RiderVehicleCount};
+
+
+/// Binds `listen_address` and serves a JSON admin API mirroring the chat ride-modification and
+/// read-only vehicle-listing commands, authenticated via `Authorization: Bearer <token>` where
+/// `token` is looked up in `admin_api_tokens` and the resulting username must be in
+/// `admin_usernames`. `achievement_update_sender` is used to enqueue achievement recalculations
+/// after a ride is modified or deleted, exactly as the chat commands do.
+pub(crate) async fn serve_admin_api(
+    listen_address: String,
+    config: Weak<RwLock<Config>>,
+    achievement_update_sender: mpsc::UnboundedSender<UpdateAchievementsData>,
+) {
+    let listener = match TcpListener::bind(&listen_address).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("failed to bind bim admin API listener on {}: {}", listen_address, e);
+            return;
+        },
+    };
+
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Ok(sa) => sa,
+            Err(e) => {
+                error!("failed to accept bim admin API connection: {}", e);
+                continue;
+            },
+        };
+
+        let config = Weak::clone(&config);
+        let achievement_update_sender = achievement_update_sender.clone();
+        tokio::spawn(async move {
+            let io = TokioIo::new(stream);
+            let service = service_fn(move |req| {
+                let config = Weak::clone(&config);
+                let achievement_update_sender = achievement_update_sender.clone();
+                async move { handle_admin_api_request(req, config, achievement_update_sender).await }
+            });
+            let serve_result = Builder::new(TokioExecutor::new())
+                .http1()
+                .serve_connection(io, service)
+                .await;
+            if let Err(e) = serve_result {
+                error!("error serving bim admin API connection from {}: {}", remote_addr, e);
+            }
+        });
+    }
+}
+
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+fn json_response<S: Serialize>(status: StatusCode, body: &S) -> Response<Full<Bytes>> {
+    let json_string = serde_json::to_string(body)
+        .expect("failed to serialize bim admin API response");
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .body(Full::new(Bytes::from(json_string)))
+        .expect("failed to assemble bim admin API response")
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    json_response(status, &ErrorBody { error: message.to_owned() })
+}
+
+
+/// Extracts the bearer token from the `Authorization` header and resolves it to a username via
+/// `admin_api_tokens`, failing unless that username is also listed in `admin_usernames`.
+fn authenticate(request: &Request<Incoming>, config: &Config) -> Result<String, Response<Full<Bytes>>> {
+    let header_value = request.headers().get(hyper::header::AUTHORIZATION)
+        .and_then(|hv| hv.to_str().ok());
+    let token = match header_value.and_then(|hv| hv.strip_prefix("Bearer ")) {
+        Some(t) => t,
+        None => return Err(error_response(StatusCode::UNAUTHORIZED, "missing or malformed Authorization header")),
+    };
+
+    let username = match config.admin_api_tokens.get(token) {
+        Some(u) => u,
+        None => return Err(error_response(StatusCode::UNAUTHORIZED, "unknown API token")),
+    };
+    if !config.admin_usernames.contains(username) {
+        return Err(error_response(StatusCode::FORBIDDEN, "token is not mapped to a bim admin"));
+    }
+
+    Ok(username.clone())
+}
+
+
+async fn handle_admin_api_request(
+    request: Request<Incoming>,
+    config: Weak<RwLock<Config>>,
+    achievement_update_sender: mpsc::UnboundedSender<UpdateAchievementsData>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let config_lock = match Weak::upgrade(&config) {
+        Some(cl) => cl,
+        None => return Ok(error_response(StatusCode::SERVICE_UNAVAILABLE, "bim plugin is shutting down")),
+    };
+    let config_guard = config_lock.read().await;
+
+    let username = match authenticate(&request, &config_guard) {
+        Ok(u) => u,
+        Err(response) => return Ok(response),
+    };
+
+    let path_segments: Vec<&str> = request.uri().path()
+        .trim_matches('/')
+        .split('/')
+        .collect();
+
+    let response = match (request.method(), path_segments.as_slice()) {
+        (&Method::GET, ["rides", "recent"]) => handle_get_recent_rides(&config_guard, request.uri().query()).await,
+        (&Method::GET, ["rides", id_str]) => handle_get_ride(&config_guard, id_str).await,
+        (&Method::PATCH, ["rides", id_str]) => handle_patch_ride(&config_guard, &username, id_str, request, &achievement_update_sender).await,
+        (&Method::DELETE, ["rides", id_str]) => handle_delete_ride(&config_guard, &username, id_str, &achievement_update_sender).await,
+        (&Method::POST, ["rides", "freshen"]) => handle_post_freshen_rides(&config_guard, &username, request, &achievement_update_sender).await,
+        (&Method::POST, ["rides", "batch"]) => handle_post_batch_rides(&config_guard, &username, request, &achievement_update_sender).await,
+        (&Method::POST, ["rides", id_str, "vehicles"]) => handle_post_ride_vehicles(&config_guard, &username, id_str, request, &achievement_update_sender).await,
+        (&Method::GET, ["vehicles", "widest"]) => handle_get_widest_vehicles(&config_guard, request.uri().query()).await,
+        (&Method::GET, ["vehicles", "last"]) => handle_get_last_or_lone_vehicles(&config_guard, request.uri().query(), false).await,
+        (&Method::GET, ["vehicles", "lone"]) => handle_get_last_or_lone_vehicles(&config_guard, request.uri().query(), true).await,
+        (&Method::GET, ["vehicles", "top"]) => handle_get_top_vehicles(&config_guard, request.uri().query()).await,
+        (&Method::GET, ["riders", "top"]) => handle_get_top_riders(&config_guard, request.uri().query()).await,
+        (&Method::GET, ["riders", "cost-savings"]) => handle_get_rider_cost_savings(&config_guard, request.uri().query()).await,
+        (&Method::GET, ["vehicle-types"]) => handle_get_vehicle_types(&config_guard, request.uri().query()).await,
+        (&Method::GET, ["fixed-coupling-monopolies"]) => handle_get_fixed_coupling_monopolies(&config_guard, request.uri().query()).await,
+        _ => error_response(StatusCode::NOT_FOUND, "no such endpoint"),
+    };
+    Ok(response)
+}
+
+fn parse_query_params(query: Option<&str>) -> HashMap<String, String> {
+    match query {
+        Some(q) => form_urlencoded::parse(q.as_bytes())
+            .into_owned()
+            .collect(),
+        None => HashMap::new(),
+    }
+}
+
+fn parse_ride_id(id_str: &str) -> Result<i64, Response<Full<Bytes>>> {
+    id_str.parse()
+        .map_err(|_| error_response(StatusCode::BAD_REQUEST, "ride ID must be an integer"))
+}
+
+
+#[derive(Serialize)]
+struct RideVehicleJson {
+    vehicle_number: VehicleNumber,
+    vehicle_type: Option<String>,
+    spec_position: i64,
+    coupling_mode: CouplingMode,
+    fixed_coupling_position: i64,
+}
+impl From<StoredRideVehicle> for RideVehicleJson {
+    fn from(v: StoredRideVehicle) -> Self {
+        Self {
+            vehicle_number: v.vehicle_number,
+            vehicle_type: v.vehicle_type,
+            spec_position: v.spec_position,
+            coupling_mode: v.coupling_mode,
+            fixed_coupling_position: v.fixed_coupling_position,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RideJson {
+    id: i64,
+    company: String,
+    rider_username: String,
+    line: Option<String>,
+    timestamp: DateTime<Local>,
+    regular_price: Option<String>,
+    actual_price: Option<String>,
+    vehicles: Vec<RideVehicleJson>,
+}
+impl From<StoredRide> for RideJson {
+    fn from(r: StoredRide) -> Self {
+        Self {
+            id: r.id,
+            company: r.company,
+            rider_username: r.rider_username,
+            line: r.line,
+            timestamp: r.timestamp,
+            regular_price: r.regular_price,
+            actual_price: r.actual_price,
+            vehicles: r.vehicles.into_iter().map(RideVehicleJson::from).collect(),
+        }
+    }
+}
+
+async fn handle_get_ride(config: &Config, id_str: &str) -> Response<Full<Bytes>> {
+    let ride_id = match parse_ride_id(id_str) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let store = PostgresRideStore::new(ride_conn);
+
+    match store.find_ride(ride_id).await {
+        Ok(Some(ride)) => json_response(StatusCode::OK, &RideJson::from(ride)),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "ride not found"),
+        Err(e) => {
+            error!("failed to query ride {} for admin API: {}", ride_id, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to query ride")
+        },
+    }
+}
+
+
+/// Enqueues an achievement recalculation after a ride is modified or deleted through the admin API,
+/// announcing into `admin_api_announce_channel` exactly as the chat commands announce into the
+/// channel the triggering command was sent in. An HTTP request has no such channel of its own, so
+/// if `admin_api_announce_channel` is unset, the recalculation is skipped entirely rather than
+/// guessing a channel to announce into.
+fn enqueue_admin_api_achievement_update(config: &Config, achievement_update_sender: &mpsc::UnboundedSender<UpdateAchievementsData>) {
+    if !config.achievements_active {
+        return;
+    }
+    let channel = match &config.admin_api_announce_channel {
+        Some(c) => c.clone(),
+        None => return,
+    };
+
+    let data = UpdateAchievementsData {
+        channel,
+        explicit: false,
+    };
+    enqueue_achievement_update(achievement_update_sender, data);
+}
+
+
+#[derive(Deserialize, Default)]
+struct RidePatchRequest {
+    rider_username: Option<String>,
+    company: Option<String>,
+    line: Option<String>,
+    timestamp: Option<DateTime<Local>>,
+    regular_price: Option<String>,
+    actual_price: Option<String>,
+    vehicles: Option<String>,
+}
+
+async fn handle_patch_ride(
+    config: &Config,
+    acting_username: &str,
+    id_str: &str,
+    request: Request<Incoming>,
+    achievement_update_sender: &mpsc::UnboundedSender<UpdateAchievementsData>,
+) -> Response<Full<Bytes>> {
+    let ride_id = match parse_ride_id(id_str) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let body_bytes = match request.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            error!("failed to read bim admin API request body: {}", e);
+            return error_response(StatusCode::BAD_REQUEST, "failed to read request body");
+        },
+    };
+    let patch: RidePatchRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(p) => p,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse JSON body: {}", e)),
+    };
+
+    if let Some(nc) = &patch.company {
+        if !config.company_to_definition.contains_key(nc) {
+            return error_response(StatusCode::BAD_REQUEST, "that company does not exist");
+        }
+    }
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => {
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection");
+        },
+    };
+    let store = PostgresRideStore::new(ride_conn);
+
+    let existing_ride = match store.find_ride(ride_id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "ride not found"),
+        Err(e) => {
+            error!("failed to look up ride {} for bim admin API: {}", ride_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to look up ride");
+        },
+    };
+
+    let update = RideUpdate {
+        rider_username: patch.rider_username.clone(),
+        company: patch.company.clone(),
+        line: patch.line.clone(),
+        timestamp: patch.timestamp,
+        regular_price: patch.regular_price.clone(),
+        actual_price: patch.actual_price.clone(),
+    };
+    if update.is_empty() && patch.vehicles.is_none() {
+        return error_response(StatusCode::BAD_REQUEST, "nothing to change");
+    }
+
+    if !update.is_empty() {
+        if let Err(e) = store.update_ride(ride_id, &update).await {
+            error!("failed to modify ride {} via bim admin API: {}", ride_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to modify ride");
+        }
+    }
+
+    if let Some(vehicles_spec) = &patch.vehicles {
+        let this_company = patch.company.as_deref().unwrap_or(existing_ride.company.as_str());
+        let bim_database_opt = crate::load_bim_database(config, this_company);
+        let vehicles = match spec_to_vehicles(vehicles_spec, bim_database_opt.as_ref(), config.allow_fixed_coupling_combos) {
+            Ok(v) => v,
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse vehicles: {}", e));
+            },
+        };
+        if let Err(e) = store.replace_vehicles(ride_id, &vehicles).await {
+            error!("failed to replace vehicles of ride {} via bim admin API: {}", ride_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to replace vehicles");
+        }
+    }
+
+    enqueue_admin_api_achievement_update(config, achievement_update_sender);
+
+    info!("bim admin API: ride {} patched by {}", ride_id, acting_username);
+
+    match store.find_ride(ride_id).await {
+        Ok(Some(ride)) => json_response(StatusCode::OK, &RideJson::from(ride)),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "ride not found"),
+        Err(e) => {
+            error!("failed to re-read ride {} after bim admin API modification: {}", ride_id, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "modified ride but failed to read it back")
+        },
+    }
+}
+
+
+#[derive(Serialize)]
+struct DeleteRideResultJson {
+    id: i64,
+    deleted: bool,
+}
+
+async fn handle_delete_ride(
+    config: &Config,
+    acting_username: &str,
+    id_str: &str,
+    achievement_update_sender: &mpsc::UnboundedSender<UpdateAchievementsData>,
+) -> Response<Full<Bytes>> {
+    let ride_id = match parse_ride_id(id_str) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let store = PostgresRideStore::new(ride_conn);
+
+    let deleted = match store.delete_ride(ride_id).await {
+        Ok(d) => d,
+        Err(e) => {
+            error!("failed to delete ride {} via bim admin API: {}", ride_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to delete ride");
+        },
+    };
+    if !deleted {
+        return error_response(StatusCode::NOT_FOUND, "ride not found");
+    }
+
+    enqueue_admin_api_achievement_update(config, achievement_update_sender);
+
+    info!("bim admin API: ride {} deleted by {}", ride_id, acting_username);
+
+    json_response(StatusCode::OK, &DeleteRideResultJson { id: ride_id, deleted: true })
+}
+
+
+#[derive(Serialize)]
+struct WidestVehicleJson {
+    company: String,
+    vehicle_number: VehicleNumber,
+    rider_count: i64,
+}
+impl From<VehicleAudience> for WidestVehicleJson {
+    fn from(v: VehicleAudience) -> Self {
+        Self {
+            company: v.company,
+            vehicle_number: v.vehicle_number,
+            rider_count: v.rider_count,
+        }
+    }
+}
+
+async fn handle_get_widest_vehicles(config: &Config, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let lookback_range = match lookback_range_from_query_params(&params, &SystemClocks) {
+        Some(lr) => lr,
+        None => return error_response(StatusCode::BAD_REQUEST, "conflicting lookback-range parameters"),
+    };
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let store = PostgresRideStore::new(ride_conn);
+
+    let audiences = match store.widest_audience_vehicles(lookback_range).await {
+        Ok(a) => a,
+        Err(e) => {
+            error!("failed to obtain widest-audience vehicles for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to obtain widest-audience vehicles");
+        },
+    };
+
+    let vehicles: Vec<WidestVehicleJson> = audiences.into_iter().map(WidestVehicleJson::from).collect();
+    json_response(StatusCode::OK, &vehicles)
+}
+
+
+#[derive(Serialize)]
+struct RiderVehicleCountJson {
+    rider_username: String,
+    vehicle_count: i64,
+}
+impl From<RiderVehicleCount> for RiderVehicleCountJson {
+    fn from(r: RiderVehicleCount) -> Self {
+        Self {
+            rider_username: r.rider_username,
+            vehicle_count: r.vehicle_count,
+        }
+    }
+}
+
+/// Backs both `/vehicles/last` (`lone = false`, mirroring `channel_command_lastbims`) and
+/// `/vehicles/lone` (`lone = true`, mirroring `channel_command_lonebims`). Unlike their chat
+/// counterparts, both accept the same lookback-range parameters as `/vehicles/widest`, restricting
+/// which rides are considered when determining the last (or only) rider of each vehicle.
+async fn handle_get_last_or_lone_vehicles(config: &Config, query: Option<&str>, lone: bool) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let lookback_range = match lookback_range_from_query_params(&params, &SystemClocks) {
+        Some(lr) => lr,
+        None => return error_response(StatusCode::BAD_REQUEST, "conflicting lookback-range parameters"),
+    };
+    let company_opt = params.get("company").or_else(|| params.get("c")).map(|s| s.as_str());
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let store = PostgresRideStore::new(ride_conn);
+
+    let counts_res = if lone {
+        store.lone_rider_counts(lookback_range, company_opt).await
+    } else {
+        store.last_rider_counts(lookback_range, company_opt).await
+    };
+    let counts = match counts_res {
+        Ok(c) => c,
+        Err(e) => {
+            error!("failed to obtain {} vehicles for bim admin API: {}", if lone { "lone" } else { "last" }, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to obtain vehicles");
+        },
+    };
+
+    let riders: Vec<RiderVehicleCountJson> = counts.into_iter().map(RiderVehicleCountJson::from).collect();
+    json_response(StatusCode::OK, &riders)
+}
+
+
+/// Wraps an already-versioned [`BimQueryResult`] envelope (see [`serialize_bim_query_result`]) in an
+/// HTTP response, reusing the exact same wire format as the `-j`/`--json` chat flag instead of
+/// re-serializing the result through [`json_response`]'s bare-body convention.
+fn bim_query_result_response(result: &BimQueryResult) -> Response<Full<Bytes>> {
+    let body = serialize_bim_query_result(result);
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json; charset=utf-8")
+        .body(Full::new(Bytes::from(body)))
+        .expect("failed to assemble bim admin API response")
+}
+
+/// Mirrors `channel_command_recentbimrides`'s query (rides in the last day, optionally restricted to
+/// one rider), returning the rows as a [`BimQueryResult::Rides`] instead of a chat-formatted table.
+/// Unlike the chat command, `rider` is taken verbatim rather than resolved through the chat
+/// interface's username resolution, since the admin API has no chat interface to resolve against.
+async fn handle_get_recent_rides(config: &Config, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let rider_username_opt = params.get("rider").cloned();
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+
+    let mut query_values: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(1);
+    let query_addendum = if let Some(rider_username) = &rider_username_opt {
+        query_values.push(rider_username);
+        "AND rider_username = $1"
+    } else {
+        ""
+    };
+    let ride_rows_res = ride_conn.query(
+        &format!(
+            "
+                SELECT
+                    rarv1.\"timestamp\", rarv1.rider_username, rarv1.line, rarv1.vehicle_number,
+                    rarv1.id, rarv2.rider_username taken_from_rider
+                FROM
+                    bim.rides_and_ridden_vehicles rarv1
+                    LEFT OUTER JOIN bim.rides_and_ridden_vehicles rarv2
+                        ON rarv2.vehicle_number = rarv1.vehicle_number
+                        AND rarv2.company = rarv1.company
+                        AND rarv2.\"timestamp\" < rarv1.\"timestamp\"
+                        AND NOT EXISTS (
+                            -- rarv2 must be the directly preceding ride in this vehicle,
+                            -- i.e. there is no other ride rarv3 in between
+                            SELECT 1
+                            FROM bim.rides_and_ridden_vehicles rarv3
+                            WHERE rarv3.vehicle_number = rarv2.vehicle_number
+                            AND rarv3.company = rarv2.company
+                            AND rarv3.\"timestamp\" < rarv1.\"timestamp\"
+                            AND rarv3.\"timestamp\" > rarv2.\"timestamp\"
+                        )
+                WHERE
+                    rarv1.\"timestamp\" >= CURRENT_TIMESTAMP - CAST('P1D' AS interval)
+                    {}
+                ORDER BY
+                    rarv1.\"timestamp\", rarv1.id, rarv1.spec_position,
+                    rarv1.fixed_coupling_position
+            ",
+            query_addendum,
+        ),
+        &query_values,
+    ).await;
+    let ride_rows = match ride_rows_res {
+        Ok(rr) => rr,
+        Err(e) => {
+            error!("failed to obtain recent rides for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to obtain recent rides");
+        },
+    };
+
+    let mut id_to_ride: HashMap<i64, (DateTime<Local>, String, Option<String>, String, Option<String>)> = HashMap::with_capacity(ride_rows.len());
+    for ride_row in ride_rows {
+        let timestamp: DateTime<Local> = ride_row.get(0);
+        let rider_username: String = ride_row.get(1);
+        let line: Option<String> = ride_row.get(2);
+        let vehicle_number: String = ride_row.get(3);
+        let ride_id: i64 = ride_row.get(4);
+        let taken_from_rider: Option<String> = ride_row.get(5);
+
+        match id_to_ride.entry(ride_id) {
+            hash_map::Entry::Occupied(mut oe) => {
+                let vehicle_numbers = &mut oe.get_mut().3;
+                if vehicle_numbers.len() > 0 {
+                    vehicle_numbers.push('+');
+                }
+                vehicle_numbers.push_str(&vehicle_number);
+            },
+            hash_map::Entry::Vacant(ve) => {
+                ve.insert((timestamp, rider_username, line, vehicle_number, taken_from_rider));
+            },
+        }
+    }
+
+    let mut rides_sorted: Vec<BimQueryRide> = id_to_ride.into_iter()
+        .map(|(ride_id, (timestamp, rider, line, vehicles, taken_from))| BimQueryRide {
+            ride_id,
+            timestamp,
+            rider,
+            line,
+            vehicles,
+            taken_from,
+        })
+        .collect();
+    rides_sorted.sort_by_key(|ride| (ride.timestamp, ride.ride_id));
+
+    bim_query_result_response(&BimQueryResult::Rides(rides_sorted))
+}
+
+
+#[derive(Deserialize)]
+struct FreshenRidesRequest {
+    ride_ids: Vec<i64>,
+}
+
+#[derive(Serialize)]
+struct FreshenRidesResultJson {
+    refreshed_ride_ids: Vec<i64>,
+}
+
+/// Mirrors `channel_command_bimfreshen`: re-derives each given ride's vehicles from the current
+/// vehicle database (skipping fixed-coupling combinations, which are always taken from the vehicle
+/// database anyway) within a single transaction, so a batch of rides is refreshed atomically.
+async fn handle_post_freshen_rides(
+    config: &Config,
+    acting_username: &str,
+    request: Request<Incoming>,
+    achievement_update_sender: &mpsc::UnboundedSender<UpdateAchievementsData>,
+) -> Response<Full<Bytes>> {
+    let body_bytes = match request.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            error!("failed to read bim admin API request body: {}", e);
+            return error_response(StatusCode::BAD_REQUEST, "failed to read request body");
+        },
+    };
+    let freshen_request: FreshenRidesRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(fr) => fr,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse JSON body: {}", e)),
+    };
+    if freshen_request.ride_ids.len() == 0 {
+        return error_response(StatusCode::BAD_REQUEST, "no ride IDs given");
+    }
+
+    let mut ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let ride_txn = match ride_conn.transaction().await {
+        Ok(txn) => txn,
+        Err(e) => {
+            error!("failed to open bim admin API database transaction: {}", e);
+            crate::metrics::record_failed_transaction();
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database transaction");
+        },
+    };
+
+    let select_ride_res = ride_txn.prepare(
+        "
+            SELECT id, company, vehicle_number, coupling_mode FROM bim.rides_and_vehicles
+            WHERE id = $1
+            AND coupling_mode <> 'F' -- ignore fixed coupling; this will be taken from the vehicle database
+            ORDER BY spec_position
+        "
+    ).await;
+    let select_ride = match select_ride_res {
+        Ok(sr) => sr,
+        Err(e) => {
+            error!("failed to prepare select-ride statement for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to prepare select-ride query");
+        },
+    };
+
+    let mut ride_to_company: HashMap<i64, String> = HashMap::new();
+    let mut ride_to_vehicle_spec: HashMap<i64, String> = HashMap::new();
+    for &ride_id in &freshen_request.ride_ids {
+        let ride_rows = match ride_txn.query(&select_ride, &[&ride_id]).await {
+            Ok(rr) => rr,
+            Err(e) => {
+                error!("failed to query ride {} for bim admin API: {}", ride_id, e);
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to query a ride");
+            },
+        };
+
+        for ride_row in ride_rows {
+            let id: i64 = ride_row.get(0);
+            let company: String = ride_row.get(1);
+            let vehicle_number_str: String = ride_row.get(2);
+            let coupling_mode: String = ride_row.get(3);
+
+            let vehicle_number: VehicleNumber = vehicle_number_str.into();
+
+            assert!(coupling_mode == "R" || coupling_mode == "E");
+            let explicitly_ridden = coupling_mode == "R";
+
+            ride_to_company.insert(id, company);
+            let vehicle_spec = ride_to_vehicle_spec
+                .entry(id)
+                .or_insert_with(|| String::new());
+            if vehicle_spec.len() > 0 {
+                vehicle_spec.push('+');
+            }
+            vehicle_spec.push_str(vehicle_number.as_str());
+            if explicitly_ridden {
+                vehicle_spec.push('!');
+            }
+        }
+    }
+
+    if ride_to_company.len() == 0 {
+        return error_response(StatusCode::NOT_FOUND, "none of the given rides were found");
+    }
+
+    let mut company_to_bim_database: HashMap<String, Option<_>> = HashMap::new();
+    let mut refreshed_ride_ids: Vec<i64> = Vec::new();
+    for (ride_id, company) in &ride_to_company {
+        let vehicle_spec = ride_to_vehicle_spec.get(ride_id)
+            .expect("ride has company but no vehicle spec");
+
+        if !company_to_bim_database.contains_key(company) {
+            let bim_database = crate::load_bim_database(config, company);
+            company_to_bim_database.insert(company.clone(), bim_database);
+        }
+        let bim_database_opt = company_to_bim_database.get(company).unwrap();
+        let vehicles = match spec_to_vehicles(vehicle_spec, bim_database_opt.as_ref(), config.allow_fixed_coupling_combos) {
+            Ok(veh) => veh,
+            Err(e) => {
+                error!("failed to reconstruct vehicles of ride {} from {:?}: {}", ride_id, vehicle_spec, e);
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("failed to reconstruct vehicles of ride {}", ride_id));
+            },
+        };
+        if let Err(e) = crate::replace_ride_vehicles(&ride_txn, *ride_id, &vehicles).await {
+            error!("failed to replace vehicles of ride {} via bim admin API: {}", ride_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("failed to replace vehicles of ride {}", ride_id));
+        }
+        refreshed_ride_ids.push(*ride_id);
+    }
+
+    if let Err(e) = ride_txn.commit().await {
+        error!("failed to commit bim admin API transaction: {}", e);
+        crate::metrics::record_failed_transaction();
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to commit transaction");
+    }
+
+    enqueue_admin_api_achievement_update(config, achievement_update_sender);
+
+    refreshed_ride_ids.sort();
+    info!("bim admin API: rides {:?} freshened by {}", refreshed_ride_ids, acting_username);
+
+    json_response(StatusCode::OK, &FreshenRidesResultJson { refreshed_ride_ids })
+}
+
+
+#[derive(Deserialize)]
+struct RideVehiclesRequest {
+    vehicles: String,
+}
+
+/// Replaces a single ride's vehicles, factoring out the `vehicles`-only half of what `PATCH
+/// /rides/{id}` already does so that a caller which only ever touches vehicles (e.g. a web UI's
+/// "recouple this ride" action) does not have to round-trip the rest of the ride's fields.
+async fn handle_post_ride_vehicles(
+    config: &Config,
+    acting_username: &str,
+    id_str: &str,
+    request: Request<Incoming>,
+    achievement_update_sender: &mpsc::UnboundedSender<UpdateAchievementsData>,
+) -> Response<Full<Bytes>> {
+    let ride_id = match parse_ride_id(id_str) {
+        Ok(id) => id,
+        Err(r) => return r,
+    };
+
+    let body_bytes = match request.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            error!("failed to read bim admin API request body: {}", e);
+            return error_response(StatusCode::BAD_REQUEST, "failed to read request body");
+        },
+    };
+    let vehicles_request: RideVehiclesRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(vr) => vr,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse JSON body: {}", e)),
+    };
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let store = PostgresRideStore::new(ride_conn);
+
+    let existing_ride = match store.find_ride(ride_id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return error_response(StatusCode::NOT_FOUND, "ride not found"),
+        Err(e) => {
+            error!("failed to look up ride {} for bim admin API: {}", ride_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to look up ride");
+        },
+    };
+
+    let bim_database_opt = crate::load_bim_database(config, existing_ride.company.as_str());
+    let vehicles = match spec_to_vehicles(&vehicles_request.vehicles, bim_database_opt.as_ref(), config.allow_fixed_coupling_combos) {
+        Ok(v) => v,
+        Err(e) => {
+            return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse vehicles: {}", e));
+        },
+    };
+    if let Err(e) = store.replace_vehicles(ride_id, &vehicles).await {
+        error!("failed to replace vehicles of ride {} via bim admin API: {}", ride_id, e);
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to replace vehicles");
+    }
+
+    enqueue_admin_api_achievement_update(config, achievement_update_sender);
+
+    info!("bim admin API: vehicles of ride {} replaced by {}", ride_id, acting_username);
+
+    match store.find_ride(ride_id).await {
+        Ok(Some(ride)) => json_response(StatusCode::OK, &RideJson::from(ride)),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, "ride not found"),
+        Err(e) => {
+            error!("failed to re-read ride {} after bim admin API modification: {}", ride_id, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, "modified ride but failed to read it back")
+        },
+    }
+}
+
+
+#[derive(Deserialize)]
+struct BatchRideOperation {
+    ride_id: i64,
+    #[serde(default)]
+    delete: bool,
+    #[serde(default)]
+    freshen: bool,
+    rider_username: Option<String>,
+    company: Option<String>,
+    line: Option<String>,
+    timestamp: Option<DateTime<Local>>,
+    regular_price: Option<String>,
+    actual_price: Option<String>,
+    vehicles: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BatchRidesRequest {
+    operations: Vec<BatchRideOperation>,
+}
+
+#[derive(Serialize)]
+struct BatchRideOutcomeJson {
+    ride_id: i64,
+    ok: bool,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BatchRidesResultJson {
+    committed: bool,
+    outcomes: Vec<BatchRideOutcomeJson>,
+}
+
+/// The HTTP counterpart of `bimbatchedit`: applies a heterogeneous, ordered list of per-ride
+/// operations (edit, freshen or delete) within a single transaction, rolling the whole batch back on
+/// the first failure while still reporting which operation failed and which ones were never
+/// attempted as a result. A single achievement recalculation is enqueued after a successful commit,
+/// never one per operation.
+async fn handle_post_batch_rides(
+    config: &Config,
+    acting_username: &str,
+    request: Request<Incoming>,
+    achievement_update_sender: &mpsc::UnboundedSender<UpdateAchievementsData>,
+) -> Response<Full<Bytes>> {
+    let body_bytes = match request.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            error!("failed to read bim admin API request body: {}", e);
+            return error_response(StatusCode::BAD_REQUEST, "failed to read request body");
+        },
+    };
+    let batch_request: BatchRidesRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(br) => br,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse JSON body: {}", e)),
+    };
+    if batch_request.operations.len() == 0 {
+        return error_response(StatusCode::BAD_REQUEST, "no operations given");
+    }
+
+    for (op_index, op) in batch_request.operations.iter().enumerate() {
+        if let Some(nc) = &op.company {
+            if !config.company_to_definition.contains_key(nc) {
+                return error_response(StatusCode::BAD_REQUEST, &format!("operation {}: company {:?} does not exist", op_index, nc));
+            }
+        }
+        let modifier_set = op.rider_username.is_some()
+            || op.company.is_some()
+            || op.line.is_some()
+            || op.timestamp.is_some()
+            || op.regular_price.is_some()
+            || op.actual_price.is_some()
+            || op.vehicles.is_some()
+            || op.freshen
+        ;
+        if op.delete && modifier_set {
+            return error_response(StatusCode::BAD_REQUEST, &format!("operation {}: cannot delete and change properties at the same time", op_index));
+        }
+        if op.freshen && op.vehicles.is_some() {
+            return error_response(StatusCode::BAD_REQUEST, &format!("operation {}: cannot freshen and specify vehicles at the same time", op_index));
+        }
+        if !op.delete && !modifier_set {
+            return error_response(StatusCode::BAD_REQUEST, &format!("operation {}: nothing to change", op_index));
+        }
+    }
+
+    // resolve prices ahead of the transaction, exactly as channel_command_bimbatchedit does
+    let mut new_prices: HashMap<i64, BigDecimal> = HashMap::new();
+    let mut new_actual_prices: HashMap<i64, BigDecimal> = HashMap::new();
+    for (op_index, op) in batch_request.operations.iter().enumerate() {
+        if let Some(rp) = &op.regular_price {
+            match rp.parse() {
+                Ok(np) => { new_prices.insert(op.ride_id, np); },
+                Err(_) => return error_response(StatusCode::BAD_REQUEST, &format!("operation {}: failed to parse regular price", op_index)),
+            }
+        }
+        if let Some(ap) = &op.actual_price {
+            match ap.parse() {
+                Ok(nap) => { new_actual_prices.insert(op.ride_id, nap); },
+                Err(_) => return error_response(StatusCode::BAD_REQUEST, &format!("operation {}: failed to parse actual price", op_index)),
+            }
+        }
+    }
+
+    let mut ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let ride_txn = match ride_conn.transaction().await {
+        Ok(txn) => txn,
+        Err(e) => {
+            error!("failed to open bim admin API database transaction: {}", e);
+            crate::metrics::record_failed_transaction();
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database transaction");
+        },
+    };
+
+    struct RideToModify { company: String }
+    let mut id_to_ride: HashMap<i64, RideToModify> = HashMap::new();
+    for op in &batch_request.operations {
+        if id_to_ride.contains_key(&op.ride_id) {
+            continue;
+        }
+        let ride_row_opt_res = ride_txn.query_opt(
+            "SELECT company FROM bim.rides WHERE id=$1",
+            &[&op.ride_id],
+        ).await;
+        match ride_row_opt_res {
+            Err(e) => {
+                error!("failed to obtain ride {} for bim admin API batch: {}", op.ride_id, e);
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("failed to obtain ride {}", op.ride_id));
+            },
+            Ok(None) => return error_response(StatusCode::NOT_FOUND, &format!("ride {} not found", op.ride_id)),
+            Ok(Some(r)) => { id_to_ride.insert(op.ride_id, RideToModify { company: r.get(0) }); },
+        }
+    }
+
+    let mut outcomes: Vec<BatchRideOutcomeJson> = Vec::new();
+    let mut failed = false;
+    for op in &batch_request.operations {
+        if failed {
+            outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("not attempted; batch aborted".to_owned()) });
+            continue;
+        }
+
+        let ride = id_to_ride.get(&op.ride_id)
+            .expect("ride looked up for every operation above");
+
+        if op.delete {
+            match ride_txn.execute("DELETE FROM bim.rides WHERE id=$1", &[&op.ride_id]).await {
+                Ok(_) => outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: true, error: None }),
+                Err(e) => {
+                    error!("failed to delete ride {} during bim admin API batch: {}", op.ride_id, e);
+                    outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("failed to delete".to_owned()) });
+                    failed = true;
+                },
+            }
+            continue;
+        }
+
+        let mut props: Vec<String> = Vec::new();
+        let mut values: Vec<&(dyn ToSql + Sync)> = Vec::new();
+        if let Some(nr) = &op.rider_username {
+            props.push(format!("rider_username = ${}", props.len() + 1));
+            values.push(nr);
+        }
+        if let Some(nc) = &op.company {
+            props.push(format!("company = ${}", props.len() + 1));
+            values.push(nc);
+        }
+        if let Some(nl) = &op.line {
+            props.push(format!("line = ${}", props.len() + 1));
+            values.push(nl);
+        }
+        if let Some(nts) = &op.timestamp {
+            props.push(format!("\"timestamp\" = ${}", props.len() + 1));
+            values.push(nts);
+        }
+        if let Some(np) = new_prices.get(&op.ride_id) {
+            props.push(format!("regular_price = TO_NUMBER(${}, {})", props.len() + 1, crate::POSTGRES_MONEY_FORMAT));
+            values.push(np);
+        }
+        if let Some(nap) = new_actual_prices.get(&op.ride_id) {
+            props.push(format!("actual_price = TO_NUMBER(${}, {})", props.len() + 1, crate::POSTGRES_MONEY_FORMAT));
+            values.push(nap);
+        }
+
+        if props.len() > 0 {
+            let query = format!("UPDATE bim.rides SET {} WHERE id = ${}", props.join(", "), props.len() + 1);
+            values.push(&op.ride_id);
+
+            if let Err(e) = ride_txn.execute(&query, &values).await {
+                error!("failed to modify ride {} during bim admin API batch: {}", op.ride_id, e);
+                outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("failed to modify".to_owned()) });
+                failed = true;
+                continue;
+            }
+        }
+
+        if let Some(nvs) = &op.vehicles {
+            let this_company = op.company.as_deref().unwrap_or(ride.company.as_str());
+            let this_bim_db_opt = crate::load_bim_database(config, this_company);
+            let vehicles_res = spec_to_vehicles(nvs, this_bim_db_opt.as_ref(), config.allow_fixed_coupling_combos);
+            let vehicles = match vehicles_res {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("failed to parse vehicles of ride {} during bim admin API batch: {}", op.ride_id, e);
+                    outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("failed to parse vehicles".to_owned()) });
+                    failed = true;
+                    continue;
+                },
+            };
+            if let Err(e) = crate::replace_ride_vehicles(&ride_txn, op.ride_id, &vehicles).await {
+                error!("failed to replace vehicles of ride {} during bim admin API batch: {}", op.ride_id, e);
+                outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("failed to replace vehicles".to_owned()) });
+                failed = true;
+                continue;
+            }
+        } else if op.freshen {
+            let freshen_rows_res = ride_txn.query(
+                "
+                    SELECT vehicle_number, coupling_mode FROM bim.rides_and_vehicles
+                    WHERE id = $1
+                    AND coupling_mode <> 'F'
+                    ORDER BY spec_position
+                ",
+                &[&op.ride_id],
+            ).await;
+            let freshen_rows = match freshen_rows_res {
+                Ok(fr) => fr,
+                Err(e) => {
+                    error!("failed to query vehicles of ride {} to freshen during bim admin API batch: {}", op.ride_id, e);
+                    outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("failed to query vehicles to freshen".to_owned()) });
+                    failed = true;
+                    continue;
+                },
+            };
+
+            let mut vehicle_spec = String::new();
+            for freshen_row in freshen_rows {
+                let vehicle_number_str: String = freshen_row.get(0);
+                let coupling_mode: String = freshen_row.get(1);
+                let vehicle_number: VehicleNumber = vehicle_number_str.into();
+
+                assert!(coupling_mode == "R" || coupling_mode == "E");
+                let explicitly_ridden = coupling_mode == "R";
+
+                if vehicle_spec.len() > 0 {
+                    vehicle_spec.push('+');
+                }
+                vehicle_spec.push_str(vehicle_number.as_str());
+                if explicitly_ridden {
+                    vehicle_spec.push('!');
+                }
+            }
+
+            let this_company = op.company.as_deref().unwrap_or(ride.company.as_str());
+            let this_bim_db_opt = crate::load_bim_database(config, this_company);
+            let vehicles_res = spec_to_vehicles(&vehicle_spec, this_bim_db_opt.as_ref(), config.allow_fixed_coupling_combos);
+            let vehicles = match vehicles_res {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("failed to reconstruct vehicles of ride {} during bim admin API batch: {}", op.ride_id, e);
+                    outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("failed to reconstruct vehicles".to_owned()) });
+                    failed = true;
+                    continue;
+                },
+            };
+            if let Err(e) = crate::replace_ride_vehicles(&ride_txn, op.ride_id, &vehicles).await {
+                error!("failed to replace (freshened) vehicles of ride {} during bim admin API batch: {}", op.ride_id, e);
+                outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: false, error: Some("failed to replace vehicles".to_owned()) });
+                failed = true;
+                continue;
+            }
+        }
+
+        outcomes.push(BatchRideOutcomeJson { ride_id: op.ride_id, ok: true, error: None });
+    }
+
+    let committed = if failed {
+        if let Err(e) = ride_txn.rollback().await {
+            error!("failed to roll back failed bim admin API batch: {}", e);
+        }
+        false
+    } else {
+        if let Err(e) = ride_txn.commit().await {
+            error!("failed to commit bim admin API batch of {} ride(s): {}", outcomes.len(), e);
+            crate::metrics::record_failed_transaction();
+            for outcome in &mut outcomes {
+                if outcome.ok {
+                    outcome.ok = false;
+                    outcome.error = Some("rolled back; failed to commit batch".to_owned());
+                }
+            }
+            false
+        } else {
+            true
+        }
+    };
+
+    if committed {
+        enqueue_admin_api_achievement_update(config, achievement_update_sender);
+    }
+
+    info!("bim admin API: batch of {} ride operation(s) by {} ({})", outcomes.len(), acting_username, if committed { "committed" } else { "rolled back" });
+
+    json_response(StatusCode::OK, &BatchRidesResultJson { committed, outcomes })
+}
+
+
+#[derive(Serialize)]
+struct TopVehicleJson {
+    company: String,
+    vehicle_number: VehicleNumber,
+}
+
+#[derive(Serialize)]
+struct TopVehicleCountJson {
+    ride_count: i64,
+    vehicles: Vec<TopVehicleJson>,
+}
+
+/// Mirrors `channel_command_topbims`: the most-ridden vehicles (optionally restricted to one
+/// company), grouped by their ride count, highest first.
+async fn handle_get_top_vehicles(config: &Config, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let lookback_range = match lookback_range_from_query_params(&params, &SystemClocks) {
+        Some(lr) => lr,
+        None => return error_response(StatusCode::BAD_REQUEST, "conflicting lookback-range parameters"),
+    };
+    let company_opt = params.get("company").or_else(|| params.get("c")).map(|s| s.as_str());
+    if let Some(c) = company_opt {
+        if !config.company_to_definition.contains_key(c) {
+            return error_response(StatusCode::BAD_REQUEST, "unknown company");
+        }
+    }
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+
+    let count_to_vehicles = match top_ridden_vehicle_counts(&ride_conn, company_opt, lookback_range, &SystemClocks).await {
+        Ok(ctv) => ctv,
+        Err(e) => {
+            error!("failed to query most-ridden vehicles for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to query most-ridden vehicles");
+        },
+    };
+
+    let counts: Vec<TopVehicleCountJson> = count_to_vehicles.into_iter()
+        .rev()
+        .map(|(ride_count, vehicles)| TopVehicleCountJson {
+            ride_count,
+            vehicles: vehicles.into_iter()
+                .map(|(company, vehicle_number)| TopVehicleJson { company, vehicle_number: VehicleNumber::from_string(vehicle_number) })
+                .collect(),
+        })
+        .collect();
+    json_response(StatusCode::OK, &counts)
+}
+
+
+#[derive(Serialize)]
+struct TopRiderJson {
+    rider_username: String,
+    ride_count: i64,
+    vehicle_count: i64,
+}
+
+/// Mirrors `channel_command_topriders`: every rider's ride and distinct-vehicle counts matching
+/// `filter` (the same free-text filter syntax `topriders` accepts as its command body), sorted by
+/// ride count descending. Unlike the chat command, the full list is returned; truncating to a
+/// top-N is left to the caller.
+async fn handle_get_top_riders(config: &Config, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let lookback_range = match lookback_range_from_query_params(&params, &SystemClocks) {
+        Some(lr) => lr,
+        None => return error_response(StatusCode::BAD_REQUEST, "conflicting lookback-range parameters"),
+    };
+    let filter_str = params.get("filter").map(|s| s.as_str()).unwrap_or("");
+    let (stats_filter, _leftover_tokens) = match StatsFilter::parse(filter_str) {
+        Ok(v) => v,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse filter: {}", e)),
+    };
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+
+    let rider_counts = match top_rider_ride_and_vehicle_counts(&ride_conn, &stats_filter, lookback_range, &SystemClocks).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to query top riders for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to query top riders");
+        },
+    };
+
+    let riders: Vec<TopRiderJson> = rider_counts.into_iter()
+        .map(|(rider_username, ride_count, vehicle_count)| TopRiderJson { rider_username, ride_count, vehicle_count })
+        .collect();
+    json_response(StatusCode::OK, &riders)
+}
+
+
+#[derive(Serialize)]
+struct BimTypeStatsJson {
+    known_vehicles: usize,
+    active_vehicles: usize,
+    ridden_vehicles: usize,
+}
+impl From<crate::BimTypeStats> for BimTypeStatsJson {
+    fn from(s: crate::BimTypeStats) -> Self {
+        Self {
+            known_vehicles: s.known_vehicles,
+            active_vehicles: s.active_vehicles,
+            ridden_vehicles: s.ridden_vehicles,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VehicleTypeStatsResponseJson {
+    company: String,
+    type_to_stats: HashMap<String, BimTypeStatsJson>,
+    unknown_type_ridden_count: usize,
+}
+
+/// Mirrors `channel_command_bimtypes`: per-type known/active/ridden vehicle counts for `company`
+/// (falling back to `Config::default_company` like the chat command), optionally restricted to
+/// `filter` and/or a single `rider` (taken verbatim, unlike the chat command's username
+/// resolution -- see `handle_get_recent_rides`).
+async fn handle_get_vehicle_types(config: &Config, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let company = params.get("company").or_else(|| params.get("c"))
+        .map(|s| s.as_str())
+        .unwrap_or(config.default_company.as_str());
+    if company.len() == 0 {
+        return error_response(StatusCode::BAD_REQUEST, "no company given and no default company configured");
+    }
+    if !config.company_to_definition.contains_key(company) {
+        return error_response(StatusCode::BAD_REQUEST, "unknown company");
+    }
+    let filter_str = params.get("filter").map(|s| s.as_str()).unwrap_or("");
+    let (stats_filter, _leftover_tokens) = match StatsFilter::parse(filter_str) {
+        Ok(v) => v,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, &format!("failed to parse filter: {}", e)),
+    };
+    let rider_username_opt = params.get("rider").map(|s| s.as_str());
+
+    let database = crate::load_bim_database(config, company).unwrap_or_else(|| HashMap::new());
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+
+    let (type_to_stats, unknown_type_ridden_count) = match bim_type_stats_for_company(
+        &ride_conn, company, &database, &stats_filter, rider_username_opt,
+    ).await {
+        Ok(v) => v,
+        Err(e) => {
+            error!("failed to query vehicle type statistics for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to query vehicle type statistics");
+        },
+    };
+
+    let type_to_stats_json: HashMap<String, BimTypeStatsJson> = type_to_stats.into_iter()
+        .map(|(tp, stats)| (tp, BimTypeStatsJson::from(stats)))
+        .collect();
+    json_response(StatusCode::OK, &VehicleTypeStatsResponseJson {
+        company: company.to_owned(),
+        type_to_stats: type_to_stats_json,
+        unknown_type_ridden_count,
+    })
+}
+
+
+#[derive(Serialize)]
+struct FixedCouplingMonopolyJson {
+    rider_username: String,
+    coupling_length_to_count: HashMap<usize, usize>,
+    total_count: usize,
+}
+
+/// Mirrors `channel_command_bimfixedmonopolies`: which riders currently hold every last ride of a
+/// fixed coupling's constituent vehicles for `company` (falling back to `Config::default_company`
+/// like the chat command), grouped by coupling length.
+async fn handle_get_fixed_coupling_monopolies(config: &Config, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let company = params.get("company").or_else(|| params.get("c"))
+        .map(|s| s.as_str())
+        .unwrap_or(config.default_company.as_str());
+    if company.len() == 0 {
+        return error_response(StatusCode::BAD_REQUEST, "no company given and no default company configured");
+    }
+
+    let database = match crate::load_bim_database(config, company) {
+        Some(db) => db,
+        None => return error_response(StatusCode::BAD_REQUEST, "no vehicle database exists for this company"),
+    };
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+
+    let rider_to_coupling_length_to_count = match fixed_coupling_monopolies(&ride_conn, company, &database).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to compute fixed-coupling monopolies for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to compute fixed-coupling monopolies");
+        },
+    };
+
+    let monopolies: Vec<FixedCouplingMonopolyJson> = rider_to_coupling_length_to_count.into_iter()
+        .map(|(rider_username, coupling_length_to_count)| {
+            let total_count = coupling_length_to_count.values().sum();
+            FixedCouplingMonopolyJson { rider_username, coupling_length_to_count, total_count }
+        })
+        .collect();
+    json_response(StatusCode::OK, &monopolies)
+}
+
+
+#[derive(Serialize)]
+struct CompanySavingsJson {
+    company: String,
+    currency: String,
+    savings: String,
+}
+
+#[derive(Serialize)]
+struct CostSavingsJson {
+    rider_username: String,
+    company_savings: Vec<CompanySavingsJson>,
+}
+
+/// Mirrors `channel_command_bimcost`: a rider's cumulative savings (`regular_price - actual_price`)
+/// within the given lookback range, broken down per company. Unlike the chat command, which
+/// converts everything into a single display currency, this endpoint reports the raw per-company
+/// breakdown (alongside each company's configured currency) and leaves any currency conversion up
+/// to the caller. Unlike the chat command, which always reports on its sender, `rider` is a
+/// required query parameter, taken verbatim rather than resolved through the chat interface's
+/// username resolution (see `handle_get_recent_rides`).
+async fn handle_get_rider_cost_savings(config: &Config, query: Option<&str>) -> Response<Full<Bytes>> {
+    let params = parse_query_params(query);
+    let lookback_range = match lookback_range_from_query_params(&params, &SystemClocks) {
+        Some(lr) => lr,
+        None => return error_response(StatusCode::BAD_REQUEST, "conflicting lookback-range parameters"),
+    };
+    let rider_username = match params.get("rider") {
+        Some(r) => r.clone(),
+        None => return error_response(StatusCode::BAD_REQUEST, "no rider given"),
+    };
+
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(c) => c,
+        Err(_) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to open database connection"),
+    };
+    let ride_db = config.ride_db_backend.ride_db();
+
+    let company_savings = match rider_cost_savings(&ride_conn, &rider_username, lookback_range, ride_db, &SystemClocks).await {
+        Ok(s) => s,
+        Err(e) => {
+            error!("failed to query bim cost savings for bim admin API: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "failed to query cost savings");
+        },
+    };
+
+    let company_savings = company_savings.into_iter()
+        .map(|(company, savings)| {
+            let currency = config.company_to_definition.get(&company)
+                .map(|cd| cd.currency.clone())
+                .unwrap_or_else(|| company.clone());
+            CompanySavingsJson { company, currency, savings: savings.to_string() }
+        })
+        .collect();
+
+    json_response(StatusCode::OK, &CostSavingsJson { rider_username, company_savings })
+}
@@ -0,0 +1,34 @@
+//! Abstracting away the wall clock so that date/time-dependent logic (the night-owl-date rule,
+//! relative timestamp parsing, streak boundaries, ...) can be driven by a fixed instant in tests
+//! instead of the real, ever-advancing system clock.
+
+use chrono::{DateTime, Local};
+
+
+/// A source of the current local time. [`SystemClocks`] is the real implementation used in
+/// production; [`FixedClocks`] returns a pinned instant, letting callers exercise date/time edge
+/// cases (e.g. a 02:30 registration folding into the previous night-owl date) deterministically.
+pub trait Clocks: Send + Sync {
+    /// The current local date and time.
+    fn now(&self) -> DateTime<Local>;
+}
+
+
+/// A [`Clocks`] implementation backed by the real system clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClocks;
+impl Clocks for SystemClocks {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+
+/// A [`Clocks`] implementation that always returns the same, fixed instant.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClocks(pub DateTime<Local>);
+impl Clocks for FixedClocks {
+    fn now(&self) -> DateTime<Local> {
+        self.0
+    }
+}
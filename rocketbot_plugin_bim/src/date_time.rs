@@ -1,6 +1,6 @@
 use std::fmt;
 
-use chrono::{Datelike, DateTime, TimeZone, Weekday};
+use chrono::{Datelike, DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
 
 
 #[inline]
@@ -52,3 +52,254 @@ pub fn canonical_date_format_relative<W: fmt::Write, Tz: TimeZone, Tz2: TimeZone
         canonical_date_format(writer, date_time, on_at, seconds)
     }
 }
+
+
+/// The result of [`parse_natural_datetime`]: either a single resolved instant, or -- when the
+/// input contained an `"until"`/`"till"`/`"to"` separator -- a start/end range.
+#[derive(Clone, Debug)]
+pub enum NaturalDateResult<Tz: TimeZone> {
+    Instant(DateTime<Tz>),
+    Range(DateTime<Tz>, DateTime<Tz>),
+}
+
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DurationUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+impl DurationUnit {
+    fn from_word(word: &str) -> Option<Self> {
+        match word {
+            "s" | "sec" | "secs" | "second" | "seconds" => Some(Self::Second),
+            "m" | "min" | "mins" | "minute" | "minutes" => Some(Self::Minute),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Some(Self::Hour),
+            "d" | "day" | "days" => Some(Self::Day),
+            "w" | "week" | "weeks" => Some(Self::Week),
+            "mo" | "mon" | "month" | "months" => Some(Self::Month),
+            "y" | "yr" | "yrs" | "year" | "years" => Some(Self::Year),
+            _ => None,
+        }
+    }
+
+    /// Approximates months as 30 days and years as 365 days, mirroring
+    /// [`crate::parse_relative_duration`].
+    fn to_duration(self, count: i64) -> Duration {
+        match self {
+            Self::Second => Duration::seconds(count),
+            Self::Minute => Duration::minutes(count),
+            Self::Hour => Duration::hours(count),
+            Self::Day => Duration::days(count),
+            Self::Week => Duration::weeks(count),
+            Self::Month => Duration::days(count * 30),
+            Self::Year => Duration::days(count * 365),
+        }
+    }
+}
+
+fn weekday_from_word(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "weds" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_clock_time(word: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(word, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(word, "%H:%M"))
+        .ok()
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Unit(DurationUnit),
+    Weekday(Weekday),
+    Time(NaiveTime),
+    Date(NaiveDate),
+    /// Any word that did not classify as one of the above; covers both keywords ("in", "next",
+    /// "today", "tomorrow", "tonight", "yesterday", "at", "until"/"till"/"to") and unrecognized
+    /// filler, which is tolerated rather than rejected.
+    Word(String),
+}
+
+/// Splits `input` into [`Token`]s, classifying each whitespace-separated piece in turn. Never
+/// fails outright; pieces that match nothing else become [`Token::Word`].
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for piece in input.to_lowercase().split_whitespace() {
+        let piece = piece.trim_matches(',');
+        if piece.len() == 0 {
+            continue;
+        }
+
+        if let Ok(date) = NaiveDate::parse_from_str(piece, "%Y-%m-%d") {
+            tokens.push(Token::Date(date));
+        } else if let Some(time) = parse_clock_time(piece) {
+            tokens.push(Token::Time(time));
+        } else if let Ok(number) = piece.parse::<i64>() {
+            tokens.push(Token::Number(number));
+        } else if let Some(unit) = DurationUnit::from_word(piece) {
+            tokens.push(Token::Unit(unit));
+        } else if let Some(weekday) = weekday_from_word(piece) {
+            tokens.push(Token::Weekday(weekday));
+        } else {
+            tokens.push(Token::Word(piece.to_owned()));
+        }
+    }
+    tokens
+}
+
+/// Folds a token slice that is known to describe a single instant (no `"until"`/`"till"`/`"to"`
+/// separator) into a resolved [`DateTime`], relative to `now`.
+fn parse_instant<Tz: TimeZone>(tokens: &[Token], now: &DateTime<Tz>) -> Option<DateTime<Tz>>
+        where Tz::Offset: Copy {
+    let today = now.date_naive();
+    let night_owl_today = crate::get_night_owl_date(now);
+
+    let mut explicit_date: Option<NaiveDate> = None;
+    let mut explicit_time: Option<NaiveTime> = None;
+    let mut duration_total = Duration::zero();
+    let mut has_duration = false;
+    let mut has_anchor = false;
+    let mut next_flag = false;
+    let mut same_day_weekday_match = false;
+
+    let mut iter = tokens.iter().peekable();
+    while let Some(token) = iter.next() {
+        match token {
+            Token::Word(w) if w == "next" => {
+                next_flag = true;
+            },
+            Token::Word(w) if w == "today" => {
+                explicit_date = Some(today);
+                has_anchor = true;
+            },
+            Token::Word(w) if w == "tomorrow" => {
+                explicit_date = Some(today + Duration::days(1));
+                has_anchor = true;
+            },
+            Token::Word(w) if w == "yesterday" => {
+                explicit_date = Some(today - Duration::days(1));
+                has_anchor = true;
+            },
+            Token::Word(w) if w == "tonight" => {
+                // use the Night Owl date so that asking "tonight" in the small hours still
+                // resolves to the night that is currently ending, not a day in the future
+                explicit_date = Some(night_owl_today);
+                has_anchor = true;
+            },
+            Token::Number(n) => {
+                let unit = match iter.peek() {
+                    Some(Token::Unit(u)) => *u,
+                    _ => return None,
+                };
+                iter.next();
+                duration_total = duration_total + unit.to_duration(*n);
+                has_duration = true;
+            },
+            Token::Unit(_) => {
+                // a bare unit with no preceding number is malformed
+                return None;
+            },
+            Token::Weekday(wd) => {
+                let days_until = (7 + wd.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64) % 7;
+                let offset = if days_until == 0 {
+                    if next_flag { 7 } else { same_day_weekday_match = true; 0 }
+                } else {
+                    days_until
+                };
+                explicit_date = Some(today + Duration::days(offset));
+                has_anchor = true;
+                next_flag = false;
+            },
+            Token::Time(t) => {
+                explicit_time = Some(*t);
+            },
+            Token::Date(d) => {
+                explicit_date = Some(*d);
+                has_anchor = true;
+            },
+            Token::Word(_) => {
+                // unrecognized filler ("in", "at", "on", ...); tolerated silently
+            },
+        }
+    }
+
+    let result = if has_anchor {
+        let date = explicit_date.unwrap();
+        let time = explicit_time.unwrap_or(NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+        let naive = NaiveDateTime::new(date, time);
+        let mut dt = match now.timezone().from_local_datetime(&naive) {
+            LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt,
+            LocalResult::None => return None,
+        };
+        if has_duration {
+            dt = dt + duration_total;
+        }
+        if same_day_weekday_match && explicit_time.is_some() && dt <= *now {
+            dt = dt + Duration::weeks(1);
+        }
+        dt
+    } else if has_duration {
+        *now + duration_total
+    } else if let Some(time) = explicit_time {
+        let naive = NaiveDateTime::new(today, time);
+        let mut dt = match now.timezone().from_local_datetime(&naive) {
+            LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt,
+            LocalResult::None => return None,
+        };
+        if dt <= *now {
+            dt = dt + Duration::days(1);
+        }
+        dt
+    } else {
+        return None;
+    };
+
+    Some(result)
+}
+
+/// Parses a natural-language date/time expression relative to `now`, e.g. `"in 5 minutes"`,
+/// `"in 2 weeks 3 days"`, `"today"`, `"tomorrow"`, `"tonight"`, `"next tuesday"`,
+/// `"friday at 15:00"`, a bare clock time like `"18:30"` (rolling to the next occurrence if
+/// already past), or an ISO `"YYYY-MM-DD[ HH:MM[:SS]]"` timestamp.
+///
+/// The expression is tokenized, then folded left-to-right, accumulating a relative [`Duration`]
+/// or setting absolute date/time fields as each token is encountered. An `"until"`/`"till"`/
+/// `"to"` separator splits the input into two halves, each parsed independently, yielding a
+/// [`NaturalDateResult::Range`] instead of a single [`NaturalDateResult::Instant`].
+///
+/// Returns `None` if no recognizable expression could be parsed.
+pub fn parse_natural_datetime<Tz: TimeZone>(input: &str, now: &DateTime<Tz>) -> Option<NaturalDateResult<Tz>>
+        where Tz::Offset: Copy {
+    let tokens = tokenize(input);
+    if tokens.len() == 0 {
+        return None;
+    }
+
+    let separator_index = tokens.iter().position(|t| matches!(
+        t,
+        Token::Word(w) if w == "until" || w == "till" || w == "to"
+    ));
+    if let Some(index) = separator_index {
+        let (left, right) = tokens.split_at(index);
+        let start = parse_instant(left, now)?;
+        let end = parse_instant(&right[1..], now)?;
+        return Some(NaturalDateResult::Range(start, end));
+    }
+
+    parse_instant(&tokens, now).map(NaturalDateResult::Instant)
+}
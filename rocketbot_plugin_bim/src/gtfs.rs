@@ -0,0 +1,233 @@
+//! Importing vehicle and line metadata from GTFS feeds.
+//!
+//! A GTFS feed models public transport as `routes` (e.g. "the M20 night bus"), grouped into
+//! `trips` (individual scheduled runs of a route) that visit stops according to `stop_times`.
+//! Riders, however, think in terms of `lines` (e.g. "the 20", regardless of whether it is
+//! currently served by a tram or a replacement bus) and `physical modes` (tram/bus/metro/etc.).
+//! [`GtfsLineDatabase`] relates all three many-to-many: a line may be served by several routes
+//! (route variants, a temporary replacement service, ...), a route has exactly one physical mode,
+//! but since several routes across several lines can share a physical mode, and a line's routes
+//! need not all share the same physical mode, a physical mode generally ends up serving several
+//! lines as well.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use rocketbot_bim_common::VehicleClass;
+
+
+/// A single GTFS `routes.txt` entry that has at least one scheduled, stopping trip, linked to the
+/// canonical line identifier it is filed under and the physical mode it is operated with.
+#[derive(Clone, Debug)]
+struct GtfsRoute {
+    pub canonical_line: String,
+    pub physical_mode: VehicleClass,
+}
+
+
+/// The routes, lines and physical modes known from a company's imported GTFS feed. See the module
+/// documentation for the relationship between these three concepts.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GtfsLineDatabase {
+    routes_by_id: HashMap<String, GtfsRoute>,
+    route_ids_by_line: BTreeMap<String, BTreeSet<String>>,
+}
+impl GtfsLineDatabase {
+    /// Whether `line` (a canonical line identifier, i.e. a GTFS `route_short_name` or, absent
+    /// that, a `route_id`) is known to this feed.
+    pub fn contains_line(&self, line: &str) -> bool {
+        self.route_ids_by_line.contains_key(line)
+    }
+
+    /// The physical modes (tram/bus/metro/etc.) that currently serve `line`, derived from every
+    /// GTFS route filed under that line. Empty if the line is unknown.
+    pub fn physical_modes_for_line(&self, line: &str) -> BTreeSet<VehicleClass> {
+        self.route_ids_by_line.get(line)
+            .into_iter()
+            .flatten()
+            .filter_map(|route_id| self.routes_by_id.get(route_id))
+            .map(|route| route.physical_mode)
+            .collect()
+    }
+}
+
+
+/// Failure modes of [`load_gtfs_line_database`].
+#[derive(Debug)]
+pub(crate) enum GtfsImportError {
+    OpenFile(PathBuf, std::io::Error),
+    Csv(PathBuf, csv::Error),
+}
+impl fmt::Display for GtfsImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OpenFile(path, e) => write!(f, "failed to open {}: {}", path.display(), e),
+            Self::Csv(path, e) => write!(f, "failed to parse {} as CSV: {}", path.display(), e),
+        }
+    }
+}
+impl std::error::Error for GtfsImportError {
+}
+
+
+/// Maps a GTFS `route_type` code (as defined by the GTFS static reference, including the
+/// "extended" route types used by some feeds) to the [`VehicleClass`] that operates it.
+fn gtfs_route_type_to_vehicle_class(route_type: &str) -> Option<VehicleClass> {
+    match route_type {
+        "0" => Some(VehicleClass::Tram), // tram, streetcar, light rail
+        "1" => Some(VehicleClass::Metro), // subway, metro
+        "2" => Some(VehicleClass::RegionalTrain), // rail
+        "3" => Some(VehicleClass::Bus),
+        "4" => Some(VehicleClass::Ship), // ferry
+        "5" => Some(VehicleClass::Tram), // cable tram
+        "6" => Some(VehicleClass::AerialTramway), // aerial lift, suspended cable car
+        "7" => Some(VehicleClass::Funicular),
+        "11" => Some(VehicleClass::Trolleybus),
+        "12" => Some(VehicleClass::Metro), // monorail
+        _ => None,
+    }
+}
+
+/// Parses the kebab-case string a [`VehicleClass`] is displayed as (e.g. `"tram-train"`) back
+/// into the variant, for use by the `physical_modes.txt` override table.
+fn parse_vehicle_class(s: &str) -> Option<VehicleClass> {
+    match s {
+        "tram" => Some(VehicleClass::Tram),
+        "metro" => Some(VehicleClass::Metro),
+        "premetro" => Some(VehicleClass::PreMetro),
+        "bus" => Some(VehicleClass::Bus),
+        "trolleybus" => Some(VehicleClass::Trolleybus),
+        "tram-train" => Some(VehicleClass::TramTrain),
+        "regional-train" => Some(VehicleClass::RegionalTrain),
+        "long-distance-train" => Some(VehicleClass::LongDistanceTrain),
+        "horse-drawn-carriage" => Some(VehicleClass::HorseDrawnCarriage),
+        "funicular" => Some(VehicleClass::Funicular),
+        "aerial-tramway" => Some(VehicleClass::AerialTramway),
+        "j-bar-lift" => Some(VehicleClass::JBarLift),
+        "t-bar-lift" => Some(VehicleClass::TBarLift),
+        "seat-lift" => Some(VehicleClass::SeatLift),
+        "gondola-lift" => Some(VehicleClass::GondolaLift),
+        "seat-and-gondola-lift" => Some(VehicleClass::SeatAndGondolaLift),
+        "ship" => Some(VehicleClass::Ship),
+        "hovercraft" => Some(VehicleClass::Hovercraft),
+        "taxibus" => Some(VehicleClass::Taxibus),
+        _ => None,
+    }
+}
+
+fn read_csv_records(path: &Path) -> Result<Vec<HashMap<String, String>>, GtfsImportError> {
+    let file = File::open(path)
+        .map_err(|e| GtfsImportError::OpenFile(path.to_owned(), e))?;
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(b',')
+        .has_headers(true)
+        .quote(b'"')
+        .quoting(true)
+        .double_quote(true)
+        .escape(None)
+        .from_reader(file);
+
+    let headers: Vec<String> = reader.headers()
+        .map_err(|e| GtfsImportError::Csv(path.to_owned(), e))?
+        .iter()
+        .map(|h| h.to_owned())
+        .collect();
+
+    let mut records = Vec::new();
+    for record_res in reader.records() {
+        let record = record_res
+            .map_err(|e| GtfsImportError::Csv(path.to_owned(), e))?;
+        let map: HashMap<String, String> = headers.iter()
+            .zip(record.iter())
+            .map(|(k, v)| (k.clone(), v.to_owned()))
+            .collect();
+        records.push(map);
+    }
+    Ok(records)
+}
+
+/// Imports a GTFS feed directory (expecting `routes.txt`, `trips.txt` and `stop_times.txt`, plus
+/// an optional `physical_modes.txt` override table) into a [`GtfsLineDatabase`].
+///
+/// A route's canonical line identifier is its `route_short_name` (falling back to its `route_id`
+/// if absent or empty). Its physical mode is derived from its GTFS `route_type`, unless
+/// overridden by a matching row (columns `route_id`, `physical_mode`) in `physical_modes.txt` --
+/// `physical_mode` is spelled the same way [`VehicleClass`] displays itself, e.g. `"tram"` or
+/// `"tram-train"`. A route is only included if `trips.txt` and `stop_times.txt` show that it has
+/// at least one scheduled trip that actually stops somewhere; this keeps routes that exist on
+/// paper only (e.g. planned future lines) out of the set of "known lines" used to validate
+/// `{cpfx}bim` line specifications.
+pub(crate) fn load_gtfs_line_database(feed_dir: &Path) -> Result<GtfsLineDatabase, GtfsImportError> {
+    let routes_records = read_csv_records(&feed_dir.join("routes.txt"))?;
+    let trips_records = read_csv_records(&feed_dir.join("trips.txt"))?;
+    let stop_times_records = read_csv_records(&feed_dir.join("stop_times.txt"))?;
+
+    let physical_modes_path = feed_dir.join("physical_modes.txt");
+    let physical_mode_overrides: HashMap<String, VehicleClass> = if physical_modes_path.is_file() {
+        read_csv_records(&physical_modes_path)?
+            .into_iter()
+            .filter_map(|rec| {
+                let route_id = rec.get("route_id")?.clone();
+                let mode = parse_vehicle_class(rec.get("physical_mode")?)?;
+                Some((route_id, mode))
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut trip_id_to_route_id: HashMap<String, String> = HashMap::new();
+    for trip in &trips_records {
+        let (Some(trip_id), Some(route_id)) = (trip.get("trip_id"), trip.get("route_id")) else { continue };
+        trip_id_to_route_id.insert(trip_id.clone(), route_id.clone());
+    }
+
+    let mut stopping_trip_ids: HashSet<&str> = HashSet::new();
+    for stop_time in &stop_times_records {
+        if let Some(trip_id) = stop_time.get("trip_id") {
+            stopping_trip_ids.insert(trip_id.as_str());
+        }
+    }
+
+    let mut route_ids_with_scheduled_stops: HashSet<&str> = HashSet::new();
+    for (trip_id, route_id) in &trip_id_to_route_id {
+        if stopping_trip_ids.contains(trip_id.as_str()) {
+            route_ids_with_scheduled_stops.insert(route_id.as_str());
+        }
+    }
+
+    let mut routes_by_id = HashMap::new();
+    let mut route_ids_by_line: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for route in &routes_records {
+        let Some(route_id) = route.get("route_id") else { continue };
+        if !route_ids_with_scheduled_stops.contains(route_id.as_str()) {
+            // no scheduled, stopping trip -- not a "real" known line
+            continue;
+        }
+
+        let physical_mode = if let Some(mode) = physical_mode_overrides.get(route_id) {
+            *mode
+        } else {
+            let route_type = route.get("route_type").map(|s| s.as_str()).unwrap_or("");
+            match gtfs_route_type_to_vehicle_class(route_type) {
+                Some(vc) => vc,
+                None => continue, // unknown/unsupported physical mode; skip this route
+            }
+        };
+
+        let canonical_line = route.get("route_short_name")
+            .filter(|s| s.len() > 0)
+            .cloned()
+            .unwrap_or_else(|| route_id.clone());
+
+        route_ids_by_line
+            .entry(canonical_line.clone())
+            .or_insert_with(BTreeSet::new)
+            .insert(route_id.clone());
+        routes_by_id.insert(route_id.clone(), GtfsRoute { canonical_line, physical_mode });
+    }
+
+    Ok(GtfsLineDatabase { routes_by_id, route_ids_by_line })
+}
@@ -1,8 +1,17 @@
 mod achievements;
+mod admin_api;
+pub mod clocks;
 mod date_time;
+mod gtfs;
+mod metrics;
+mod query_result;
 mod range_set;
+mod ride_db;
+mod ride_query;
+mod ride_store;
 mod serde;
 mod short_last_rider_status;
+mod stats_filter;
 pub mod table_draw;
 
 
@@ -20,10 +29,11 @@ use bigdecimal::{BigDecimal, Zero};
 use chrono::{
     Datelike, DateTime, Duration, Local, LocalResult, NaiveDate, NaiveDateTime, Timelike, TimeZone,
 };
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
 use once_cell::sync::{Lazy, OnceCell};
 use rand::{Rng, thread_rng};
 use regex::{Captures, Regex};
-use rocketbot_bim_common::{CouplingMode, LastRider, VehicleInfo, VehicleNumber};
+use rocketbot_bim_common::{CouplingMode, LastRider, VehicleClass, VehicleInfo, VehicleNumber};
 use rocketbot_bim_common::achievements::ACHIEVEMENT_DEFINITIONS;
 use rocketbot_bim_common::ride_table::{Ride, RideTableData, RideTableVehicle, UserRide};
 use rocketbot_interface::{phrase_join, send_channel_message};
@@ -42,9 +52,18 @@ use tokio_postgres::NoTls;
 use tokio_postgres::types::ToSql;
 use tracing::{debug, error, info};
 
-use crate::achievements::{get_all_achievements, recalculate_achievements};
+use crate::achievements::{achievement_unlock_ranks, get_all_achievements, recalculate_achievements};
+use crate::clocks::{Clocks, SystemClocks};
 use crate::date_time::{canonical_date_format, weekday_abbr2};
+use crate::gtfs::{GtfsLineDatabase, load_gtfs_line_database};
+use crate::query_result::{
+    BimQueryRide, BimQueryRiderBalance, BimQueryRiderScore, BimQueryResult,
+    serialize_bim_query_result,
+};
 use crate::range_set::RangeSet;
+use crate::ride_db::{RideDb, RideDbBackend};
+use crate::ride_query::RideQuery;
+use crate::stats_filter::StatsFilter;
 use crate::table_draw::draw_ride_table;
 
 
@@ -83,6 +102,13 @@ enum LookbackRange {
     LastMonth,
     LastWeek,
     LastDay,
+    /// An explicit lower bound parsed from a `--since`/`-s` option, e.g. a relative duration
+    /// (`"7d"`, `"1w3d"`), an anchored phrase (`"yesterday"`, `"last week"`) or an absolute date
+    /// (`"2023-01-01"`).
+    Since(DateTime<Local>),
+    /// An explicit `start..end` lower-and-upper bound parsed from a `--since`/`-s` option, e.g.
+    /// `"2023-01-01..2023-02-01"`.
+    Range(DateTime<Local>, DateTime<Local>),
 }
 impl LookbackRange {
     pub fn days(&self) -> Option<i64> {
@@ -92,12 +118,29 @@ impl LookbackRange {
             Self::LastMonth => Some(31), // yeah, I know
             Self::LastWeek => Some(7),
             Self::LastDay => Some(1),
+            Self::Since(_) => None,
+            Self::Range(_, _) => None,
         }
     }
 
-    pub fn start_timestamp(&self) -> Option<DateTime<Local>> {
+    pub fn start_timestamp(&self, clocks: &dyn Clocks) -> Option<DateTime<Local>> {
+        if let Self::Since(dt) = self {
+            return Some(*dt);
+        }
+        if let Self::Range(start, _end) = self {
+            return Some(*start);
+        }
         self.days()
-            .map(|d| Local::now() - Duration::days(d))
+            .map(|d| clocks.now() - Duration::days(d))
+    }
+
+    /// The exclusive upper bound of this lookback range, or `None` if it is open-ended (i.e.
+    /// extends up to the present moment).
+    pub fn end_timestamp(&self) -> Option<DateTime<Local>> {
+        match self {
+            Self::Range(_start, end) => Some(*end),
+            _ => None,
+        }
     }
 }
 impl Default for LookbackRange {
@@ -119,6 +162,8 @@ impl AddLookbackFlags for CommandDefinitionBuilder {
             .add_flag("last-week")
             .add_flag("d")
             .add_flag("last-day")
+            .add_option("since", CommandValueType::String)
+            .add_option("s", CommandValueType::String)
     }
 }
 
@@ -219,7 +264,12 @@ impl BimTypeStats {
 pub struct CompanyDefinition {
     pub name: String,
     pub country: String,
+    pub currency: String,
     pub bim_database_path: Option<String>,
+    /// Path to a directory containing a GTFS feed (`routes.txt`, `trips.txt`, `stop_times.txt`
+    /// and an optional `physical_modes.txt` override table) used to validate line specifications
+    /// and attach a physical mode to rides; see [`crate::gtfs`].
+    #[serde(default)] pub gtfs_feed_dir: Option<String>,
     #[serde(with = "serde_opt_regex")] pub vehicle_number_regex: Option<Regex>,
     #[serde(with = "serde_opt_regex")] pub line_number_regex: Option<Regex>,
     #[serde(default, with = "crate::serde::serde_opt_big_decimal")] pub default_price: Option<BigDecimal>,
@@ -333,6 +383,76 @@ struct Config {
     #[serde(default)] highlight_coupled_rides: bool,
     #[serde(default)] emoji_reactions: HashMap<EmojiReaction, String>,
     #[serde(default)] vehicle_emoji_reactions: Vec<VehicleEmojiReaction>,
+    #[serde(default = "default_export_window_days")] export_window_days: i64,
+    #[serde(default)] metrics_listen_address: Option<String>,
+    #[serde(default = "default_metrics_refresh_interval_s")] metrics_refresh_interval_s: i64,
+    #[serde(default)] admin_api_listen_address: Option<String>,
+    #[serde(default)] admin_api_tokens: HashMap<String, String>,
+    #[serde(default)] admin_api_announce_channel: Option<String>,
+    #[serde(default)] ride_db_backend: RideDbBackend,
+    #[serde(default = "default_gap_limit")] default_gap_limit: i64,
+    #[serde(default)] currency_exchange_rates: Vec<CurrencyExchangeRate>,
+    #[serde(default = "default_ride_db_pool_size")] ride_db_pool_size: usize,
+    #[serde(skip)] ride_db_pool: RideDbPoolCell,
+}
+impl Config {
+    /// Returns the connection pool for [`ride_db_conn_string`](Self::ride_db_conn_string),
+    /// building it on first use. Since the whole `Config` is replaced wholesale on
+    /// `configuration_updated`, a fresh (empty) pool is built automatically whenever the
+    /// connection string (or pool size) changes, the same way e.g.
+    /// [`CompanyDefinition::vehicle_and_line_regex`] is recomputed from fresh derived state.
+    fn ride_db_pool(&self) -> Result<&Pool, RideDbPoolError> {
+        self.ride_db_pool.0.get_or_try_init(|| {
+            let pg_config: tokio_postgres::Config = self.ride_db_conn_string.parse()
+                .map_err(RideDbPoolError::InvalidConnString)?;
+            let manager_config = ManagerConfig { recycling_method: RecyclingMethod::Fast };
+            let manager = Manager::from_config(pg_config, NoTls, manager_config);
+            Pool::builder(manager)
+                .max_size(self.ride_db_pool_size)
+                .build()
+                .map_err(RideDbPoolError::Build)
+        })
+    }
+}
+
+fn default_export_window_days() -> i64 { 14 }
+
+fn default_metrics_refresh_interval_s() -> i64 { 15 }
+
+fn default_gap_limit() -> i64 { 10 }
+
+fn default_ride_db_pool_size() -> usize { 8 }
+
+/// Wraps the lazily-built ride database connection pool so it can live inside [`Config`]
+/// alongside the other derived, non-serialized state, without requiring `deadpool_postgres::Pool`
+/// to implement `Debug`.
+#[derive(Clone, Default)]
+struct RideDbPoolCell(OnceCell<Pool>);
+impl fmt::Debug for RideDbPoolCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("RideDbPoolCell(..)")
+    }
+}
+
+/// Error obtaining a connection from the ride database's connection pool.
+#[derive(Debug)]
+pub(crate) enum RideDbPoolError {
+    InvalidConnString(tokio_postgres::Error),
+    Build(deadpool_postgres::BuildError),
+    Pool(deadpool_postgres::PoolError),
+}
+impl fmt::Display for RideDbPoolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidConnString(e) => write!(f, "invalid ride database connection string: {}", e),
+            Self::Build(e) => write!(f, "failed to build ride database connection pool: {}", e),
+            Self::Pool(e) => write!(f, "failed to obtain ride database connection: {}", e),
+        }
+    }
+}
+impl std::error::Error for RideDbPoolError {}
+impl From<deadpool_postgres::PoolError> for RideDbPoolError {
+    fn from(e: deadpool_postgres::PoolError) -> Self { Self::Pool(e) }
 }
 
 
@@ -364,6 +484,17 @@ struct VehicleEmojiReaction {
     #[serde(default)] pub only_ridden_vehicles: bool,
 }
 
+
+/// A static exchange rate: one unit of `from_currency` is worth `rate` units of `to_currency`.
+/// The reverse direction (`to_currency` to `from_currency`) is derived by dividing by `rate`, so
+/// only one direction needs to be configured per currency pair.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct CurrencyExchangeRate {
+    pub from_currency: String,
+    pub to_currency: String,
+    #[serde(with = "crate::serde::serde_big_decimal")] pub rate: BigDecimal,
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 struct PlusMinus {
     pub plus: i64,
@@ -375,38 +506,20 @@ pub struct BimPlugin {
     interface: Weak<dyn RocketBotInterface>,
     config: Arc<RwLock<Config>>,
     achievement_update_sender: mpsc::UnboundedSender<UpdateAchievementsData>,
+    clocks: Arc<dyn Clocks>,
 }
 impl BimPlugin {
     fn load_bim_database(&self, config: &Config, company: &str) -> Option<HashMap<VehicleNumber, VehicleInfo>> {
-        let path_opt = match config.company_to_definition.get(company) {
-            Some(p) => p.bim_database_path.as_ref(),
-            None => {
-                error!("unknown company {:?}", company);
-                return None;
-            },
-        };
-        let path = match path_opt {
-            Some(p) => p,
-            None => return None, // valid company but no database
-        };
-        let f = match File::open(path) {
-            Ok(f) => f,
-            Err(e) => {
-                error!("failed to open bim database: {}", e);
-                return None;
-            },
-        };
-        let mut vehicles: Vec<VehicleInfo> = match ciborium::from_reader(f) {
-            Ok(v) => v,
-            Err(e) => {
-                error!("failed to parse bim database: {}", e);
-                return None;
-            },
-        };
-        let vehicle_hash_map: HashMap<VehicleNumber, VehicleInfo> = vehicles.drain(..)
-            .map(|vi| (vi.number.clone(), vi))
-            .collect();
-        Some(vehicle_hash_map)
+        load_bim_database(config, company)
+    }
+
+    fn load_gtfs_line_database(&self, config: &Config, company: &str) -> Option<GtfsLineDatabase> {
+        load_gtfs_line_database_for_company(config, company)
+    }
+
+    /// Enqueues an achievement recalculation, counting it for the `/metrics` endpoint.
+    fn enqueue_achievement_update(&self, data: UpdateAchievementsData) {
+        enqueue_achievement_update(&self.achievement_update_sender, data);
     }
 
     fn load_operator_databases(&self, config: &Config) -> Option<RegionToLineToOperator> {
@@ -440,7 +553,7 @@ impl BimPlugin {
         Some(region_to_line_to_operator)
     }
 
-    fn lookback_range_from_command(command: &CommandInstance) -> Option<LookbackRange> {
+    fn lookback_range_from_command(command: &CommandInstance, clocks: &dyn Clocks) -> Option<LookbackRange> {
         let last_month =
             command.flags.contains("m")
             || command.flags.contains("last-month")
@@ -457,38 +570,92 @@ impl BimPlugin {
             command.flags.contains("d")
             || command.flags.contains("last-day")
         ;
+        let since_str = command.options.get("since")
+            .or_else(|| command.options.get("s"))
+            .map(|v| v.as_str().unwrap());
 
-        match (last_year, last_month, last_week, last_day) {
-            (true, false, false, false) => Some(LookbackRange::LastYear),
-            (false, true, false, false) => Some(LookbackRange::LastMonth),
-            (false, false, true, false) => Some(LookbackRange::LastWeek),
-            (false, false, false, true) => Some(LookbackRange::LastDay),
-            (false, false, false, false) => Some(LookbackRange::SinceBeginning),
-            _ => None,
-        }
+        lookback_range_from_flags(last_year, last_month, last_week, last_day, since_str, clocks)
     }
 
+    /// Substitutes `{LOOKBACK_TIMESTAMP}` in `query_template` with a condition restricting
+    /// `timestamp_column` (e.g. `r."timestamp"`) to `lookback_range`, or with an empty string if
+    /// `lookback_range` is open-ended on both sides. The placeholder(s) the condition needs are
+    /// numbered to continue on from `other_params`, so callers never have to compute `$N` indices
+    /// themselves.
     async fn timestamp_query(
         conn: &tokio_postgres::Client,
         query_template: &str,
-        timestamp_block: &str,
-        no_timestamp_block: &str,
+        timestamp_column: &str,
         lookback_range: LookbackRange,
         other_params: &[&(dyn ToSql + Sync)],
+        clocks: &dyn Clocks,
     ) -> Result<Vec<tokio_postgres::Row>, tokio_postgres::Error> {
-        let lookback_timestamp_opt = lookback_range.start_timestamp();
+        let start_opt = lookback_range.start_timestamp(clocks);
+        let end_opt = lookback_range.end_timestamp();
 
-        let mut new_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(other_params.len() + 1);
+        let mut new_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(other_params.len() + 2);
         new_params.extend(other_params);
 
-        if let Some(lt) = lookback_timestamp_opt {
-            new_params.push(&lt);
-            let query = query_template.replace("{LOOKBACK_TIMESTAMP}", timestamp_block);
-            conn.query(&query, &new_params).await
+        let mut conditions = Vec::with_capacity(2);
+        if let Some(start) = &start_opt {
+            new_params.push(start);
+            conditions.push(format!("{} >= ${}", timestamp_column, new_params.len()));
+        }
+        if let Some(end) = &end_opt {
+            new_params.push(end);
+            conditions.push(format!("{} < ${}", timestamp_column, new_params.len()));
+        }
+
+        let block = if conditions.is_empty() {
+            String::new()
         } else {
-            let query = query_template.replace("{LOOKBACK_TIMESTAMP}", no_timestamp_block);
-            conn.query(&query, &new_params).await
+            format!("AND {}", conditions.join(" AND "))
+        };
+
+        let query = query_template.replace("{LOOKBACK_TIMESTAMP}", &block);
+        conn.query(&query, &new_params).await
+    }
+
+    /// Like [`Self::timestamp_query`], but takes a [`RideQuery`] that has already accumulated its
+    /// own `$N`-numbered conditions (e.g. a username criterion and/or a [`StatsFilter`]) instead
+    /// of a fixed `other_params` slice. The lookback bound(s), if any, are appended as the last
+    /// condition(s), so their placeholders always line up correctly regardless of how many
+    /// conditions the query already has. `query_template` must contain a `{CRITERIA}` marker
+    /// where the accumulated `AND`-prefixed conditions (continuing an existing `WHERE`) should be
+    /// inserted.
+    async fn ride_query(
+        conn: &tokio_postgres::Client,
+        query_template: &str,
+        lookback_range: LookbackRange,
+        query: &RideQuery<'_>,
+        clocks: &dyn Clocks,
+    ) -> Result<Vec<tokio_postgres::Row>, tokio_postgres::Error> {
+        let start_opt = lookback_range.start_timestamp(clocks);
+        let end_opt = lookback_range.end_timestamp();
+
+        let mut params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(query.params().len() + 2);
+        params.extend(query.params());
+
+        let mut lookback_conditions = Vec::with_capacity(2);
+        if let Some(start) = &start_opt {
+            let placeholder = query.next_placeholder() + lookback_conditions.len();
+            params.push(start);
+            lookback_conditions.push(format!("AND r.\"timestamp\" >= ${}", placeholder));
         }
+        if let Some(end) = &end_opt {
+            let placeholder = query.next_placeholder() + lookback_conditions.len();
+            params.push(end);
+            lookback_conditions.push(format!("AND r.\"timestamp\" < ${}", placeholder));
+        }
+
+        let mut criteria = query.and_clause();
+        for condition in &lookback_conditions {
+            criteria.push('\n');
+            criteria.push_str(condition);
+        }
+        let sql = query_template.replace("{CRITERIA}", &criteria);
+
+        conn.query(&sql, &params).await
     }
 
     async fn channel_command_bim(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
@@ -601,7 +768,7 @@ impl BimPlugin {
                 format!("This vehicle has been ridden {}.", BimPlugin::english_adverbial_number(count))
             };
 
-            let now = Local::now();
+            let now = self.clocks.now();
             let short_status = crate::short_last_rider_status::get(
                 &ride_conn,
                 company,
@@ -737,6 +904,7 @@ impl BimPlugin {
                 ats.as_str().expect("timestamp string not a string?!"),
                 utc_timestamp,
                 &channel_message.channel.name,
+                None,
             ).await;
             match timestamp_opt {
                 Some(t) => t,
@@ -817,6 +985,7 @@ impl BimPlugin {
         };
 
         let bim_database_opt = self.load_bim_database(&config_guard, company);
+        let gtfs_database_opt = self.load_gtfs_line_database(&config_guard, company);
         let mut ride_conn = match connect_ride_db(&config_guard).await {
             Ok(c) => c,
             Err(_) => {
@@ -832,6 +1001,7 @@ impl BimPlugin {
         let increment_res = increment_rides_by_spec(
             &mut ride_conn,
             bim_database_opt.as_ref(),
+            gtfs_database_opt.as_ref(),
             company,
             company_def,
             rider_username,
@@ -869,6 +1039,14 @@ impl BimPlugin {
                 ).await;
                 return;
             },
+            Err(IncrementBySpecError::UnknownLine(line)) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Line {:?} does not exist according to the imported GTFS feed.", line),
+                ).await;
+                return;
+            },
             Err(e) => {
                 error!("increment-by-spec error: {}", e);
                 send_channel_message!(
@@ -1039,7 +1217,7 @@ impl BimPlugin {
                     channel: channel_message.channel.name.clone(),
                     explicit: false,
                 };
-                let _ = self.achievement_update_sender.send(data);
+                self.enqueue_achievement_update(data);
             }
         }
     }
@@ -1055,7 +1233,7 @@ impl BimPlugin {
         let company_opt = command.options.get("company")
             .or_else(|| command.options.get("c"))
             .map(|v| v.as_str().unwrap());
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -1090,59 +1268,8 @@ impl BimPlugin {
             },
         };
 
-        let company_stored;
-        let mut other_params: Vec<&(dyn ToSql + Sync)> = Vec::new();
-        let (company_block, timestamp_block) = if let Some(c) = company_opt {
-            company_stored = c.to_owned();
-            other_params.push(&company_stored);
-            ("AND r.company = $1", "AND r.\"timestamp\" >= $2")
-        } else {
-            ("", "AND r.\"timestamp\" >= $1")
-        };
-        let query_template = format!(
-            "
-                WITH
-                    total_rides(company, vehicle_number, total_ride_count) AS (
-                        SELECT
-                            r.company,
-                            rv.vehicle_number,
-                            CAST(COUNT(*) AS bigint) total_ride_count
-                        FROM
-                            bim.rides r
-                            INNER JOIN bim.ride_vehicles rv
-                                ON rv.ride_id = r.id
-                        WHERE
-                            rv.coupling_mode = 'R'
-                            {}
-                            {{LOOKBACK_TIMESTAMP}}
-                        GROUP BY
-                            r.company,
-                            rv.vehicle_number
-                    ),
-                    top_five_counts(total_ride_count) AS (
-                        SELECT DISTINCT total_ride_count
-                        FROM total_rides
-                        ORDER BY total_ride_count DESC
-                        LIMIT 5
-                    )
-                SELECT tr.company, tr.vehicle_number, tr.total_ride_count
-                FROM total_rides tr
-                WHERE tr.total_ride_count IN (SELECT total_ride_count FROM top_five_counts)
-                ORDER BY tr.total_ride_count DESC, tr.vehicle_number USING OPERATOR(bim.<~<)
-            ",
-            company_block,
-        );
-
-        let rows_res = Self::timestamp_query(
-            &ride_conn,
-            &query_template,
-            timestamp_block,
-            "",
-            lookback_range,
-            other_params.as_slice(),
-        ).await;
-        let rows = match rows_res {
-            Ok(r) => r,
+        let count_to_vehicles = match top_ridden_vehicle_counts(&ride_conn, company_opt, lookback_range, self.clocks.as_ref()).await {
+            Ok(ctv) => ctv,
             Err(e) => {
                 error!("failed to query most-ridden vehicles: {}", e);
                 send_channel_message!(
@@ -1154,18 +1281,6 @@ impl BimPlugin {
             },
         };
 
-        let mut count_to_vehicles: BTreeMap<i64, Vec<(String, String)>> = BTreeMap::new();
-        for row in &rows {
-            let company: String = row.get(0);
-            let vehicle_number: String = row.get(1);
-            let total_ride_count: i64 = row.get(2);
-
-            count_to_vehicles
-                .entry(total_ride_count)
-                .or_insert_with(|| Vec::new())
-                .push((company, vehicle_number));
-        }
-
         let response_str = if count_to_vehicles.len() == 0 {
             format!("No vehicles have been ridden yet!")
         } else {
@@ -1209,7 +1324,7 @@ impl BimPlugin {
 
         let config_guard = self.config.read().await;
 
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -1221,77 +1336,34 @@ impl BimPlugin {
             },
         };
 
-        let ride_conn = match connect_ride_db(&config_guard).await {
-            Ok(c) => c,
-            Err(_) => {
+        let (stats_filter, _leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    "Failed to open database connection. :disappointed:",
+                    &format!("Failed to parse filter: {}", e),
                 ).await;
                 return;
             },
         };
 
-        let ride_rows_res = Self::timestamp_query(
-            &ride_conn,
-            "
-                SELECT r.rider_username, CAST(COUNT(*) AS bigint) ride_count
-                FROM bim.rides r
-                {LOOKBACK_TIMESTAMP}
-                GROUP BY r.rider_username
-            ",
-            "WHERE r.\"timestamp\" >= $1",
-            "",
-            lookback_range,
-            &[],
-        ).await;
-        let ride_rows = match ride_rows_res {
-            Ok(r) => r,
-            Err(e) => {
-                error!("failed to query most active riders: {}", e);
+        let ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    "Failed to query database. :disappointed:",
+                    "Failed to open database connection. :disappointed:",
                 ).await;
                 return;
             },
         };
 
-        let mut rider_to_ride_and_vehicle_count = HashMap::new();
-        for row in ride_rows {
-            let rider_username: String = row.get(0);
-            let ride_count: i64 = row.get(1);
-
-            let rider_ride_and_vehicle_count = rider_to_ride_and_vehicle_count
-                .entry(rider_username.clone())
-                .or_insert((0i64, 0i64));
-            rider_ride_and_vehicle_count.0 += ride_count;
-        }
-
-        let vehicle_rows_res = Self::timestamp_query(
-            &ride_conn,
-            "
-                SELECT i.rider_username, CAST(COUNT(*) AS bigint) vehicle_count
-                FROM (
-                    SELECT DISTINCT r.rider_username, r.company, rv.vehicle_number
-                    FROM bim.rides r
-                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-                    WHERE rv.coupling_mode = 'R'
-                    {LOOKBACK_TIMESTAMP}
-                ) i
-                GROUP BY i.rider_username
-            ",
-            "AND r.\"timestamp\" >= $1",
-            "",
-            lookback_range,
-            &[],
-        ).await;
-        let vehicle_rows = match vehicle_rows_res {
+        let rider_and_ride_and_vehicle_count = match top_rider_ride_and_vehicle_counts(&ride_conn, &stats_filter, lookback_range, self.clocks.as_ref()).await {
             Ok(r) => r,
             Err(e) => {
-                error!("failed to query most active riders with vehicles: {}", e);
+                error!("failed to query most active riders: {}", e);
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
@@ -1301,21 +1373,6 @@ impl BimPlugin {
             },
         };
 
-        for row in vehicle_rows {
-            let rider_username: String = row.get(0);
-            let vehicle_count: i64 = row.get(1);
-
-            let rider_ride_and_vehicle_count = rider_to_ride_and_vehicle_count
-                .entry(rider_username.clone())
-                .or_insert((0i64, 0i64));
-            rider_ride_and_vehicle_count.1 += vehicle_count;
-        }
-
-        let mut rider_and_ride_and_vehicle_count: Vec<(String, i64, i64)> = rider_to_ride_and_vehicle_count
-            .iter()
-            .map(|(r, (rc, vc))| (r.clone(), *rc, *vc))
-            .collect();
-        rider_and_ride_and_vehicle_count.sort_unstable_by_key(|(r, rc, _vc)| (-*rc, r.clone()));
         let mut rider_strings: Vec<String> = rider_and_ride_and_vehicle_count.iter()
             .map(|(rider_name, ride_count, vehicle_count)| {
                 let ride_text = if *ride_count == 1 {
@@ -1450,7 +1507,7 @@ impl BimPlugin {
 
         let config_guard = self.config.read().await;
 
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -1461,13 +1518,24 @@ impl BimPlugin {
                 return;
             },
         };
-        let rider_username_input = command.rest.trim();
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse filter: {}", e),
+                ).await;
+                return;
+            },
+        };
+        let rider_username_input = leftover_tokens.join(" ");
         let rider_username_opt = if rider_username_input.len() == 0 {
             None
         } else {
-            match interface.resolve_username(rider_username_input).await {
+            match interface.resolve_username(&rider_username_input).await {
                 Some(ru) => Some(ru),
-                None => Some(rider_username_input.to_owned()),
+                None => Some(rider_username_input.clone()),
             }
         };
 
@@ -1483,7 +1551,9 @@ impl BimPlugin {
             },
         };
 
-        let rows_res = Self::timestamp_query(
+        let mut ride_query = RideQuery::new();
+        stats_filter.apply(&mut ride_query, "r");
+        let rows_res = Self::ride_query(
             &ride_conn,
             "
                 WITH
@@ -1492,7 +1562,7 @@ impl BimPlugin {
                         FROM bim.rides r
                         INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
                         WHERE rv.coupling_mode = 'R'
-                        {LOOKBACK_TIMESTAMP}
+                        {CRITERIA}
                         GROUP BY r.rider_username, r.company, rv.vehicle_number
                     ),
                     rider_top_ride_counts(rider_username, ride_count) AS (
@@ -1520,10 +1590,9 @@ impl BimPlugin {
                 SELECT rider_username, company, vehicle_number, CAST(ride_count AS bigint)
                 FROM fav_vehicles
             ",
-            "AND r.\"timestamp\" >= $1",
-            "",
             lookback_range,
-            &[],
+            &ride_query,
+            self.clocks.as_ref(),
         ).await;
         let rows = match rows_res {
             Ok(r) => r,
@@ -1646,7 +1715,7 @@ impl BimPlugin {
 
         let config_guard = self.config.read().await;
 
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -1657,13 +1726,24 @@ impl BimPlugin {
                 return;
             },
         };
-        let rider_username_input = command.rest.trim();
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse filter: {}", e),
+                ).await;
+                return;
+            },
+        };
+        let rider_username_input = leftover_tokens.join(" ");
         let rider_username_opt = if rider_username_input.len() == 0 {
             None
         } else {
-            match interface.resolve_username(rider_username_input).await {
+            match interface.resolve_username(&rider_username_input).await {
                 Some(ru) => Some(ru),
-                None => Some(rider_username_input.to_owned()),
+                None => Some(rider_username_input.clone()),
             }
         };
 
@@ -1679,57 +1759,47 @@ impl BimPlugin {
             },
         };
 
-        let query_template = "
-            WITH
-            rides_dates(ride_date) AS (
-                SELECT
-                    -- count rides before 04:00 to previous day
-                    CAST(
-                        CASE WHEN EXTRACT(HOUR FROM r.\"timestamp\") < 4
-                        THEN r.\"timestamp\" - CAST('P1D' AS interval)
-                        ELSE r.\"timestamp\"
-                        END
-                    AS date)
-                FROM
-                    bim.rides r
-                {USERNAME_CRITERION}
-                {LOOKBACK_TIMESTAMP}
-            ),
-            ride_date_count(ride_year, ride_month, ride_day, ride_count) AS (
-                SELECT
-                    CAST(EXTRACT(YEAR FROM ride_date) AS bigint),
-                    CAST(EXTRACT(MONTH FROM ride_date) AS bigint),
-                    CAST(EXTRACT(DAY FROM ride_date) AS bigint),
-                    COUNT(*)
-                FROM rides_dates
-                GROUP BY ride_date
-            )
-            SELECT ride_year, ride_month, ride_day, CAST(ride_count AS bigint) ride_count
-            FROM ride_date_count
-            ORDER BY ride_count DESC, ride_year DESC, ride_month DESC, ride_day DESC
-            LIMIT 6
-        ";
-        let rows_res = if let Some(ru) = &rider_username_opt {
-            let query = query_template.replace("{USERNAME_CRITERION}", "WHERE LOWER(r.rider_username) = LOWER($1)");
-            Self::timestamp_query(
-                &ride_conn,
-                &query,
-                "AND r.\"timestamp\" >= $2",
-                "",
-                lookback_range,
-                &[&ru],
-            ).await
-        } else {
-            let query = query_template.replace("{USERNAME_CRITERION}", "");
-            Self::timestamp_query(
-                &ride_conn,
-                &query,
-                "WHERE r.\"timestamp\" >= $1",
-                "",
-                lookback_range,
-                &[],
-            ).await
-        };
+        let mut ride_query = RideQuery::new();
+        if let Some(ru) = &rider_username_opt {
+            ride_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[ru]);
+        }
+        stats_filter.apply(&mut ride_query, "r");
+        let rows_res = Self::ride_query(
+            &ride_conn,
+            "
+                WITH
+                rides_dates(ride_date) AS (
+                    SELECT
+                        -- count rides before 04:00 to previous day
+                        CAST(
+                            CASE WHEN EXTRACT(HOUR FROM r.\"timestamp\") < 4
+                            THEN r.\"timestamp\" - CAST('P1D' AS interval)
+                            ELSE r.\"timestamp\"
+                            END
+                        AS date)
+                    FROM
+                        bim.rides r
+                    WHERE 1=1
+                    {CRITERIA}
+                ),
+                ride_date_count(ride_year, ride_month, ride_day, ride_count) AS (
+                    SELECT
+                        CAST(EXTRACT(YEAR FROM ride_date) AS bigint),
+                        CAST(EXTRACT(MONTH FROM ride_date) AS bigint),
+                        CAST(EXTRACT(DAY FROM ride_date) AS bigint),
+                        COUNT(*)
+                    FROM rides_dates
+                    GROUP BY ride_date
+                )
+                SELECT ride_year, ride_month, ride_day, CAST(ride_count AS bigint) ride_count
+                FROM ride_date_count
+                ORDER BY ride_count DESC, ride_year DESC, ride_month DESC, ride_day DESC
+                LIMIT 6
+            ",
+            lookback_range,
+            &ride_query,
+            self.clocks.as_ref(),
+        ).await;
         let rows = match rows_res {
             Ok(r) => r,
             Err(e) => {
@@ -1790,7 +1860,7 @@ impl BimPlugin {
 
         let config_guard = self.config.read().await;
 
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -1801,13 +1871,24 @@ impl BimPlugin {
                 return;
             },
         };
-        let rider_username_input = command.rest.trim();
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse filter: {}", e),
+                ).await;
+                return;
+            },
+        };
+        let rider_username_input = leftover_tokens.join(" ");
         let rider_username_opt = if rider_username_input.len() == 0 {
             None
         } else {
-            match interface.resolve_username(rider_username_input).await {
+            match interface.resolve_username(&rider_username_input).await {
                 Some(ru) => Some(ru),
-                None => Some(rider_username_input.to_owned()),
+                None => Some(rider_username_input.clone()),
             }
         };
 
@@ -1823,50 +1904,39 @@ impl BimPlugin {
             },
         };
 
-        let query_template = "
-            WITH ride_counts(company, line, ride_count) AS (
-                SELECT r.company, r.line, COUNT(*)
-                FROM bim.rides r
-                WHERE r.line IS NOT NULL
-                {USERNAME_CRITERION}
-                {LOOKBACK_TIMESTAMP}
-                GROUP BY r.company, r.line
-            ),
-            top_ride_counts(ride_count) AS (
-                SELECT DISTINCT ride_count
-                FROM ride_counts
-                ORDER BY ride_count DESC
-                LIMIT 6
-            )
-            SELECT rc.company, rc.line, CAST(rc.ride_count AS bigint)
-            FROM ride_counts rc
-            WHERE EXISTS (
-                SELECT 1
-                FROM top_ride_counts trc
-                WHERE trc.ride_count = rc.ride_count
-            )
-        ";
-        let rows_res = if let Some(ru) = &rider_username_opt {
-            let query = query_template.replace("{USERNAME_CRITERION}", "AND LOWER(r.rider_username) = LOWER($1)");
-            Self::timestamp_query(
-                &ride_conn,
-                &query,
-                "AND r.\"timestamp\" >= $2",
-                "",
-                lookback_range,
-                &[&ru],
-            ).await
-        } else {
-            let query = query_template.replace("{USERNAME_CRITERION}", "");
-            Self::timestamp_query(
-                &ride_conn,
-                &query,
-                "AND r.\"timestamp\" >= $1",
-                "",
-                lookback_range,
-                &[],
-            ).await
-        };
+        let mut ride_query = RideQuery::new();
+        if let Some(ru) = &rider_username_opt {
+            ride_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[ru]);
+        }
+        stats_filter.apply(&mut ride_query, "r");
+        let rows_res = Self::ride_query(
+            &ride_conn,
+            "
+                WITH ride_counts(company, line, ride_count) AS (
+                    SELECT r.company, r.line, COUNT(*)
+                    FROM bim.rides r
+                    WHERE r.line IS NOT NULL
+                    {CRITERIA}
+                    GROUP BY r.company, r.line
+                ),
+                top_ride_counts(ride_count) AS (
+                    SELECT DISTINCT ride_count
+                    FROM ride_counts
+                    ORDER BY ride_count DESC
+                    LIMIT 6
+                )
+                SELECT rc.company, rc.line, CAST(rc.ride_count AS bigint)
+                FROM ride_counts rc
+                WHERE EXISTS (
+                    SELECT 1
+                    FROM top_ride_counts trc
+                    WHERE trc.ride_count = rc.ride_count
+                )
+            ",
+            lookback_range,
+            &ride_query,
+            self.clocks.as_ref(),
+        ).await;
         let rows = match rows_res {
             Ok(r) => r,
             Err(e) => {
@@ -1924,7 +1994,7 @@ impl BimPlugin {
         ).await;
     }
 
-    async fn channel_command_bimridertypes(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+    async fn channel_command_bimexport(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
         let interface = match self.interface.upgrade() {
             None => return,
             Some(i) => i,
@@ -1932,31 +2002,51 @@ impl BimPlugin {
 
         let config_guard = self.config.read().await;
 
-        let lookback_range = match Self::lookback_range_from_command(command) {
-            Some(lr) => lr,
-            None => {
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    "Hey, no mixing options that mean different time ranges!",
+                    &format!("Failed to parse filter: {}", e),
                 ).await;
                 return;
             },
         };
-        let sort_by_number =
-            command.flags.contains("n")
-            || command.flags.contains("sort-by-number")
-        ;
-        let rider_username_input = command.rest.trim();
-        let rider_username = if rider_username_input.len() == 0 {
-            channel_message.message.sender.username.clone()
+        let rider_username_input = leftover_tokens.join(" ");
+        let rider_username_opt = if rider_username_input.len() == 0 {
+            None
         } else {
-            match interface.resolve_username(rider_username_input).await {
-                Some(ru) => ru,
-                None => rider_username_input.to_owned(),
+            match interface.resolve_username(&rider_username_input).await {
+                Some(ru) => Some(ru),
+                None => Some(rider_username_input.clone()),
             }
         };
 
+        let now = self.clocks.now();
+        let range_from = match stats_filter.after {
+            Some(d) => match Local.from_local_datetime(&d.and_hms_opt(0, 0, 0).unwrap()) {
+                LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt,
+                LocalResult::None => now,
+            },
+            None => now - Duration::days(366 * 5),
+        };
+        let range_to = match stats_filter.before {
+            Some(d) => match Local.from_local_datetime(&d.and_hms_opt(0, 0, 0).unwrap()) {
+                LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt,
+                LocalResult::None => now,
+            },
+            None => now,
+        };
+        if range_to <= range_from {
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                "The requested export range is empty.",
+            ).await;
+            return;
+        }
+
         let ride_conn = match connect_ride_db(&config_guard).await {
             Ok(c) => c,
             Err(_) => {
@@ -1969,27 +2059,319 @@ impl BimPlugin {
             },
         };
 
-        let rows_res = Self::timestamp_query(
-            &ride_conn,
-            "
-                SELECT
-                    r.company,
-                    rv.vehicle_number,
-                    CAST(COUNT(*) AS bigint) ride_count
+        // cheap existence probe so we don't chunk-query an empty range
+        let mut probe_query = RideQuery::new();
+        if let Some(ru) = &rider_username_opt {
+            probe_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[ru]);
+        }
+        stats_filter.apply(&mut probe_query, "r");
+        probe_query.and_where("r.\"timestamp\" >= ?", &[&range_from]);
+        probe_query.and_where("r.\"timestamp\" < ?", &[&range_to]);
+        let probe_sql = format!(
+            "SELECT 1 FROM bim.rides r WHERE 1=1 {} LIMIT 1",
+            probe_query.and_clause(),
+        );
+        match ride_conn.query_opt(&probe_sql, probe_query.params()).await {
+            Ok(Some(_)) => {},
+            Ok(None) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Nothing to export.",
+                ).await;
+                return;
+            },
+            Err(e) => {
+                error!("failed to probe ride export range: {}", e);
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to query database. :disappointed:",
+                ).await;
+                return;
+            },
+        }
+
+        // fetch the range in windows so a year-long export doesn't land in one giant result set
+        let window = Duration::days(config_guard.export_window_days.max(1));
+        let mut csv = String::from("rider,company,vehicle,line,timestamp\n");
+        let mut current_from = range_from;
+        while current_from < range_to {
+            let current_to = std::cmp::min(current_from + window, range_to);
+
+            let mut window_query = RideQuery::new();
+            if let Some(ru) = &rider_username_opt {
+                window_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[ru]);
+            }
+            stats_filter.apply(&mut window_query, "r");
+            window_query.and_where("r.\"timestamp\" >= ?", &[&current_from]);
+            window_query.and_where("r.\"timestamp\" < ?", &[&current_to]);
+
+            let sql = format!(
+                "
+                    SELECT r.rider_username, r.company, rv.vehicle_number, r.line, r.\"timestamp\"
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE 1=1
+                    {}
+                    ORDER BY r.\"timestamp\"
+                ",
+                window_query.and_clause(),
+            );
+            let rows_res = ride_conn.query(&sql, window_query.params()).await;
+            let rows = match rows_res {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("failed to query ride export window {}..{}: {}", current_from, current_to, e);
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Failed to query database. :disappointed:",
+                    ).await;
+                    return;
+                },
+            };
+
+            for row in rows {
+                let rider_username: String = row.get(0);
+                let company: String = row.get(1);
+                let vehicle_number: String = row.get(2);
+                let line: Option<String> = row.get(3);
+                let timestamp: DateTime<Local> = row.get(4);
+                write_expect!(
+                    csv,
+                    "{},{},{},{},{}\n",
+                    rider_username, company, vehicle_number, line.unwrap_or_default(), timestamp.to_rfc3339(),
+                );
+            }
+
+            current_from = current_to;
+        }
+
+        let attachment = Attachment::new(
+            csv.into_bytes(),
+            "bimexport.csv".to_owned(),
+            "text/csv".to_owned(),
+            None,
+        );
+        interface.send_channel_message_with_attachment(
+            &channel_message.channel.name,
+            OutgoingMessageWithAttachmentBuilder::new(attachment)
+                .build()
+        ).await;
+    }
+
+    async fn channel_command_topbimstreaks(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse filter: {}", e),
+                ).await;
+                return;
+            },
+        };
+        let rider_username_input = leftover_tokens.join(" ");
+        let rider_username_opt = if rider_username_input.len() == 0 {
+            None
+        } else {
+            match interface.resolve_username(&rider_username_input).await {
+                Some(ru) => Some(ru),
+                None => Some(rider_username_input.clone()),
+            }
+        };
+
+        let ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database connection. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        let mut ride_query = RideQuery::new();
+        if let Some(ru) = &rider_username_opt {
+            ride_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[ru]);
+        }
+        stats_filter.apply(&mut ride_query, "r");
+        let sql = format!(
+            "
+                SELECT r.rider_username, r.\"timestamp\"
+                FROM bim.rides r
+                WHERE 1=1
+                {}
+            ",
+            ride_query.and_clause(),
+        );
+        let rows_res = ride_conn.query(&sql, ride_query.params()).await;
+        let rows = match rows_res {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to query ride dates for streaks: {}", e);
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to query database. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        // the 04:00 rollover must be applied before de-duplicating dates, so a 02:00 ride is
+        // folded into the previous calendar day's streak
+        let mut rider_to_dates: BTreeMap<String, BTreeSet<NaiveDate>> = BTreeMap::new();
+        for row in rows {
+            let rider_username: String = row.get(0);
+            let timestamp: DateTime<Local> = row.get(1);
+            rider_to_dates
+                .entry(rider_username)
+                .or_insert_with(|| BTreeSet::new())
+                .insert(get_night_owl_date(&timestamp));
+        }
+
+        let today = get_night_owl_date(&self.clocks.now());
+        let mut rider_to_streaks: BTreeMap<String, RiderStreaks> = BTreeMap::new();
+        for (rider, dates) in &rider_to_dates {
+            rider_to_streaks.insert(rider.clone(), RiderStreaks::calculate(dates, today));
+        }
+
+        let response = if let Some(ru) = &rider_username_opt {
+            match rider_to_streaks.get(ru) {
+                Some(streaks) => format!(
+                    "{}'s longest streak: {} day{} ({} to {}){}",
+                    ru,
+                    streaks.longest_len,
+                    if streaks.longest_len == 1 { "" } else { "s" },
+                    streaks.longest_start.format("%Y-%m-%d"),
+                    streaks.longest_end.format("%Y-%m-%d"),
+                    if streaks.current_len > 0 {
+                        format!("; current streak: {} day{}", streaks.current_len, if streaks.current_len == 1 { "" } else { "s" })
+                    } else {
+                        String::new()
+                    },
+                ),
+                None => format!("{} has no rides matching this filter.", ru),
+            }
+        } else {
+            let mut by_longest: Vec<(&String, &RiderStreaks)> = rider_to_streaks.iter().collect();
+            by_longest.sort_by(|(rider_a, streaks_a), (rider_b, streaks_b)| {
+                streaks_b.longest_len.cmp(&streaks_a.longest_len)
+                    .then_with(|| rider_a.cmp(rider_b))
+            });
+            by_longest.truncate(5);
+
+            let mut lines = vec!["Top streaks:".to_owned()];
+            for (rider, streaks) in by_longest {
+                lines.push(format!(
+                    "{}: {} day{} ({} to {})",
+                    rider,
+                    streaks.longest_len,
+                    if streaks.longest_len == 1 { "" } else { "s" },
+                    streaks.longest_start.format("%Y-%m-%d"),
+                    streaks.longest_end.format("%Y-%m-%d"),
+                ));
+            }
+            lines.join("\n")
+        };
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
+    }
+
+    async fn channel_command_bimridertypes(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
+            Some(lr) => lr,
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Hey, no mixing options that mean different time ranges!",
+                ).await;
+                return;
+            },
+        };
+        let sort_by_number =
+            command.flags.contains("n")
+            || command.flags.contains("sort-by-number")
+        ;
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse filter: {}", e),
+                ).await;
+                return;
+            },
+        };
+        let rider_username_input = leftover_tokens.join(" ");
+        let rider_username = if rider_username_input.len() == 0 {
+            channel_message.message.sender.username.clone()
+        } else {
+            match interface.resolve_username(&rider_username_input).await {
+                Some(ru) => ru,
+                None => rider_username_input.clone(),
+            }
+        };
+
+        let ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database connection. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        let mut rider_types_query = RideQuery::new();
+        rider_types_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[&rider_username]);
+        rider_types_query.and_where_literal("rv.coupling_mode = 'R'");
+        stats_filter.apply(&mut rider_types_query, "r");
+        let rows_res = Self::ride_query(
+            &ride_conn,
+            "
+                SELECT
+                    r.company,
+                    rv.vehicle_number,
+                    CAST(COUNT(*) AS bigint) ride_count
                 FROM bim.rides r
                 INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-                WHERE
-                    LOWER(r.rider_username) = LOWER($1)
-                    AND rv.coupling_mode = 'R'
-                    {LOOKBACK_TIMESTAMP}
+                WHERE 1=1
+                {CRITERIA}
                 GROUP BY
                     r.company,
                     rv.vehicle_number
             ",
-            "AND r.\"timestamp\" >= $2",
-            "",
             lookback_range,
-            &[&rider_username],
+            &rider_types_query,
+            self.clocks.as_ref(),
         ).await;
         let rows = match rows_res {
             Ok(r) => r,
@@ -2079,7 +2461,7 @@ impl BimPlugin {
             command.flags.contains("a")
             || command.flags.contains("all")
         ;
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -2090,13 +2472,24 @@ impl BimPlugin {
                 return;
             },
         };
-        let rider_username_input = command.rest.trim();
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse filter: {}", e),
+                ).await;
+                return;
+            },
+        };
+        let rider_username_input = leftover_tokens.join(" ");
         let rider_username = if rider_username_input.len() == 0 {
             channel_message.message.sender.username.clone()
         } else {
-            match interface.resolve_username(rider_username_input).await {
+            match interface.resolve_username(&rider_username_input).await {
                 Some(ru) => ru,
-                None => rider_username_input.to_owned(),
+                None => rider_username_input.clone(),
             }
         };
 
@@ -2112,7 +2505,11 @@ impl BimPlugin {
             },
         };
 
-        let rows_res = Self::timestamp_query(
+        let mut rider_lines_query = RideQuery::new();
+        rider_lines_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[&rider_username]);
+        rider_lines_query.and_where_literal("r.line IS NOT NULL");
+        stats_filter.apply(&mut rider_lines_query, "r");
+        let rows_res = Self::ride_query(
             &ride_conn,
             "
                 SELECT
@@ -2120,18 +2517,15 @@ impl BimPlugin {
                     r.line,
                     CAST(COUNT(*) AS bigint) ride_count
                 FROM bim.rides r
-                WHERE
-                    LOWER(r.rider_username) = LOWER($1)
-                    AND r.line IS NOT NULL
-                    {LOOKBACK_TIMESTAMP}
+                WHERE 1=1
+                {CRITERIA}
                 GROUP BY
                     r.company,
                     r.line
             ",
-            "AND r.\"timestamp\" >= $2",
-            "",
             lookback_range,
-            &[&rider_username],
+            &rider_lines_query,
+            self.clocks.as_ref(),
         ).await;
         let rows = match rows_res {
             Ok(r) => r,
@@ -2251,20 +2645,7 @@ impl BimPlugin {
             }
 
             type_to_ranges.iter()
-                .map(|(tp, ranges)| {
-                    let range_strings: Vec<String> = ranges.ranges()
-                        .map(|r|
-                            if r.range.start == r.range.end - 1 {
-                                // single number
-                                format!("{}", r.range.start)
-                            } else {
-                                format!("{}-{}", r.range.start, r.range.end - 1)
-                            }
-                        )
-                        .collect();
-                    let ranges_string = range_strings.join(", ");
-                    format!("{}{}: {}", tp, INVISIBLE_JOINER, ranges_string)
-                })
+                .map(|(tp, ranges)| format!("{}{}: {}", tp, INVISIBLE_JOINER, format_range_set(ranges)))
                 .collect()
         } else {
             let mut type_to_range: BTreeMap<String, (VehicleNumber, VehicleNumber)> = BTreeMap::new();
@@ -2295,7 +2676,7 @@ impl BimPlugin {
         ).await;
     }
 
-    async fn channel_command_bimtypes(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+    async fn channel_command_bimgaps(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
         let interface = match self.interface.upgrade() {
             None => return,
             Some(i) => i,
@@ -2310,105 +2691,406 @@ impl BimPlugin {
         if company.len() == 0 {
             return;
         }
-        let company_name = match config_guard.company_to_definition.get(company) {
-            Some(cd) => cd.name.as_str(),
+
+        let type_code = match command.options.get("type").or_else(|| command.options.get("t")) {
+            Some(v) => v.as_str().unwrap(),
             None => {
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    "Unknown company.",
+                    "Please specify a vehicle type using -t/--type.",
                 ).await;
                 return;
             },
         };
-
-        let rider_username_input = command.rest.trim();
-        let rider_username_opt = if rider_username_input.len() == 0 {
-            None
-        } else {
-            match interface.resolve_username(rider_username_input).await {
-                Some(ru) => Some(ru),
-                None => Some(rider_username_input.to_owned()),
-            }
-        };
+        let gap_limit: usize = command.options.get("gap-limit")
+            .or_else(|| command.options.get("g"))
+            .map(|v| v.as_i64().expect("--gap-limit value not an i64"))
+            .unwrap_or(config_guard.default_gap_limit)
+            .try_into()
+            .unwrap_or(0);
+        if gap_limit == 0 {
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                "The gap limit must be a positive number.",
+            ).await;
+            return;
+        }
 
         let database = match self.load_bim_database(&config_guard, company) {
             Some(db) => db,
-            None => HashMap::new(), // work with an empty database
-        };
-
-        let ride_conn = match connect_ride_db(&config_guard).await {
-            Ok(c) => c,
-            Err(_) => {
+            None => {
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    "Failed to open database connection. :disappointed:",
+                    "No vehicle database exists for this company.",
                 ).await;
                 return;
             },
         };
 
-        let query_template = "
-            SELECT DISTINCT
-                rv.vehicle_number
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv
-                ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.coupling_mode = 'R'
-                {AND_RIDER_USERNAME}
-        ";
-        let (mut response, rows_res) = if let Some(ru) = rider_username_opt {
-            (
-                format!("Statistics for vehicles of {} ridden by {}:", company_name, ru),
-                ride_conn.query(
-                    &query_template.replace("{AND_RIDER_USERNAME}", "AND LOWER(r.rider_username) = LOWER($2)"),
-                    &[&company, &ru],
-                ).await
-            )
-        } else {
-            (
-                format!("General statistics for vehicles of {}:", company_name),
-                ride_conn.query(
-                    &query_template.replace("{AND_RIDER_USERNAME}", ""),
-                    &[&company],
-                ).await
-            )
-        };
-        let rows = match rows_res {
-            Ok(r) => r,
-            Err(e) => {
-                error!("failed to query bim types: {}", e);
+        let mut known_numbers: BTreeSet<u64> = BTreeSet::new();
+        for veh_info in database.values() {
+            if veh_info.type_code != type_code {
+                continue;
+            }
+            if let Ok(n) = veh_info.number.parse() {
+                known_numbers.insert(n);
+            }
+        }
+        let start = match known_numbers.iter().next() {
+            Some(&s) => s,
+            None => {
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    "Failed to query database. :disappointed:",
+                    &format!("I don't know of any vehicles of type {} for {}.", type_code, company),
                 ).await;
                 return;
             },
         };
-        let mut ridden_vehicles: HashSet<VehicleNumber> = HashSet::new();
-        for row in rows {
-            let vehicle_number = VehicleNumber::from_string(row.get(0));
-            ridden_vehicles.insert(vehicle_number);
-        }
 
-        // run through database
-        let mut type_to_stats: BTreeMap<String, BimTypeStats> = BTreeMap::new();
-        for vehicle in database.values() {
-            let type_stats = type_to_stats
-                .entry(vehicle.type_code.clone())
-                .or_insert_with(|| BimTypeStats::new());
-            type_stats.known_vehicles += 1;
-            if vehicle.in_service_since.is_some() && vehicle.out_of_service_since.is_none() {
-                type_stats.active_vehicles += 1;
-            }
-            if ridden_vehicles.remove(&vehicle.number) {
-                type_stats.ridden_vehicles += 1;
+        let ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database connection. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        let ridden_rows_res = ride_conn.query(
+            "
+                SELECT DISTINCT
+                    rv.vehicle_number
+                FROM bim.rides r
+                INNER JOIN bim.ride_vehicles rv
+                    ON rv.ride_id = r.id
+                WHERE
+                    r.company = $1
+                    AND rv.coupling_mode = 'R'
+            ",
+            &[&company],
+        ).await;
+        let ridden_rows = match ridden_rows_res {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to query ridden bim vehicles: {}", e);
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to query database. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+        let ridden_numbers: HashSet<u64> = ridden_rows.iter()
+            .filter_map(|row| {
+                let vehicle_number: String = row.get(0);
+                vehicle_number.parse().ok()
+            })
+            .collect();
+
+        // walk consecutive numbers starting at the lowest known one, treating both missing
+        // numbers and known-but-never-ridden ones as "empty"; once `gap_limit` consecutive empty
+        // numbers turn up, assume we have run off the end of the active fleet
+        let mut never_ridden: RangeSet<u64> = RangeSet::new();
+        let mut missing: RangeSet<u64> = RangeSet::new();
+        let mut consecutive_empty = 0usize;
+        let mut boundary = start - 1;
+        let mut n = start;
+        loop {
+            let is_known = known_numbers.contains(&n);
+            let is_ridden = is_known && ridden_numbers.contains(&n);
+            if is_ridden {
+                consecutive_empty = 0;
+                boundary = n;
+            } else {
+                consecutive_empty += 1;
+                if is_known {
+                    never_ridden.insert(n);
+                } else {
+                    missing.insert(n);
+                }
+                if consecutive_empty >= gap_limit {
+                    break;
+                }
+            }
+            n += 1;
+        }
+        never_ridden.remove_range((boundary + 1)..(n + 1));
+        missing.remove_range((boundary + 1)..(n + 1));
+
+        let response = if boundary < start {
+            format!("{} of {}: no vehicle within the first {} numbers from {} has ever been ridden.", type_code, company, gap_limit, start)
+        } else {
+            let never_ridden_str = if never_ridden.ranges().next().is_some() {
+                format_range_set(&never_ridden)
+            } else {
+                "none".to_owned()
+            };
+            let missing_str = if missing.ranges().next().is_some() {
+                format_range_set(&missing)
+            } else {
+                "none".to_owned()
+            };
+            format!(
+                "{} of {}: active range {}-{}\nNever ridden: {}\nMissing from database: {}",
+                type_code, company, start, boundary, never_ridden_str, missing_str,
+            )
+        };
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
+    }
+
+    async fn channel_command_bimcoverage(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        let company = command.options.get("company")
+            .or_else(|| command.options.get("c"))
+            .map(|v| v.as_str().unwrap())
+            .unwrap_or(config_guard.default_company.as_str());
+        if company.len() == 0 {
+            return;
+        }
+
+        let show_all =
+            command.flags.contains("a")
+            || command.flags.contains("all")
+        ;
+
+        let rider_username_input = command.rest.trim();
+        let rider_username = if rider_username_input.len() == 0 {
+            channel_message.message.sender.username.clone()
+        } else {
+            match interface.resolve_username(rider_username_input).await {
+                Some(ru) => ru,
+                None => rider_username_input.to_owned(),
+            }
+        };
+
+        let database = match self.load_bim_database(&config_guard, company) {
+            Some(db) => db,
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "No vehicle database exists for this company.",
+                ).await;
+                return;
+            },
+        };
+
+        let ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database connection. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        let ridden_rows_res = ride_conn.query(
+            "
+                SELECT DISTINCT
+                    rv.vehicle_number
+                FROM bim.rides r
+                INNER JOIN bim.ride_vehicles rv
+                    ON rv.ride_id = r.id
+                WHERE
+                    r.company = $1
+                    AND LOWER(r.rider_username) = LOWER($2)
+                    AND rv.coupling_mode = 'R'
+            ",
+            &[&company, &rider_username],
+        ).await;
+        let ridden_rows = match ridden_rows_res {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to query ridden bim vehicles: {}", e);
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to query database. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+        let ridden_vehicles: HashSet<VehicleNumber> = ridden_rows.iter()
+            .map(|row| VehicleNumber::from_string(row.get(0)))
+            .collect();
+
+        let mut type_to_known: BTreeMap<String, RangeSet<u64>> = BTreeMap::new();
+        let mut type_to_ridden: BTreeMap<String, RangeSet<u64>> = BTreeMap::new();
+        let mut type_to_stats: BTreeMap<String, BimTypeStats> = BTreeMap::new();
+        for (veh_id, veh_info) in database.iter() {
+            let veh_id_u64: u64 = match veh_id.parse() {
+                Ok(vi) => vi,
+                Err(_) => continue,
+            };
+
+            type_to_known
+                .entry(veh_info.type_code.clone())
+                .or_insert_with(|| RangeSet::new())
+                .insert(veh_id_u64);
+            let stats = type_to_stats
+                .entry(veh_info.type_code.clone())
+                .or_insert_with(|| BimTypeStats::new());
+            stats.known_vehicles += 1;
+
+            if ridden_vehicles.contains(veh_id) {
+                type_to_ridden
+                    .entry(veh_info.type_code.clone())
+                    .or_insert_with(|| RangeSet::new())
+                    .insert(veh_id_u64);
+                stats.ridden_vehicles += 1;
+            }
+        }
+
+        let mut lines: Vec<String> = Vec::new();
+        for (tp, known_ranges) in type_to_known.iter() {
+            let stats = type_to_stats.get(tp).expect("every known type has stats");
+            if stats.known_vehicles > 0 && stats.ridden_vehicles == stats.known_vehicles && !show_all {
+                // fully covered; skip unless the caller wants to see everything
+                continue;
+            }
+
+            let mut remaining_ranges = known_ranges.clone();
+            if let Some(ridden_ranges) = type_to_ridden.get(tp) {
+                for range in ridden_ranges.ranges() {
+                    remaining_ranges.remove_range(range.range.clone());
+                }
             }
+            let remaining_string = if remaining_ranges.ranges().next().is_some() {
+                format_range_set(&remaining_ranges)
+            } else {
+                "none".to_owned()
+            };
+
+            lines.push(format!(
+                "{}{}: {} ({}/{} = {:.2}%)",
+                tp, INVISIBLE_JOINER, remaining_string,
+                stats.ridden_vehicles, stats.known_vehicles, stats.ridden_known() * 100.0,
+            ));
+        }
+
+        let response = if lines.len() == 0 {
+            format!("{} has ridden every known vehicle of {}! :tada:", rider_username, company)
+        } else {
+            format!("{} still needs to ride, of {}:\n{}", rider_username, company, lines.join("\n"))
+        };
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
+    }
+
+    async fn channel_command_bimtypes(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        let company = command.options.get("company")
+            .or_else(|| command.options.get("c"))
+            .map(|v| v.as_str().unwrap())
+            .unwrap_or(config_guard.default_company.as_str());
+        if company.len() == 0 {
+            return;
         }
+        let company_name = match config_guard.company_to_definition.get(company) {
+            Some(cd) => cd.name.as_str(),
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Unknown company.",
+                ).await;
+                return;
+            },
+        };
+
+        let (stats_filter, leftover_tokens) = match StatsFilter::parse(command.rest.trim()) {
+            Ok(v) => v,
+            Err(e) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse filter: {}", e),
+                ).await;
+                return;
+            },
+        };
+        let rider_username_input = leftover_tokens.join(" ");
+        let rider_username_opt = if rider_username_input.len() == 0 {
+            None
+        } else {
+            match interface.resolve_username(&rider_username_input).await {
+                Some(ru) => Some(ru),
+                None => Some(rider_username_input.clone()),
+            }
+        };
+
+        let database = match self.load_bim_database(&config_guard, company) {
+            Some(db) => db,
+            None => HashMap::new(), // work with an empty database
+        };
+
+        let ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database connection. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        let mut response = if let Some(ru) = &rider_username_opt {
+            format!("Statistics for vehicles of {} ridden by {}:", company_name, ru)
+        } else {
+            format!("General statistics for vehicles of {}:", company_name)
+        };
+
+        // `company` is pinned by the `--company`/`-c` option above, so the filter's own company
+        // criterion (if any) would be redundant at best and contradictory at worst; every other
+        // criterion (line, weekday, hour, before/after, coupling) still applies normally.
+        let (type_to_stats, unknown_type_ridden_count) = match bim_type_stats_for_company(
+            &ride_conn, company, &database, &stats_filter, rider_username_opt.as_deref(),
+        ).await {
+            Ok(v) => v,
+            Err(e) => {
+                error!("failed to query bim types: {}", e);
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to query database. :disappointed:",
+                ).await;
+                return;
+            },
+        };
 
         // collate information
         if type_to_stats.len() == 0 {
@@ -2433,10 +3115,8 @@ impl BimPlugin {
                 }
             }
         }
-        // we have been emptying ridden_vehicles while collecting stats
-        // what remains are the unknown types
-        if ridden_vehicles.len() > 0 {
-            write_expect!(&mut response, "\n{} vehicles of unknown type ridden", ridden_vehicles.len());
+        if unknown_type_ridden_count > 0 {
+            write_expect!(&mut response, "\n{} vehicles of unknown type ridden", unknown_type_ridden_count);
         }
 
         send_channel_message!(
@@ -2463,9 +3143,23 @@ impl BimPlugin {
 
         let delete = command.flags.contains("d") || command.flags.contains("delete");
         let utc_time = command.flags.contains("u") || command.flags.contains("utc");
-        let ride_id = command.options.get("i")
+        let ride_id_spec = command.options.get("i")
             .or_else(|| command.options.get("id"))
-            .map(|cv| cv.as_i64().unwrap());
+            .map(|cv| cv.as_str().unwrap());
+        let ride_ids_opt: Option<Vec<i64>> = match ride_id_spec {
+            Some(spec) => match parse_ride_id_spec(spec) {
+                Some(ids) => Some(ids),
+                None => {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Failed to parse ride ID(s). Expected e.g. `105`, `100,102,104` or `100-110`.",
+                    ).await;
+                    return;
+                },
+            },
+            None => None,
+        };
         let rider_username = command.options.get("r")
             .or_else(|| command.options.get("rider"))
             .map(|cv| cv.as_str().unwrap())
@@ -2565,20 +3259,6 @@ impl BimPlugin {
             // FIXME: verify vehicle and line numbers against company-specific regexes?
         }
 
-        let new_timestamp_opt = if let Some(nts) = new_timestamp_str {
-            let nt = self.parse_user_timestamp(
-                nts,
-                utc_time,
-                &channel_message.channel.name,
-            ).await;
-            match nt {
-                Some(t) => Some(t),
-                None => return, // error message already output
-            }
-        } else {
-            None
-        };
-
         let new_price_opt: Option<BigDecimal> = if let Some(nps) = new_price_str {
             match nps.parse() {
                 Ok(np) => Some(np),
@@ -2627,6 +3307,7 @@ impl BimPlugin {
             Ok(txn) => txn,
             Err(e) => {
                 error!("failed to open database transaction: {}", e);
+                crate::metrics::record_failed_transaction();
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
@@ -2636,219 +3317,709 @@ impl BimPlugin {
             },
         };
 
-        let ride_row_opt_res = if let Some(rid) = ride_id {
-            ride_txn.query_opt(
-                "
-                    SELECT id, rider_username, \"timestamp\", company FROM bim.rides
-                    WHERE id=$1
-                ",
-                &[&rid],
-            ).await
-        } else {
-            ride_txn.query_opt(
-                "
-                    SELECT id, rider_username, \"timestamp\", company FROM bim.rides
+        let mut rides_to_fix = Vec::new();
+        if let Some(ids) = &ride_ids_opt {
+            for &rid in ids {
+                let ride_row_opt_res = ride_txn.query_opt(
+                    "
+                        SELECT id, rider_username, \"timestamp\", company FROM bim.rides
+                        WHERE id=$1
+                    ",
+                    &[&rid],
+                ).await;
+                match ride_row_opt_res {
+                    Err(e) => {
+                        error!("failed to obtain ride {} to modify: {}", rid, e);
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            &format!("Failed to obtain ride {} to modify. :disappointed:", rid),
+                        ).await;
+                        return;
+                    },
+                    Ok(None) => {
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            &format!("Ride {} not found. :disappointed:", rid),
+                        ).await;
+                        return;
+                    },
+                    Ok(Some(r)) => rides_to_fix.push(r),
+                }
+            }
+        } else {
+            let ride_row_opt_res = ride_txn.query_opt(
+                "
+                    SELECT id, rider_username, \"timestamp\", company FROM bim.rides
                     WHERE rider_username=$1
                     ORDER BY \"timestamp\" DESC, id DESC
                     LIMIT 1
                 ",
                 &[&rider_username],
-            ).await
-        };
-        let ride_row = match ride_row_opt_res {
-            Err(e) => {
-                error!("failed to obtain ride to modify: {}", e);
-                send_channel_message!(
-                    interface,
-                    &channel_message.channel.name,
-                    "Failed to obtain ride to modify. :disappointed:",
-                ).await;
-                return;
-            },
-            Ok(None) => {
-                send_channel_message!(
-                    interface,
-                    &channel_message.channel.name,
-                    "Ride not found. :disappointed:",
-                ).await;
-                return;
-            },
-            Ok(Some(r)) => r,
-        };
-
-        let ride_id: i64 = ride_row.get(0);
-        let rider_username: String = ride_row.get(1);
-        let ride_timestamp: DateTime<Local> = ride_row.get(2);
-        let ride_company: String = ride_row.get(3);
-
-        if !is_admin {
-            let max_edit_dur = Duration::seconds(config_guard.max_edit_s);
-            let now = Local::now();
-            if now - ride_timestamp > max_edit_dur {
-                if config_guard.max_edit_s > 0 {
+            ).await;
+            match ride_row_opt_res {
+                Err(e) => {
+                    error!("failed to obtain ride to modify: {}", e);
                     send_channel_message!(
                         interface,
                         &channel_message.channel.name,
-                        &format!("Ride {} is too old to be edited. Ask a `bim` admin for help.", ride_id),
+                        "Failed to obtain ride to modify. :disappointed:",
                     ).await;
-                } else {
+                    return;
+                },
+                Ok(None) => {
                     send_channel_message!(
                         interface,
                         &channel_message.channel.name,
-                        "You cannot edit your own rides. Ask a `bim` admin for help.",
+                        "Ride not found. :disappointed:",
                     ).await;
-                }
-                return;
+                    return;
+                },
+                Ok(Some(r)) => rides_to_fix.push(r),
             }
+        }
 
-            if rider_username != sender_username {
-                send_channel_message!(
-                    interface,
+        struct RideToFix { id: i64, rider_username: String, timestamp: DateTime<Local>, company: String }
+        let rides_to_fix: Vec<RideToFix> = rides_to_fix.iter()
+            .map(|r| RideToFix {
+                id: r.get(0),
+                rider_username: r.get(1),
+                timestamp: r.get(2),
+                company: r.get(3),
+            })
+            .collect();
+
+        // resolve the new timestamp per ride, so that relative offsets (e.g. "-2h") anchor on
+        // each ride's own existing timestamp instead of an arbitrary shared reference
+        let mut new_timestamps: HashMap<i64, DateTime<Local>> = HashMap::new();
+        if let Some(nts) = new_timestamp_str {
+            for ride in &rides_to_fix {
+                let nt = self.parse_user_timestamp(
+                    nts,
+                    utc_time,
                     &channel_message.channel.name,
-                    "Only `bim` admins can modify other riders' rides.",
+                    Some(ride.timestamp),
                 ).await;
-                return;
+                match nt {
+                    Some(t) => { new_timestamps.insert(ride.id, t); },
+                    None => return, // error message already output
+                }
+            }
+        }
+
+        // verify permissions for every ride in the batch before writing anything
+        if !is_admin {
+            let max_edit_dur = Duration::seconds(config_guard.max_edit_s);
+            let now = self.clocks.now();
+            for ride in &rides_to_fix {
+                if now - ride.timestamp > max_edit_dur {
+                    if config_guard.max_edit_s > 0 {
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            &format!("Ride {} is too old to be edited. Ask a `bim` admin for help.", ride.id),
+                        ).await;
+                    } else {
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            "You cannot edit your own rides. Ask a `bim` admin for help.",
+                        ).await;
+                    }
+                    return;
+                }
+
+                if ride.rider_username != sender_username {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Only `bim` admins can modify other riders' rides.",
+                    ).await;
+                    return;
+                }
             }
         }
 
         if delete {
-            if let Err(e) = ride_txn.execute("DELETE FROM bim.rides WHERE id=$1", &[&ride_id]).await {
-                error!("failed to delete ride {}: {}", ride_id, e);
-                send_channel_message!(
-                    interface,
-                    &channel_message.channel.name,
-                    &format!("Failed to delete ride {}.", ride_id),
-                ).await;
-                return;
+            for ride in &rides_to_fix {
+                if let Err(e) = ride_txn.execute("DELETE FROM bim.rides WHERE id=$1", &[&ride.id]).await {
+                    error!("failed to delete ride {}: {}", ride.id, e);
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &format!("Failed to delete ride {}.", ride.id),
+                    ).await;
+                    return;
+                }
             }
 
             if let Err(e) = ride_txn.commit().await {
-                error!("failed to commit changes on ride {}: {}", ride_id, e);
+                error!("failed to commit deletion of {} ride(s): {}", rides_to_fix.len(), e);
+                crate::metrics::record_failed_transaction();
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    &format!("Failed to commit ride {} changes.", ride_id),
+                    "Failed to commit ride deletion.",
                 ).await;
                 return;
             }
 
+            let response = if rides_to_fix.len() == 1 {
+                format!("Ride {} deleted.", rides_to_fix[0].id)
+            } else {
+                format!("Deleted {} rides.", rides_to_fix.len())
+            };
             send_channel_message!(
                 interface,
                 &channel_message.channel.name,
-                &format!("Ride {} deleted.", ride_id),
+                &response,
             ).await;
             return;
         }
 
         // update what there is to update
         let mut props: Vec<String> = Vec::new();
-        let mut values: Vec<&(dyn ToSql + Sync)> = Vec::new();
+        let mut base_values: Vec<&(dyn ToSql + Sync)> = Vec::new();
 
-        let (remember_new_rider, remember_new_company, remember_new_line, remember_new_timestamp, remember_new_price, remember_new_actual_price);
+        let (remember_new_rider, remember_new_company, remember_new_line, remember_new_price, remember_new_actual_price);
         if let Some(nr) = new_rider {
             remember_new_rider = nr.to_owned();
             props.push(format!("rider_username = ${}", props.len() + 1));
-            values.push(&remember_new_rider);
+            base_values.push(&remember_new_rider);
         }
         if let Some(nc) = new_company {
             remember_new_company = nc.to_owned();
             props.push(format!("company = ${}", props.len() + 1));
-            values.push(&remember_new_company);
+            base_values.push(&remember_new_company);
         }
         if let Some(nl) = new_line {
             remember_new_line = nl.to_owned();
             props.push(format!("line = ${}", props.len() + 1));
-            values.push(&remember_new_line);
-        }
-        if let Some(nts) = new_timestamp_opt {
-            remember_new_timestamp = nts.clone();
-            props.push(format!("\"timestamp\" = ${}", props.len() + 1));
-            values.push(&remember_new_timestamp);
+            base_values.push(&remember_new_line);
         }
         if let Some(np) = new_price_opt {
             remember_new_price = np.to_string();
             props.push(format!("regular_price = TO_NUMBER(${}, {})", props.len() + 1, POSTGRES_MONEY_FORMAT));
-            values.push(&remember_new_price);
+            base_values.push(&remember_new_price);
         }
         if let Some(nap) = new_actual_price_opt {
             remember_new_actual_price = nap.to_string();
             props.push(format!("actual_price = TO_NUMBER(${}, {})", props.len() + 1, POSTGRES_MONEY_FORMAT));
-            values.push(&remember_new_actual_price);
+            base_values.push(&remember_new_actual_price);
         }
 
         if props.len() > 0 {
             let props_string = props.join(", ");
             let query = format!("UPDATE bim.rides SET {} WHERE id = ${}", props_string, props.len() + 1);
-            values.push(&ride_id);
 
-            if let Err(e) = ride_txn.execute(&query, &values).await {
-                error!("failed to modify ride {}: {}", ride_id, e);
-                send_channel_message!(
-                    interface,
-                    &channel_message.channel.name,
-                    &format!("Failed to modify ride {}.", ride_id),
-                ).await;
-                return;
+            for ride in &rides_to_fix {
+                let mut values = base_values.clone();
+                values.push(&ride.id);
+
+                if let Err(e) = ride_txn.execute(&query, &values).await {
+                    error!("failed to modify ride {}: {}", ride.id, e);
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &format!("Failed to modify ride {}.", ride.id),
+                    ).await;
+                    return;
+                }
+            }
+        }
+
+        if new_timestamp_str.is_some() {
+            for ride in &rides_to_fix {
+                let new_timestamp = new_timestamps.get(&ride.id)
+                    .expect("timestamp resolved for every ride above");
+                if let Err(e) = ride_txn.execute(
+                    "UPDATE bim.rides SET \"timestamp\" = $1 WHERE id = $2",
+                    &[new_timestamp, &ride.id],
+                ).await {
+                    error!("failed to modify timestamp of ride {}: {}", ride.id, e);
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &format!("Failed to modify ride {}.", ride.id),
+                    ).await;
+                    return;
+                }
+            }
+        }
+
+        if let Some(nvs) = new_vehicles_str {
+            for ride in &rides_to_fix {
+                let this_company = new_company
+                    .unwrap_or(ride.company.as_str());
+                let this_bim_db_opt = self.load_bim_database(&config_guard, this_company);
+                let vehicles_res = spec_to_vehicles(
+                    nvs,
+                    this_bim_db_opt.as_ref(),
+                    config_guard.allow_fixed_coupling_combos,
+                );
+                let vehicles = match vehicles_res {
+                    Ok(vehicles) => vehicles,
+                    Err(e) => {
+                        error!("failed to parse vehicles of ride {}: {}", ride.id, e);
+                        let response = format!("Failed to parse vehicles of ride {}.", ride.id);
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            &response,
+                        ).await;
+                        return;
+                    },
+                };
+                if let Err(e) = replace_ride_vehicles(&ride_txn, ride.id, &vehicles).await {
+                    error!("failed to replace vehicles of ride {}: {}", ride.id, e);
+                    let response = format!("Failed to replace vehicles of ride {}.", ride.id);
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &response,
+                    ).await;
+                    return;
+                }
+            }
+        }
+
+        if let Err(e) = ride_txn.commit().await {
+            error!("failed to commit transaction while modifying {} ride(s): {}", rides_to_fix.len(), e);
+            crate::metrics::record_failed_transaction();
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                "Failed to commit transaction while modifying rides.",
+            ).await;
+            return;
+        }
+
+        let response = if rides_to_fix.len() == 1 {
+            format!("Ride {} modified.", rides_to_fix[0].id)
+        } else {
+            format!("Modified {} rides.", rides_to_fix.len())
+        };
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
+
+        // enqueue achievement recalculation
+        if config_guard.achievements_active {
+            let data = UpdateAchievementsData {
+                channel: channel_message.channel.name.clone(),
+                explicit: false,
+            };
+            self.enqueue_achievement_update(data);
+        }
+    }
+
+    async fn channel_command_bimbatchedit(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+        let sender_username = channel_message.message.sender.username.as_str();
+        let is_admin = config_guard.admin_usernames.contains(sender_username);
+        let utc_time = command.flags.contains("u") || command.flags.contains("utc");
+
+        let entries = match parse_batch_edit_spec(&command.rest) {
+            Ok(e) => e,
+            Err(msg) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("Failed to parse batch edit operations: {}", msg),
+                ).await;
+                return;
+            },
+        };
+
+        if !is_admin {
+            for entry in &entries {
+                if entry.new_rider.is_some() {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Only `bim` admins can modify a ride's rider.",
+                    ).await;
+                    return;
+                }
+
+                if entry.new_timestamp_str.is_some() {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Only `bim` admins can modify a ride's timestamp.",
+                    ).await;
+                    return;
+                }
+            }
+        }
+
+        for entry in &entries {
+            if let Some(nc) = &entry.new_company {
+                if !config_guard.company_to_definition.contains_key(nc) {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &format!("Company {:?} does not exist.", nc),
+                    ).await;
+                    return;
+                }
+            }
+        }
+
+        // resolve prices ahead of the transaction; parse_user_timestamp (for timestamps) is
+        // handled further down, once each ride's own timestamp is known to anchor relative offsets
+        let mut new_prices: HashMap<i64, BigDecimal> = HashMap::new();
+        let mut new_actual_prices: HashMap<i64, BigDecimal> = HashMap::new();
+        for entry in &entries {
+            if let Some(nps) = &entry.new_price {
+                match nps.parse() {
+                    Ok(np) => { new_prices.insert(entry.ride_id, np); },
+                    Err(_) => {
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            &format!("Failed to parse new price for ride {}.", entry.ride_id),
+                        ).await;
+                        return;
+                    },
+                }
+            }
+            if let Some(naps) = &entry.new_actual_price {
+                match naps.parse() {
+                    Ok(nap) => { new_actual_prices.insert(entry.ride_id, nap); },
+                    Err(_) => {
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            &format!("Failed to parse new actual price for ride {}.", entry.ride_id),
+                        ).await;
+                        return;
+                    },
+                }
+            }
+        }
+
+        let mut ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database connection. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+        let ride_txn = match ride_conn.transaction().await {
+            Ok(txn) => txn,
+            Err(e) => {
+                error!("failed to open database transaction: {}", e);
+                crate::metrics::record_failed_transaction();
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database transaction. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        struct RideToFix { rider_username: String, timestamp: DateTime<Local>, company: String }
+        let mut id_to_ride: HashMap<i64, RideToFix> = HashMap::new();
+        for entry in &entries {
+            if id_to_ride.contains_key(&entry.ride_id) {
+                continue;
+            }
+
+            let ride_row_opt_res = ride_txn.query_opt(
+                "
+                    SELECT rider_username, \"timestamp\", company FROM bim.rides
+                    WHERE id=$1
+                ",
+                &[&entry.ride_id],
+            ).await;
+            match ride_row_opt_res {
+                Err(e) => {
+                    error!("failed to obtain ride {} to modify: {}", entry.ride_id, e);
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &format!("Failed to obtain ride {} to modify. :disappointed:", entry.ride_id),
+                    ).await;
+                    return;
+                },
+                Ok(None) => {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &format!("Ride {} not found. :disappointed:", entry.ride_id),
+                    ).await;
+                    return;
+                },
+                Ok(Some(r)) => {
+                    id_to_ride.insert(entry.ride_id, RideToFix {
+                        rider_username: r.get(0),
+                        timestamp: r.get(1),
+                        company: r.get(2),
+                    });
+                },
+            }
+        }
+
+        // resolve timestamps now that each ride's own timestamp is known, so relative offsets
+        // (e.g. "-2h") anchor on the ride being edited rather than an arbitrary shared reference
+        let mut new_timestamps: HashMap<i64, DateTime<Local>> = HashMap::new();
+        for entry in &entries {
+            if let Some(nts) = &entry.new_timestamp_str {
+                let reference = id_to_ride.get(&entry.ride_id)
+                    .expect("ride looked up for every entry above")
+                    .timestamp;
+                let nt = self.parse_user_timestamp(
+                    nts,
+                    utc_time,
+                    &channel_message.channel.name,
+                    Some(reference),
+                ).await;
+                match nt {
+                    Some(t) => { new_timestamps.insert(entry.ride_id, t); },
+                    None => return, // error message already output
+                }
+            }
+        }
+
+        // verify permissions for every ride in the batch before writing anything
+        if !is_admin {
+            let max_edit_dur = Duration::seconds(config_guard.max_edit_s);
+            let now = self.clocks.now();
+            for (ride_id, ride) in &id_to_ride {
+                if now - ride.timestamp > max_edit_dur {
+                    if config_guard.max_edit_s > 0 {
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            &format!("Ride {} is too old to be edited. Ask a `bim` admin for help.", ride_id),
+                        ).await;
+                    } else {
+                        send_channel_message!(
+                            interface,
+                            &channel_message.channel.name,
+                            "You cannot edit your own rides. Ask a `bim` admin for help.",
+                        ).await;
+                    }
+                    return;
+                }
+
+                if ride.rider_username != sender_username {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Only `bim` admins can modify other riders' rides.",
+                    ).await;
+                    return;
+                }
+            }
+        }
+
+        // apply each operation; abort (and roll back the whole batch) on the first failure
+        let mut outcomes: Vec<(i64, Result<(), String>)> = Vec::new();
+        let mut failed = false;
+        for entry in &entries {
+            if failed {
+                outcomes.push((entry.ride_id, Err("not attempted; batch aborted".to_owned())));
+                continue;
+            }
+
+            let ride = id_to_ride.get(&entry.ride_id)
+                .expect("ride looked up for every entry above");
+
+            if entry.delete {
+                match ride_txn.execute("DELETE FROM bim.rides WHERE id=$1", &[&entry.ride_id]).await {
+                    Ok(_) => outcomes.push((entry.ride_id, Ok(()))),
+                    Err(e) => {
+                        error!("failed to delete ride {} during batch edit: {}", entry.ride_id, e);
+                        outcomes.push((entry.ride_id, Err("failed to delete".to_owned())));
+                        failed = true;
+                    },
+                }
+                continue;
+            }
+
+            let mut props: Vec<String> = Vec::new();
+            let mut values: Vec<&(dyn ToSql + Sync)> = Vec::new();
+            if let Some(nr) = &entry.new_rider {
+                props.push(format!("rider_username = ${}", props.len() + 1));
+                values.push(nr);
+            }
+            if let Some(nc) = &entry.new_company {
+                props.push(format!("company = ${}", props.len() + 1));
+                values.push(nc);
+            }
+            if let Some(nl) = &entry.new_line {
+                props.push(format!("line = ${}", props.len() + 1));
+                values.push(nl);
+            }
+            if let Some(nts) = new_timestamps.get(&entry.ride_id) {
+                props.push(format!("\"timestamp\" = ${}", props.len() + 1));
+                values.push(nts);
+            }
+            if let Some(np) = new_prices.get(&entry.ride_id) {
+                props.push(format!("regular_price = TO_NUMBER(${}, {})", props.len() + 1, POSTGRES_MONEY_FORMAT));
+                values.push(np);
+            }
+            if let Some(nap) = new_actual_prices.get(&entry.ride_id) {
+                props.push(format!("actual_price = TO_NUMBER(${}, {})", props.len() + 1, POSTGRES_MONEY_FORMAT));
+                values.push(nap);
+            }
+
+            if props.len() > 0 {
+                let query = format!("UPDATE bim.rides SET {} WHERE id = ${}", props.join(", "), props.len() + 1);
+                values.push(&entry.ride_id);
+
+                if let Err(e) = ride_txn.execute(&query, &values).await {
+                    error!("failed to modify ride {} during batch edit: {}", entry.ride_id, e);
+                    outcomes.push((entry.ride_id, Err("failed to modify".to_owned())));
+                    failed = true;
+                    continue;
+                }
+            }
+
+            if let Some(nvs) = &entry.new_vehicles {
+                let this_company = entry.new_company.as_deref()
+                    .unwrap_or(ride.company.as_str());
+                let this_bim_db_opt = self.load_bim_database(&config_guard, this_company);
+                let vehicles_res = spec_to_vehicles(
+                    nvs,
+                    this_bim_db_opt.as_ref(),
+                    config_guard.allow_fixed_coupling_combos,
+                );
+                let vehicles = match vehicles_res {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("failed to parse vehicles of ride {} during batch edit: {}", entry.ride_id, e);
+                        outcomes.push((entry.ride_id, Err("failed to parse vehicles".to_owned())));
+                        failed = true;
+                        continue;
+                    },
+                };
+                if let Err(e) = replace_ride_vehicles(&ride_txn, entry.ride_id, &vehicles).await {
+                    error!("failed to replace vehicles of ride {} during batch edit: {}", entry.ride_id, e);
+                    outcomes.push((entry.ride_id, Err("failed to replace vehicles".to_owned())));
+                    failed = true;
+                    continue;
+                }
+            } else if entry.freshen {
+                // re-derive this ride's vehicles from the vehicle database, exactly as
+                // channel_command_bimfreshen does, ignoring fixed-coupling vehicles since those
+                // are always taken from the vehicle database anyway
+                let freshen_rows_res = ride_txn.query(
+                    "
+                        SELECT vehicle_number, coupling_mode FROM bim.rides_and_vehicles
+                        WHERE id = $1
+                        AND coupling_mode <> 'F'
+                        ORDER BY spec_position
+                    ",
+                    &[&entry.ride_id],
+                ).await;
+                let freshen_rows = match freshen_rows_res {
+                    Ok(fr) => fr,
+                    Err(e) => {
+                        error!("failed to query vehicles of ride {} to freshen during batch edit: {}", entry.ride_id, e);
+                        outcomes.push((entry.ride_id, Err("failed to query vehicles to freshen".to_owned())));
+                        failed = true;
+                        continue;
+                    },
+                };
+
+                let mut vehicle_spec = String::new();
+                for freshen_row in freshen_rows {
+                    let vehicle_number_str: String = freshen_row.get(0);
+                    let coupling_mode: String = freshen_row.get(1);
+                    let vehicle_number: VehicleNumber = vehicle_number_str.into();
+
+                    assert!(coupling_mode == "R" || coupling_mode == "E");
+                    let explicitly_ridden = coupling_mode == "R";
+
+                    if vehicle_spec.len() > 0 {
+                        vehicle_spec.push('+');
+                    }
+                    vehicle_spec.push_str(vehicle_number.as_str());
+                    if explicitly_ridden {
+                        vehicle_spec.push('!');
+                    }
+                }
+
+                let this_company = entry.new_company.as_deref()
+                    .unwrap_or(ride.company.as_str());
+                let this_bim_db_opt = self.load_bim_database(&config_guard, this_company);
+                let vehicles_res = spec_to_vehicles(
+                    &vehicle_spec,
+                    this_bim_db_opt.as_ref(),
+                    config_guard.allow_fixed_coupling_combos,
+                );
+                let vehicles = match vehicles_res {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("failed to reconstruct vehicles of ride {} during batch edit: {}", entry.ride_id, e);
+                        outcomes.push((entry.ride_id, Err("failed to reconstruct vehicles".to_owned())));
+                        failed = true;
+                        continue;
+                    },
+                };
+                if let Err(e) = replace_ride_vehicles(&ride_txn, entry.ride_id, &vehicles).await {
+                    error!("failed to replace (freshened) vehicles of ride {} during batch edit: {}", entry.ride_id, e);
+                    outcomes.push((entry.ride_id, Err("failed to replace vehicles".to_owned())));
+                    failed = true;
+                    continue;
+                }
             }
+
+            outcomes.push((entry.ride_id, Ok(())));
         }
 
-        if let Some(nvs) = new_vehicles_str {
-            let this_company = new_company
-                .unwrap_or(ride_company.as_str());
-            let this_bim_db_opt = self.load_bim_database(&config_guard, this_company);
-            let vehicles_res = spec_to_vehicles(
-                nvs,
-                this_bim_db_opt.as_ref(),
-                config_guard.allow_fixed_coupling_combos,
-            );
-            let vehicles = match vehicles_res {
-                Ok(vehicles) => vehicles,
-                Err(e) => {
-                    error!("failed to parse vehicles of ride {}: {}", ride_id, e);
-                    let response = format!("Failed to parse vehicles of ride {}.", ride_id);
-                    send_channel_message!(
-                        interface,
-                        &channel_message.channel.name,
-                        &response,
-                    ).await;
-                    return;
-                },
-            };
-            if let Err(e) = replace_ride_vehicles(&ride_txn, ride_id, &vehicles).await {
-                error!("failed to replace vehicles of ride {}: {}", ride_id, e);
-                let response = format!("Failed to replace vehicles of ride {}.", ride_id);
-                send_channel_message!(
-                    interface,
-                    &channel_message.channel.name,
-                    &response,
-                ).await;
-                return;
+        if failed {
+            if let Err(e) = ride_txn.rollback().await {
+                error!("failed to roll back failed batch edit: {}", e);
+            }
+        } else if let Err(e) = ride_txn.commit().await {
+            error!("failed to commit batch edit of {} ride(s): {}", outcomes.len(), e);
+            crate::metrics::record_failed_transaction();
+            failed = true;
+            for outcome in &mut outcomes {
+                if outcome.1.is_ok() {
+                    outcome.1 = Err("rolled back; failed to commit batch".to_owned());
+                }
             }
         }
 
-        if let Err(e) = ride_txn.commit().await {
-            error!("failed to commit transaction while modifying ride {}: {}", ride_id, e);
-            send_channel_message!(
-                interface,
-                &channel_message.channel.name,
-                &format!("Failed to commit transaction while modifying ride {}.", ride_id),
-            ).await;
-            return;
+        let mut response = String::new();
+        write!(response, "Batch edit ({} ride(s), {}):\n```", outcomes.len(), if failed { "rolled back" } else { "committed" }).unwrap();
+        for (ride_id, outcome) in &outcomes {
+            match outcome {
+                Ok(()) => write!(response, "\n{}: OK", ride_id).unwrap(),
+                Err(msg) => write!(response, "\n{}: FAILED ({})", ride_id, msg).unwrap(),
+            }
         }
-
+        response.push_str("\n```");
         send_channel_message!(
             interface,
             &channel_message.channel.name,
-            &format!("Ride {} modified.", ride_id),
+            &response,
         ).await;
 
-        // enqueue achievement recalculation
-        if config_guard.achievements_active {
+        if !failed && config_guard.achievements_active {
             let data = UpdateAchievementsData {
                 channel: channel_message.channel.name.clone(),
                 explicit: false,
             };
-            let _ = self.achievement_update_sender.send(data);
+            self.enqueue_achievement_update(data);
         }
     }
 
@@ -2860,7 +4031,7 @@ impl BimPlugin {
 
         let config_guard = self.config.read().await;
 
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -2902,10 +4073,10 @@ impl BimPlugin {
                     WHERE vadrc2.rider_count > vadrc.rider_count
                 )
             ",
-            "AND rav.\"timestamp\" >= $1",
-            "",
+            "rav.\"timestamp\"",
             lookback_range,
             &[],
+            self.clocks.as_ref(),
         ).await;
         let ride_rows = match ride_rows_res {
             Ok(rr) => rr,
@@ -3015,7 +4186,117 @@ impl BimPlugin {
             channel: channel_message.channel.name.clone(),
             explicit: true,
         };
-        let _ = self.achievement_update_sender.send(data);
+        self.enqueue_achievement_update(data);
+    }
+
+    /// Looks up an achievement by ID or (case-insensitively) by name, lists every rider who has
+    /// unlocked it in unlock order, and reports the global unlock count alongside the rarest and
+    /// most common achievements overall.
+    async fn channel_command_bimachievers(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let query_input = command.rest.trim();
+        if query_input.len() == 0 {
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                "Please specify an achievement ID or name.",
+            ).await;
+            return;
+        }
+
+        let ach_def_opt = if let Ok(id) = query_input.parse::<i64>() {
+            ACHIEVEMENT_DEFINITIONS.iter().find(|ad| ad.id == id)
+        } else {
+            ACHIEVEMENT_DEFINITIONS.iter().find(|ad| ad.name.eq_ignore_ascii_case(query_input))
+        };
+        let ach_def = match ach_def_opt {
+            Some(ad) => ad,
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("No achievement found matching {:?}. :disappointed:", query_input),
+                ).await;
+                return;
+            },
+        };
+
+        let config_guard = self.config.read().await;
+        let ride_conn = match connect_ride_db(&config_guard).await {
+            Ok(c) => c,
+            Err(_) => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to open database connection. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        let ach_id_to_unlocks = match achievement_unlock_ranks(&ride_conn).await {
+            Ok(a) => a,
+            Err(e) => {
+                error!("failed to obtain achievement unlock ranks: {}", e);
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to obtain achievement unlock ranks. :disappointed:",
+                ).await;
+                return;
+            },
+        };
+
+        let empty_unlocks = Vec::new();
+        let unlocks = ach_id_to_unlocks.get(&ach_def.id).unwrap_or(&empty_unlocks);
+
+        let mut response = format!(
+            "*{}* ({}): {} unlocker{}\n```",
+            ach_def.name,
+            ach_def.description,
+            unlocks.len(),
+            if unlocks.len() == 1 { "" } else { "s" },
+        );
+        for unlock in unlocks {
+            write_expect!(response, "\n{}. {} -- ", ordinal(unlock.rank), unlock.rider_username);
+            canonical_date_format(&mut response, &unlock.achieved_on, false, false).unwrap();
+        }
+        if unlocks.len() == 0 {
+            write_expect!(response, "\n(nobody yet)");
+        }
+        response.push_str("\n```");
+
+        let id_to_count: Vec<(i64, usize)> = ACHIEVEMENT_DEFINITIONS.iter()
+            .map(|ad| (ad.id, ach_id_to_unlocks.get(&ad.id).map(|u| u.len()).unwrap_or(0)))
+            .collect();
+        let rarest = id_to_count.iter().min_by_key(|(_id, count)| *count);
+        let most_common = id_to_count.iter().max_by_key(|(_id, count)| *count);
+        if let (Some((rarest_id, rarest_count)), Some((common_id, common_count))) = (rarest, most_common) {
+            let rarest_name = ACHIEVEMENT_DEFINITIONS.iter()
+                .find(|ad| ad.id == *rarest_id)
+                .map(|ad| ad.name.to_string())
+                .unwrap_or_else(|| "?".to_owned());
+            let common_name = ACHIEVEMENT_DEFINITIONS.iter()
+                .find(|ad| ad.id == *common_id)
+                .map(|ad| ad.name.to_string())
+                .unwrap_or_else(|| "?".to_owned());
+            write_expect!(
+                response,
+                "\nRarest achievement: *{}* ({} unlocker{}); most common achievement: *{}* ({} unlocker{})",
+                rarest_name, rarest_count, if *rarest_count == 1 { "" } else { "s" },
+                common_name, common_count, if *common_count == 1 { "" } else { "s" },
+            );
+        }
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
     }
 
     async fn channel_command_bimop(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
@@ -3101,39 +4382,8 @@ impl BimPlugin {
             },
         };
 
-        let query_string = format!(
-            "
-                SELECT innerquery.rider_username, CAST(COUNT(*) AS bigint) vehicle_count
-                FROM (
-                    SELECT DISTINCT rav1.rider_username, rav1.company, rav1.vehicle_number
-                    FROM bim.rides_and_vehicles rav1
-                    WHERE rav1.coupling_mode = 'R'
-                    AND NOT EXISTS (
-                        -- same vehicle, later timestamp
-                        SELECT 1
-                        FROM bim.rides_and_vehicles rav2
-                        WHERE rav2.company = rav1.company
-                        AND rav2.vehicle_number = rav1.vehicle_number
-                        AND rav2.coupling_mode = rav1.coupling_mode
-                        AND rav2.\"timestamp\" > rav1.\"timestamp\"
-                    )
-                ) innerquery
-                {}
-                GROUP BY innerquery.rider_username
-                ORDER BY
-                    vehicle_count DESC,
-                    rider_username
-            ",
-            if company_opt.is_some() { "WHERE innerquery.company = $1" } else { "" },
-        );
-        let mut query_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(1);
-        if company_opt.is_some() {
-            query_params.push(&company_opt);
-        }
-
-        let ride_rows_res = ride_conn.query(&query_string, &query_params).await;
-        let ride_rows = match ride_rows_res {
-            Ok(rr) => rr,
+        let rider_vehicle_counts = match last_rider_vehicle_counts(&ride_conn, company_opt).await {
+            Ok(r) => r,
             Err(e) => {
                 error!("failed to obtain last vehicles: {}", e);
                 send_channel_message!(
@@ -3144,7 +4394,7 @@ impl BimPlugin {
                 return;
             },
         };
-        if ride_rows.len() == 0 {
+        if rider_vehicle_counts.len() == 0 {
             send_channel_message!(
                 interface,
                 &channel_message.channel.name,
@@ -3153,9 +4403,7 @@ impl BimPlugin {
             return;
         }
         let mut text = "Last rider in this number of vehicles:".to_owned();
-        for ride_row in ride_rows {
-            let rider_username: String = ride_row.get(0);
-            let vehicle_count: i64 = ride_row.get(1);
+        for (rider_username, vehicle_count) in rider_vehicle_counts {
             write_expect!(text, "\n{}: {}", rider_username, vehicle_count);
         }
         send_channel_message!(
@@ -3248,6 +4496,8 @@ impl BimPlugin {
             Some(i) => i,
         };
 
+        let want_json = command.flags.contains("j") || command.flags.contains("json");
+
         let rider_username_input = command.rest.trim();
         let rider_username_opt = if rider_username_input.len() == 0 {
             None
@@ -3388,6 +4638,35 @@ impl BimPlugin {
         // sort by timestamp, then by ID
         rides_sorted.sort_by_key(|tuple| (tuple.1, tuple.0));
 
+        if want_json {
+            let rides_json: Vec<BimQueryRide> = rides_sorted.iter()
+                .map(|ride| {
+                    let (id, timestamp, rider, line, vehicles, taken_from) = ride;
+                    BimQueryRide {
+                        ride_id: *id,
+                        timestamp: **timestamp,
+                        rider: rider.to_string(),
+                        line: line.map(|ln| ln.to_string()),
+                        vehicles: vehicles.to_string(),
+                        taken_from: taken_from.map(|tf| tf.to_string()),
+                    }
+                })
+                .collect();
+            let result = BimQueryResult::Rides(rides_json);
+            let attachment = Attachment::new(
+                serialize_bim_query_result(&result),
+                "recentbimrides.json".to_owned(),
+                "application/json".to_owned(),
+                None,
+            );
+            interface.send_channel_message_with_attachment(
+                &channel_message.channel.name,
+                OutgoingMessageWithAttachmentBuilder::new(attachment)
+                    .build()
+            ).await;
+            return;
+        }
+
         // assemble ride lines
         let mut ride_lines = String::from("```");
         for ride in rides_sorted.iter() {
@@ -3503,6 +4782,7 @@ impl BimPlugin {
             Ok(txn) => txn,
             Err(e) => {
                 error!("failed to open database transaction: {}", e);
+                crate::metrics::record_failed_transaction();
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
@@ -3619,6 +4899,7 @@ impl BimPlugin {
 
         if let Err(e) = ride_txn.commit().await {
             error!("failed to commit transaction: {}", e);
+            crate::metrics::record_failed_transaction();
             send_channel_message!(
                 interface,
                 &channel_message.channel.name,
@@ -3639,7 +4920,7 @@ impl BimPlugin {
                 channel: channel_message.channel.name.clone(),
                 explicit: false,
             };
-            let _ = self.achievement_update_sender.send(data);
+            self.enqueue_achievement_update(data);
         }
     }
 
@@ -3649,11 +4930,12 @@ impl BimPlugin {
             Some(i) => i,
         };
 
+        let want_json = command.flags.contains("j") || command.flags.contains("json");
         let sort_by_number =
             command.flags.contains("n")
             || command.flags.contains("sort-by-number")
         ;
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -3664,7 +4946,8 @@ impl BimPlugin {
                 return;
             },
         };
-        let lookback_start_opt = lookback_range.start_timestamp();
+        let lookback_start_opt = lookback_range.start_timestamp(self.clocks.as_ref());
+        let lookback_end_opt = lookback_range.end_timestamp();
 
         let config_guard = self.config.read().await;
         let ride_conn = match connect_ride_db(&config_guard).await {
@@ -3680,11 +4963,15 @@ impl BimPlugin {
         };
 
         let mut criteria = Vec::new();
-        let mut query_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(1);
+        let mut query_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(2);
         if let Some(lookback_start) = &lookback_start_opt {
             criteria.push(format!("rvto.\"timestamp\" >= ${}", query_params.len() + 1));
             query_params.push(lookback_start);
         }
+        if let Some(lookback_end) = &lookback_end_opt {
+            criteria.push(format!("rvto.\"timestamp\" < ${}", query_params.len() + 1));
+            query_params.push(lookback_end);
+        }
 
         let query = format!(
             "
@@ -3741,6 +5028,29 @@ impl BimPlugin {
             riders_and_balances.sort_unstable_by_key(|(r, pm)| (pm.minus - pm.plus, r.clone()));
         }
 
+        if want_json {
+            let balances_json: Vec<BimQueryRiderBalance> = riders_and_balances.iter()
+                .map(|(rider, pm)| BimQueryRiderBalance {
+                    rider: rider.clone(),
+                    plus: pm.plus,
+                    minus: pm.minus,
+                })
+                .collect();
+            let result = BimQueryResult::RiderBalances(balances_json);
+            let attachment = Attachment::new(
+                serialize_bim_query_result(&result),
+                "lastbimriderbalance.json".to_owned(),
+                "application/json".to_owned(),
+                None,
+            );
+            interface.send_channel_message_with_attachment(
+                &channel_message.channel.name,
+                OutgoingMessageWithAttachmentBuilder::new(attachment)
+                    .build()
+            ).await;
+            return;
+        }
+
         let response_body = if riders_and_balances.len() > 0 {
             let mut ret = "Last-rider balances:".to_owned();
             for (rider, pm) in &riders_and_balances {
@@ -3769,11 +5079,12 @@ impl BimPlugin {
             Some(i) => i,
         };
 
+        let want_json = command.flags.contains("j") || command.flags.contains("json");
         let sort_by_number =
             command.flags.contains("n")
             || command.flags.contains("sort-by-number")
         ;
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -3784,7 +5095,8 @@ impl BimPlugin {
                 return;
             },
         };
-        let lookback_start_opt = lookback_range.start_timestamp();
+        let lookback_start_opt = lookback_range.start_timestamp(self.clocks.as_ref());
+        let lookback_end_opt = lookback_range.end_timestamp();
 
         let config_guard = self.config.read().await;
         let ride_conn = match connect_ride_db(&config_guard).await {
@@ -3799,12 +5111,24 @@ impl BimPlugin {
             },
         };
 
+        // The CTEs below extract the numeric portions of the vehicle number and line with
+        // PostgreSQL-only building blocks (the `bim.char_to_bigint_or_null` stored function and
+        // `SIMILAR TO`/`SUBSTRING ... SIMILAR` regex-style matching, neither of which SQLite has a
+        // built-in equivalent for), so this query stays PostgreSQL-only regardless of
+        // `Config::ride_db_backend`; only the divisibility predicate at the end is routed through
+        // `RideDb` so it is at least consistent with the other ride-database queries.
+        let ride_db = config_guard.ride_db_backend.ride_db();
+
         let mut criteria = Vec::new();
-        let mut query_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(1);
+        let mut query_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(2);
         if let Some(lookback_start) = &lookback_start_opt {
             criteria.push(format!("AND nnrn.\"timestamp\" >= ${}", query_params.len() + 1));
             query_params.push(lookback_start);
         }
+        if let Some(lookback_end) = &lookback_end_opt {
+            criteria.push(format!("AND nnrn.\"timestamp\" < ${}", query_params.len() + 1));
+            query_params.push(lookback_end);
+        }
 
         let query = format!(
             // SUBSTRING SIMILAR extraction is done by wrapping the subpattern
@@ -3832,10 +5156,11 @@ impl BimPlugin {
                     nnrn.rider_username,
                     CAST(SUM(nnrn.line_number) AS bigint) div_score
                 FROM not_null_ride_numbers nnrn
-                WHERE MOD(nnrn.vehicle_number, nnrn.line_number) = 0
+                WHERE {}
                 {}
                 GROUP BY nnrn.rider_username
             ",
+            ride_db.divisible_by("nnrn.vehicle_number", "nnrn.line_number"),
             criteria.join(" "),
         );
         let rides = match ride_conn.query(&query, &query_params).await {
@@ -3865,6 +5190,28 @@ impl BimPlugin {
             riders_and_scores.sort_unstable_by_key(|(r, _score)| r.clone());
         }
 
+        if want_json {
+            let scores_json: Vec<BimQueryRiderScore> = riders_and_scores.iter()
+                .map(|(rider, score)| BimQueryRiderScore {
+                    rider: rider.clone(),
+                    score: *score,
+                })
+                .collect();
+            let result = BimQueryResult::RiderScores(scores_json);
+            let attachment = Attachment::new(
+                serialize_bim_query_result(&result),
+                "bimdivscore.json".to_owned(),
+                "application/json".to_owned(),
+                None,
+            );
+            interface.send_channel_message_with_attachment(
+                &channel_message.channel.name,
+                OutgoingMessageWithAttachmentBuilder::new(attachment)
+                    .build()
+            ).await;
+            return;
+        }
+
         let response_body = if riders_and_scores.len() > 0 {
             let mut ret = "Divisibility scores:".to_owned();
             for (rider, score) in &riders_and_scores {
@@ -3934,87 +5281,19 @@ impl BimPlugin {
             },
         };
 
-        let query = "
-            SELECT rarv.rider_username
-            FROM bim.rides_and_ridden_vehicles rarv
-            WHERE
-                rarv.company = $1
-                AND rarv.vehicle_number = $2
-                AND NOT EXISTS (
-                    SELECT 1
-                    FROM bim.rides_and_ridden_vehicles rarv2
-                    WHERE rarv2.company = rarv.company
-                    AND rarv2.vehicle_number = rarv.vehicle_number
-                    AND rarv2.\"timestamp\" > rarv.\"timestamp\"
-                )
-        ";
-        let statement = match ride_conn.prepare(query).await {
-            Ok(s) => s,
+        let rider_to_coupling_length_to_count = match fixed_coupling_monopolies(&ride_conn, company, &database).await {
+            Ok(r) => r,
             Err(e) => {
-                error!("failed to prepare rider query: {}", e);
+                error!("failed to compute fixed-coupling monopolies for company {:?}: {}", company, e);
                 send_channel_message!(
                     interface,
                     &channel_message.channel.name,
-                    "Failed to prepare rider query. :disappointed:",
+                    "Failed to compute fixed-coupling monopolies. :disappointed:",
                 ).await;
                 return;
             },
         };
 
-        let mut rider_to_coupling_length_to_count = BTreeMap::new();
-        for vehicle in database.values() {
-            if vehicle.fixed_coupling.len() == 0 {
-                // not a fixed coupling
-                continue;
-            }
-
-            if !vehicle.fixed_coupling.first().map(|f| f == &vehicle.number).unwrap_or(false) {
-                // we are not the first vehicle in the coupling
-                continue;
-            }
-
-            // alright, look it up
-            let mut riders = HashSet::new();
-            for vehicle_number in &vehicle.fixed_coupling {
-                match ride_conn.query(&statement, &[&company, &vehicle_number.as_str()]).await {
-                    Ok(mut rr) => {
-                        if rr.len() == 0 {
-                            // this vehicle does not have a last rider
-                            // => nobody can have a monopoly
-                            riders.clear();
-                            break;
-                        }
-                        if rr.len() != 1 {
-                            error!("obtained more than one rider row ({} rows) for company {:?} vehicle {:?}", rr.len(), company, vehicle.number);
-                            riders.clear();
-                            break;
-                        }
-
-                        let row = rr.remove(0);
-                        let rider_username: String = row.get(0);
-                        riders.insert(rider_username);
-                    },
-                    Err(e) => {
-                        error!("failed to obtain latest rider for company {:?} vehicle {:?}: {}", company, vehicle.number, e);
-                        riders.clear();
-                        break;
-                    },
-                };
-            }
-
-            if riders.len() == 1 {
-                // monopoly!
-                let rider_username = riders.iter().nth(0).map(|ru| ru.clone()).unwrap();
-
-                let monopoly_count = rider_to_coupling_length_to_count
-                    .entry(rider_username)
-                    .or_insert_with(|| BTreeMap::new())
-                    .entry(vehicle.fixed_coupling.len())
-                    .or_insert(0usize);
-                *monopoly_count += 1;
-            }
-        }
-
         if rider_to_coupling_length_to_count.len() == 0 {
             send_channel_message!(
                 interface,
@@ -4061,7 +5340,7 @@ impl BimPlugin {
         };
 
         let config_guard = self.config.read().await;
-        let lookback_range = match Self::lookback_range_from_command(command) {
+        let lookback_range = match Self::lookback_range_from_command(command, self.clocks.as_ref()) {
             Some(lr) => lr,
             None => {
                 send_channel_message!(
@@ -4084,27 +5363,9 @@ impl BimPlugin {
             },
         };
 
-        let query_template = format!(
-            "
-                SELECT TO_CHAR(COALESCE(SUM(r.regular_price - r.actual_price), 0), {}) sums
-                FROM bim.rides r
-                WHERE
-                    r.rider_username = $1
-                    {{LOOKBACK_TIMESTAMP}}
-            ",
-            POSTGRES_MONEY_FORMAT,
-        );
-
-        let rows_res = Self::timestamp_query(
-            &ride_conn,
-            &query_template,
-            "AND r.\"timestamp\" >= $2",
-            "",
-            lookback_range,
-            &[&channel_message.message.sender.username],
-        ).await;
-        let rows = match rows_res {
-            Ok(r) => r,
+        let ride_db = config_guard.ride_db_backend.ride_db();
+        let company_savings = match rider_cost_savings(&ride_conn, &channel_message.message.sender.username, lookback_range, ride_db, self.clocks.as_ref()).await {
+            Ok(s) => s,
             Err(e) => {
                 error!("failed to query bim cost: {}", e);
                 send_channel_message!(
@@ -4115,24 +5376,69 @@ impl BimPlugin {
                 return;
             },
         };
-        if rows.len() > 0 {
-            let savings_string: String = rows[0].get(0);
-            let savings: BigDecimal = match savings_string.parse() {
-                Ok(s) => s,
-                Err(e) => {
-                    error!("failed to parse savings {:?}: {}", savings_string, e);
-                    return;
-                },
-            };
 
+        // group each company's savings by its currency (several companies may share one)
+        let mut currency_totals: BTreeMap<String, BigDecimal> = BTreeMap::new();
+        for (company, savings) in company_savings {
+            let currency = config_guard.company_to_definition.get(&company)
+                .map(|cd| cd.currency.clone())
+                .unwrap_or_else(|| company.clone());
+            *currency_totals.entry(currency).or_insert_with(BigDecimal::zero) += savings;
+        }
+
+        let target_currency = match command.options.get("currency").or_else(|| command.options.get("C")) {
+            Some(v) => v.as_str().unwrap().to_owned(),
+            None => config_guard.company_to_definition.get(config_guard.default_company.as_str())
+                .map(|cd| cd.currency.clone())
+                .or_else(|| currency_totals.keys().next().cloned())
+                .unwrap_or_else(|| "EUR".to_owned()),
+        };
+
+        if currency_totals.len() == 0 {
             send_channel_message!(
                 interface,
                 &channel_message.channel.name,
-                &format!("@{} You have saved {}.", channel_message.message.sender.username, savings),
+                &format!("@{} You have saved {}.", channel_message.message.sender.username, format_money(&BigDecimal::zero(), &target_currency)),
             ).await;
-        } else {
-            error!("no rows?!");
+            return;
+        }
+
+        let mut grand_total = BigDecimal::zero();
+        let mut subtotal_strings: Vec<String> = Vec::new();
+        let mut untracked_strings: Vec<String> = Vec::new();
+        for (currency, subtotal) in &currency_totals {
+            match exchange_rate(&config_guard.currency_exchange_rates, currency, &target_currency) {
+                Some(rate) => {
+                    grand_total += subtotal * &rate;
+                    if currency == &target_currency {
+                        subtotal_strings.push(format_money(subtotal, currency));
+                    } else {
+                        subtotal_strings.push(format!("{} @ {}", format_money(subtotal, currency), rate.with_scale(4)));
+                    }
+                },
+                None => {
+                    untracked_strings.push(format_money(subtotal, currency));
+                },
+            }
+        }
+
+        let mut response = format!(
+            "@{} You have saved {}",
+            channel_message.message.sender.username, format_money(&grand_total, &target_currency),
+        );
+        if subtotal_strings.len() > 1 || untracked_strings.len() > 0 {
+            write_expect!(&mut response, " ({})", subtotal_strings.join(" + "));
+        }
+        if untracked_strings.len() > 0 {
+            write_expect!(&mut response, ", plus {} for which no exchange rate is configured", untracked_strings.join(" + "));
         }
+        write_expect!(&mut response, ".");
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
     }
 
     fn english_adverbial_number(num: i64) -> String {
@@ -4154,7 +5460,19 @@ impl BimPlugin {
         }
     }
 
-    async fn parse_user_timestamp(&self, timestamp_str: &str, utc_time: bool, channel_name: &str) -> Option<DateTime<Local>> {
+    /// Parses a timestamp given by a user, either as an absolute timestamp in
+    /// `TIMESTAMP_INPUT_FORMAT` or, if that fails, as a relative expression handled by
+    /// [`try_parse_relative_edit_timestamp`] (e.g. `"-2h"`, `"5 minutes ago"`, `"yesterday 18:30"`).
+    /// `reference_timestamp`, if given, anchors relative offsets like `"-2h"` on the ride being
+    /// edited instead of the current time; pass `None` when there is no such ride (e.g. when
+    /// registering a new ride).
+    async fn parse_user_timestamp(
+        &self,
+        timestamp_str: &str,
+        utc_time: bool,
+        channel_name: &str,
+        reference_timestamp: Option<DateTime<Local>>,
+    ) -> Option<DateTime<Local>> {
         let interface = match self.interface.upgrade() {
             Some(rbi) => rbi,
             None => return None,
@@ -4163,6 +5481,9 @@ impl BimPlugin {
         let ndt = match try_parse_timestamp(timestamp_str) {
             Some(ndt) => ndt,
             None => {
+                if let Some(relative) = try_parse_relative_edit_timestamp(self.clocks.as_ref(), timestamp_str, reference_timestamp) {
+                    return Some(relative);
+                }
                 send_channel_message!(
                     interface,
                     channel_name,
@@ -4310,6 +5631,24 @@ impl RocketBotPlugin for BimPlugin {
                 .add_lookback_flags()
                 .build()
         ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "bimexport",
+                "bim",
+                "{cpfx}bimexport [FILTER] [USERNAME]",
+                "Exports raw ride history matching the given filter as a CSV attachment.",
+            )
+                .build()
+        ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "topbimstreaks",
+                "bim",
+                "{cpfx}topbimstreaks [FILTER] [USERNAME]",
+                "Returns the longest consecutive-day riding streaks.",
+            )
+                .build()
+        ).await;
         my_interface.register_channel_command(
             &CommandDefinitionBuilder::new(
                 "bimridertypes",
@@ -4349,6 +5688,19 @@ impl RocketBotPlugin for BimPlugin {
                 .add_option("c", CommandValueType::String)
                 .build()
         ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "bimcoverage",
+                "bim",
+                "{cpfx}bimcoverage [{sopfx}a] [{sopfx}c COMPANY] [RIDER]",
+                "Lists the vehicles a rider still needs to ride to complete each type's coverage.",
+            )
+                .add_flag("a")
+                .add_flag("all")
+                .add_option("company", CommandValueType::String)
+                .add_option("c", CommandValueType::String)
+                .build()
+        ).await;
         my_interface.register_channel_command(
             &CommandDefinitionBuilder::new(
                 "bimtypes",
@@ -4371,8 +5723,8 @@ impl RocketBotPlugin for BimPlugin {
                 .add_flag("delete")
                 .add_flag("u")
                 .add_flag("utc")
-                .add_option("i", CommandValueType::Integer)
-                .add_option("id", CommandValueType::Integer)
+                .add_option("i", CommandValueType::String)
+                .add_option("id", CommandValueType::String)
                 .add_option("r", CommandValueType::String)
                 .add_option("rider", CommandValueType::String)
                 .add_option("R", CommandValueType::String)
@@ -4391,6 +5743,17 @@ impl RocketBotPlugin for BimPlugin {
                 .add_option("actual-price", CommandValueType::String)
                 .build()
         ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "bimbatchedit",
+                "bim",
+                "{cpfx}bimbatchedit\nRIDEID OPERATION...\nRIDEID OPERATION...\n...",
+                "Applies multiple per-ride edits, freshenings or deletions in a single transaction.",
+            )
+                .add_flag("u")
+                .add_flag("utc")
+                .build()
+        ).await;
         my_interface.register_channel_command(
             &CommandDefinitionBuilder::new(
                 "widestbims",
@@ -4445,9 +5808,11 @@ impl RocketBotPlugin for BimPlugin {
             &CommandDefinitionBuilder::new(
                 "recentbimrides",
                 "bim",
-                "{cpfx}recentbimrides [USERNAME]",
+                "{cpfx}recentbimrides [{sopfx}j] [USERNAME]",
                 "A list of recent rides.",
             )
+                .add_flag("j")
+                .add_flag("json")
                 .build()
         ).await;
         my_interface.register_channel_command(
@@ -4468,6 +5833,8 @@ impl RocketBotPlugin for BimPlugin {
             )
                 .add_flag("n")
                 .add_flag("sort-by-number")
+                .add_flag("j")
+                .add_flag("json")
                 .add_lookback_flags()
                 .build()
         ).await;
@@ -4475,11 +5842,13 @@ impl RocketBotPlugin for BimPlugin {
             &CommandDefinitionBuilder::new(
                 "bimdivscore",
                 "bim",
-                "{cpfx}bimdivscore [{sopfx}n]",
+                "{cpfx}bimdivscore [{sopfx}n] [{sopfx}j]",
                 "A list of riders and their divisibility scores.",
             )
                 .add_flag("n")
                 .add_flag("sort-by-number")
+                .add_flag("j")
+                .add_flag("json")
                 .add_lookback_flags()
                 .build()
         ).await;
@@ -4498,12 +5867,38 @@ impl RocketBotPlugin for BimPlugin {
             &CommandDefinitionBuilder::new(
                 "bimcost",
                 "bim",
-                "{cpfx}bimcost [{lopfx}LOOKBACK]",
+                "{cpfx}bimcost [{lopfx}LOOKBACK] [{sopfx}C CURRENCY]",
                 "The sum of ticket money saved over the past slice of time.",
             )
             .add_lookback_flags()
+            .add_option("currency", CommandValueType::String)
+            .add_option("C", CommandValueType::String)
             .build()
         ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "bimgaps",
+                "bim",
+                "{cpfx}bimgaps {sopfx}t TYPE [{sopfx}c COMPANY] [{sopfx}g GAP_LIMIT]",
+                "Finds vehicles of a type that have never been ridden, inferring the active fleet boundary with a gap-limit scan.",
+            )
+                .add_option("type", CommandValueType::String)
+                .add_option("t", CommandValueType::String)
+                .add_option("company", CommandValueType::String)
+                .add_option("c", CommandValueType::String)
+                .add_option("gap-limit", CommandValueType::Integer)
+                .add_option("g", CommandValueType::Integer)
+                .build()
+        ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "bimachievers",
+                "bim",
+                "{cpfx}bimachievers ACHIEVEMENT_ID_OR_NAME",
+                "Lists who has unlocked an achievement, in unlock order, and how rare it is.",
+            )
+                .build()
+        ).await;
 
         // set up the achievement update loop
         let (achievement_update_sender, mut achievement_update_receiver) = mpsc::unbounded_channel();
@@ -4547,10 +5942,35 @@ impl RocketBotPlugin for BimPlugin {
             }
         });
 
+        // set up the Prometheus metrics endpoint, if configured
+        {
+            let config_guard = config_lock.read().await;
+            if let Some(listen_address) = config_guard.metrics_listen_address.clone() {
+                let refresh_interval_s = config_guard.metrics_refresh_interval_s;
+                let metrics_config_lock = Arc::downgrade(&config_lock);
+                tokio::spawn(async move {
+                    crate::metrics::serve_metrics(listen_address, refresh_interval_s, metrics_config_lock).await;
+                });
+            }
+        }
+
+        // set up the admin HTTP API, if configured
+        {
+            let config_guard = config_lock.read().await;
+            if let Some(listen_address) = config_guard.admin_api_listen_address.clone() {
+                let admin_api_config_lock = Arc::downgrade(&config_lock);
+                let admin_api_achievement_update_sender = achievement_update_sender.clone();
+                tokio::spawn(async move {
+                    crate::admin_api::serve_admin_api(listen_address, admin_api_config_lock, admin_api_achievement_update_sender).await;
+                });
+            }
+        }
+
         Self {
             interface,
             config: config_lock,
             achievement_update_sender,
+            clocks: Arc::new(SystemClocks),
         }
     }
 
@@ -4571,16 +5991,24 @@ impl RocketBotPlugin for BimPlugin {
             self.channel_command_topbimdays(channel_message, command).await
         } else if command.name == "topbimlines" {
             self.channel_command_topbimlines(channel_message, command).await
+        } else if command.name == "bimexport" {
+            self.channel_command_bimexport(channel_message, command).await
+        } else if command.name == "topbimstreaks" {
+            self.channel_command_topbimstreaks(channel_message, command).await
         } else if command.name == "bimridertypes" {
             self.channel_command_bimridertypes(channel_message, command).await
         } else if command.name == "bimriderlines" {
             self.channel_command_bimriderlines(channel_message, command).await
         } else if command.name == "bimranges" {
             self.channel_command_bimranges(channel_message, command).await
+        } else if command.name == "bimcoverage" {
+            self.channel_command_bimcoverage(channel_message, command).await
         } else if command.name == "bimtypes" {
             self.channel_command_bimtypes(channel_message, command).await
         } else if command.name == "fixbimride" {
             self.channel_command_fixbimride(channel_message, command).await
+        } else if command.name == "bimbatchedit" {
+            self.channel_command_bimbatchedit(channel_message, command).await
         } else if command.name == "widestbims" {
             self.channel_command_widestbims(channel_message, command).await
         } else if command.name == "refreshbimach" {
@@ -4603,6 +6031,10 @@ impl RocketBotPlugin for BimPlugin {
             self.channel_command_bimfixedmonopolies(channel_message, command).await
         } else if command.name == "bimcost" {
             self.channel_command_bimcost(channel_message, command).await
+        } else if command.name == "bimgaps" {
+            self.channel_command_bimgaps(channel_message, command).await
+        } else if command.name == "bimachievers" {
+            self.channel_command_bimachievers(channel_message, command).await
         }
     }
 
@@ -4638,16 +6070,24 @@ impl RocketBotPlugin for BimPlugin {
             Some(include_str!("../help/topbimdays.md").to_owned())
         } else if command_name == "topbimlines" {
             Some(include_str!("../help/topbimlines.md").to_owned())
+        } else if command_name == "bimexport" {
+            Some(include_str!("../help/bimexport.md").to_owned())
+        } else if command_name == "topbimstreaks" {
+            Some(include_str!("../help/topbimstreaks.md").to_owned())
         } else if command_name == "bimridertypes" {
             Some(include_str!("../help/bimridertypes.md").to_owned())
         } else if command_name == "bimriderlines" {
             Some(include_str!("../help/bimriderlines.md").to_owned())
         } else if command_name == "bimranges" {
             Some(include_str!("../help/bimranges.md").to_owned())
+        } else if command_name == "bimcoverage" {
+            Some(include_str!("../help/bimcoverage.md").to_owned())
         } else if command_name == "bimtypes" {
             Some(include_str!("../help/bimtypes.md").to_owned())
         } else if command_name == "fixbimride" {
             Some(include_str!("../help/fixbimride.md").to_owned())
+        } else if command_name == "bimbatchedit" {
+            Some(include_str!("../help/bimbatchedit.md").to_owned())
         } else if command_name == "widestbims" {
             Some(include_str!("../help/widestbims.md").to_owned())
         } else if command_name == "refreshbimach" {
@@ -4670,6 +6110,8 @@ impl RocketBotPlugin for BimPlugin {
             Some(include_str!("../help/bimfixedmonopolies.md").to_owned())
         } else if command_name == "bimcost" {
             Some(include_str!("../help/bimcost.md").to_owned())
+        } else if command_name == "bimgaps" {
+            Some(include_str!("../help/bimgaps.md").to_owned())
         } else {
             None
         }
@@ -4691,18 +6133,488 @@ impl RocketBotPlugin for BimPlugin {
 }
 
 
-async fn connect_ride_db(config: &Config) -> Result<tokio_postgres::Client, tokio_postgres::Error> {
-    let (client, connection) = match tokio_postgres::connect(&config.ride_db_conn_string, NoTls).await {
-        Ok(cc) => cc,
+fn load_bim_database(config: &Config, company: &str) -> Option<HashMap<VehicleNumber, VehicleInfo>> {
+    let path_opt = match config.company_to_definition.get(company) {
+        Some(p) => p.bim_database_path.as_ref(),
+        None => {
+            error!("unknown company {:?}", company);
+            return None;
+        },
+    };
+    let path = match path_opt {
+        Some(p) => p,
+        None => return None, // valid company but no database
+    };
+    let f = match File::open(path) {
+        Ok(f) => f,
+        Err(e) => {
+            error!("failed to open bim database: {}", e);
+            return None;
+        },
+    };
+    let mut vehicles: Vec<VehicleInfo> = match ciborium::from_reader(f) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("failed to parse bim database: {}", e);
+            return None;
+        },
+    };
+    let vehicle_hash_map: HashMap<VehicleNumber, VehicleInfo> = vehicles.drain(..)
+        .map(|vi| (vi.number.clone(), vi))
+        .collect();
+    Some(vehicle_hash_map)
+}
+
+fn load_gtfs_line_database_for_company(config: &Config, company: &str) -> Option<GtfsLineDatabase> {
+    let feed_dir_opt = match config.company_to_definition.get(company) {
+        Some(cd) => cd.gtfs_feed_dir.as_ref(),
+        None => {
+            error!("unknown company {:?}", company);
+            return None;
+        },
+    };
+    let feed_dir = match feed_dir_opt {
+        Some(fd) => fd,
+        None => return None, // valid company but no GTFS feed configured
+    };
+    match load_gtfs_line_database(feed_dir.as_ref()) {
+        Ok(db) => Some(db),
+        Err(e) => {
+            error!("failed to load GTFS feed for company {:?}: {}", company, e);
+            None
+        },
+    }
+}
+
+/// Computes, for every rider, how many fixed-coupling "monopolies" (a fixed coupling all of whose
+/// vehicles' last rider is the same person) they currently hold for `company`, grouped by coupling
+/// length. Shared by `channel_command_bimfixedmonopolies` and the metrics exporter so both report
+/// the same figures from the same query.
+pub(crate) async fn fixed_coupling_monopolies(
+    ride_conn: &tokio_postgres::Client,
+    company: &str,
+    database: &HashMap<VehicleNumber, VehicleInfo>,
+) -> Result<BTreeMap<String, BTreeMap<usize, usize>>, tokio_postgres::Error> {
+    let query = "
+        SELECT rarv.rider_username
+        FROM bim.rides_and_ridden_vehicles rarv
+        WHERE
+            rarv.company = $1
+            AND rarv.vehicle_number = $2
+            AND NOT EXISTS (
+                SELECT 1
+                FROM bim.rides_and_ridden_vehicles rarv2
+                WHERE rarv2.company = rarv.company
+                AND rarv2.vehicle_number = rarv.vehicle_number
+                AND rarv2.\"timestamp\" > rarv.\"timestamp\"
+            )
+    ";
+    let statement = ride_conn.prepare(query).await?;
+
+    let mut rider_to_coupling_length_to_count: BTreeMap<String, BTreeMap<usize, usize>> = BTreeMap::new();
+    for vehicle in database.values() {
+        if vehicle.fixed_coupling.len() == 0 {
+            // not a fixed coupling
+            continue;
+        }
+
+        if !vehicle.fixed_coupling.first().map(|f| f == &vehicle.number).unwrap_or(false) {
+            // we are not the first vehicle in the coupling
+            continue;
+        }
+
+        // alright, look it up
+        let mut riders = HashSet::new();
+        for vehicle_number in &vehicle.fixed_coupling {
+            let mut rr = ride_conn.query(&statement, &[&company, &vehicle_number.as_str()]).await?;
+            if rr.len() == 0 {
+                // this vehicle does not have a last rider
+                // => nobody can have a monopoly
+                riders.clear();
+                break;
+            }
+            if rr.len() != 1 {
+                error!("obtained more than one rider row ({} rows) for company {:?} vehicle {:?}", rr.len(), company, vehicle.number);
+                riders.clear();
+                break;
+            }
+
+            let row = rr.remove(0);
+            let rider_username: String = row.get(0);
+            riders.insert(rider_username);
+        }
+
+        if riders.len() == 1 {
+            // monopoly!
+            let rider_username = riders.iter().nth(0).map(|ru| ru.clone()).unwrap();
+
+            let monopoly_count = rider_to_coupling_length_to_count
+                .entry(rider_username)
+                .or_insert_with(|| BTreeMap::new())
+                .entry(vehicle.fixed_coupling.len())
+                .or_insert(0usize);
+            *monopoly_count += 1;
+        }
+    }
+
+    Ok(rider_to_coupling_length_to_count)
+}
+
+/// Computes, per ride count, which vehicles have been ridden that many times. Shared by
+/// `channel_command_topbims` and its HTTP admin API counterpart.
+pub(crate) async fn top_ridden_vehicle_counts(
+    ride_conn: &tokio_postgres::Client,
+    company: Option<&str>,
+    lookback_range: LookbackRange,
+    clocks: &dyn Clocks,
+) -> Result<BTreeMap<i64, Vec<(String, String)>>, tokio_postgres::Error> {
+    let company_stored;
+    let mut other_params: Vec<&(dyn ToSql + Sync)> = Vec::new();
+    let company_block = if let Some(c) = company {
+        company_stored = c.to_owned();
+        other_params.push(&company_stored);
+        "AND r.company = $1"
+    } else {
+        ""
+    };
+    let query_template = format!(
+        "
+            WITH
+                total_rides(company, vehicle_number, total_ride_count) AS (
+                    SELECT
+                        r.company,
+                        rv.vehicle_number,
+                        CAST(COUNT(*) AS bigint) total_ride_count
+                    FROM
+                        bim.rides r
+                        INNER JOIN bim.ride_vehicles rv
+                            ON rv.ride_id = r.id
+                    WHERE
+                        rv.coupling_mode = 'R'
+                        {}
+                        {{LOOKBACK_TIMESTAMP}}
+                    GROUP BY
+                        r.company,
+                        rv.vehicle_number
+                ),
+                top_five_counts(total_ride_count) AS (
+                    SELECT DISTINCT total_ride_count
+                    FROM total_rides
+                    ORDER BY total_ride_count DESC
+                    LIMIT 5
+                )
+            SELECT tr.company, tr.vehicle_number, tr.total_ride_count
+            FROM total_rides tr
+            WHERE tr.total_ride_count IN (SELECT total_ride_count FROM top_five_counts)
+            ORDER BY tr.total_ride_count DESC, tr.vehicle_number USING OPERATOR(bim.<~<)
+        ",
+        company_block,
+    );
+
+    let rows = BimPlugin::timestamp_query(
+        ride_conn,
+        &query_template,
+        "r.\"timestamp\"",
+        lookback_range,
+        other_params.as_slice(),
+        clocks,
+    ).await?;
+
+    let mut count_to_vehicles: BTreeMap<i64, Vec<(String, String)>> = BTreeMap::new();
+    for row in &rows {
+        let company: String = row.get(0);
+        let vehicle_number: String = row.get(1);
+        let total_ride_count: i64 = row.get(2);
+
+        count_to_vehicles
+            .entry(total_ride_count)
+            .or_insert_with(|| Vec::new())
+            .push((company, vehicle_number));
+    }
+    Ok(count_to_vehicles)
+}
+
+/// Computes, per rider, the number of rides taken and distinct vehicles ridden, matching
+/// `stats_filter`. Shared by `channel_command_topriders` and its HTTP admin API counterpart; the
+/// caller decides whether (and how) to truncate the result to a top-N list.
+pub(crate) async fn top_rider_ride_and_vehicle_counts(
+    ride_conn: &tokio_postgres::Client,
+    stats_filter: &StatsFilter,
+    lookback_range: LookbackRange,
+    clocks: &dyn Clocks,
+) -> Result<Vec<(String, i64, i64)>, tokio_postgres::Error> {
+    let mut ride_count_query = RideQuery::new();
+    stats_filter.apply(&mut ride_count_query, "r");
+    let ride_rows = BimPlugin::ride_query(
+        ride_conn,
+        "
+            SELECT r.rider_username, CAST(COUNT(*) AS bigint) ride_count
+            FROM bim.rides r
+            WHERE 1=1
+            {CRITERIA}
+            GROUP BY r.rider_username
+        ",
+        lookback_range,
+        &ride_count_query,
+        clocks,
+    ).await?;
+
+    let mut rider_to_ride_and_vehicle_count: HashMap<String, (i64, i64)> = HashMap::new();
+    for row in ride_rows {
+        let rider_username: String = row.get(0);
+        let ride_count: i64 = row.get(1);
+
+        let rider_ride_and_vehicle_count = rider_to_ride_and_vehicle_count
+            .entry(rider_username.clone())
+            .or_insert((0i64, 0i64));
+        rider_ride_and_vehicle_count.0 += ride_count;
+    }
+
+    let mut vehicle_count_query = RideQuery::new();
+    stats_filter.apply(&mut vehicle_count_query, "r");
+    let vehicle_rows = BimPlugin::ride_query(
+        ride_conn,
+        "
+            SELECT i.rider_username, CAST(COUNT(*) AS bigint) vehicle_count
+            FROM (
+                SELECT DISTINCT r.rider_username, r.company, rv.vehicle_number
+                FROM bim.rides r
+                INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                WHERE rv.coupling_mode = 'R'
+                {CRITERIA}
+            ) i
+            GROUP BY i.rider_username
+        ",
+        lookback_range,
+        &vehicle_count_query,
+        clocks,
+    ).await?;
+
+    for row in vehicle_rows {
+        let rider_username: String = row.get(0);
+        let vehicle_count: i64 = row.get(1);
+
+        let rider_ride_and_vehicle_count = rider_to_ride_and_vehicle_count
+            .entry(rider_username.clone())
+            .or_insert((0i64, 0i64));
+        rider_ride_and_vehicle_count.1 += vehicle_count;
+    }
+
+    let mut rider_and_ride_and_vehicle_count: Vec<(String, i64, i64)> = rider_to_ride_and_vehicle_count
+        .iter()
+        .map(|(r, (rc, vc))| (r.clone(), *rc, *vc))
+        .collect();
+    rider_and_ride_and_vehicle_count.sort_unstable_by_key(|(r, rc, _vc)| (-*rc, r.clone()));
+    Ok(rider_and_ride_and_vehicle_count)
+}
+
+/// Computes vehicle-type statistics (known/active/ridden counts) for `company`, optionally
+/// restricted to vehicles ridden by `rider_username_opt` and/or matching `stats_filter` (whose own
+/// `company` criterion, if any, is ignored since `company` already pins it). Returns the
+/// per-type stats plus the number of ridden vehicles that are not present in `database` at all.
+/// Shared by `channel_command_bimtypes` and its HTTP admin API counterpart.
+pub(crate) async fn bim_type_stats_for_company(
+    ride_conn: &tokio_postgres::Client,
+    company: &str,
+    database: &HashMap<VehicleNumber, VehicleInfo>,
+    stats_filter: &StatsFilter,
+    rider_username_opt: Option<&str>,
+) -> Result<(BTreeMap<String, BimTypeStats>, usize), tokio_postgres::Error> {
+    let mut ridden_query = RideQuery::new();
+    ridden_query.and_where("r.company = ?", &[&company]);
+    ridden_query.and_where_literal("rv.coupling_mode = 'R'");
+    let mut filter_without_company = stats_filter.clone();
+    filter_without_company.company = None;
+    filter_without_company.apply(&mut ridden_query, "r");
+    if let Some(ru) = rider_username_opt {
+        ridden_query.and_where("LOWER(r.rider_username) = LOWER(?)", &[&ru]);
+    }
+
+    let rows = ride_conn.query(
+        &format!(
+            "
+                SELECT DISTINCT
+                    rv.vehicle_number
+                FROM bim.rides r
+                INNER JOIN bim.ride_vehicles rv
+                    ON rv.ride_id = r.id
+                WHERE 1=1
+                {}
+            ",
+            ridden_query.and_clause(),
+        ),
+        ridden_query.params(),
+    ).await?;
+    let mut ridden_vehicles: HashSet<VehicleNumber> = HashSet::new();
+    for row in rows {
+        let vehicle_number = VehicleNumber::from_string(row.get(0));
+        ridden_vehicles.insert(vehicle_number);
+    }
+
+    let mut type_to_stats: BTreeMap<String, BimTypeStats> = BTreeMap::new();
+    for vehicle in database.values() {
+        let type_stats = type_to_stats
+            .entry(vehicle.type_code.clone())
+            .or_insert_with(|| BimTypeStats::new());
+        type_stats.known_vehicles += 1;
+        if vehicle.in_service_since.is_some() && vehicle.out_of_service_since.is_none() {
+            type_stats.active_vehicles += 1;
+        }
+        if ridden_vehicles.remove(&vehicle.number) {
+            type_stats.ridden_vehicles += 1;
+        }
+    }
+
+    // whatever remains in `ridden_vehicles` has been ridden but is of unknown type
+    Ok((type_to_stats, ridden_vehicles.len()))
+}
+
+/// Computes, per rider, the number of vehicles for which they are currently the last rider.
+/// Shared by `channel_command_lastbims` and its HTTP admin API counterpart.
+pub(crate) async fn last_rider_vehicle_counts(
+    ride_conn: &tokio_postgres::Client,
+    company: Option<&str>,
+) -> Result<Vec<(String, i64)>, tokio_postgres::Error> {
+    let query_string = format!(
+        "
+            SELECT innerquery.rider_username, CAST(COUNT(*) AS bigint) vehicle_count
+            FROM (
+                SELECT DISTINCT rav1.rider_username, rav1.company, rav1.vehicle_number
+                FROM bim.rides_and_vehicles rav1
+                WHERE rav1.coupling_mode = 'R'
+                AND NOT EXISTS (
+                    -- same vehicle, later timestamp
+                    SELECT 1
+                    FROM bim.rides_and_vehicles rav2
+                    WHERE rav2.company = rav1.company
+                    AND rav2.vehicle_number = rav1.vehicle_number
+                    AND rav2.coupling_mode = rav1.coupling_mode
+                    AND rav2.\"timestamp\" > rav1.\"timestamp\"
+                )
+            ) innerquery
+            {}
+            GROUP BY innerquery.rider_username
+            ORDER BY
+                vehicle_count DESC,
+                rider_username
+        ",
+        if company.is_some() { "WHERE innerquery.company = $1" } else { "" },
+    );
+    let mut query_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(1);
+    if company.is_some() {
+        query_params.push(&company);
+    }
+
+    let ride_rows = ride_conn.query(&query_string, &query_params).await?;
+    let mut rider_vehicle_counts = Vec::with_capacity(ride_rows.len());
+    for ride_row in ride_rows {
+        let rider_username: String = ride_row.get(0);
+        let vehicle_count: i64 = ride_row.get(1);
+        rider_vehicle_counts.push((rider_username, vehicle_count));
+    }
+    Ok(rider_vehicle_counts)
+}
+
+/// Computes the cumulative savings (`SUM(regular_price - actual_price)`) for `rider_username`
+/// within `lookback_range`, per company (since each company may price in a different currency --
+/// see [`CompanyDefinition::currency`]). Shared by `channel_command_bimcost` and its HTTP admin
+/// API counterpart.
+pub(crate) async fn rider_cost_savings(
+    ride_conn: &tokio_postgres::Client,
+    rider_username: &str,
+    lookback_range: LookbackRange,
+    ride_db: &dyn RideDb,
+    clocks: &dyn Clocks,
+) -> Result<Vec<(String, BigDecimal)>, tokio_postgres::Error> {
+    let query_template = format!(
+        "
+            SELECT r.company, {} sums
+            FROM bim.rides r
+            WHERE
+                r.rider_username = $1
+                {{LOOKBACK_TIMESTAMP}}
+            GROUP BY r.company
+        ",
+        ride_db.format_money_as_text("COALESCE(SUM(r.regular_price - r.actual_price), 0)"),
+    );
+
+    let rows = BimPlugin::timestamp_query(
+        ride_conn,
+        &query_template,
+        "r.\"timestamp\"",
+        lookback_range,
+        &[&rider_username],
+        clocks,
+    ).await?;
+
+    let mut company_savings = Vec::with_capacity(rows.len());
+    for row in rows {
+        let company: String = row.get(0);
+        let savings_string: String = row.get(1);
+        let savings = savings_string.parse().unwrap_or_else(|e| {
+            error!("failed to parse savings {:?} for company {:?}: {}", savings_string, company, e);
+            BigDecimal::zero()
+        });
+        company_savings.push((company, savings));
+    }
+    Ok(company_savings)
+}
+
+/// Looks up the rate at which one unit of `from_currency` can be converted into `to_currency`
+/// using `rates`, trying the reverse direction (and inverting) if no direct rate is configured.
+/// Returns `None` if `from_currency` and `to_currency` differ and no rate (direct or reverse)
+/// relates them.
+fn exchange_rate(rates: &[CurrencyExchangeRate], from_currency: &str, to_currency: &str) -> Option<BigDecimal> {
+    if from_currency == to_currency {
+        return Some(BigDecimal::from(1));
+    }
+    for rate in rates {
+        if rate.from_currency == from_currency && rate.to_currency == to_currency {
+            return Some(rate.rate.clone());
+        }
+        if rate.from_currency == to_currency && rate.to_currency == from_currency && !rate.rate.is_zero() {
+            return Some(BigDecimal::from(1) / &rate.rate);
+        }
+    }
+    None
+}
+
+/// Formats a monetary amount with two decimal digits, followed by its currency code, e.g.
+/// `"12.40 EUR"`. The shared formatting point for every place a converted or raw currency amount
+/// is shown to the user.
+fn format_money(amount: &BigDecimal, currency: &str) -> String {
+    format!("{} {}", amount.with_scale(2), currency)
+}
+
+
+/// Enqueues an achievement recalculation on `sender`, counting it for the `/metrics` endpoint.
+/// Used both by [`BimPlugin::enqueue_achievement_update`] and the admin HTTP API.
+pub(crate) fn enqueue_achievement_update(sender: &mpsc::UnboundedSender<UpdateAchievementsData>, data: UpdateAchievementsData) {
+    crate::metrics::record_achievement_recalc_enqueued();
+    let _ = sender.send(data);
+}
+
+
+/// Checks a connection out of `config`'s ride database connection pool (building the pool on
+/// first use), instead of opening a fresh `tokio_postgres` connection (and its driver task) on
+/// every call.
+async fn connect_ride_db(config: &Config) -> Result<deadpool_postgres::Object, RideDbPoolError> {
+    let pool = match config.ride_db_pool() {
+        Ok(p) => p,
         Err(e) => {
-            error!("error connecting to database: {}", e);
+            error!("error building ride database connection pool: {}", e);
+            crate::metrics::record_failed_db_connection();
             return Err(e);
         },
     };
-    tokio::spawn(async move {
-        connection.await
-    });
-    Ok(client)
+    match pool.get().await {
+        Ok(obj) => Ok(obj),
+        Err(e) => {
+            error!("error obtaining database connection from pool: {}", e);
+            crate::metrics::record_failed_db_connection();
+            Err(e.into())
+        },
+    }
 }
 
 
@@ -4825,304 +6737,258 @@ pub async fn add_ride(
     sandbox: bool,
     highlight_coupled_rides: bool,
 ) -> Result<(i64, Vec<RideTableVehicle>), tokio_postgres::Error> {
-    async fn prepare_pair(
-        ride_conn: &tokio_postgres::Transaction<'_>,
-        count_query: &str,
-        streak_suffix: &str,
-    ) -> Result<(tokio_postgres::Statement, tokio_postgres::Statement), tokio_postgres::Error> {
-        let count_stmt = ride_conn.prepare(count_query).await?;
-        let streak_stmt = ride_conn.prepare(&format!("{} {}", count_query, streak_suffix)).await?;
-        Ok((count_stmt, streak_stmt))
+    /// The four count/streak/last-ride figures computed for one vehicle by [`vehicle_stats`].
+    #[derive(Clone)]
+    struct VehicleStats {
+        my_same_count: i64,
+        my_same_streak: i64,
+        my_same_timestamp: Option<DateTime<Local>>,
+        my_same_line: Option<String>,
+        my_coupled_count: i64,
+        my_coupled_streak: i64,
+        my_coupled_timestamp: Option<DateTime<Local>>,
+        my_coupled_line: Option<String>,
+        other_same_count: i64,
+        other_same_streak: i64,
+        other_same_timestamp: Option<DateTime<Local>>,
+        other_same_line: Option<String>,
+        other_same_rider: Option<String>,
+        other_coupled_count: i64,
+        other_coupled_streak: i64,
+        other_coupled_timestamp: Option<DateTime<Local>>,
+        other_coupled_line: Option<String>,
+        other_coupled_rider: Option<String>,
     }
 
-    let (prev_my_same_count_stmt, prev_my_same_streak_stmt) = prepare_pair(
-        ride_conn,
-        "
-            SELECT CAST(COUNT(*) AS bigint)
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username = $3
-                AND rv.coupling_mode = 'R'
-        ",
-        "
-                AND NOT EXISTS (
-                    SELECT 1
-                    FROM bim.rides r2
-                    INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
-                    WHERE r2.company = r.company
-                    AND rv2.vehicle_number = rv.vehicle_number
-                    AND r2.rider_username <> r.rider_username
-                    AND rv2.coupling_mode = 'R'
-                    AND r2.\"timestamp\" > r.\"timestamp\"
-                )
-        ",
-    ).await?;
-    let prev_my_same_row_stmt = ride_conn.prepare(
-        "
-            SELECT r.\"timestamp\", r.line
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username = $3
-                AND rv.coupling_mode = 'R'
-            ORDER BY r.\"timestamp\" DESC
-            LIMIT 1
-        ",
-    ).await?;
-    let (prev_my_coupled_count_stmt, prev_my_coupled_streak_stmt) = prepare_pair(
-        ride_conn,
-        "
-            SELECT CAST(COUNT(*) AS bigint)
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username = $3
-                AND rv.coupling_mode <> 'R'
-        ",
-        "
-                AND NOT EXISTS (
-                    SELECT 1
-                    FROM bim.rides r2
-                    INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
-                    WHERE r2.company = r.company
-                    AND rv2.vehicle_number = rv.vehicle_number
-                    AND r2.rider_username <> r.rider_username
-                    AND rv2.coupling_mode <> 'R'
-                    AND r2.\"timestamp\" > r.\"timestamp\"
-                )
-        ",
-    ).await?;
-    let prev_my_coupled_row_stmt = ride_conn.prepare(
-        "
-            SELECT r.\"timestamp\", r.line
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username = $3
-                AND rv.coupling_mode <> 'R'
-            ORDER BY r.\"timestamp\" DESC
-            LIMIT 1
-        ",
-    ).await?;
-    let (prev_other_same_count_stmt, prev_other_same_streak_stmt) = prepare_pair(
-        ride_conn,
-        "
-            SELECT CAST(COUNT(*) AS bigint)
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username <> $3
-                AND rv.coupling_mode = 'R'
-        ",
-        "
-                AND NOT EXISTS (
-                    SELECT 1
-                    FROM bim.rides r2
-                    INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
-                    WHERE r2.company = r.company
-                    AND rv2.vehicle_number = rv.vehicle_number
-                    AND r2.rider_username <> r.rider_username
-                    AND rv2.coupling_mode = 'R'
-                    AND r2.\"timestamp\" > r.\"timestamp\"
-                )
-        ",
-    ).await?;
-    let prev_other_same_row_stmt = ride_conn.prepare(
-        "
-            SELECT r.\"timestamp\", r.line, r.rider_username
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username <> $3
-                AND rv.coupling_mode = 'R'
-            ORDER BY r.\"timestamp\" DESC
-            LIMIT 1
-        ",
-    ).await?;
-    let (prev_other_coupled_count_stmt, prev_other_coupled_streak_stmt) = prepare_pair(
-        ride_conn,
-        "
-            SELECT CAST(COUNT(*) AS bigint)
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username <> $3
-                AND rv.coupling_mode <> 'R'
-        ",
-        "
-                AND NOT EXISTS (
-                    SELECT 1
-                    FROM bim.rides r2
-                    INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
-                    WHERE r2.company = r.company
-                    AND rv2.vehicle_number = rv.vehicle_number
-                    AND r2.rider_username <> r.rider_username
-                    AND rv2.coupling_mode <> 'R'
-                    AND r2.\"timestamp\" > r.\"timestamp\"
+    /// Computes [`VehicleStats`] for every one of `vehicle_numbers` in a single round-trip:
+    /// `UNNEST`s the vehicle numbers into a derived table and `LEFT JOIN LATERAL`s each of the
+    /// four count/streak/last-ride subqueries (that used to be fired individually per vehicle)
+    /// against it. This turns the former 12 round trips per vehicle (one per my-same/my-coupled/
+    /// other-same/other-coupled count, streak and last-ride lookup) into a single query regardless
+    /// of how many vehicles a ride covers.
+    async fn vehicle_stats(
+        ride_conn: &tokio_postgres::Transaction<'_>,
+        company: &str,
+        vehicle_numbers: &[&str],
+        rider_username: &str,
+    ) -> Result<HashMap<String, VehicleStats>, tokio_postgres::Error> {
+        let rows = ride_conn.query(
+            "
+                WITH v AS (
+                    SELECT UNNEST($1::text[]) AS vehicle_number
                 )
-        ",
-    ).await?;
-    let prev_other_coupled_row_stmt = ride_conn.prepare(
-        "
-            SELECT r.\"timestamp\", r.line, r.rider_username
-            FROM bim.rides r
-            INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
-            WHERE
-                r.company = $1
-                AND rv.vehicle_number = $2
-                AND r.rider_username <> $3
-                AND rv.coupling_mode <> 'R'
-            ORDER BY r.\"timestamp\" DESC
-            LIMIT 1
-        ",
-    ).await?;
-
-    let mut vehicle_data = Vec::new();
-    for vehicle in vehicles {
-        let prev_my_same_streak: i64 = {
-            let prev_my_same_streak_row = ride_conn.query_one(
-                &prev_my_same_streak_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_my_same_streak_row.get(0)
-        };
-        let prev_my_same_count: i64 = {
-            let prev_my_same_count_row = ride_conn.query_one(
-                &prev_my_same_count_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_my_same_count_row.get(0)
-        };
-        let (prev_my_same_timestamp, prev_my_same_line): (Option<DateTime<Local>>, Option<String>) = {
-            let prev_my_same_row_opt = ride_conn.query_opt(
-                &prev_my_same_row_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            let prev_my_same_timestamp = prev_my_same_row_opt.as_ref().map(|r| r.get(0));
-            let prev_my_same_line = prev_my_same_row_opt.as_ref().map(|r| r.get(1)).flatten();
-            (prev_my_same_timestamp, prev_my_same_line)
-        };
+                SELECT
+                    v.vehicle_number,
+                    my_same_count.count, my_same_streak.count, my_same_row.\"timestamp\", my_same_row.line,
+                    my_coupled_count.count, my_coupled_streak.count, my_coupled_row.\"timestamp\", my_coupled_row.line,
+                    other_same_count.count, other_same_streak.count, other_same_row.\"timestamp\", other_same_row.line, other_same_row.rider_username,
+                    other_coupled_count.count, other_coupled_streak.count, other_coupled_row.\"timestamp\", other_coupled_row.line, other_coupled_row.rider_username
+                FROM v
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username = $3 AND rv.coupling_mode = 'R'
+                ) my_same_count ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username = $3 AND rv.coupling_mode = 'R'
+                        AND NOT EXISTS (
+                            SELECT 1
+                            FROM bim.rides r2
+                            INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
+                            WHERE r2.company = r.company
+                            AND rv2.vehicle_number = rv.vehicle_number
+                            AND r2.rider_username <> r.rider_username
+                            AND rv2.coupling_mode = 'R'
+                            AND r2.\"timestamp\" > r.\"timestamp\"
+                        )
+                ) my_same_streak ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT r.\"timestamp\", r.line
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username = $3 AND rv.coupling_mode = 'R'
+                    ORDER BY r.\"timestamp\" DESC
+                    LIMIT 1
+                ) my_same_row ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username = $3 AND rv.coupling_mode <> 'R'
+                ) my_coupled_count ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username = $3 AND rv.coupling_mode <> 'R'
+                        AND NOT EXISTS (
+                            SELECT 1
+                            FROM bim.rides r2
+                            INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
+                            WHERE r2.company = r.company
+                            AND rv2.vehicle_number = rv.vehicle_number
+                            AND r2.rider_username <> r.rider_username
+                            AND rv2.coupling_mode <> 'R'
+                            AND r2.\"timestamp\" > r.\"timestamp\"
+                        )
+                ) my_coupled_streak ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT r.\"timestamp\", r.line
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username = $3 AND rv.coupling_mode <> 'R'
+                    ORDER BY r.\"timestamp\" DESC
+                    LIMIT 1
+                ) my_coupled_row ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username <> $3 AND rv.coupling_mode = 'R'
+                ) other_same_count ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username <> $3 AND rv.coupling_mode = 'R'
+                        AND NOT EXISTS (
+                            SELECT 1
+                            FROM bim.rides r2
+                            INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
+                            WHERE r2.company = r.company
+                            AND rv2.vehicle_number = rv.vehicle_number
+                            AND r2.rider_username <> r.rider_username
+                            AND rv2.coupling_mode = 'R'
+                            AND r2.\"timestamp\" > r.\"timestamp\"
+                        )
+                ) other_same_streak ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT r.\"timestamp\", r.line, r.rider_username
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username <> $3 AND rv.coupling_mode = 'R'
+                    ORDER BY r.\"timestamp\" DESC
+                    LIMIT 1
+                ) other_same_row ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username <> $3 AND rv.coupling_mode <> 'R'
+                ) other_coupled_count ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT CAST(COUNT(*) AS bigint) count
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username <> $3 AND rv.coupling_mode <> 'R'
+                        AND NOT EXISTS (
+                            SELECT 1
+                            FROM bim.rides r2
+                            INNER JOIN bim.ride_vehicles rv2 ON rv2.ride_id = r2.id
+                            WHERE r2.company = r.company
+                            AND rv2.vehicle_number = rv.vehicle_number
+                            AND r2.rider_username <> r.rider_username
+                            AND rv2.coupling_mode <> 'R'
+                            AND r2.\"timestamp\" > r.\"timestamp\"
+                        )
+                ) other_coupled_streak ON TRUE
+                LEFT JOIN LATERAL (
+                    SELECT r.\"timestamp\", r.line, r.rider_username
+                    FROM bim.rides r
+                    INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                    WHERE r.company = $2 AND rv.vehicle_number = v.vehicle_number
+                        AND r.rider_username <> $3 AND rv.coupling_mode <> 'R'
+                    ORDER BY r.\"timestamp\" DESC
+                    LIMIT 1
+                ) other_coupled_row ON TRUE
+            ",
+            &[&vehicle_numbers, &company, &rider_username],
+        ).await?;
 
-        let prev_my_coupled_streak: i64 = {
-            let prev_my_coupled_streak_row = ride_conn.query_one(
-                &prev_my_coupled_streak_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_my_coupled_streak_row.get(0)
-        };
-        let prev_my_coupled_count: i64 = {
-            let prev_my_coupled_count_row = ride_conn.query_one(
-                &prev_my_coupled_count_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_my_coupled_count_row.get(0)
-        };
-        let (prev_my_coupled_timestamp, prev_my_coupled_line): (Option<DateTime<Local>>, Option<String>) = {
-            let prev_my_coupled_row_opt = ride_conn.query_opt(
-                &prev_my_coupled_row_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            let prev_my_coupled_timestamp = prev_my_coupled_row_opt.as_ref().map(|r| r.get(0));
-            let prev_my_coupled_line = prev_my_coupled_row_opt.as_ref().map(|r| r.get(1)).flatten();
-            (prev_my_coupled_timestamp, prev_my_coupled_line)
-        };
+        let mut stats_by_vehicle = HashMap::with_capacity(rows.len());
+        for row in rows {
+            let vehicle_number: String = row.get(0);
+            stats_by_vehicle.insert(vehicle_number, VehicleStats {
+                my_same_count: row.get(1),
+                my_same_streak: row.get(2),
+                my_same_timestamp: row.get(3),
+                my_same_line: row.get(4),
+                my_coupled_count: row.get(5),
+                my_coupled_streak: row.get(6),
+                my_coupled_timestamp: row.get(7),
+                my_coupled_line: row.get(8),
+                other_same_count: row.get(9),
+                other_same_streak: row.get(10),
+                other_same_timestamp: row.get(11),
+                other_same_line: row.get(12),
+                other_same_rider: row.get(13),
+                other_coupled_count: row.get(14),
+                other_coupled_streak: row.get(15),
+                other_coupled_timestamp: row.get(16),
+                other_coupled_line: row.get(17),
+                other_coupled_rider: row.get(18),
+            });
+        }
+        Ok(stats_by_vehicle)
+    }
 
-        let prev_other_same_streak: i64 = {
-            let prev_other_same_streak_row = ride_conn.query_one(
-                &prev_other_same_streak_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_other_same_streak_row.get(0)
-        };
-        let prev_other_same_count: i64 = {
-            let prev_other_same_count_row = ride_conn.query_one(
-                &prev_other_same_count_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_other_same_count_row.get(0)
-        };
-        let (prev_other_same_timestamp, prev_other_same_line, prev_other_same_rider): (Option<DateTime<Local>>, Option<String>, Option<String>) = {
-            let prev_other_same_row_opt = ride_conn.query_opt(
-                &prev_other_same_row_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            let prev_other_same_timestamp = prev_other_same_row_opt.as_ref().map(|r| r.get(0));
-            let prev_other_same_line = prev_other_same_row_opt.as_ref().map(|r| r.get(1)).flatten();
-            let prev_other_same_rider = prev_other_same_row_opt.as_ref().map(|r| r.get(2));
-            (prev_other_same_timestamp, prev_other_same_line, prev_other_same_rider)
-        };
+    let vehicle_numbers: Vec<&str> = vehicles.iter().map(|v| v.number.as_str()).collect();
+    let stats_by_vehicle = vehicle_stats(ride_conn, company, &vehicle_numbers, rider_username).await?;
 
-        let prev_other_coupled_streak: i64 = {
-            let prev_other_coupled_streak_row = ride_conn.query_one(
-                &prev_other_coupled_streak_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_other_coupled_streak_row.get(0)
-        };
-        let prev_other_coupled_count: i64 = {
-            let prev_other_coupled_count_row = ride_conn.query_one(
-                &prev_other_coupled_count_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            prev_other_coupled_count_row.get(0)
-        };
-        let (prev_other_coupled_timestamp, prev_other_coupled_line, prev_other_coupled_rider): (Option<DateTime<Local>>, Option<String>, Option<String>) = {
-            let prev_other_coupled_row_opt = ride_conn.query_opt(
-                &prev_other_coupled_row_stmt,
-                &[&company, &vehicle.number.as_str(), &rider_username],
-            ).await?;
-            let prev_other_coupled_timestamp = prev_other_coupled_row_opt.as_ref().map(|r| r.get(0));
-            let prev_other_coupled_line = prev_other_coupled_row_opt.as_ref().map(|r| r.get(1)).flatten();
-            let prev_other_coupled_rider = prev_other_coupled_row_opt.as_ref().map(|r| r.get(2));
-            (prev_other_coupled_timestamp, prev_other_coupled_line, prev_other_coupled_rider)
-        };
+    let mut vehicle_data = Vec::new();
+    for vehicle in vehicles {
+        // `.cloned()`, not `.remove()`, since a coupling could in principle list the same
+        // vehicle number more than once
+        let stats = stats_by_vehicle.get(vehicle.number.as_str())
+            .cloned()
+            .expect("vehicle_stats did not return a row for a requested vehicle number");
 
         vehicle_data.push(RideTableVehicle {
             vehicle_number: vehicle.number.clone().into_string(),
             vehicle_type: vehicle.type_code.clone(),
-            my_same_count_streak: prev_my_same_streak,
-            my_same_count: prev_my_same_count,
-            my_same_last: prev_my_same_timestamp.map(|timestamp| Ride {
+            my_same_count_streak: stats.my_same_streak,
+            my_same_count: stats.my_same_count,
+            my_same_last: stats.my_same_timestamp.map(|timestamp| Ride {
                 timestamp,
-                line: prev_my_same_line,
+                line: stats.my_same_line,
             }),
-            my_coupled_count_streak: prev_my_coupled_streak,
-            my_coupled_count: prev_my_coupled_count,
-            my_coupled_last: prev_my_coupled_timestamp.map(|timestamp| Ride {
+            my_coupled_count_streak: stats.my_coupled_streak,
+            my_coupled_count: stats.my_coupled_count,
+            my_coupled_last: stats.my_coupled_timestamp.map(|timestamp| Ride {
                 timestamp,
-                line: prev_my_coupled_line,
+                line: stats.my_coupled_line,
             }),
-            other_same_count_streak: prev_other_same_streak,
-            other_same_count: prev_other_same_count,
-            other_same_last: prev_other_same_timestamp.map(|timestamp| UserRide {
-                rider_username: prev_other_same_rider.unwrap(),
+            other_same_count_streak: stats.other_same_streak,
+            other_same_count: stats.other_same_count,
+            other_same_last: stats.other_same_timestamp.map(|timestamp| UserRide {
+                rider_username: stats.other_same_rider.unwrap(),
                 ride: Ride {
                     timestamp,
-                    line: prev_other_same_line,
+                    line: stats.other_same_line,
                 },
             }),
-            other_coupled_count_streak: prev_other_coupled_streak,
-            other_coupled_count: prev_other_coupled_count,
-            other_coupled_last: prev_other_coupled_timestamp.map(|timestamp| UserRide {
-                rider_username: prev_other_coupled_rider.unwrap(),
+            other_coupled_count_streak: stats.other_coupled_streak,
+            other_coupled_count: stats.other_coupled_count,
+            other_coupled_last: stats.other_coupled_timestamp.map(|timestamp| UserRide {
+                rider_username: stats.other_coupled_rider.unwrap(),
                 ride: Ride {
                     timestamp,
-                    line: prev_other_coupled_line,
+                    line: stats.other_coupled_line,
                 },
             }),
             highlight_coupled_rides,
@@ -5211,6 +7077,7 @@ pub enum IncrementBySpecError {
     SpecParseFailure(String),
     VehicleNumberParseFailure(String, ParseIntError),
     FixedCouplingCombo(VehicleNumber),
+    UnknownLine(String),
     DatabaseQuery(String, Vec<NewVehicleEntry>, Option<String>, tokio_postgres::Error),
     DatabaseBeginTransaction(tokio_postgres::Error),
     DatabaseCommitTransaction(tokio_postgres::Error),
@@ -5221,6 +7088,7 @@ impl fmt::Display for IncrementBySpecError {
             Self::SpecParseFailure(spec) => write!(f, "failed to parse spec {:?}", spec),
             Self::VehicleNumberParseFailure(num_str, e) => write!(f, "failed to parse vehicle number {:?}: {}", num_str, e),
             Self::FixedCouplingCombo(coupled_number) => write!(f, "vehicle number {} is part of a fixed coupling and cannot be ridden in combination with other vehicles", coupled_number),
+            Self::UnknownLine(line) => write!(f, "line {:?} is not known to the imported GTFS feed", line),
             Self::DatabaseQuery(rider, vehicle_nums, line_opt, e) => write!(f, "database query error registering {} riding on vehicles {:?} on line {:?}: {}", rider, vehicle_nums, line_opt, e),
             Self::DatabaseBeginTransaction(e) => write!(f, "database error beginning transaction: {}", e),
             Self::DatabaseCommitTransaction(e) => write!(f, "database error committing transaction: {}", e),
@@ -5230,6 +7098,163 @@ impl fmt::Display for IncrementBySpecError {
 impl std::error::Error for IncrementBySpecError {
 }
 
+/// Collapses a [`RangeSet<u64>`] into the `start-end`/single-number notation used by
+/// `bimranges`/`bimcoverage`, e.g. `"1-5, 7, 10-12"`.
+fn format_range_set(ranges: &RangeSet<u64>) -> String {
+    ranges.ranges()
+        .map(|r|
+            if r.range.start == r.range.end - 1 {
+                // single number
+                format!("{}", r.range.start)
+            } else {
+                format!("{}-{}", r.range.start, r.range.end - 1)
+            }
+        )
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Parses a `--id`/`-i` value for `fixbimride` into the ride IDs it names. Accepts a single ID
+/// (`"105"`), a comma-separated list (`"100,102,104"`) and/or inclusive ranges (`"100-110"`), any
+/// of which may be combined (`"100-102,108"`). Returns the IDs in the order given, without
+/// duplicates.
+fn parse_ride_id_spec(spec: &str) -> Option<Vec<i64>> {
+    let mut seen = HashSet::new();
+    let mut ids = Vec::new();
+    for piece in spec.split(',') {
+        let piece = piece.trim();
+        if piece.len() == 0 {
+            return None;
+        }
+
+        if let Some((from_str, to_str)) = piece.split_once('-') {
+            let from: i64 = from_str.trim().parse().ok()?;
+            let to: i64 = to_str.trim().parse().ok()?;
+            if to < from {
+                return None;
+            }
+            for id in from..=to {
+                if seen.insert(id) {
+                    ids.push(id);
+                }
+            }
+        } else {
+            let id: i64 = piece.parse().ok()?;
+            if seen.insert(id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    if ids.len() == 0 {
+        None
+    } else {
+        Some(ids)
+    }
+}
+
+/// One parsed line of a `bimbatchedit` specification: a ride ID together with the edits to apply
+/// to it, or a delete flag. Mirrors the per-option vocabulary of `fixbimride`, but scoped to a
+/// single line instead of command flags, since each ride in the batch can carry different edits.
+struct BatchEditEntry {
+    ride_id: i64,
+    delete: bool,
+    freshen: bool,
+    new_rider: Option<String>,
+    new_company: Option<String>,
+    new_line: Option<String>,
+    new_timestamp_str: Option<String>,
+    new_vehicles: Option<String>,
+    new_price: Option<String>,
+    new_actual_price: Option<String>,
+}
+
+/// Parses the body of a `bimbatchedit` command: one operation per line, each starting with a ride
+/// ID followed by whitespace-separated `KEY=VALUE` edits (`r`/`rider`, `c`/`company`, `l`/`line`,
+/// `t`/`timestamp`, `v`/`vehicles`, `p`/`price`, `a`/`actual-price`) or the bare word `d`/`delete`
+/// or `f`/`freshen` (re-derive the ride's vehicles from the vehicle database, exactly as
+/// `bimfreshen` would, instead of replacing them with an explicit `vehicles` spec).
+fn parse_batch_edit_spec(spec: &str) -> Result<Vec<BatchEditEntry>, String> {
+    let mut entries = Vec::new();
+    for (line_index, line_raw) in spec.lines().enumerate() {
+        let line_no = line_index + 1;
+        let line = line_raw.trim();
+        if line.len() == 0 {
+            continue;
+        }
+
+        let mut tokens = line.split_whitespace();
+        let ride_id_str = tokens.next()
+            .ok_or_else(|| format!("line {}: missing ride ID", line_no))?;
+        let ride_id: i64 = ride_id_str.parse()
+            .map_err(|_| format!("line {}: invalid ride ID {:?}", line_no, ride_id_str))?;
+
+        let mut entry = BatchEditEntry {
+            ride_id,
+            delete: false,
+            freshen: false,
+            new_rider: None,
+            new_company: None,
+            new_line: None,
+            new_timestamp_str: None,
+            new_vehicles: None,
+            new_price: None,
+            new_actual_price: None,
+        };
+
+        for token in tokens {
+            if token == "d" || token == "delete" {
+                entry.delete = true;
+                continue;
+            }
+            if token == "f" || token == "freshen" {
+                entry.freshen = true;
+                continue;
+            }
+
+            let (key, value) = token.split_once('=')
+                .ok_or_else(|| format!("line {}: expected KEY=VALUE, \"delete\" or \"freshen\", got {:?}", line_no, token))?;
+            match key {
+                "r" | "rider" => entry.new_rider = Some(value.to_owned()),
+                "c" | "company" => entry.new_company = Some(value.to_owned()),
+                "l" | "line" => entry.new_line = Some(value.to_owned()),
+                "t" | "timestamp" => entry.new_timestamp_str = Some(value.to_owned()),
+                "v" | "vehicles" => entry.new_vehicles = Some(value.to_owned()),
+                "p" | "price" => entry.new_price = Some(value.to_owned()),
+                "a" | "actual-price" => entry.new_actual_price = Some(value.to_owned()),
+                other => return Err(format!("line {}: unknown key {:?}", line_no, other)),
+            }
+        }
+
+        let modifier_set = entry.new_rider.is_some()
+            || entry.new_company.is_some()
+            || entry.new_line.is_some()
+            || entry.new_timestamp_str.is_some()
+            || entry.new_vehicles.is_some()
+            || entry.new_price.is_some()
+            || entry.new_actual_price.is_some()
+            || entry.freshen
+        ;
+        if entry.delete && modifier_set {
+            return Err(format!("line {}: cannot delete and change properties at the same time", line_no));
+        }
+        if entry.freshen && entry.new_vehicles.is_some() {
+            return Err(format!("line {}: cannot freshen and specify vehicles at the same time", line_no));
+        }
+        if !entry.delete && !modifier_set {
+            return Err(format!("line {}: nothing to change", line_no));
+        }
+
+        entries.push(entry);
+    }
+
+    if entries.len() == 0 {
+        return Err("no edit operations given".to_owned());
+    }
+
+    Ok(entries)
+}
+
 fn spec_to_vehicles(
     vehicles_str: &str,
     bim_database_opt: Option<&HashMap<VehicleNumber, VehicleInfo>>,
@@ -5326,9 +7351,14 @@ fn spec_to_vehicles(
     Ok(all_vehicles)
 }
 
-pub async fn increment_rides_by_spec(
-    ride_conn: &mut tokio_postgres::Client,
+/// Parses `rides_spec` and registers the ride it describes within `xact`, without committing (or
+/// rolling back) the transaction. Shared by [`increment_rides_by_spec`] (which wraps a single call
+/// in its own transaction) and [`increment_rides_by_spec_batch`] (which reuses one transaction
+/// across many calls).
+async fn register_ride_in_transaction(
+    xact: &tokio_postgres::Transaction<'_>,
     bim_database_opt: Option<&HashMap<VehicleNumber, VehicleInfo>>,
+    gtfs_database_opt: Option<&GtfsLineDatabase>,
     company: &str,
     company_def: &CompanyDefinition,
     rider_username: &str,
@@ -5372,53 +7402,184 @@ pub async fn increment_rides_by_spec(
         .nth(0);
     let line_str_opt = line_cow_str_opt.as_deref();
 
+    let physical_modes = if let (Some(gtfs_database), Some(line_str)) = (gtfs_database_opt, line_str_opt) {
+        if !gtfs_database.contains_line(line_str) {
+            return Err(IncrementBySpecError::UnknownLine(line_str.to_owned()));
+        }
+        gtfs_database.physical_modes_for_line(line_str).into_iter().collect()
+    } else {
+        Vec::new()
+    };
+
     let all_vehicles = spec_to_vehicles(
         vehicles_str,
         bim_database_opt,
         allow_fixed_coupling_combos,
     )?;
 
-    let (ride_id, vehicles) = {
-        let xact = ride_conn.transaction().await
-            .map_err(|e| IncrementBySpecError::DatabaseBeginTransaction(e))?;
+    let (ride_id, vehicles) = add_ride(
+        xact,
+        company,
+        &all_vehicles,
+        rider_username,
+        timestamp,
+        line_str_opt,
+        regular_price,
+        actual_price,
+        sandbox,
+        highlight_coupled_rides,
+    )
+        .await.map_err(|e|
+            IncrementBySpecError::DatabaseQuery(rider_username.to_owned(), all_vehicles.clone(), line_str_opt.map(|l| l.to_owned()), e)
+        )?;
+
+    Ok(RideTableData {
+        ride_id,
+        company: company.to_owned(),
+        line: line_str_opt.map(|l| l.to_owned()),
+        rider_username: rider_username.to_owned(),
+        vehicles,
+        relative_time: Some(timestamp),
+        physical_modes,
+    })
+}
+
+pub async fn increment_rides_by_spec(
+    ride_conn: &mut tokio_postgres::Client,
+    bim_database_opt: Option<&HashMap<VehicleNumber, VehicleInfo>>,
+    gtfs_database_opt: Option<&GtfsLineDatabase>,
+    company: &str,
+    company_def: &CompanyDefinition,
+    rider_username: &str,
+    timestamp: DateTime<Local>,
+    regular_price: &BigDecimal,
+    actual_price: &BigDecimal,
+    rides_spec: &str,
+    allow_fixed_coupling_combos: bool,
+    sandbox: bool,
+    highlight_coupled_rides: bool,
+) -> Result<RideTableData, IncrementBySpecError> {
+    let xact = ride_conn.transaction().await
+        .map_err(|e| IncrementBySpecError::DatabaseBeginTransaction(e))?;
+
+    let ride_table = register_ride_in_transaction(
+        &xact,
+        bim_database_opt,
+        gtfs_database_opt,
+        company,
+        company_def,
+        rider_username,
+        timestamp,
+        regular_price,
+        actual_price,
+        rides_spec,
+        allow_fixed_coupling_combos,
+        sandbox,
+        highlight_coupled_rides,
+    ).await?;
+
+    xact.commit().await
+        .map_err(|e| IncrementBySpecError::DatabaseCommitTransaction(e))?;
+
+    Ok(ride_table)
+}
+
+/// One entry of a [`increment_rides_by_spec_batch`] call: the timestamp, vehicle/line
+/// specification (in the same syntax [`increment_rides_by_spec`] accepts) and prices of a single
+/// ride to import.
+#[derive(Clone, Debug)]
+pub struct BatchRideEntry {
+    pub timestamp: DateTime<Local>,
+    pub rides_spec: String,
+    pub regular_price: BigDecimal,
+    pub actual_price: BigDecimal,
+}
+
+/// Whether a failing entry in [`increment_rides_by_spec_batch`] aborts the whole batch (rolling
+/// back every entry registered so far) or is merely reported, letting the other entries commit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BatchFailureMode {
+    /// Roll back the whole transaction if any entry fails.
+    AllOrNothing,
+    /// Commit every entry that succeeded; report the ones that failed.
+    BestEffort,
+}
+
+/// The outcome of registering one [`BatchRideEntry`] as part of an
+/// [`increment_rides_by_spec_batch`] call.
+pub type BatchRideOutcome = Result<RideTableData, IncrementBySpecError>;
+
+/// Registers every ride in `entries` within a single transaction, without recomputing statistics
+/// (e.g. rerunning achievement recalculation) between them. Returns one [`BatchRideOutcome`] per
+/// entry, in the same order as `entries`, regardless of whether it succeeded or failed.
+///
+/// Under [`BatchFailureMode::AllOrNothing`], the whole transaction is rolled back if any entry
+/// fails -- subsequent entries are not even attempted, and every returned outcome beyond the first
+/// failure is that same error. Under [`BatchFailureMode::BestEffort`], every entry is attempted and
+/// whatever succeeded is committed, even if others failed.
+pub async fn increment_rides_by_spec_batch(
+    ride_conn: &mut tokio_postgres::Client,
+    bim_database_opt: Option<&HashMap<VehicleNumber, VehicleInfo>>,
+    gtfs_database_opt: Option<&GtfsLineDatabase>,
+    company: &str,
+    company_def: &CompanyDefinition,
+    rider_username: &str,
+    entries: &[BatchRideEntry],
+    allow_fixed_coupling_combos: bool,
+    sandbox: bool,
+    highlight_coupled_rides: bool,
+    failure_mode: BatchFailureMode,
+) -> Result<Vec<BatchRideOutcome>, IncrementBySpecError> {
+    let xact = ride_conn.transaction().await
+        .map_err(|e| IncrementBySpecError::DatabaseBeginTransaction(e))?;
+
+    let mut outcomes = Vec::with_capacity(entries.len());
+    let mut any_failed = false;
+    for entry in entries {
+        if any_failed && failure_mode == BatchFailureMode::AllOrNothing {
+            // the transaction is doomed; stop bothering the database, but still report a result
+            // for every entry so the caller sees a 1:1 correspondence
+            outcomes.push(Err(IncrementBySpecError::SpecParseFailure(entry.rides_spec.clone())));
+            continue;
+        }
 
-        let (rid, vehicles) = add_ride(
+        let outcome = register_ride_in_transaction(
             &xact,
+            bim_database_opt,
+            gtfs_database_opt,
             company,
-            &all_vehicles,
+            company_def,
             rider_username,
-            timestamp,
-            line_str_opt,
-            regular_price,
-            actual_price,
+            entry.timestamp,
+            &entry.regular_price,
+            &entry.actual_price,
+            &entry.rides_spec,
+            allow_fixed_coupling_combos,
             sandbox,
             highlight_coupled_rides,
-        )
-            .await.map_err(|e|
-                IncrementBySpecError::DatabaseQuery(rider_username.to_owned(), all_vehicles.clone(), line_str_opt.map(|l| l.to_owned()), e)
-            )?;
+        ).await;
+        if outcome.is_err() {
+            any_failed = true;
+        }
+        outcomes.push(outcome);
+    }
 
+    if any_failed && failure_mode == BatchFailureMode::AllOrNothing {
+        xact.rollback().await
+            .map_err(|e| IncrementBySpecError::DatabaseCommitTransaction(e))?;
+    } else {
         xact.commit().await
             .map_err(|e| IncrementBySpecError::DatabaseCommitTransaction(e))?;
+    }
 
-        (rid, vehicles)
-    };
-
-    Ok(RideTableData {
-        ride_id,
-        company: company.to_owned(),
-        line: line_str_opt.map(|l| l.to_owned()),
-        rider_username: rider_username.to_owned(),
-        vehicles,
-        relative_time: Some(timestamp),
-    })
+    Ok(outcomes)
 }
 
 
 /// Returns the Night Owl Time date for the given date.
 ///
 /// With Night Owl Time, hours 0, 1, 2 and 3 are counted towards the previous day.
-fn get_night_owl_date<D: Datelike + Timelike>(date_time: &D) -> NaiveDate {
+pub(crate) fn get_night_owl_date<D: Datelike + Timelike>(date_time: &D) -> NaiveDate {
     let naive_date = NaiveDate::from_ymd_opt(date_time.year(), date_time.month(), date_time.day())
         .unwrap();
     if date_time.hour() < 4 {
@@ -5429,6 +7590,98 @@ fn get_night_owl_date<D: Datelike + Timelike>(date_time: &D) -> NaiveDate {
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clocks::{Clocks, FixedClocks};
+
+    fn local_datetime(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        let naive = NaiveDate::from_ymd_opt(year, month, day).unwrap()
+            .and_hms_opt(hour, minute, 0).unwrap();
+        match Local.from_local_datetime(&naive) {
+            LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => dt,
+            LocalResult::None => panic!("local time {:?} does not exist", naive),
+        }
+    }
+
+    #[test]
+    fn test_get_night_owl_date_with_fixed_clocks_before_boundary() {
+        // 02:30 is still counted towards the previous Night Owl Time day
+        let clocks = FixedClocks(local_datetime(2024, 3, 5, 2, 30));
+        assert_eq!(get_night_owl_date(&clocks.now()), NaiveDate::from_ymd_opt(2024, 3, 4).unwrap());
+    }
+
+    #[test]
+    fn test_get_night_owl_date_with_fixed_clocks_after_boundary() {
+        let clocks = FixedClocks(local_datetime(2024, 3, 5, 4, 0));
+        assert_eq!(get_night_owl_date(&clocks.now()), NaiveDate::from_ymd_opt(2024, 3, 5).unwrap());
+    }
+
+    #[test]
+    fn test_parse_since_spec_today_follows_fixed_clocks() {
+        let clocks = FixedClocks(local_datetime(2024, 3, 5, 2, 30));
+        let today = parse_since_spec("today", &clocks).unwrap();
+        assert_eq!(today, local_datetime(2024, 3, 5, 0, 0));
+    }
+}
+
+
+/// The longest and current consecutive-day riding streak derived from a set of (Night Owl Time)
+/// ride dates, as used by `{cpfx}topbimstreaks`.
+pub(crate) struct RiderStreaks {
+    longest_start: NaiveDate,
+    longest_end: NaiveDate,
+    longest_len: i64,
+    pub current_len: i64,
+}
+impl RiderStreaks {
+    /// Scans `dates` (assumed sorted, as `BTreeSet` guarantees) for the longest run of
+    /// consecutive calendar days, as well as the length of the run ending on `today` (or
+    /// yesterday, to still count a streak that has not yet been continued today).
+    pub(crate) fn calculate(dates: &BTreeSet<NaiveDate>, today: NaiveDate) -> Self {
+        let mut longest_start = NaiveDate::MIN;
+        let mut longest_end = NaiveDate::MIN;
+        let mut longest_len: i64 = 0;
+
+        let mut run_start = NaiveDate::MIN;
+        let mut run_len: i64 = 0;
+        let mut previous: Option<NaiveDate> = None;
+
+        for &date in dates {
+            match previous {
+                Some(prev) if prev.succ_opt() == Some(date) => {
+                    run_len += 1;
+                },
+                _ => {
+                    run_start = date;
+                    run_len = 1;
+                },
+            }
+
+            if run_len > longest_len {
+                longest_len = run_len;
+                longest_start = run_start;
+                longest_end = date;
+            }
+
+            previous = Some(date);
+        }
+
+        let current_len = match previous {
+            Some(last) if last == today || last.succ_opt() == Some(today) => run_len,
+            _ => 0,
+        };
+
+        Self {
+            longest_start,
+            longest_end,
+            longest_len,
+            current_len,
+        }
+    }
+}
+
+
 /// Attempts to parse the given timestamp string.
 fn try_parse_timestamp(timestamp_str: &str) -> Option<NaiveDateTime> {
     let caps = TIMESTAMP_RE.captures(timestamp_str)?;
@@ -5473,6 +7726,238 @@ fn try_parse_timestamp(timestamp_str: &str) -> Option<NaiveDateTime> {
 }
 
 
+/// Parses a duration given as a single `NUMBER UNIT` phrase (e.g. `"5 minutes"`, `"2 hours"`),
+/// as used in `"... ago"` expressions accepted by [`try_parse_relative_edit_timestamp`]. Unlike
+/// [`parse_relative_duration`], units are whole words (optionally abbreviated) and are separated
+/// from the number by whitespace; only a single number+unit pair is accepted.
+fn parse_word_duration(value: &str) -> Option<Duration> {
+    let mut parts = value.split_whitespace();
+    let number: i64 = parts.next()?.parse().ok()?;
+    let unit = parts.next()?.to_lowercase();
+    if parts.next().is_some() {
+        return None;
+    }
+
+    match unit.as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(Duration::seconds(number)),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(Duration::minutes(number)),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(Duration::hours(number)),
+        "d" | "day" | "days" => Some(Duration::days(number)),
+        "w" | "week" | "weeks" => Some(Duration::weeks(number)),
+        _ => None,
+    }
+}
+
+/// Parses a relative or partial timestamp expression accepted when editing a ride, as a fallback
+/// for when [`try_parse_timestamp`] rejects the input as not an absolute timestamp:
+/// - a signed duration offset from `reference_timestamp` (the ride's existing timestamp), e.g.
+///   `"-2h"`, `"+15m"` (using the same compact `NUMBER`+`UNIT` syntax as [`parse_relative_duration`]);
+///   requires a `reference_timestamp` to offset from, so is rejected when editing has no such ride
+/// - a duration phrase anchored on the current time, e.g. `"5 minutes ago"`
+/// - `"today"`/`"yesterday"` combined with a time of day, e.g. `"yesterday 18:30"`
+fn try_parse_relative_edit_timestamp(clocks: &dyn Clocks, timestamp_str: &str, reference_timestamp: Option<DateTime<Local>>) -> Option<DateTime<Local>> {
+    let trimmed = timestamp_str.trim();
+
+    if let Some(reference) = reference_timestamp {
+        if let Some(unsigned) = trimmed.strip_prefix('-') {
+            let duration = parse_relative_duration(unsigned)?;
+            return Some(reference - duration);
+        }
+        if let Some(unsigned) = trimmed.strip_prefix('+') {
+            let duration = parse_relative_duration(unsigned)?;
+            return Some(reference + duration);
+        }
+    }
+
+    if let Some(rest) = trimmed.strip_suffix("ago") {
+        let duration = parse_word_duration(rest.trim())?;
+        return Some(clocks.now() - duration);
+    }
+
+    let lower = trimmed.to_lowercase();
+    for (word, days_before_today) in [("today", 0i64), ("yesterday", 1i64)] {
+        let time_part = match lower.strip_prefix(word) {
+            Some(tp) => tp.trim(),
+            None => continue,
+        };
+        if time_part.len() == 0 {
+            continue;
+        }
+
+        let naive_time = NaiveDateTime::parse_from_str(&format!("2000-01-01 {}", time_part), "%Y-%m-%d %H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(&format!("2000-01-01 {}", time_part), "%Y-%m-%d %H:%M"))
+            .ok()?
+            .time();
+        let date = clocks.now().date_naive() - Duration::days(days_before_today);
+        let ndt = NaiveDateTime::new(date, naive_time);
+        return match Local.from_local_datetime(&ndt) {
+            LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt),
+            LocalResult::None => None,
+        };
+    }
+
+    None
+}
+
+
+/// Resolves the mutually exclusive `--last-year`/`--last-month`/`--last-week`/`--last-day`/
+/// `--since` lookback options to a single [`LookbackRange`], regardless of whether they were
+/// parsed from chat command flags/options ([`BimPlugin::lookback_range_from_command`]) or from
+/// HTTP query parameters ([`lookback_range_from_query_params`]). `--since` is parsed with
+/// [`parse_lookback`], so it may also carry a full `"START..END"` range.
+fn lookback_range_from_flags(
+    last_year: bool,
+    last_month: bool,
+    last_week: bool,
+    last_day: bool,
+    since_str: Option<&str>,
+    clocks: &dyn Clocks,
+) -> Option<LookbackRange> {
+    let flag_range = match (last_year, last_month, last_week, last_day) {
+        (true, false, false, false) => Some(LookbackRange::LastYear),
+        (false, true, false, false) => Some(LookbackRange::LastMonth),
+        (false, false, true, false) => Some(LookbackRange::LastWeek),
+        (false, false, false, true) => Some(LookbackRange::LastDay),
+        (false, false, false, false) => Some(LookbackRange::SinceBeginning),
+        _ => None,
+    }?;
+
+    match since_str {
+        None => Some(flag_range),
+        Some(since) if flag_range == LookbackRange::SinceBeginning => {
+            parse_lookback(since, clocks)
+        },
+        Some(_) => None, // mixing --since with one of the other lookback flags
+    }
+}
+
+/// Like [`BimPlugin::lookback_range_from_command`], but for the admin HTTP API, whose requests
+/// carry the same lookback parameters (`m`/`last-month`, `y`/`last-year`, `w`/`last-week`,
+/// `d`/`last-day`, `since`/`s`) as query-string parameters instead of command flags/options.
+pub(crate) fn lookback_range_from_query_params(params: &HashMap<String, String>, clocks: &dyn Clocks) -> Option<LookbackRange> {
+    let is_set = |name: &str| params.get(name).map(|v| v != "0" && !v.eq_ignore_ascii_case("false")).unwrap_or(false);
+
+    let last_month = is_set("m") || is_set("last-month");
+    let last_year = is_set("y") || is_set("last-year");
+    let last_week = is_set("w") || is_set("last-week");
+    let last_day = is_set("d") || is_set("last-day");
+    let since_str = params.get("since")
+        .or_else(|| params.get("s"))
+        .map(|s| s.as_str());
+
+    lookback_range_from_flags(last_year, last_month, last_week, last_day, since_str, clocks)
+}
+
+/// Parses the value of a `--since`/`-s` lookback option into a [`LookbackRange`]. Recognises an
+/// explicit `"START..END"` date range (each side in `parse_since_spec` syntax) as a
+/// [`LookbackRange::Range`]; everything else -- absolute dates, anchored phrases and relative
+/// durations -- is delegated to [`parse_since_spec`] and wrapped in a [`LookbackRange::Since`].
+fn parse_lookback(value: &str, clocks: &dyn Clocks) -> Option<LookbackRange> {
+    let trimmed = value.trim();
+
+    if let Some((start_str, end_str)) = trimmed.split_once("..") {
+        let start = parse_since_spec(start_str, clocks)?;
+        let end = parse_since_spec(end_str, clocks)?;
+        return Some(LookbackRange::Range(start, end));
+    }
+
+    parse_since_spec(trimmed, clocks).map(LookbackRange::Since)
+}
+
+fn parse_since_spec(value: &str, clocks: &dyn Clocks) -> Option<DateTime<Local>> {
+    let trimmed = value.trim();
+    if trimmed.len() == 0 {
+        return None;
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(trimmed, "%Y-%m-%d") {
+        return naive_date_to_local_midnight(date);
+    }
+
+    let today = clocks.now().date_naive();
+    match trimmed.to_lowercase().as_str() {
+        "today" => return naive_date_to_local_midnight(today),
+        "yesterday" => return naive_date_to_local_midnight(today - Duration::days(1)),
+        "this week" | "this-week" => return naive_date_to_local_midnight(start_of_week(today)),
+        "last week" | "last-week" => return naive_date_to_local_midnight(start_of_week(today) - Duration::weeks(1)),
+        "this month" | "this-month" => return naive_date_to_local_midnight(start_of_month(today)),
+        "last month" | "last-month" => return naive_date_to_local_midnight(start_of_month(start_of_month(today) - Duration::days(1))),
+        "this year" | "this-year" => return naive_date_to_local_midnight(NaiveDate::from_ymd_opt(today.year(), 1, 1)?),
+        "last year" | "last-year" => return naive_date_to_local_midnight(NaiveDate::from_ymd_opt(today.year() - 1, 1, 1)?),
+        _ => {},
+    }
+
+    let duration = parse_relative_duration(trimmed)?;
+    Some(clocks.now() - duration)
+}
+
+fn naive_date_to_local_midnight(date: NaiveDate) -> Option<DateTime<Local>> {
+    match Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?) {
+        LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) => Some(dt),
+        LocalResult::None => None,
+    }
+}
+
+fn start_of_week(date: NaiveDate) -> NaiveDate {
+    let days_since_monday = date.weekday().num_days_from_monday() as i64;
+    date - Duration::days(days_since_monday)
+}
+
+fn start_of_month(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), date.month(), 1)
+        .expect("first of an existing month must be a valid date")
+}
+
+/// Parses a sequence of `number`+`unit` tokens (e.g. `"1w3d"`) into their summed duration. Known
+/// units are `s` (seconds), `m` (minutes), `h` (hours), `d` (days), `w` (weeks), `mo` (months,
+/// approximated as 30 days) and `y` (years, approximated as 365 days).
+fn parse_relative_duration(value: &str) -> Option<Duration> {
+    let bytes = value.as_bytes();
+    let mut index = 0;
+    let mut total = Duration::zero();
+    let mut matched_any = false;
+
+    while index < bytes.len() {
+        let number_start = index;
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+        if index == number_start {
+            return None;
+        }
+        let number: i64 = value[number_start..index].parse().ok()?;
+
+        let unit_start = index;
+        while index < bytes.len() && bytes[index].is_ascii_alphabetic() {
+            index += 1;
+        }
+        if index == unit_start {
+            return None;
+        }
+        let unit = value[unit_start..index].to_lowercase();
+
+        let component = match unit.as_str() {
+            "s" => Duration::seconds(number),
+            "m" => Duration::minutes(number),
+            "h" => Duration::hours(number),
+            "d" => Duration::days(number),
+            "w" => Duration::weeks(number),
+            "mo" => Duration::days(number * 30),
+            "y" => Duration::days(number * 365),
+            _ => return None,
+        };
+        total = total + component;
+        matched_any = true;
+    }
+
+    if matched_any {
+        Some(total)
+    } else {
+        None
+    }
+}
+
+
 /// Returns whether the sole digit block in the given vehicle number is divisible by the sole digit
 /// block in the given line number.
 ///
@@ -5531,3 +8016,19 @@ fn fold_whitespace_xml(s: &str) -> Cow<str> {
         }
     })
 }
+
+/// Renders `n` with its English ordinal suffix (`1` -> `"1st"`, `11` -> `"11th"`, `22` ->
+/// `"22nd"`).
+fn ordinal(n: i64) -> String {
+    let suffix = if (11..=13).contains(&(n.rem_euclid(100))) {
+        "th"
+    } else {
+        match n.rem_euclid(10) {
+            1 => "st",
+            2 => "nd",
+            3 => "rd",
+            _ => "th",
+        }
+    };
+    format!("{}{}", n, suffix)
+}
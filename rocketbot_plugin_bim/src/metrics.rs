@@ -0,0 +1,610 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Weak;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate};
+use http_body_util::Full;
+use hyper::{Request, Response};
+use hyper::body::{Bytes, Incoming};
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use rocketbot_bim_common::VehicleNumber;
+use tokio::net::TcpListener;
+use tracing::error;
+
+use crate::{BimTypeStats, Config, connect_ride_db, fixed_coupling_monopolies, get_night_owl_date, load_bim_database, LookbackRange, naive_date_to_local_midnight, PlusMinus, RiderStreaks, RwLock};
+use crate::ride_store::{PostgresRideStore, RideStore};
+
+
+/// How many days of Night Owl Time history the `bim_rides_by_night_owl_date_total` histogram
+/// covers. Kept bounded so that the number of exported time series does not grow forever.
+const NIGHT_OWL_HISTOGRAM_DAYS: i64 = 30;
+
+
+/// Number of times establishing a ride database connection (`connect_ride_db`) has failed.
+static FAILED_DB_CONNECTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of times beginning or committing a ride database transaction has failed.
+static FAILED_TRANSACTIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of achievement recalculations enqueued via `achievement_update_sender`.
+static ACHIEVEMENT_RECALCS_ENQUEUED: AtomicU64 = AtomicU64::new(0);
+
+/// Called whenever `connect_ride_db` fails to establish a connection.
+pub(crate) fn record_failed_db_connection() {
+    FAILED_DB_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Called whenever beginning or committing a ride database transaction fails.
+pub(crate) fn record_failed_transaction() {
+    FAILED_TRANSACTIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Called whenever an achievement recalculation is enqueued.
+pub(crate) fn record_achievement_recalc_enqueued() {
+    ACHIEVEMENT_RECALCS_ENQUEUED.fetch_add(1, Ordering::Relaxed);
+}
+
+
+/// Binds `listen_address` and serves a Prometheus text-format `/metrics` endpoint. The exposed
+/// text is recomputed from the same `bim.rides`/`bim.ride_vehicles` joins and `load_bim_database`
+/// lookups as `channel_command_bimtypes`/`bimriderlines`/`bimridertypes`/`recentbimrides`/
+/// `lastbimriderbalance`/`bimdivscore`/`bimcost`/`bimfixedmonopolies`/`topbimstreaks` every
+/// `refresh_interval_s` seconds rather than on each scrape, so frequent scraping does not add load
+/// to the ride database.
+pub(crate) async fn serve_metrics(listen_address: String, refresh_interval_s: i64, config: Weak<RwLock<Config>>) {
+    let listener = match TcpListener::bind(&listen_address).await {
+        Ok(l) => l,
+        Err(e) => {
+            error!("failed to bind bim metrics listener on {}: {}", listen_address, e);
+            return;
+        },
+    };
+
+    let cache: Arc<RwLock<Option<String>>> = Arc::new(RwLock::new(None));
+    {
+        let cache = Arc::clone(&cache);
+        let config = Weak::clone(&config);
+        tokio::spawn(async move {
+            refresh_cache_periodically(cache, config, refresh_interval_s).await;
+        });
+    }
+
+    loop {
+        let (stream, remote_addr) = match listener.accept().await {
+            Ok(sa) => sa,
+            Err(e) => {
+                error!("failed to accept bim metrics connection: {}", e);
+                continue;
+            },
+        };
+
+        let cache = Arc::clone(&cache);
+        tokio::spawn(async move {
+            let io = TokioIo::new(stream);
+            let service = service_fn(move |req| {
+                let cache = Arc::clone(&cache);
+                async move { handle_metrics_request(req, cache).await }
+            });
+            let serve_result = Builder::new(TokioExecutor::new())
+                .http1()
+                .serve_connection(io, service)
+                .await;
+            if let Err(e) = serve_result {
+                error!("error serving bim metrics connection from {}: {}", remote_addr, e);
+            }
+        });
+    }
+}
+
+/// Recomputes the cached metrics text every `refresh_interval_s` seconds until `config` can no
+/// longer be upgraded (i.e. the bim plugin has been unloaded).
+async fn refresh_cache_periodically(cache: Arc<RwLock<Option<String>>>, config: Weak<RwLock<Config>>, refresh_interval_s: i64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(refresh_interval_s.max(1) as u64));
+    loop {
+        interval.tick().await;
+
+        let config_lock = match Weak::upgrade(&config) {
+            Some(cl) => cl,
+            None => return,
+        };
+        let body = {
+            let config_guard = config_lock.read().await;
+            render_metrics(&config_guard).await
+        };
+
+        *cache.write().await = body;
+    }
+}
+
+fn text_response(status: u16, body: &'static str) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .body(Full::new(Bytes::from(body)))
+        .expect("failed to assemble bim metrics response")
+}
+
+async fn handle_metrics_request(request: Request<Incoming>, cache: Arc<RwLock<Option<String>>>) -> Result<Response<Full<Bytes>>, Infallible> {
+    if request.uri().path() != "/metrics" {
+        return Ok(text_response(404, "404 Not Found"));
+    }
+
+    let body = match cache.read().await.clone() {
+        Some(b) => b,
+        None => return Ok(text_response(503, "503 Service Unavailable")),
+    };
+
+    let response = Response::builder()
+        .status(200)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .body(Full::new(Bytes::from(body)))
+        .expect("failed to assemble bim metrics response");
+    Ok(response)
+}
+
+async fn render_metrics(config: &Config) -> Option<String> {
+    let ride_conn = match connect_ride_db(config).await {
+        Ok(rc) => rc,
+        Err(e) => {
+            error!("failed to open database connection for bim metrics: {}", e);
+            return None;
+        },
+    };
+
+    let ride_total_rows = ride_conn.query(
+        "
+            SELECT r.company, r.line, CAST(COUNT(*) AS bigint)
+            FROM bim.rides r
+            GROUP BY r.company, r.line
+        ",
+        &[],
+    ).await;
+    let ride_total_rows = match ride_total_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query ride totals for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut rides_total: BTreeMap<(String, String), i64> = BTreeMap::new();
+    for row in ride_total_rows {
+        let company: String = row.get(0);
+        let line: Option<String> = row.get(1);
+        let ride_count: i64 = row.get(2);
+        rides_total.insert((company, line.unwrap_or_default()), ride_count);
+    }
+
+    let ridden_vehicle_rows = ride_conn.query(
+        "
+            SELECT DISTINCT r.company, rv.vehicle_number
+            FROM bim.rides r
+            INNER JOIN bim.ride_vehicles rv
+                ON rv.ride_id = r.id
+            WHERE rv.coupling_mode = 'R'
+        ",
+        &[],
+    ).await;
+    let ridden_vehicle_rows = match ridden_vehicle_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query ridden vehicles for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut company_to_ridden_vehicles: HashMap<String, HashSet<VehicleNumber>> = HashMap::new();
+    for row in ridden_vehicle_rows {
+        let company: String = row.get(0);
+        let vehicle_number = VehicleNumber::from_string(row.get(1));
+        company_to_ridden_vehicles
+            .entry(company)
+            .or_insert_with(|| HashSet::new())
+            .insert(vehicle_number);
+    }
+
+    let distinct_rider_rows = ride_conn.query(
+        "
+            SELECT r.company, CAST(COUNT(DISTINCT LOWER(r.rider_username)) AS bigint)
+            FROM bim.rides r
+            GROUP BY r.company
+        ",
+        &[],
+    ).await;
+    let distinct_rider_rows = match distinct_rider_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query distinct riders for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut distinct_riders: BTreeMap<String, i64> = BTreeMap::new();
+    for row in distinct_rider_rows {
+        let company: String = row.get(0);
+        let rider_count: i64 = row.get(1);
+        distinct_riders.insert(company, rider_count);
+    }
+
+    let today_midnight = naive_date_to_local_midnight(Local::now().date_naive());
+    let rides_today_rows = match &today_midnight {
+        Some(midnight) => {
+            let rows = ride_conn.query(
+                "
+                    SELECT r.company, CAST(COUNT(*) AS bigint)
+                    FROM bim.rides r
+                    WHERE r.\"timestamp\" >= $1
+                    GROUP BY r.company
+                ",
+                &[midnight],
+            ).await;
+            match rows {
+                Ok(rs) => rs,
+                Err(e) => {
+                    error!("failed to query today's rides for bim metrics: {}", e);
+                    return None;
+                },
+            }
+        },
+        None => Vec::new(),
+    };
+    let mut rides_today: BTreeMap<String, i64> = BTreeMap::new();
+    for row in rides_today_rows {
+        let company: String = row.get(0);
+        let ride_count: i64 = row.get(1);
+        rides_today.insert(company, ride_count);
+    }
+
+    let rider_ride_count_rows = ride_conn.query(
+        "
+            SELECT r.rider_username, CAST(COUNT(*) AS bigint)
+            FROM bim.rides r
+            GROUP BY r.rider_username
+        ",
+        &[],
+    ).await;
+    let rider_ride_count_rows = match rider_ride_count_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query per-rider ride counts for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut rider_rides_total: BTreeMap<String, i64> = BTreeMap::new();
+    for row in rider_ride_count_rows {
+        let rider_username: String = row.get(0);
+        let ride_count: i64 = row.get(1);
+        rider_rides_total.insert(rider_username, ride_count);
+    }
+
+    // mirrors channel_command_lastbimriderbalance, but without a time window
+    let last_rider_rows = ride_conn.query(
+        "
+            SELECT rvto.old_rider, rvto.new_rider
+            FROM bim.ridden_vehicles_between_riders(FALSE) rvto
+            ORDER BY rvto.\"timestamp\"
+        ",
+        &[],
+    ).await;
+    let last_rider_rows = match last_rider_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query last-rider transitions for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut rider_to_plus_minus: BTreeMap<String, PlusMinus> = BTreeMap::new();
+    let mut first_ride_count: i64 = 0;
+    for row in last_rider_rows {
+        let prev_rider: Option<String> = row.get(0);
+        let now_rider: String = row.get(1);
+
+        if let Some(pr) = prev_rider {
+            if pr == now_rider {
+                continue;
+            }
+            rider_to_plus_minus.entry(pr).or_insert_with(|| PlusMinus::default()).minus += 1;
+        } else {
+            first_ride_count += 1;
+        }
+
+        rider_to_plus_minus.entry(now_rider).or_insert_with(|| PlusMinus::default()).plus += 1;
+    }
+
+    // mirrors channel_command_bimdivscore, but without a time window
+    let div_score_rows = ride_conn.query(
+        // SUBSTRING SIMILAR extraction is done by wrapping the subpattern
+        // in sequences of the escape character followed by the double quote
+        "
+            WITH
+                ride_numbers(id, rider_username, vehicle_number, line_number) AS (
+                    SELECT
+                        rarv.id, rarv.rider_username,
+                        bim.char_to_bigint_or_null(SUBSTRING(rarv.vehicle_number SIMILAR '[^0-9]*#\"[0-9]+#\"[^0-9]*' ESCAPE '#')),
+                        bim.char_to_bigint_or_null(SUBSTRING(rarv.line SIMILAR '[^0-9]*#\"[0-9]+#\"[^0-9]*' ESCAPE '#'))
+                    FROM bim.rides_and_ridden_vehicles rarv
+                    WHERE
+                        rarv.line IS NOT NULL
+                        AND rarv.line SIMILAR TO '[^0-9]*[0-9]+[^0-9]*'
+                        AND rarv.vehicle_number SIMILAR TO '[^0-9]*[0-9]+[^0-9]*'
+                ),
+                not_null_ride_numbers(id, rider_username, vehicle_number, line_number) AS (
+                    SELECT id, rider_username, vehicle_number, line_number
+                    FROM ride_numbers
+                    WHERE vehicle_number IS NOT NULL
+                    AND line_number IS NOT NULL
+                )
+            SELECT
+                nnrn.rider_username,
+                CAST(SUM(nnrn.line_number) AS bigint) div_score
+            FROM not_null_ride_numbers nnrn
+            WHERE MOD(nnrn.vehicle_number, nnrn.line_number) = 0
+            GROUP BY nnrn.rider_username
+        ",
+        &[],
+    ).await;
+    let div_score_rows = match div_score_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query div-scores for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut rider_to_div_score: BTreeMap<String, i64> = BTreeMap::new();
+    for row in div_score_rows {
+        let rider_username: String = row.get(0);
+        let div_score: i64 = row.get(1);
+        rider_to_div_score.insert(rider_username, div_score);
+    }
+
+    let ride_store = PostgresRideStore::new(ride_conn);
+    let mut company_rider_to_lone_vehicles: BTreeMap<(String, String), i64> = BTreeMap::new();
+    for company in config.company_to_definition.keys() {
+        let lone_counts = match ride_store.lone_rider_counts(LookbackRange::SinceBeginning, Some(company.as_str())).await {
+            Ok(lc) => lc,
+            Err(e) => {
+                error!("failed to query lone-vehicle counts for bim metrics: {}", e);
+                return None;
+            },
+        };
+        for rider_vehicle_count in lone_counts {
+            company_rider_to_lone_vehicles.insert(
+                (company.clone(), rider_vehicle_count.rider_username),
+                rider_vehicle_count.vehicle_count,
+            );
+        }
+    }
+
+    let mut company_type_to_stats: BTreeMap<(String, String), BimTypeStats> = BTreeMap::new();
+    let mut company_rider_to_monopoly_count: BTreeMap<(String, String), i64> = BTreeMap::new();
+    for company in config.company_to_definition.keys() {
+        let database = match load_bim_database(config, company) {
+            Some(db) => db,
+            None => continue,
+        };
+        let ridden_vehicles = company_to_ridden_vehicles.get(company);
+        for vehicle in database.values() {
+            let stats = company_type_to_stats
+                .entry((company.clone(), vehicle.type_code.clone()))
+                .or_insert_with(|| BimTypeStats::new());
+            stats.known_vehicles += 1;
+            if vehicle.in_service_since.is_some() && vehicle.out_of_service_since.is_none() {
+                stats.active_vehicles += 1;
+            }
+            if ridden_vehicles.map(|rv| rv.contains(&vehicle.number)).unwrap_or(false) {
+                stats.ridden_vehicles += 1;
+            }
+        }
+
+        // mirrors channel_command_bimfixedmonopolies
+        let rider_to_coupling_length_to_count = match fixed_coupling_monopolies(&ride_conn, company, &database).await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to compute fixed-coupling monopolies for bim metrics, company {:?}: {}", company, e);
+                return None;
+            },
+        };
+        for (rider, coupling_length_to_count) in rider_to_coupling_length_to_count {
+            let total_count: i64 = coupling_length_to_count.values().map(|count| *count as i64).sum();
+            company_rider_to_monopoly_count.insert((company.clone(), rider), total_count);
+        }
+    }
+
+    // mirrors channel_command_topbimstreaks, but for every rider at once and without a filter
+    let all_ride_timestamp_rows = ride_conn.query(
+        "
+            SELECT r.rider_username, r.\"timestamp\"
+            FROM bim.rides r
+        ",
+        &[],
+    ).await;
+    let all_ride_timestamp_rows = match all_ride_timestamp_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query ride timestamps for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut rider_to_night_owl_dates: BTreeMap<String, BTreeSet<NaiveDate>> = BTreeMap::new();
+    for row in &all_ride_timestamp_rows {
+        let rider_username: String = row.get(0);
+        let timestamp: DateTime<Local> = row.get(1);
+        rider_to_night_owl_dates
+            .entry(rider_username)
+            .or_insert_with(|| BTreeSet::new())
+            .insert(get_night_owl_date(&timestamp));
+    }
+
+    let today = get_night_owl_date(&Local::now());
+    let mut rider_to_current_streak: BTreeMap<String, i64> = BTreeMap::new();
+    for (rider, dates) in &rider_to_night_owl_dates {
+        let streaks = RiderStreaks::calculate(dates, today);
+        rider_to_current_streak.insert(rider.clone(), streaks.current_len);
+    }
+
+    // a company/date histogram of ride counts, bounded to the last NIGHT_OWL_HISTOGRAM_DAYS days
+    // so the number of exported time series stays finite
+    let histogram_start_date = today - chrono::Duration::days(NIGHT_OWL_HISTOGRAM_DAYS);
+    let company_night_owl_rows = match naive_date_to_local_midnight(histogram_start_date) {
+        Some(cutoff) => {
+            let rows = ride_conn.query(
+                "
+                    SELECT r.company, r.\"timestamp\"
+                    FROM bim.rides r
+                    WHERE r.\"timestamp\" >= $1
+                ",
+                &[&cutoff],
+            ).await;
+            match rows {
+                Ok(rs) => rs,
+                Err(e) => {
+                    error!("failed to query recent ride timestamps for bim metrics: {}", e);
+                    return None;
+                },
+            }
+        },
+        None => Vec::new(),
+    };
+    let mut company_date_to_ride_count: BTreeMap<(String, NaiveDate), i64> = BTreeMap::new();
+    for row in company_night_owl_rows {
+        let company: String = row.get(0);
+        let timestamp: DateTime<Local> = row.get(1);
+        let night_owl_date = get_night_owl_date(&timestamp);
+        if night_owl_date < histogram_start_date {
+            // ride fell before the cutoff once folded into the previous Night Owl Time date
+            continue;
+        }
+        *company_date_to_ride_count.entry((company, night_owl_date)).or_insert(0) += 1;
+    }
+
+    let ride_db = config.ride_db_backend.ride_db();
+    let savings_rows = ride_conn.query(
+        &format!(
+            "
+                SELECT r.company, {} savings
+                FROM bim.rides r
+                GROUP BY r.company
+            ",
+            ride_db.format_money_as_text("COALESCE(SUM(r.regular_price - r.actual_price), 0)"),
+        ),
+        &[],
+    ).await;
+    let savings_rows = match savings_rows {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("failed to query cumulative savings for bim metrics: {}", e);
+            return None;
+        },
+    };
+    let mut company_to_savings: BTreeMap<String, String> = BTreeMap::new();
+    for row in savings_rows {
+        let company: String = row.get(0);
+        let savings: String = row.get(1);
+        company_to_savings.insert(company, savings.trim().to_owned());
+    }
+
+    let mut output = String::new();
+    output.push_str("# HELP bim_rides_total Total number of registered rides, by company and line.\n");
+    output.push_str("# TYPE bim_rides_total counter\n");
+    for ((company, line), ride_count) in &rides_total {
+        output.push_str(&format!("bim_rides_total{{company={:?},line={:?}}} {}\n", company, line, ride_count));
+    }
+
+    output.push_str("# HELP bim_vehicles_known Number of vehicles known to the bim database, by company and type.\n");
+    output.push_str("# TYPE bim_vehicles_known gauge\n");
+    for ((company, type_code), stats) in &company_type_to_stats {
+        output.push_str(&format!("bim_vehicles_known{{company={:?},type={:?}}} {}\n", company, type_code, stats.known_vehicles));
+    }
+
+    output.push_str("# HELP bim_vehicles_active Number of vehicles currently in service, by company and type.\n");
+    output.push_str("# TYPE bim_vehicles_active gauge\n");
+    for ((company, type_code), stats) in &company_type_to_stats {
+        output.push_str(&format!("bim_vehicles_active{{company={:?},type={:?}}} {}\n", company, type_code, stats.active_vehicles));
+    }
+
+    output.push_str("# HELP bim_vehicles_ridden Number of distinct vehicles that have been ridden, by company and type.\n");
+    output.push_str("# TYPE bim_vehicles_ridden gauge\n");
+    for ((company, type_code), stats) in &company_type_to_stats {
+        output.push_str(&format!("bim_vehicles_ridden{{company={:?},type={:?}}} {}\n", company, type_code, stats.ridden_vehicles));
+    }
+
+    output.push_str("# HELP bim_distinct_riders Number of distinct riders that have registered a ride, by company.\n");
+    output.push_str("# TYPE bim_distinct_riders gauge\n");
+    for (company, rider_count) in &distinct_riders {
+        output.push_str(&format!("bim_distinct_riders{{company={:?}}} {}\n", company, rider_count));
+    }
+
+    output.push_str("# HELP bim_rider_rides_total Total number of registered rides, by rider.\n");
+    output.push_str("# TYPE bim_rider_rides_total counter\n");
+    for (rider, ride_count) in &rider_rides_total {
+        output.push_str(&format!("bim_rider_rides_total{{rider={:?}}} {}\n", rider, ride_count));
+    }
+
+    output.push_str("# HELP bim_last_rider_balance Last-rider status balance (vehicles taken minus vehicles given up), by rider.\n");
+    output.push_str("# TYPE bim_last_rider_balance gauge\n");
+    for (rider, pm) in &rider_to_plus_minus {
+        output.push_str(&format!("bim_last_rider_balance{{rider={:?}}} {}\n", rider, pm.plus - pm.minus));
+    }
+
+    output.push_str("# HELP bim_first_rides_total Number of rides that were the first recorded ride of their vehicle.\n");
+    output.push_str("# TYPE bim_first_rides_total gauge\n");
+    output.push_str(&format!("bim_first_rides_total {}\n", first_ride_count));
+
+    output.push_str("# HELP bim_div_score Divisibility score (see bimdivscore), by rider.\n");
+    output.push_str("# TYPE bim_div_score gauge\n");
+    for (rider, div_score) in &rider_to_div_score {
+        output.push_str(&format!("bim_div_score{{rider={:?}}} {}\n", rider, div_score));
+    }
+
+    output.push_str("# HELP bim_vehicles_lone Number of vehicles ridden by only one rider so far, by company and rider.\n");
+    output.push_str("# TYPE bim_vehicles_lone gauge\n");
+    for ((company, rider), lone_count) in &company_rider_to_lone_vehicles {
+        output.push_str(&format!("bim_vehicles_lone{{company={:?},rider={:?}}} {}\n", company, rider, lone_count));
+    }
+
+    output.push_str("# HELP bim_rides_today Number of rides registered since local midnight, by company. Scrape this over time to chart rides-per-day trends.\n");
+    output.push_str("# TYPE bim_rides_today gauge\n");
+    for (company, ride_count) in &rides_today {
+        output.push_str(&format!("bim_rides_today{{company={:?}}} {}\n", company, ride_count));
+    }
+
+    output.push_str("# HELP bim_savings_total Cumulative savings (regular price minus actual price) across all rides, by company.\n");
+    output.push_str("# TYPE bim_savings_total counter\n");
+    for (company, savings) in &company_to_savings {
+        output.push_str(&format!("bim_savings_total{{company={:?}}} {}\n", company, savings));
+    }
+
+    output.push_str("# HELP bim_fixed_coupling_monopolies Number of fixed couplings whose vehicles were all last ridden by the same rider, by company and rider.\n");
+    output.push_str("# TYPE bim_fixed_coupling_monopolies gauge\n");
+    for ((company, rider), monopoly_count) in &company_rider_to_monopoly_count {
+        output.push_str(&format!("bim_fixed_coupling_monopolies{{company={:?},rider={:?}}} {}\n", company, rider, monopoly_count));
+    }
+
+    output.push_str("# HELP bim_failed_db_connections_total Number of times establishing a ride database connection has failed since startup.\n");
+    output.push_str("# TYPE bim_failed_db_connections_total counter\n");
+    output.push_str(&format!("bim_failed_db_connections_total {}\n", FAILED_DB_CONNECTIONS.load(Ordering::Relaxed)));
+
+    output.push_str("# HELP bim_failed_transactions_total Number of times beginning or committing a ride database transaction has failed since startup.\n");
+    output.push_str("# TYPE bim_failed_transactions_total counter\n");
+    output.push_str(&format!("bim_failed_transactions_total {}\n", FAILED_TRANSACTIONS.load(Ordering::Relaxed)));
+
+    output.push_str("# HELP bim_achievement_recalcs_enqueued_total Number of achievement recalculations enqueued since startup.\n");
+    output.push_str("# TYPE bim_achievement_recalcs_enqueued_total counter\n");
+    output.push_str(&format!("bim_achievement_recalcs_enqueued_total {}\n", ACHIEVEMENT_RECALCS_ENQUEUED.load(Ordering::Relaxed)));
+
+    output.push_str("# HELP bim_rider_current_streak_days Length, in days, of the Night Owl Time riding streak currently active for a rider (see topbimstreaks), by rider.\n");
+    output.push_str("# TYPE bim_rider_current_streak_days gauge\n");
+    for (rider, current_streak) in &rider_to_current_streak {
+        output.push_str(&format!("bim_rider_current_streak_days{{rider={:?}}} {}\n", rider, current_streak));
+    }
+
+    output.push_str(&format!("# HELP bim_rides_by_night_owl_date_total Number of rides registered on a given Night Owl Time date (see topbimstreaks), by company and date, for the last {} days.\n", NIGHT_OWL_HISTOGRAM_DAYS));
+    output.push_str("# TYPE bim_rides_by_night_owl_date_total gauge\n");
+    for ((company, date), ride_count) in &company_date_to_ride_count {
+        output.push_str(&format!("bim_rides_by_night_owl_date_total{{company={:?},date={:?}}} {}\n", company, date.format("%Y-%m-%d").to_string(), ride_count));
+    }
+
+    Some(output)
+}
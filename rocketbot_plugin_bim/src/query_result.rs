@@ -0,0 +1,65 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+
+/// The current version of the [`BimQueryResult`] wire format, bumped whenever the shape of
+/// [`BimQueryResult`] or one of its row types changes in a way that is not purely additive.
+pub(crate) const BIM_QUERY_RESULT_VERSION: u32 = 1;
+
+
+/// A single ride, as returned by ride-listing commands such as `recentbimrides`.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct BimQueryRide {
+    pub ride_id: i64,
+    pub timestamp: DateTime<Local>,
+    pub rider: String,
+    pub line: Option<String>,
+    pub vehicles: String,
+    pub taken_from: Option<String>,
+}
+
+/// A rider's plus/minus balance, as returned by `lastbimriderbalance`.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct BimQueryRiderBalance {
+    pub rider: String,
+    pub plus: i64,
+    pub minus: i64,
+}
+
+/// A rider's score, as returned by `bimdivscore`.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct BimQueryRiderScore {
+    pub rider: String,
+    pub score: i64,
+}
+
+/// The common result shape shared by the `bim` plugin's statistics commands, populated from the
+/// same query rows that would otherwise only feed the hand-built chat tables. This decouples data
+/// extraction from presentation, so a command's rows can be consumed by something other than the
+/// column-aligned text formatter, e.g. the `-j`/`--json` chat flag ([`serialize_bim_query_result`])
+/// or an equivalent HTTP admin API endpoint.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum BimQueryResult {
+    Rides(Vec<BimQueryRide>),
+    RiderBalances(Vec<BimQueryRiderBalance>),
+    RiderScores(Vec<BimQueryRiderScore>),
+}
+
+#[derive(Serialize)]
+struct BimQueryResultEnvelope<'r> {
+    version: u32,
+    result: &'r BimQueryResult,
+}
+
+/// Flattens a [`BimQueryResult`] into a versioned, self-describing byte buffer (currently JSON,
+/// matching the rest of this plugin's machine-readable output) suitable for attaching to a chat
+/// message or returning directly from an HTTP endpoint.
+pub(crate) fn serialize_bim_query_result(result: &BimQueryResult) -> Vec<u8> {
+    let envelope = BimQueryResultEnvelope {
+        version: BIM_QUERY_RESULT_VERSION,
+        result,
+    };
+    serde_json::to_vec(&envelope)
+        .expect("failed to serialize bim query result")
+}
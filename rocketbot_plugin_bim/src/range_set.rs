@@ -80,6 +80,7 @@ impl<T: fmt::Display + Ord> fmt::Display for OrderableRange<T> {
 }
 
 
+#[derive(Clone)]
 pub struct RangeSet<T: Clone + Ord + QuasiStep> {
     inner_set: BTreeSet<OrderableRange<T>>,
 }
@@ -0,0 +1,85 @@
+//! Backend-specific SQL fragment generation for the ride database.
+//!
+//! [`connect_ride_db`](crate::connect_ride_db) still only ever opens a PostgreSQL connection --
+//! what varies per [`RideDbBackend`] is the *shape* of the handful of SQL fragments that are not
+//! portable between engines (money formatting, integer-divisibility predicates). A command that
+//! needs one of these fragments asks the [`RideDb`] implementation selected by
+//! `Config::ride_db_backend` to build it instead of hard-coding PostgreSQL syntax inline.
+//!
+//! There is no `SqliteRideDb` connection or query-execution path yet: selecting
+//! [`RideDbBackend::Sqlite`] only changes which fragments [`channel_command_bimcost`] and its
+//! siblings ask for, it does not open a SQLite database. Getting an embedded engine actually
+//! running behind `connect_ride_db` -- and translating the `bimdivscore` query's
+//! `bim.char_to_bigint_or_null` stored function and `SIMILAR TO`/`SUBSTRING ... SIMILAR` regex
+//! matching, neither of which SQLite has a built-in equivalent for -- is a larger follow-up that
+//! is not attempted here.
+
+use serde::{Deserialize, Serialize};
+
+
+/// Selects which [`RideDb`] fragment generator a bim command should consult.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum RideDbBackend {
+    #[default]
+    Postgres,
+    Sqlite,
+}
+impl RideDbBackend {
+    pub(crate) fn ride_db(&self) -> &'static dyn RideDb {
+        match self {
+            RideDbBackend::Postgres => &PostgresRideDb,
+            RideDbBackend::Sqlite => &SqliteRideDb,
+        }
+    }
+}
+
+
+/// Generates the handful of SQL fragments whose syntax differs between database engines.
+pub(crate) trait RideDb: Send + Sync {
+    /// Wraps `sql_expr` (a numeric SQL expression, e.g. a column reference or `SUM(...)`) so that
+    /// it is rendered as fixed-point decimal text, the way PostgreSQL's
+    /// `TO_CHAR(expr, POSTGRES_MONEY_FORMAT)` does.
+    fn format_money_as_text(&self, sql_expr: &str) -> String;
+
+    /// Wraps `placeholder` (a bound-parameter placeholder holding fixed-point decimal text, e.g.
+    /// `"$5"`) so that it is parsed back into a numeric SQL value, the way PostgreSQL's
+    /// `TO_NUMBER(placeholder, POSTGRES_MONEY_FORMAT)` does.
+    fn parse_money_from_text(&self, placeholder: &str) -> String;
+
+    /// Builds a boolean SQL predicate that is true when `numerator_expr` is evenly divisible by
+    /// `denominator_expr`.
+    fn divisible_by(&self, numerator_expr: &str, denominator_expr: &str) -> String;
+}
+
+
+pub(crate) struct PostgresRideDb;
+impl RideDb for PostgresRideDb {
+    fn format_money_as_text(&self, sql_expr: &str) -> String {
+        format!("TO_CHAR({}, {})", sql_expr, crate::POSTGRES_MONEY_FORMAT)
+    }
+
+    fn parse_money_from_text(&self, placeholder: &str) -> String {
+        format!("TO_NUMBER({}, {})", placeholder, crate::POSTGRES_MONEY_FORMAT)
+    }
+
+    fn divisible_by(&self, numerator_expr: &str, denominator_expr: &str) -> String {
+        format!("MOD({}, {}) = 0", numerator_expr, denominator_expr)
+    }
+}
+
+
+pub(crate) struct SqliteRideDb;
+impl RideDb for SqliteRideDb {
+    fn format_money_as_text(&self, sql_expr: &str) -> String {
+        format!("PRINTF('%.4f', {})", sql_expr)
+    }
+
+    fn parse_money_from_text(&self, placeholder: &str) -> String {
+        format!("CAST({} AS REAL)", placeholder)
+    }
+
+    fn divisible_by(&self, numerator_expr: &str, denominator_expr: &str) -> String {
+        format!("(({}) % ({})) = 0", numerator_expr, denominator_expr)
+    }
+}
@@ -0,0 +1,80 @@
+use tokio_postgres::types::ToSql;
+
+
+/// Incrementally assembles a `WHERE`-style condition list for a `bim.rides`-based query,
+/// auto-numbering `$N` placeholders as conditions are pushed so that adding or removing an
+/// optional criterion never shifts the numbering of the ones that follow it.
+///
+/// Each condition template may contain `?` placeholders, which are replaced left-to-right with
+/// correctly-numbered `$N` markers; `params` supplies the values bound to them, in the same
+/// order. The final SQL string and the accumulated parameter slice are obtained via
+/// [`RideQuery::and_clause`]/[`RideQuery::where_clause`] and [`RideQuery::params`] respectively.
+#[derive(Default)]
+pub(crate) struct RideQuery<'p> {
+    conditions: Vec<String>,
+    params: Vec<&'p (dyn ToSql + Sync)>,
+}
+impl<'p> RideQuery<'p> {
+    pub fn new() -> Self {
+        Self { conditions: Vec::new(), params: Vec::new() }
+    }
+
+    /// The `$N` index the next pushed parameter (or the next call to `and_where`) would receive.
+    pub fn next_placeholder(&self) -> usize {
+        self.params.len() + 1
+    }
+
+    /// Appends a condition, substituting each `?` in `template` with the next free `$N`
+    /// placeholder and binding it to the corresponding entry of `params`.
+    pub fn and_where(&mut self, template: &str, params: &[&'p (dyn ToSql + Sync)]) -> &mut Self {
+        let mut fragment = String::with_capacity(template.len());
+        let mut param_iter = params.iter();
+        for ch in template.chars() {
+            if ch == '?' {
+                let placeholder = self.next_placeholder();
+                fragment.push_str(&format!("${}", placeholder));
+                let param = param_iter.next()
+                    .expect("fewer params than '?' placeholders passed to and_where");
+                self.params.push(*param);
+            } else {
+                fragment.push(ch);
+            }
+        }
+        if param_iter.next().is_some() {
+            panic!("more params than '?' placeholders passed to and_where");
+        }
+        self.conditions.push(fragment);
+        self
+    }
+
+    /// Appends a condition that carries no bound parameters (e.g. one that is already validated
+    /// and embedded as a SQL literal).
+    pub fn and_where_literal(&mut self, condition: impl Into<String>) -> &mut Self {
+        self.conditions.push(condition.into());
+        self
+    }
+
+    /// Renders the accumulated conditions as `AND`-prefixed fragments, one per line, suitable for
+    /// insertion into a template that already contains a `WHERE` clause.
+    pub fn and_clause(&self) -> String {
+        self.conditions.iter()
+            .map(|c| format!("AND {}", c))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the accumulated conditions as a full `WHERE ...` clause, or `fallback` (e.g. `""`
+    /// or `"WHERE 1=1"`) if no conditions were pushed.
+    pub fn where_clause(&self, fallback: &str) -> String {
+        if self.conditions.is_empty() {
+            fallback.to_owned()
+        } else {
+            format!("WHERE {}", self.conditions.join(" AND "))
+        }
+    }
+
+    /// The parameters accumulated so far, in `$N` order, ready to hand to `tokio_postgres`.
+    pub fn params(&self) -> &[&'p (dyn ToSql + Sync)] {
+        &self.params
+    }
+}
@@ -0,0 +1,352 @@
+//! Abstracts the ride database behind a [`RideStore`] trait so that callers operate on typed
+//! structs instead of hand-rolled SQL. [`PostgresRideStore`] wraps the existing `tokio_postgres`
+//! queries; an embedded (e.g. SQLite) implementation for Postgres-free deployments is planned but
+//! not yet written, so [`RideStore`] currently has only the one implementor.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use rocketbot_bim_common::{CouplingMode, VehicleNumber};
+use tokio::sync::Mutex;
+use tokio_postgres::types::ToSql;
+
+use crate::{BimPlugin, LookbackRange, NewVehicleEntry, POSTGRES_MONEY_FORMAT, replace_ride_vehicles};
+
+
+/// Error produced by a [`RideStore`] implementation.
+#[derive(Debug)]
+pub(crate) enum RideStoreError {
+    Postgres(tokio_postgres::Error),
+}
+impl std::fmt::Display for RideStoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Postgres(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+impl std::error::Error for RideStoreError {}
+impl From<tokio_postgres::Error> for RideStoreError {
+    fn from(e: tokio_postgres::Error) -> Self { Self::Postgres(e) }
+}
+
+
+/// A ride together with its vehicles, as read back from a [`RideStore`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StoredRide {
+    pub id: i64,
+    pub company: String,
+    pub rider_username: String,
+    pub line: Option<String>,
+    pub timestamp: DateTime<Local>,
+    pub regular_price: Option<String>,
+    pub actual_price: Option<String>,
+    pub vehicles: Vec<StoredRideVehicle>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StoredRideVehicle {
+    pub vehicle_number: VehicleNumber,
+    pub vehicle_type: Option<String>,
+    pub spec_position: i64,
+    pub coupling_mode: CouplingMode,
+    pub fixed_coupling_position: i64,
+}
+
+/// The subset of a ride's top-level fields that [`RideStore::update_ride`] can change; a `None`
+/// field is left untouched.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct RideUpdate {
+    pub rider_username: Option<String>,
+    pub company: Option<String>,
+    pub line: Option<String>,
+    pub timestamp: Option<DateTime<Local>>,
+    pub regular_price: Option<String>,
+    pub actual_price: Option<String>,
+}
+impl RideUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.rider_username.is_none()
+            && self.company.is_none()
+            && self.line.is_none()
+            && self.timestamp.is_none()
+            && self.regular_price.is_none()
+            && self.actual_price.is_none()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct VehicleAudience {
+    pub company: String,
+    pub vehicle_number: VehicleNumber,
+    pub rider_count: i64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RiderVehicleCount {
+    pub rider_username: String,
+    pub vehicle_count: i64,
+}
+
+
+/// Abstracts the ride database so that code reading or modifying rides does not need to know
+/// which concrete store backs it.
+///
+/// Note: [`Self::update_ride`] and [`Self::replace_vehicles`] are independent operations, each
+/// committed in its own transaction; a caller that needs to change both a ride's fields and its
+/// vehicles atomically cannot do so through this trait alone.
+#[async_trait]
+pub(crate) trait RideStore: Send + Sync {
+    async fn find_ride(&self, ride_id: i64) -> Result<Option<StoredRide>, RideStoreError>;
+    async fn update_ride(&self, ride_id: i64, update: &RideUpdate) -> Result<bool, RideStoreError>;
+    async fn delete_ride(&self, ride_id: i64) -> Result<bool, RideStoreError>;
+    async fn widest_audience_vehicles(&self, lookback_range: LookbackRange) -> Result<Vec<VehicleAudience>, RideStoreError>;
+    async fn last_rider_counts(&self, lookback_range: LookbackRange, company: Option<&str>) -> Result<Vec<RiderVehicleCount>, RideStoreError>;
+    async fn lone_rider_counts(&self, lookback_range: LookbackRange, company: Option<&str>) -> Result<Vec<RiderVehicleCount>, RideStoreError>;
+    async fn replace_vehicles(&self, ride_id: i64, vehicles: &[NewVehicleEntry]) -> Result<(), RideStoreError>;
+}
+
+
+/// [`RideStore`] implementation backed by the existing `bim.rides`/`bim.ride_vehicles` Postgres
+/// schema. The client is held behind a [`Mutex`] solely so [`Self::replace_vehicles`] can borrow
+/// it mutably to open a transaction; plain reads and single-statement writes only ever hold the
+/// lock for the duration of one query.
+pub(crate) struct PostgresRideStore {
+    client: Mutex<deadpool_postgres::Object>,
+}
+impl PostgresRideStore {
+    pub fn new(client: deadpool_postgres::Object) -> Self {
+        Self { client: Mutex::new(client) }
+    }
+
+    async fn rider_vehicle_counts(
+        &self,
+        lookback_range: LookbackRange,
+        company: Option<&str>,
+        lone: bool,
+    ) -> Result<Vec<RiderVehicleCount>, RideStoreError> {
+        let distinctness_condition = if lone {
+            "AND rav2.rider_username <> rav1.rider_username"
+        } else {
+            "AND rav2.\"timestamp\" > rav1.\"timestamp\""
+        };
+
+        let mut other_params: Vec<&(dyn ToSql + Sync)> = Vec::with_capacity(1);
+        let company_condition = if company.is_some() {
+            other_params.push(&company);
+            format!("AND rav1.company = ${}", other_params.len())
+        } else {
+            String::new()
+        };
+        let timestamp_block = format!("AND rav1.\"timestamp\" >= ${}", other_params.len() + 1);
+
+        let query_template = format!(
+            "
+                WITH innerquery(rider_username, company, vehicle_number) AS (
+                    SELECT DISTINCT rav1.rider_username, rav1.company, rav1.vehicle_number
+                    FROM bim.rides_and_vehicles rav1
+                    WHERE rav1.coupling_mode = 'R'
+                    {company_condition}
+                    {{LOOKBACK_TIMESTAMP}}
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM bim.rides_and_vehicles rav2
+                        WHERE rav2.company = rav1.company
+                        AND rav2.vehicle_number = rav1.vehicle_number
+                        AND rav2.coupling_mode = rav1.coupling_mode
+                        {distinctness_condition}
+                    )
+                )
+                SELECT innerquery.rider_username, CAST(COUNT(*) AS bigint) vehicle_count
+                FROM innerquery
+                GROUP BY innerquery.rider_username
+                ORDER BY
+                    vehicle_count DESC,
+                    rider_username
+            ",
+            company_condition = company_condition,
+            distinctness_condition = distinctness_condition,
+        );
+
+        let client = self.client.lock().await;
+        let rows = BimPlugin::timestamp_query(
+            &client,
+            &query_template,
+            &timestamp_block,
+            "",
+            lookback_range,
+            &other_params,
+        ).await?;
+
+        Ok(rows.iter()
+            .map(|row| RiderVehicleCount {
+                rider_username: row.get(0),
+                vehicle_count: row.get(1),
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl RideStore for PostgresRideStore {
+    async fn find_ride(&self, ride_id: i64) -> Result<Option<StoredRide>, RideStoreError> {
+        let client = self.client.lock().await;
+
+        let ride_row_opt = client.query_opt(
+            &format!(
+                "
+                    SELECT
+                        r.id, r.company, r.rider_username, r.line, r.\"timestamp\",
+                        TO_CHAR(r.regular_price, {money_format}), TO_CHAR(r.actual_price, {money_format})
+                    FROM bim.rides r
+                    WHERE r.id = $1
+                ",
+                money_format = POSTGRES_MONEY_FORMAT,
+            ),
+            &[&ride_id],
+        ).await?;
+        let ride_row = match ride_row_opt {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
+        let vehicle_rows = client.query(
+            "
+                SELECT vehicle_number, vehicle_type, spec_position, coupling_mode, fixed_coupling_position
+                FROM bim.ride_vehicles
+                WHERE ride_id = $1
+                ORDER BY spec_position
+            ",
+            &[&ride_id],
+        ).await?;
+        let vehicles: Vec<StoredRideVehicle> = vehicle_rows.iter()
+            .map(|vr| {
+                let coupling_mode_str: String = vr.get(3);
+                StoredRideVehicle {
+                    vehicle_number: VehicleNumber::from_string(vr.get(0)),
+                    vehicle_type: vr.get(1),
+                    spec_position: vr.get(2),
+                    coupling_mode: CouplingMode::try_from_db_str(&coupling_mode_str)
+                        .unwrap_or(CouplingMode::Ridden),
+                    fixed_coupling_position: vr.get(4),
+                }
+            })
+            .collect();
+
+        Ok(Some(StoredRide {
+            id: ride_row.get(0),
+            company: ride_row.get(1),
+            rider_username: ride_row.get(2),
+            line: ride_row.get(3),
+            timestamp: ride_row.get(4),
+            regular_price: ride_row.get(5),
+            actual_price: ride_row.get(6),
+            vehicles,
+        }))
+    }
+
+    async fn update_ride(&self, ride_id: i64, update: &RideUpdate) -> Result<bool, RideStoreError> {
+        if update.is_empty() {
+            return Ok(true);
+        }
+
+        let mut props: Vec<String> = Vec::new();
+        let mut values: Vec<&(dyn ToSql + Sync)> = Vec::new();
+        if let Some(rider_username) = &update.rider_username {
+            props.push(format!("rider_username = ${}", props.len() + 1));
+            values.push(rider_username);
+        }
+        if let Some(company) = &update.company {
+            props.push(format!("company = ${}", props.len() + 1));
+            values.push(company);
+        }
+        if let Some(line) = &update.line {
+            props.push(format!("line = ${}", props.len() + 1));
+            values.push(line);
+        }
+        if let Some(timestamp) = &update.timestamp {
+            props.push(format!("\"timestamp\" = ${}", props.len() + 1));
+            values.push(timestamp);
+        }
+        if let Some(regular_price) = &update.regular_price {
+            props.push(format!("regular_price = TO_NUMBER(${}, {})", props.len() + 1, POSTGRES_MONEY_FORMAT));
+            values.push(regular_price);
+        }
+        if let Some(actual_price) = &update.actual_price {
+            props.push(format!("actual_price = TO_NUMBER(${}, {})", props.len() + 1, POSTGRES_MONEY_FORMAT));
+            values.push(actual_price);
+        }
+
+        let query = format!("UPDATE bim.rides SET {} WHERE id = ${}", props.join(", "), props.len() + 1);
+        values.push(&ride_id);
+
+        let client = self.client.lock().await;
+        let modified_count = client.execute(&query, &values).await?;
+        Ok(modified_count > 0)
+    }
+
+    async fn delete_ride(&self, ride_id: i64) -> Result<bool, RideStoreError> {
+        let client = self.client.lock().await;
+        let deleted_count = client.execute("DELETE FROM bim.rides WHERE id = $1", &[&ride_id]).await?;
+        Ok(deleted_count > 0)
+    }
+
+    async fn widest_audience_vehicles(&self, lookback_range: LookbackRange) -> Result<Vec<VehicleAudience>, RideStoreError> {
+        let client = self.client.lock().await;
+        let rows = BimPlugin::timestamp_query(
+            &client,
+            "
+                WITH vehicle_and_distinct_rider_count(company, vehicle_number, rider_count) AS (
+                    SELECT rav.company, rav.vehicle_number, COUNT(DISTINCT rav.rider_username)
+                    FROM bim.rides_and_vehicles rav
+                    WHERE rav.coupling_mode = 'R'
+                    {LOOKBACK_TIMESTAMP}
+                    GROUP BY rav.company, rav.vehicle_number
+                )
+                SELECT vadrc.company, vadrc.vehicle_number, CAST(vadrc.rider_count AS bigint) rc
+                FROM vehicle_and_distinct_rider_count vadrc
+                WHERE NOT EXISTS ( -- ensure it's the maximum
+                    SELECT 1
+                    FROM vehicle_and_distinct_rider_count vadrc2
+                    WHERE vadrc2.rider_count > vadrc.rider_count
+                )
+            ",
+            "AND rav.\"timestamp\" >= $1",
+            "",
+            lookback_range,
+            &[],
+        ).await?;
+
+        Ok(rows.iter()
+            .map(|row| VehicleAudience {
+                company: row.get(0),
+                vehicle_number: VehicleNumber::from_string(row.get(1)),
+                rider_count: row.get(2),
+            })
+            .collect())
+    }
+
+    async fn last_rider_counts(&self, lookback_range: LookbackRange, company: Option<&str>) -> Result<Vec<RiderVehicleCount>, RideStoreError> {
+        self.rider_vehicle_counts(lookback_range, company, false).await
+    }
+
+    async fn lone_rider_counts(&self, lookback_range: LookbackRange, company: Option<&str>) -> Result<Vec<RiderVehicleCount>, RideStoreError> {
+        self.rider_vehicle_counts(lookback_range, company, true).await
+    }
+
+    async fn replace_vehicles(&self, ride_id: i64, vehicles: &[NewVehicleEntry]) -> Result<(), RideStoreError> {
+        let mut client = self.client.lock().await;
+        let txn = match client.transaction().await {
+            Ok(t) => t,
+            Err(e) => {
+                crate::metrics::record_failed_transaction();
+                return Err(e.into());
+            },
+        };
+        replace_ride_vehicles(&txn, ride_id, vehicles).await?;
+        if let Err(e) = txn.commit().await {
+            crate::metrics::record_failed_transaction();
+            return Err(e.into());
+        }
+        Ok(())
+    }
+}
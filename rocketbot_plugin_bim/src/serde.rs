@@ -22,3 +22,19 @@ pub(crate) mod serde_opt_big_decimal {
             .serialize(serializer)
     }
 }
+
+pub(crate) mod serde_big_decimal {
+    use bigdecimal::BigDecimal;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde::de::Error as DeError;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigDecimal, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        string.parse()
+            .map_err(DeError::custom)
+    }
+
+    pub fn serialize<S: Serializer>(value: &BigDecimal, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_string().serialize(serializer)
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt;
+
+use chrono::{NaiveDate, Weekday};
+use rocketbot_bim_common::CouplingMode;
+
+use crate::ride_query::RideQuery;
+
+
+/// A parsed set of additional criteria for the bim statistics commands (`favbims`, `topbimdays`,
+/// `topbimlines`, most-active-riders, etc.), written as space-separated `key:value` tokens, e.g.
+/// `company:VIE line:U4 weekday:sat hour:4-10`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct StatsFilter {
+    pub company: Option<String>,
+    pub line: Option<String>,
+    pub weekday: Option<Weekday>,
+    pub hour_range: Option<(u32, u32)>,
+    pub before: Option<NaiveDate>,
+    pub after: Option<NaiveDate>,
+    pub coupling_mode: Option<CouplingMode>,
+}
+impl StatsFilter {
+    /// Parses a filter string consisting of space-separated `key:value` tokens. Tokens that are
+    /// not recognized as filter criteria are left for the caller to interpret (e.g. as the rider
+    /// username), so this function returns both the filter and the leftover tokens.
+    pub fn parse(input: &str) -> Result<(Self, Vec<&str>), StatsFilterParseError> {
+        let mut filter = Self::default();
+        let mut leftover = Vec::new();
+
+        for token in input.split_whitespace() {
+            let (key, value) = match token.split_once(':') {
+                Some((k, v)) => (k, v),
+                None => {
+                    leftover.push(token);
+                    continue;
+                },
+            };
+
+            match key {
+                "company" => {
+                    filter.company = Some(value.to_owned());
+                },
+                "line" => {
+                    filter.line = Some(value.to_owned());
+                },
+                "weekday" => {
+                    filter.weekday = Some(parse_weekday(value)
+                        .ok_or_else(|| StatsFilterParseError::InvalidWeekday(value.to_owned()))?);
+                },
+                "hour" => {
+                    filter.hour_range = Some(parse_hour_range(value)
+                        .ok_or_else(|| StatsFilterParseError::InvalidHourRange(value.to_owned()))?);
+                },
+                "before" => {
+                    filter.before = Some(NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                        .map_err(|_| StatsFilterParseError::InvalidDate(value.to_owned()))?);
+                },
+                "after" => {
+                    filter.after = Some(NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                        .map_err(|_| StatsFilterParseError::InvalidDate(value.to_owned()))?);
+                },
+                "coupling" => {
+                    filter.coupling_mode = Some(CouplingMode::try_from_db_str(value)
+                        .ok_or_else(|| StatsFilterParseError::InvalidCouplingMode(value.to_owned()))?);
+                },
+                other => {
+                    return Err(StatsFilterParseError::UnknownKey(other.to_owned()));
+                },
+            }
+        }
+
+        Ok((filter, leftover))
+    }
+
+    /// Returns whether this filter has no criteria set at all.
+    pub fn is_empty(&self) -> bool {
+        self.company.is_none()
+            && self.line.is_none()
+            && self.weekday.is_none()
+            && self.hour_range.is_none()
+            && self.before.is_none()
+            && self.after.is_none()
+            && self.coupling_mode.is_none()
+    }
+
+    /// Pushes this filter's conditions onto `query`, referencing the rides table under the given
+    /// alias (e.g. `"r"`). Letting `query` auto-number the placeholders means this filter no
+    /// longer needs to know or care which index is next free.
+    pub fn apply<'p>(&'p self, query: &mut RideQuery<'p>, alias: &str) {
+        if let Some(company) = &self.company {
+            query.and_where(&format!("LOWER({}.company) = LOWER(?)", alias), &[company]);
+        }
+        if let Some(line) = &self.line {
+            query.and_where(&format!("LOWER({}.line) = LOWER(?)", alias), &[line]);
+        }
+        if let Some(weekday) = &self.weekday {
+            // Postgres' EXTRACT(DOW) returns 0 (Sunday) through 6 (Saturday). `weekday` was
+            // already validated against a fixed set of names by `parse`, so it is safe to embed
+            // as a literal rather than a bound parameter.
+            query.and_where_literal(format!("EXTRACT(DOW FROM {}.\"timestamp\") = {}", alias, weekday_to_dow(*weekday)));
+        }
+        if let Some((from_hour, to_hour)) = &self.hour_range {
+            // both bounds were range-checked (0..=23) by `parse`.
+            query.and_where_literal(format!(
+                "EXTRACT(HOUR FROM {alias}.\"timestamp\") >= {from} AND EXTRACT(HOUR FROM {alias}.\"timestamp\") <= {to}",
+                alias = alias, from = from_hour, to = to_hour,
+            ));
+        }
+        if let Some(before) = &self.before {
+            query.and_where(&format!("{}.\"timestamp\" < ?", alias), &[before]);
+        }
+        if let Some(after) = &self.after {
+            query.and_where(&format!("{}.\"timestamp\" >= ?", alias), &[after]);
+        }
+        if let Some(coupling_mode) = &self.coupling_mode {
+            // `coupling_mode` was already validated by `parse`, so it is safe to embed directly.
+            query.and_where_literal(format!("{}.coupling_mode = '{}'", alias, coupling_mode.as_db_str()));
+        }
+    }
+}
+
+fn weekday_to_dow(weekday: Weekday) -> u32 {
+    match weekday {
+        Weekday::Sun => 0,
+        Weekday::Mon => 1,
+        Weekday::Tue => 2,
+        Weekday::Wed => 3,
+        Weekday::Thu => 4,
+        Weekday::Fri => 5,
+        Weekday::Sat => 6,
+    }
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    match value.to_lowercase().as_str() {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_hour_range(value: &str) -> Option<(u32, u32)> {
+    let (from_str, to_str) = value.split_once('-')?;
+    let from_hour: u32 = from_str.parse().ok()?;
+    let to_hour: u32 = to_str.parse().ok()?;
+    if from_hour > 23 || to_hour > 23 {
+        return None;
+    }
+    Some((from_hour, to_hour))
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum StatsFilterParseError {
+    UnknownKey(String),
+    InvalidWeekday(String),
+    InvalidHourRange(String),
+    InvalidDate(String),
+    InvalidCouplingMode(String),
+}
+impl fmt::Display for StatsFilterParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKey(key) => write!(f, "unknown filter key {:?}", key),
+            Self::InvalidWeekday(value) => write!(f, "invalid weekday {:?}", value),
+            Self::InvalidHourRange(value) => write!(f, "invalid hour range {:?} (expected e.g. \"4-10\")", value),
+            Self::InvalidDate(value) => write!(f, "invalid date {:?} (expected e.g. \"2023-01-01\")", value),
+            Self::InvalidCouplingMode(value) => write!(f, "invalid coupling mode {:?} (expected one of \"R\", \"E\", \"F\")", value),
+        }
+    }
+}
+impl std::error::Error for StatsFilterParseError {
+}
@@ -3,10 +3,11 @@ use std::fmt;
 use std::time::{Duration, Instant};
 
 use num_bigint::BigInt;
+use num_rational::BigRational;
 use num_traits::cast::ToPrimitive;
 
 use crate::grimoire::{Constant, Function};
-use crate::numbers::{Number, NumberOperationError, NumberValue};
+use crate::numbers::{rational_to_number_value, Number, NumberOperationError, NumberValue};
 use crate::units::{NumberUnits, UnitDatabase};
 
 
@@ -37,6 +38,11 @@ pub(crate) enum AstNode {
     FunctionCall(String, Vec<AstNodeAtLocation>),
     BinaryOperation(BinaryOperation, Box<AstNodeAtLocation>, Box<AstNodeAtLocation>),
     UnaryOperation(UnaryOperation, Box<AstNodeAtLocation>),
+    /// A fixed-size sequence of numbers, produced by built-in functions with more than one
+    /// result (e.g. `elldir`'s destination latitude/longitude). Never produced by the parser, so
+    /// it can't appear as an operand of an operator or another function call; it is only ever a
+    /// leaf in the simplified result.
+    Tuple(Vec<Number>),
 }
 impl From<Number> for AstNode {
     fn from(n: Number) -> Self {
@@ -83,6 +89,7 @@ pub(crate) enum SimplificationError {
     LeftOperandUnitsRightOperandFloat,
     OperandHasUnits,
     UnitReconciliation,
+    PolarDayOrNight,
 }
 impl SimplificationError {
     pub fn at_location(self, start_end: Option<(usize, usize)>) -> SimplificationErrorAtLocation {
@@ -123,6 +130,8 @@ impl fmt::Display for SimplificationError {
                 => write!(f, "operand has units; it mustn't"),
             SimplificationError::UnitReconciliation
                 => write!(f, "failed to reconcile operand units"),
+            SimplificationError::PolarDayOrNight
+                => write!(f, "the sun never rises or sets at this location on this day"),
         }
     }
 }
@@ -158,12 +167,27 @@ pub(crate) type SimplificationResult = Result<AstNodeAtLocation, SimplificationE
 pub(crate) type BuiltInFuncResult = Result<AstNode, SimplificationError>;
 pub(crate) type BuiltInFunction = Box<dyn Fn(&SimplificationState, &[AstNodeAtLocation]) -> BuiltInFuncResult>;
 
+/// The unit in which the circular trigonometric functions (`sin`/`cos`/`tan` and their inverses)
+/// interpret and produce angles. Defaults to [`AngleMode::Radians`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum AngleMode {
+    Radians,
+    Degrees,
+    Gradians,
+}
+impl Default for AngleMode {
+    fn default() -> Self {
+        Self::Radians
+    }
+}
+
 pub(crate) struct SimplificationState {
     pub constants: HashMap<String, Constant>,
     pub functions: HashMap<String, Function>,
     pub units: UnitDatabase,
     pub start_time: Instant,
     pub timeout: Duration,
+    pub angle_mode: AngleMode,
 }
 
 
@@ -305,6 +329,54 @@ fn pow(start_end: Option<(usize, usize)>, left: &AstNodeAtLocation, right: &AstN
                                 NumberUnits::new(),
                             ))
                         },
+                        (NumberValue::Rational(l), NumberValue::Int(r)) => {
+                            let (invert, power_of) = if r < &BigInt::from(0) {
+                                (true, -r)
+                            } else {
+                                (false, r.clone())
+                            };
+                            let one = BigInt::from(1);
+                            let mut val = BigRational::from_integer(BigInt::from(1));
+                            let mut counter = BigInt::from(0);
+                            while counter < power_of {
+                                val = val * l.clone();
+                                counter += &one;
+                                check_timeout(state)?;
+                            }
+
+                            let result_value = if invert {
+                                NumberValue::Float(1.0 / val.to_f64().expect("conversion failed"))
+                            } else {
+                                rational_to_number_value(val)
+                            };
+
+                            // multiply unit powers
+                            let mut new_units = NumberUnits::new();
+                            for (unit, power) in &lnum.units {
+                                let new_unit_power = power * r;
+                                new_units.insert(
+                                    unit.clone(),
+                                    new_unit_power,
+                                );
+                            }
+
+                            AstNode::Number(Number::new(
+                                result_value,
+                                new_units,
+                            ))
+                        },
+                        // any combination involving a fractional exponent (a `Rational` or
+                        // `Float` right operand not already handled above) falls back to an
+                        // approximate floating-point result, same as the plain float/float case
+                        (l, r) => {
+                            if lnum.units.len() > 0 {
+                                return Err(SimplificationError::LeftOperandUnitsRightOperandFloat.at_location(start_end));
+                            }
+                            AstNode::Number(Number::new(
+                                NumberValue::Float(l.to_f64().powf(r.to_f64())),
+                                NumberUnits::new(),
+                            ))
+                        },
                     }
                 },
                 _other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", right.node)).at_location_of(right)),
@@ -337,7 +409,7 @@ impl AstNodeAtLocation {
         check_timeout(state)?;
 
         match &self.node {
-            AstNode::Number(_) => Ok(self.clone()),
+            AstNode::Number(_) | AstNode::Tuple(_) => Ok(self.clone()),
             AstNode::Constant(name) => {
                 match state.constants.get(name) {
                     None => Err(SimplificationError::ConstantNotFound(name.clone()).at_location_of(self)),
@@ -467,6 +539,7 @@ mod tests {
             units: UnitDatabase::new_empty(),
             start_time: Instant::now(),
             timeout: Duration::from_secs(10),
+            angle_mode: AngleMode::default(),
         };
         let result = parsed.root_node.simplify(&mut state).unwrap();
         let obtained = match result.node {
@@ -0,0 +1,403 @@
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::cast::ToPrimitive;
+
+use crate::ast::{AstNode, AstNodeAtLocation, BinaryOperation, UnaryOperation};
+use crate::numbers::{rational_to_number_value, Number, NumberOperationError, NumberValue};
+use crate::units::{NumberUnits, UnitDatabase};
+
+
+/// Caps the repeated-squaring loops in [`eval_power`] and the iteration loop in the factorial
+/// branch of [`eval_unary`], mirroring the timeout guard `ast::pow`/`ast::AstNodeAtLocation::simplify`
+/// apply via `SimplificationState`; the bytecode VM has no such state to check against, so a flat
+/// iteration cap takes its place.
+const MAX_REPEATED_MULTIPLICATIONS: u64 = 1_000_000;
+
+/// A single instruction in the flat bytecode produced by [`lower`].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Instr {
+    PushNumber(Number),
+    LoadConstant(String),
+    BinaryOp(BinaryOperation),
+    UnaryOp(UnaryOperation),
+    CallFunction(String, usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum BytecodeError {
+    StackUnderflow,
+    UnknownConstant(String),
+    UnexpectedOperandType(String),
+    RightOperandHasUnits,
+    LeftOperandUnitsRightOperandFloat,
+    OperandHasUnits,
+    RepeatedMultiplicationLimitExceeded,
+    DivisionByZero,
+    OperationFailed,
+    UnitReconciliation,
+}
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytecodeError::StackUnderflow
+                => write!(f, "stack underflow"),
+            BytecodeError::UnknownConstant(c)
+                => write!(f, "constant {:?} not found", c),
+            BytecodeError::UnexpectedOperandType(t)
+                => write!(f, "operand type {} unexpected", t),
+            BytecodeError::RightOperandHasUnits
+                => write!(f, "right operand has units; it mustn't"),
+            BytecodeError::LeftOperandUnitsRightOperandFloat
+                => write!(f, "left operand has units but the right operand is floating-point"),
+            BytecodeError::OperandHasUnits
+                => write!(f, "operand has units; it mustn't"),
+            BytecodeError::RepeatedMultiplicationLimitExceeded
+                => write!(f, "exponent or factorial argument too large"),
+            BytecodeError::DivisionByZero
+                => write!(f, "division by zero"),
+            BytecodeError::OperationFailed
+                => write!(f, "operation failed"),
+            BytecodeError::UnitReconciliation
+                => write!(f, "failed to reconcile operand units"),
+        }
+    }
+}
+impl std::error::Error for BytecodeError {
+}
+impl From<NumberOperationError> for BytecodeError {
+    fn from(noe: NumberOperationError) -> Self {
+        match noe {
+            NumberOperationError::OperationFailed => Self::OperationFailed,
+            NumberOperationError::UnitReconciliation => Self::UnitReconciliation,
+        }
+    }
+}
+
+/// Lowers `node` into a flat sequence of [`Instr`]s via post-order traversal: each child's
+/// instructions are emitted before the operation that consumes them, so running the result is a
+/// single left-to-right pass over the vector pushing and popping a value stack. Post-order
+/// traversal already encodes operator associativity (including `Power`'s right-associativity), so
+/// [`run`] needs no special-casing for it.
+pub(crate) fn lower(node: &AstNodeAtLocation) -> Vec<Instr> {
+    let mut instrs = Vec::new();
+    lower_into(&node.node, &mut instrs);
+    instrs
+}
+
+fn lower_into(node: &AstNode, instrs: &mut Vec<Instr>) {
+    match node {
+        AstNode::Number(n) => instrs.push(Instr::PushNumber(n.clone())),
+        AstNode::Constant(name) => instrs.push(Instr::LoadConstant(name.clone())),
+        AstNode::FunctionCall(name, args) => {
+            for arg in args {
+                lower_into(&arg.node, instrs);
+            }
+            instrs.push(Instr::CallFunction(name.clone(), args.len()));
+        },
+        AstNode::BinaryOperation(op, left, right) => {
+            lower_into(&left.node, instrs);
+            lower_into(&right.node, instrs);
+            instrs.push(Instr::BinaryOp(*op));
+        },
+        AstNode::UnaryOperation(op, operand) => {
+            lower_into(&operand.node, instrs);
+            instrs.push(Instr::UnaryOp(*op));
+        },
+        AstNode::Tuple(_) => unreachable!("the parser never produces AstNode::Tuple"),
+    }
+}
+
+/// Runs `instrs` against a value stack, resolving constants via `resolve_constant` and function
+/// calls via `call_function`. Keeping both as closures lets the same compiled program run against
+/// many environments (e.g. the same plotted expression evaluated once per sample point) without
+/// recompiling it each time.
+pub(crate) fn run<C, F>(
+    instrs: &[Instr],
+    units: &UnitDatabase,
+    resolve_constant: C,
+    call_function: F,
+) -> Result<Number, BytecodeError>
+where
+    C: Fn(&str) -> Option<Number>,
+    F: Fn(&str, &[Number]) -> Result<Number, BytecodeError>,
+{
+    let mut stack: Vec<Number> = Vec::new();
+
+    for instr in instrs {
+        match instr {
+            Instr::PushNumber(n) => stack.push(n.clone()),
+            Instr::LoadConstant(name) => {
+                let value = resolve_constant(name)
+                    .ok_or_else(|| BytecodeError::UnknownConstant(name.clone()))?;
+                stack.push(value);
+            },
+            Instr::BinaryOp(op) => {
+                let right = stack.pop().ok_or(BytecodeError::StackUnderflow)?;
+                let left = stack.pop().ok_or(BytecodeError::StackUnderflow)?;
+                stack.push(eval_binary(*op, &left, &right, units)?);
+            },
+            Instr::UnaryOp(op) => {
+                let operand = stack.pop().ok_or(BytecodeError::StackUnderflow)?;
+                stack.push(eval_unary(*op, &operand)?);
+            },
+            Instr::CallFunction(name, argc) => {
+                if stack.len() < *argc {
+                    return Err(BytecodeError::StackUnderflow);
+                }
+                let args = stack.split_off(stack.len() - argc);
+                stack.push(call_function(name, &args)?);
+            },
+        }
+    }
+
+    stack.pop().ok_or(BytecodeError::StackUnderflow)
+}
+
+fn eval_binary(op: BinaryOperation, left: &Number, right: &Number, units: &UnitDatabase) -> Result<Number, BytecodeError> {
+    match op {
+        BinaryOperation::Add => Ok(left.checked_add(right.clone(), units)?),
+        BinaryOperation::Subtract => Ok(left.checked_sub(right.clone(), units)?),
+        BinaryOperation::Multiply => Ok(left.checked_mul(right.clone())?),
+        BinaryOperation::Divide => {
+            // `Number::checked_div` coerces to f64 and would silently yield infinity rather
+            // than erroring, so the zero check happens here instead.
+            if is_zero(&right.value) {
+                return Err(BytecodeError::DivisionByZero);
+            }
+            Ok(left.checked_div(right.clone())?)
+        },
+        BinaryOperation::DivideIntegral => {
+            // `Number::checked_whole_div` divides `BigInt`s directly, which panics on a zero
+            // divisor rather than erroring, so the zero check happens here instead.
+            if is_zero(&right.value) {
+                return Err(BytecodeError::DivisionByZero);
+            }
+            Ok(left.checked_whole_div(right.clone())?)
+        },
+        BinaryOperation::Remainder => {
+            // Same BigInt-panics-on-zero concern as `DivideIntegral` above.
+            if is_zero(&right.value) {
+                return Err(BytecodeError::DivisionByZero);
+            }
+            Ok(left.checked_rem(right.clone())?)
+        },
+        BinaryOperation::BinaryAnd => Ok(left.checked_bit_and(right.clone(), units)?),
+        BinaryOperation::BinaryOr => Ok(left.checked_bit_or(right.clone(), units)?),
+        BinaryOperation::BinaryXor => Ok(left.checked_bit_xor(right.clone(), units)?),
+        BinaryOperation::Power => eval_power(left, right),
+    }
+}
+
+fn is_zero(value: &NumberValue) -> bool {
+    match value {
+        NumberValue::Int(i) => i == &BigInt::from(0),
+        NumberValue::Rational(r) => r == &BigRational::from_integer(BigInt::from(0)),
+        NumberValue::Float(f) => *f == 0.0,
+    }
+}
+
+/// Mirrors the int/int, int/float, float/int and float/float branches of `ast::pow`, but bounds
+/// the integer repeated-squaring loop with [`MAX_REPEATED_MULTIPLICATIONS`] instead of a timeout.
+fn eval_power(left: &Number, right: &Number) -> Result<Number, BytecodeError> {
+    match (&left.value, &right.value) {
+        (NumberValue::Int(l), NumberValue::Int(r)) => {
+            if right.units.len() > 0 {
+                return Err(BytecodeError::RightOperandHasUnits);
+            }
+
+            let (invert, power_of) = if r < &BigInt::from(0) {
+                (true, -r)
+            } else {
+                (false, r.clone())
+            };
+            let one = BigInt::from(1);
+            let mut val = one.clone();
+            let mut counter = BigInt::from(0);
+            let mut iterations = 0u64;
+            while counter < power_of {
+                val *= l;
+                counter += &one;
+                iterations += 1;
+                if iterations > MAX_REPEATED_MULTIPLICATIONS {
+                    return Err(BytecodeError::RepeatedMultiplicationLimitExceeded);
+                }
+            }
+
+            let result_value = if invert {
+                NumberValue::Float(1.0 / val.to_f64().expect("conversion failed"))
+            } else {
+                NumberValue::Int(val)
+            };
+
+            let mut new_units = NumberUnits::new();
+            for (unit, power) in &left.units {
+                new_units.insert(unit.clone(), power * r);
+            }
+
+            Ok(Number::new(result_value, new_units))
+        },
+        (NumberValue::Int(l), NumberValue::Float(r)) => {
+            if left.units.len() > 0 {
+                return Err(BytecodeError::LeftOperandUnitsRightOperandFloat);
+            }
+            let l_f64 = l.to_f64().expect("conversion failed");
+            Ok(Number::new(NumberValue::Float(l_f64.powf(*r)), NumberUnits::new()))
+        },
+        (NumberValue::Float(l), NumberValue::Int(r)) => {
+            let r_f64 = r.to_f64().expect("conversion failed");
+            let mut new_units = NumberUnits::new();
+            for (unit, power) in &left.units {
+                new_units.insert(unit.clone(), power * r);
+            }
+            Ok(Number::new(NumberValue::Float(l.powf(r_f64)), new_units))
+        },
+        (NumberValue::Float(l), NumberValue::Float(r)) => {
+            if left.units.len() > 0 {
+                return Err(BytecodeError::LeftOperandUnitsRightOperandFloat);
+            }
+            Ok(Number::new(NumberValue::Float(l.powf(*r)), NumberUnits::new()))
+        },
+        (NumberValue::Rational(l), NumberValue::Int(r)) => {
+            if right.units.len() > 0 {
+                return Err(BytecodeError::RightOperandHasUnits);
+            }
+
+            let (invert, power_of) = if r < &BigInt::from(0) {
+                (true, -r)
+            } else {
+                (false, r.clone())
+            };
+            let one = BigInt::from(1);
+            let mut val = BigRational::from_integer(BigInt::from(1));
+            let mut counter = BigInt::from(0);
+            let mut iterations = 0u64;
+            while counter < power_of {
+                val = val * l.clone();
+                counter += &one;
+                iterations += 1;
+                if iterations > MAX_REPEATED_MULTIPLICATIONS {
+                    return Err(BytecodeError::RepeatedMultiplicationLimitExceeded);
+                }
+            }
+
+            let result_value = if invert {
+                NumberValue::Float(1.0 / val.to_f64().expect("conversion failed"))
+            } else {
+                rational_to_number_value(val)
+            };
+
+            let mut new_units = NumberUnits::new();
+            for (unit, power) in &left.units {
+                new_units.insert(unit.clone(), power * r);
+            }
+
+            Ok(Number::new(result_value, new_units))
+        },
+        // any combination involving a fractional exponent (a `Rational` or `Float` right
+        // operand not already handled above) falls back to an approximate floating-point result
+        (l, r) => {
+            if left.units.len() > 0 {
+                return Err(BytecodeError::LeftOperandUnitsRightOperandFloat);
+            }
+            Ok(Number::new(NumberValue::Float(l.to_f64().powf(r.to_f64())), NumberUnits::new()))
+        },
+    }
+}
+
+fn eval_unary(op: UnaryOperation, operand: &Number) -> Result<Number, BytecodeError> {
+    match op {
+        UnaryOperation::Negate => Ok(operand.negated()),
+        UnaryOperation::Factorial => {
+            if operand.units.len() > 0 {
+                return Err(BytecodeError::OperandHasUnits);
+            }
+            let NumberValue::Int(o) = &operand.value else {
+                return Err(BytecodeError::UnexpectedOperandType(format!("{:?}", operand.value)));
+            };
+
+            let mut i = BigInt::from(2);
+            let one = BigInt::from(1);
+            let mut val = one.clone();
+            let mut iterations = 0u64;
+            while i <= *o {
+                val *= &i;
+                i += &one;
+                iterations += 1;
+                if iterations > MAX_REPEATED_MULTIPLICATIONS {
+                    return Err(BytecodeError::RepeatedMultiplicationLimitExceeded);
+                }
+            }
+            Ok(Number::new(NumberValue::Int(val), NumberUnits::new()))
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_full_expression;
+
+    fn run_test(expected: &str, parse_me: &str) {
+        let parsed = parse_full_expression(parse_me).unwrap();
+        let instrs = lower(&parsed.root_node);
+        let units = UnitDatabase::new_empty();
+        let result = run(
+            &instrs,
+            &units,
+            |name| if name == "pi" { Some(Number::new(NumberValue::Float(std::f64::consts::PI), NumberUnits::new())) } else { None },
+            |name, args| if name == "double" && args.len() == 1 {
+                args[0].checked_mul(args[0].clone()).map_err(BytecodeError::from)
+            } else {
+                Err(BytecodeError::UnexpectedOperandType(name.to_string()))
+            },
+        ).unwrap();
+        assert_eq!(expected, result.to_string());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        run_test("10", "2 * 3 + 4");
+        run_test("14", "2 + 3 * 4");
+    }
+
+    #[test]
+    fn test_associativity_sub_sub() {
+        run_test("2", "7 - 4 - 1");
+    }
+
+    #[test]
+    fn test_associativity_pow_pow() {
+        run_test("134217728", "2**3**3");
+    }
+
+    #[test]
+    fn test_constant_lookup() {
+        run_test("3.141592653589793", "pi");
+    }
+
+    #[test]
+    fn test_function_call() {
+        run_test("9", "double(3)");
+    }
+
+    #[test]
+    fn test_unknown_constant_errors() {
+        let parsed = parse_full_expression("nope").unwrap();
+        let instrs = lower(&parsed.root_node);
+        let units = UnitDatabase::new_empty();
+        let result = run(&instrs, &units, |_| None, |_, _| Err(BytecodeError::StackUnderflow));
+        assert_eq!(Err(BytecodeError::UnknownConstant("nope".to_string())), result);
+    }
+
+    #[test]
+    fn test_division_by_zero_errors() {
+        let parsed = parse_full_expression("1 / 0").unwrap();
+        let instrs = lower(&parsed.root_node);
+        let units = UnitDatabase::new_empty();
+        let result = run(&instrs, &units, |_| None, |_, _| Err(BytecodeError::StackUnderflow));
+        assert_eq!(Err(BytecodeError::DivisionByZero), result);
+    }
+}
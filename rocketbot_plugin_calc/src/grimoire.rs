@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::f64::consts::PI;
 
@@ -6,11 +7,12 @@ use num_traits::ToPrimitive;
 use once_cell::sync::Lazy;
 
 use crate::ast::{
-    AstNode, AstNodeAtLocation, BuiltInFunction, BuiltInFuncResult, SimplificationError,
+    AngleMode, AstNode, AstNodeAtLocation, BuiltInFunction, BuiltInFuncResult, SimplificationError,
     SimplificationState,
 };
 use crate::numbers::{Number, NumberValue};
-use crate::units::{coerce_to_base_units, coerce_to_unit, NumberUnits};
+use crate::ops;
+use crate::units::{coerce_to_base_units, coerce_to_unit, NumberUnits, UnitDatabase};
 
 
 pub const GOLDEN_RATIO: f64 = 1.6180339887498948482045868344;
@@ -19,6 +21,12 @@ pub const WGS84_INVERSE_FLATTENING: f64 = 298.257_223_563;
 pub static WGS84_MEAN_RADIUS: Lazy<f64> = Lazy::new(|| ellipsoid_mean_radius(WGS84_EQUATOR_RADIUS_M, WGS84_INVERSE_FLATTENING));
 pub const SPEED_LIGHT_M_PER_S: i64 = 299_792_458;
 pub const RACK_POST_GAP_19IN_IN: f64 = 17.75;
+pub const EARTH_GM: f64 = 3.986_004_418e14;
+pub const SUN_GM: f64 = 1.327_124_400_18e20;
+pub const BIG_G: f64 = 6.674e-11;
+pub const PLANCK_H: f64 = 6.626_070_15e-34;
+pub const BOLTZMANN_K: f64 = 1.380_649e-23;
+pub const AVOGADRO_N: f64 = 6.022_140_76e23;
 
 
 #[derive(Clone, Debug, PartialEq)]
@@ -129,6 +137,85 @@ pub(crate) fn get_canonical_constants() -> HashMap<String, Constant> {
         ),
     ));
 
+    let mut m3_per_s2 = NumberUnits::new();
+    m3_per_s2.insert("m".to_owned(), (3i8).into());
+    m3_per_s2.insert("s".to_owned(), (-2i8).into());
+    prepared.insert("earthGM", Constant::new(
+        Number::new(
+            NumberValue::Float(EARTH_GM),
+            m3_per_s2.clone(),
+        ),
+        concat!(
+            "The Earth's standard gravitational parameter \\(GM\\), in cubic meters per square",
+            " second. You may want to use it with `orbperiod`, `orbsma`, `visviva` or",
+            " `orbvelcirc`.",
+        ),
+    ));
+    prepared.insert("sunGM", Constant::new(
+        Number::new(
+            NumberValue::Float(SUN_GM),
+            m3_per_s2,
+        ),
+        concat!(
+            "The Sun's standard gravitational parameter \\(GM\\), in cubic meters per square",
+            " second. You may want to use it with `orbperiod`, `orbsma`, `visviva` or",
+            " `orbvelcirc`.",
+        ),
+    ));
+    let mut m3_per_kg_s2 = NumberUnits::new();
+    m3_per_kg_s2.insert("m".to_owned(), (3i8).into());
+    m3_per_kg_s2.insert("kg".to_owned(), (-1i8).into());
+    m3_per_kg_s2.insert("s".to_owned(), (-2i8).into());
+    prepared.insert("bigG", Constant::new(
+        Number::new(
+            NumberValue::Float(BIG_G),
+            m3_per_kg_s2,
+        ),
+        concat!(
+            "The Newtonian constant of gravitation \\(G\\), in cubic meters per kilogram per",
+            " square second.",
+        ),
+    ));
+
+    let mut kg_m2_per_s = NumberUnits::new();
+    kg_m2_per_s.insert("kg".to_owned(), (1i8).into());
+    kg_m2_per_s.insert("m".to_owned(), (2i8).into());
+    kg_m2_per_s.insert("s".to_owned(), (-1i8).into());
+    prepared.insert("planckH", Constant::new(
+        Number::new(
+            NumberValue::Float(PLANCK_H),
+            kg_m2_per_s,
+        ),
+        concat!(
+            "The Planck constant \\(h\\), in kilogram-square-meters per second (i.e. joule-seconds",
+            " expressed in base units).",
+        ),
+    ));
+    let mut kg_m2_per_s2_K = NumberUnits::new();
+    kg_m2_per_s2_K.insert("kg".to_owned(), (1i8).into());
+    kg_m2_per_s2_K.insert("m".to_owned(), (2i8).into());
+    kg_m2_per_s2_K.insert("s".to_owned(), (-2i8).into());
+    kg_m2_per_s2_K.insert("K".to_owned(), (-1i8).into());
+    prepared.insert("boltzmannK", Constant::new(
+        Number::new(
+            NumberValue::Float(BOLTZMANN_K),
+            kg_m2_per_s2_K,
+        ),
+        concat!(
+            "The Boltzmann constant \\(k_B\\), in kilogram-square-meters per square second per",
+            " kelvin (i.e. joules per kelvin expressed in base units).",
+        ),
+    ));
+    let mut per_mol = NumberUnits::new();
+    per_mol.insert("mol".to_owned(), (-1i8).into());
+    prepared.insert("avogadro", Constant::new(
+        Number::new(
+            NumberValue::Float(AVOGADRO_N),
+            per_mol,
+        ),
+        "The Avogadro constant \\(N_A\\), in entities per mole.",
+    ));
+
     prepared.drain()
         .map(|(k, v)| (k.to_owned(), v))
         .collect()
@@ -139,37 +226,58 @@ pub(crate) fn get_canonical_functions() -> HashMap<String, Function> {
 
     prepared.insert("sqrt", f64_f64(
         "sqrt",
-        |f| f.sqrt(),
+        |f| ops::sqrt(f),
         "`sqrt(x)` calculates the square root of a number, i.e. \\(y = \\sqrt{x}\\) such that \\(y^2 = x\\)",
     ));
 
-    prepared.insert("sin", f64_f64(
+    prepared.insert("sin", angle_f64(
         "sin",
-        |f| f.sin(),
+        |f| ops::sin(f),
         concat!(
-            "`sin(theta)` calculates the sine of an angle in radians, the ratio of the length of",
-            " the leg opposite the angle in a triangle to its hypotenuse.",
+            "`sin(theta)` calculates the sine of an angle, the ratio of the length of the leg",
+            " opposite the angle in a triangle to its hypotenuse. `theta` is interpreted in the",
+            " evaluation's current angle mode (radians by default; see the `deg`/`grad`",
+            " instructions).",
         ),
     ));
-    prepared.insert("cos", f64_f64(
+    prepared.insert("cos", angle_f64(
         "cos",
-        |f| f.cos(),
+        |f| ops::cos(f),
         concat!(
-            "`cos(theta)` calculates the cosine of an angle in radians, the ratio of the length of",
-            " the leg adjacent to the angle in a triangle to its hypotenuse.",
+            "`cos(theta)` calculates the cosine of an angle, the ratio of the length of the leg",
+            " adjacent to the angle in a triangle to its hypotenuse. `theta` is interpreted in",
+            " the evaluation's current angle mode (radians by default; see the `deg`/`grad`",
+            " instructions).",
         ),
     ));
-    prepared.insert("tan", f64_f64(
+    prepared.insert("tan", angle_f64(
         "tan",
-        |f| f.tan(),
+        |f| ops::tan(f),
         concat!(
-            "`tan(theta)` calculates the tangent of an angle in radians, the ratio of the length of",
-            " the leg opposite the angle in a triangle to the leg adjacent to the angle.",
+            "`tan(theta)` calculates the tangent of an angle, the ratio of the length of the leg",
+            " opposite the angle in a triangle to the leg adjacent to the angle. `theta` is",
+            " interpreted in the evaluation's current angle mode (radians by default; see the",
+            " `deg`/`grad` instructions).",
         ),
     ));
+    prepared.insert("csc", angle_f64(
+        "csc",
+        |f| ops::recip(ops::sin(f)),
+        "`csc(theta)` calculates the cosecant of an angle, the reciprocal of `sin(theta)`.",
+    ));
+    prepared.insert("sec", angle_f64(
+        "sec",
+        |f| ops::recip(ops::cos(f)),
+        "`sec(theta)` calculates the secant of an angle, the reciprocal of `cos(theta)`.",
+    ));
+    prepared.insert("cot", angle_f64(
+        "cot",
+        |f| ops::recip(ops::tan(f)),
+        "`cot(theta)` calculates the cotangent of an angle, the reciprocal of `tan(theta)`.",
+    ));
     prepared.insert("exp", f64_f64(
         "exp",
-        |f| f.exp(),
+        |f| ops::exp(f),
         concat!(
             "`exp(x)` calculates the exponential function, the function where",
             " \\(\\text{exp}(0) = 1\\) and",
@@ -177,39 +285,57 @@ pub(crate) fn get_canonical_functions() -> HashMap<String, Function> {
             " equivalent to `e**x`.",
         ),
     ));
-    prepared.insert("asin", f64_f64(
+    prepared.insert("asin", f64_angle(
         "asin",
-        |f| f.asin(),
+        |f| ops::asin(f),
         concat!(
             "`asin(x)` calculates the inverse sine, the angle in the triangle with the given ratio",
             " of the length of the leg opposite the angle to the hypotenuse. The result is",
-            " returned in radians because mathematicians hate people.",
+            " returned in the evaluation's current angle mode (radians by default; see the",
+            " `deg`/`grad` instructions).",
         ),
     ));
-    prepared.insert("acos", f64_f64(
+    prepared.insert("acos", f64_angle(
         "acos",
-        |f| f.acos(),
+        |f| ops::acos(f),
         concat!(
             "`acos(x)` calculates the inverse cosine, the angle in the triangle with the given",
             " ratio of the length of the leg adjacent to the angle to the hypotenuse. The result",
-            " is returned in radians because mathematicians hate people.",
+            " is returned in the evaluation's current angle mode (radians by default; see the",
+            " `deg`/`grad` instructions).",
         ),
     ));
-    prepared.insert("atan", f64_f64(
+    prepared.insert("atan", f64_angle(
         "atan",
-        |f| f.atan(),
+        |f| ops::atan(f),
         concat!(
             "`atan(x)` calculates the inverse tangent, the angle in the triangle with the given",
             " ratio of the length of the leg opposite to the angle to the leg adjacend to the",
-            " angle. The result is returned in radians because mathematicians hate people. Note",
-            " that the angle will always be returned in the range of \\(-\\frac{\\pi}{2}\\) to",
-            " \\(\\frac{\\pi}{2}\\); to calculate the inverse tangent in a way that respects the",
-            " four quadrants, see `atan2(y, x)`.",
+            " angle. The result is returned in the evaluation's current angle mode (radians by",
+            " default; see the `deg`/`grad` instructions). Note that the angle will always be",
+            " returned in the range of \\(-\\frac{\\pi}{2}\\) to \\(\\frac{\\pi}{2}\\) (or its",
+            " equivalent in the current angle mode); to calculate the inverse tangent in a way",
+            " that respects the four quadrants, see `atan2(y, x)`.",
         ),
     ));
+    prepared.insert("acsc", f64_angle(
+        "acsc",
+        |f| ops::asin(ops::recip(f)),
+        "`acsc(x)` calculates the inverse cosecant, i.e. `asin(1/x)`.",
+    ));
+    prepared.insert("asec", f64_angle(
+        "asec",
+        |f| ops::acos(ops::recip(f)),
+        "`asec(x)` calculates the inverse secant, i.e. `acos(1/x)`.",
+    ));
+    prepared.insert("acot", f64_angle(
+        "acot",
+        |f| ops::atan(ops::recip(f)),
+        "`acot(x)` calculates the inverse cotangent, i.e. `atan(1/x)`.",
+    ));
     prepared.insert("atan2", f64_f64_f64(
         "atan2",
-        |f, g| f.atan2(g),
+        |f, g| ops::atan2(f, g),
         concat!(
             "`atan2(y, x)` calculates the inverse tangent, the angle in the triangle with the",
             " given ratio of the length of the leg opposite to the angle (`y`) and the leg",
@@ -232,9 +358,24 @@ pub(crate) fn get_canonical_functions() -> HashMap<String, Function> {
         |f| f.tanh(),
         "`tanh(x)` calculates the hyperbolic tangent of `x`.",
     ));
+    prepared.insert("asinh", f64_f64(
+        "asinh",
+        |f| f.asinh(),
+        "`asinh(x)` calculates the inverse hyperbolic sine of `x`.",
+    ));
+    prepared.insert("acosh", f64_f64(
+        "acosh",
+        |f| f.acosh(),
+        "`acosh(x)` calculates the inverse hyperbolic cosine of `x`.",
+    ));
+    prepared.insert("atanh", f64_f64(
+        "atanh",
+        |f| f.atanh(),
+        "`atanh(x)` calculates the inverse hyperbolic tangent of `x`.",
+    ));
     prepared.insert("ln", f64_f64(
         "ln",
-        |f| f.ln(),
+        |f| ops::ln(f),
         concat!(
             "`ln(x)` calculates the natural logarithm, i.e. the logarithm with base \\(e\\), of",
             " `x`.",
@@ -314,20 +455,179 @@ pub(crate) fn get_canonical_functions() -> HashMap<String, Function> {
             " `earthER` and `earthIF`.",
         ),
     ));
+    prepared.insert("elldir", f64_multi_tuple(
+        "elldir",
+        ellipsoid_direct_array,
+        concat!(
+            "`elldir(er, if, lat1, lon1, bearing, distance)` solves the direct geodesic problem:",
+            " starting at `(lat1, lon1)` on an ellipsoid with equatorial radius `er` and inverse",
+            " flattening `if`, travels `distance` along the surface on initial `bearing`, and",
+            " returns the destination as a `(lat2, lon2)` tuple. The latitudes, longitude and",
+            " bearing must be in radians; see `elldirdeg` for a degrees-based version.",
+        ),
+    ));
+    prepared.insert("elldirdeg", f64_multi_tuple(
+        "elldirdeg",
+        ellipsoid_direct_deg_array,
+        concat!(
+            "`elldirdeg(er, if, lat1, lon1, bearing, distance)` solves the direct geodesic problem:",
+            " starting at `(lat1, lon1)` on an ellipsoid with equatorial radius `er` and inverse",
+            " flattening `if`, travels `distance` along the surface on initial `bearing`, and",
+            " returns the destination as a `(lat2, lon2)` tuple. The latitudes, longitude and",
+            " bearing must be in degrees; see `elldir` for a radians-based version.",
+        ),
+    ));
+    prepared.insert("ellbearing", f64_multi_tuple(
+        "ellbearing",
+        ellipsoid_bearings_array,
+        concat!(
+            "`ellbearing(if, lat1, lon1, lat2, lon2)` calculates the initial and final azimuths",
+            " of the geodesic between points `(lat1, lon1)` and `(lat2, lon2)` on an ellipsoid",
+            " with inverse flattening `if`, returned as an `(initial, final)` tuple. The",
+            " latitudes and longitudes must be in radians; see `ellbearingdeg` for a",
+            " degrees-based version.",
+        ),
+    ));
+    prepared.insert("ellbearingdeg", f64_multi_tuple(
+        "ellbearingdeg",
+        ellipsoid_bearings_deg_array,
+        concat!(
+            "`ellbearingdeg(if, lat1, lon1, lat2, lon2)` calculates the initial and final azimuths",
+            " of the geodesic between points `(lat1, lon1)` and `(lat2, lon2)` on an ellipsoid",
+            " with inverse flattening `if`, returned as an `(initial, final)` tuple. The",
+            " latitudes and longitudes must be in degrees; see `ellbearing` for a radians-based",
+            " version.",
+        ),
+    ));
+    prepared.insert("geodetic2ecef", f64_multi_tuple(
+        "geodetic2ecef",
+        geodetic2ecef_array,
+        concat!(
+            "`geodetic2ecef(lat, lon, alt)` converts a geodetic position (latitude and longitude",
+            " in degrees, altitude `alt` in meters above the WGS84 ellipsoid) to",
+            " Earth-Centered-Earth-Fixed Cartesian coordinates, returned as an `(x, y, z)` tuple",
+            " in meters. See `ecef2geodetic` for the inverse conversion.",
+        ),
+    ));
+    prepared.insert("ecef2geodetic", f64_multi_tuple(
+        "ecef2geodetic",
+        ecef2geodetic_array,
+        concat!(
+            "`ecef2geodetic(x, y, z)` converts Earth-Centered-Earth-Fixed Cartesian coordinates",
+            " (in meters) to a geodetic position on the WGS84 ellipsoid, returned as a",
+            " `(lat, lon, alt)` tuple (degrees, degrees, meters). See `geodetic2ecef` for the",
+            " inverse conversion.",
+        ),
+    ));
+    prepared.insert("utmzone", f64_multi_f64(
+        "utmzone",
+        utm_zone_array,
+        concat!(
+            "`utmzone(lat, lon)` returns the number of the UTM (Universal Transverse Mercator)",
+            " zone that `(lat, lon)` (degrees) falls into.",
+        ),
+    ));
+    prepared.insert("latlon2utm", f64_multi_tuple(
+        "latlon2utm",
+        latlon2utm_array,
+        concat!(
+            "`latlon2utm(lat, lon)` converts a geodetic position (degrees) on the WGS84",
+            " ellipsoid to a UTM grid position, returned as a `(zone, easting, northing)` tuple",
+            " (easting and northing in meters). See `utm2latlon` for the inverse conversion.",
+        ),
+    ));
+    prepared.insert("utm2latlon", f64_multi_tuple(
+        "utm2latlon",
+        utm2latlon_array,
+        concat!(
+            "`utm2latlon(zone, hemisphere, easting, northing)` converts a UTM grid position back",
+            " to a geodetic position (degrees) on the WGS84 ellipsoid, returned as a `(lat, lon)`",
+            " tuple. `hemisphere` follows this module's usual sign convention (zero or positive",
+            " is northern, negative is southern). See `latlon2utm` for the inverse conversion.",
+        ),
+    ));
+    prepared.insert("sundecl", f64_multi_f64(
+        "sundecl",
+        sun_declination_array,
+        concat!(
+            "`sundecl(dayOfYear)` approximates the sun's declination (in degrees) on the given",
+            " day of the year (1-based).",
+        ),
+    ));
+    prepared.insert("sunrise", Function::new(
+        Box::new(sunrise),
+        concat!(
+            "`sunrise(lat, lon, dayOfYear, tzOffset)` approximates the local clock time (decimal",
+            " hours, adjusted by the fixed UTC offset `tzOffset`) of sunrise at `(lat, lon)`",
+            " (degrees) on the given day of the year. Fails with an error instead of returning",
+            " `NaN` if the sun never rises on this day at this latitude (polar night) or never",
+            " sets (polar day). See `sunset` for the complementary event and `dms`/`dm` for",
+            " expressing `lat`/`lon` in degrees-minutes-seconds.",
+        ),
+    ));
+    prepared.insert("sunset", Function::new(
+        Box::new(sunset),
+        concat!(
+            "`sunset(lat, lon, dayOfYear, tzOffset)` approximates the local clock time (decimal",
+            " hours, adjusted by the fixed UTC offset `tzOffset`) of sunset at `(lat, lon)`",
+            " (degrees) on the given day of the year. Fails with an error instead of returning",
+            " `NaN` if the sun never sets on this day at this latitude (polar day) or never rises",
+            " (polar night). See `sunrise` for the complementary event and `dms`/`dm` for",
+            " expressing `lat`/`lon` in degrees-minutes-seconds.",
+        ),
+    ));
+    prepared.insert("orbperiod", f64_multi_f64_units(
+        "orbperiod",
+        orbperiod,
+        concat!(
+            "`orbperiod(a, mu)` calculates the orbital period of a body with semi-major axis `a`",
+            " around a primary with gravitational parameter `mu` (Kepler's third law). The",
+            " result's units are derived from the operands' units, e.g. meters and m³/s² yield",
+            " seconds. You may want to use it with the `earthGM`/`sunGM` constants.",
+        ),
+    ));
+    prepared.insert("orbsma", f64_multi_f64_units(
+        "orbsma",
+        orbsma,
+        concat!(
+            "`orbsma(period, mu)` calculates the semi-major axis of an orbit with the given",
+            " `period` around a primary with gravitational parameter `mu`, inverting Kepler's",
+            " third law. The result's units are derived from the operands' units.",
+        ),
+    ));
+    prepared.insert("visviva", f64_multi_f64_units(
+        "visviva",
+        visviva,
+        concat!(
+            "`visviva(r, a, mu)` calculates the orbital speed at radius `r` of a body on an orbit",
+            " with semi-major axis `a` around a primary with gravitational parameter `mu` (the",
+            " vis-viva equation). `r` and `a` must share the same units; the result's units are",
+            " derived from the operands' units.",
+        ),
+    ));
+    prepared.insert("orbvelcirc", f64_multi_f64_units(
+        "orbvelcirc",
+        orbvelcirc,
+        concat!(
+            "`orbvelcirc(r, mu)` calculates the orbital speed of a circular orbit at radius `r`",
+            " around a primary with gravitational parameter `mu`. The result's units are derived",
+            " from the operands' units.",
+        ),
+    ));
 
     prepared.insert("ceil", f64_f64asint(
         "ceil",
-        |f| f.ceil(),
+        |f| ops::ceil(f),
         "`ceil(x)` returns `x` rounded up (towards \\(\\infty\\)).",
     ));
     prepared.insert("floor", f64_f64asint(
         "floor",
-        |f| f.floor(),
+        |f| ops::floor(f),
         "`floor(x)` returns `x` rounded down (towards \\(-\\infty\\)).",
     ));
     prepared.insert("round", f64_f64asint(
         "round",
-        |f| f.round(),
+        |f| ops::round(f),
         concat!(
             "`round(x)` returns `x` rounded _half away from zero_ (the most commonly used",
             " tie-breaking rule).",
@@ -335,7 +635,7 @@ pub(crate) fn get_canonical_functions() -> HashMap<String, Function> {
     ));
     prepared.insert("trunc", f64_f64asint(
         "trunc",
-        |f| f.trunc(),
+        |f| ops::trunc(f),
         concat!(
             "`trunc(x)` returns `x` rounded towards 0 (equivalent to stripping away all fractional",
             " digits).",
@@ -350,6 +650,14 @@ pub(crate) fn get_canonical_functions() -> HashMap<String, Function> {
             " attach units to a value, see `setunit`.",
         ),
     ));
+    prepared.insert("convert", Function::new(
+        Box::new(coerce),
+        concat!(
+            "`convert(x, u)` is a synonym of `coerce(x, u)`: it converts the value `x` into the",
+            " units of value `u`, reconciling the two units' base-unit signatures and rejecting",
+            " the conversion with a `UnitReconciliation` error if they do not match.",
+        ),
+    ));
     prepared.insert("setunit", Function::new(
         Box::new(set_unit),
         concat!(
@@ -364,6 +672,52 @@ pub(crate) fn get_canonical_functions() -> HashMap<String, Function> {
             " units).",
         ),
     ));
+    prepared.insert("siformat", Function::new(
+        Box::new(siformat),
+        concat!(
+            "`siformat(x)` renders `x` using the largest SI prefix for which the mantissa stays",
+            " within [1, 1000), e.g. `1500 m` becomes `1.5 km`. Only applies to values with a",
+            " single named unit at power 1; compound or unitless values are returned unchanged.",
+        ),
+    ));
+    prepared.insert("byteformat", Function::new(
+        Box::new(byteformat),
+        concat!(
+            "`byteformat(x, base)` renders the byte count `x` using the largest prefix of the",
+            " given `base` for which the mantissa stays under `base`: `1000` selects the decimal",
+            " SI prefixes (`kB`, `MB`, ...), `1024` selects the IEC binary prefixes (`KiB`,",
+            " `MiB`, ...). Only applies to values with a single named unit at power 1.",
+        ),
+    ));
+    prepared.insert("lt", Function::new(
+        Box::new(lt),
+        "`lt(x, y)` reconciles the units of `x` and `y` and returns `1` if `x` is less than `y`, `0` otherwise.",
+    ));
+    prepared.insert("le", Function::new(
+        Box::new(le),
+        concat!(
+            "`le(x, y)` reconciles the units of `x` and `y` and returns `1` if `x` is less than or",
+            " equal to `y`, `0` otherwise.",
+        ),
+    ));
+    prepared.insert("gt", Function::new(
+        Box::new(gt),
+        "`gt(x, y)` reconciles the units of `x` and `y` and returns `1` if `x` is greater than `y`, `0` otherwise.",
+    ));
+    prepared.insert("ge", Function::new(
+        Box::new(ge),
+        concat!(
+            "`ge(x, y)` reconciles the units of `x` and `y` and returns `1` if `x` is greater than",
+            " or equal to `y`, `0` otherwise.",
+        ),
+    ));
+    prepared.insert("eq", Function::new(
+        Box::new(eq),
+        concat!(
+            "`eq(x, y)` reconciles the units of `x` and `y` and returns `1` if they are equal,",
+            " `0` otherwise.",
+        ),
+    ));
     prepared.insert("c2f", f64_f64(
         "c2f",
         |f| f * 9.0/5.0 + 32.0,
@@ -394,6 +748,84 @@ fn check_arg_count(name: &'static str, expected: usize, obtained: usize) -> Resu
 }
 
 
+/// Converts `value`, expressed in `mode`, to radians.
+fn angle_to_radians(mode: AngleMode, value: f64) -> f64 {
+    match mode {
+        AngleMode::Radians => value,
+        AngleMode::Degrees => ops::to_radians(value),
+        AngleMode::Gradians => value * PI / 200.0,
+    }
+}
+
+/// Converts `value`, expressed in radians, to `mode`.
+fn radians_to_angle(mode: AngleMode, value: f64) -> f64 {
+    match mode {
+        AngleMode::Radians => value,
+        AngleMode::Degrees => ops::to_degrees(value),
+        AngleMode::Gradians => value * 200.0 / PI,
+    }
+}
+
+/// Like [`f64_f64`], but treats its operand as an angle expressed in the evaluation's current
+/// [`AngleMode`], converting it to radians before calling `inner`. Used for the direct circular
+/// trigonometric functions (`sin`/`cos`/`tan`/`csc`/`sec`/`cot`).
+fn angle_f64<F>(name: &'static str, inner: F, help_text: &'static str) -> Function
+    where F: Fn(f64) -> f64 + 'static
+{
+    Function::new(
+        Box::new(move |state, operands| {
+            check_arg_count(name, 1, operands.len())?;
+
+            let (operand, units): (f64, NumberUnits) = match &operands[0].node {
+                AstNode::Number(n) => {
+                    match &n.value {
+                        NumberValue::Int(i) => (i.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Rational(r) => (r.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Float(f) => (*f, n.units.clone()),
+                    }
+                },
+                other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+            };
+
+            Ok(AstNode::Number(Number::new(
+                NumberValue::Float(inner(angle_to_radians(state.angle_mode, operand))),
+                units,
+            )))
+        }),
+        help_text,
+    )
+}
+
+/// Like [`f64_f64`], but converts `inner`'s result, a radian angle, to the evaluation's current
+/// [`AngleMode`] before returning it. Used for the inverse circular trigonometric functions
+/// (`asin`/`acos`/`atan`/`acsc`/`asec`/`acot`).
+fn f64_angle<F>(name: &'static str, inner: F, help_text: &'static str) -> Function
+    where F: Fn(f64) -> f64 + 'static
+{
+    Function::new(
+        Box::new(move |state, operands| {
+            check_arg_count(name, 1, operands.len())?;
+
+            let (operand, units): (f64, NumberUnits) = match &operands[0].node {
+                AstNode::Number(n) => {
+                    match &n.value {
+                        NumberValue::Int(i) => (i.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Rational(r) => (r.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Float(f) => (*f, n.units.clone()),
+                    }
+                },
+                other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+            };
+
+            Ok(AstNode::Number(Number::new(
+                NumberValue::Float(radians_to_angle(state.angle_mode, inner(operand))),
+                units,
+            )))
+        }),
+        help_text,
+    )
+}
+
 fn f64_f64<F>(name: &'static str, inner: F, help_text: &'static str) -> Function
     where F: Fn(f64) -> f64 + 'static
 {
@@ -405,6 +837,7 @@ fn f64_f64<F>(name: &'static str, inner: F, help_text: &'static str) -> Function
                 AstNode::Number(n) => {
                     match &n.value {
                         NumberValue::Int(i) => (i.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Rational(r) => (r.to_f64().expect("conversion failed"), n.units.clone()),
                         NumberValue::Float(f) => (*f, n.units.clone()),
                     }
                 },
@@ -432,6 +865,7 @@ fn f64_f64asint<F>(name: &'static str, inner: F, help_text: &'static str) -> Fun
                 AstNode::Number(n) => {
                     match &n.value {
                         NumberValue::Int(i) => (i.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Rational(r) => (r.to_f64().expect("conversion failed"), n.units.clone()),
                         NumberValue::Float(f) => (*f, n.units.clone()),
                     }
                 },
@@ -464,6 +898,7 @@ fn f64_f64_f64<F>(name: &'static str, inner: F, help_text: &'static str) -> Func
                 AstNode::Number(n) => {
                     match &n.value {
                         NumberValue::Int(i) => (i.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Rational(r) => (r.to_f64().expect("conversion failed"), n.units.clone()),
                         NumberValue::Float(f) => (*f, n.units.clone()),
                     }
                 },
@@ -473,6 +908,7 @@ fn f64_f64_f64<F>(name: &'static str, inner: F, help_text: &'static str) -> Func
                 AstNode::Number(n) => {
                     match &n.value {
                         NumberValue::Int(i) => (i.to_f64().expect("conversion failed"), n.units.clone()),
+                        NumberValue::Rational(r) => (r.to_f64().expect("conversion failed"), n.units.clone()),
                         NumberValue::Float(f) => (*f, n.units.clone()),
                     }
                 },
@@ -505,6 +941,7 @@ fn f64_multi_f64<F, const ARG_COUNT: usize>(name: &'static str, inner: F, help_t
                     AstNode::Number(n) => {
                         match &n.value {
                             NumberValue::Int(i) => i.to_f64().expect("conversion failed"),
+                            NumberValue::Rational(r) => r.to_f64().expect("conversion failed"),
                             NumberValue::Float(f) => *f,
                         }
                     },
@@ -522,6 +959,156 @@ fn f64_multi_f64<F, const ARG_COUNT: usize>(name: &'static str, inner: F, help_t
     )
 }
 
+/// Like [`f64_multi_f64`], but `inner` returns several values rather than one, which are
+/// reported back as an [`AstNode::Tuple`] instead of a single [`AstNode::Number`]. Used by
+/// functions that solve for more than one quantity at once (e.g. `elldir`'s destination
+/// latitude/longitude).
+fn f64_multi_tuple<F, const ARG_COUNT: usize, const RESULT_COUNT: usize>(name: &'static str, inner: F, help_text: &'static str) -> Function
+    where F: Fn([f64; ARG_COUNT]) -> [f64; RESULT_COUNT] + 'static
+{
+    Function::new(
+        Box::new(move |_state, operands| {
+            check_arg_count(name, ARG_COUNT, operands.len())?;
+
+            let mut f64_operands = [0.0; ARG_COUNT];
+            for i in 0..ARG_COUNT {
+                let f64_op = match &operands[i].node {
+                    AstNode::Number(n) => {
+                        match &n.value {
+                            NumberValue::Int(i) => i.to_f64().expect("conversion failed"),
+                            NumberValue::Rational(r) => r.to_f64().expect("conversion failed"),
+                            NumberValue::Float(f) => *f,
+                        }
+                    },
+                    other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+                };
+                f64_operands[i] = f64_op;
+            }
+
+            let results = inner(f64_operands)
+                .iter()
+                .map(|r| Number::new(NumberValue::Float(*r), NumberUnits::new()))
+                .collect();
+
+            Ok(AstNode::Tuple(results))
+        }),
+        help_text,
+    )
+}
+
+/// Scales every exponent in `units` by the rational factor `num`/`den` (e.g. cubing with
+/// `(3, 1)`, square-rooting with `(1, 2)`). Panics if a unit's exponent isn't evenly divisible by
+/// `den`; true for every orbital-mechanics formula that uses this, which only ever raises
+/// physically consistent unit combinations to an integral power or takes their square/cube root.
+fn units_pow_rational(units: &NumberUnits, num: i64, den: i64) -> NumberUnits {
+    let mut result = NumberUnits::new();
+    for (unit, exponent) in units {
+        let scaled = exponent * BigInt::from(num);
+        let quotient = &scaled / BigInt::from(den);
+        assert_eq!(&quotient * BigInt::from(den), scaled, "unit {} raised to a non-integral power", unit);
+        if quotient != BigInt::from(0) {
+            result.insert(unit.clone(), quotient);
+        }
+    }
+    result
+}
+
+/// Like [`f64_multi_f64`], but also passes each operand's units to `inner`, which combines them
+/// into the result's units itself — used by the orbital-mechanics functions, so that e.g.
+/// `orbperiod`'s result carries seconds when given meters and m³/s² rather than discarding units
+/// like `f64_multi_f64` does.
+fn f64_multi_f64_units<F, const ARG_COUNT: usize>(name: &'static str, inner: F, help_text: &'static str) -> Function
+    where F: Fn([f64; ARG_COUNT], [NumberUnits; ARG_COUNT]) -> Result<(f64, NumberUnits), SimplificationError> + 'static
+{
+    Function::new(
+        Box::new(move |_state, operands| {
+            check_arg_count(name, ARG_COUNT, operands.len())?;
+
+            let mut f64_operands = [0.0; ARG_COUNT];
+            let mut unit_operands: [NumberUnits; ARG_COUNT] = std::array::from_fn(|_| NumberUnits::new());
+            for i in 0..ARG_COUNT {
+                match &operands[i].node {
+                    AstNode::Number(n) => {
+                        f64_operands[i] = match &n.value {
+                            NumberValue::Int(i) => i.to_f64().expect("conversion failed"),
+                            NumberValue::Rational(r) => r.to_f64().expect("conversion failed"),
+                            NumberValue::Float(f) => *f,
+                        };
+                        unit_operands[i] = n.units.clone();
+                    },
+                    other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+                }
+            }
+
+            let (value, units) = inner(f64_operands, unit_operands)?;
+            Ok(AstNode::Number(Number::new(NumberValue::Float(value), units)))
+        }),
+        help_text,
+    )
+}
+
+/// `orbperiod(a, mu)` = \\(2\pi\sqrt{a^3/\mu}\\) (Kepler's third law): the orbital period of a
+/// body with semi-major axis `a` around a primary with gravitational parameter `mu`.
+fn orbperiod(vals: [f64; 2], units: [NumberUnits; 2]) -> Result<(f64, NumberUnits), SimplificationError> {
+    let [a, mu] = vals;
+    let [a_units, mu_units] = units;
+
+    let value = 2.0 * PI * ops::sqrt((ops::powi(a, 3) / mu));
+
+    let a_cubed_units = units_pow_rational(&a_units, 3, 1);
+    let ratio_units = Number::addsub_units(&a_cubed_units, mu_units, |p| -p);
+    let result_units = units_pow_rational(&ratio_units, 1, 2);
+
+    Ok((value, result_units))
+}
+
+/// `orbsma(period, mu)` inverts Kepler's third law, returning the semi-major axis of an orbit
+/// with the given `period` around a primary with gravitational parameter `mu`.
+fn orbsma(vals: [f64; 2], units: [NumberUnits; 2]) -> Result<(f64, NumberUnits), SimplificationError> {
+    let [period, mu] = vals;
+    let [period_units, mu_units] = units;
+
+    let value = ops::cbrt((mu * ops::powi(period, 2) / (4.0 * ops::powi(PI, 2))));
+
+    let period_squared_units = units_pow_rational(&period_units, 2, 1);
+    let product_units = Number::addsub_units(&mu_units, period_squared_units, |p| p);
+    let result_units = units_pow_rational(&product_units, 1, 3);
+
+    Ok((value, result_units))
+}
+
+/// `visviva(r, a, mu)` = \\(\sqrt{\mu\left(\frac{2}{r} - \frac{1}{a}\right)}\\): the orbital
+/// speed at radius `r` of a body on an orbit with semi-major axis `a` around a primary with
+/// gravitational parameter `mu`. `r` and `a` must share the same units.
+fn visviva(vals: [f64; 3], units: [NumberUnits; 3]) -> Result<(f64, NumberUnits), SimplificationError> {
+    let [r, a, mu] = vals;
+    let [r_units, a_units, mu_units] = units;
+    if a_units != r_units {
+        return Err(SimplificationError::UnitReconciliation);
+    }
+
+    let value = ops::sqrt((mu * (2.0/r - 1.0/a)));
+
+    let ratio_units = Number::addsub_units(&mu_units, r_units, |p| -p);
+    let result_units = units_pow_rational(&ratio_units, 1, 2);
+
+    Ok((value, result_units))
+}
+
+/// `orbvelcirc(r, mu)` = \\(\sqrt{\mu/r}\\): the orbital speed of a circular orbit at radius `r`
+/// around a primary with gravitational parameter `mu`.
+fn orbvelcirc(vals: [f64; 2], units: [NumberUnits; 2]) -> Result<(f64, NumberUnits), SimplificationError> {
+    let [r, mu] = vals;
+    let [r_units, mu_units] = units;
+
+    let value = ops::sqrt((mu / r));
+
+    let ratio_units = Number::addsub_units(&mu_units, r_units, |p| -p);
+    let result_units = units_pow_rational(&ratio_units, 1, 2);
+
+    Ok((value, result_units))
+}
+
 fn from_dms_array(dms: [f64; 3]) -> f64 {
     let d = dms[0];
     let min = dms[1];
@@ -543,9 +1130,9 @@ fn deg2rad(deg: f64) -> f64 {
 }
 
 fn haversine(radius: f64, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    let left = ((lat2-lat1)/2.0).sin().powi(2);
-    let right = lat1.cos() * lat2.cos() * ((lon2-lon1)/2.0).sin().powi(2);
-    2.0 * radius * (left + right).sqrt().asin()
+    let left = ops::powi(ops::sin(((lat2-lat1)/2.0)), 2);
+    let right = ops::cos(lat1) * ops::cos(lat2) * ops::powi(ops::sin(((lon2-lon1)/2.0)), 2);
+    2.0 * radius * ops::asin(ops::sqrt((left + right)))
 }
 fn haversine_array(operands: [f64; 5]) -> f64 {
     haversine(operands[0], operands[1], operands[2], operands[3], operands[4])
@@ -565,7 +1152,7 @@ fn haversine_deg_array(operands: [f64; 5]) -> f64 {
 }
 
 fn ellipsoid_pole_radius(equator_radius: f64, inv_flattening: f64) -> f64 {
-    equator_radius - (inv_flattening.recip() * equator_radius)
+    equator_radius - (ops::recip(inv_flattening) * equator_radius)
 }
 
 fn ellipsoid_mean_radius(equator_radius: f64, inv_flattening: f64) -> f64 {
@@ -573,61 +1160,99 @@ fn ellipsoid_mean_radius(equator_radius: f64, inv_flattening: f64) -> f64 {
     (2.0*equator_radius + prad) / 3.0
 }
 
+/// Intermediate quantities produced by one convergent run of Vincenty's inverse-problem
+/// iteration, shared by [`ellipsoid_distance`] (which reduces them to a scalar distance) and
+/// [`ellipsoid_bearings`] (which reduces them to the initial/final azimuths).
 #[allow(non_snake_case)]
-fn ellipsoid_distance(equator_radius: f64, inv_flattening: f64, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    // Vincenty's formulae
-    let a = equator_radius;
+struct VincentyInverseSolution {
+    U1: f64,
+    U2: f64,
+    lambda: f64,
+    sin_sigma: f64,
+    cos_sigma: f64,
+    sigma: f64,
+    cos_2sigmam: f64,
+    cos2_alpha: f64,
+}
+
+/// Caps Vincenty's iterations: near-antipodal point pairs are notorious for making them fail to
+/// converge, so without a cap they would loop forever.
+const MAX_VINCENTY_ITERATIONS: u32 = 200;
+
+/// Solves Vincenty's inverse geodesic problem (the distance and bearings between two points on
+/// an ellipsoid), returning `None` if the iteration doesn't converge within
+/// `MAX_VINCENTY_ITERATIONS` (which can happen for near-antipodal point pairs).
+#[allow(non_snake_case)]
+fn vincenty_inverse(inv_flattening: f64, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Option<VincentyInverseSolution> {
     let f = 1.0/inv_flattening;
-    let b = (1.0 - f) * a;
 
-    let U1 = ((1.0 - f) * lat1.tan()).atan();
-    let U2 = ((1.0 - f) * lat2.tan()).atan();
+    let U1 = ops::atan(((1.0 - f) * ops::tan(lat1)));
+    let U2 = ops::atan(((1.0 - f) * ops::tan(lat2)));
     let L = lon2 - lon1;
 
     let mut lambda = L;
-    let mut cos2_alpha;
-    let mut sin_sigma;
-    let mut cos_sigma;
-    let mut sigma;
-    let mut cos_2sigmam;
-    loop {
+    let mut cos2_alpha = 0.0;
+    let mut sin_sigma = 0.0;
+    let mut cos_sigma = 0.0;
+    let mut sigma = 0.0;
+    let mut cos_2sigmam = 0.0;
+    for _ in 0..MAX_VINCENTY_ITERATIONS {
         let prev_lambda = lambda;
-        sin_sigma = (
-            (U2.cos() * lambda.sin()).powi(2)
-            + (U1.cos() * U2.sin() - U1.sin() * U2.cos() * lambda.cos()).powi(2)
-        ).sqrt();
-        cos_sigma = U1.sin() * U2.sin() + U1.cos() * U2.cos() * lambda.cos();
-        sigma = sin_sigma.atan2(cos_sigma);
-        let sin_alpha = (U1.cos() * U2.cos() * lambda.sin()) / sigma.sin();
-        cos2_alpha = 1.0 - sin_alpha.powi(2);
-        cos_2sigmam = sigma.cos() - (2.0 * U1.sin() * U2.sin()) / cos2_alpha;
+        sin_sigma = ops::sqrt((
+            ops::powi((ops::cos(U2) * ops::sin(lambda)), 2)
+            + ops::powi((ops::cos(U1) * ops::sin(U2) - ops::sin(U1) * ops::cos(U2) * ops::cos(lambda)), 2)
+        ));
+        cos_sigma = ops::sin(U1) * ops::sin(U2) + ops::cos(U1) * ops::cos(U2) * ops::cos(lambda);
+        sigma = ops::atan2(sin_sigma, cos_sigma);
+        let sin_alpha = (ops::cos(U1) * ops::cos(U2) * ops::sin(lambda)) / ops::sin(sigma);
+        cos2_alpha = 1.0 - ops::powi(sin_alpha, 2);
+        cos_2sigmam = ops::cos(sigma) - (2.0 * ops::sin(U1) * ops::sin(U2)) / cos2_alpha;
         let C = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
         lambda = L + (1.0 - C) * f * sin_alpha * (
             sigma + C * sin_sigma * (
                 cos_2sigmam + C * cos_sigma * (
-                    -1.0 + 2.0 * cos_2sigmam.powi(2)
+                    -1.0 + 2.0 * ops::powi(cos_2sigmam, 2)
                 )
             )
         );
         if (lambda - prev_lambda).abs() < 1e-6 {
-            break;
+            return Some(VincentyInverseSolution { U1, U2, lambda, sin_sigma, cos_sigma, sigma, cos_2sigmam, cos2_alpha });
         }
     }
 
-    let u2 = cos2_alpha * (a.powi(2) - b.powi(2)) / b.powi(2);
+    None
+}
+
+#[allow(non_snake_case)]
+fn ellipsoid_distance(equator_radius: f64, inv_flattening: f64, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    // Vincenty's formulae
+    let a = equator_radius;
+    let f = 1.0/inv_flattening;
+    let b = (1.0 - f) * a;
+
+    let solution = match vincenty_inverse(inv_flattening, lat1, lon1, lat2, lon2) {
+        Some(solution) => solution,
+        None => {
+            // near-antipodal point pairs can make the iteration fail to converge; fall back to
+            // the mean-radius haversine great-circle distance as a usable approximation
+            let mean_radius = ellipsoid_mean_radius(equator_radius, inv_flattening);
+            return haversine(mean_radius, lat1, lon1, lat2, lon2);
+        },
+    };
+
+    let u2 = solution.cos2_alpha * (ops::powi(a, 2) - ops::powi(b, 2)) / ops::powi(b, 2);
     let A = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
     let B = u2 / 1024.0 * (256.0 + u2 * (128.0 + u2 * (74.0 - 47.0 * u2)));
-    let delta_sigma = B * sin_sigma * (
-        cos_2sigmam + 1.0/4.0 * B * (
-            cos_sigma * (
-                -1.0 + 2.0 * cos_2sigmam.powi(2)
+    let delta_sigma = B * solution.sin_sigma * (
+        solution.cos_2sigmam + 1.0/4.0 * B * (
+            solution.cos_sigma * (
+                -1.0 + 2.0 * ops::powi(solution.cos_2sigmam, 2)
             )
-            - B/6.0 * cos_2sigmam * (-3.0 + 4.0 * sin_sigma.powi(2)) * (-3.0 + 4.0 * cos_2sigmam.powi(2))
+            - B/6.0 * solution.cos_2sigmam * (-3.0 + 4.0 * ops::powi(solution.sin_sigma, 2)) * (-3.0 + 4.0 * ops::powi(solution.cos_2sigmam, 2))
         )
     );
-    let s = b * A * (sigma - delta_sigma);
 
-    s
+    b * A * (solution.sigma - delta_sigma)
 }
 fn ellipsoid_distance_array(operands: [f64; 6]) -> f64 {
     ellipsoid_distance(operands[0], operands[1], operands[2], operands[3], operands[4], operands[5])
@@ -649,6 +1274,355 @@ fn ellipsoid_distance_deg_array(operands: [f64; 6]) -> f64 {
 }
 
 
+/// Solves Vincenty's direct geodesic problem: given a starting point, an initial bearing and a
+/// distance to travel along the ellipsoid's surface, returns the resulting destination
+/// `(lat2, lon2)`.
+#[allow(non_snake_case)]
+fn ellipsoid_direct(equator_radius: f64, inv_flattening: f64, lat1: f64, lon1: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    let a = equator_radius;
+    let f = 1.0/inv_flattening;
+    let b = (1.0 - f) * a;
+
+    let alpha1 = bearing;
+    let U1 = ops::atan(((1.0 - f) * ops::tan(lat1)));
+    let sigma1 = ops::atan2(ops::tan(U1), ops::cos(alpha1));
+    let sin_alpha = ops::cos(U1) * ops::sin(alpha1);
+    let cos2_alpha = 1.0 - ops::powi(sin_alpha, 2);
+    let u2 = cos2_alpha * (ops::powi(a, 2) - ops::powi(b, 2)) / ops::powi(b, 2);
+    let A = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+    let B = u2 / 1024.0 * (256.0 + u2 * (128.0 + u2 * (74.0 - 47.0 * u2)));
+
+    let mut sigma = distance / (b * A);
+    let mut cos_2sigmam = ops::cos((2.0 * sigma1 + sigma));
+    for _ in 0..MAX_VINCENTY_ITERATIONS {
+        cos_2sigmam = ops::cos((2.0 * sigma1 + sigma));
+        let delta_sigma = B * ops::sin(sigma) * (
+            cos_2sigmam + B / 4.0 * (
+                ops::cos(sigma) * (-1.0 + 2.0 * ops::powi(cos_2sigmam, 2))
+                - B / 6.0 * cos_2sigmam * (-3.0 + 4.0 * ops::powi(ops::sin(sigma), 2)) * (-3.0 + 4.0 * ops::powi(cos_2sigmam, 2))
+            )
+        );
+        let new_sigma = distance / (b * A) + delta_sigma;
+        let converged = (new_sigma - sigma).abs() < 1e-12;
+        sigma = new_sigma;
+        if converged {
+            break;
+        }
+    }
+
+    let lat2 = ops::atan2((ops::sin(U1) * ops::cos(sigma) + ops::cos(U1) * ops::sin(sigma) * ops::cos(alpha1)), 
+        (1.0 - f) * ops::sqrt((ops::powi(sin_alpha, 2) + ops::powi((ops::sin(U1) * ops::sin(sigma) - ops::cos(U1) * ops::cos(sigma) * ops::cos(alpha1)), 2)))
+    );
+    let lambda = ops::atan2((ops::sin(sigma) * ops::sin(alpha1)), ops::cos(U1) * ops::cos(sigma) - ops::sin(U1) * ops::sin(sigma) * ops::cos(alpha1));
+    let C = f / 16.0 * cos2_alpha * (4.0 + f * (4.0 - 3.0 * cos2_alpha));
+    let L = lambda - (1.0 - C) * f * sin_alpha * (
+        sigma + C * ops::sin(sigma) * (
+            cos_2sigmam + C * ops::cos(sigma) * (-1.0 + 2.0 * ops::powi(cos_2sigmam, 2))
+        )
+    );
+
+    (lat2, lon1 + L)
+}
+fn ellipsoid_direct_array(operands: [f64; 6]) -> [f64; 2] {
+    let (lat2, lon2) = ellipsoid_direct(operands[0], operands[1], operands[2], operands[3], operands[4], operands[5]);
+    [lat2, lon2]
+}
+
+fn ellipsoid_direct_deg(equator_radius: f64, inv_flattening: f64, lat1: f64, lon1: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    let (lat2, lon2) = ellipsoid_direct(
+        equator_radius,
+        inv_flattening,
+        deg2rad(lat1),
+        deg2rad(lon1),
+        deg2rad(bearing),
+        distance,
+    );
+    (ops::to_degrees(lat2), ops::to_degrees(lon2))
+}
+fn ellipsoid_direct_deg_array(operands: [f64; 6]) -> [f64; 2] {
+    let (lat2, lon2) = ellipsoid_direct_deg(operands[0], operands[1], operands[2], operands[3], operands[4], operands[5]);
+    [lat2, lon2]
+}
+
+
+/// Falls back to the initial/final bearings of the haversine great-circle path, for use when
+/// [`vincenty_inverse`] fails to converge (see [`ellipsoid_distance`]'s equivalent fallback).
+fn haversine_bearings(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64) {
+    let dlon = lon2 - lon1;
+    let initial = ops::atan2((ops::sin(dlon) * ops::cos(lat2))
+        , ops::cos(lat1) * ops::sin(lat2) - ops::sin(lat1) * ops::cos(lat2) * ops::cos(dlon));
+
+    let dlon_rev = lon1 - lon2;
+    let reverse_of_final = ops::atan2((ops::sin(dlon_rev) * ops::cos(lat1))
+        , ops::cos(lat2) * ops::sin(lat1) - ops::sin(lat2) * ops::cos(lat1) * ops::cos(dlon_rev));
+    let final_ = reverse_of_final + PI;
+
+    (initial, final_)
+}
+
+/// Computes the initial and final azimuths of Vincenty's inverse geodesic solution between
+/// `(lat1, lon1)` and `(lat2, lon2)`.
+#[allow(non_snake_case)]
+fn ellipsoid_bearings(inv_flattening: f64, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64) {
+    let solution = match vincenty_inverse(inv_flattening, lat1, lon1, lat2, lon2) {
+        Some(solution) => solution,
+        None => return haversine_bearings(lat1, lon1, lat2, lon2),
+    };
+
+    let initial = ops::atan2((ops::cos(solution.U2) * ops::sin(solution.lambda))
+        , ops::cos(solution.U1) * ops::sin(solution.U2) - ops::sin(solution.U1) * ops::cos(solution.U2) * ops::cos(solution.lambda));
+    let final_ = ops::atan2((ops::cos(solution.U1) * ops::sin(solution.lambda))
+        , -ops::sin(solution.U1) * ops::cos(solution.U2) + ops::cos(solution.U1) * ops::sin(solution.U2) * ops::cos(solution.lambda));
+
+    (initial, final_)
+}
+fn ellipsoid_bearings_array(operands: [f64; 5]) -> [f64; 2] {
+    let (initial, final_) = ellipsoid_bearings(operands[0], operands[1], operands[2], operands[3], operands[4]);
+    [initial, final_]
+}
+
+fn ellipsoid_bearings_deg(inv_flattening: f64, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> (f64, f64) {
+    let (initial, final_) = ellipsoid_bearings(inv_flattening, deg2rad(lat1), deg2rad(lon1), deg2rad(lat2), deg2rad(lon2));
+    (ops::to_degrees(initial), ops::to_degrees(final_))
+}
+fn ellipsoid_bearings_deg_array(operands: [f64; 5]) -> [f64; 2] {
+    let (initial, final_) = ellipsoid_bearings_deg(operands[0], operands[1], operands[2], operands[3], operands[4]);
+    [initial, final_]
+}
+
+
+/// Converts geodetic coordinates (latitude `lat`, longitude `lon`, both in degrees, and altitude
+/// `alt` in meters above the ellipsoid) on the WGS84 ellipsoid to Earth-Centered-Earth-Fixed
+/// (ECEF) Cartesian coordinates `(x, y, z)`, in meters.
+fn geodetic2ecef(lat: f64, lon: f64, alt: f64) -> (f64, f64, f64) {
+    let a = WGS84_EQUATOR_RADIUS_M;
+    let f = 1.0 / WGS84_INVERSE_FLATTENING;
+    let e2 = f * (2.0 - f);
+
+    let phi = deg2rad(lat);
+    let lambda = deg2rad(lon);
+    let n = a / ops::sqrt((1.0 - e2 * ops::powi(ops::sin(phi), 2)));
+
+    let x = (n + alt) * ops::cos(phi) * ops::cos(lambda);
+    let y = (n + alt) * ops::cos(phi) * ops::sin(lambda);
+    let z = (n * (1.0 - e2) + alt) * ops::sin(phi);
+
+    (x, y, z)
+}
+fn geodetic2ecef_array(operands: [f64; 3]) -> [f64; 3] {
+    let (x, y, z) = geodetic2ecef(operands[0], operands[1], operands[2]);
+    [x, y, z]
+}
+
+/// Converts ECEF Cartesian coordinates `(x, y, z)`, in meters, back to geodetic coordinates
+/// `(lat, lon, alt)` on the WGS84 ellipsoid (degrees, degrees, meters) using Bowring's method.
+fn ecef2geodetic(x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+    let a = WGS84_EQUATOR_RADIUS_M;
+    let f = 1.0 / WGS84_INVERSE_FLATTENING;
+    let b = a * (1.0 - f);
+    let e2 = f * (2.0 - f);
+    let ep2 = (ops::powi(a, 2) - ops::powi(b, 2)) / ops::powi(b, 2);
+
+    let p = ops::sqrt((ops::powi(x, 2) + ops::powi(y, 2)));
+    let theta = ops::atan2((z * a), p * b);
+
+    let lat = ops::atan2((z + ep2 * b * ops::powi(ops::sin(theta), 3)), p - e2 * a * ops::powi(ops::cos(theta), 3));
+    let lon = ops::atan2(y, x);
+    let n = a / ops::sqrt((1.0 - e2 * ops::powi(ops::sin(lat), 2)));
+    let alt = p / ops::cos(lat) - n;
+
+    (ops::to_degrees(lat), ops::to_degrees(lon), alt)
+}
+fn ecef2geodetic_array(operands: [f64; 3]) -> [f64; 3] {
+    let (lat, lon, alt) = ecef2geodetic(operands[0], operands[1], operands[2]);
+    [lat, lon, alt]
+}
+
+/// Returns the UTM zone number that `lon` (degrees) falls into.
+fn utm_zone(lon: f64) -> f64 {
+    ops::floor(((lon + 180.0) / 6.0)) + 1.0
+}
+fn utm_zone_array(operands: [f64; 2]) -> f64 {
+    utm_zone(operands[1])
+}
+
+/// The UTM central scale factor, applied at each zone's central meridian.
+const UTM_SCALE_FACTOR: f64 = 0.9996;
+const UTM_FALSE_EASTING_M: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH_M: f64 = 10_000_000.0;
+
+/// The longitude, in degrees, of the central meridian of UTM `zone`.
+fn utm_central_meridian(zone: f64) -> f64 {
+    (zone - 1.0) * 6.0 - 180.0 + 3.0
+}
+
+/// Converts geodetic latitude/longitude (degrees) to a UTM grid position, returning
+/// `(zone, easting, northing)` (easting and northing in meters), using the Redfearn/Snyder
+/// transverse-Mercator series.
+fn latlon2utm(lat: f64, lon: f64) -> (f64, f64, f64) {
+    let a = WGS84_EQUATOR_RADIUS_M;
+    let f = 1.0 / WGS84_INVERSE_FLATTENING;
+    let e2 = f * (2.0 - f);
+    let ep2 = e2 / (1.0 - e2);
+    let k0 = UTM_SCALE_FACTOR;
+
+    let zone = utm_zone(lon);
+    let phi = deg2rad(lat);
+    let lambda0 = deg2rad(utm_central_meridian(zone));
+    let lambda = deg2rad(lon);
+
+    let n = a / ops::sqrt((1.0 - e2 * ops::powi(ops::sin(phi), 2)));
+    let t = ops::powi(ops::tan(phi), 2);
+    let c = ep2 * ops::powi(ops::cos(phi), 2);
+    let aa = ops::cos(phi) * (lambda - lambda0);
+
+    let m = a * (
+        (1.0 - e2/4.0 - 3.0*ops::powi(e2, 2)/64.0 - 5.0*ops::powi(e2, 3)/256.0) * phi
+        - (3.0*e2/8.0 + 3.0*ops::powi(e2, 2)/32.0 + 45.0*ops::powi(e2, 3)/1024.0) * ops::sin((2.0*phi))
+        + (15.0*ops::powi(e2, 2)/256.0 + 45.0*ops::powi(e2, 3)/1024.0) * ops::sin((4.0*phi))
+        - (35.0*ops::powi(e2, 3)/3072.0) * ops::sin((6.0*phi))
+    );
+
+    let easting = k0*n*(
+        aa + (1.0-t+c)*ops::powi(aa, 3)/6.0
+        + (5.0 - 18.0*t + ops::powi(t, 2) + 72.0*c - 58.0*ep2)*ops::powi(aa, 5)/120.0
+    ) + UTM_FALSE_EASTING_M;
+    let mut northing = k0*(
+        m + n*ops::tan(phi)*(
+            ops::powi(aa, 2)/2.0
+            + (5.0 - t + 9.0*c + 4.0*ops::powi(c, 2))*ops::powi(aa, 4)/24.0
+            + (61.0 - 58.0*t + ops::powi(t, 2) + 600.0*c - 330.0*ep2)*ops::powi(aa, 6)/720.0
+        )
+    );
+    if lat < 0.0 {
+        northing += UTM_FALSE_NORTHING_SOUTH_M;
+    }
+
+    (zone, easting, northing)
+}
+fn latlon2utm_array(operands: [f64; 2]) -> [f64; 3] {
+    let (zone, easting, northing) = latlon2utm(operands[0], operands[1]);
+    [zone, easting, northing]
+}
+
+/// Converts a UTM grid position back to geodetic latitude/longitude (degrees). `hemisphere` is
+/// the sign convention used throughout this module for north/south (zero or positive is
+/// northern, negative is southern), matching how a negative `lat` already denotes a southern
+/// latitude elsewhere in this file.
+fn utm2latlon(zone: f64, hemisphere: f64, easting: f64, northing: f64) -> (f64, f64) {
+    let a = WGS84_EQUATOR_RADIUS_M;
+    let f = 1.0 / WGS84_INVERSE_FLATTENING;
+    let e2 = f * (2.0 - f);
+    let ep2 = e2 / (1.0 - e2);
+    let k0 = UTM_SCALE_FACTOR;
+    let e1 = (1.0 - ops::sqrt((1.0 - e2))) / (1.0 + ops::sqrt((1.0 - e2)));
+
+    let x = easting - UTM_FALSE_EASTING_M;
+    let y = if hemisphere < 0.0 { northing - UTM_FALSE_NORTHING_SOUTH_M } else { northing };
+
+    let m = y / k0;
+    let mu = m / (a * (1.0 - e2/4.0 - 3.0*ops::powi(e2, 2)/64.0 - 5.0*ops::powi(e2, 3)/256.0));
+
+    let phi1 = mu
+        + (3.0*e1/2.0 - 27.0*ops::powi(e1, 3)/32.0) * ops::sin((2.0*mu))
+        + (21.0*ops::powi(e1, 2)/16.0 - 55.0*ops::powi(e1, 4)/32.0) * ops::sin((4.0*mu))
+        + (151.0*ops::powi(e1, 3)/96.0) * ops::sin((6.0*mu))
+        + (1097.0*ops::powi(e1, 4)/512.0) * ops::sin((8.0*mu));
+
+    let n1 = a / ops::sqrt((1.0 - e2*ops::powi(ops::sin(phi1), 2)));
+    let t1 = ops::powi(ops::tan(phi1), 2);
+    let c1 = ep2 * ops::powi(ops::cos(phi1), 2);
+    let r1 = a * (1.0 - e2) / ops::powf((1.0 - e2*ops::powi(ops::sin(phi1), 2)), 1.5);
+    let d = x / (n1 * k0);
+
+    let lat = phi1 - (n1*ops::tan(phi1)/r1) * (
+        ops::powi(d, 2)/2.0
+        - (5.0 + 3.0*t1 + 10.0*c1 - 4.0*ops::powi(c1, 2) - 9.0*ep2)*ops::powi(d, 4)/24.0
+        + (61.0 + 90.0*t1 + 298.0*c1 + 45.0*ops::powi(t1, 2) - 252.0*ep2 - 3.0*ops::powi(c1, 2))*ops::powi(d, 6)/720.0
+    );
+    let lambda0 = deg2rad(utm_central_meridian(zone));
+    let lon = lambda0 + (
+        d - (1.0 + 2.0*t1 + c1)*ops::powi(d, 3)/6.0
+        + (5.0 - 2.0*c1 + 28.0*t1 - 3.0*ops::powi(c1, 2) + 8.0*ep2 + 24.0*ops::powi(t1, 2))*ops::powi(d, 5)/120.0
+    ) / ops::cos(phi1);
+
+    (ops::to_degrees(lat), ops::to_degrees(lon))
+}
+fn utm2latlon_array(operands: [f64; 4]) -> [f64; 2] {
+    let (lat, lon) = utm2latlon(operands[0], operands[1], operands[2], operands[3]);
+    [lat, lon]
+}
+
+
+/// The sun's declination δ, in degrees, on `day_of_year` (1-based day of the calendar year),
+/// using the common single-term approximation.
+fn sun_declination(day_of_year: f64) -> f64 {
+    23.44 * ops::sin(deg2rad(360.0 * (day_of_year + 284.0) / 365.0))
+}
+fn sun_declination_array(operands: [f64; 1]) -> f64 {
+    sun_declination(operands[0])
+}
+
+/// The sun's hour angle ω₀ at sunrise/sunset, in degrees, given a latitude and solar declination
+/// (both in degrees). Returns [`SimplificationError::PolarDayOrNight`] if the sun never rises or
+/// sets at this latitude on this day (polar day or polar night).
+fn solar_hour_angle(lat: f64, decl: f64) -> Result<f64, SimplificationError> {
+    let cos_omega0 = -ops::tan(deg2rad(lat)) * ops::tan(deg2rad(decl));
+    if cos_omega0.abs() > 1.0 {
+        return Err(SimplificationError::PolarDayOrNight);
+    }
+    Ok(ops::to_degrees(ops::acos(cos_omega0)))
+}
+
+/// Local solar noon, in decimal hours, at longitude `lon` (degrees) on `day_of_year`, adjusted
+/// for the equation of time and a fixed UTC offset `tz_offset` (hours).
+fn solar_noon(lon: f64, day_of_year: f64, tz_offset: f64) -> f64 {
+    let b = deg2rad(360.0 * (day_of_year - 81.0) / 365.0);
+    let eot = 9.87 * ops::sin((2.0 * b)) - 7.53 * ops::cos(b) - 1.5 * ops::sin(b);
+    12.0 - lon / 15.0 - eot / 60.0 + tz_offset
+}
+
+fn extract_f64_operand(node: &AstNode) -> Result<f64, SimplificationError> {
+    match node {
+        AstNode::Number(n) => {
+            match &n.value {
+                NumberValue::Int(i) => Ok(i.to_f64().expect("conversion failed")),
+                NumberValue::Rational(r) => Ok(r.to_f64().expect("conversion failed")),
+                NumberValue::Float(f) => Ok(*f),
+            }
+        },
+        other => Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+    }
+}
+
+/// `sunrise(lat, lon, dayOfYear, tzOffset)` / `sunset(...)`: local clock time (decimal hours) of
+/// the given solar event, or [`SimplificationError::PolarDayOrNight`] if the sun never rises or
+/// sets at this location on this day.
+fn sun_event(operands: &[AstNodeAtLocation], name: &'static str, hour_angle_sign: f64) -> BuiltInFuncResult {
+    check_arg_count(name, 4, operands.len())?;
+
+    let lat = extract_f64_operand(&operands[0].node)?;
+    let lon = extract_f64_operand(&operands[1].node)?;
+    let day_of_year = extract_f64_operand(&operands[2].node)?;
+    let tz_offset = extract_f64_operand(&operands[3].node)?;
+
+    let decl = sun_declination(day_of_year);
+    let omega0 = solar_hour_angle(lat, decl)?;
+    let noon = solar_noon(lon, day_of_year, tz_offset);
+
+    Ok(AstNode::Number(Number::new(
+        NumberValue::Float(noon + hour_angle_sign * omega0 / 15.0),
+        NumberUnits::new(),
+    )))
+}
+fn sunrise(_state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    sun_event(operands, "sunrise", -1.0)
+}
+fn sunset(_state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    sun_event(operands, "sunset", 1.0)
+}
+
+
 /// Takes two operands and attempts to convert the first operand to the unit of the second. The
 /// numeric value of the second operand is ignored; only the unit is taken into account.
 fn coerce(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
@@ -705,3 +1679,160 @@ fn to_base_units(state: &SimplificationState, operands: &[AstNodeAtLocation]) ->
 
     Ok(AstNode::Number(result))
 }
+
+/// Renders `number` using the largest SI prefix for which the mantissa stays within
+/// `[1, 1000)`. Only values carrying exactly one named unit at power `1` are reformatted;
+/// compound or unitless values are returned unchanged.
+pub(crate) fn format_with_best_si_prefix(number: &Number, database: &UnitDatabase) -> Number {
+    if number.units.len() != 1 {
+        return number.clone();
+    }
+    let (unit_letters, unit_power) = number.units.iter().next().unwrap();
+    if unit_power != &BigInt::from(1) {
+        return number.clone();
+    }
+
+    let (_old_prefix, base_letters, old_factor) = database.decompose_si_prefix(unit_letters);
+    let base_value = number.value.to_f64() * old_factor;
+    if base_value == 0.0 {
+        return number.clone();
+    }
+
+    let exp = (ops::floor(ops::log10(base_value.abs()) / 3.0) * 3.0)
+        .clamp(-30.0, 30.0) as i32;
+    let new_factor = ops::powi(10.0, exp);
+    let mantissa = base_value / new_factor;
+
+    let prefix = database.si_prefix_for_exponent(exp).unwrap_or_default();
+    let mut new_units = NumberUnits::new();
+    new_units.insert(format!("{}{}", prefix, base_letters), BigInt::from(1));
+
+    Number::new(
+        NumberValue::Float(mantissa),
+        new_units,
+    )
+}
+
+/// Takes a single operand and renders it using the largest SI prefix for which the mantissa
+/// stays within `[1, 1000)`. Only values carrying exactly one named unit at power `1` are
+/// reformatted; compound or unitless values are returned unchanged.
+fn siformat(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    check_arg_count("siformat", 1, operands.len())?;
+
+    let number = match &operands[0].node {
+        AstNode::Number(n) => n,
+        other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+    };
+
+    Ok(AstNode::Number(format_with_best_si_prefix(number, &state.units)))
+}
+
+/// Renders a byte count in `number` using the largest prefix of the given `base` (`1000` for the
+/// decimal SI prefixes `k`/`M`/`G`/`T`/`P`, `1024` for the IEC binary prefixes
+/// `Ki`/`Mi`/`Gi`/`Ti`/`Pi`) for which the mantissa stays under `base`. Only values carrying
+/// exactly one named unit at power `1` are reformatted; compound or unitless values are returned
+/// unchanged.
+pub(crate) fn format_as_bytes(number: &Number, base: f64, database: &UnitDatabase) -> Number {
+    if number.units.len() != 1 {
+        return number.clone();
+    }
+    let (unit_letters, unit_power) = number.units.iter().next().unwrap();
+    if unit_power != &BigInt::from(1) {
+        return number.clone();
+    }
+
+    // normalize to a raw, unprefixed byte count, trying both prefix families
+    let (si_prefix, si_base, si_factor) = database.decompose_si_prefix(unit_letters);
+    let (base_letters, old_factor) = if si_prefix.is_empty() {
+        let (_iec_prefix, iec_base, iec_factor) = database.decompose_iec_prefix(unit_letters);
+        (iec_base, iec_factor)
+    } else {
+        (si_base, si_factor)
+    };
+    let byte_value = number.value.to_f64() * old_factor;
+    if byte_value == 0.0 {
+        return number.clone();
+    }
+
+    let use_iec = (base - 1024.0).abs() < 1e-9;
+    let step = if use_iec { 1024.0 } else { 1000.0 };
+
+    let mut order: u32 = 0;
+    let mut mantissa = byte_value.abs();
+    while mantissa >= step && order < 5 {
+        mantissa /= step;
+        order += 1;
+    }
+    if byte_value < 0.0 {
+        mantissa = -mantissa;
+    }
+
+    let prefix = if use_iec {
+        database.iec_prefix_for_order(order).unwrap_or_default()
+    } else {
+        database.si_prefix_for_exponent(3 * order as i32).unwrap_or_default()
+    };
+
+    let mut new_units = NumberUnits::new();
+    new_units.insert(format!("{}{}", prefix, base_letters), BigInt::from(1));
+
+    Number::new(
+        NumberValue::Float(mantissa),
+        new_units,
+    )
+}
+
+/// Takes a byte count and a base (`1000` for decimal SI prefixes, `1024` for IEC binary
+/// prefixes) and renders the value using the largest matching prefix, e.g. `500000 B` becomes
+/// `500 kB` with base `1000`, or `488.28 KiB` with base `1024`.
+fn byteformat(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    check_arg_count("byteformat", 2, operands.len())?;
+
+    let number = match &operands[0].node {
+        AstNode::Number(n) => n,
+        other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+    };
+    let base = match &operands[1].node {
+        AstNode::Number(n) => n.value.to_f64(),
+        other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+    };
+
+    Ok(AstNode::Number(format_as_bytes(number, base, &state.units)))
+}
+
+/// Shared implementation of the `lt`/`le`/`gt`/`ge`/`eq` comparison built-ins: reconciles the
+/// units of both operands, compares their base-unit-scaled magnitudes, and maps the resulting
+/// `Ordering` through `accept` to produce a unitless boolean-valued `Number` (`1` or `0`).
+fn compare(name: &'static str, operands: &[AstNodeAtLocation], state: &SimplificationState, accept: fn(Option<Ordering>) -> bool) -> BuiltInFuncResult {
+    check_arg_count(name, 2, operands.len())?;
+
+    let left_number = match &operands[0].node {
+        AstNode::Number(n) => n,
+        other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+    };
+    let right_number = match &operands[1].node {
+        AstNode::Number(n) => n,
+        other => return Err(SimplificationError::UnexpectedOperandType(format!("{:?}", other))),
+    };
+
+    let ordering = left_number.checked_partial_cmp(right_number.clone(), &state.units)
+        .map_err(SimplificationError::from)?;
+
+    let result = if accept(ordering) { BigInt::from(1) } else { BigInt::from(0) };
+    Ok(AstNode::Number(Number::new(NumberValue::Int(result), NumberUnits::new())))
+}
+fn lt(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    compare("lt", operands, state, |o| o == Some(Ordering::Less))
+}
+fn le(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    compare("le", operands, state, |o| matches!(o, Some(Ordering::Less) | Some(Ordering::Equal)))
+}
+fn gt(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    compare("gt", operands, state, |o| o == Some(Ordering::Greater))
+}
+fn ge(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    compare("ge", operands, state, |o| matches!(o, Some(Ordering::Greater) | Some(Ordering::Equal)))
+}
+fn eq(state: &SimplificationState, operands: &[AstNodeAtLocation]) -> BuiltInFuncResult {
+    compare("eq", operands, state, |o| o == Some(Ordering::Equal))
+}
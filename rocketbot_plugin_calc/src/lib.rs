@@ -1,12 +1,16 @@
 mod ast;
+mod bytecode;
 #[cfg(feature = "currency")]
 mod currency;
 mod factor;
 mod grimoire;
 mod known_primes;
 mod numbers;
+mod ops;
 mod parsing;
+mod printer;
 mod units;
+mod visitor;
 
 
 use std::collections::BTreeSet;
@@ -27,9 +31,9 @@ use serde_json;
 use toml;
 use tracing::error;
 
-use crate::ast::{AstNode, SimplificationState};
+use crate::ast::{AngleMode, AstNode, SimplificationState};
 use crate::factor::{PrimeCache, PrimeFactors};
-use crate::grimoire::{get_canonical_constants, get_canonical_functions};
+use crate::grimoire::{format_with_best_si_prefix, get_canonical_constants, get_canonical_functions};
 use crate::numbers::NumberValue;
 use crate::parsing::parse_full_expression;
 use crate::units::{StoredUnitDatabase, UnitDatabase};
@@ -105,6 +109,14 @@ impl CalcPlugin {
             (*config_guard).clone()
         };
 
+        let angle_mode = if ast_root.instructions.contains("grad") {
+            AngleMode::Gradians
+        } else if ast_root.instructions.contains("deg") {
+            AngleMode::Degrees
+        } else {
+            AngleMode::Radians
+        };
+
         let simplified_res = {
             let mut state = SimplificationState {
                 constants: get_canonical_constants(),
@@ -112,6 +124,7 @@ impl CalcPlugin {
                 units: config_copy.unit_database.clone(),
                 start_time: Instant::now(),
                 timeout: Duration::from_secs_f64(config_copy.timeout_seconds),
+                angle_mode,
             };
             ast_root.root_node.simplify(&mut state)
         };
@@ -174,6 +187,15 @@ impl CalcPlugin {
                                     )
                                 };
                                 format!("\\({}°{}'{}\\)", deg_string, min_string, i.units_to_tex())
+                            } else if ast_root.instructions.contains("si") {
+                                // output using the best-fitting SI prefix
+                                let si_i = format_with_best_si_prefix(i, &config_copy.unit_database);
+                                let num_string = if ast_root.instructions.contains("thou") {
+                                    si_i.value.to_tex_string_thou_sep()
+                                } else {
+                                    si_i.value.to_string()
+                                };
+                                format!("\\({}{}\\)", num_string, si_i.units_to_tex())
                             } else {
                                 // regular output
                                 let num_string = if ast_root.instructions.contains("thou") {
@@ -211,12 +233,21 @@ impl CalcPlugin {
                                 let deg = f64_val.trunc();
                                 let min = (f64_val - deg) * 60.0;
                                 format!("{}°{}'{}", deg, min, i.units_to_string())
+                            } else if ast_root.instructions.contains("si") {
+                                // output using the best-fitting SI prefix
+                                format_with_best_si_prefix(i, &config_copy.unit_database).to_string()
                             } else {
                                 // regular output
                                 i.to_string()
                             }
                         }
                     },
+                    AstNode::Tuple(ns) => {
+                        let parts: Vec<String> = ns.iter()
+                            .map(|n| n.to_string())
+                            .collect();
+                        format!("({})", parts.join(", "))
+                    },
                     other => {
                         error!("simplification produced invalid value: {:?}", other);
                         send_channel_message!(
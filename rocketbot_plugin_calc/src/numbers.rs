@@ -4,7 +4,8 @@ use std::fmt::{self, Write};
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign};
 
 use num_bigint::{BigInt, ToBigInt};
-use num_traits::{FromPrimitive, ToPrimitive};
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, ToPrimitive, Zero};
 
 use crate::units::{coerce_to_common_unit, NumberUnits, UnitDatabase};
 
@@ -15,124 +16,192 @@ trait WholeDiv {
 }
 
 
+/// Reduces `r` and collapses it to `NumberValue::Int` if it divides evenly, keeping `Rational`
+/// reserved for values that are genuinely fractional.
+pub(crate) fn rational_to_number_value(r: BigRational) -> NumberValue {
+    if r.is_integer() {
+        NumberValue::Int(r.to_integer())
+    } else {
+        NumberValue::Rational(r)
+    }
+}
+
+
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum NumberValue {
     Int(BigInt),
+    /// An exact fraction that isn't a whole number. Arithmetic promotes `Int` to `Rational` (and
+    /// reduces back down to `Int` whenever the result divides evenly), keeping decimal literals
+    /// and division exact until a `Float` operand forces a fallback to floating-point.
+    Rational(BigRational),
     Float(f64),
 }
 impl NumberValue {
-    fn bin_op<I, F, O>(
+    /// Converts `self` to a `BigRational`, losslessly for `Int` and `Rational`; returns `None`
+    /// for `Float`, since promoting a `Float` happens via [`Self::to_f64_checked`] instead.
+    fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            Self::Int(i) => Some(BigRational::from_integer(i.clone())),
+            Self::Rational(r) => Some(r.clone()),
+            Self::Float(_) => None,
+        }
+    }
+
+    /// Converts `self` to an `f64`, returning `None` only if a `BigInt`/`BigRational` value is
+    /// out of `f64`'s representable range.
+    fn to_f64_checked(&self) -> Option<f64> {
+        match self {
+            Self::Int(i) => i.to_f64(),
+            Self::Rational(r) => r.to_f64(),
+            Self::Float(f) => Some(*f),
+        }
+    }
+
+    /// Dispatches a binary operation to one of three closures depending on the operands' types,
+    /// implementing the `Int -> Rational -> Float` promotion: both operands integral uses
+    /// `int_op`, either operand floating-point uses `float_op` (coercing the other side to
+    /// `f64`), otherwise (at least one `Rational` operand, no `Float`) uses `rational_op`.
+    fn bin_op3<I, R, F, O>(
         &self,
         other: &Self,
         mut int_op: I,
+        mut rational_op: R,
         mut float_op: F,
     ) -> Option<O>
         where
             I: FnMut(&BigInt, &BigInt) -> Option<O>,
+            R: FnMut(&BigRational, &BigRational) -> Option<O>,
             F: FnMut(f64, f64) -> Option<O>,
     {
         match (self, other) {
             (Self::Int(s), Self::Int(o)) => {
                 int_op(s, o)
             },
-            (Self::Int(s), Self::Float(o)) => {
-                let s_f64: f64 = match s.to_f64() {
-                    Some(sf) => sf,
-                    None => return None,
-                };
-                float_op(s_f64, *o)
-            },
-            (Self::Float(s), Self::Int(o)) => {
-                let o_f64: f64 = match o.to_f64() {
-                    Some(of) => of,
-                    None => return None,
-                };
-                float_op(*s, o_f64)
+            (Self::Float(_), _) | (_, Self::Float(_)) => {
+                let s_f64 = self.to_f64_checked()?;
+                let o_f64 = other.to_f64_checked()?;
+                float_op(s_f64, o_f64)
             },
-            (Self::Float(s), Self::Float(o)) => {
-                float_op(*s, *o)
+            _ => {
+                let s_r = self.to_rational()?;
+                let o_r = other.to_rational()?;
+                rational_op(&s_r, &o_r)
             },
         }
     }
 
     pub fn checked_add(&self, rhs: Self) -> Option<Self> {
-        self.bin_op(
+        self.bin_op3(
             &rhs,
             |s, r| s.checked_add(r).map(|x| NumberValue::Int(x)),
+            |s, r| Some(rational_to_number_value(s.clone() + r.clone())),
             |s, r| Some(NumberValue::Float(s + r)),
         )
     }
 
     pub fn checked_bit_and(&self, rhs: Self) -> Option<Self> {
-        self.bin_op(
-            &rhs,
-            |s, r| Some(NumberValue::Int(s & r)),
-            |_s, _r| None,
-        )
+        match (self, &rhs) {
+            (Self::Int(s), Self::Int(o)) => Some(NumberValue::Int(s & o)),
+            _ => None,
+        }
     }
 
     pub fn checked_bit_or(&self, rhs: Self) -> Option<Self> {
-        self.bin_op(
-            &rhs,
-            |s, r| Some(NumberValue::Int(s | r)),
-            |_s, _r| None,
-        )
+        match (self, &rhs) {
+            (Self::Int(s), Self::Int(o)) => Some(NumberValue::Int(s | o)),
+            _ => None,
+        }
     }
 
     pub fn checked_bit_xor(&self, rhs: Self) -> Option<Self> {
-        self.bin_op(
-            &rhs,
-            |s, r| Some(NumberValue::Int(s ^ r)),
-            |_s, _r| None,
-        )
+        match (self, &rhs) {
+            (Self::Int(s), Self::Int(o)) => Some(NumberValue::Int(s ^ o)),
+            _ => None,
+        }
     }
 
     pub fn checked_sub(&self, rhs: Self) -> Option<Self> {
-        self.bin_op(
+        self.bin_op3(
             &rhs,
             |s, r| s.checked_sub(r).map(|x| NumberValue::Int(x)),
+            |s, r| Some(rational_to_number_value(s.clone() - r.clone())),
             |s, r| Some(NumberValue::Float(s - r)),
         )
     }
 
     pub fn checked_mul(&self, rhs: Self) -> Option<Self> {
-        self.bin_op(
+        self.bin_op3(
             &rhs,
             |s, r| s.checked_mul(r).map(|x| NumberValue::Int(x)),
+            |s, r| Some(rational_to_number_value(s.clone() * r.clone())),
             |s, r| Some(NumberValue::Float(s * r)),
         )
     }
 
     pub fn checked_div(&self, rhs: Self) -> Option<Self> {
-        // coerce to f64
-        let s_f64: f64 = match self {
-            Self::Int(s) => s.to_f64()?,
-            Self::Float(s) => *s,
-        };
-        let r_f64: f64 = match rhs {
-            Self::Int(r) => r.to_f64()?,
-            Self::Float(r) => r,
-        };
-        Some(NumberValue::Float(s_f64 / r_f64))
+        self.bin_op3(
+            &rhs,
+            |s, o| {
+                if o.is_zero() {
+                    return None;
+                }
+                Some(rational_to_number_value(BigRational::new(s.clone(), o.clone())))
+            },
+            |s, o| {
+                if o.is_zero() {
+                    return None;
+                }
+                Some(rational_to_number_value(s.clone() / o.clone()))
+            },
+            |s, r| Some(NumberValue::Float(s / r)),
+        )
     }
 
     pub fn checked_whole_div(&self, rhs: Self) -> Option<Self> {
-        // coerce to BigInt
-        let s_bi: BigInt = match self {
-            Self::Int(s) => s.clone(),
-            Self::Float(s) => s.to_bigint()?,
-        };
-        let r_bi: BigInt = match rhs {
-            Self::Int(r) => r,
-            Self::Float(r) => r.to_bigint()?,
-        };
-        Some(NumberValue::Int(s_bi / r_bi))
+        self.bin_op3(
+            &rhs,
+            |s, o| {
+                if o.is_zero() {
+                    return None;
+                }
+                Some(NumberValue::Int(s / o))
+            },
+            |s, o| {
+                if o.is_zero() {
+                    return None;
+                }
+                let quotient = s.clone() / o.clone();
+                Some(NumberValue::Int(quotient.floor().to_integer()))
+            },
+            |s, o| {
+                // coerce to BigInt the way the original float//float path did
+                let s_bi = BigInt::from_f64(s)?;
+                let o_bi = BigInt::from_f64(o)?;
+                if o_bi.is_zero() {
+                    return None;
+                }
+                Some(NumberValue::Int(s_bi / o_bi))
+            },
+        )
     }
 
     pub fn checked_rem(&self, rhs: Self) -> Option<Self> {
-        self.bin_op(
+        self.bin_op3(
             &rhs,
-            |s, o| Some(NumberValue::Int(s % o)),
+            |s, o| {
+                if o.is_zero() {
+                    return None;
+                }
+                Some(NumberValue::Int(s % o))
+            },
+            |s, o| {
+                if o.is_zero() {
+                    return None;
+                }
+                // floored modulo: s - o * floor(s / o)
+                let quotient_floor = (s.clone() / o.clone()).floor();
+                Some(rational_to_number_value(s.clone() - quotient_floor * o.clone()))
+            },
             |s, o| Some(NumberValue::Float(s % o)),
         )
     }
@@ -140,6 +209,7 @@ impl NumberValue {
     pub fn to_f64(&self) -> f64 {
         match self {
             Self::Int(i) => i.to_f64().unwrap(),
+            Self::Rational(r) => r.to_f64().unwrap(),
             Self::Float(f) => *f,
         }
     }
@@ -147,6 +217,7 @@ impl NumberValue {
     pub fn to_int_trunc(&self) -> Self {
         match self {
             Self::Int(i) => Self::Int(i.clone()),
+            Self::Rational(r) => Self::Int(r.to_integer()),
             Self::Float(f) => match BigInt::from_f64(f.trunc()) {
                 Some(n) => Self::Int(n),
                 None => Self::Float(*f), // conversion failed
@@ -209,9 +280,10 @@ impl NumberValue {
 }
 impl PartialOrd for NumberValue {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.bin_op(
+        self.bin_op3(
             other,
             |s, o| s.partial_cmp(o),
+            |s, o| s.partial_cmp(o),
             |s, o| s.partial_cmp(&o),
         )
     }
@@ -258,6 +330,7 @@ impl Neg for NumberValue {
     fn neg(self) -> Self::Output {
         match self {
             Self::Int(s) => Self::Int(-s),
+            Self::Rational(s) => Self::Rational(-s),
             Self::Float(s) => Self::Float(-s),
         }
     }
@@ -297,6 +370,7 @@ impl fmt::Display for NumberValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Int(v) => fmt::Display::fmt(v, f),
+            Self::Rational(v) => fmt::Display::fmt(v, f),
             Self::Float(v) => fmt::Display::fmt(v, f),
         }
     }
@@ -305,6 +379,7 @@ impl fmt::LowerHex for NumberValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Int(v) => fmt::LowerHex::fmt(v, f),
+            Self::Rational(v) => fmt::Display::fmt(v, f), // alas
             Self::Float(v) => fmt::Display::fmt(v, f), // alas
         }
     }
@@ -313,6 +388,7 @@ impl fmt::UpperHex for NumberValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Int(v) => fmt::UpperHex::fmt(v, f),
+            Self::Rational(v) => fmt::Display::fmt(v, f), // alas
             Self::Float(v) => fmt::Display::fmt(v, f), // alas
         }
     }
@@ -321,6 +397,7 @@ impl fmt::Binary for NumberValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Int(v) => fmt::Binary::fmt(v, f),
+            Self::Rational(v) => fmt::Display::fmt(v, f), // alas
             Self::Float(v) => fmt::Display::fmt(v, f), // alas
         }
     }
@@ -329,6 +406,7 @@ impl fmt::Octal for NumberValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Int(v) => fmt::Octal::fmt(v, f),
+            Self::Rational(v) => fmt::Display::fmt(v, f), // alas
             Self::Float(v) => fmt::Display::fmt(v, f), // alas
         }
     }
@@ -378,6 +456,15 @@ impl Number {
         }
     }
 
+    pub fn checked_partial_cmp(&self, rhs: Self, database: &UnitDatabase) -> Result<Option<Ordering>, NumberOperationError> {
+        // coerce to same unit
+        let (self_co, rhs_co) = coerce_to_common_unit(&self, &rhs, database)
+            .ok_or(NumberOperationError::UnitReconciliation)?;
+        debug_assert_eq!(self_co.units, rhs_co.units);
+
+        Ok(self_co.value.partial_cmp(&rhs_co.value))
+    }
+
     pub fn checked_add(&self, rhs: Self, database: &UnitDatabase) -> Result<Self, NumberOperationError> {
         // coerce to same unit
         let (self_co, rhs_co) = coerce_to_common_unit(&self, &rhs, database)
@@ -433,7 +520,7 @@ impl Number {
         Ok(Number::new(new_value, self_co.units))
     }
 
-    fn addsub_units<F: FnMut(BigInt) -> BigInt>(lhs_units: &NumberUnits, rhs_units: NumberUnits, mut transform_rhs: F) -> NumberUnits {
+    pub(crate) fn addsub_units<F: FnMut(BigInt) -> BigInt>(lhs_units: &NumberUnits, rhs_units: NumberUnits, mut transform_rhs: F) -> NumberUnits {
         let mut new_units = NumberUnits::new();
         for (self_unit, self_pow) in lhs_units {
             if let Some(rhs_pow) = rhs_units.get(self_unit) {
@@ -0,0 +1,127 @@
+//! Floating-point primitives used by [`crate::grimoire`].
+//!
+//! By default, these simply forward to the standard library's `f64` methods, whose precision is
+//! unspecified and may vary between platforms and Rust versions. With the `libm` feature enabled,
+//! they instead forward to the `libm` crate's pure-Rust, platform-independent implementations, so
+//! that the calculator produces bit-identical output regardless of the machine it runs on.
+
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn sin(x: f64) -> f64 { x.sin() }
+#[cfg(feature = "libm")]
+pub(crate) fn sin(x: f64) -> f64 { libm::sin(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn cos(x: f64) -> f64 { x.cos() }
+#[cfg(feature = "libm")]
+pub(crate) fn cos(x: f64) -> f64 { libm::cos(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn tan(x: f64) -> f64 { x.tan() }
+#[cfg(feature = "libm")]
+pub(crate) fn tan(x: f64) -> f64 { libm::tan(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn asin(x: f64) -> f64 { x.asin() }
+#[cfg(feature = "libm")]
+pub(crate) fn asin(x: f64) -> f64 { libm::asin(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn acos(x: f64) -> f64 { x.acos() }
+#[cfg(feature = "libm")]
+pub(crate) fn acos(x: f64) -> f64 { libm::acos(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn atan(x: f64) -> f64 { x.atan() }
+#[cfg(feature = "libm")]
+pub(crate) fn atan(x: f64) -> f64 { libm::atan(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 { y.atan2(x) }
+#[cfg(feature = "libm")]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 { libm::atan2(y, x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn sqrt(x: f64) -> f64 { x.sqrt() }
+#[cfg(feature = "libm")]
+pub(crate) fn sqrt(x: f64) -> f64 { libm::sqrt(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn cbrt(x: f64) -> f64 { x.cbrt() }
+#[cfg(feature = "libm")]
+pub(crate) fn cbrt(x: f64) -> f64 { libm::cbrt(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn ln(x: f64) -> f64 { x.ln() }
+#[cfg(feature = "libm")]
+pub(crate) fn ln(x: f64) -> f64 { libm::log(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn exp(x: f64) -> f64 { x.exp() }
+#[cfg(feature = "libm")]
+pub(crate) fn exp(x: f64) -> f64 { libm::exp(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn log10(x: f64) -> f64 { x.log10() }
+#[cfg(feature = "libm")]
+pub(crate) fn log10(x: f64) -> f64 { libm::log10(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn to_radians(x: f64) -> f64 { x.to_radians() }
+#[cfg(feature = "libm")]
+pub(crate) fn to_radians(x: f64) -> f64 { x * (std::f64::consts::PI / 180.0) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn to_degrees(x: f64) -> f64 { x.to_degrees() }
+#[cfg(feature = "libm")]
+pub(crate) fn to_degrees(x: f64) -> f64 { x * (180.0 / std::f64::consts::PI) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn floor(x: f64) -> f64 { x.floor() }
+#[cfg(feature = "libm")]
+pub(crate) fn floor(x: f64) -> f64 { libm::floor(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn ceil(x: f64) -> f64 { x.ceil() }
+#[cfg(feature = "libm")]
+pub(crate) fn ceil(x: f64) -> f64 { libm::ceil(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn round(x: f64) -> f64 { x.round() }
+#[cfg(feature = "libm")]
+pub(crate) fn round(x: f64) -> f64 { libm::round(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn trunc(x: f64) -> f64 { x.trunc() }
+#[cfg(feature = "libm")]
+pub(crate) fn trunc(x: f64) -> f64 { libm::trunc(x) }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn recip(x: f64) -> f64 { x.recip() }
+#[cfg(feature = "libm")]
+pub(crate) fn recip(x: f64) -> f64 { 1.0 / x }
+
+#[cfg(not(feature = "libm"))]
+pub(crate) fn powf(x: f64, y: f64) -> f64 { x.powf(y) }
+#[cfg(feature = "libm")]
+pub(crate) fn powf(x: f64, y: f64) -> f64 { libm::pow(x, y) }
+
+/// Raises `x` to the integer power `n` using repeated multiplication, since `libm` has no
+/// dedicated integer-power function.
+pub(crate) fn powi(x: f64, n: i32) -> f64 {
+    if n < 0 {
+        return recip(powi(x, -n));
+    }
+
+    let mut result = 1.0;
+    let mut base = x;
+    let mut exponent = n as u32;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result *= base;
+        }
+        base *= base;
+        exponent >>= 1;
+    }
+    result
+}
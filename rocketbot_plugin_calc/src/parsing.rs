@@ -2,6 +2,7 @@ use std::collections::VecDeque;
 
 use log::trace;
 use num_bigint::BigInt;
+use num_rational::BigRational;
 use pest::Parser;
 use pest::error::Error;
 use pest::iterators::{Pair, Pairs};
@@ -185,13 +186,14 @@ fn parse_atom_expression(pair: &Pair<'_, Rule>) -> AstNodeAtLocation {
         },
         Rule::decimal_expression => {
             let mut innerer = child.into_inner();
-            let float: f64 = innerer
-                .next().expect("missing float")
-                .as_str().parse().expect("failed to parse decimal expression");
+            let decimal_text = innerer
+                .next().expect("missing decimal")
+                .as_str();
+            let value = parse_decimal_exact(decimal_text);
             let units = parse_unit_suffixes(innerer);
             AstNodeAtLocation {
                 node: AstNode::Number(Number::new(
-                    NumberValue::Float(float),
+                    value,
                     units,
                 )),
                 start_end: Some((pair.as_span().start(), pair.as_span().end())),
@@ -201,6 +203,31 @@ fn parse_atom_expression(pair: &Pair<'_, Rule>) -> AstNodeAtLocation {
     }
 }
 
+/// Parses a decimal literal (e.g. `"123.45"`) exactly rather than through `f64`: the integer and
+/// fractional digit groups become one `BigInt` numerator, the fractional digit count determines
+/// the power-of-ten denominator, and the resulting fraction is reduced, falling back to
+/// `NumberValue::Int` when it divides evenly (e.g. `"1.0"`).
+fn parse_decimal_exact(text: &str) -> NumberValue {
+    let (whole_part, fractional_part) = text.split_once('.')
+        .expect("decimal literal without a decimal point");
+
+    let digits = format!("{}{}", whole_part, fractional_part);
+    let numerator: BigInt = digits.parse().expect("failed to parse decimal digits");
+
+    let ten = BigInt::from(10);
+    let mut denominator = BigInt::from(1);
+    for _ in 0..fractional_part.len() {
+        denominator *= &ten;
+    }
+
+    let rational = BigRational::new(numerator, denominator);
+    if rational.is_integer() {
+        NumberValue::Int(rational.to_integer())
+    } else {
+        NumberValue::Rational(rational)
+    }
+}
+
 fn parse_unit_suffixes(mut pairs: Pairs<'_, Rule>) -> NumberUnits {
     trace!("parse_unit_suffixes: {:?}", pairs);
     let mut number_units = NumberUnits::new();
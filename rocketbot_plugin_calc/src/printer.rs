@@ -0,0 +1,183 @@
+use std::fmt::Write;
+
+use crate::ast::{AstNode, AstNodeAtLocation, BinaryOperation, UnaryOperation};
+
+
+/// Precedence ranks mirroring the parse levels in `parsing.rs`, from loosest-binding to
+/// tightest-binding.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+enum PrecedenceRank {
+    BinaryOr,
+    BinaryXor,
+    BinaryAnd,
+    AddSubtract,
+    MulDivRem,
+    Power,
+    Negate,
+    Factorial,
+    Atom,
+}
+
+fn binary_operation_rank(op: BinaryOperation) -> PrecedenceRank {
+    match op {
+        BinaryOperation::BinaryOr => PrecedenceRank::BinaryOr,
+        BinaryOperation::BinaryXor => PrecedenceRank::BinaryXor,
+        BinaryOperation::BinaryAnd => PrecedenceRank::BinaryAnd,
+        BinaryOperation::Add | BinaryOperation::Subtract => PrecedenceRank::AddSubtract,
+        BinaryOperation::Multiply | BinaryOperation::Divide
+            | BinaryOperation::DivideIntegral | BinaryOperation::Remainder => PrecedenceRank::MulDivRem,
+        BinaryOperation::Power => PrecedenceRank::Power,
+    }
+}
+
+fn binary_operation_text(op: BinaryOperation) -> &'static str {
+    match op {
+        BinaryOperation::Power => "**",
+        BinaryOperation::Multiply => "*",
+        BinaryOperation::Divide => "/",
+        BinaryOperation::DivideIntegral => "//",
+        BinaryOperation::Remainder => "%",
+        BinaryOperation::Add => "+",
+        BinaryOperation::Subtract => "-",
+        BinaryOperation::BinaryAnd => "&",
+        BinaryOperation::BinaryOr => "|",
+        BinaryOperation::BinaryXor => "^",
+    }
+}
+
+fn node_rank(node: &AstNode) -> PrecedenceRank {
+    match node {
+        AstNode::Number(_) | AstNode::Constant(_) | AstNode::FunctionCall(_, _) | AstNode::Tuple(_) => PrecedenceRank::Atom,
+        AstNode::BinaryOperation(op, _, _) => binary_operation_rank(*op),
+        AstNode::UnaryOperation(UnaryOperation::Negate, _) => PrecedenceRank::Negate,
+        AstNode::UnaryOperation(UnaryOperation::Factorial, _) => PrecedenceRank::Factorial,
+    }
+}
+
+/// Renders `node` back to canonical calc-language source text, inserting the minimum parentheses
+/// necessary to preserve its structure.
+pub(crate) fn print_expression(node: &AstNodeAtLocation) -> String {
+    let mut output = String::new();
+    write_node(&mut output, node, None);
+    output
+}
+
+/// Writes `node`'s rendering to `output`. `context` is `Some((parent_rank, force_at_equal_rank))`
+/// when `node` is a direct operand of another operation; `force_at_equal_rank` additionally
+/// parenthesizes a same-rank child, needed to preserve non-associative operators like `-`, `/`,
+/// `%` (on their right operand) and `**` (on its left operand, since it is right-associative).
+fn write_node(output: &mut String, node: &AstNodeAtLocation, context: Option<(PrecedenceRank, bool)>) {
+    let rank = node_rank(&node.node);
+    let needs_parens = match context {
+        None => false,
+        Some((parent_rank, force_at_equal_rank)) => {
+            rank < parent_rank || (force_at_equal_rank && rank == parent_rank)
+        },
+    };
+
+    if needs_parens {
+        output.push('(');
+    }
+
+    match &node.node {
+        AstNode::Number(n) => write!(output, "{}", n).unwrap(),
+        AstNode::Tuple(ns) => {
+            output.push('(');
+            for (i, n) in ns.iter().enumerate() {
+                if i > 0 {
+                    output.push_str(", ");
+                }
+                write!(output, "{}", n).unwrap();
+            }
+            output.push(')');
+        },
+        AstNode::Constant(name) => output.push_str(name),
+        AstNode::FunctionCall(name, args) => {
+            write!(output, "{}(", name).unwrap();
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    output.push_str(", ");
+                }
+                write_node(output, arg, None);
+            }
+            output.push(')');
+        },
+        AstNode::BinaryOperation(op, left, right) => {
+            let op_rank = binary_operation_rank(*op);
+            let left_force_equal = matches!(op, BinaryOperation::Power);
+            let right_force_equal = matches!(
+                op,
+                BinaryOperation::Subtract | BinaryOperation::Divide | BinaryOperation::Remainder,
+            );
+
+            write_node(output, left, Some((op_rank, left_force_equal)));
+            write!(output, " {} ", binary_operation_text(*op)).unwrap();
+            write_node(output, right, Some((op_rank, right_force_equal)));
+        },
+        AstNode::UnaryOperation(UnaryOperation::Negate, operand) => {
+            output.push('-');
+            write_node(output, operand, Some((PrecedenceRank::Negate, false)));
+        },
+        AstNode::UnaryOperation(UnaryOperation::Factorial, operand) => {
+            write_node(output, operand, Some((PrecedenceRank::Factorial, false)));
+            output.push('!');
+        },
+    }
+
+    if needs_parens {
+        output.push(')');
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_full_expression;
+
+    fn run_test(expected: &str, parse_me: &str) {
+        let parsed = parse_full_expression(parse_me).unwrap();
+        let printed = print_expression(&parsed.root_node);
+        assert_eq!(expected, printed);
+    }
+
+    #[test]
+    fn test_print_precedence_mul_add() {
+        run_test("2 * 3 + 4", "2 * 3 + 4");
+        run_test("2 + 3 * 4", "2 + 3 * 4");
+        run_test("(2 + 3) * 4", "(2 + 3) * 4");
+    }
+
+    #[test]
+    fn test_print_associativity_sub() {
+        run_test("7 - 4 - 1", "7 - 4 - 1");
+        run_test("7 - (4 - 1)", "7 - (4 - 1)");
+    }
+
+    #[test]
+    fn test_print_associativity_pow() {
+        run_test("2 ** 3 ** 3", "2**3**3");
+        run_test("(2 ** 3) ** 3", "(2**3)**3");
+    }
+
+    #[test]
+    fn test_print_function_call() {
+        run_test("sqrt(2 + 3)", "sqrt(2 + 3)");
+    }
+
+    #[test]
+    fn test_print_units() {
+        run_test("123#W", "123#W");
+    }
+
+    #[test]
+    fn test_print_negate_factorial() {
+        run_test("-2!", "-2!");
+        run_test("-(2 + 3)", "-(2 + 3)");
+    }
+
+    #[test]
+    fn test_print_constant() {
+        run_test("pi", "pi");
+    }
+}
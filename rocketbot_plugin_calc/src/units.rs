@@ -69,6 +69,7 @@ impl std::error::Error for UnitDatabaseError {
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct UnitDatabase {
     si_prefix_to_factor: HashMap<String, f64>,
+    iec_prefix_to_factor: HashMap<String, f64>,
     letters_to_base_unit: HashMap<String, BaseUnit>,
     letters_to_derived_unit: HashMap<String, DerivedUnit>,
     letters_to_max_depth: HashMap<String, usize>,
@@ -77,6 +78,7 @@ impl UnitDatabase {
     pub fn new_empty() -> Self {
         Self {
             si_prefix_to_factor: HashMap::new(),
+            iec_prefix_to_factor: HashMap::new(),
             letters_to_base_unit: HashMap::new(),
             letters_to_derived_unit: HashMap::new(),
             letters_to_max_depth: HashMap::new(),
@@ -114,6 +116,24 @@ impl UnitDatabase {
         self.si_prefix_to_factor.insert("q".to_owned(), 1e-30);
     }
 
+    /// Registers the IEC binary prefixes (`Ki`, `Mi`, `Gi`, `Ti`, `Pi`), each a power of 1024.
+    /// Because they all start with an uppercase letter followed by a lowercase `i`, none collides
+    /// with a canonical SI prefix (which are either lowercase or, for the large ones, a single
+    /// uppercase letter with no trailing `i`), so `KiB` is never mistaken for an SI-prefixed unit.
+    pub fn insert_canonical_iec_prefixes(&mut self) {
+        self.iec_prefix_to_factor.insert("Ki".to_owned(), 1024f64.powi(1));
+        self.iec_prefix_to_factor.insert("Mi".to_owned(), 1024f64.powi(2));
+        self.iec_prefix_to_factor.insert("Gi".to_owned(), 1024f64.powi(3));
+        self.iec_prefix_to_factor.insert("Ti".to_owned(), 1024f64.powi(4));
+        self.iec_prefix_to_factor.insert("Pi".to_owned(), 1024f64.powi(5));
+    }
+
+    /// Registers the base unit `B` (byte), so that `B` itself and its SI- and IEC-prefixed forms
+    /// (`kB`, `KiB`, `MB`, `MiB`, ...) are recognized and freely convertible between each other.
+    pub fn insert_canonical_byte_unit(&mut self) -> Result<(), UnitDatabaseError> {
+        self.register_base_unit(BaseUnit::new("B".to_owned()))
+    }
+
     pub fn get_base_unit(&self, letters: &str) -> Option<BaseUnit> {
         self.letters_to_base_unit.get(letters)
             .map(|bu| bu.clone())
@@ -126,13 +146,13 @@ impl UnitDatabase {
             return Some(du.clone());
         }
 
-        // try applying SI prefixes
-        for (si_pfx, ten_pow) in &self.si_prefix_to_factor {
-            if !letters.starts_with(si_pfx) {
+        // try applying SI and IEC prefixes
+        for (pfx, factor) in self.si_prefix_to_factor.iter().chain(self.iec_prefix_to_factor.iter()) {
+            if !letters.starts_with(pfx) {
                 continue;
             }
 
-            let non_prefix_unit = &letters[si_pfx.len()..];
+            let non_prefix_unit = &letters[pfx.len()..];
             // don't allow multiprefix units (otherwise replace with recursive call)
             if self.letters_to_derived_unit.contains_key(non_prefix_unit) || self.letters_to_base_unit.contains_key(non_prefix_unit) {
                 // perfect
@@ -143,7 +163,7 @@ impl UnitDatabase {
                 let synth_derived_unit = DerivedUnit::new(
                     letters.to_owned(),
                     synth_parents,
-                    *ten_pow,
+                    *factor,
                 );
                 return Some(synth_derived_unit);
             }
@@ -152,6 +172,71 @@ impl UnitDatabase {
         None
     }
 
+    /// Attempts to split `letters` into a leading SI prefix and the unprefixed unit it modifies,
+    /// returning `(prefix_symbol, unprefixed_letters, prefix_factor)`. If `letters` is itself a
+    /// registered base or derived unit (carrying no prefix) or no prefix can be identified, an
+    /// empty prefix and a factor of `1.0` are returned.
+    pub fn decompose_si_prefix(&self, letters: &str) -> (String, String, f64) {
+        if self.letters_to_base_unit.contains_key(letters) || self.letters_to_derived_unit.contains_key(letters) {
+            return (String::new(), letters.to_owned(), 1.0);
+        }
+
+        for (si_pfx, factor) in &self.si_prefix_to_factor {
+            if let Some(rest) = letters.strip_prefix(si_pfx.as_str()) {
+                if self.letters_to_base_unit.contains_key(rest) || self.letters_to_derived_unit.contains_key(rest) {
+                    return (si_pfx.clone(), rest.to_owned(), *factor);
+                }
+            }
+        }
+
+        (String::new(), letters.to_owned(), 1.0)
+    }
+
+    /// Finds the SI prefix symbol whose factor equals `10^exponent`. Returns `None` if
+    /// `exponent` is `0` (no prefix needed) or no registered prefix matches.
+    pub fn si_prefix_for_exponent(&self, exponent: i32) -> Option<String> {
+        if exponent == 0 {
+            return None;
+        }
+
+        let target = 10f64.powi(exponent);
+        self.si_prefix_to_factor.iter()
+            .find(|(_letters, factor)| (**factor - target).abs() < target.abs() * 1e-9)
+            .map(|(letters, _factor)| letters.clone())
+    }
+
+    /// Attempts to split `letters` into a leading IEC binary prefix and the unprefixed unit it
+    /// modifies, returning `(prefix_symbol, unprefixed_letters, prefix_factor)`. If `letters`
+    /// carries no recognized IEC prefix, an empty prefix and a factor of `1.0` are returned.
+    pub fn decompose_iec_prefix(&self, letters: &str) -> (String, String, f64) {
+        if self.letters_to_base_unit.contains_key(letters) || self.letters_to_derived_unit.contains_key(letters) {
+            return (String::new(), letters.to_owned(), 1.0);
+        }
+
+        for (iec_pfx, factor) in &self.iec_prefix_to_factor {
+            if let Some(rest) = letters.strip_prefix(iec_pfx.as_str()) {
+                if self.letters_to_base_unit.contains_key(rest) || self.letters_to_derived_unit.contains_key(rest) {
+                    return (iec_pfx.clone(), rest.to_owned(), *factor);
+                }
+            }
+        }
+
+        (String::new(), letters.to_owned(), 1.0)
+    }
+
+    /// Finds the IEC prefix symbol whose factor equals `1024^order`. Returns `None` if `order`
+    /// is `0` (no prefix needed) or no registered prefix matches.
+    pub fn iec_prefix_for_order(&self, order: u32) -> Option<String> {
+        if order == 0 {
+            return None;
+        }
+
+        let target = 1024f64.powi(order as i32);
+        self.iec_prefix_to_factor.iter()
+            .find(|(_letters, factor)| (**factor - target).abs() < target * 1e-9)
+            .map(|(letters, _factor)| letters.clone())
+    }
+
     pub fn get_max_depth(&self, letters: &str) -> Option<usize> {
         // does it exist?
         if let Some(d) = self.letters_to_max_depth.get(letters).map(|s| *s) {
@@ -159,10 +244,10 @@ impl UnitDatabase {
             return Some(d);
         }
 
-        // try applying SI prefixes
-        for (si_pfx, _ten_pow) in &self.si_prefix_to_factor {
-            if letters.starts_with(si_pfx) {
-                let non_prefix_unit = &letters[si_pfx.len()..];
+        // try applying SI and IEC prefixes
+        for pfx in self.si_prefix_to_factor.keys().chain(self.iec_prefix_to_factor.keys()) {
+            if letters.starts_with(pfx) {
+                let non_prefix_unit = &letters[pfx.len()..];
                 // don't allow multiprefix units (otherwise replace with recursive call)
                 if let Some(md) = self.letters_to_max_depth.get(non_prefix_unit) {
                     return Some(*md + 1);
@@ -247,6 +332,8 @@ impl StoredUnitDatabase {
     pub fn to_unit_database(&self) -> Result<UnitDatabase, UnitDatabaseError> {
         let mut unit_db = UnitDatabase::new_empty();
         unit_db.insert_canonical_si_prefixes();
+        unit_db.insert_canonical_iec_prefixes();
+        unit_db.insert_canonical_byte_unit()?;
 
         for base_unit in &self.base_units {
             unit_db.register_base_unit(base_unit.clone())?;
@@ -0,0 +1,228 @@
+use num_bigint::BigInt;
+
+use crate::ast::{AstNode, AstNodeAtLocation, BinaryOperation, UnaryOperation};
+use crate::numbers::{Number, NumberValue};
+use crate::units::{NumberUnits, UnitDatabase};
+
+
+/// Transforms an `AstNodeAtLocation` tree, one node at a time. Implementors only need to provide
+/// `rewrite`; the default `walk` recurses into every child of a node (preserving each node's own
+/// `start_end` span) and is the natural building block for a `rewrite` that first simplifies a
+/// node's children and then considers simplifying the node itself.
+pub(crate) trait AstRewriter {
+    fn rewrite(&mut self, node: AstNodeAtLocation) -> AstNodeAtLocation {
+        self.walk(node)
+    }
+
+    /// Rewrites every child of `node` and reassembles it, without attempting to transform `node`
+    /// itself.
+    fn walk(&mut self, node: AstNodeAtLocation) -> AstNodeAtLocation {
+        let AstNodeAtLocation { node: inner, start_end } = node;
+
+        let rewritten = match inner {
+            AstNode::Number(_) | AstNode::Constant(_) | AstNode::Tuple(_) => inner,
+            AstNode::FunctionCall(name, args) => {
+                let new_args = args.into_iter()
+                    .map(|arg| self.rewrite(arg))
+                    .collect();
+                AstNode::FunctionCall(name, new_args)
+            },
+            AstNode::BinaryOperation(op, left, right) => {
+                let new_left = Box::new(self.rewrite(*left));
+                let new_right = Box::new(self.rewrite(*right));
+                AstNode::BinaryOperation(op, new_left, new_right)
+            },
+            AstNode::UnaryOperation(op, operand) => {
+                let new_operand = Box::new(self.rewrite(*operand));
+                AstNode::UnaryOperation(op, new_operand)
+            },
+        };
+
+        AstNodeAtLocation { node: rewritten, start_end }
+    }
+}
+
+
+/// Evaluates a binary operation over two literal numbers, reusing `Number`'s checked arithmetic
+/// (which respects units) the same way `ast.rs` does when simplifying a fully-literal node.
+///
+/// `Power` is left unfolded: computing it requires a timeout-guarded loop over an arbitrarily
+/// large exponent (see `ast::pow`), which doesn't belong in an always-on optimization pass.
+fn fold_binary(op: BinaryOperation, left: &Number, right: &Number, units: &UnitDatabase) -> Option<Number> {
+    match op {
+        BinaryOperation::Add => left.checked_add(right.clone(), units).ok(),
+        BinaryOperation::Subtract => left.checked_sub(right.clone(), units).ok(),
+        BinaryOperation::Multiply => left.checked_mul(right.clone()).ok(),
+        BinaryOperation::Divide => left.checked_div(right.clone()).ok(),
+        BinaryOperation::DivideIntegral => left.checked_whole_div(right.clone()).ok(),
+        BinaryOperation::Remainder => left.checked_rem(right.clone()).ok(),
+        BinaryOperation::BinaryAnd => left.checked_bit_and(right.clone(), units).ok(),
+        BinaryOperation::BinaryOr => left.checked_bit_or(right.clone(), units).ok(),
+        BinaryOperation::BinaryXor => left.checked_bit_xor(right.clone(), units).ok(),
+        BinaryOperation::Power => None,
+    }
+}
+
+/// Folds subtrees whose operands are all literal [`Number`]s into a single `Number` node.
+pub(crate) struct ConstantFolder<'u> {
+    units: &'u UnitDatabase,
+}
+impl<'u> ConstantFolder<'u> {
+    pub(crate) fn new(units: &'u UnitDatabase) -> Self {
+        Self { units }
+    }
+}
+impl<'u> AstRewriter for ConstantFolder<'u> {
+    fn rewrite(&mut self, node: AstNodeAtLocation) -> AstNodeAtLocation {
+        let walked = self.walk(node);
+        let start_end = walked.start_end;
+
+        let folded = match &walked.node {
+            AstNode::BinaryOperation(op, left, right) => match (&left.node, &right.node) {
+                (AstNode::Number(l), AstNode::Number(r)) => fold_binary(*op, l, r, self.units),
+                _ => None,
+            },
+            AstNode::UnaryOperation(UnaryOperation::Negate, operand) => match &operand.node {
+                AstNode::Number(n) => Some(n.negated()),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        match folded {
+            Some(number) => AstNodeAtLocation { node: AstNode::Number(number), start_end },
+            None => walked,
+        }
+    }
+}
+
+
+/// Returns whether `node` is a unitless literal number equal to `target`.
+fn is_literal_value(node: &AstNode, target: i64) -> bool {
+    match node {
+        AstNode::Number(n) if n.units.len() == 0 => match &n.value {
+            NumberValue::Int(i) => i == &BigInt::from(target),
+            // a live `Rational` is always non-integral: `rational_to_number_value` collapses
+            // any rational that divides evenly back down to `Int`.
+            NumberValue::Rational(_) => false,
+            NumberValue::Float(f) => *f == target as f64,
+        },
+        _ => false,
+    }
+}
+
+/// Applies identities that don't require evaluating any operand: `x+0`, `x*1`, `x*0`, `x^1`,
+/// `x^0`, and double negation.
+pub(crate) struct AlgebraicSimplifier;
+impl AstRewriter for AlgebraicSimplifier {
+    fn rewrite(&mut self, node: AstNodeAtLocation) -> AstNodeAtLocation {
+        let walked = self.walk(node);
+        let start_end = walked.start_end;
+
+        match walked.node {
+            AstNode::BinaryOperation(BinaryOperation::Add, left, right) => {
+                if is_literal_value(&left.node, 0) {
+                    return *right;
+                }
+                if is_literal_value(&right.node, 0) {
+                    return *left;
+                }
+                AstNodeAtLocation { node: AstNode::BinaryOperation(BinaryOperation::Add, left, right), start_end }
+            },
+            AstNode::BinaryOperation(BinaryOperation::Multiply, left, right) => {
+                if is_literal_value(&left.node, 0) || is_literal_value(&right.node, 0) {
+                    let zero = Number::new(NumberValue::Int(BigInt::from(0)), NumberUnits::new());
+                    return AstNodeAtLocation { node: AstNode::Number(zero), start_end };
+                }
+                if is_literal_value(&left.node, 1) {
+                    return *right;
+                }
+                if is_literal_value(&right.node, 1) {
+                    return *left;
+                }
+                AstNodeAtLocation { node: AstNode::BinaryOperation(BinaryOperation::Multiply, left, right), start_end }
+            },
+            AstNode::BinaryOperation(BinaryOperation::Power, left, right) => {
+                if is_literal_value(&right.node, 0) {
+                    let one = Number::new(NumberValue::Int(BigInt::from(1)), NumberUnits::new());
+                    return AstNodeAtLocation { node: AstNode::Number(one), start_end };
+                }
+                if is_literal_value(&right.node, 1) {
+                    return *left;
+                }
+                AstNodeAtLocation { node: AstNode::BinaryOperation(BinaryOperation::Power, left, right), start_end }
+            },
+            AstNode::UnaryOperation(UnaryOperation::Negate, operand) => {
+                if let AstNode::UnaryOperation(UnaryOperation::Negate, inner) = operand.node {
+                    return *inner;
+                }
+                AstNodeAtLocation { node: AstNode::UnaryOperation(UnaryOperation::Negate, operand), start_end }
+            },
+            other => AstNodeAtLocation { node: other, start_end },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsing::parse_full_expression;
+    use crate::printer::print_expression;
+
+    fn run_fold_test(expected: &str, parse_me: &str) {
+        let parsed = parse_full_expression(parse_me).unwrap();
+        let units = UnitDatabase::new_empty();
+        let mut folder = ConstantFolder::new(&units);
+        let folded = folder.rewrite(parsed.root_node);
+        assert_eq!(expected, print_expression(&folded));
+    }
+
+    fn run_simplify_test(expected: &str, parse_me: &str) {
+        let parsed = parse_full_expression(parse_me).unwrap();
+        let mut simplifier = AlgebraicSimplifier;
+        let simplified = simplifier.rewrite(parsed.root_node);
+        assert_eq!(expected, print_expression(&simplified));
+    }
+
+    #[test]
+    fn test_fold_arithmetic() {
+        run_fold_test("7", "3 + 4");
+        run_fold_test("10", "2 * (3 + 2)");
+    }
+
+    #[test]
+    fn test_fold_leaves_power() {
+        run_fold_test("2 ** 3", "2 ** 3");
+    }
+
+    #[test]
+    fn test_fold_mixed_with_variable() {
+        run_fold_test("x + 7", "x + (3 + 4)");
+    }
+
+    #[test]
+    fn test_simplify_add_zero() {
+        run_simplify_test("x", "x + 0");
+        run_simplify_test("x", "0 + x");
+    }
+
+    #[test]
+    fn test_simplify_multiply_identities() {
+        run_simplify_test("x", "x * 1");
+        run_simplify_test("x", "1 * x");
+        run_simplify_test("0", "x * 0");
+        run_simplify_test("0", "0 * x");
+    }
+
+    #[test]
+    fn test_simplify_power_identities() {
+        run_simplify_test("x", "x ** 1");
+        run_simplify_test("1", "x ** 0");
+    }
+
+    #[test]
+    fn test_simplify_double_negate() {
+        run_simplify_test("x", "--x");
+    }
+}
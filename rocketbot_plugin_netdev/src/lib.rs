@@ -1,11 +1,11 @@
 use std::borrow::Cow;
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt::Write;
-use std::sync::Weak;
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 use async_trait::async_trait;
-use log::{debug, error};
+use log::{debug, error, info};
 use rocketbot_interface::{JsonValueExtensions, send_channel_message, send_private_message};
 use rocketbot_interface::commands::{CommandDefinitionBuilder, CommandInstance};
 use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
@@ -18,8 +18,25 @@ use url::Url;
 pub struct Config {
     pub jack_to_port_api_uri: Url,
     pub port_api_uri: Url,
+    pub neighbor_api_uri: Url,
+    pub port_control_api_uri: Url,
     pub authorized_usernames: BTreeSet<String>,
     pub timeout_ms: u64,
+    /// The switch/port pairs watched by the background port-health monitor.
+    pub monitor_ports: Vec<(String, String)>,
+    /// The channel the port-health monitor posts change alerts to.
+    pub monitor_channel: Option<String>,
+    /// How often, in milliseconds, the port-health monitor polls each watched port.
+    pub monitor_interval_ms: u64,
+    /// How much an error/discard counter may climb between two consecutive monitor samples
+    /// before it is considered alert-worthy.
+    pub monitor_error_discard_threshold: u64,
+    /// Whether `dose`/`port` should render their output as a table by default, without the
+    /// `--table` flag being given.
+    pub default_output_table: bool,
+    /// The rendered table's maximum character width; if the actual table would be wider, the
+    /// verbose block format is used instead.
+    pub table_max_width: u64,
 }
 
 
@@ -97,6 +114,21 @@ fn counter_diff(older_counters: &serde_json::Value, newer_counters: &serde_json:
 }
 
 
+const SI_PREFIXES: [&str; 11] = ["", "K", "M", "G", "T", "P", "E", "Z", "Y", "R", "Q"];
+
+/// Formats a bits-per-second rate using SI-prefix scaling with one decimal place, e.g. `2.5 Gb/s`.
+fn format_bps(rate_bps: f64) -> String {
+    let mut scaled = rate_bps;
+    let mut si_prefix_index = 0;
+
+    while scaled >= 1000.0 && si_prefix_index < SI_PREFIXES.len() - 1 {
+        scaled /= 1000.0;
+        si_prefix_index += 1;
+    }
+    format!("{:.1} {}b/s", scaled, SI_PREFIXES[si_prefix_index])
+}
+
+
 fn extend_with_realtime_info(info_block: &mut String, port: &serde_json::Value) {
     let physical = &port["realtime"]["physical"];
     let aggregation = &port["realtime"]["aggregation"];
@@ -136,7 +168,6 @@ fn extend_with_realtime_info(info_block: &mut String, port: &serde_json::Value)
 
         if show_speed {
             if let Some(port_speed) = common["speed_bps"].as_u64() {
-                const SI_PREFIXES: [&str; 11] = ["", "K", "M", "G", "T", "P", "E", "Z", "Y", "R", "Q"];
                 let mut modified_speed = port_speed;
                 let mut si_prefix_index = 0;
 
@@ -184,34 +215,83 @@ fn extend_with_realtime_info(info_block: &mut String, port: &serde_json::Value)
         if let Some(counter_age_ms) = realtime["later_sample_delay_ms"].as_f64() {
             let older_counters = &common["counters"];
             let newer_counters = &realtime["later_counter_sample"];
+            let speed_bps = if show_speed { common["speed_bps"].as_u64().filter(|&s| s > 0) } else { None };
+
+            // a rate can only be derived if the sampling interval is known and non-zero
+            let rate_bps = |delta_bytes: u64| -> Option<f64> {
+                if counter_age_ms > 0.0 {
+                    Some((delta_bytes as f64) * 8.0 * 1000.0 / counter_age_ms)
+                } else {
+                    None
+                }
+            };
+            let pps = |delta_packets: u64| -> Option<f64> {
+                if counter_age_ms > 0.0 {
+                    Some((delta_packets as f64) * 1000.0 / counter_age_ms)
+                } else {
+                    None
+                }
+            };
 
             let mut counter_changes = Vec::with_capacity(6);
             // always show base values
             if let Some(incoming_delta) = counter_diff(older_counters, newer_counters, "incoming_bytes") {
-                counter_changes.push(format!("{} B received", incoming_delta));
+                let mut change = format!("{} B received", incoming_delta);
+                if let Some(rate) = rate_bps(incoming_delta) {
+                    write_expect!(change, " ({}", format_bps(rate));
+                    if let Some(speed) = speed_bps {
+                        write_expect!(change, ", {:.1}% utilization", rate / (speed as f64) * 100.0);
+                    }
+                    change.push(')');
+                }
+                counter_changes.push(change);
             }
             if let Some(outgoing_delta) = counter_diff(older_counters, newer_counters, "outgoing_bytes") {
-                counter_changes.push(format!("{} B sent", outgoing_delta));
+                let mut change = format!("{} B sent", outgoing_delta);
+                if let Some(rate) = rate_bps(outgoing_delta) {
+                    write_expect!(change, " ({}", format_bps(rate));
+                    if let Some(speed) = speed_bps {
+                        write_expect!(change, ", {:.1}% utilization", rate / (speed as f64) * 100.0);
+                    }
+                    change.push(')');
+                }
+                counter_changes.push(change);
             }
             // only show error values if they aren't zero
             if let Some(incoming_discard_delta) = counter_diff(older_counters, newer_counters, "incoming_discarded_packets") {
                 if incoming_discard_delta > 0 {
-                    counter_changes.push(format!("{} incoming packets dropped", incoming_discard_delta));
+                    let mut change = format!("{} incoming packets dropped", incoming_discard_delta);
+                    if let Some(rate) = pps(incoming_discard_delta) {
+                        write_expect!(change, " ({:.1} pkt/s)", rate);
+                    }
+                    counter_changes.push(change);
                 }
             }
             if let Some(incoming_error_delta) = counter_diff(older_counters, newer_counters, "incoming_error_packets") {
                 if incoming_error_delta > 0 {
-                    counter_changes.push(format!("{} incoming packets have errors", incoming_error_delta));
+                    let mut change = format!("{} incoming packets have errors", incoming_error_delta);
+                    if let Some(rate) = pps(incoming_error_delta) {
+                        write_expect!(change, " ({:.1} pkt/s)", rate);
+                    }
+                    counter_changes.push(change);
                 }
             }
             if let Some(outgoing_discard_delta) = counter_diff(older_counters, newer_counters, "outgoing_discarded_packets") {
                 if outgoing_discard_delta > 0 {
-                    counter_changes.push(format!("{} outgoing packets dropped", outgoing_discard_delta));
+                    let mut change = format!("{} outgoing packets dropped", outgoing_discard_delta);
+                    if let Some(rate) = pps(outgoing_discard_delta) {
+                        write_expect!(change, " ({:.1} pkt/s)", rate);
+                    }
+                    counter_changes.push(change);
                 }
             }
             if let Some(outgoing_error_delta) = counter_diff(older_counters, newer_counters, "outgoing_error_packets") {
                 if outgoing_error_delta > 0 {
-                    counter_changes.push(format!("{} outgoing packets have errors", outgoing_error_delta));
+                    let mut change = format!("{} outgoing packets have errors", outgoing_error_delta);
+                    if let Some(rate) = pps(outgoing_error_delta) {
+                        write_expect!(change, " ({:.1} pkt/s)", rate);
+                    }
+                    counter_changes.push(change);
                 }
             }
 
@@ -221,9 +301,345 @@ fn extend_with_realtime_info(info_block: &mut String, port: &serde_json::Value)
 }
 
 
+/// Maps a neighbor/MAC-table entry's raw `state` field to a human-friendly word.
+fn friendly_neighbor_state(state: &str) -> Cow<str> {
+    match state {
+        "reachable" => Cow::Borrowed("reachable"),
+        "stale" => Cow::Borrowed("stale"),
+        "delay" => Cow::Borrowed("pending confirmation"),
+        "probe" => Cow::Borrowed("being probed"),
+        "incomplete" => Cow::Borrowed("incomplete"),
+        "unreachable" => Cow::Borrowed("unreachable"),
+        "static" => Cow::Borrowed("statically configured"),
+        other => Cow::Owned(other.to_owned()),
+    }
+}
+
+
+/// Appends a rendering of the neighbor/MAC-address-table entries learned on a port to
+/// `info_block`, deduplicating identical MAC+IP pairs and sorting by VLAN then MAC.
+fn extend_with_neighbor_info(info_block: &mut String, neighbors: &serde_json::Value) {
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+
+    for neighbor in neighbors.members_or_empty() {
+        let mac = match neighbor["mac"].as_str() {
+            Some(m) => m.to_owned(),
+            None => continue,
+        };
+        let ip = neighbor["ipv4"].as_str()
+            .or_else(|| neighbor["ipv6"].as_str())
+            .map(|s| s.to_owned());
+        let vlan_id = neighbor["vlan_id"].as_u64().unwrap_or(0);
+        let state = friendly_neighbor_state(neighbor["state"].as_str().unwrap_or(""));
+
+        if !seen.insert((mac.clone(), ip.clone())) {
+            // duplicate MAC+IP pair
+            continue;
+        }
+
+        entries.push((vlan_id, mac, ip, state.into_owned()));
+    }
+
+    entries.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+
+    if entries.len() == 0 {
+        write_expect!(info_block, "\nno addresses learned");
+        return;
+    }
+
+    for (vlan_id, mac, ip, state) in entries {
+        match ip {
+            Some(ip) => write_expect!(info_block, "\nMAC {} ({}) on VLAN {} — {}", mac, ip, vlan_id, state),
+            None => write_expect!(info_block, "\nMAC {} on VLAN {} — {}", mac, vlan_id, state),
+        }
+    }
+}
+
+
+/// One row of the monospace port table rendered by [`render_port_table`].
+struct PortTableRow {
+    switch: String,
+    port: String,
+    status: String,
+    speed: String,
+    vlan: String,
+    errors: String,
+}
+
+/// Extracts the columns of [`PortTableRow`] from a single fetched port, using the same
+/// physical-or-aggregation fallback and status/speed logic as [`extend_with_realtime_info`].
+fn port_table_row(switch_name: &str, port_name: &str, port: &serde_json::Value) -> PortTableRow {
+    let physical = &port["realtime"]["physical"];
+    let aggregation = &port["realtime"]["aggregation"];
+    let realtime = if !physical.is_null() {
+        physical
+    } else if !aggregation.is_null() {
+        aggregation
+    } else {
+        &serde_json::Value::Null
+    };
+    let common = &realtime["port"]["common"];
+
+    let admin_status = common["admin_status"].as_str().unwrap_or("?");
+    let oper_status = common["oper_status"].as_str().unwrap_or("?");
+    let (status, show_speed) = match (admin_status, oper_status) {
+        ("up", "up") => ("up".to_owned(), true),
+        ("up", "down") => ("down".to_owned(), false),
+        ("down", "down") => ("shut".to_owned(), false),
+        (a, o) => (format!("{}/{}", a, o), false),
+    };
+
+    let speed = if show_speed {
+        common["speed_bps"].as_u64()
+            .map(|s| format_bps(s as f64))
+            .unwrap_or_else(|| "?".to_owned())
+    } else {
+        "-".to_owned()
+    };
+
+    let vlan = common["untagged_vlan_id"].as_u64()
+        .filter(|&v| v != 0)
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "-".to_owned());
+
+    let errors = if realtime["later_sample_delay_ms"].as_f64().is_some() {
+        let older_counters = &common["counters"];
+        let newer_counters = &realtime["later_counter_sample"];
+        let total: u64 = MONITOR_COUNTER_KEYS.iter()
+            .filter_map(|key| counter_diff(older_counters, newer_counters, key))
+            .sum();
+        total.to_string()
+    } else {
+        "-".to_owned()
+    };
+
+    PortTableRow {
+        switch: switch_name.to_owned(),
+        port: port_name.to_owned(),
+        status,
+        speed,
+        vlan,
+        errors,
+    }
+}
+
+const PORT_TABLE_HEADERS: [&str; 6] = ["switch", "port", "status", "speed", "VLAN", "errors"];
+
+/// Lays out `rows` as a left-padded monospace table with one column per [`PortTableRow`] field,
+/// returning `None` (so the caller can fall back to the verbose block format) if the rendered
+/// table would be wider than `max_width` characters.
+fn render_port_table(rows: &[PortTableRow], max_width: u64) -> Option<String> {
+    let mut widths = PORT_TABLE_HEADERS.map(|h| h.chars().count());
+    for row in rows {
+        let cells = [&row.switch, &row.port, &row.status, &row.speed, &row.vlan, &row.errors];
+        for (width, cell) in widths.iter_mut().zip(cells.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let separator_width = 2 * (widths.len() - 1);
+    let total_width: usize = widths.iter().sum::<usize>() + separator_width;
+    if (total_width as u64) > max_width {
+        return None;
+    }
+
+    let mut table = String::from("```");
+    for (i, (header, width)) in PORT_TABLE_HEADERS.iter().zip(widths.iter()).enumerate() {
+        if i > 0 {
+            table.push_str("  ");
+        }
+        write_expect!(table, "{}", header);
+        for _ in 0..(*width - header.chars().count()) {
+            table.push(' ');
+        }
+    }
+    for row in rows {
+        table.push('\n');
+        let cells = [&row.switch, &row.port, &row.status, &row.speed, &row.vlan, &row.errors];
+        for (i, (cell, width)) in cells.iter().zip(widths.iter()).enumerate() {
+            if i > 0 {
+                table.push_str("  ");
+            }
+            write_expect!(table, "{}", cell);
+            for _ in 0..(*width - cell.chars().count()) {
+                table.push(' ');
+            }
+        }
+    }
+    table.push_str("\n```");
+
+    Some(table)
+}
+
+
+/// Fetches `uri` via `client` and decodes the response body as JSON, logging and returning `None`
+/// instead of panicking on any failure.
+async fn fetch_http_json(client: &reqwest::Client, uri: Url, timeout: Duration) -> Option<serde_json::Value> {
+    let resp_res = client
+        .get(uri.clone())
+        .timeout(timeout)
+        .send().await
+        .and_then(|response| response.error_for_status());
+    let resp = match resp_res {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to obtain {} response: {}", uri, e);
+            return None;
+        },
+    };
+    let bytes = match resp.bytes().await {
+        Ok(b) => b,
+        Err(e) => {
+            error!("failed to obtain {} bytes: {}", uri, e);
+            return None;
+        },
+    };
+    let json: serde_json::Value = match serde_json::from_slice(&bytes) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("failed to decode {} as JSON: {}", uri, e);
+            return None;
+        },
+    };
+    Some(json)
+}
+
+
+/// Fetches a switch port's realtime data, bypassing `NetdevPlugin::fetch_port` because the
+/// background monitor only has a cloned [`reqwest::Client`], not a reference to the plugin.
+async fn fetch_port_for_monitor(http_client: &reqwest::Client, config: &Config, switch_name: &str, port_name: &str) -> Option<serde_json::Value> {
+    let mut port_uri = config.port_api_uri.clone();
+    port_uri.query_pairs_mut()
+        .append_pair("switch", switch_name)
+        .append_pair("port", port_name);
+    fetch_http_json(http_client, port_uri, Duration::from_millis(config.timeout_ms)).await
+}
+
+/// Extracts the physical-or-aggregation `common` block of a fetched port, mirroring the fallback
+/// logic in [`extend_with_realtime_info`].
+fn port_common(port: &serde_json::Value) -> serde_json::Value {
+    let physical = &port["realtime"]["physical"];
+    let aggregation = &port["realtime"]["aggregation"];
+    let realtime = if !physical.is_null() { physical } else { aggregation };
+    realtime["port"]["common"].clone()
+}
+
+/// How many recent `common` samples are kept per watched port to distinguish a sustained problem
+/// from a single transient spike.
+const MONITOR_SAMPLE_HISTORY: usize = 5;
+const MONITOR_COUNTER_KEYS: [&str; 4] = [
+    "incoming_discarded_packets",
+    "incoming_error_packets",
+    "outgoing_discarded_packets",
+    "outgoing_error_packets",
+];
+
+/// Checks whether a counter has been climbing consistently over the last few samples rather than
+/// spiking once, requiring at least two consecutive positive deltas with the latest exceeding
+/// `threshold`.
+fn counter_climbing_sustained(samples: &VecDeque<serde_json::Value>, key: &str, threshold: u64) -> bool {
+    if samples.len() < 3 {
+        return false;
+    }
+
+    let mut deltas = Vec::with_capacity(samples.len() - 1);
+    for window in samples.iter().collect::<Vec<_>>().windows(2) {
+        match counter_diff(&window[0]["counters"], &window[1]["counters"], key) {
+            Some(d) => deltas.push(d),
+            None => return false,
+        }
+    }
+
+    let last_two = &deltas[deltas.len() - 2..];
+    last_two.iter().all(|&d| d > 0) && *last_two.last().unwrap() > threshold
+}
+
+/// Watches a configured set of switch ports for operational-status changes, newly-appearing
+/// err-disable reasons, and sustained error/discard counter climbs, posting an alert to
+/// `config.monitor_channel` (if set) whenever one is detected. Runs until either `interface` or
+/// `config` can no longer be upgraded, i.e. the plugin has been unloaded.
+async fn run_port_monitor(interface: Weak<dyn RocketBotInterface>, config: Weak<RwLock<Config>>, http_client: reqwest::Client) {
+    let mut history: HashMap<(String, String), VecDeque<serde_json::Value>> = HashMap::new();
+
+    loop {
+        let config_lock = match Weak::upgrade(&config) {
+            Some(cl) => cl,
+            None => return,
+        };
+        let config_guard = config_lock.read().await;
+        let interval_ms = config_guard.monitor_interval_ms.max(1);
+
+        for (switch_name, port_name) in &config_guard.monitor_ports {
+            let port_data = match fetch_port_for_monitor(&http_client, &config_guard, switch_name, port_name).await {
+                Some(pd) => pd,
+                None => continue,
+            };
+            let port = match port_data["ports"].members_or_empty().next() {
+                Some(p) => p,
+                None => continue,
+            };
+            let common = port_common(port);
+
+            let key = (switch_name.clone(), port_name.clone());
+            let samples = history.entry(key).or_insert_with(VecDeque::new);
+
+            let mut alerts = Vec::new();
+            if let Some(previous) = samples.back() {
+                let old_oper_status = previous["oper_status"].as_str().unwrap_or("");
+                let new_oper_status = common["oper_status"].as_str().unwrap_or("");
+                if old_oper_status != new_oper_status && old_oper_status.len() > 0 {
+                    alerts.push(format!("operational status changed from {} to {}", old_oper_status, new_oper_status));
+                }
+
+                let old_dis_reason = previous["error_disabled_reason"].as_str();
+                let new_dis_reason = common["error_disabled_reason"].as_str();
+                if old_dis_reason.is_none() {
+                    if let Some(reason) = new_dis_reason {
+                        alerts.push(format!("port became err-disabled; reason: {}", reason));
+                    }
+                }
+            }
+
+            samples.push_back(common);
+            while samples.len() > MONITOR_SAMPLE_HISTORY {
+                samples.pop_front();
+            }
+
+            for counter_key in MONITOR_COUNTER_KEYS {
+                if counter_climbing_sustained(samples, counter_key, config_guard.monitor_error_discard_threshold) {
+                    alerts.push(format!("{} have been climbing steadily", counter_key.replace('_', " ")));
+                }
+            }
+
+            if alerts.len() == 0 {
+                continue;
+            }
+
+            let monitor_channel = match &config_guard.monitor_channel {
+                Some(mc) => mc,
+                None => continue,
+            };
+            let interface = match Weak::upgrade(&interface) {
+                Some(i) => i,
+                None => return,
+            };
+
+            let mut alert_body = format!("⚠️ switch {}, port {}: {}", switch_name, port_name, alerts.join("; "));
+            extend_with_realtime_info(&mut alert_body, port);
+
+            send_channel_message!(interface, monitor_channel, &alert_body).await;
+        }
+
+        drop(config_guard);
+        tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+    }
+}
+
+
 pub struct NetdevPlugin {
     interface: Weak<dyn RocketBotInterface>,
-    config: RwLock<Config>,
+    config: Arc<RwLock<Config>>,
     known_commands: HashSet<String>,
     http_client: reqwest::Client,
 }
@@ -237,6 +653,14 @@ impl NetdevPlugin {
             .as_str().ok_or("port_api_uri missing or not a string")?;
         let port_api_uri = Url::parse(port_api_uri_str)
             .or(Err("port_api_uri not a valid URL"))?;
+        let neighbor_api_uri_str = config["neighbor_api_uri"]
+            .as_str().ok_or("neighbor_api_uri missing or not a string")?;
+        let neighbor_api_uri = Url::parse(neighbor_api_uri_str)
+            .or(Err("neighbor_api_uri not a valid URL"))?;
+        let port_control_api_uri_str = config["port_control_api_uri"]
+            .as_str().ok_or("port_control_api_uri missing or not a string")?;
+        let port_control_api_uri = Url::parse(port_control_api_uri_str)
+            .or(Err("port_control_api_uri not a valid URL"))?;
         let timeout_ms = config["timeout_ms"]
             .as_u64_or_strict(5_000).ok_or("timeout_ms missing or not an unsigned 64-bit integer")?;
 
@@ -251,11 +675,39 @@ impl NetdevPlugin {
             );
         }
 
+        let mut monitor_ports = Vec::new();
+        for entry in config["monitor_ports"].members_or_empty() {
+            let switch_name = entry["switch"].as_str().ok_or("monitor_ports entry switch missing or not a string")?;
+            let port_name = entry["port"].as_str().ok_or("monitor_ports entry port missing or not a string")?;
+            monitor_ports.push((switch_name.to_owned(), port_name.to_owned()));
+        }
+        let monitor_channel = config["monitor_channel"].as_str().map(|s| s.to_owned());
+        let monitor_interval_ms = config["monitor_interval_ms"]
+            .as_u64_or_strict(60_000).ok_or("monitor_interval_ms not an unsigned 64-bit integer")?;
+        let monitor_error_discard_threshold = config["monitor_error_discard_threshold"]
+            .as_u64_or_strict(10).ok_or("monitor_error_discard_threshold not an unsigned 64-bit integer")?;
+
+        let default_output_table = match config["default_output"].as_str() {
+            Some("table") => true,
+            Some("verbose") | None => false,
+            Some(_) => return Err("default_output must be either \"table\" or \"verbose\""),
+        };
+        let table_max_width = config["table_max_width"]
+            .as_u64_or_strict(100).ok_or("table_max_width not an unsigned 64-bit integer")?;
+
         Ok(Config {
             jack_to_port_api_uri,
             port_api_uri,
+            neighbor_api_uri,
+            port_control_api_uri,
             authorized_usernames,
             timeout_ms,
+            monitor_ports,
+            monitor_channel,
+            monitor_interval_ms,
+            monitor_error_discard_threshold,
+            default_output_table,
+            table_max_width,
         })
     }
 
@@ -282,38 +734,147 @@ impl NetdevPlugin {
         match command.name.as_str() {
             "dose" => self.handle_dose_command(message, command, &config).await,
             "port" => self.handle_port_command(message, command, &config).await,
+            "neighbors" => self.handle_neighbors_command(message, command, &config).await,
+            "portset" => self.handle_portset_command(message, command, &config).await,
+            "portdescr" => self.handle_portdescr_command(message, command, &config).await,
             _ => {},
         };
     }
 
-    async fn get_http_json(&self, uri: Url, timeout: Duration) -> Option<serde_json::Value> {
-        let resp_res = self.http_client
-            .get(uri.clone())
-            .timeout(timeout)
-            .send().await
-            .and_then(|response| response.error_for_status());
-        let resp = match resp_res {
-            Ok(r) => r,
-            Err(e) => {
-                error!("failed to obtain {} response: {}", uri, e);
-                return None;
-            },
+    /// Re-fetches the given port and renders its `admin_status`/`oper_status` as confirmation
+    /// text, for use after a mutating command has been applied.
+    async fn fetch_status_confirmation(&self, switch_name: &str, port_name: &str, config: &Config) -> String {
+        let port_data = match self.fetch_port(switch_name, port_name, config).await {
+            Some(pd) => pd,
+            None => return "Could not confirm the new state (failed to re-fetch the port).".to_owned(),
         };
-        let bytes = match resp.bytes().await {
-            Ok(b) => b,
-            Err(e) => {
-                error!("failed to obtain {} bytes: {}", uri, e);
-                return None;
-            },
+
+        let port = match port_data["ports"].members_or_empty().next() {
+            Some(p) => p,
+            None => return "Could not confirm the new state (port no longer known).".to_owned(),
         };
-        let json: serde_json::Value = match serde_json::from_slice(&bytes) {
-            Ok(v) => v,
-            Err(e) => {
-                error!("failed to decode {} as JSON: {}", uri, e);
-                return None;
+
+        let physical = &port["realtime"]["physical"];
+        let aggregation = &port["realtime"]["aggregation"];
+        let realtime = if !physical.is_null() { physical } else { aggregation };
+        let common = &realtime["port"]["common"];
+
+        format!(
+            "admin status: {}, operational status: {}",
+            stringify(&common["admin_status"]), stringify(&common["oper_status"]),
+        )
+    }
+
+    async fn fetch_port(&self, switch_name: &str, port_name: &str, config: &Config) -> Option<serde_json::Value> {
+        let mut port_uri = config.port_api_uri.clone();
+        port_uri.query_pairs_mut()
+            .append_pair("switch", switch_name)
+            .append_pair("port", port_name);
+        self.get_http_json(port_uri, Duration::from_millis(config.timeout_ms)).await
+    }
+
+    async fn handle_portset_command(&self, message: AnyMessage, command: &CommandInstance, config: &Config) {
+        let interface = match self.interface.upgrade() {
+            Some(i) => i,
+            None => return,
+        };
+
+        let switch_name = &command.args[0];
+        let port_name = &command.args[1];
+        let action = command.args[2].to_lowercase();
+        let confirm = command.rest.trim();
+        let username = &message.sender().username;
+
+        if confirm != port_name {
+            message.respond(&*interface, "To confirm, please repeat the port name as the last argument.").await;
+            return;
+        }
+
+        let desired_admin_status = match action.as_str() {
+            "up" => "up",
+            "down" => "down",
+            _ => {
+                message.respond(&*interface, "Action must be `up` or `down`.").await;
+                return;
             },
         };
-        Some(json)
+
+        info!("{} is setting port {:?} on switch {:?} administratively {}", username, port_name, switch_name, desired_admin_status);
+
+        let body = serde_json::json!({
+            "switch": switch_name,
+            "port": port_name,
+            "admin_status": desired_admin_status,
+        });
+        let post_result = self.http_client
+            .post(config.port_control_api_uri.clone())
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .json(&body)
+            .send().await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = post_result {
+            error!("{} failed to set port {:?} on switch {:?} administratively {}: {}", username, port_name, switch_name, desired_admin_status, e);
+            message.respond(&*interface, "Failed to apply the port configuration change.").await;
+            return;
+        }
+
+        let status_text = self.fetch_status_confirmation(switch_name, port_name, config).await;
+        message.respond(&*interface, &format!("Port {} on switch {} set administratively {}.\n{}", port_name, switch_name, desired_admin_status, status_text)).await;
+    }
+
+    async fn handle_portdescr_command(&self, message: AnyMessage, command: &CommandInstance, config: &Config) {
+        let interface = match self.interface.upgrade() {
+            Some(i) => i,
+            None => return,
+        };
+
+        let switch_name = &command.args[0];
+        let port_name = &command.args[1];
+        let rest = command.rest.trim();
+        let username = &message.sender().username;
+
+        let (confirm, description) = match rest.split_once(char::is_whitespace) {
+            Some((c, d)) => (c, d.trim_start()),
+            None => (rest, ""),
+        };
+        if confirm != port_name {
+            message.respond(&*interface, "To confirm, please repeat the port name before the description.").await;
+            return;
+        }
+
+        info!("{} is setting description of port {:?} on switch {:?} to {:?}", username, port_name, switch_name, description);
+
+        let body = serde_json::json!({
+            "switch": switch_name,
+            "port": port_name,
+            "description": description,
+        });
+        let post_result = self.http_client
+            .post(config.port_control_api_uri.clone())
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .json(&body)
+            .send().await
+            .and_then(|r| r.error_for_status());
+        if let Err(e) = post_result {
+            error!("{} failed to set description of port {:?} on switch {:?}: {}", username, port_name, switch_name, e);
+            message.respond(&*interface, "Failed to apply the port configuration change.").await;
+            return;
+        }
+
+        let status_text = self.fetch_status_confirmation(switch_name, port_name, config).await;
+        message.respond(&*interface, &format!("Description of port {} on switch {} updated.\n{}", port_name, switch_name, status_text)).await;
+    }
+
+    async fn fetch_neighbors(&self, switch_name: &str, port_name: &str, config: &Config) -> Option<serde_json::Value> {
+        let mut neighbor_uri = config.neighbor_api_uri.clone();
+        neighbor_uri.query_pairs_mut()
+            .append_pair("switch", switch_name)
+            .append_pair("port", port_name);
+        self.get_http_json(neighbor_uri, Duration::from_millis(config.timeout_ms)).await
+    }
+
+    async fn get_http_json(&self, uri: Url, timeout: Duration) -> Option<serde_json::Value> {
+        fetch_http_json(&self.http_client, uri, timeout).await
     }
 
     async fn handle_dose_command(&self, message: AnyMessage, command: &CommandInstance, config: &Config) {
@@ -351,17 +912,34 @@ impl NetdevPlugin {
             return;
         }
 
+        let use_table = command.flags.contains("t") || command.flags.contains("table") || config.default_output_table;
+
         let mut info_blocks = Vec::new();
+        let mut table_rows = Vec::new();
         for port in ports {
             let switch_name = port["switch"].as_str().unwrap_or("???");
             let port_name = port["port"].as_str().unwrap_or("???");
 
+            table_rows.push(port_table_row(switch_name, port_name, port));
+
             let mut info_block = format!("connected to {} port {}", switch_name, port_name);
             extend_with_realtime_info(&mut info_block, port);
 
+            let neighbors = self.fetch_neighbors(switch_name, port_name, config).await
+                .unwrap_or(serde_json::Value::Null);
+            extend_with_neighbor_info(&mut info_block, &neighbors["neighbors"]);
+
             info_blocks.push(info_block);
         }
 
+        if use_table {
+            if let Some(table) = render_port_table(&table_rows, config.table_max_width) {
+                message.respond(&*interface, &format!("jack `{}`:\n{}", jack_name, table)).await;
+                return;
+            }
+            // table too wide for the configured limit; fall back to the verbose format below
+        }
+
         let mut response_text = format!("jack `{}`:", jack_name);
         for info_block in info_blocks {
             response_text.push_str("\n\n");
@@ -407,17 +985,34 @@ impl NetdevPlugin {
             return;
         }
 
+        let use_table = command.flags.contains("t") || command.flags.contains("table") || config.default_output_table;
+
         let mut info_blocks = Vec::new();
+        let mut table_rows = Vec::new();
         for port in ports {
             let actual_switch_name = port["switch"].as_str().unwrap_or("???");
             let actual_port_name = port["port"].as_str().unwrap_or("???");
 
+            table_rows.push(port_table_row(actual_switch_name, actual_port_name, port));
+
             let mut info_block = format!("switch {} port {}", actual_switch_name, actual_port_name);
             extend_with_realtime_info(&mut info_block, port);
 
+            let neighbors = self.fetch_neighbors(actual_switch_name, actual_port_name, config).await
+                .unwrap_or(serde_json::Value::Null);
+            extend_with_neighbor_info(&mut info_block, &neighbors["neighbors"]);
+
             info_blocks.push(info_block);
         }
 
+        if use_table {
+            if let Some(table) = render_port_table(&table_rows, config.table_max_width) {
+                message.respond(&*interface, &table).await;
+                return;
+            }
+            // table too wide for the configured limit; fall back to the verbose format below
+        }
+
         let mut response_text = String::new();
         for info_block in info_blocks {
             if response_text.len() > 0 {
@@ -428,6 +1023,31 @@ impl NetdevPlugin {
 
         message.respond(&*interface, &response_text).await;
     }
+
+    async fn handle_neighbors_command(&self, message: AnyMessage, command: &CommandInstance, config: &Config) {
+        let interface = match self.interface.upgrade() {
+            Some(i) => i,
+            None => return,
+        };
+
+        let switch_name = &command.args[0];
+        let port_name = command.rest.trim();
+
+        let neighbor_data = match self.fetch_neighbors(switch_name, port_name, config).await {
+            Some(nd) => nd,
+            None => {
+                message.respond(&*interface, "Failed to obtain neighbor data.").await;
+                return;
+            },
+        };
+
+        debug!("obtained neighbor data: {}", neighbor_data);
+
+        let mut info_block = format!("neighbors on switch {} port {}:", switch_name, port_name);
+        extend_with_neighbor_info(&mut info_block, &neighbor_data["neighbors"]);
+
+        message.respond(&*interface, &info_block).await;
+    }
 }
 #[async_trait]
 impl RocketBotPlugin for NetdevPlugin {
@@ -439,7 +1059,7 @@ impl RocketBotPlugin for NetdevPlugin {
 
         let config_object = Self::try_get_config(config)
             .expect("failed to load config");
-        let config_lock = RwLock::new(config_object);
+        let config_lock = Arc::new(RwLock::new(config_object));
 
         let http_client = reqwest::Client::new();
 
@@ -448,17 +1068,45 @@ impl RocketBotPlugin for NetdevPlugin {
             CommandDefinitionBuilder::new(
                 "dose",
                 "netdev",
-                "{cpfx}dose JACK",
+                "{cpfx}dose [{sopfx}t] JACK",
                 "Outputs information about a network jack and the switch port it is connected to.",
             )
+                .add_flag("t")
+                .add_flag("table")
                 .build(),
             CommandDefinitionBuilder::new(
                 "port",
                 "netdev",
-                "{cpfx}port SWITCH PORT",
+                "{cpfx}port [{sopfx}t] SWITCH PORT",
                 "Outputs information about a switch port.",
             )
                 .arg_count(1)
+                .add_flag("t")
+                .add_flag("table")
+                .build(),
+            CommandDefinitionBuilder::new(
+                "neighbors",
+                "netdev",
+                "{cpfx}neighbors SWITCH PORT",
+                "Outputs the neighbor/MAC-address-table entries learned on a switch port.",
+            )
+                .arg_count(1)
+                .build(),
+            CommandDefinitionBuilder::new(
+                "portset",
+                "netdev",
+                "{cpfx}portset SWITCH PORT {up|down} PORT",
+                "Administratively sets a switch port up or down. Repeat the port name as the last argument to confirm.",
+            )
+                .arg_count(3)
+                .build(),
+            CommandDefinitionBuilder::new(
+                "portdescr",
+                "netdev",
+                "{cpfx}portdescr SWITCH PORT PORT TEXT",
+                "Sets a switch port's description. Repeat the port name before the description to confirm.",
+            )
+                .arg_count(2)
                 .build(),
         ];
         for command in commands {
@@ -467,6 +1115,16 @@ impl RocketBotPlugin for NetdevPlugin {
             my_interface.register_private_message_command(&command).await;
         }
 
+        // set up the background port-health monitor
+        {
+            let monitor_interface = Weak::clone(&interface);
+            let monitor_config_lock = Arc::downgrade(&config_lock);
+            let monitor_http_client = http_client.clone();
+            tokio::spawn(async move {
+                run_port_monitor(monitor_interface, monitor_config_lock, monitor_http_client).await;
+            });
+        }
+
         Self {
             interface,
             config: config_lock,
@@ -506,6 +1164,12 @@ impl RocketBotPlugin for NetdevPlugin {
             Some(include_str!("../help/dose.md").to_owned())
         } else if command_name == "port" {
             Some(include_str!("../help/port.md").to_owned())
+        } else if command_name == "neighbors" {
+            Some(include_str!("../help/neighbors.md").to_owned())
+        } else if command_name == "portset" {
+            Some(include_str!("../help/portset.md").to_owned())
+        } else if command_name == "portdescr" {
+            Some(include_str!("../help/portdescr.md").to_owned())
         } else {
             None
         }
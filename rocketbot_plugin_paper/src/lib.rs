@@ -18,9 +18,19 @@ use tracing::error;
 
 
 static PAPER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(
-    "^\\s*(?P<series>[A-Za-z])\\s*(?P<index>-?\\s*[0-9]+(?:\\s*[0-9]+)*)\\s*$",
+    "^\\s*(?P<series>[A-Za-z])\\s*(?P<index>-?\\s*[0-9]+(?:\\s*[0-9]+)*)\\s*(?:\\s+(?P<unit>mm|cm|pica|pc|in|pt|m))?\\s*$",
 ).expect("failed to compile regex"));
 
+static WHAT_PAPER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(
+    "(?i)^\\s*(?P<w>[0-9]+(?:\\.[0-9]+)?)\\s*(?P<wunit>mm|cm|m)?\\s*[x\u{D7}*]\\s*(?P<h>[0-9]+(?:\\.[0-9]+)?)\\s*(?P<hunit>mm|cm|m)?\\s*$",
+).expect("failed to compile regex"));
+
+/// A small tolerance (in percent) below which a candidate size is reported as an exact match
+/// rather than a close approximation.
+const WHAT_PAPER_EXACT_TOLERANCE_PCT: Lazy<BigDecimal> = Lazy::new(|| {
+    "0.1".parse().expect("failed to parse exact-match tolerance")
+});
+
 static SI_THOUSANDS: &[&str] = &[
     "q", "r", "y", "z", "a", "f", "p", "n", "\u{3BC}", "m",
     "",
@@ -197,6 +207,71 @@ fn paper_size(series: &str, order: &BigInt) -> Option<(BigDecimal, BigDecimal)>
     Some((long_m, short_m))
 }
 
+fn bd_abs(value: BigDecimal) -> BigDecimal {
+    if value < BigDecimal::zero() {
+        -value
+    } else {
+        value
+    }
+}
+
+/// Interprets `value_str` as a length, scaling it down to metres according to `unit`
+/// (`"mm"`/`"cm"`/`"m"`, case-insensitively; no unit is treated as bare metres).
+fn parse_dimension(value_str: &str, unit: Option<&str>) -> Option<BigDecimal> {
+    let value: BigDecimal = value_str.parse().ok()?;
+    let divisor = match unit.map(|u| u.to_ascii_lowercase()) {
+        Some(u) if u == "mm" => BigDecimal::from(1000),
+        Some(u) if u == "cm" => BigDecimal::from(100),
+        _ => BigDecimal::one(),
+    };
+    Some(value / divisor)
+}
+
+struct ClosestPaper {
+    series: String,
+    index: BigInt,
+    deviation_pct: BigDecimal,
+    is_exact: bool,
+}
+
+/// Searches every series `A`..`Z` and index in `-max_index..=max_index` for the `paper_size`
+/// whose long/short pair is closest (by relative error) to `target_long`/`target_short` (both in
+/// metres, with `target_long >= target_short`).
+fn find_closest_paper(target_long: &BigDecimal, target_short: &BigDecimal, max_index: &BigInt) -> Option<ClosestPaper> {
+    let mut best: Option<(String, BigInt, BigDecimal, BigDecimal)> = None;
+
+    let mut index = -max_index.clone();
+    while &index <= max_index {
+        for series_char in 'A'..='Z' {
+            let series = series_char.to_string();
+            let (long_m, short_m) = match paper_size(&series, &index) {
+                Some(lmsm) => lmsm,
+                None => continue,
+            };
+
+            let rel_long = bd_abs(long_m - target_long.clone()) / target_long.clone();
+            let rel_short = bd_abs(short_m - target_short.clone()) / target_short.clone();
+            let combined_error = rel_long.clone() + rel_short.clone();
+            let max_rel = if rel_long > rel_short { rel_long } else { rel_short };
+            let deviation_pct = max_rel * BigDecimal::from(100);
+
+            let is_better = match &best {
+                None => true,
+                Some((_, _, best_error, _)) => combined_error < *best_error,
+            };
+            if is_better {
+                best = Some((series, index.clone(), combined_error, deviation_pct));
+            }
+        }
+        index += 1;
+    }
+
+    best.map(|(series, index, _combined_error, deviation_pct)| {
+        let is_exact = deviation_pct < WHAT_PAPER_EXACT_TOLERANCE_PCT.deref().clone();
+        ClosestPaper { series, index, deviation_pct, is_exact }
+    })
+}
+
 fn si_prefix(mut value: BigDecimal) -> (&'static str, BigDecimal) {
     let mut index_with_offset = SI_THOUSANDS_OFFSET;
     let max_index: isize = isize::try_from(SI_THOUSANDS.len()).unwrap() - 1;
@@ -286,11 +361,100 @@ fn maybe_to_scientific(dec: &BigDecimal) -> String {
     }
 }
 
+/// Rounds a length given in metres down to the next lower whole millimetre, per the ISO 216
+/// rounding rule (dimensions are rounded toward the next lower millimetre, never to the nearest
+/// one). Assumes `value_m` is non-negative, which always holds for the paper dimensions we emit.
+fn round_down_mm(value_m: &BigDecimal) -> BigInt {
+    let mm = value_m * BigDecimal::from(1000);
+    let (bi, exp) = mm.as_bigint_and_exponent();
+    if exp <= 0 {
+        let mut scaled = bi;
+        for _ in 0..(-exp) {
+            scaled *= BigInt::from(10);
+        }
+        scaled
+    } else {
+        let mut divisor = BigInt::one();
+        for _ in 0..exp {
+            divisor *= BigInt::from(10);
+        }
+        bi / divisor
+    }
+}
+
+
+/// A unit in which a paper dimension may be displayed. `Metre` keeps the original SI-prefixed
+/// display; the others bypass `si_prefix` and are shown as a single exact-factor conversion.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum OutputUnit {
+    Metre,
+    Millimetre,
+    Inch,
+    Point,
+    Pica,
+}
+impl OutputUnit {
+    fn try_from_str(unit_str: &str) -> Option<Self> {
+        match unit_str.to_ascii_lowercase().as_str() {
+            "m" => Some(Self::Metre),
+            "mm" => Some(Self::Millimetre),
+            "in" => Some(Self::Inch),
+            "pt" => Some(Self::Point),
+            "pc" | "pica" => Some(Self::Pica),
+            _ => None,
+        }
+    }
+
+    /// The exact number of metres in one of this unit.
+    fn metres_per_unit(&self) -> BigDecimal {
+        match self {
+            Self::Metre => BigDecimal::one(),
+            Self::Millimetre => "0.001".parse().unwrap(),
+            Self::Inch => "0.0254".parse().unwrap(),
+            Self::Point => "0.0254".parse::<BigDecimal>().unwrap() / BigDecimal::from(72),
+            Self::Pica => "0.0254".parse::<BigDecimal>().unwrap() / BigDecimal::from(72) * BigDecimal::from(12),
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            Self::Metre => "m",
+            Self::Millimetre => "mm",
+            Self::Inch => "in",
+            Self::Point => "pt",
+            Self::Pica => "pc",
+        }
+    }
+}
+
+/// Formats a length given in metres for display in `unit`, returning the formatted value and the
+/// unit label to append to it. `Metre` keeps the SI-prefix behavior of `si_prefix`; every other
+/// unit is an exact-factor conversion with no further scaling.
+fn format_length(value_m: BigDecimal, unit: OutputUnit, output_precision: u64) -> (String, String) {
+    match unit {
+        OutputUnit::Metre => {
+            let (prefix, scaled) = si_prefix(value_m);
+            let prec = scaled.with_prec(output_precision);
+            (maybe_to_scientific(&prec), format!("{}m", prefix))
+        },
+        other => {
+            let scaled = value_m / other.metres_per_unit();
+            let prec = scaled.with_prec(output_precision);
+            (maybe_to_scientific(&prec), other.suffix().to_owned())
+        },
+    }
+}
+
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 struct Config {
     max_index: BigInt,
     output_precision: u64,
+    default_unit: OutputUnit,
+
+    /// Whether to additionally report the ISO 216 standard nominal size (the exact dimensions
+    /// rounded down to the nearest whole millimetre) alongside the mathematically exact one.
+    show_standard_size: bool,
 }
 
 
@@ -306,12 +470,103 @@ impl PaperPlugin {
             .or_msg("failed to parse max_index")?;
         let output_precision = config["output_precision"].as_u64()
             .ok_or("output_precision missing or not a u64")?;
+        let default_unit = match config["default_unit"].as_str() {
+            Some(s) => OutputUnit::try_from_str(s)
+                .ok_or("default_unit is not a recognized unit")?,
+            None => OutputUnit::Metre,
+        };
+        let show_standard_size = config["show_standard_size"].as_bool()
+            .unwrap_or(false);
 
         Ok(Config {
             max_index,
             output_precision,
+            default_unit,
+            show_standard_size,
         })
     }
+
+    async fn handle_whatpaper_command(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        let caps = match WHAT_PAPER_RE.captures(&command.rest) {
+            Some(c) => c,
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("@{} Failed to parse dimensions.", channel_message.message.sender.username),
+                ).await;
+                return;
+            },
+        };
+
+        let w_str = caps.name("w").unwrap().as_str();
+        let w_unit = caps.name("wunit").map(|m| m.as_str());
+        let h_str = caps.name("h").unwrap().as_str();
+        let h_unit = caps.name("hunit").map(|m| m.as_str());
+
+        let w = parse_dimension(w_str, w_unit);
+        let h = parse_dimension(h_str, h_unit);
+        let (w, h) = match (w, h) {
+            (Some(w), Some(h)) => (w, h),
+            _ => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("@{} Failed to parse dimensions.", channel_message.message.sender.username),
+                ).await;
+                return;
+            },
+        };
+
+        if w <= BigDecimal::zero() || h <= BigDecimal::zero() {
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                &format!("@{} Dimensions must be positive.", channel_message.message.sender.username),
+            ).await;
+            return;
+        }
+
+        let (target_long, target_short) = if w >= h {
+            (w, h)
+        } else {
+            (h, w)
+        };
+
+        let closest = match find_closest_paper(&target_long, &target_short, &config_guard.max_index) {
+            Some(c) => c,
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("@{} Could not find a matching paper size.", channel_message.message.sender.username),
+                ).await;
+                return;
+            },
+        };
+
+        let deviation_prec = closest.deviation_pct.with_prec(config_guard.output_precision);
+        let deviation_sci = maybe_to_scientific(&deviation_prec);
+        let match_label = if closest.is_exact { "exact match" } else { "closest match" };
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &format!(
+                "@{} {}{} ({}, {}% deviation)",
+                channel_message.message.sender.username,
+                closest.series, closest.index,
+                match_label, deviation_sci,
+            ),
+        ).await;
+    }
 }
 #[async_trait]
 impl RocketBotPlugin for PaperPlugin {
@@ -332,11 +587,20 @@ impl RocketBotPlugin for PaperPlugin {
             &CommandDefinitionBuilder::new(
                 "paper",
                 "paper",
-                "{cpfx}paper PAPER",
+                "{cpfx}paper PAPER [UNIT]",
                 "Displays the size of the given ISO 216-like paper.",
             )
                 .build()
         ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "whatpaper",
+                "paper",
+                "{cpfx}whatpaper WIDTHxHEIGHT",
+                "Finds the closest standard paper size matching the given dimensions.",
+            )
+                .build()
+        ).await;
 
         PaperPlugin {
             interface,
@@ -354,14 +618,23 @@ impl RocketBotPlugin for PaperPlugin {
             Some(i) => i,
         };
 
+        if command.name == "whatpaper" {
+            self.handle_whatpaper_command(channel_message, command).await;
+            return;
+        }
+
         if command.name != "paper" {
             return;
         }
 
         let config_guard = self.config.read().await;
 
-        let (series, index_str) = match PAPER_RE.captures(&command.rest) {
-            Some(caps) => (caps.name("series").unwrap().as_str(), caps.name("index").unwrap().as_str()),
+        let (series, index_str, unit_str) = match PAPER_RE.captures(&command.rest) {
+            Some(caps) => (
+                caps.name("series").unwrap().as_str(),
+                caps.name("index").unwrap().as_str(),
+                caps.name("unit").map(|m| m.as_str()),
+            ),
             None => {
                 send_channel_message!(
                     interface,
@@ -372,6 +645,21 @@ impl RocketBotPlugin for PaperPlugin {
             },
         };
 
+        let unit = match unit_str {
+            Some(u) => match OutputUnit::try_from_str(u) {
+                Some(unit) => unit,
+                None => {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        &format!("@{} Failed to parse unit.", channel_message.message.sender.username),
+                    ).await;
+                    return;
+                },
+            },
+            None => config_guard.default_unit,
+        };
+
         let mut index_trimmed = String::with_capacity(index_str.len());
         for c in index_str.chars() {
             if c == '-' || c.is_ascii_digit() {
@@ -411,29 +699,43 @@ impl RocketBotPlugin for PaperPlugin {
                 return;
             }
         };
-        let (long_pfx, long_val) = si_prefix(long_m);
-        let (short_pfx, short_val) = si_prefix(short_m);
+        let standard_mm = if config_guard.show_standard_size {
+            Some((round_down_mm(&long_m), round_down_mm(&short_m)))
+        } else {
+            None
+        };
 
-        let long_prec = long_val.with_prec(config_guard.output_precision);
-        let short_prec = short_val.with_prec(config_guard.output_precision);
-        let long_sci = maybe_to_scientific(&long_prec);
-        let short_sci = maybe_to_scientific(&short_prec);
+        let (long_sci, long_unit) = format_length(long_m, unit, config_guard.output_precision);
+        let (short_sci, short_unit) = format_length(short_m, unit, config_guard.output_precision);
 
-        send_channel_message!(
-            interface,
-            &channel_message.channel.name,
-            &format!(
-                "@{} {}{}: {} {}m \u{D7} {} {}m",
+        let message = match standard_mm {
+            Some((long_mm, short_mm)) => format!(
+                "@{} {}{}: {} {} \u{D7} {} {} (standard: {}mm \u{D7} {}mm)",
                 channel_message.message.sender.username,
                 series, index,
-                long_sci, long_pfx, short_sci, short_pfx,
+                long_sci, long_unit, short_sci, short_unit,
+                long_mm, short_mm,
             ),
+            None => format!(
+                "@{} {}{}: {} {} \u{D7} {} {}",
+                channel_message.message.sender.username,
+                series, index,
+                long_sci, long_unit, short_sci, short_unit,
+            ),
+        };
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &message,
         ).await;
     }
 
     async fn get_command_help(&self, command_name: &str) -> Option<String> {
         if command_name == "paper" {
             Some(include_str!("../help/paper.md").to_owned())
+        } else if command_name == "whatpaper" {
+            Some(include_str!("../help/whatpaper.md").to_owned())
         } else {
             None
         }
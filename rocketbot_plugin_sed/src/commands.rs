@@ -10,11 +10,19 @@ pub trait Transformer {
 }
 
 
+/// The default backtracking limit applied to `fancy_regex::Regex` values built for sed commands,
+/// preventing patterns with catastrophic backtracking (e.g. `(a+)+$`) from hanging the bot when
+/// matched against adversarial chat input.
+pub(crate) const DEFAULT_BACKTRACK_LIMIT: usize = 1_000_000;
+
+
 #[derive(Debug)]
 pub(crate) enum SedCommand {
     Substitute(SubstituteCommand),
     Transpose(TransposeCommand),
     Exchange(ExchangeCommand),
+    #[allow(unused)] Rotate(RotateCommand),
+    ReplaceLiteral(ReplaceLiteralCommand),
 }
 impl Transformer for SedCommand {
     fn transform(&self, text: &str) -> String {
@@ -22,6 +30,8 @@ impl Transformer for SedCommand {
             SedCommand::Substitute(sc) => sc.transform(text),
             SedCommand::Transpose(tc) => tc.transform(text),
             SedCommand::Exchange(ec) => ec.transform(text),
+            SedCommand::Rotate(rc) => rc.transform(text),
+            SedCommand::ReplaceLiteral(rlc) => rlc.transform(text),
         }
     }
 }
@@ -55,9 +65,23 @@ impl SubstituteCommand {
 }
 impl Transformer for SubstituteCommand {
     fn transform(&self, text: &str) -> String {
+        // collect all matches up front, bailing out gracefully (instead of panicking) if the
+        // backtracking engine gives up partway through -- this happens e.g. if the pattern is
+        // susceptible to catastrophic backtracking and the match exceeds `backtrack_limit`
+        let mut all_captures: Vec<Captures> = Vec::new();
+        for captures_result in self.pattern.captures_iter(text) {
+            match captures_result {
+                Ok(caps) => all_captures.push(caps),
+                Err(e) => {
+                    warn!("giving up on substitution: match attempt failed: {}", e);
+                    return text.to_owned();
+                },
+            }
+        }
+
         let first_match: isize = if self.first_match < 0 {
             // match from end => we must count the matches first
-            let match_count_usize = self.pattern.find_iter(text).count();
+            let match_count_usize = all_captures.len();
             let match_count: isize = match match_count_usize.try_into() {
                 Ok(mc) => mc,
                 Err(_) => {
@@ -81,26 +105,114 @@ impl Transformer for SubstituteCommand {
             self.first_match
         };
 
-        let mut match_index: isize = -1;
-        let replaced = self.pattern.replace_all(text, |caps: &Captures| {
-            match_index += 1;
+        let mut ret = String::with_capacity(text.len());
+        let mut last_end = 0usize;
+        for (match_index_usize, caps) in all_captures.iter().enumerate() {
+            let whole_match = caps.get(0).expect("failed to get full match");
+            ret.push_str(&text[last_end..whole_match.start()]);
 
-            if match_index < first_match {
-                // unchanged
-                caps.get(0).expect("failed to get full match")
-                    .as_str().to_owned()
-            } else if match_index > first_match && !self.replace_all {
+            let match_index: isize = match_index_usize.try_into().unwrap_or(isize::MAX);
+            if match_index < first_match || (match_index > first_match && !self.replace_all) {
                 // unchanged
-                caps.get(0).expect("failed to get full match")
-                    .as_str().to_owned()
+                ret.push_str(whole_match.as_str());
             } else {
-                let mut ret = String::new();
                 caps.expand(&self.replacement, &mut ret);
-                ret
             }
-        });
 
-        replaced.into_owned()
+            last_end = whole_match.end();
+        }
+        ret.push_str(&text[last_end..]);
+
+        ret
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ReplaceLiteralCommand {
+    pattern: String,
+    replacement: String,
+    first_match: isize,
+    replace_all: bool,
+}
+impl ReplaceLiteralCommand {
+    pub fn new(
+        pattern: String,
+        replacement: String,
+        first_match: isize,
+        replace_all: bool,
+    ) -> ReplaceLiteralCommand {
+        ReplaceLiteralCommand {
+            pattern,
+            replacement,
+            first_match,
+            replace_all,
+        }
+    }
+
+    #[allow(unused)] pub fn pattern(&self) -> &str { self.pattern.as_str() }
+    #[allow(unused)] pub fn replacement(&self) -> &str { self.replacement.as_str() }
+    #[allow(unused)] pub fn first_match(&self) -> isize { self.first_match }
+    #[allow(unused)] pub fn replace_all(&self) -> bool { self.replace_all }
+}
+impl Transformer for ReplaceLiteralCommand {
+    fn transform(&self, text: &str) -> String {
+        if self.pattern.is_empty() {
+            return text.to_owned();
+        }
+
+        // find every non-overlapping byte occurrence of the needle, left-to-right
+        let mut occurrences: Vec<usize> = Vec::new();
+        let mut search_from = 0usize;
+        while let Some(relative_index) = text[search_from..].find(&self.pattern) {
+            let start = search_from + relative_index;
+            occurrences.push(start);
+            search_from = start + self.pattern.len();
+        }
+
+        let first_match: isize = if self.first_match < 0 {
+            // match from end => we must count the matches first
+            let match_count: isize = match occurrences.len().try_into() {
+                Ok(mc) => mc,
+                Err(_) => {
+                    // give up
+                    warn!("failed to convert match count {} from usize to isize", occurrences.len());
+                    return text.to_owned();
+                },
+            };
+
+            if match_count + self.first_match < 0 {
+                // give up
+                warn!(
+                    "match_count ({}) plus first_match ({}) are less than 0 ({})",
+                    match_count, self.first_match, match_count + self.first_match,
+                );
+                return text.to_owned();
+            }
+
+            match_count + self.first_match
+        } else {
+            self.first_match
+        };
+
+        let mut ret = String::with_capacity(text.len());
+        let mut last_end = 0usize;
+        for (match_index_usize, &start) in occurrences.iter().enumerate() {
+            let end = start + self.pattern.len();
+            ret.push_str(&text[last_end..start]);
+
+            let match_index: isize = match_index_usize.try_into().unwrap_or(isize::MAX);
+            if match_index < first_match || (match_index > first_match && !self.replace_all) {
+                // unchanged
+                ret.push_str(&self.pattern);
+            } else {
+                ret.push_str(&self.replacement);
+            }
+
+            last_end = end;
+        }
+        ret.push_str(&text[last_end..]);
+
+        ret
     }
 }
 
@@ -143,55 +255,170 @@ impl Transformer for TransposeCommand {
 pub(crate) struct ExchangeCommand {
     from_regex: Regex,
     to_regex: Regex,
+    swap_all: bool,
 }
 impl ExchangeCommand {
     pub fn new(
         from_regex: Regex,
         to_regex: Regex,
+        swap_all: bool,
     ) -> Self {
         Self {
             from_regex,
             to_regex,
+            swap_all,
         }
     }
-}
-impl Transformer for ExchangeCommand {
-    fn transform(&self, text: &str) -> String {
-        let from_match_opt = self.from_regex
-            .find(text).expect("from_regex.find failed");
-        let from_match = match from_match_opt {
-            Some(fm) => fm,
-            None => return text.to_owned(),
-        };
-        let mut to_match_opt = None;
-        for match_res in self.to_regex.find_iter(text) {
-            let m = match_res.expect("to_regex.find failed");
-            if !ranges_overlap(&from_match.range(), &m.range()) {
-                to_match_opt = Some(m);
-                break;
+
+    #[allow(unused)] pub fn swap_all(&self) -> bool { self.swap_all }
+
+    /// Finds every non-overlapping match of `regex` in `text`, giving up (returning `None`) if the
+    /// underlying backtracking engine errors out on any of them.
+    fn find_all(regex: &Regex, text: &str) -> Option<Vec<Range<usize>>> {
+        let mut ranges = Vec::new();
+        for match_res in regex.find_iter(text) {
+            match match_res {
+                Ok(m) => ranges.push(m.range()),
+                Err(e) => {
+                    warn!("giving up on exchange: match attempt failed: {}", e);
+                    return None;
+                },
             }
         }
-        let to_match = match to_match_opt {
-            Some(tm) => tm,
+        Some(ranges)
+    }
+
+    fn transform_first(&self, text: &str) -> String {
+        let from_ranges = match Self::find_all(&self.from_regex, text) {
+            Some(r) => r,
+            None => return text.to_owned(),
+        };
+        let from_range = match from_ranges.first() {
+            Some(r) => r.clone(),
             None => return text.to_owned(),
         };
 
-        let mut ret = String::with_capacity(text.len());
-        if from_match.start() < to_match.start() {
-            ret.push_str(&text[..from_match.start()]);
-            ret.push_str(to_match.as_str());
-            ret.push_str(&text[from_match.end()..to_match.start()]);
-            ret.push_str(from_match.as_str());
-            ret.push_str(&text[to_match.end()..]);
+        let to_ranges = match Self::find_all(&self.to_regex, text) {
+            Some(r) => r,
+            None => return text.to_owned(),
+        };
+        let to_range = match to_ranges.iter().find(|r| !ranges_overlap(&from_range, r)) {
+            Some(r) => r.clone(),
+            None => return text.to_owned(),
+        };
+
+        splice_spans(text, &mut vec![
+            (from_range, text[to_range.clone()].to_owned()),
+            (to_range, text[from_range.clone()].to_owned()),
+        ])
+    }
+
+    fn transform_all(&self, text: &str) -> String {
+        let from_ranges = match Self::find_all(&self.from_regex, text) {
+            Some(r) => r,
+            None => return text.to_owned(),
+        };
+        let to_ranges = match Self::find_all(&self.to_regex, text) {
+            Some(r) => r,
+            None => return text.to_owned(),
+        };
+
+        // greedily pair up successive non-overlapping from/to matches, left-to-right
+        let mut chosen: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+        let mut used_to_indices: Vec<usize> = Vec::new();
+        for from_range in &from_ranges {
+            if chosen.iter().any(|(f, t)| ranges_overlap(from_range, f) || ranges_overlap(from_range, t)) {
+                continue;
+            }
+
+            let found = to_ranges.iter().enumerate().find(|(i, to_range)| {
+                !used_to_indices.contains(i)
+                    && !ranges_overlap(from_range, to_range)
+                    && !chosen.iter().any(|(f, t)| ranges_overlap(to_range, f) || ranges_overlap(to_range, t))
+            });
+            if let Some((to_index, to_range)) = found {
+                used_to_indices.push(to_index);
+                chosen.push((from_range.clone(), to_range.clone()));
+            }
+        }
+
+        if chosen.is_empty() {
+            return text.to_owned();
+        }
+
+        let mut spans: Vec<(Range<usize>, String)> = Vec::with_capacity(chosen.len() * 2);
+        for (from_range, to_range) in &chosen {
+            spans.push((from_range.clone(), text[to_range.clone()].to_owned()));
+            spans.push((to_range.clone(), text[from_range.clone()].to_owned()));
+        }
+        splice_spans(text, &mut spans)
+    }
+}
+impl Transformer for ExchangeCommand {
+    fn transform(&self, text: &str) -> String {
+        if self.swap_all {
+            self.transform_all(text)
         } else {
-            assert!(to_match.start() < from_match.start());
-            ret.push_str(&text[..to_match.start()]);
-            ret.push_str(from_match.as_str());
-            ret.push_str(&text[to_match.end()..from_match.start()]);
-            ret.push_str(to_match.as_str());
-            ret.push_str(&text[from_match.end()..]);
+            self.transform_first(text)
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub(crate) struct RotateCommand {
+    regexes: Vec<Regex>,
+}
+impl RotateCommand {
+    #[allow(unused)]
+    pub fn new(
+        regexes: Vec<Regex>,
+    ) -> Self {
+        Self {
+            regexes,
         }
-        ret
+    }
+}
+impl Transformer for RotateCommand {
+    fn transform(&self, text: &str) -> String {
+        if self.regexes.len() < 2 {
+            // nothing to rotate
+            return text.to_owned();
+        }
+
+        let mut chosen: Vec<Range<usize>> = Vec::with_capacity(self.regexes.len());
+        for regex in &self.regexes {
+            let mut found = None;
+            for match_res in regex.find_iter(text) {
+                let m = match match_res {
+                    Ok(m) => m,
+                    Err(e) => {
+                        warn!("giving up on rotation: match attempt failed: {}", e);
+                        return text.to_owned();
+                    },
+                };
+                if !chosen.iter().any(|r| ranges_overlap(&m.range(), r)) {
+                    found = Some(m.range());
+                    break;
+                }
+            }
+
+            match found {
+                Some(r) => chosen.push(r),
+                None => return text.to_owned(),
+            }
+        }
+
+        // match of regexes[0] gets regexes[1]'s text, ..., match of the last regex gets
+        // regexes[0]'s text
+        let count = chosen.len();
+        let mut spans: Vec<(Range<usize>, String)> = Vec::with_capacity(count);
+        for i in 0..count {
+            let source_range = chosen[(i + 1) % count].clone();
+            spans.push((chosen[i].clone(), text[source_range].to_owned()));
+        }
+
+        splice_spans(text, &mut spans)
     }
 }
 
@@ -207,16 +434,32 @@ fn ranges_overlap<T: PartialOrd>(one: &Range<T>, other: &Range<T>) -> bool {
     )
 }
 
+/// Sorts `spans` by start offset and rebuilds `text`, substituting each span's range with its
+/// associated replacement text and leaving the untouched gaps between spans as-is.
+fn splice_spans(text: &str, spans: &mut Vec<(Range<usize>, String)>) -> String {
+    spans.sort_by_key(|(range, _)| range.start);
+
+    let mut ret = String::with_capacity(text.len());
+    let mut last_end = 0usize;
+    for (range, replacement) in spans.iter() {
+        ret.push_str(&text[last_end..range.start]);
+        ret.push_str(replacement);
+        last_end = range.end;
+    }
+    ret.push_str(&text[last_end..]);
+    ret
+}
+
 
 #[cfg(test)]
 mod tests {
-    use super::{ExchangeCommand, Transformer};
+    use super::{ExchangeCommand, ReplaceLiteralCommand, RotateCommand, Transformer};
     use fancy_regex::Regex;
 
     fn tec1(from_regex_str: &str, to_regex_str: &str, subject: &str, expected: &str) {
         let from_regex = Regex::new(from_regex_str).unwrap();
         let to_regex = Regex::new(to_regex_str).unwrap();
-        let cmd = ExchangeCommand::new(from_regex.clone(), to_regex.clone());
+        let cmd = ExchangeCommand::new(from_regex.clone(), to_regex.clone(), false);
         let transformed = cmd.transform(subject);
         assert_eq!(expected, transformed.as_str());
     }
@@ -224,16 +467,31 @@ mod tests {
     fn tec(from_regex_str: &str, to_regex_str: &str, subject: &str, expected: &str) {
         let from_regex = Regex::new(from_regex_str).unwrap();
         let to_regex = Regex::new(to_regex_str).unwrap();
-        let cmd = ExchangeCommand::new(from_regex.clone(), to_regex.clone());
+        let cmd = ExchangeCommand::new(from_regex.clone(), to_regex.clone(), false);
         let transformed = cmd.transform(subject);
         assert_eq!(expected, transformed.as_str());
 
         // also try it the other way around
-        let cmd2 = ExchangeCommand::new(to_regex.clone(), from_regex.clone());
+        let cmd2 = ExchangeCommand::new(to_regex.clone(), from_regex.clone(), false);
         let transformed2 = cmd2.transform(subject);
         assert_eq!(expected, transformed2.as_str());
     }
 
+    fn tec_all(from_regex_str: &str, to_regex_str: &str, subject: &str, expected: &str) {
+        let from_regex = Regex::new(from_regex_str).unwrap();
+        let to_regex = Regex::new(to_regex_str).unwrap();
+        let cmd = ExchangeCommand::new(from_regex, to_regex, true);
+        let transformed = cmd.transform(subject);
+        assert_eq!(expected, transformed.as_str());
+    }
+
+    fn trc(regex_strs: &[&str], subject: &str, expected: &str) {
+        let regexes: Vec<Regex> = regex_strs.iter().map(|s| Regex::new(s).unwrap()).collect();
+        let cmd = RotateCommand::new(regexes);
+        let transformed = cmd.transform(subject);
+        assert_eq!(expected, transformed.as_str());
+    }
+
     #[test]
     fn test_exchange_command() {
         tec(
@@ -260,4 +518,86 @@ mod tests {
             "do not exchange overoverlapping parts as lap breeds confusion",
         );
     }
+
+    #[test]
+    fn test_exchange_command_swap_all() {
+        tec_all(
+            "fox", "dog",
+            "the fox met another fox near the dog and a second dog",
+            "the dog met another dog near the fox and a second fox",
+        );
+
+        // non-overlapping pairing still applies when the sets collide
+        tec_all(
+            "overlap", "lap",
+            "do not exchange overlapping parts as overlap breeds confusion",
+            "do not exchange lapping parts as overoverlap breeds confusion",
+        );
+
+        // no matches at all => text is unchanged
+        tec_all(
+            "fox", "dog",
+            "nothing to see here",
+            "nothing to see here",
+        );
+    }
+
+    #[test]
+    fn test_rotate_command() {
+        trc(
+            &["fox", "dog", "cat"],
+            "the fox, the dog and the cat",
+            "the dog, the cat and the fox",
+        );
+
+        trc(
+            &["one", "two"],
+            "one and two",
+            "two and one",
+        );
+
+        // fewer than two patterns => no-op
+        trc(
+            &["fox"],
+            "the fox",
+            "the fox",
+        );
+
+        // a pattern without a match => no-op
+        trc(
+            &["fox", "dog", "elephant"],
+            "the fox and the dog",
+            "the fox and the dog",
+        );
+    }
+
+    fn trlc(pattern: &str, replacement: &str, first_match: isize, replace_all: bool, subject: &str, expected: &str) {
+        let cmd = ReplaceLiteralCommand::new(pattern.to_owned(), replacement.to_owned(), first_match, replace_all);
+        let transformed = cmd.transform(subject);
+        assert_eq!(expected, transformed.as_str());
+    }
+
+    #[test]
+    fn test_replace_literal_command() {
+        // replaces only the first occurrence by default
+        trlc("a.b", "X", 0, false, "a.ba.ba.b", "Xa.ba.b");
+
+        // replace_all replaces every occurrence
+        trlc("a.b", "X", 0, true, "a.ba.ba.b", "XXX");
+
+        // the pattern is not interpreted as a regex: "." matches only a literal dot
+        trlc("a.b", "X", 0, true, "axb a.b", "axb X");
+
+        // the replacement is not interpreted as an expansion template
+        trlc("a", "$1", 0, true, "banana", "b$1n$1n$1");
+
+        // first_match selects a specific occurrence (0-indexed)
+        trlc("a", "X", 1, false, "banana", "banXna");
+
+        // negative first_match counts from the end
+        trlc("a", "X", -1, false, "banana", "bananX");
+
+        // an empty pattern is a no-op rather than matching everywhere
+        trlc("", "X", 0, true, "banana", "banana");
+    }
 }
@@ -1,10 +1,10 @@
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 
-use fancy_regex::Regex;
+use fancy_regex::RegexBuilder;
 use once_cell::sync::Lazy;
 
-use crate::commands::{ExchangeCommand, SedCommand, SubstituteCommand, TransposeCommand};
+use crate::commands::{DEFAULT_BACKTRACK_LIMIT, ExchangeCommand, ReplaceLiteralCommand, SedCommand, SubstituteCommand, TransposeCommand};
 
 
 const SPLITTERS_STR: &'static str = "!\"#$%&'*+,-./:;=?^_`|~";
@@ -25,6 +25,7 @@ pub(crate) enum ParserError {
     MissingFlags,
     InvalidSubFlags(SubFlagsError),
     InvalidTransposeMode(String),
+    InvalidExchangeMode(String),
     PatternSyntaxError{ pattern: String, error_description: String },
     ReplacementSyntaxError{ replacement: String, error: ReplacementError },
     TransposeRangeLengthMismatch{ froms: Vec<char>, tos: Vec<char> },
@@ -63,6 +64,8 @@ impl fmt::Display for ParserError {
                 => write!(f, "invalid flags: {}", sfe),
             Self::InvalidTransposeMode(m)
                 => write!(f, "invalid transposition mode {:?}", m),
+            Self::InvalidExchangeMode(m)
+                => write!(f, "invalid exchange mode {:?}", m),
             Self::PatternSyntaxError{ pattern, error_description }
                 => write!(f, "syntax error in pattern {:?}: {}", pattern, error_description),
             Self::ReplacementSyntaxError{ replacement, error }
@@ -165,17 +168,20 @@ struct SubFlags {
     options: String,
     first_match: isize,
     replace_all: bool,
+    literal: bool,
 }
 impl SubFlags {
     pub fn new(
         options: String,
         first_match: isize,
         replace_all: bool,
+        literal: bool,
     ) -> SubFlags {
         SubFlags {
             options,
             first_match,
             replace_all,
+            literal,
         }
     }
 }
@@ -185,6 +191,7 @@ fn parse_sub_flags(flags: &str) -> Result<SubFlags, SubFlagsError> {
     let mut options = String::new();
     let mut first_match = 0isize;
     let mut replace_all = false;
+    let mut literal = false;
 
     let mut reading_number = false;
     let mut first_match_builder = String::new();
@@ -211,6 +218,8 @@ fn parse_sub_flags(flags: &str) -> Result<SubFlags, SubFlagsError> {
                 options.push(c);
             } else if c == 'g' {
                 replace_all = true;
+            } else if c == 'l' {
+                literal = true;
             } else {
                 // invalid flag
                 return Err(SubFlagsError::UnknownFlag{ flag_char: c, index: i });
@@ -232,6 +241,7 @@ fn parse_sub_flags(flags: &str) -> Result<SubFlags, SubFlagsError> {
         options,
         first_match,
         replace_all,
+        literal,
     ))
 }
 
@@ -461,12 +471,24 @@ fn make_substitute_command(command: &GenericReplacementCommand) -> Result<SedCom
     let sub_flags = parse_sub_flags(&flags)
         .map_err(|sfe| ParserError::InvalidSubFlags(sfe))?;
 
+    if sub_flags.literal {
+        // plain substring substitution: no regex options, no `$1`-style interpolation
+        return Ok(SedCommand::ReplaceLiteral(ReplaceLiteralCommand::new(
+            command.old_string.clone(),
+            command.new_string.clone(),
+            sub_flags.first_match,
+            sub_flags.replace_all,
+        )));
+    }
+
     let flagged_regex_string = if sub_flags.options.len() > 0 {
         format!("(?{}){}", sub_flags.options, command.old_string)
     } else {
         command.old_string.clone()
     };
-    let flagged_regex = Regex::new(&flagged_regex_string)
+    let flagged_regex = RegexBuilder::new(&flagged_regex_string)
+        .backtrack_limit(DEFAULT_BACKTRACK_LIMIT)
+        .build()
         .map_err(|e| ParserError::PatternSyntaxError{ pattern: flagged_regex_string, error_description: e.to_string() })?;
 
     let replacement_string = transform_replacement_string(
@@ -625,13 +647,25 @@ fn parse_with_ranges(spec: &str) -> Result<Vec<char>, ParserError> {
 }
 
 fn make_exchange_command(command: &GenericReplacementCommand) -> Result<SedCommand, ParserError> {
-    let from_regex = Regex::new(&command.old_string)
+    let flags = command.flags.as_deref().unwrap_or("");
+    let swap_all = match flags {
+        "" => false,
+        "a" => true,
+        _ => return Err(ParserError::InvalidExchangeMode(flags.to_owned())),
+    };
+
+    let from_regex = RegexBuilder::new(&command.old_string)
+        .backtrack_limit(DEFAULT_BACKTRACK_LIMIT)
+        .build()
         .map_err(|e| ParserError::PatternSyntaxError { pattern: command.old_string.clone(), error_description: e.to_string() })?;
-    let to_regex = Regex::new(&command.new_string)
+    let to_regex = RegexBuilder::new(&command.new_string)
+        .backtrack_limit(DEFAULT_BACKTRACK_LIMIT)
+        .build()
         .map_err(|e| ParserError::PatternSyntaxError { pattern: command.new_string.clone(), error_description: e.to_string() })?;
     Ok(SedCommand::Exchange(ExchangeCommand::new(
         from_regex,
         to_regex,
+        swap_all,
     )))
 }
 
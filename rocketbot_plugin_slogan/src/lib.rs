@@ -1,13 +1,18 @@
+use std::collections::HashMap;
 use std::sync::Weak;
+use std::time::Instant;
 
 use async_trait::async_trait;
 use log::{debug, error};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 use regex::Regex;
 use rocketbot_interface::{ResultExtensions, send_channel_message, send_private_message};
 use rocketbot_interface::commands::{CommandDefinitionBuilder, CommandInstance};
 use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
 use rocketbot_interface::model::{ChannelMessage, PrivateMessage};
-use rocketbot_interface::sync::RwLock;
+use rocketbot_interface::sync::{Mutex, RwLock};
+use scraper;
 use serde_json;
 use sxd_document;
 use sxd_document::dom::Element;
@@ -21,122 +26,212 @@ struct CleanupRegex {
 }
 
 
+/// Which extraction engine is used to pull the slogan text out of the fetched response.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum ExtractorKind {
+    /// `slogan_expression` is an XPath expression evaluated against an XML/XHTML document.
+    Xpath,
+
+    /// `slogan_expression` is a dotted/indexed JSONPath-style path (e.g. `foo.bar[0].baz`)
+    /// evaluated against a JSON document.
+    JsonPath,
+
+    /// `slogan_expression` is a CSS selector evaluated against an HTML document.
+    Css,
+}
+
+
+/// One slogan/quote/fact provider. `generate_slogan` picks among a `Config`'s sources by weighted
+/// random selection, falling through to the next one (in weighted-random order) if fetching,
+/// parsing or extracting from it fails.
 #[derive(Clone, Debug)]
-struct Config {
+struct Source {
     slogan_url: String,
     cleanup_regexes: Vec<CleanupRegex>,
-    slogan_xpath: String,
+    extractor_kind: ExtractorKind,
+    slogan_expression: String,
+    weight: u32,
+}
+
+
+#[derive(Clone, Debug)]
+struct Config {
+    sources: Vec<Source>,
     subject_placeholder: String,
+    cache_ttl_seconds: u64,
+    min_fetch_interval_seconds: u64,
+}
+
+
+/// A previously extracted slogan string, cached to spare the source another fetch.
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    extracted_string: String,
+    fetched_at: Instant,
 }
 
 
 pub struct SloganPlugin {
     interface: Weak<dyn RocketBotInterface>,
     config: RwLock<Config>,
+    /// Cached extractions, keyed by `(slogan_url, extractor_kind, slogan_expression)` so that two
+    /// sources sharing a URL but extracting different content don't clobber each other's cache.
+    url_to_cache_entry: Mutex<HashMap<(String, ExtractorKind, String), CacheEntry>>,
+    rng: Mutex<StdRng>,
 }
 impl SloganPlugin {
-    async fn generate_slogan(&self, config: &Config, subject: &str) -> Option<String> {
+    /// Fetches `source.slogan_url`, cleans it up and extracts the slogan text from it, without
+    /// touching the cache or substituting the subject placeholder.
+    async fn fetch_and_extract(source: &Source) -> Option<String> {
         // obtain URL content
-        let response = match reqwest::get(&config.slogan_url).await {
+        let response = match reqwest::get(&source.slogan_url).await {
             Ok(r) => r,
             Err(e) => {
-                error!("failed to obtain {} response: {}", config.slogan_url, e);
+                error!("failed to obtain {} response: {}", source.slogan_url, e);
                 return None;
             },
         };
         if response.status() != 200 {
-            error!("response from {} is {}", config.slogan_url, response.status());
+            error!("response from {} is {}", source.slogan_url, response.status());
             return None;
         }
         let mut response_text = match response.text().await {
             Ok(rt) => rt,
             Err(e) => {
-                error!("failed to open {} response text: {}", config.slogan_url, e);
+                error!("failed to open {} response text: {}", source.slogan_url, e);
                 return None;
             },
         };
 
         // apply cleanup regexes
-        for clean_regex in &config.cleanup_regexes {
+        for clean_regex in &source.cleanup_regexes {
             response_text = clean_regex.regex
                 .replace_all(&response_text, &clean_regex.replacement)
                 .into_owned();
         }
 
-        // parse
-        let doc_package = match sxd_document::parser::parse(&response_text) {
-            Ok(dp) => dp,
-            Err(e) => {
-                error!("failed to parse {} response: {}", config.slogan_url, e);
-                debug!("document content is: {:?}", response_text);
-                return None;
-            },
-        };
+        // extract the slogan text using whichever engine is configured
+        match source.extractor_kind {
+            ExtractorKind::Xpath => extract_xpath(&source.slogan_url, &source.slogan_expression, &response_text),
+            ExtractorKind::JsonPath => extract_json_path(&source.slogan_url, &source.slogan_expression, &response_text),
+            ExtractorKind::Css => extract_css(&source.slogan_url, &source.slogan_expression, &response_text),
+        }
+    }
 
-        // apply xpath
-        let xpath_factory = sxd_xpath::Factory::new();
-        let xpath = match xpath_factory.build(&config.slogan_xpath) {
-            Ok(Some(xp)) => xp,
-            Ok(None) => {
-                error!("XPath {:?} generated a None value", config.slogan_xpath);
-                return None;
-            },
-            Err(e) => {
-                error!("failed to parse XPath {:?}: {}", config.slogan_xpath, e);
-                return None;
-            },
-        };
-        let mut xpath_ctx = sxd_xpath::Context::new();
-        xpath_ctx.set_namespace("h", "http://www.w3.org/1999/xhtml");
-        let xpath_result = match xpath.evaluate(&xpath_ctx, doc_package.as_document().root()) {
-            Ok(r) => r,
-            Err(e) => {
-                error!("failed to evaluate XPath {:?}: {}", config.slogan_xpath, e);
-                return None;
-            },
-        };
-        let xpath_string = match xpath_result {
-            sxd_xpath::Value::String(s) => {
-                s
-            },
-            sxd_xpath::Value::Nodeset(nodeset) => {
-                let mut total_text = String::new();
-                for node in nodeset.document_order() {
-                    if let Some(t) = node.text() {
-                        total_text.push_str(t.text());
-                    } else if let Some(elem) = node.element() {
-                        let s = collect_element_strings(&elem);
-                        total_text.push_str(&s);
-                    }
+    /// Returns the cached or freshly fetched+extracted slogan text for `source.slogan_url`,
+    /// honoring `cache_ttl_seconds` (serve the cached value without touching the network) and
+    /// `min_fetch_interval_seconds` (never fetch more often than this, even if the cache has
+    /// already expired; a stale cached value is served instead). Falls back to a stale cached
+    /// value if the source is currently unreachable.
+    async fn obtain_extracted_string(&self, source: &Source, cache_ttl_seconds: u64, min_fetch_interval_seconds: u64) -> Option<String> {
+        let now = Instant::now();
+        let cache_key = (source.slogan_url.clone(), source.extractor_kind, source.slogan_expression.clone());
+
+        {
+            let cache_guard = self.url_to_cache_entry.lock().await;
+            if let Some(entry) = cache_guard.get(&cache_key) {
+                let age_secs = now.duration_since(entry.fetched_at).as_secs();
+                if age_secs < cache_ttl_seconds {
+                    return Some(entry.extracted_string.clone());
                 }
-                total_text
+                if age_secs < min_fetch_interval_seconds {
+                    debug!("not refetching {} yet; min_fetch_interval_seconds not elapsed", source.slogan_url);
+                    return Some(entry.extracted_string.clone());
+                }
+            }
+        }
+
+        // don't hold the cache lock across the network request, or a slow/stuck fetch would
+        // block every other invocation (even ones that could be served from the cache)
+        match Self::fetch_and_extract(source).await {
+            Some(fresh) => {
+                let mut cache_guard = self.url_to_cache_entry.lock().await;
+                cache_guard.insert(cache_key, CacheEntry {
+                    extracted_string: fresh.clone(),
+                    fetched_at: now,
+                });
+                Some(fresh)
             },
-            other => {
-                error!("XPath {:?} returned {:?}, not a string value", config.slogan_xpath, other);
-                return None;
+            None => {
+                let cache_guard = self.url_to_cache_entry.lock().await;
+                match cache_guard.get(&cache_key) {
+                    Some(entry) => {
+                        debug!("{} is unreachable; serving stale cached content", source.slogan_url);
+                        Some(entry.extracted_string.clone())
+                    },
+                    None => None,
+                }
             },
-        };
+        }
+    }
+
+    /// Returns the indices of `sources`, drawn without replacement using weighted random
+    /// selection (each source's `weight`, floored to at least 1). The result is the order in
+    /// which `generate_slogan` tries sources, falling through on failure.
+    async fn weighted_source_order(&self, sources: &[Source]) -> Vec<usize> {
+        let mut remaining: Vec<usize> = (0..sources.len()).collect();
+        let mut order = Vec::with_capacity(sources.len());
+
+        let mut rng_guard = self.rng.lock().await;
+        while !remaining.is_empty() {
+            // sum as u64 so that several large (but individually valid) u32 weights can't overflow
+            let total_weight: u64 = remaining.iter()
+                .map(|&i| u64::from(sources[i].weight.max(1)))
+                .sum();
+            let mut pick = rng_guard.gen_range(0..total_weight);
+
+            let mut chosen_pos = remaining.len() - 1;
+            for (pos, &i) in remaining.iter().enumerate() {
+                let weight = u64::from(sources[i].weight.max(1));
+                if pick < weight {
+                    chosen_pos = pos;
+                    break;
+                }
+                pick -= weight;
+            }
+
+            order.push(remaining.remove(chosen_pos));
+        }
+
+        order
+    }
+
+    async fn generate_slogan(&self, config: &Config, subject: &str) -> Option<String> {
+        if config.sources.is_empty() {
+            return None;
+        }
 
-        let response_string = xpath_string
-            .replace(&config.subject_placeholder, &format!("*{}*", subject));
+        let order = self.weighted_source_order(&config.sources).await;
+        for index in order {
+            let source = &config.sources[index];
+            if let Some(extracted_string) = self.obtain_extracted_string(
+                source,
+                config.cache_ttl_seconds,
+                config.min_fetch_interval_seconds,
+            ).await {
+                let response_string = extracted_string
+                    .replace(&config.subject_placeholder, &format!("*{}*", subject));
+                return Some(response_string);
+            }
+        }
 
-        Some(response_string)
+        None
     }
 
-    fn try_get_config(config: serde_json::Value) -> Result<Config, &'static str> {
-        let slogan_url = config["slogan_url"]
-            .as_str().ok_or("slogan_url is not a string")?
+    fn try_get_source(source: &serde_json::Value) -> Result<Source, &'static str> {
+        let slogan_url = source["slogan_url"]
+            .as_str().ok_or("sources[...].slogan_url is not a string")?
             .to_owned();
 
         let mut cleanup_regexes = Vec::new();
-        for cleanup_regex_obj in config["cleanup_regexes"].as_array().ok_or("cleanup_regexes not an array")?.iter() {
+        for cleanup_regex_obj in source["cleanup_regexes"].as_array().ok_or("sources[...].cleanup_regexes not an array")?.iter() {
             let regex_str = cleanup_regex_obj["regex"]
-                .as_str().ok_or("cleanup_regexes[...].regex not a string")?;
+                .as_str().ok_or("sources[...].cleanup_regexes[...].regex not a string")?;
             let regex = Regex::new(regex_str)
-                .or_msg("failed to parse cleanup_regexes[...].regex")?;
+                .or_msg("failed to parse sources[...].cleanup_regexes[...].regex")?;
 
             let replacement = cleanup_regex_obj["replacement"]
-                .as_str().ok_or("cleanup_regexes[...].replacement not a string")?
+                .as_str().ok_or("sources[...].cleanup_regexes[...].replacement not a string")?
                 .to_owned();
 
             cleanup_regexes.push(CleanupRegex {
@@ -145,18 +240,59 @@ impl SloganPlugin {
             })
         }
 
-        let slogan_xpath = config["slogan_xpath"]
-            .as_str().ok_or("slogan_xpath is not a string")?
+        let extractor_kind_val = &source["extractor_kind"];
+        let extractor_kind = if extractor_kind_val.is_null() {
+            ExtractorKind::Xpath
+        } else {
+            match extractor_kind_val.as_str().ok_or("sources[...].extractor_kind is not a string")? {
+                "xpath" => ExtractorKind::Xpath,
+                "jsonpath" => ExtractorKind::JsonPath,
+                "css" => ExtractorKind::Css,
+                _ => return Err("sources[...].extractor_kind must be one of \"xpath\", \"jsonpath\", \"css\""),
+            }
+        };
+
+        let slogan_expression = source["slogan_expression"]
+            .as_str().ok_or("sources[...].slogan_expression is not a string")?
             .to_owned();
+
+        let weight_val = &source["weight"];
+        let weight = if weight_val.is_null() {
+            1
+        } else {
+            u32::try_from(weight_val.as_u64().ok_or("sources[...].weight is not representable as u32")?)
+                .or_msg("sources[...].weight is not representable as u32")?
+        };
+
+        Ok(Source {
+            slogan_url,
+            cleanup_regexes,
+            extractor_kind,
+            slogan_expression,
+            weight,
+        })
+    }
+
+    fn try_get_config(config: serde_json::Value) -> Result<Config, &'static str> {
+        let mut sources = Vec::new();
+        for source_obj in config["sources"].as_array().ok_or("sources not an array")?.iter() {
+            sources.push(Self::try_get_source(source_obj)?);
+        }
+
         let subject_placeholder = config["subject_placeholder"]
             .as_str().ok_or("subject_placeholder is not a string")?
             .to_owned();
 
+        let cache_ttl_seconds = config["cache_ttl_seconds"].as_u64()
+            .ok_or("cache_ttl_seconds missing or not representable as u64")?;
+        let min_fetch_interval_seconds = config["min_fetch_interval_seconds"].as_u64()
+            .ok_or("min_fetch_interval_seconds missing or not representable as u64")?;
+
         Ok(Config {
-            slogan_url,
-            cleanup_regexes,
-            slogan_xpath,
+            sources,
             subject_placeholder,
+            cache_ttl_seconds,
+            min_fetch_interval_seconds,
         })
     }
 }
@@ -174,6 +310,14 @@ impl RocketBotPlugin for SloganPlugin {
             "SloganPlugin::config",
             config_object,
         );
+        let url_to_cache_entry = Mutex::new(
+            "SloganPlugin::url_to_cache_entry",
+            HashMap::new(),
+        );
+        let rng = Mutex::new(
+            "SloganPlugin::rng",
+            StdRng::from_entropy(),
+        );
 
         let slogan_command = CommandDefinitionBuilder::new(
             "slogan".to_owned(),
@@ -188,6 +332,8 @@ impl RocketBotPlugin for SloganPlugin {
         Self {
             interface,
             config: config_lock,
+            url_to_cache_entry,
+            rng,
         }
     }
 
@@ -260,6 +406,10 @@ impl RocketBotPlugin for SloganPlugin {
             Ok(c) => {
                 let mut config_guard = self.config.write().await;
                 *config_guard = c;
+
+                // the cached extraction may have been produced with the old extractor/expression
+                self.url_to_cache_entry.lock().await.clear();
+
                 true
             },
             Err(e) => {
@@ -286,3 +436,168 @@ fn collect_element_strings(element: &Element) -> String {
     }
     total_text
 }
+
+/// Mirrors [`collect_element_strings`]'s text-flattening (treating `<br>` as a space) for elements
+/// obtained via the `scraper` crate instead of `sxd_document`.
+fn collect_scraper_element_strings(element: &scraper::ElementRef) -> String {
+    if element.value().name() == "br" {
+        return " ".to_owned();
+    }
+
+    let mut total_text = String::new();
+    for child in element.children() {
+        if let Some(text) = child.value().as_text() {
+            total_text.push_str(text);
+        } else if let Some(child_element) = scraper::ElementRef::wrap(child) {
+            total_text.push_str(&collect_scraper_element_strings(&child_element));
+        }
+    }
+    total_text
+}
+
+fn extract_xpath(source_url: &str, xpath_str: &str, response_text: &str) -> Option<String> {
+    let doc_package = match sxd_document::parser::parse(response_text) {
+        Ok(dp) => dp,
+        Err(e) => {
+            error!("failed to parse {} response: {}", source_url, e);
+            debug!("document content is: {:?}", response_text);
+            return None;
+        },
+    };
+
+    let xpath_factory = sxd_xpath::Factory::new();
+    let xpath = match xpath_factory.build(xpath_str) {
+        Ok(Some(xp)) => xp,
+        Ok(None) => {
+            error!("XPath {:?} generated a None value", xpath_str);
+            return None;
+        },
+        Err(e) => {
+            error!("failed to parse XPath {:?}: {}", xpath_str, e);
+            return None;
+        },
+    };
+    let mut xpath_ctx = sxd_xpath::Context::new();
+    xpath_ctx.set_namespace("h", "http://www.w3.org/1999/xhtml");
+    let xpath_result = match xpath.evaluate(&xpath_ctx, doc_package.as_document().root()) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("failed to evaluate XPath {:?}: {}", xpath_str, e);
+            return None;
+        },
+    };
+    let xpath_string = match xpath_result {
+        sxd_xpath::Value::String(s) => {
+            s
+        },
+        sxd_xpath::Value::Nodeset(nodeset) => {
+            let mut total_text = String::new();
+            for node in nodeset.document_order() {
+                if let Some(t) = node.text() {
+                    total_text.push_str(t.text());
+                } else if let Some(elem) = node.element() {
+                    let s = collect_element_strings(&elem);
+                    total_text.push_str(&s);
+                }
+            }
+            total_text
+        },
+        other => {
+            error!("XPath {:?} returned {:?}, not a string value", xpath_str, other);
+            return None;
+        },
+    };
+
+    Some(xpath_string)
+}
+
+/// Walks a minimal JSONPath-style dotted/indexed path (e.g. `foo.bar[0].baz`) into `value`,
+/// returning the value found at its end, or `None` if any segment does not resolve.
+fn walk_json_path<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let mut current = value;
+
+    for segment in path.split('.') {
+        if segment.is_empty() {
+            continue;
+        }
+
+        let bracket_pos = segment.find('[').unwrap_or(segment.len());
+        let (name, indices_str) = segment.split_at(bracket_pos);
+
+        if !name.is_empty() {
+            current = current.get(name)?;
+        }
+
+        for index_part in indices_str.split('[') {
+            if index_part.is_empty() {
+                continue;
+            }
+            let index_str = index_part.strip_suffix(']')?;
+            let index: usize = index_str.parse().ok()?;
+            current = current.get(index)?;
+        }
+    }
+
+    Some(current)
+}
+
+/// Concatenates the string (and stringified scalar) leaves of `value`, recursing into arrays and
+/// objects, in the same spirit as the XPath extractor's nodeset-to-string flattening.
+fn collect_json_strings(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::String(s) => out.push_str(s),
+        serde_json::Value::Number(n) => out.push_str(&n.to_string()),
+        serde_json::Value::Bool(b) => out.push_str(&b.to_string()),
+        serde_json::Value::Array(arr) => {
+            for item in arr {
+                collect_json_strings(item, out);
+            }
+        },
+        serde_json::Value::Object(obj) => {
+            for item in obj.values() {
+                collect_json_strings(item, out);
+            }
+        },
+        serde_json::Value::Null => {},
+    }
+}
+
+fn extract_json_path(source_url: &str, path_str: &str, response_text: &str) -> Option<String> {
+    let json_value: serde_json::Value = match serde_json::from_str(response_text) {
+        Ok(v) => v,
+        Err(e) => {
+            error!("failed to parse {} response as JSON: {}", source_url, e);
+            debug!("document content is: {:?}", response_text);
+            return None;
+        },
+    };
+
+    let target = match walk_json_path(&json_value, path_str) {
+        Some(v) => v,
+        None => {
+            error!("JSONPath {:?} did not resolve within the response", path_str);
+            return None;
+        },
+    };
+
+    let mut total_text = String::new();
+    collect_json_strings(target, &mut total_text);
+    Some(total_text)
+}
+
+fn extract_css(source_url: &str, selector_str: &str, response_text: &str) -> Option<String> {
+    let selector = match scraper::Selector::parse(selector_str) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("failed to parse CSS selector {:?} for {}: {:?}", selector_str, source_url, e);
+            return None;
+        },
+    };
+
+    let document = scraper::Html::parse_document(response_text);
+    let mut total_text = String::new();
+    for element in document.select(&selector) {
+        total_text.push_str(&collect_scraper_element_strings(&element));
+    }
+    Some(total_text)
+}
@@ -1,27 +1,156 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Weak;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
 use log::error;
-use rocketbot_interface::{JsonValueExtensions, send_channel_message_advanced};
+use regex::Regex;
+use rocketbot_interface::{JsonValueExtensions, send_channel_message_advanced, send_private_message};
 use rocketbot_interface::commands::{CommandDefinitionBuilder, CommandInstance, CommandValueType};
+use rocketbot_interface::hooks::{hooks_from_config, HookContext, HookRegistry, HookVerdict};
 use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
 use rocketbot_interface::model::{ImpersonationInfo, OutgoingMessage, PrivateMessage};
-use rocketbot_interface::sync::RwLock;
+use rocketbot_interface::sync::{Mutex, RwLock};
 use serde_json;
 
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// Parses a duration specification such as `1h30m` or `45s` (a sequence of `<amount><unit>`
+/// components, where `unit` is one of `d`, `h`, `m` or `s`) into a [`chrono::Duration`]. Returns
+/// `None` if the specification is empty or contains anything that isn't such a component.
+fn parse_duration_spec(spec: &str) -> Option<chrono::Duration> {
+    let component_regex = Regex::new("(\\d+)([dhms])").unwrap();
+
+    let mut total = chrono::Duration::zero();
+    let mut consumed = 0;
+    for cap in component_regex.captures_iter(spec) {
+        let whole_match = cap.get(0).unwrap();
+        if whole_match.start() != consumed {
+            // gap (or garbage) before this component
+            return None;
+        }
+        consumed = whole_match.end();
+
+        let amount: i64 = cap[1].parse().ok()?;
+        let component = match &cap[2] {
+            "d" => chrono::Duration::days(amount),
+            "h" => chrono::Duration::hours(amount),
+            "m" => chrono::Duration::minutes(amount),
+            "s" => chrono::Duration::seconds(amount),
+            _ => unreachable!(),
+        };
+        total = total + component;
+    }
+
+    if consumed == 0 || consumed != spec.len() {
+        None
+    } else {
+        Some(total)
+    }
+}
+
+
+/// A channel message whose delivery has been deferred to `fire_time`.
+#[derive(Clone, Debug)]
+struct PendingSend {
+    channel_name: String,
+    body: String,
+    impersonation: Option<ImpersonationInfo>,
+    fire_time: DateTime<Utc>,
+}
+
+
+/// A capability a user may be granted over the sockpuppet plugin's private commands.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Capability {
+    /// May send plain (non-impersonated) messages into a channel via `{cpfx}chansend`.
+    Chansend,
+
+    /// May additionally impersonate another user via `{cpfx}chansend --impersonate`.
+    Impersonate,
+
+    /// May add or remove reactions via `{cpfx}react`.
+    React,
+
+    /// May reload the bot's configuration via `{cpfx}reload`.
+    Reload,
+}
+impl Capability {
+    fn try_from_str(s: &str) -> Option<Self> {
+        match s {
+            "chansend" => Some(Self::Chansend),
+            "impersonate" => Some(Self::Impersonate),
+            "react" => Some(Self::React),
+            "reload" => Some(Self::Reload),
+            _ => None,
+        }
+    }
+}
+
+/// The capabilities granted to a single user, plus any per-capability scoping.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct UserGrant {
+    capabilities: HashSet<Capability>,
+
+    /// The channels this user may `{cpfx}chansend` into. `None` means any channel is allowed;
+    /// `Some(_)` restricts `Capability::Chansend` to the listed channels.
+    chansend_channels: Option<HashSet<String>>,
+}
+impl UserGrant {
+    fn has_capability(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    fn may_chansend_into(&self, channel_name: &str) -> bool {
+        if !self.has_capability(Capability::Chansend) {
+            return false;
+        }
+        match &self.chansend_channels {
+            Some(channels) => channels.contains(channel_name),
+            None => true,
+        }
+    }
+}
+
+#[derive(Clone)]
 struct Config {
-    allowed_usernames: HashSet<String>,
+    user_grants: HashMap<String, UserGrant>,
+
+    /// The hooks (see `rocketbot_interface::hooks`) that commands may be gated behind, looked up
+    /// by the names used in `command_hooks`.
+    hook_registry: HookRegistry,
+
+    /// The ordered list of hook names (evaluated in `hook_registry`) that must allow a command
+    /// before it is dispatched. Commands without an entry here are not gated by a hook (they are
+    /// still subject to the capability checks in `user_grants`).
+    command_hooks: HashMap<String, Vec<String>>,
+}
+impl Config {
+    fn grant_for(&self, username: &str) -> Option<&UserGrant> {
+        self.user_grants.get(username)
+    }
 }
 
 
 pub struct SockpuppetPlugin {
     interface: Weak<dyn RocketBotInterface>,
     config: RwLock<Config>,
+
+    /// Channel messages scheduled (via `{cpfx}chansend --after`/`--at`) for later delivery, keyed
+    /// by the id under which they can be listed or cancelled using `{cpfx}chanqueue`. Kept outside
+    /// `config` so that a `{cpfx}reload` does not lose pending entries.
+    pending_sends: Mutex<HashMap<u64, PendingSend>>,
+    next_pending_send_id: AtomicU64,
 }
 impl SockpuppetPlugin {
+    async fn deny(&self, interface: &dyn RocketBotInterface, private_message: &PrivateMessage) {
+        send_private_message!(
+            interface,
+            &private_message.conversation.id,
+            "You are not permitted to do that.",
+        ).await;
+    }
+
     async fn private_command_chansend(&self, private_message: &PrivateMessage, command: &CommandInstance) {
         let interface = match self.interface.upgrade() {
             None => return,
@@ -30,14 +159,27 @@ impl SockpuppetPlugin {
 
         let config_guard = self.config.read().await;
 
-        if !config_guard.allowed_usernames.contains(&private_message.message.sender.username) {
+        let channel_name = command.args[0].clone();
+        let grant = config_guard.grant_for(&private_message.message.sender.username);
+        let may_chansend = grant
+            .map(|g| g.may_chansend_into(&channel_name))
+            .unwrap_or(false);
+        if !may_chansend {
+            self.deny(&*interface, private_message).await;
             return;
         }
 
-        let channel_name = command.args[0].clone();
         let message_body = command.rest.clone();
 
         let impersonation = if let Some(imp_username_val) = command.options.get("impersonate") {
+            let may_impersonate = grant
+                .map(|g| g.has_capability(Capability::Impersonate))
+                .unwrap_or(false);
+            if !may_impersonate {
+                self.deny(&*interface, private_message).await;
+                return;
+            }
+
             let imp_username = imp_username_val.as_str().expect("--impersonate value is string");
 
             let channel_users_opt = interface.obtain_users_in_channel(&channel_name).await;
@@ -60,6 +202,56 @@ impl SockpuppetPlugin {
             None
         };
 
+        let fire_time = if let Some(after_value) = command.options.get("after") {
+            let after_str = after_value.as_str().expect("--after value is string");
+            match parse_duration_spec(after_str) {
+                Some(duration) => Some(Utc::now() + duration),
+                None => {
+                    send_private_message!(
+                        interface,
+                        &private_message.conversation.id,
+                        format!("Failed to parse duration {:?}.", after_str),
+                    ).await;
+                    return;
+                },
+            }
+        } else if let Some(at_value) = command.options.get("at") {
+            let at_str = at_value.as_str().expect("--at value is string");
+            match Utc.datetime_from_str(at_str, "%Y-%m-%d %H:%M:%S") {
+                Ok(ts) => Some(ts),
+                Err(_) => {
+                    send_private_message!(
+                        interface,
+                        &private_message.conversation.id,
+                        format!("Failed to parse timestamp {:?} (expected format: YYYY-MM-DD HH:MM:SS).", at_str),
+                    ).await;
+                    return;
+                },
+            }
+        } else {
+            None
+        };
+
+        if let Some(fire_time) = fire_time {
+            let id = self.next_pending_send_id.fetch_add(1, Ordering::SeqCst);
+            {
+                let mut pending_sends_guard = self.pending_sends.lock().await;
+                pending_sends_guard.insert(id, PendingSend {
+                    channel_name,
+                    body: message_body,
+                    impersonation,
+                    fire_time,
+                });
+            }
+            interface.register_timer(fire_time, serde_json::json!(["sockpuppet_chansend", id])).await;
+            send_private_message!(
+                interface,
+                &private_message.conversation.id,
+                format!("Scheduled as #{} for {}.", id, fire_time.format("%Y-%m-%d %H:%M:%S")),
+            ).await;
+            return;
+        }
+
         let outgoing_message = OutgoingMessage::new(
             message_body,
             impersonation,
@@ -68,6 +260,64 @@ impl SockpuppetPlugin {
         send_channel_message_advanced!(interface, &channel_name, outgoing_message).await;
     }
 
+    async fn private_command_chanqueue(&self, private_message: &PrivateMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let may_chansend = {
+            let config_guard = self.config.read().await;
+            config_guard.grant_for(&private_message.message.sender.username)
+                .map(|g| g.has_capability(Capability::Chansend))
+                .unwrap_or(false)
+        };
+        if !may_chansend {
+            self.deny(&*interface, private_message).await;
+            return;
+        }
+
+        if let Some(cancel_value) = command.options.get("cancel") {
+            let cancel_id = match cancel_value.as_i64() {
+                Some(i) if i >= 0 => i as u64,
+                _ => {
+                    send_private_message!(interface, &private_message.conversation.id, "Invalid id.").await;
+                    return;
+                },
+            };
+
+            let removed = {
+                let mut pending_sends_guard = self.pending_sends.lock().await;
+                pending_sends_guard.remove(&cancel_id)
+            };
+            let response = if removed.is_some() {
+                format!("Cancelled scheduled message #{}.", cancel_id)
+            } else {
+                format!("No scheduled message with id #{}.", cancel_id)
+            };
+            send_private_message!(interface, &private_message.conversation.id, response).await;
+            return;
+        }
+
+        let pending_sends_guard = self.pending_sends.lock().await;
+        if pending_sends_guard.len() == 0 {
+            send_private_message!(interface, &private_message.conversation.id, "No scheduled messages pending.").await;
+            return;
+        }
+
+        let mut ids: Vec<u64> = pending_sends_guard.keys().copied().collect();
+        ids.sort();
+        let mut lines = Vec::new();
+        for id in ids {
+            let pending = &pending_sends_guard[&id];
+            lines.push(format!(
+                "#{}: {} @ {} -> {:?}",
+                id, pending.channel_name, pending.fire_time.format("%Y-%m-%d %H:%M:%S"), pending.body,
+            ));
+        }
+        send_private_message!(interface, &private_message.conversation.id, lines.join("\n")).await;
+    }
+
     async fn private_command_react(&self, private_message: &PrivateMessage, command: &CommandInstance) {
         let interface = match self.interface.upgrade() {
             None => return,
@@ -76,7 +326,11 @@ impl SockpuppetPlugin {
 
         let config_guard = self.config.read().await;
 
-        if !config_guard.allowed_usernames.contains(&private_message.message.sender.username) {
+        let may_react = config_guard.grant_for(&private_message.message.sender.username)
+            .map(|g| g.has_capability(Capability::React))
+            .unwrap_or(false);
+        if !may_react {
+            self.deny(&*interface, private_message).await;
             return;
         }
 
@@ -101,7 +355,11 @@ impl SockpuppetPlugin {
         {
             let config_guard = self.config.read().await;
 
-            if !config_guard.allowed_usernames.contains(&private_message.message.sender.username) {
+            let may_reload = config_guard.grant_for(&private_message.message.sender.username)
+                .map(|g| g.has_capability(Capability::Reload))
+                .unwrap_or(false);
+            if !may_reload {
+                self.deny(&*interface, private_message).await;
                 return;
             }
         }
@@ -110,15 +368,47 @@ impl SockpuppetPlugin {
     }
 
     fn try_get_config(config: serde_json::Value) -> Result<Config, &'static str> {
-        let mut allowed_usernames = HashSet::new();
-        for username_value in config["allowed_usernames"].members().ok_or("allowed_usernames not a list")? {
-            let username = username_value
-                .as_str().ok_or("entry in allowed_usernames not a string")?;
-            allowed_usernames.insert(username.to_owned());
+        let mut user_grants = HashMap::new();
+        for (username, grant_value) in config["users"].entries().ok_or("users not a dict")? {
+            let mut capabilities = HashSet::new();
+            for capability_value in grant_value["capabilities"].members().ok_or("capabilities not a list")? {
+                let capability_str = capability_value
+                    .as_str().ok_or("entry in capabilities not a string")?;
+                let capability = Capability::try_from_str(capability_str)
+                    .ok_or("unknown capability")?;
+                capabilities.insert(capability);
+            }
+
+            let chansend_channels = if grant_value["chansend_channels"].is_null() {
+                None
+            } else {
+                let mut channels = HashSet::new();
+                for channel_value in grant_value["chansend_channels"].members().ok_or("chansend_channels not a list")? {
+                    let channel_name = channel_value
+                        .as_str().ok_or("entry in chansend_channels not a string")?;
+                    channels.insert(channel_name.to_owned());
+                }
+                Some(channels)
+            };
+
+            user_grants.insert(username.clone(), UserGrant { capabilities, chansend_channels });
+        }
+
+        let hook_registry = hooks_from_config(&config["hooks"])?;
+        let mut command_hooks = HashMap::new();
+        for (cmd_name, hook_names_value) in config["command_hooks"].entries_or_empty() {
+            let mut hook_names = Vec::new();
+            for hook_name_value in hook_names_value.members().ok_or("command_hooks entry is not a list")? {
+                let hook_name = hook_name_value.as_str().ok_or("hook name is not a string")?;
+                hook_names.push(hook_name.to_owned());
+            }
+            command_hooks.insert(cmd_name.clone(), hook_names);
         }
 
         Ok(Config {
-            allowed_usernames,
+            user_grants,
+            hook_registry,
+            command_hooks,
         })
     }
 }
@@ -140,10 +430,12 @@ impl RocketBotPlugin for SockpuppetPlugin {
         let chansend_command = CommandDefinitionBuilder::new(
             "chansend",
             "sockpuppet",
-            "{cpfx}chansend [{lopfx}impersonate USERNAME] CHANNEL MESSAGE",
-            "Sends a message, pretending to be the bot or someone else.",
+            "{cpfx}chansend [{lopfx}impersonate USERNAME] [{lopfx}after DURATION|{lopfx}at TIMESTAMP] CHANNEL MESSAGE",
+            "Sends a message, pretending to be the bot or someone else, immediately or at a later time.",
         )
             .add_option("impersonate", CommandValueType::String)
+            .add_option("after", CommandValueType::String)
+            .add_option("at", CommandValueType::String)
             .arg_count(1)
             .build();
         my_interface.register_private_message_command(&chansend_command).await;
@@ -169,10 +461,22 @@ impl RocketBotPlugin for SockpuppetPlugin {
             )
                 .build()
         ).await;
+        my_interface.register_private_message_command(
+            &CommandDefinitionBuilder::new(
+                "chanqueue",
+                "sockpuppet",
+                "{cpfx}chanqueue [{lopfx}cancel ID]",
+                "Lists or cancels messages scheduled using {cpfx}chansend --after/--at.",
+            )
+                .add_option("cancel", CommandValueType::Integer)
+                .build()
+        ).await;
 
         SockpuppetPlugin {
             interface,
             config: config_lock,
+            pending_sends: Mutex::new("SockpuppetPlugin::pending_sends", HashMap::new()),
+            next_pending_send_id: AtomicU64::new(0),
         }
     }
 
@@ -181,12 +485,42 @@ impl RocketBotPlugin for SockpuppetPlugin {
     }
 
     async fn private_command(&self, private_message: &PrivateMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        // commands that take a channel as their first argument expose it to channel-scoped hooks
+        let channel_name = if command.name == "chansend" {
+            command.args.get(0).cloned()
+        } else {
+            None
+        };
+        let hook_verdict = {
+            let config_guard = self.config.read().await;
+            let hook_names = config_guard.command_hooks.get(&command.name)
+                .cloned().unwrap_or_default();
+            let context = HookContext {
+                command_name: command.name.clone(),
+                channel_name,
+                sender_username: private_message.message.sender.username.clone(),
+            };
+            config_guard.hook_registry.evaluate(&hook_names, &context).await
+        };
+        if let HookVerdict::Deny { feedback } = hook_verdict {
+            let message = feedback.unwrap_or_else(|| "You are not permitted to do that.".to_owned());
+            send_private_message!(interface, &private_message.conversation.id, message).await;
+            return;
+        }
+
         if command.name == "chansend" {
             self.private_command_chansend(private_message, command).await
         } else if command.name == "react" {
             self.private_command_react(private_message, command).await
         } else if command.name == "reload" {
             self.private_command_reload(private_message, command).await
+        } else if command.name == "chanqueue" {
+            self.private_command_chanqueue(private_message, command).await
         }
     }
 
@@ -197,11 +531,45 @@ impl RocketBotPlugin for SockpuppetPlugin {
             Some(include_str!("../help/react.md").to_owned())
         } else if command_name == "reload" {
             Some(include_str!("../help/reload.md").to_owned())
+        } else if command_name == "chanqueue" {
+            Some(include_str!("../help/chanqueue.md").to_owned())
         } else {
             None
         }
     }
 
+    async fn timer_elapsed(&self, custom_data: &serde_json::Value) {
+        if custom_data[0] != "sockpuppet_chansend" {
+            return;
+        }
+        let id = match custom_data[1].as_u64() {
+            Some(i) => i,
+            None => return,
+        };
+
+        let pending = {
+            let mut pending_sends_guard = self.pending_sends.lock().await;
+            pending_sends_guard.remove(&id)
+        };
+        // if the entry is gone, the scheduled send has been cancelled in the meantime
+        let pending = match pending {
+            Some(p) => p,
+            None => return,
+        };
+
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let outgoing_message = OutgoingMessage::new(
+            pending.body,
+            pending.impersonation,
+            None,
+        );
+        send_channel_message_advanced!(interface, &pending.channel_name, outgoing_message).await;
+    }
+
     async fn configuration_updated(&self, new_config: serde_json::Value) -> bool {
         match Self::try_get_config(new_config) {
             Ok(c) => {
@@ -1,3 +1,6 @@
+mod message_split;
+mod rate_limiter;
+
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::sync::{Arc, Weak};
@@ -7,12 +10,16 @@ use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
 use rocketbot_interface::{JsonValueExtensions, send_channel_message};
 use rocketbot_interface::commands::{CommandDefinitionBuilder, CommandInstance};
+use rocketbot_interface::hooks::{hooks_from_config, HookContext, HookRegistry, HookVerdict};
 use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
 use rocketbot_interface::model::ChannelMessage;
 use rocketbot_interface::sync::{Mutex, RwLock};
 use serde_json;
 use tracing::{debug, error};
 
+use crate::message_split::split_message;
+use crate::rate_limiter::RateLimiter;
+
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 struct MadLibsCommand {
@@ -22,17 +29,54 @@ struct MadLibsCommand {
     response_templates: Vec<String>,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// The token-bucket capacity and refill rate used to rate-limit replies to a command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct RateLimitSettings {
+    capacity: f64,
+    refill_per_sec: f64,
+}
+
+#[derive(Clone)]
 struct Config {
     commands_responses: HashMap<String, Vec<String>>,
     nicknamable_commands_responses: HashMap<String, Vec<String>>,
     mad_libs_commands: HashMap<String, MadLibsCommand>,
+
+    /// The rate limit applied to a command if it has no entry in `command_rate_limits`. `None`
+    /// means commands are not rate-limited unless individually configured.
+    default_rate_limit: Option<RateLimitSettings>,
+
+    /// Per-command overrides of `default_rate_limit`.
+    command_rate_limits: HashMap<String, RateLimitSettings>,
+
+    /// The maximum length, in characters, a single rendered response may have before it is split
+    /// across several messages (for commands listed in `split_long_responses`). `None` falls back
+    /// to `RocketBotInterface::get_maximum_message_length`.
+    max_message_len: Option<usize>,
+
+    /// The commands whose rendered response is split across several messages (on line, then word
+    /// boundaries) if it exceeds `max_message_len`, rather than being sent as a single, possibly
+    /// oversized message.
+    split_long_responses: HashSet<String>,
+
+    /// The hooks (see `rocketbot_interface::hooks`) that commands may be gated behind, looked up
+    /// by the names used in `command_hooks`.
+    hook_registry: HookRegistry,
+
+    /// The ordered list of hook names (evaluated in `hook_registry`) that must allow a command
+    /// before it is dispatched. Commands without an entry here are not gated.
+    command_hooks: HashMap<String, Vec<String>>,
 }
 
 pub struct TextCommandsPlugin {
     interface: Weak<dyn RocketBotInterface>,
     config: RwLock<Config>,
     rng: Mutex<StdRng>,
+
+    /// Token buckets, one per channel (or, for commands with their own rate limit override, one
+    /// per channel and command), shared across the plain, nicknamable and mad-libs command
+    /// families.
+    rate_limiters: Mutex<HashMap<String, RateLimiter>>,
 }
 impl TextCommandsPlugin {
     fn collect_commands(config_dict: &serde_json::Value) -> Result<HashMap<String, Vec<String>>, &'static str> {
@@ -106,6 +150,17 @@ impl TextCommandsPlugin {
         interface.register_channel_command(&command).await;
     }
 
+    fn try_get_rate_limit_settings(value: &serde_json::Value) -> Result<Option<RateLimitSettings>, &'static str> {
+        if value.is_null() {
+            return Ok(None);
+        }
+        let capacity = value["capacity"].as_f64()
+            .ok_or("rate limit capacity is not a number")?;
+        let refill_per_sec = value["refill_per_sec"].as_f64()
+            .ok_or("rate limit refill_per_sec is not a number")?;
+        Ok(Some(RateLimitSettings { capacity, refill_per_sec }))
+    }
+
     fn try_get_config(config: serde_json::Value) -> Result<Config, &'static str> {
         let commands_responses = Self::collect_commands(
             &config["commands_responses"],
@@ -114,6 +169,37 @@ impl TextCommandsPlugin {
             &config["nicknamable_commands_responses"],
         )?;
 
+        let default_rate_limit = Self::try_get_rate_limit_settings(&config["rate_limit"])?;
+        let mut command_rate_limits = HashMap::new();
+        for (cmd_name, cmd_rate_limit) in config["command_rate_limits"].entries_or_empty() {
+            if let Some(settings) = Self::try_get_rate_limit_settings(cmd_rate_limit)? {
+                command_rate_limits.insert(cmd_name.clone(), settings);
+            }
+        }
+
+        let hook_registry = hooks_from_config(&config["hooks"])?;
+        let mut command_hooks = HashMap::new();
+        for (cmd_name, hook_names_value) in config["command_hooks"].entries_or_empty() {
+            let mut hook_names = Vec::new();
+            for hook_name_value in hook_names_value.members().ok_or("command_hooks entry is not a list")? {
+                let hook_name = hook_name_value.as_str().ok_or("hook name is not a string")?;
+                hook_names.push(hook_name.to_owned());
+            }
+            command_hooks.insert(cmd_name.clone(), hook_names);
+        }
+
+        let max_message_len = if config["max_message_len"].is_null() {
+            None
+        } else {
+            Some(config["max_message_len"].as_usize().ok_or("max_message_len is not a number")?)
+        };
+        let mut split_long_responses = HashSet::new();
+        for cmd_name_value in config["split_long_responses"].members_or_empty() {
+            let cmd_name = cmd_name_value
+                .as_str().ok_or("entry in split_long_responses not a string")?;
+            split_long_responses.insert(cmd_name.to_owned());
+        }
+
         let mut mad_libs_commands = HashMap::new();
         for (cmd_name, cmd_def) in config["mad_libs_commands"].entries_or_empty() {
             let arg_count = match cmd_def["arg_count"].as_usize() {
@@ -141,8 +227,83 @@ impl TextCommandsPlugin {
             commands_responses,
             nicknamable_commands_responses,
             mad_libs_commands,
+            default_rate_limit,
+            command_rate_limits,
+            max_message_len,
+            split_long_responses,
+            hook_registry,
+            command_hooks,
         })
     }
+
+    /// Evaluates the hooks configured for `command_name` (see `rocketbot_interface::hooks`)
+    /// against the given circumstances, returning the resulting verdict. Commands without any
+    /// configured hooks always allow.
+    async fn run_hooks(&self, config: &Config, channel_name: &str, command_name: &str, sender_username: &str) -> HookVerdict {
+        let hook_names = match config.command_hooks.get(command_name) {
+            Some(names) => names,
+            None => return HookVerdict::Allow,
+        };
+        let context = HookContext {
+            command_name: command_name.to_owned(),
+            channel_name: Some(channel_name.to_owned()),
+            sender_username: sender_username.to_owned(),
+        };
+        config.hook_registry.evaluate(hook_names, &context).await
+    }
+
+    /// Attempts to consume a token from `channel_name`'s rate limit bucket for `command_name`.
+    /// Returns `true` (having consumed a token) if the command is allowed to reply, either because
+    /// it is not rate-limited or because its bucket still has tokens available.
+    async fn try_consume_rate_limit_token(&self, config: &Config, channel_name: &str, command_name: &str) -> bool {
+        let settings = match config.command_rate_limits.get(command_name) {
+            Some(s) => *s,
+            None => match config.default_rate_limit {
+                Some(s) => s,
+                None => return true,
+            },
+        };
+
+        // commands with their own override get their own bucket per channel; commands sharing the
+        // default rate limit share one bucket per channel across all three command families
+        let bucket_key = if config.command_rate_limits.contains_key(command_name) {
+            format!("{}\0{}", channel_name, command_name)
+        } else {
+            channel_name.to_owned()
+        };
+
+        let mut rate_limiters_guard = self.rate_limiters.lock().await;
+        let limiter = rate_limiters_guard
+            .entry(bucket_key)
+            .or_insert_with(|| RateLimiter::new(settings.capacity, settings.refill_per_sec));
+        limiter.try_consume()
+    }
+
+    /// Sends `response` to `channel_name`, splitting it across several messages first if
+    /// `command_name` is listed in `config.split_long_responses` and `response` exceeds the
+    /// applicable maximum message length.
+    async fn send_response(&self, interface: &dyn RocketBotInterface, config: &Config, channel_name: &str, command_name: &str, response: &str) {
+        if !config.split_long_responses.contains(command_name) {
+            send_channel_message!(interface, channel_name, response).await;
+            return;
+        }
+
+        let max_len = match config.max_message_len {
+            Some(ml) => Some(ml),
+            None => interface.get_maximum_message_length().await,
+        };
+
+        match max_len {
+            Some(ml) if ml > 0 => {
+                for chunk in split_message(response, ml) {
+                    send_channel_message!(interface, channel_name, &chunk).await;
+                }
+            },
+            _ => {
+                send_channel_message!(interface, channel_name, response).await;
+            },
+        }
+    }
 }
 #[async_trait]
 impl RocketBotPlugin for TextCommandsPlugin {
@@ -175,10 +336,16 @@ impl RocketBotPlugin for TextCommandsPlugin {
             StdRng::from_entropy(),
         );
 
+        let rate_limiters = Mutex::new(
+            "TextCommandsPlugin::rate_limiters",
+            HashMap::new(),
+        );
+
         TextCommandsPlugin {
             interface,
             config: config_lock,
             rng,
+            rate_limiters,
         }
     }
 
@@ -194,6 +361,22 @@ impl RocketBotPlugin for TextCommandsPlugin {
 
         let config_guard = self.config.read().await;
 
+        let hook_verdict = self.run_hooks(
+            &config_guard, &channel_message.channel.name, &command.name, &channel_message.message.sender.username,
+        ).await;
+        if let HookVerdict::Deny { feedback } = hook_verdict {
+            debug!("hook denied text command {:?} in channel {:?}", command.name, channel_message.channel.name);
+            if let Some(feedback) = feedback {
+                send_channel_message!(interface, &channel_message.channel.name, &feedback).await;
+            }
+            return;
+        }
+
+        if !self.try_consume_rate_limit_token(&config_guard, &channel_message.channel.name, &command.name).await {
+            debug!("rate-limiting text command {:?} in channel {:?}", command.name, channel_message.channel.name);
+            return;
+        }
+
         if let Some(responses) = config_guard.commands_responses.get(&command.name) {
             if responses.len() == 0 {
                 return;
@@ -205,11 +388,7 @@ impl RocketBotPlugin for TextCommandsPlugin {
                 responses[index].clone()
             };
 
-            send_channel_message!(
-                interface,
-                &channel_message.channel.name,
-                &variant,
-            ).await;
+            self.send_response(&*interface, &config_guard, &channel_message.channel.name, &command.name, &variant).await;
         } else if let Some(nicknamable_responses) = config_guard.nicknamable_commands_responses.get(&command.name) {
             if nicknamable_responses.len() == 0 {
                 return;
@@ -259,11 +438,7 @@ impl RocketBotPlugin for TextCommandsPlugin {
 
             let message_with_target = variant.replace("{{NICKNAME}}", &target);
 
-            send_channel_message!(
-                interface,
-                &channel_message.channel.name,
-                &message_with_target,
-            ).await;
+            self.send_response(&*interface, &config_guard, &channel_message.channel.name, &command.name, &message_with_target).await;
         } else if let Some(mad_libs_def) = config_guard.mad_libs_commands.get(&command.name) {
             if mad_libs_def.response_templates.len() == 0 {
                 return;
@@ -281,11 +456,7 @@ impl RocketBotPlugin for TextCommandsPlugin {
             }
             outgoing = outgoing.replace("{{TEXT}}", &command.rest);
 
-            send_channel_message!(
-                interface,
-                &channel_message.channel.name,
-                &outgoing,
-            ).await;
+            self.send_response(&*interface, &config_guard, &channel_message.channel.name, &command.name, &outgoing).await;
         }
     }
 
@@ -0,0 +1,69 @@
+//! Splitting an overlong, already-rendered command response into an ordered sequence of messages
+//! that each fit within a maximum length, inspired by discord-rusty-bot's line/word-based
+//! splitting.
+
+/// Splits `text` into a sequence of chunks, none of which exceeds `max_len` characters. Lines are
+/// kept together where possible; a line that does not fit on its own is broken on word
+/// boundaries, and a single word still too long to fit is hard-split.
+///
+/// Returns `vec![text.to_owned()]` unsplit if `text` already fits within `max_len`.
+pub(crate) fn split_message(text: &str, max_len: usize) -> Vec<String> {
+    if max_len == 0 || text.chars().count() <= max_len {
+        return vec![text.to_owned()];
+    }
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for line in text.split('\n') {
+        if line.chars().count() <= max_len {
+            append_piece(&mut chunks, &mut current, line, '\n', max_len);
+            continue;
+        }
+
+        // the line itself does not fit; split it into words instead
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        for word in line.split(' ') {
+            if word.chars().count() > max_len {
+                if !current.is_empty() {
+                    chunks.push(std::mem::take(&mut current));
+                }
+                chunks.extend(hard_split(word, max_len));
+                continue;
+            }
+            append_piece(&mut chunks, &mut current, word, ' ', max_len);
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+}
+
+/// Appends `piece` to `current`, separated by `separator` from whatever is already there, first
+/// flushing `current` into `chunks` if appending would exceed `max_len`.
+fn append_piece(chunks: &mut Vec<String>, current: &mut String, piece: &str, separator: char, max_len: usize) {
+    let separator_len = if current.is_empty() { 0 } else { 1 };
+    let candidate_len = current.chars().count() + separator_len + piece.chars().count();
+    if candidate_len > max_len && !current.is_empty() {
+        chunks.push(std::mem::take(current));
+    }
+    if !current.is_empty() {
+        current.push(separator);
+    }
+    current.push_str(piece);
+}
+
+/// Splits `word` into `max_len`-character chunks, without regard for word or line boundaries,
+/// since it does not contain any.
+fn hard_split(word: &str, max_len: usize) -> Vec<String> {
+    let chars: Vec<char> = word.chars().collect();
+    chars
+        .chunks(max_len)
+        .map(|c| c.iter().collect())
+        .collect()
+}
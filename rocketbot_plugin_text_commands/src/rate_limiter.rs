@@ -0,0 +1,46 @@
+//! A per-channel token-bucket rate limiter, used to keep `{cpfx}cmd` floods from spamming a
+//! channel with replies.
+
+use std::time::Instant;
+
+
+/// A token bucket: holds up to `capacity` tokens, refilling at `refill_per_sec` tokens per second
+/// (computed from the elapsed wall-clock time since the previous check); each allowed action
+/// consumes one token.
+#[derive(Clone, Debug)]
+pub(crate) struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    tokens: f64,
+    last_check: Instant,
+}
+impl RateLimiter {
+    /// Creates a new rate limiter, its bucket starting out full.
+    pub(crate) fn new(capacity: f64, refill_per_sec: f64) -> Self {
+        Self {
+            capacity,
+            refill_per_sec,
+            tokens: capacity,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Attempts to consume one token, first refilling the bucket for the time elapsed since the
+    /// previous check. Returns `true` (having consumed a token) if at least one token was
+    /// available; returns `false` (consuming nothing) otherwise.
+    pub(crate) fn try_consume(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed_secs = now.duration_since(self.last_check).as_secs_f64();
+        self.last_check = now;
+
+        let new_tokens = self.tokens + elapsed_secs * self.refill_per_sec;
+        self.tokens = if new_tokens > self.capacity { self.capacity } else { new_tokens };
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
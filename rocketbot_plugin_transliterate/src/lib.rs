@@ -2,7 +2,9 @@ mod model;
 
 
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::fs::{File, read_dir};
+use std::hash::{Hash, Hasher};
 use std::ops::DerefMut;
 use std::sync::{Arc, Weak};
 
@@ -24,6 +26,7 @@ use crate::model::{Language, Transformation};
 struct Config {
     languages: HashMap<String, Language>,
     command_to_lang_combo: HashMap<String, (String, String)>,
+    base_seed: Option<u64>,
 }
 
 
@@ -33,6 +36,16 @@ pub struct TransliteratePlugin {
     rng: Mutex<StdRng>,
 }
 impl TransliteratePlugin {
+    /// Derives a reproducible RNG from `base_seed` and the text being transliterated, so that
+    /// transliterating the same text with the same base seed always picks the same replacements.
+    fn seeded_rng(base_seed: u64, text: &str) -> StdRng {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let text_hash = hasher.finish();
+
+        StdRng::seed_from_u64(base_seed ^ text_hash)
+    }
+
     fn transliterate<R: RngCore>(
         rng: &mut R,
         transformations: &[Transformation],
@@ -131,24 +144,38 @@ impl TransliteratePlugin {
             },
         };
 
-        let transliterated = {
-            let mut rng_lock = self.rng.lock().await;
+        let transliterated = match config.base_seed {
+            Some(base_seed) => {
+                let mut seeded_rng = Self::seeded_rng(base_seed, &command.rest);
 
-            // transliterate from source language
-            let intermediate = Self::transliterate(
-                rng_lock.deref_mut(),
-                &source_language.from_lang,
-                &command.rest,
-            );
+                let intermediate = Self::transliterate(
+                    &mut seeded_rng,
+                    &source_language.from_lang,
+                    &command.rest,
+                );
+                Self::transliterate(
+                    &mut seeded_rng,
+                    &dest_language.to_lang,
+                    &intermediate,
+                )
+            },
+            None => {
+                let mut rng_lock = self.rng.lock().await;
 
-            // transliterate to target language
-            let target = Self::transliterate(
-                rng_lock.deref_mut(),
-                &dest_language.to_lang,
-                &intermediate,
-            );
+                // transliterate from source language
+                let intermediate = Self::transliterate(
+                    rng_lock.deref_mut(),
+                    &source_language.from_lang,
+                    &command.rest,
+                );
 
-            target
+                // transliterate to target language
+                Self::transliterate(
+                    rng_lock.deref_mut(),
+                    &dest_language.to_lang,
+                    &intermediate,
+                )
+            },
         };
 
         send_channel_message!(
@@ -205,13 +232,16 @@ impl TransliteratePlugin {
             },
         };
 
-        let result = {
-            let mut rng_lock = self.rng.lock().await;
-            Self::transliterate(
-                rng_lock.deref_mut(),
-                if detransliterate { &language.from_lang } else { &language.to_lang },
-                &command.rest,
-            )
+        let transformations = if detransliterate { &language.from_lang } else { &language.to_lang };
+        let result = match config_guard.base_seed {
+            Some(base_seed) => {
+                let mut seeded_rng = Self::seeded_rng(base_seed, &command.rest);
+                Self::transliterate(&mut seeded_rng, transformations, &command.rest)
+            },
+            None => {
+                let mut rng_lock = self.rng.lock().await;
+                Self::transliterate(rng_lock.deref_mut(), transformations, &command.rest)
+            },
         };
         send_channel_message!(
             interface,
@@ -286,9 +316,12 @@ impl TransliteratePlugin {
             }
         }
 
+        let base_seed = config["base_seed"].as_u64();
+
         Ok(Config {
             languages,
             command_to_lang_combo,
+            base_seed,
         })
     }
 
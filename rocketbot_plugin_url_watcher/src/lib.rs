@@ -0,0 +1,424 @@
+use std::collections::BTreeMap;
+use std::sync::Weak;
+
+use async_trait::async_trait;
+use chrono::{Local, Utc};
+use md5::{Digest, Md5};
+use regex::Regex;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use rocketbot_interface::send_channel_message;
+use rocketbot_interface::http_client::HttpClient;
+use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
+use rocketbot_interface::model::ChannelMessage;
+use rocketbot_interface::sync::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+
+/// A single page this plugin keeps an eye on: it is polled every `poll_interval_seconds` and,
+/// optionally, can also be checked on demand whenever a channel message matches `trigger_regex`.
+#[derive(Clone, Debug)]
+struct TargetConfig {
+    name: String,
+    url: String,
+    poll_interval_seconds: u64,
+    announce_channels: Vec<String>,
+    trigger_regex: Option<Regex>,
+    changed_message: String,
+    unchanged_message: String,
+}
+
+#[derive(Clone, Debug)]
+struct Config {
+    state_file: String,
+    targets: Vec<TargetConfig>,
+}
+
+
+/// What was last observed about a [`TargetConfig`], persisted to `state_file` so that a plugin
+/// restart does not forget the previously seen content and re-announce a change that was already
+/// reported before the restart.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct TargetState {
+    content_hash: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    last_checked: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct PersistedState {
+    targets: BTreeMap<String, TargetState>,
+}
+
+
+/// Whether a [`TargetConfig`] poll found the page's content to have changed since the previously
+/// recorded hash.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum CheckOutcome {
+    Changed,
+    Unchanged,
+}
+
+
+fn hex_encode(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        hex.push_str(&format!("{:02x}", b));
+    }
+    hex
+}
+
+fn fill_placeholders(template: &str, target: &TargetConfig) -> String {
+    template
+        .replace("{name}", &target.name)
+        .replace("{url}", &target.url)
+}
+
+fn parse_target_config(target_value: &serde_json::Value) -> Option<TargetConfig> {
+    let name = match target_value["name"].as_str() {
+        Some(val) => val.to_owned(),
+        None => {
+            error!("target name missing or not a string");
+            return None;
+        },
+    };
+    let url = match target_value["url"].as_str() {
+        Some(val) => val.to_owned(),
+        None => {
+            error!("target {:?}: url missing or not a string", name);
+            return None;
+        },
+    };
+    let poll_interval_seconds = match target_value["poll_interval_seconds"].as_u64() {
+        Some(val) => val,
+        None => {
+            error!("target {:?}: poll_interval_seconds missing or not a u64", name);
+            return None;
+        },
+    };
+
+    let announce_channels_values = match target_value["announce_channels"].as_array() {
+        Some(val) => val,
+        None => {
+            error!("target {:?}: announce_channels missing or not an array", name);
+            return None;
+        },
+    };
+    let mut announce_channels = Vec::with_capacity(announce_channels_values.len());
+    for channel_value in announce_channels_values {
+        let channel_name = match channel_value.as_str() {
+            Some(val) => val,
+            None => {
+                error!("target {:?}: element of announce_channels is not a string", name);
+                return None;
+            },
+        };
+        announce_channels.push(channel_name.to_owned());
+    }
+
+    let trigger_regex = match target_value["trigger_regex"].as_str() {
+        Some(pattern) => {
+            match Regex::new(pattern) {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    error!("target {:?}: failed to parse trigger_regex {:?}: {}", name, pattern, e);
+                    return None;
+                },
+            }
+        },
+        None => None,
+    };
+
+    let changed_message = match target_value["changed_message"].as_str() {
+        Some(val) => val.to_owned(),
+        None => {
+            error!("target {:?}: changed_message missing or not a string", name);
+            return None;
+        },
+    };
+    let unchanged_message = match target_value["unchanged_message"].as_str() {
+        Some(val) => val.to_owned(),
+        None => {
+            error!("target {:?}: unchanged_message missing or not a string", name);
+            return None;
+        },
+    };
+
+    Some(TargetConfig {
+        name,
+        url,
+        poll_interval_seconds,
+        announce_channels,
+        trigger_regex,
+        changed_message,
+        unchanged_message,
+    })
+}
+
+
+/// Watches a configured set of pages for changes, proactively polling each on its own timer and
+/// announcing to its `announce_channels` whenever its content hash changes from the last seen
+/// value. Generalized from the original single-purpose n-gate.com novelty check (which only
+/// compared one frozen MD5 against a hardcoded URL) so that any number of pages can be watched and
+/// real changes reported as they actually happen, rather than only on demand.
+pub struct UrlWatcherPlugin {
+    interface: Weak<dyn RocketBotInterface>,
+    http_client: HttpClient,
+    config: Config,
+    state: RwLock<PersistedState>,
+}
+impl UrlWatcherPlugin {
+    fn load_config(config: &serde_json::Value) -> Option<Config> {
+        let state_file = match config["state_file"].as_str() {
+            Some(val) => val.to_owned(),
+            None => {
+                error!("state_file missing in config or not a string");
+                return None;
+            },
+        };
+
+        let target_values = match config["targets"].as_array() {
+            Some(val) => val,
+            None => {
+                error!("targets missing in config or not an array");
+                return None;
+            },
+        };
+        let mut targets = Vec::with_capacity(target_values.len());
+        for target_value in target_values {
+            targets.push(parse_target_config(target_value)?);
+        }
+
+        Some(Config {
+            state_file,
+            targets,
+        })
+    }
+
+    fn load_state(state_file: &str) -> PersistedState {
+        let state_string = match std::fs::read_to_string(state_file) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("failed to read state file {:?} (assuming no prior state): {}", state_file, e);
+                return PersistedState::default();
+            },
+        };
+        match serde_json::from_str(&state_string) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("failed to parse state file {:?} (assuming no prior state): {}", state_file, e);
+                PersistedState::default()
+            },
+        }
+    }
+
+    async fn persist_state(&self) {
+        let state_guard = self.state.read().await;
+        let state_string = match serde_json::to_string(&*state_guard) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("failed to serialize state: {}", e);
+                return;
+            },
+        };
+        if let Err(e) = std::fs::write(&self.config.state_file, state_string.as_bytes()) {
+            error!("failed to write state file {:?}: {}", self.config.state_file, e);
+        }
+    }
+
+    /// Fetches `target`'s URL, using the previously stored `ETag`/`Last-Modified` (if any) to make
+    /// a conditional request, and compares the content's MD5 hash against the previously stored
+    /// one. Updates and persists the stored state regardless of outcome. Returns `None` if the
+    /// page could not be fetched.
+    async fn check_target(&self, target: &TargetConfig) -> Option<CheckOutcome> {
+        let previous_state = {
+            let state_guard = self.state.read().await;
+            state_guard.targets.get(&target.name).cloned().unwrap_or_default()
+        };
+
+        let mut extra_headers = Vec::new();
+        if let Some(etag) = &previous_state.etag {
+            extra_headers.push((IF_NONE_MATCH, etag.as_str()));
+        }
+        if let Some(last_modified) = &previous_state.last_modified {
+            extra_headers.push((IF_MODIFIED_SINCE, last_modified.as_str()));
+        }
+
+        let response = match self.http_client.fetch(&target.url, &extra_headers).await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to fetch {:?}: {}", target.url, e);
+                return None;
+            },
+        };
+
+        let now = Utc::now().to_rfc3339();
+
+        if response.status == reqwest::StatusCode::NOT_MODIFIED {
+            let mut state_guard = self.state.write().await;
+            let target_state = state_guard.targets.entry(target.name.clone()).or_insert_with(TargetState::default);
+            target_state.last_checked = Some(now);
+            drop(state_guard);
+            self.persist_state().await;
+            return Some(CheckOutcome::Unchanged);
+        }
+
+        let new_etag = response.headers.get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_owned());
+        let new_last_modified = response.headers.get(LAST_MODIFIED)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_owned());
+
+        let content_hash = {
+            let mut hasher = Md5::new();
+            hasher.update(&response.body);
+            hex_encode(&hasher.finalize())
+        };
+
+        let changed = match &previous_state.content_hash {
+            Some(prev_hash) => prev_hash != &content_hash,
+            // no prior record: this is the first check, so establish a baseline instead of
+            // announcing a "change" against nothing
+            None => false,
+        };
+
+        {
+            let mut state_guard = self.state.write().await;
+            let target_state = state_guard.targets.entry(target.name.clone()).or_insert_with(TargetState::default);
+            target_state.content_hash = Some(content_hash);
+            target_state.etag = new_etag;
+            target_state.last_modified = new_last_modified;
+            target_state.last_checked = Some(now);
+        }
+        self.persist_state().await;
+
+        Some(if changed { CheckOutcome::Changed } else { CheckOutcome::Unchanged })
+    }
+
+    /// Registers a timer that will cause `target_index` to be polled via `timer_elapsed` once its
+    /// `poll_interval_seconds` have elapsed.
+    async fn schedule_poll(interface: &std::sync::Arc<dyn RocketBotInterface>, target_index: usize, poll_interval_seconds: u64) {
+        let next_poll = Utc::now() + chrono::Duration::seconds(poll_interval_seconds as i64);
+        let custom_data = serde_json::json!(["url_watcher", target_index]);
+        interface.register_timer(next_poll, custom_data).await;
+    }
+
+    async fn timer_elapsed_poll(&self, target_index: usize) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let target = match self.config.targets.get(target_index) {
+            Some(t) => t,
+            None => return,
+        };
+
+        if let Some(CheckOutcome::Changed) = self.check_target(target).await {
+            let message = fill_placeholders(&target.changed_message, target);
+            for channel_name in &target.announce_channels {
+                send_channel_message!(interface, channel_name, &message).await;
+            }
+        }
+
+        Self::schedule_poll(&interface, target_index, target.poll_interval_seconds).await;
+    }
+}
+#[async_trait]
+impl RocketBotPlugin for UrlWatcherPlugin {
+    async fn new(interface: Weak<dyn RocketBotInterface>, config: serde_json::Value) -> Self {
+        let my_interface = match interface.upgrade() {
+            None => panic!("interface is gone"),
+            Some(i) => i,
+        };
+
+        let actual_config = Self::load_config(&config)
+            .expect("failed to load config");
+        let initial_state = Self::load_state(&actual_config.state_file);
+
+        for (index, target) in actual_config.targets.iter().enumerate() {
+            Self::schedule_poll(&my_interface, index, target.poll_interval_seconds).await;
+        }
+
+        UrlWatcherPlugin {
+            interface,
+            http_client: HttpClient::with_default_resolver(),
+            config: actual_config,
+            state: RwLock::new("UrlWatcherPlugin::state", initial_state),
+        }
+    }
+
+    async fn plugin_name(&self) -> String {
+        "url_watcher".to_owned()
+    }
+
+    async fn timer_elapsed(&self, custom_data: &serde_json::Value) {
+        if !custom_data.is_array() {
+            return;
+        }
+        if custom_data[0] != "url_watcher" {
+            return;
+        }
+        let target_index = match custom_data[1].as_u64() {
+            Some(i) => i as usize,
+            None => return,
+        };
+
+        self.timer_elapsed_poll(target_index).await;
+    }
+
+    async fn channel_message(&self, channel_message: &ChannelMessage) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let body = match &channel_message.message.raw {
+            Some(b) => b,
+            None => return,
+        };
+
+        // don't trigger on-demand checks if Serious Mode is active
+        let behavior_flags = serde_json::Value::Object(interface.obtain_behavior_flags().await);
+        if let Some(serious_mode_until) = behavior_flags["srs"][&channel_message.channel.id].as_i64() {
+            if serious_mode_until > Local::now().timestamp() {
+                return;
+            }
+        }
+
+        for target in &self.config.targets {
+            let trigger_regex = match &target.trigger_regex {
+                Some(r) => r,
+                None => continue,
+            };
+            if !trigger_regex.is_match(body) {
+                continue;
+            }
+
+            let outcome = match self.check_target(target).await {
+                Some(o) => o,
+                None => continue,
+            };
+
+            let message = match outcome {
+                CheckOutcome::Changed => fill_placeholders(&target.changed_message, target),
+                CheckOutcome::Unchanged => fill_placeholders(&target.unchanged_message, target),
+            };
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                &message,
+            ).await;
+
+            if outcome == CheckOutcome::Changed {
+                for channel_name in &target.announce_channels {
+                    if channel_name != &channel_message.channel.name {
+                        send_channel_message!(interface, channel_name, &message).await;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Weak;
 
 use async_trait::async_trait;
-use log::{debug, warn};
+use log::debug;
 use rocketbot_interface::send_channel_message;
 use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
 use rocketbot_interface::model::Channel;
@@ -15,19 +15,151 @@ struct ChannelInfo {
     pub usernames: Option<HashSet<String>>,
     pub join_message_format: Option<String>,
     pub leave_message_format: Option<String>,
+    pub announce_usernames: Option<HashSet<String>>,
+    pub ignore_usernames: HashSet<String>,
+    pub batch_threshold: Option<usize>,
+    pub batch_join_message_format: Option<String>,
+    pub batch_leave_message_format: Option<String>,
 }
 impl ChannelInfo {
     pub fn new(
         usernames: Option<HashSet<String>>,
         join_message_format: Option<String>,
         leave_message_format: Option<String>,
+        announce_usernames: Option<HashSet<String>>,
+        ignore_usernames: HashSet<String>,
+        batch_threshold: Option<usize>,
+        batch_join_message_format: Option<String>,
+        batch_leave_message_format: Option<String>,
     ) -> Self {
         Self {
             usernames,
             join_message_format,
             leave_message_format,
+            announce_usernames,
+            ignore_usernames,
+            batch_threshold,
+            batch_join_message_format,
+            batch_leave_message_format,
         }
     }
+
+    pub fn should_announce(&self, username: &str) -> bool {
+        if let Some(allowlist) = &self.announce_usernames {
+            if !allowlist.contains(username) {
+                return false;
+            }
+        }
+        !self.ignore_usernames.contains(username)
+    }
+}
+
+
+fn substitute_placeholders(format: &str, channel_name: &str, display_name: &str, username: &str, count: usize) -> String {
+    format
+        .replace("{CHANNEL}", channel_name)
+        .replace("{DISPLAYNAME}", display_name)
+        .replace("{USERNAME}", username)
+        .replace("{COUNT}", &count.to_string())
+}
+
+fn substitute_batch_placeholders(format: &str, channel_name: &str, usernames: &[&String], count: usize) -> String {
+    let joined_usernames = usernames.iter()
+        .map(|u| u.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ");
+    format
+        .replace("{CHANNEL}", channel_name)
+        .replace("{USERNAMES}", &joined_usernames)
+        .replace("{COUNT}", &count.to_string())
+}
+
+
+fn parse_string_hash_set(value: &serde_json::Value) -> HashSet<String> {
+    value.as_array()
+        .expect("username list is not an array")
+        .iter()
+        .map(|v| v.as_str().expect("username is not a str").to_owned())
+        .collect()
+}
+
+
+fn parse_channel_name_to_info(config: &serde_json::Value) -> HashMap<String, ChannelInfo> {
+    let channel_array = config["channels"].as_object()
+        .expect("channels is not an object");
+    let mut channel_name_to_info_map = HashMap::new();
+    for (channel_name, channel_config) in channel_array {
+        let join_message_format = if channel_config["join_message_format"].is_null() {
+            None
+        } else {
+            Some(
+                channel_config["join_message_format"]
+                    .as_str().expect("join_message_format is neither null nor str")
+                    .to_owned()
+            )
+        };
+        let leave_message_format = if channel_config["leave_message_format"].is_null() {
+            None
+        } else {
+            Some(
+                channel_config["leave_message_format"]
+                    .as_str().expect("leave_message_format is neither null nor str")
+                    .to_owned()
+            )
+        };
+        let announce_usernames = if channel_config["announce_usernames"].is_null() {
+            None
+        } else {
+            Some(parse_string_hash_set(&channel_config["announce_usernames"]))
+        };
+        let ignore_usernames = if channel_config["ignore_usernames"].is_null() {
+            HashSet::new()
+        } else {
+            parse_string_hash_set(&channel_config["ignore_usernames"])
+        };
+        let batch_threshold = if channel_config["batch_threshold"].is_null() {
+            None
+        } else {
+            Some(
+                channel_config["batch_threshold"]
+                    .as_u64().expect("batch_threshold is neither null nor u64")
+                    .try_into().expect("batch_threshold does not fit into usize")
+            )
+        };
+        let batch_join_message_format = if channel_config["batch_join_message_format"].is_null() {
+            None
+        } else {
+            Some(
+                channel_config["batch_join_message_format"]
+                    .as_str().expect("batch_join_message_format is neither null nor str")
+                    .to_owned()
+            )
+        };
+        let batch_leave_message_format = if channel_config["batch_leave_message_format"].is_null() {
+            None
+        } else {
+            Some(
+                channel_config["batch_leave_message_format"]
+                    .as_str().expect("batch_leave_message_format is neither null nor str")
+                    .to_owned()
+            )
+        };
+
+        channel_name_to_info_map.insert(
+            channel_name.clone(),
+            ChannelInfo::new(
+                None,
+                join_message_format,
+                leave_message_format,
+                announce_usernames,
+                ignore_usernames,
+                batch_threshold,
+                batch_join_message_format,
+                batch_leave_message_format,
+            ),
+        );
+    }
+    channel_name_to_info_map
 }
 
 
@@ -39,38 +171,7 @@ pub struct UserListMessagePlugin {
 impl RocketBotPlugin for UserListMessagePlugin {
     async fn new(interface: Weak<dyn RocketBotInterface>, config: serde_json::Value) -> Self {
         // read configuration
-        let channel_array = config["channels"].as_object()
-            .expect("channels is not an object");
-        let mut channel_name_to_info_map = HashMap::new();
-        for (channel_name, channel_config) in channel_array {
-            let join_message_format = if channel_config["join_message_format"].is_null() {
-                None
-            } else {
-                Some(
-                    channel_config["join_message_format"]
-                        .as_str().expect("join_message_format is neither null nor str")
-                        .to_owned()
-                )
-            };
-            let leave_message_format = if channel_config["leave_message_format"].is_null() {
-                None
-            } else {
-                Some(
-                    channel_config["leave_message_format"]
-                        .as_str().expect("leave_message_format is neither null nor str")
-                        .to_owned()
-                )
-            };
-
-            channel_name_to_info_map.insert(
-                channel_name.clone(),
-                ChannelInfo::new(
-                    None,
-                    join_message_format,
-                    leave_message_format,
-                ),
-            );
-        }
+        let channel_name_to_info_map = parse_channel_name_to_info(&config);
 
         let channel_name_to_info = Mutex::new(
             "UserListMessagePlugin::channel_name_to_info",
@@ -102,9 +203,13 @@ impl RocketBotPlugin for UserListMessagePlugin {
             None => return,
             Some(nu) => nu,
         };
+        let username_to_display_name: HashMap<&String, &str> = new_users.iter()
+            .map(|u| (&u.username, u.nickname_or_username()))
+            .collect();
         let new_usernames: HashSet<String> = new_users.iter()
             .map(|u| u.username.clone())
             .collect();
+        let member_count = new_usernames.len();
         debug!("new usernames for {:?}: {:?}", channel.name, new_usernames);
 
         if let Some(old_usernames) = &channel_info.usernames {
@@ -112,32 +217,49 @@ impl RocketBotPlugin for UserListMessagePlugin {
             debug!("old usernames for {:?}: {:?}", channel.name, old_usernames);
 
             if let Some(jmf) = &channel_info.join_message_format {
-                let joined_usernames: HashSet<&String> = new_usernames
+                let joined_usernames: Vec<&String> = new_usernames
                     .difference(old_usernames)
+                    .filter(|u| channel_info.should_announce(u))
                     .collect();
-                for joined_username in joined_usernames {
-                    let joined_message = jmf
-                        .replace("{USERNAME}", joined_username);
-                    send_channel_message!(
-                        interface,
-                        &channel.name,
-                        &joined_message,
-                    ).await;
+                if channel_info.batch_threshold.is_some_and(|t| joined_usernames.len() > t) {
+                    if let Some(bjmf) = &channel_info.batch_join_message_format {
+                        let batch_message = substitute_batch_placeholders(bjmf, &channel.name, &joined_usernames, joined_usernames.len());
+                        send_channel_message!(interface, &channel.name, &batch_message).await;
+                    }
+                } else {
+                    for joined_username in joined_usernames {
+                        let display_name = username_to_display_name.get(joined_username)
+                            .copied().unwrap_or(joined_username);
+                        let joined_message = substitute_placeholders(jmf, &channel.name, display_name, joined_username, member_count);
+                        send_channel_message!(
+                            interface,
+                            &channel.name,
+                            &joined_message,
+                        ).await;
+                    }
                 }
             }
 
             if let Some(lmf) = &channel_info.leave_message_format {
-                let left_usernames: HashSet<&String> = old_usernames
+                let left_usernames: Vec<&String> = old_usernames
                     .difference(&new_usernames)
+                    .filter(|u| channel_info.should_announce(u))
                     .collect();
-                for left_username in left_usernames {
-                    let left_message = lmf
-                        .replace("{USERNAME}", left_username);
-                    send_channel_message!(
-                        interface,
-                        &channel.name,
-                        &left_message,
-                    ).await;
+                if channel_info.batch_threshold.is_some_and(|t| left_usernames.len() > t) {
+                    if let Some(blmf) = &channel_info.batch_leave_message_format {
+                        let batch_message = substitute_batch_placeholders(blmf, &channel.name, &left_usernames, left_usernames.len());
+                        send_channel_message!(interface, &channel.name, &batch_message).await;
+                    }
+                } else {
+                    for left_username in left_usernames {
+                        // the user has already left, so we no longer know their display name
+                        let left_message = substitute_placeholders(lmf, &channel.name, left_username, left_username, member_count);
+                        send_channel_message!(
+                            interface,
+                            &channel.name,
+                            &left_message,
+                        ).await;
+                    }
                 }
             }
         }
@@ -146,8 +268,18 @@ impl RocketBotPlugin for UserListMessagePlugin {
         channel_info.usernames = Some(new_usernames);
     }
 
-    async fn configuration_updated(&self, _new_config: serde_json::Value) -> bool {
-        warn!("configuration updates are not yet supported for the user_list_message plugin");
-        false
+    async fn configuration_updated(&self, new_config: serde_json::Value) -> bool {
+        let mut new_channel_name_to_info = parse_channel_name_to_info(&new_config);
+
+        let mut channel_guard = self.channel_name_to_info.lock().await;
+        for (channel_name, new_info) in new_channel_name_to_info.iter_mut() {
+            if let Some(old_info) = channel_guard.get(channel_name) {
+                // keep the last-seen usernames so a reload doesn't cause a spurious join flood
+                new_info.usernames = old_info.usernames.clone();
+            }
+        }
+        *channel_guard = new_channel_name_to_info;
+
+        true
     }
 }
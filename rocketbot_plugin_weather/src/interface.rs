@@ -1,21 +1,37 @@
+use std::collections::BTreeMap;
 use std::fmt;
 
 use async_trait::async_trait;
+use chrono::{Date, Datelike, DateTime, Utc, Weekday};
 use json::JsonValue;
+use rocketbot_graph_drawing::ChartTheme;
+use rocketbot_graph_drawing::line::{AxisSide, LineGraph};
 
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct WeatherError {
     message: String,
+    cooldown: bool,
 }
 impl WeatherError {
     pub fn new(message: String) -> WeatherError {
-        WeatherError { message }
+        WeatherError { message, cooldown: false }
     }
 
     pub fn new_str(message: &str) -> WeatherError {
         WeatherError::new(message.into())
     }
+
+    /// Like [`Self::new`], but marks the error as representing a provider-side cooldown/rate
+    /// limit rather than an actual failure, so that callers can show a friendlier message for it
+    /// without leaking the detail of other (e.g. network or parsing) errors to the user.
+    pub fn new_cooldown(message: String) -> WeatherError {
+        WeatherError { message, cooldown: true }
+    }
+
+    pub fn is_cooldown(&self) -> bool {
+        self.cooldown
+    }
 }
 impl fmt::Display for WeatherError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -26,9 +42,421 @@ impl std::error::Error for WeatherError {
 }
 
 
+/// The current conditions at a location, as obtained from a single weather provider. Fields a
+/// provider cannot supply (or that don't apply, e.g. a merged [`crate::providers::combined::CombinedProvider`]
+/// reading) are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurrentConditions {
+    pub condition: String,
+    pub temperature_celsius: f64,
+    pub humidity_percent: f64,
+    pub feels_like_celsius: Option<f64>,
+    pub wind_speed_mps: Option<f64>,
+    pub wind_direction_deg: Option<f64>,
+    pub pressure_hectopascal: Option<f64>,
+    pub visibility_meters: Option<u64>,
+    pub sunrise: Option<DateTime<Utc>>,
+    pub sunset: Option<DateTime<Utc>>,
+}
+impl CurrentConditions {
+    pub fn new(
+        condition: String,
+        temperature_celsius: f64,
+        humidity_percent: f64,
+        feels_like_celsius: Option<f64>,
+        wind_speed_mps: Option<f64>,
+        wind_direction_deg: Option<f64>,
+        pressure_hectopascal: Option<f64>,
+        visibility_meters: Option<u64>,
+        sunrise: Option<DateTime<Utc>>,
+        sunset: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            condition,
+            temperature_celsius,
+            humidity_percent,
+            feels_like_celsius,
+            wind_speed_mps,
+            wind_direction_deg,
+            pressure_hectopascal,
+            visibility_meters,
+            sunrise,
+            sunset,
+        }
+    }
+}
+
+
+/// The forecast for a single time slot, as obtained from a single weather provider (or merged
+/// from several by [`crate::providers::combined::CombinedProvider`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForecastPoint {
+    pub timestamp: DateTime<Utc>,
+    pub condition: String,
+    pub min_temperature_celsius: f64,
+    pub max_temperature_celsius: f64,
+}
+impl ForecastPoint {
+    pub fn new(
+        timestamp: DateTime<Utc>,
+        condition: String,
+        min_temperature_celsius: f64,
+        max_temperature_celsius: f64,
+    ) -> Self {
+        Self {
+            timestamp,
+            condition,
+            min_temperature_celsius,
+            max_temperature_celsius,
+        }
+    }
+}
+
+
+/// The structured result of querying a [`WeatherProvider`] for a location: the current
+/// conditions (if the provider has any) plus zero or more upcoming forecast slots.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeatherData {
+    pub current: Option<CurrentConditions>,
+    pub forecast: Vec<ForecastPoint>,
+}
+impl WeatherData {
+    pub fn new(current: Option<CurrentConditions>, forecast: Vec<ForecastPoint>) -> Self {
+        Self {
+            current,
+            forecast,
+        }
+    }
+}
+
+
 #[async_trait]
 pub trait WeatherProvider : Send + Sync {
     async fn new(config: JsonValue) -> Self where Self: Sized;
     async fn get_weather_description_for_special(&self, special_string: &str) -> Option<String>;
     async fn get_weather_description_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> String;
+
+    /// Like [`Self::get_weather_description_for_coordinates`], but returns the structured data
+    /// instead of a pre-formatted description, allowing callers (in particular
+    /// [`crate::providers::combined::CombinedProvider`]) to combine it with data from other
+    /// providers before it is rendered.
+    async fn get_weather_data_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> Result<WeatherData, WeatherError>;
+
+    /// Renders the forecast as a PNG line chart via [`render_forecast_chart`], for callers that
+    /// want to attach an image instead of (or in addition to) the textual summary. Built purely on
+    /// [`Self::get_weather_data_for_coordinates`], so providers don't need to implement this
+    /// themselves.
+    async fn get_weather_chart_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> Result<Vec<u8>, WeatherError> {
+        let data = self.get_weather_data_for_coordinates(latitude_deg_north, longitude_deg_east).await?;
+        Ok(render_forecast_chart(&data.forecast))
+    }
+}
+
+
+const fn weekday_to_short(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "Mo",
+        Weekday::Tue => "Tu",
+        Weekday::Wed => "We",
+        Weekday::Thu => "Th",
+        Weekday::Fri => "Fr",
+        Weekday::Sat => "Sa",
+        Weekday::Sun => "Su",
+    }
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+struct DaySummary {
+    pub min_temperature_celsius: f64,
+    pub max_temperature_celsius: f64,
+    pub conditions: Vec<String>,
+}
+
+fn summarize_forecast_by_day(forecast: &[ForecastPoint]) -> BTreeMap<Date<Utc>, DaySummary> {
+    // `point.condition` may itself be several conditions joined with "/" (e.g. a provider
+    // reporting "Rain/Clouds" for a single time slot, or several providers' conditions merged by
+    // crate::providers::combined::CombinedProvider), so conditions are deduplicated per token
+    // rather than per whole string.
+    let mut ret: BTreeMap<Date<Utc>, DaySummary> = BTreeMap::new();
+    for point in forecast {
+        let date = point.timestamp.date();
+
+        ret.entry(date)
+            .and_modify(|e| {
+                e.max_temperature_celsius = e.max_temperature_celsius.max(point.max_temperature_celsius);
+                e.min_temperature_celsius = e.min_temperature_celsius.min(point.min_temperature_celsius);
+                for condition in point.condition.split('/') {
+                    if !e.conditions.iter().any(|c| c == condition) {
+                        e.conditions.push(condition.to_owned());
+                    }
+                }
+            })
+            .or_insert_with(|| {
+                let mut conditions: Vec<String> = Vec::new();
+                for condition in point.condition.split('/') {
+                    if !conditions.iter().any(|c| c == condition) {
+                        conditions.push(condition.to_owned());
+                    }
+                }
+                DaySummary {
+                    min_temperature_celsius: point.min_temperature_celsius,
+                    max_temperature_celsius: point.max_temperature_celsius,
+                    conditions,
+                }
+            });
+    }
+    ret
+}
+
+
+/// The measurement system a [`CurrentConditions`] is rendered in by [`format_current_conditions`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Units {
+    Metric,
+    Imperial,
+}
+impl Units {
+    pub fn from_config_str(value: &str) -> Option<Self> {
+        match value {
+            "metric" => Some(Self::Metric),
+            "imperial" => Some(Self::Imperial),
+            _ => None,
+        }
+    }
+
+    fn temperature_suffix(&self) -> &'static str {
+        match self {
+            Self::Metric => "\u{B0}C",
+            Self::Imperial => "\u{B0}F",
+        }
+    }
+
+    fn celsius_to_display(&self, celsius: f64) -> f64 {
+        match self {
+            Self::Metric => celsius,
+            Self::Imperial => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+}
+impl Default for Units {
+    fn default() -> Self { Self::Metric }
+}
+
+
+/// The default value of the `format` configuration key consumed by [`format_current_conditions`];
+/// reproduces the single-line rendering this crate always used before that template became
+/// configurable.
+pub const DEFAULT_CURRENT_CONDITIONS_FORMAT: &str = "{condition}, {temp}, {humidity}";
+
+
+fn time_tuple(value: i64, singular: &str, plural: &str) -> (i64, String) {
+    (value, format!("{} {}", value, if value == 1 { singular } else { plural }))
+}
+
+/// Renders a [`chrono::Duration`] as a human-readable "in X" / "X ago" phrase, showing at most the
+/// two largest non-zero units (e.g. "in 2 hours 3 minutes").
+pub(crate) fn format_duration(mut duration: chrono::Duration) -> String {
+    let mut ago = false;
+    if duration < chrono::Duration::zero() {
+        duration = -duration;
+        ago = true;
+    }
+
+    if duration < chrono::Duration::seconds(1) {
+        return "now".into();
+    }
+
+    let mut o_tempora_o_mores: Vec<(i64, String)> = vec![
+        time_tuple(duration.num_days(), "day", "days"),
+        time_tuple(duration.num_hours() % 24, "hour", "hours"),
+        time_tuple(duration.num_minutes() % 60, "minute", "minutes"),
+        time_tuple(duration.num_seconds() % 60, "second", "seconds"),
+    ];
+
+    // remove the empty large units
+    while o_tempora_o_mores.len() > 0 && o_tempora_o_mores[0].0 == 0 {
+        o_tempora_o_mores.remove(0);
+    }
+
+    // show two consecutive units at most
+    while o_tempora_o_mores.len() > 2 {
+        o_tempora_o_mores.remove(o_tempora_o_mores.len() - 1);
+    }
+
+    // delete the second unit if it is zero
+    if o_tempora_o_mores.len() > 1 && o_tempora_o_mores[0].0 == 0 {
+        o_tempora_o_mores.remove(1);
+    }
+
+    // fun!
+    let joint_vec: Vec<String> = o_tempora_o_mores
+        .iter()
+        .map(|otom| otom.1.clone())
+        .collect();
+    let joint = joint_vec.join(" ");
+
+    if ago {
+        format!("{} ago", joint)
+    } else {
+        format!("in {}", joint)
+    }
+}
+
+
+/// The eight cardinal/intercardinal compass points, used to render a wind direction in degrees as
+/// a short arrow-like abbreviation instead of a bare number.
+const COMPASS_DIRECTIONS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+
+/// Maps a wind direction in degrees (0 = north, clockwise) to the nearest of the eight
+/// [`COMPASS_DIRECTIONS`].
+fn wind_direction_to_compass(direction_deg: f64) -> &'static str {
+    let normalized = direction_deg.rem_euclid(360.0);
+    let index = ((normalized / 45.0).round() as usize) % COMPASS_DIRECTIONS.len();
+    COMPASS_DIRECTIONS[index]
+}
+
+
+/// Replaces every `{name}` token in `template` with its corresponding entry in `values`; a token
+/// without a matching entry is left verbatim (including its braces), so that templates can
+/// reference placeholders a given data source doesn't (yet) provide.
+fn substitute_placeholders(template: &str, values: &BTreeMap<&str, String>) -> String {
+    let mut ret = String::with_capacity(template.len());
+    let mut i = 0;
+    while i < template.len() {
+        if template.as_bytes()[i] == b'{' {
+            if let Some(rel_end) = template[i+1..].find('}') {
+                let name = &template[i+1..i+1+rel_end];
+                match values.get(name) {
+                    Some(value) => ret.push_str(value),
+                    None => ret.push_str(&template[i..i+2+rel_end]),
+                }
+                i += 2 + rel_end;
+                continue;
+            }
+        }
+
+        let next_char = template[i..].chars().next().expect("index within a UTF-8 string boundary");
+        ret.push(next_char);
+        i += next_char.len_utf8();
+    }
+    ret
+}
+
+
+/// Renders a single [`CurrentConditions`] as one line of text via `template`, substituting
+/// `{condition}`, `{temp}`, `{humidity}`, `{feels_like}`, `{wind}`, `{pressure}`, `{visibility}`,
+/// `{sunrise}` and `{sunset}` with `data`'s fields, formatted according to `units`. Tokens whose
+/// underlying field is `None` are left untouched (including their braces), so a template
+/// referencing e.g. `{wind}` degrades gracefully against a provider that can't supply it.
+pub fn format_current_conditions(template: &str, units: Units, current: &CurrentConditions) -> String {
+    let mut values: BTreeMap<&str, String> = BTreeMap::new();
+    values.insert("condition", current.condition.clone());
+    values.insert("temp", format!("{:.1} {}", units.celsius_to_display(current.temperature_celsius), units.temperature_suffix()));
+    values.insert("humidity", format!("{:.0}% humidity", current.humidity_percent));
+
+    if let Some(feels_like_celsius) = current.feels_like_celsius {
+        values.insert("feels_like", format!("feels like {:.1} {}", units.celsius_to_display(feels_like_celsius), units.temperature_suffix()));
+    }
+    if let Some(wind_speed_mps) = current.wind_speed_mps {
+        let direction = current.wind_direction_deg
+            .map(|deg| format!(" {}", wind_direction_to_compass(deg)))
+            .unwrap_or_else(String::new);
+        values.insert("wind", format!("{:.1} m/s{} wind", wind_speed_mps, direction));
+    }
+    if let Some(pressure_hectopascal) = current.pressure_hectopascal {
+        values.insert("pressure", format!("{:.0} hPa", pressure_hectopascal));
+    }
+    if let Some(visibility_meters) = current.visibility_meters {
+        values.insert("visibility", format!("{} m visibility", visibility_meters));
+    }
+    if let Some(sunrise) = current.sunrise {
+        values.insert("sunrise", format!("sunrise {}", format_duration(sunrise - Utc::now())));
+    }
+    if let Some(sunset) = current.sunset {
+        values.insert("sunset", format!("sunset {}", format_duration(sunset - Utc::now())));
+    }
+
+    substitute_placeholders(template, &values)
+}
+
+
+/// Renders [`WeatherData`] the same way regardless of which provider (or combination of
+/// providers) produced it, so that every [`WeatherProvider::get_weather_description_for_coordinates`]
+/// implementation can be a thin wrapper around this function.
+pub fn format_weather_data(provider_label: &str, data: &WeatherData) -> String {
+    format_weather_data_with_format(provider_label, data, DEFAULT_CURRENT_CONDITIONS_FORMAT, Units::Metric)
+}
+
+/// Like [`format_weather_data`], but renders the current-conditions line with a caller-supplied
+/// `current_format` template and `units`, for providers whose configuration lets operators
+/// customize those.
+pub fn format_weather_data_with_format(provider_label: &str, data: &WeatherData, current_format: &str, units: Units) -> String {
+    let mut ret = String::new();
+
+    if let Some(current) = &data.current {
+        ret.push_str(&format_current_conditions(current_format, units, current));
+    }
+
+    if data.forecast.len() > 0 {
+        if ret.len() > 0 {
+            ret.push_str("\n");
+        }
+        ret.push_str("forecast:\n");
+
+        let summarized = summarize_forecast_by_day(&data.forecast);
+        let forecast_list: Vec<String> = summarized
+            .iter()
+            .map(|(d, ds)| format!(
+                "*{}* {}.{:02}. {} {:.1}\u{2013}{:.1} \u{B0}C",
+                weekday_to_short(d.weekday()),
+                d.day(),
+                d.month(),
+                ds.conditions.join("/"),
+                ds.min_temperature_celsius,
+                ds.max_temperature_celsius,
+            ))
+            .collect();
+        ret.push_str(&forecast_list.join("\n"));
+    }
+
+    format!("{}:\n{}", provider_label, ret)
+}
+
+
+/// Renders `forecast` as a PNG line chart of the minimum/maximum temperature of each time slot,
+/// via [`LineGraph`]. Midnight boundaries are marked with [`LineGraph::draw_time_subdivision`] and
+/// labeled with the short weekday name; since [`LineGraph`]'s axis now auto-ranges over a signed
+/// domain, sub-zero temperatures are plotted directly (with a zero-crossing baseline where
+/// applicable) instead of needing to be shifted into non-negative territory first.
+pub fn render_forecast_chart(forecast: &[ForecastPoint]) -> Vec<u8> {
+    if forecast.len() == 0 {
+        return LineGraph::new_for_ranges(1, 0.0, 1.0, 0, ChartTheme::default()).canvas().to_png();
+    }
+
+    let min_temperature_celsius = forecast.iter()
+        .map(|p| p.min_temperature_celsius)
+        .fold(f64::INFINITY, f64::min);
+    let max_temperature_celsius = forecast.iter()
+        .map(|p| p.max_temperature_celsius)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let mut graph = LineGraph::new_for_ranges(forecast.len(), min_temperature_celsius, max_temperature_celsius, 0, ChartTheme::default());
+
+    let mut previous_date = None;
+    for (i, point) in forecast.iter().enumerate() {
+        let date = point.timestamp.date();
+        if previous_date != Some(date) {
+            if previous_date.is_some() {
+                graph.draw_time_subdivision(i);
+            }
+            graph.canvas_mut().draw_string(i + 1, 1, weekday_to_short(date.weekday()));
+            previous_date = Some(date);
+        }
+
+        graph.draw_data_point(i, point.min_temperature_celsius, 0, AxisSide::Left);
+        graph.draw_data_point(i, point.max_temperature_celsius, 1, AxisSide::Left);
+    }
+
+    graph.canvas().to_png()
 }
@@ -13,7 +13,7 @@ use rocketbot_geocoding::{Geocoder, GeoCoordinates};
 use rocketbot_interface::{JsonValueExtensions, send_channel_message};
 use rocketbot_interface::commands::{CommandDefinitionBuilder, CommandInstance};
 use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
-use rocketbot_interface::model::ChannelMessage;
+use rocketbot_interface::model::{Attachment, ChannelMessage, OutgoingMessageWithAttachmentBuilder};
 use rocketbot_interface::sync::RwLock;
 use serde_json;
 
@@ -119,6 +119,8 @@ impl WeatherPlugin {
             (loc.coordinates.latitude_deg, loc.coordinates.longitude_deg, Some(loc.place))
         };
 
+        let want_chart = command.flags.contains("c") || command.flags.contains("chart");
+
         for provider in &config_guard.providers {
             let weather = provider
                 .get_weather_description_for_coordinates(latitude, longitude).await;
@@ -126,7 +128,14 @@ impl WeatherPlugin {
                 channel_message,
                 if show_loc_name { loc_name.as_deref() } else { None },
                 &weather,
-            ).await
+            ).await;
+
+            if want_chart {
+                match provider.get_weather_chart_for_coordinates(latitude, longitude).await {
+                    Ok(png) => self.output_weather_chart(channel_message, png).await,
+                    Err(e) => warn!("failed to render weather chart: {}", e),
+                }
+            }
         }
     }
 
@@ -151,6 +160,39 @@ impl WeatherPlugin {
         }
     }
 
+    async fn output_weather_chart(&self, channel_message: &ChannelMessage, png_data: Vec<u8>) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let attachment = Attachment::new(
+            png_data,
+            "forecast.png".to_owned(),
+            "image/png".to_owned(),
+            None,
+        );
+        interface.send_channel_message_with_attachment(
+            &channel_message.channel.name,
+            OutgoingMessageWithAttachmentBuilder::new(attachment)
+                .build(),
+        ).await;
+    }
+
+    /// Instantiates the weather provider named `name` with the given `config`. Shared between
+    /// [`Self::try_get_config`] and [`crate::providers::combined::CombinedProvider`], which
+    /// constructs its own sub-providers the same way.
+    pub(crate) async fn create_provider(name: &str, provider_config: serde_json::Value) -> Result<Box<dyn WeatherProvider>, &'static str> {
+        if name == "owm" {
+            Ok(Box::new(crate::providers::owm::OpenWeatherMapProvider::new(provider_config).await))
+        } else if name == "combined" {
+            Ok(Box::new(crate::providers::combined::CombinedProvider::new(provider_config).await))
+        } else {
+            error!("unknown weather provider {:?}", name);
+            Err("unknown weather provider")
+        }
+    }
+
     async fn try_get_config(config: serde_json::Value) -> Result<Config, &'static str> {
         let default_location = config["default_location"]
             .as_str().ok_or("default_location is missing or not a string")?
@@ -173,12 +215,7 @@ impl WeatherPlugin {
                 .as_str().ok_or("provider name missing or not representable as a string")?;
             let provider_config = provider_entry["config"].clone();
 
-            let provider: Box<dyn WeatherProvider> = if name == "owm" {
-                Box::new(crate::providers::owm::OpenWeatherMapProvider::new(provider_config).await)
-            } else {
-                error!("unknown weather provider {:?}", name);
-                return Err("unknown weather provider");
-            };
+            let provider = Self::create_provider(name, provider_config).await?;
             providers.push(provider);
         }
 
@@ -211,9 +248,11 @@ impl RocketBotPlugin for WeatherPlugin {
         let weather_command = CommandDefinitionBuilder::new(
             "weather",
             "weather",
-            "{cpfx}weather|{cpfx}lweather [LOCATION]",
+            "{cpfx}weather|{cpfx}lweather [-c|--chart] [LOCATION]",
             "Displays the current weather as well as a forecast for the given location.",
         )
+            .add_flag("c")
+            .add_flag("chart")
             .build();
         let lweather_command = weather_command.copy_named("lweather");
         let wetter_command = weather_command.copy_named("wetter");
@@ -1,10 +1,12 @@
-use std::fmt::Write;
-
 use async_trait::async_trait;
-use chrono::{Datelike, Local, Weekday};
+use chrono::{Local, TimeZone, Utc};
 use rand::{Rng, thread_rng};
 
-use crate::interface::WeatherProvider;
+use crate::interface::{CurrentConditions, ForecastPoint, WeatherData, WeatherError, WeatherProvider, format_weather_data};
+
+
+const PROVIDER_LABEL: &'static str = "OpenWeatherMap";
+const CONDITION: &'static str = "Snow";
 
 
 pub(crate) struct AlwaysSnowingProvider;
@@ -18,37 +20,35 @@ impl AlwaysSnowingProvider {
         (before_decimal, after_decimal)
     }
 
-    fn generate_believable_temperature() -> String {
-        let (before_decimal, after_decimal) = Self::generate_believable_temperature_value();
-        format!("{}.{}", before_decimal, after_decimal)
+    /// Converts a `(before, after)` decimal-point pair into its actual Celsius value. The naive
+    /// conversion `before as f64 + after as f64 / 10.0` is wrong for negative values: `(-3, 7)`
+    /// represents "-3.7", not "-3 + 0.7 = -2.3", so the fractional part must be subtracted instead
+    /// of added whenever the whole part is negative.
+    fn temperature_value_to_celsius((before, after): (i8, u8)) -> f64 {
+        if before < 0 {
+            before as f64 - (after as f64 / 10.0)
+        } else {
+            before as f64 + (after as f64 / 10.0)
+        }
+    }
+
+    fn generate_believable_temperature_celsius() -> f64 {
+        Self::temperature_value_to_celsius(Self::generate_believable_temperature_value())
     }
 
-    fn generate_believable_humidity() -> String {
+    fn generate_believable_humidity_percent() -> f64 {
         // 76..96
         let mut rng = thread_rng();
-        let percent = rng.gen_range(76..96);
-        format!("{}", percent)
+        rng.gen_range(76..96) as f64
     }
 
-    fn generate_believable_temperature_range() -> String {
-        let (one_before, one_after) = Self::generate_believable_temperature_value();
-        let (other_before, other_after) = Self::generate_believable_temperature_value();
-        if (one_before, one_after) < (other_before, other_after) {
-            format!("{}.{}\u{2013}{}.{}", one_before, one_after, other_before, other_after)
+    fn generate_believable_temperature_range_celsius() -> (f64, f64) {
+        let one = Self::generate_believable_temperature_celsius();
+        let other = Self::generate_believable_temperature_celsius();
+        if one < other {
+            (one, other)
         } else {
-            format!("{}.{}\u{2013}{}.{}", other_before, other_after, one_before, one_after)
-        }
-    }
-
-    fn weekday_name(weekday: Weekday) -> &'static str {
-        match weekday {
-            Weekday::Mon => "Mo",
-            Weekday::Tue => "Tu",
-            Weekday::Wed => "We",
-            Weekday::Thu => "Th",
-            Weekday::Fri => "Fr",
-            Weekday::Sat => "Sa",
-            Weekday::Sun => "Su",
+            (other, one)
         }
     }
 }
@@ -62,33 +62,40 @@ impl WeatherProvider for AlwaysSnowingProvider {
         None
     }
 
-    async fn get_weather_description_for_coordinates(&self, _latitude_deg_north: f64, _longitude_deg_east: f64) -> String {
-        let general_temp = Self::generate_believable_temperature();
-        let general_humidity = Self::generate_believable_humidity();
+    async fn get_weather_description_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> String {
+        // infallible; the only `Err` case this provider could produce doesn't exist
+        let data = self.get_weather_data_for_coordinates(latitude_deg_north, longitude_deg_east).await
+            .expect("AlwaysSnowingProvider::get_weather_data_for_coordinates never fails");
+        format_weather_data(PROVIDER_LABEL, &data)
+    }
 
-        let mut today = Local::now().date_naive();
+    async fn get_weather_data_for_coordinates(&self, _latitude_deg_north: f64, _longitude_deg_east: f64) -> Result<WeatherData, WeatherError> {
+        let current = CurrentConditions::new(
+            CONDITION.to_owned(),
+            Self::generate_believable_temperature_celsius(),
+            Self::generate_believable_humidity_percent(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
 
-        let mut ret = String::new();
-        // claim we are OWM
-        write!(ret, "OpenWeatherMap:").unwrap();
-        write!(ret, "\nSnow, {} \u{B0}C, {}% humidity", general_temp, general_humidity).unwrap();
-        write!(ret, "\nforecast:").unwrap();
+        let mut today = Local::now().date_naive();
+        let mut forecast = Vec::new();
         for _ in 0..5 {
-            let temp_range = Self::generate_believable_temperature_range();
-            write!(
-                ret,
-                "\n*{}* {}.{:02}. Snow {} \u{B0}C",
-                Self::weekday_name(today.weekday()),
-                today.day(),
-                today.month(),
-                temp_range,
-            ).unwrap();
+            let (min_temp, max_temp) = Self::generate_believable_temperature_range_celsius();
+            let timestamp = Utc.from_utc_date(&today).and_hms(12, 0, 0);
+            forecast.push(ForecastPoint::new(timestamp, CONDITION.to_owned(), min_temp, max_temp));
 
             today = match today.succ_opt() {
                 Some(t) => t,
                 None => break,
             };
         }
-        ret
+
+        Ok(WeatherData::new(Some(current), forecast))
     }
 }
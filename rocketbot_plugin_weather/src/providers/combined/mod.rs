@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::{error, warn};
+use rocketbot_interface::JsonValueExtensions;
+
+use crate::WeatherPlugin;
+use crate::interface::{CurrentConditions, DEFAULT_CURRENT_CONDITIONS_FORMAT, ForecastPoint, Units, WeatherData, WeatherError, WeatherProvider, format_weather_data_with_format};
+
+
+const PROVIDER_LABEL: &'static str = "Combined";
+const ERROR_TEXT: &'static str = "An error occurred.";
+
+/// Weather conditions, ranked from worst to best. Used to pick the "worst" condition when merging
+/// the forecasts of several sub-providers for the same time slot. Conditions not found in this
+/// list are considered better than any condition that is.
+const CONDITION_SEVERITY_WORST_FIRST: &[&str] = &[
+    "Tornado",
+    "Squall",
+    "Thunderstorm",
+    "Snow",
+    "Sleet",
+    "Rain",
+    "Drizzle",
+    "Ash",
+    "Sand",
+    "Dust",
+    "Fog",
+    "Smoke",
+    "Haze",
+    "Mist",
+    "Clouds",
+    "Clear",
+];
+
+fn condition_rank(condition: &str) -> usize {
+    CONDITION_SEVERITY_WORST_FIRST.iter()
+        .position(|c| *c == condition)
+        .unwrap_or(usize::MAX)
+}
+
+/// Picks the worst of several conditions, keeping the first one encountered in case of a tie.
+/// Each condition may itself be several conditions joined with "/" (a provider reporting more
+/// than one simultaneous condition for a single slot), so ranking operates on the individual
+/// "/"-separated tokens rather than the joined string as a whole.
+fn worst_condition(conditions: &[String]) -> String {
+    let mut worst: Option<&str> = None;
+    let mut worst_rank = usize::MAX;
+    for condition in conditions {
+        for token in condition.split('/') {
+            let rank = condition_rank(token);
+            if worst.is_none() || rank < worst_rank {
+                worst = Some(token);
+                worst_rank = rank;
+            }
+        }
+    }
+    worst.map(|s| s.to_owned()).unwrap_or_else(String::new)
+}
+
+
+/// How to combine a numeric field across several sub-providers' forecasts for the same time slot.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TemperatureReduction {
+    Minimum,
+    Maximum,
+    Average,
+}
+impl TemperatureReduction {
+    fn from_config_str(value: &str) -> Result<Self, &'static str> {
+        match value {
+            "minimum" => Ok(Self::Minimum),
+            "maximum" => Ok(Self::Maximum),
+            "average" => Ok(Self::Average),
+            _ => Err("unknown temperature reduction mode (known: \"minimum\", \"maximum\", \"average\")"),
+        }
+    }
+
+    fn reduce(&self, values: &[f64]) -> f64 {
+        match self {
+            Self::Minimum => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Self::Maximum => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Self::Average => values.iter().copied().sum::<f64>() / values.len() as f64,
+        }
+    }
+}
+
+
+/// A meta-provider that queries several other [`WeatherProvider`]s and merges their results into
+/// a single [`WeatherData`]: forecasts are merged per time slot (using
+/// [`Self::min_temperature_reduction`]/[`Self::max_temperature_reduction`] for the temperature
+/// fields and the worst reported condition for the condition field), while the current conditions
+/// are taken from the first sub-provider that has any.
+pub(crate) struct CombinedProvider {
+    providers: Vec<Box<dyn WeatherProvider>>,
+    min_temperature_reduction: TemperatureReduction,
+    max_temperature_reduction: TemperatureReduction,
+    units: Units,
+    current_conditions_format: String,
+}
+impl CombinedProvider {
+    fn merge_current(currents: Vec<CurrentConditions>) -> Option<CurrentConditions> {
+        currents.into_iter().next()
+    }
+
+    fn merge_slot(
+        mut points: Vec<ForecastPoint>,
+        min_temperature_reduction: TemperatureReduction,
+        max_temperature_reduction: TemperatureReduction,
+    ) -> ForecastPoint {
+        if points.len() == 1 {
+            return points.remove(0);
+        }
+
+        let timestamp = points[0].timestamp;
+        let conditions: Vec<String> = points.iter().map(|p| p.condition.clone()).collect();
+        let min_temps: Vec<f64> = points.iter().map(|p| p.min_temperature_celsius).collect();
+        let max_temps: Vec<f64> = points.iter().map(|p| p.max_temperature_celsius).collect();
+
+        ForecastPoint::new(
+            timestamp,
+            worst_condition(&conditions),
+            min_temperature_reduction.reduce(&min_temps),
+            max_temperature_reduction.reduce(&max_temps),
+        )
+    }
+
+    fn merge_forecasts(
+        forecasts: Vec<Vec<ForecastPoint>>,
+        min_temperature_reduction: TemperatureReduction,
+        max_temperature_reduction: TemperatureReduction,
+    ) -> Vec<ForecastPoint> {
+        let mut slots: BTreeMap<DateTime<Utc>, Vec<ForecastPoint>> = BTreeMap::new();
+        for forecast in forecasts {
+            for point in forecast {
+                slots.entry(point.timestamp)
+                    .or_insert_with(Vec::new)
+                    .push(point);
+            }
+        }
+
+        slots.into_values()
+            .map(|points| Self::merge_slot(points, min_temperature_reduction, max_temperature_reduction))
+            .collect()
+    }
+}
+#[async_trait]
+impl WeatherProvider for CombinedProvider {
+    async fn new(config: serde_json::Value) -> Self {
+        let min_temperature_reduction = if config["min_temperature_reduction"].is_null() {
+            TemperatureReduction::Minimum
+        } else {
+            let value = config["min_temperature_reduction"]
+                .as_str().expect("min_temperature_reduction is not a string");
+            TemperatureReduction::from_config_str(value)
+                .expect("failed to parse min_temperature_reduction")
+        };
+        let max_temperature_reduction = if config["max_temperature_reduction"].is_null() {
+            TemperatureReduction::Maximum
+        } else {
+            let value = config["max_temperature_reduction"]
+                .as_str().expect("max_temperature_reduction is not a string");
+            TemperatureReduction::from_config_str(value)
+                .expect("failed to parse max_temperature_reduction")
+        };
+
+        let mut providers: Vec<Box<dyn WeatherProvider>> = Vec::new();
+        for provider_entry in config["providers"].members().expect("providers is not a list") {
+            let name = provider_entry["name"]
+                .as_str().expect("provider name missing or not representable as a string");
+            let provider_config = provider_entry["config"].clone();
+
+            let provider = WeatherPlugin::create_provider(name, provider_config).await
+                .expect("failed to create sub-provider");
+            providers.push(provider);
+        }
+
+        let units = if config["units"].is_null() {
+            Units::default()
+        } else {
+            let value = config["units"]
+                .as_str().expect("units is not a string");
+            Units::from_config_str(value)
+                .expect("units is neither \"metric\" nor \"imperial\"")
+        };
+        let current_conditions_format = if config["format"].is_null() {
+            DEFAULT_CURRENT_CONDITIONS_FORMAT.to_owned()
+        } else {
+            config["format"]
+                .as_str().expect("format is not a string")
+                .to_owned()
+        };
+
+        CombinedProvider {
+            providers,
+            min_temperature_reduction,
+            max_temperature_reduction,
+            units,
+            current_conditions_format,
+        }
+    }
+
+    async fn get_weather_description_for_special(&self, special_string: &str) -> Option<String> {
+        for provider in &self.providers {
+            if let Some(description) = provider.get_weather_description_for_special(special_string).await {
+                return Some(description);
+            }
+        }
+        None
+    }
+
+    async fn get_weather_description_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> String {
+        match self.get_weather_data_for_coordinates(latitude_deg_north, longitude_deg_east).await {
+            Ok(data) => format_weather_data_with_format(PROVIDER_LABEL, &data, &self.current_conditions_format, self.units),
+            Err(e) => {
+                error!("failed to obtain combined weather for lat={} lon={}: {}", latitude_deg_north, longitude_deg_east, e);
+                ERROR_TEXT.to_owned()
+            },
+        }
+    }
+
+    async fn get_weather_data_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> Result<WeatherData, WeatherError> {
+        let mut currents = Vec::new();
+        let mut forecasts = Vec::new();
+        let mut any_succeeded = false;
+
+        for provider in &self.providers {
+            match provider.get_weather_data_for_coordinates(latitude_deg_north, longitude_deg_east).await {
+                Ok(data) => {
+                    any_succeeded = true;
+                    if let Some(current) = data.current {
+                        currents.push(current);
+                    }
+                    forecasts.push(data.forecast);
+                },
+                Err(e) => {
+                    warn!("a CombinedProvider sub-provider failed to obtain weather data: {}", e);
+                },
+            }
+        }
+
+        if !any_succeeded {
+            return Err(WeatherError::new_str("all of CombinedProvider's sub-providers failed to obtain weather data"));
+        }
+
+        let current = Self::merge_current(currents);
+        let forecast = Self::merge_forecasts(forecasts, self.min_temperature_reduction, self.max_temperature_reduction);
+        Ok(WeatherData::new(current, forecast))
+    }
+}
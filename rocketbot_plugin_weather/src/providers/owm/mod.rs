@@ -1,11 +1,11 @@
 mod model;
 
 
-use std::collections::{BTreeSet, BTreeMap};
+use std::collections::{BTreeMap, BTreeSet};
 
 use async_trait::async_trait;
 use bytes::Buf;
-use chrono::{Date, Datelike, DateTime, Duration, TimeZone, Utc, Weekday};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use log::{debug, error};
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -15,138 +15,68 @@ use rocketbot_interface::sync::Mutex;
 use serde::de::DeserializeOwned;
 use serde_json;
 
-use crate::interface::{WeatherError, WeatherProvider};
-use crate::providers::owm::model::{Forecast, StationReading, WeatherState};
+use crate::interface::{CurrentConditions, DEFAULT_CURRENT_CONDITIONS_FORMAT, ForecastPoint, Units, WeatherData, WeatherError, WeatherProvider, format_duration, format_weather_data_with_format};
+use crate::providers::owm::model::{AirPollutionResponse, Forecast, StationReading, UvIndexResponse, WeatherState};
 
 
 static WEATHER_STATION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
     "^owm:ws:(?P<id>[0-9a-f]+)$"
 ).expect("failed to compile regex"));
 const ERROR_TEXT: &'static str = "An error occurred.";
+const PROVIDER_LABEL: &'static str = "OpenWeatherMap";
 
 
 fn kelvin_to_celsius(kelvin: f64) -> f64 {
     kelvin - 273.15
 }
 
-const fn weekday_to_short(wd: Weekday) -> &'static str {
-    match wd {
-        Weekday::Mon => "Mo",
-        Weekday::Tue => "Tu",
-        Weekday::Wed => "We",
-        Weekday::Thu => "Th",
-        Weekday::Fri => "Fr",
-        Weekday::Sat => "Sa",
-        Weekday::Sun => "Su",
-    }
-}
-
-fn time_tuple(value: i64, singular: &str, plural: &str) -> (i64, String) {
-    (value, format!("{} {}", value, if value == 1 { singular } else { plural }))
+/// Rounds a coordinate to four decimal places (about 11m of precision) so that requests for
+/// practically the same location share a single cache entry.
+fn round_coordinate(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
 }
 
-fn format_duration(mut duration: Duration) -> String {
-    let mut ago = false;
-    if duration < Duration::zero() {
-        duration = -duration;
-        ago = true;
-    }
-
-    if duration < Duration::seconds(1) {
-        return "now".into();
-    }
-
-    let mut o_tempora_o_mores: Vec<(i64, String)> = vec![
-        time_tuple(duration.num_days(), "day", "days"),
-        time_tuple(duration.num_hours() % 24, "hour", "hours"),
-        time_tuple(duration.num_minutes() % 60, "minute", "minutes"),
-        time_tuple(duration.num_seconds() % 60, "second", "seconds"),
-    ];
 
-    // remove the empty large units
-    while o_tempora_o_mores.len() > 0 && o_tempora_o_mores[0].0 == 0 {
-        o_tempora_o_mores.remove(0);
-    }
+/// An entry in one of [`OpenWeatherMapProvider`]'s response caches: a previously parsed response
+/// plus the point in time at which it stops being servable from the cache.
+#[derive(Clone, Debug)]
+struct CacheEntry<T> {
+    value: T,
+    expires_at: DateTime<Utc>,
+}
 
-    // show two consecutive units at most
-    while o_tempora_o_mores.len() > 2 {
-        o_tempora_o_mores.remove(o_tempora_o_mores.len() - 1);
-    }
 
-    // delete the second unit if it is zero
-    if o_tempora_o_mores.len() > 1 && o_tempora_o_mores[0].0 == 0 {
-        o_tempora_o_mores.remove(1);
-    }
+/// The worst-to-best-ranked Air Quality Index descriptions used by OpenWeatherMap's
+/// `air_pollution` endpoint (1 = best, 5 = worst).
+const AQI_DESCRIPTIONS: [&str; 5] = ["good", "fair", "moderate", "poor", "very poor"];
 
-    // fun!
-    let joint_vec: Vec<String> = o_tempora_o_mores
-        .iter()
-        .map(|otom| otom.1.clone())
-        .collect();
-    let joint = joint_vec.join(" ");
-
-    if ago {
-        format!("{} ago", joint)
-    } else {
-        format!("in {}", joint)
-    }
+fn aqi_description(aqi: u8) -> &'static str {
+    aqi.checked_sub(1)
+        .and_then(|index| AQI_DESCRIPTIONS.get(usize::from(index)))
+        .copied()
+        .unwrap_or("unknown")
 }
 
 
-#[derive(Clone, Debug, PartialEq)]
-struct ForecastSummary {
-    pub min_temp_kelvin: f64,
-    pub max_temp_kelvin: f64,
-    pub weather_states: Vec<String>,
+/// A queryable OpenWeatherMap endpoint, selectable (and orderable, for the purposes of rendering
+/// [`OpenWeatherMapProvider::get_weather_description_for_coordinates`]) via the `metrics` config
+/// key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Metric {
+    Weather,
+    Forecast,
+    AirQuality,
+    Uv,
 }
-impl ForecastSummary {
-    fn new(
-        min_temp_kelvin: f64,
-        max_temp_kelvin: f64,
-        weather_states: Vec<String>,
-    ) -> ForecastSummary {
-        ForecastSummary {
-            min_temp_kelvin,
-            max_temp_kelvin,
-            weather_states,
-        }
-    }
-
-    fn summarize_forecast(forecast: &Forecast) -> BTreeMap<Date<Utc>, ForecastSummary> {
-        let mut ret: BTreeMap<Date<Utc>, ForecastSummary> = BTreeMap::new();
-        for weather_state in &forecast.weather_states {
-            let timestamp = Utc.timestamp(weather_state.unix_timestamp, 0);
-            let date = timestamp.date();
-
-            let this_max_kelvin = weather_state.main.max_temp_kelvin;
-            let this_min_kelvin = weather_state.main.min_temp_kelvin;
-
-            let mut weather_states: Vec<String> = Vec::new();
-            for weather in &weather_state.weathers {
-                weather_states.push(weather.main.clone());
-            }
-
-            ret.entry(date)
-                .and_modify(|e| {
-                    e.max_temp_kelvin = e.max_temp_kelvin.max(this_max_kelvin);
-                    e.min_temp_kelvin = e.min_temp_kelvin.min(this_min_kelvin);
-
-                    for weather_state in weather_states.drain(..) {
-                        if !e.weather_states.contains(&weather_state) {
-                            e.weather_states.push(weather_state);
-                        }
-                    }
-                })
-                .or_insert_with(|| {
-                    ForecastSummary::new(
-                        this_min_kelvin,
-                        this_max_kelvin,
-                        weather_states,
-                    )
-                });
-
+impl Metric {
+    fn from_config_str(value: &str) -> Option<Self> {
+        match value {
+            "weather" => Some(Self::Weather),
+            "forecast" => Some(Self::Forecast),
+            "air_quality" => Some(Self::AirQuality),
+            "uv" => Some(Self::Uv),
+            _ => None,
         }
-        ret
     }
 }
 
@@ -155,8 +85,16 @@ pub(crate) struct OpenWeatherMapProvider {
     api_key: String,
     max_calls_per_minute: Option<usize>,
     weather_station_look_back_minutes: i64,
+    units: Units,
+    lang: String,
+    current_conditions_format: String,
+    metrics: Vec<Metric>,
+    cache_ttl_seconds: Option<i64>,
     last_queries: Mutex<BTreeSet<DateTime<Utc>>>,
     http_client: Mutex<reqwest::Client>,
+    weather_cache: Mutex<BTreeMap<String, CacheEntry<WeatherState>>>,
+    forecast_cache: Mutex<BTreeMap<String, CacheEntry<Forecast>>>,
+    station_cache: Mutex<BTreeMap<String, CacheEntry<Vec<StationReading>>>>,
 }
 impl OpenWeatherMapProvider {
     async fn check_cooldown_enough(&self, required_count: usize) -> bool {
@@ -182,6 +120,27 @@ impl OpenWeatherMapProvider {
         }
     }
 
+    /// Returns `key`'s cached value from `cache` if it is present and not yet expired.
+    async fn get_cached<T: Clone>(&self, cache: &Mutex<BTreeMap<String, CacheEntry<T>>>, key: &str) -> Option<T> {
+        let guard = cache.lock().await;
+        guard.get(key)
+            .filter(|entry| entry.expires_at > Utc::now())
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Stores `value` in `cache` under `key`, to expire [`Self::cache_ttl_seconds`] from now. Does
+    /// nothing if caching is disabled (`cache_ttl_seconds` is `None`).
+    async fn store_cached<T>(&self, cache: &Mutex<BTreeMap<String, CacheEntry<T>>>, key: String, value: T) {
+        let ttl_seconds = match self.cache_ttl_seconds {
+            Some(t) => t,
+            None => return,
+        };
+
+        let expires_at = Utc::now() + Duration::seconds(ttl_seconds);
+        let mut guard = cache.lock().await;
+        guard.insert(key, CacheEntry { value, expires_at });
+    }
+
     async fn get_and_populate_json<T: DeserializeOwned>(&self, uri: &str) -> Result<T, WeatherError> {
         debug!("obtaining weather data from {}", uri);
 
@@ -205,18 +164,26 @@ impl OpenWeatherMapProvider {
     }
 
     async fn get_weather_description_for_weather_station(&self, weather_station_id: &str) -> String {
-        let now_time = Utc::now().timestamp();
-        let lookback_time = now_time - (self.weather_station_look_back_minutes * 60);
-
-        let weather_uri = format!(
-            "https://api.openweathermap.org/data/3.0/measurements?station_id={}&type=m&limit=10&from={}&to={}&appid={}",
-            weather_station_id, lookback_time, now_time, self.api_key,
-        );
-        let mut readings: Vec<StationReading> = match self.get_and_populate_json(&weather_uri).await {
-            Ok(cw) => cw,
-            Err(e) => {
-                error!("error obtaining weather station readings: {}", e);
-                return ERROR_TEXT.to_owned();
+        let cache_key = weather_station_id.to_owned();
+        let mut readings: Vec<StationReading> = match self.get_cached(&self.station_cache, &cache_key).await {
+            Some(cached) => cached,
+            None => {
+                let now_time = Utc::now().timestamp();
+                let lookback_time = now_time - (self.weather_station_look_back_minutes * 60);
+
+                let weather_uri = format!(
+                    "https://api.openweathermap.org/data/3.0/measurements?station_id={}&type=m&limit=10&from={}&to={}&appid={}",
+                    weather_station_id, lookback_time, now_time, self.api_key,
+                );
+                let fetched: Vec<StationReading> = match self.get_and_populate_json(&weather_uri).await {
+                    Ok(cw) => cw,
+                    Err(e) => {
+                        error!("error obtaining weather station readings: {}", e);
+                        return ERROR_TEXT.to_owned();
+                    },
+                };
+                self.store_cached(&self.station_cache, cache_key, fetched.clone()).await;
+                fetched
             },
         };
 
@@ -249,6 +216,31 @@ impl OpenWeatherMapProvider {
 
         format!("OpenWeatherMap: {}", ret)
     }
+
+    async fn get_air_quality_line(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> Result<String, WeatherError> {
+        let uri = format!(
+            "https://api.openweathermap.org/data/2.5/air_pollution?lat={}&lon={}&appid={}",
+            latitude_deg_north, longitude_deg_east, self.api_key,
+        );
+        let response: AirPollutionResponse = self.get_and_populate_json(&uri).await?;
+        let entry = response.list.first()
+            .ok_or_else(|| WeatherError::new_str("air_pollution response contained no entries"))?;
+
+        Ok(format!(
+            "AQI {} ({}), PM2.5 {:.0} \u{b5}g/m\u{b3}, PM10 {:.0} \u{b5}g/m\u{b3}",
+            entry.main.aqi, aqi_description(entry.main.aqi),
+            entry.components.pm2_5_micrograms_per_cubic_meter, entry.components.pm10_micrograms_per_cubic_meter,
+        ))
+    }
+
+    async fn get_uv_index_line(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> Result<String, WeatherError> {
+        let uri = format!(
+            "https://api.openweathermap.org/data/2.5/uvi?lat={}&lon={}&appid={}",
+            latitude_deg_north, longitude_deg_east, self.api_key,
+        );
+        let response: UvIndexResponse = self.get_and_populate_json(&uri).await?;
+        Ok(format!("UV index {:.1}", response.uv_index))
+    }
 }
 #[async_trait]
 impl WeatherProvider for OpenWeatherMapProvider {
@@ -270,6 +262,47 @@ impl WeatherProvider for OpenWeatherMapProvider {
             config["weather_station_look_back_minutes"]
                 .as_i64().expect("weather_station_look_back_minutes is not representable as usize")
         };
+        let units = if config["units"].is_null() {
+            Units::default()
+        } else {
+            let value = config["units"]
+                .as_str().expect("units is not a string");
+            Units::from_config_str(value)
+                .expect("units is neither \"metric\" nor \"imperial\"")
+        };
+        let lang = if config["lang"].is_null() {
+            "en".to_owned()
+        } else {
+            config["lang"]
+                .as_str().expect("lang is not a string")
+                .to_owned()
+        };
+        let current_conditions_format = if config["format"].is_null() {
+            DEFAULT_CURRENT_CONDITIONS_FORMAT.to_owned()
+        } else {
+            config["format"]
+                .as_str().expect("format is not a string")
+                .to_owned()
+        };
+        let metrics = if config["metrics"].is_null() {
+            vec![Metric::Weather, Metric::Forecast]
+        } else {
+            config["metrics"].members().expect("metrics is not a list")
+                .map(|v| {
+                    let value = v.as_str().expect("metric is not a string");
+                    Metric::from_config_str(value)
+                        .expect("unknown metric (known: \"weather\", \"forecast\", \"air_quality\", \"uv\")")
+                })
+                .collect()
+        };
+        let cache_ttl_seconds = if config["cache_ttl_seconds"].is_null() {
+            None
+        } else {
+            Some(
+                config["cache_ttl_seconds"]
+                    .as_i64().expect("cache_ttl_seconds is either missing or not representable as i64")
+            )
+        };
         let last_queries = Mutex::new(
             "OpenWeatherMapProvider::last_queries",
             BTreeSet::new(),
@@ -278,13 +311,33 @@ impl WeatherProvider for OpenWeatherMapProvider {
             "OpenWeatherMapProvider::http_client",
             reqwest::Client::new(),
         );
+        let weather_cache = Mutex::new(
+            "OpenWeatherMapProvider::weather_cache",
+            BTreeMap::new(),
+        );
+        let forecast_cache = Mutex::new(
+            "OpenWeatherMapProvider::forecast_cache",
+            BTreeMap::new(),
+        );
+        let station_cache = Mutex::new(
+            "OpenWeatherMapProvider::station_cache",
+            BTreeMap::new(),
+        );
 
         OpenWeatherMapProvider {
             api_key,
             max_calls_per_minute,
             weather_station_look_back_minutes,
+            units,
+            lang,
+            current_conditions_format,
+            metrics,
+            cache_ttl_seconds,
             last_queries,
             http_client,
+            weather_cache,
+            forecast_cache,
+            station_cache,
         }
     }
 
@@ -300,80 +353,144 @@ impl WeatherProvider for OpenWeatherMapProvider {
     }
 
     async fn get_weather_description_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> String {
-        if !self.check_cooldown_enough(2).await {
-            return "OpenWeatherMap is on cooldown. :(".into();
+        let mut sections = Vec::new();
+        let mut weather_or_forecast_rendered = false;
+
+        for metric in &self.metrics {
+            match metric {
+                Metric::Weather | Metric::Forecast => {
+                    if weather_or_forecast_rendered {
+                        continue;
+                    }
+                    weather_or_forecast_rendered = true;
+
+                    let section = match self.get_weather_data_for_coordinates(latitude_deg_north, longitude_deg_east).await {
+                        Ok(data) => format_weather_data_with_format(PROVIDER_LABEL, &data, &self.current_conditions_format, self.units),
+                        Err(e) if e.is_cooldown() => format!("{} is on cooldown. :(", PROVIDER_LABEL),
+                        Err(e) => {
+                            error!("failed to obtain weather for lat={} lon={}: {}", latitude_deg_north, longitude_deg_east, e);
+                            format!("{}: {}", PROVIDER_LABEL, ERROR_TEXT)
+                        },
+                    };
+                    sections.push(section);
+                },
+                Metric::AirQuality => {
+                    let section = match self.get_air_quality_line(latitude_deg_north, longitude_deg_east).await {
+                        Ok(line) => line,
+                        Err(e) => {
+                            error!("failed to obtain air quality for lat={} lon={}: {}", latitude_deg_north, longitude_deg_east, e);
+                            format!("air quality: {}", ERROR_TEXT)
+                        },
+                    };
+                    sections.push(section);
+                },
+                Metric::Uv => {
+                    let section = match self.get_uv_index_line(latitude_deg_north, longitude_deg_east).await {
+                        Ok(line) => line,
+                        Err(e) => {
+                            error!("failed to obtain UV index for lat={} lon={}: {}", latitude_deg_north, longitude_deg_east, e);
+                            format!("UV index: {}", ERROR_TEXT)
+                        },
+                    };
+                    sections.push(section);
+                },
+            }
         }
 
-        let weather_uri = format!(
-            "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}",
-            latitude_deg_north, longitude_deg_east, self.api_key,
-        );
-        let current_weather: WeatherState = match self.get_and_populate_json(&weather_uri).await {
-            Ok(cw) => cw,
-            Err(e) => {
-                error!("failed to obtain weather for lat={} lon={}: {}", latitude_deg_north, longitude_deg_east, e);
-                return ERROR_TEXT.to_owned();
-            },
-        };
+        sections.join("\n")
+    }
 
-        let forecast_uri = format!(
-            "https://api.openweathermap.org/data/2.5/forecast?lat={}&lon={}&appid={}",
-            latitude_deg_north, longitude_deg_east, self.api_key,
+    async fn get_weather_data_for_coordinates(&self, latitude_deg_north: f64, longitude_deg_east: f64) -> Result<WeatherData, WeatherError> {
+        // cache key also covers `lang`, since the cached response's text is rendered in that
+        // language
+        let cache_key = format!(
+            "{:.4},{:.4},{}",
+            round_coordinate(latitude_deg_north), round_coordinate(longitude_deg_east), self.lang,
         );
-        let forecast: Forecast = match self.get_and_populate_json(&forecast_uri).await {
-            Ok(f) => f,
-            Err(e) => {
-                error!("failed to obtain forecast for lat={} lon={}: {}", latitude_deg_north, longitude_deg_east, e);
-                return ERROR_TEXT.to_owned();
-            },
-        };
-
-        let mut ret = String::new();
-
-        // weather status
-        if let Some(first_weather) = current_weather.weathers.first() {
-            ret.push_str(&first_weather.main);
-        }
 
-        // current temperature
-        if ret.len() > 0 {
-            ret.push_str(", ");
-        }
-        ret.push_str(&format!(
-            "{:.1} \u{B0}C", kelvin_to_celsius(current_weather.main.temperature_kelvin),
-        ));
+        let current = if self.metrics.contains(&Metric::Weather) {
+            let current_weather: WeatherState = match self.get_cached(&self.weather_cache, &cache_key).await {
+                Some(cached) => cached,
+                None => {
+                    if !self.check_cooldown_enough(1).await {
+                        return Err(WeatherError::new_cooldown(format!("{} is currently on cooldown", PROVIDER_LABEL)));
+                    }
 
-        // current humidity
-        if ret.len() > 0 {
-            ret.push_str(", ");
-        }
-        ret.push_str(&format!(
-            "{:.0}% humidity", current_weather.main.humidity_percent,
-        ));
+                    let weather_uri = format!(
+                        "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&lang={}",
+                        latitude_deg_north, longitude_deg_east, self.api_key, self.lang,
+                    );
+                    let fetched: WeatherState = self.get_and_populate_json(&weather_uri).await?;
+                    self.store_cached(&self.weather_cache, cache_key.clone(), fetched.clone()).await;
+                    fetched
+                },
+            };
+
+            // use the (potentially localized) free-text description rather than the canonical
+            // `main` category, which OpenWeatherMap never translates
+            let condition = current_weather.weathers.first()
+                .map(|w| w.description.clone())
+                .unwrap_or_else(String::new);
+            let sunrise = current_weather.sys
+                .and_then(|sys| sys.sunrise)
+                .map(|ts| Utc.timestamp(ts, 0));
+            let sunset = current_weather.sys
+                .and_then(|sys| sys.sunset)
+                .map(|ts| Utc.timestamp(ts, 0));
+            Some(CurrentConditions::new(
+                condition,
+                kelvin_to_celsius(current_weather.main.temperature_kelvin),
+                current_weather.main.humidity_percent,
+                Some(kelvin_to_celsius(current_weather.main.feels_like_kelvin)),
+                current_weather.wind.map(|w| w.speed_mps),
+                current_weather.wind.map(|w| w.direction_deg),
+                Some(current_weather.main.pressure_hectopascal),
+                current_weather.visibility,
+                sunrise,
+                sunset,
+            ))
+        } else {
+            None
+        };
 
-        if forecast.weather_states.len() > 0 {
-            if ret.len() > 0 {
-                ret.push_str("\n");
-            }
-            ret.push_str("forecast:\n");
+        let forecast_points = if self.metrics.contains(&Metric::Forecast) {
+            let forecast: Forecast = match self.get_cached(&self.forecast_cache, &cache_key).await {
+                Some(cached) => cached,
+                None => {
+                    if !self.check_cooldown_enough(1).await {
+                        return Err(WeatherError::new_cooldown(format!("{} is currently on cooldown", PROVIDER_LABEL)));
+                    }
 
-            let summarized = ForecastSummary::summarize_forecast(&forecast);
-            let forecast_list: Vec<String> = summarized
+                    let forecast_uri = format!(
+                        "https://api.openweathermap.org/data/2.5/forecast?lat={}&lon={}&appid={}&lang={}",
+                        latitude_deg_north, longitude_deg_east, self.api_key, self.lang,
+                    );
+                    let fetched: Forecast = self.get_and_populate_json(&forecast_uri).await?;
+                    self.store_cached(&self.forecast_cache, cache_key.clone(), fetched.clone()).await;
+                    fetched
+                },
+            };
+
+            forecast.weather_states
                 .iter()
-                .map(|(d, fs)| format!(
-                    "*{}* {}.{:02}. {} {:.1}\u{2013}{:.1} \u{B0}C",
-                    weekday_to_short(d.weekday()),
-                    d.day(),
-                    d.month(),
-                    fs.weather_states.join("/"),
-                    kelvin_to_celsius(fs.min_temp_kelvin),
-                    kelvin_to_celsius(fs.max_temp_kelvin),
-                ))
-                .collect();
-            let forecast_string = forecast_list.join("\n");
-            ret.push_str(&forecast_string);
-        }
+                .map(|weather_state| {
+                    let timestamp = Utc.timestamp(weather_state.unix_timestamp, 0);
+                    let conditions: Vec<String> = weather_state.weathers
+                        .iter()
+                        .map(|w| w.main.clone())
+                        .collect();
+                    ForecastPoint::new(
+                        timestamp,
+                        conditions.join("/"),
+                        kelvin_to_celsius(weather_state.main.min_temp_kelvin),
+                        kelvin_to_celsius(weather_state.main.max_temp_kelvin),
+                    )
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
 
-        format!("OpenWeatherMap:\n{}", ret)
+        Ok(WeatherData::new(current, forecast_points))
     }
 }
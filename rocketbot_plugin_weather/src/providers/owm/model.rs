@@ -6,6 +6,9 @@ pub(crate) struct Main {
     #[serde(rename = "temp")]
     pub temperature_kelvin: f64,
 
+    #[serde(rename = "feels_like")]
+    pub feels_like_kelvin: f64,
+
     #[serde(rename = "pressure")]
     pub pressure_hectopascal: f64,
 
@@ -19,6 +22,26 @@ pub(crate) struct Main {
     pub max_temp_kelvin: f64,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Wind {
+    #[serde(rename = "speed")]
+    pub speed_mps: f64,
+
+    #[serde(rename = "deg")]
+    pub direction_deg: f64,
+
+    #[serde(rename = "gust")]
+    pub gust_mps: Option<f64>,
+}
+
+/// Sunrise/sunset times, present on the `/weather` endpoint's `sys` object but absent (replaced by
+/// a bare "part of day" indicator) on the `/forecast` endpoint's, so both fields are optional.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Sys {
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub(crate) struct Weather {
     pub id: u64,
@@ -34,6 +57,12 @@ pub(crate) struct WeatherState {
 
     pub main: Main,
 
+    pub wind: Option<Wind>,
+
+    pub visibility: Option<u64>,
+
+    pub sys: Option<Sys>,
+
     pub name: Option<String>,
 
     #[serde(rename = "dt")]
@@ -85,3 +114,40 @@ pub(crate) struct Forecast {
     #[serde(rename = "list")]
     pub weather_states: Vec<WeatherState>,
 }
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct AirQualityIndex {
+    pub aqi: u8,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct AirQualityComponents {
+    #[serde(rename = "pm2_5")]
+    pub pm2_5_micrograms_per_cubic_meter: f64,
+
+    #[serde(rename = "pm10")]
+    pub pm10_micrograms_per_cubic_meter: f64,
+
+    #[serde(rename = "no2")]
+    pub no2_micrograms_per_cubic_meter: f64,
+
+    #[serde(rename = "o3")]
+    pub o3_micrograms_per_cubic_meter: f64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct AirPollutionEntry {
+    pub main: AirQualityIndex,
+    pub components: AirQualityComponents,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct AirPollutionResponse {
+    pub list: Vec<AirPollutionEntry>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct UvIndexResponse {
+    #[serde(rename = "value")]
+    pub uv_index: f64,
+}
@@ -1,40 +1,73 @@
 mod model;
+mod provider;
 
 
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Write;
 use std::sync::Weak;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use csv;
-use hyper::body::Buf;
+use chrono::Utc;
 use log::error;
 use reqwest;
 use rocketbot_interface::send_channel_message;
 use rocketbot_interface::commands::{CommandDefinitionBuilder, CommandInstance, CommandValueType};
 use rocketbot_interface::interfaces::{RocketBotInterface, RocketBotPlugin};
-use rocketbot_interface::model::ChannelMessage;
-use rocketbot_interface::sync::{Mutex, RwLock};
+use rocketbot_interface::model::{Attachment, ChannelMessage, OutgoingMessageWithAttachmentBuilder};
+use rocketbot_interface::sync::RwLock;
 use serde_json;
 use strsim::damerau_levenshtein;
 
-use crate::model::{DepartureLine, DepartureTimeEntry, MonitorWrapper, StoppingPoint};
+use crate::model::{DepartureLine, DepartureTimeEntry, StoppingPoint};
+use crate::provider::{DepartureProvider, WienerLinienProvider};
 
 
 #[derive(Clone, Debug, PartialEq)]
 struct StationDatabase {
     pub stations: Vec<(String, StoppingPoint)>,
     pub instant: Option<Instant>,
+
+    /// Indices into `stations`, sorted by lowercased name, so that `find_station` can locate the
+    /// contiguous range of prefix matches via a binary search instead of a full scan.
+    sorted_by_name: Vec<usize>,
+
+    /// Maps each character trigram occurring in a station's lowercased name to the indices of
+    /// stations whose name contains it, so `find_station`'s Damerau-Levenshtein similarity pass
+    /// only needs to consider stations sharing at least one trigram with the query.
+    trigram_index: HashMap<[char; 3], Vec<usize>>,
 }
 impl Default for StationDatabase {
     fn default() -> Self {
         Self {
             stations: Vec::new(),
             instant: None,
+            sorted_by_name: Vec::new(),
+            trigram_index: HashMap::new(),
         }
     }
 }
+impl StationDatabase {
+    /// Rebuilds `sorted_by_name` and `trigram_index` from the current `stations`. Must be called
+    /// whenever `stations` is replaced.
+    fn rebuild_index(&mut self) {
+        let mut sorted_by_name: Vec<usize> = (0..self.stations.len()).collect();
+        sorted_by_name.sort_unstable_by(|&a, &b| self.stations[a].0.cmp(&self.stations[b].0));
+
+        let mut trigram_index: HashMap<[char; 3], Vec<usize>> = HashMap::new();
+        for (idx, (lower_name, _station)) in self.stations.iter().enumerate() {
+            for trigram in name_trigrams(lower_name) {
+                trigram_index.entry(trigram)
+                    .or_insert_with(Vec::new)
+                    .push(idx);
+            }
+        }
+
+        self.sorted_by_name = sorted_by_name;
+        self.trigram_index = trigram_index;
+    }
+}
 
 
 #[derive(Clone, Debug, PartialEq)]
@@ -65,6 +98,39 @@ impl<'a> BestStations<'a> {
 }
 
 
+/// The minimum change in `delay_minutes` between two polls of a checked-in departure that is
+/// worth notifying the user about.
+const DELAY_CHANGE_THRESHOLD_MINUTES: i64 = 2;
+
+/// The number of upcoming departures exported by `{cpfx}depcal` if the caller does not specify
+/// `-n`/`--count`.
+const DEFAULT_ICAL_DEPARTURE_COUNT: i64 = 10;
+
+
+/// Escapes a piece of text for use within an iCalendar (RFC 5545) `TEXT` value.
+fn ics_escape_text(text: &str) -> String {
+    text
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+
+/// Computes the overlapping three-character windows ("trigrams") of `name`, used to narrow down
+/// Damerau-Levenshtein similarity candidates in `find_station`. Returns no trigrams for strings
+/// shorter than three characters, in which case callers fall back to a full scan.
+fn name_trigrams(name: &str) -> Vec<[char; 3]> {
+    let chars: Vec<char> = name.chars().collect();
+    if chars.len() < 3 {
+        return Vec::new();
+    }
+    chars.windows(3)
+        .map(|w| [w[0], w[1], w[2]])
+        .collect()
+}
+
+
 fn find_station<'a, 'b>(database: &'a StationDatabase, station_name_lower: &'b str) -> BestStations<'a> {
     let number_station = if let Ok(station_number) = station_name_lower.parse::<u32>() {
         // try pinpointing the station using the number
@@ -82,11 +148,28 @@ fn find_station<'a, 'b>(database: &'a StationDatabase, station_name_lower: &'b s
 
     // try finding the station using prefix and substring search
     // prefer stations with shorter names
+
+    // prefix matches form a contiguous range of `sorted_by_name` starting at the first name that
+    // is not lexicographically smaller than the query
     let mut prefix_stations: Vec<(&StoppingPoint, &str)> = Vec::new();
+    let range_start = database.sorted_by_name.partition_point(
+        |&idx| database.stations[idx].0.as_str() < station_name_lower
+    );
+    for &idx in &database.sorted_by_name[range_start..] {
+        let (lower_name, station) = &database.stations[idx];
+        if lower_name.starts_with(station_name_lower) {
+            prefix_stations.push((station, lower_name.as_str()));
+        } else {
+            // sorted order means no later entry can share this prefix either
+            break;
+        }
+    }
+
     let mut substring_stations: Vec<(&StoppingPoint, &str)> = Vec::new();
     for (lower_name, station) in &database.stations {
         if lower_name.starts_with(station_name_lower) {
-            prefix_stations.push((station, lower_name));
+            // already covered by prefix_stations
+            continue;
         } else if lower_name.contains(station_name_lower) {
             substring_stations.push((station, lower_name));
         }
@@ -103,17 +186,31 @@ fn find_station<'a, 'b>(database: &'a StationDatabase, station_name_lower: &'b s
         .map(|(st, _nm)| st)
         .collect();
 
-    // find the best station using Damerau-Levenshtein
+    // find the best station using Damerau-Levenshtein, restricting the search to stations that
+    // share at least one trigram with the query; fall back to a full scan if that set is empty
+    // (e.g. the query is too short to have any trigrams of its own)
+    let mut candidate_indices: BTreeSet<usize> = BTreeSet::new();
+    for trigram in name_trigrams(station_name_lower) {
+        if let Some(indices) = database.trigram_index.get(&trigram) {
+            candidate_indices.extend(indices.iter().copied());
+        }
+    }
+    let candidates: Vec<&(String, StoppingPoint)> = if candidate_indices.is_empty() {
+        database.stations.iter().collect()
+    } else {
+        candidate_indices.iter().map(|&idx| &database.stations[idx]).collect()
+    };
+
     let best_station_distance = {
         let mut bsd: Option<(&StoppingPoint, usize)> = None;
-        for (lower_name, station) in &database.stations {
+        for (lower_name, station) in candidates {
             let distance = damerau_levenshtein(&station_name_lower, lower_name);
             if let Some((_, best_distance)) = &bsd {
                 if distance < *best_distance {
-                    bsd = Some((&station, distance));
+                    bsd = Some((station, distance));
                 }
             } else {
-                bsd = Some((&station, distance));
+                bsd = Some((station, distance));
             }
         }
         bsd
@@ -132,9 +229,68 @@ fn find_station<'a, 'b>(database: &'a StationDatabase, station_name_lower: &'b s
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 struct Config {
+    /// Selects the [`DepartureProvider`] implementation to query; currently only `"wienerlinien"`
+    /// is recognized.
+    provider: String,
     stop_points_url: String,
     monitor_url_format: String,
     max_stations_age_min: u64,
+    checkin_poll_interval_seconds: u64,
+    watch_poll_interval_seconds: u64,
+    watch_imminent_threshold_minutes: u64,
+}
+
+
+/// A user's travelynx-style check-in to a specific upcoming departure, tracked across repeated
+/// polls of the monitor until the vehicle departs, is lost from the monitor, or the user checks
+/// out again.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct CheckIn {
+    /// Distinguishes this check-in from any earlier one the same user may have made, so that a
+    /// poll timer belonging to a checked-out-and-replaced check-in recognizes itself as stale
+    /// instead of polling on behalf of the new one.
+    pub generation: u64,
+    pub channel_name: String,
+    pub stop_id: u32,
+    pub station_name: String,
+    pub line_name: String,
+    pub towards: String,
+    pub last_delay_minutes: i64,
+}
+
+
+/// A channel's ongoing subscription to delay/condition alerts for a station, optionally filtered
+/// to a single line, established via `{cpfx}depwatch` and removed via `{cpfx}unwatch`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Watch {
+    pub channel_name: String,
+    pub stop_id: u32,
+    pub station_name: String,
+    pub line_filter: Option<String>,
+
+    /// The `(line_name, target_station, condition)` keys that were alerted on during the previous
+    /// poll, so that an unchanged condition is not re-announced on every single poll; a condition
+    /// is free to fire again once it has cleared (vanished from a poll) and then reappeared.
+    pub last_alert_keys: BTreeSet<(String, String, String)>,
+}
+
+
+/// Finds the first of `lines` whose (already display-resolved) `line_name` matches
+/// `line_name_lower` and whose target station matches `towards_lower`, preferring an exact match
+/// of the target station over a substring match.
+///
+/// `line_name` is matched against [`DepartureLine::line_name`] rather than the raw API
+/// `Line.name`, since a departure's vehicle can carry its own designation (see
+/// [`WienerLinienPlugin::get_departures`]) that differs from the line it is servicing; using the
+/// resolved name keeps check-ins consistent with what `{cpfx}dep` actually displayed.
+fn find_departure_line<'a>(lines: &'a [DepartureLine], line_name_lower: &str, towards_lower: &str) -> Option<&'a DepartureLine> {
+    let same_line: Vec<&DepartureLine> = lines.iter()
+        .filter(|dl| dl.line_name.to_lowercase() == line_name_lower)
+        .collect();
+
+    same_line.iter().copied()
+        .find(|dl| dl.target_station.to_lowercase() == towards_lower)
+        .or_else(|| same_line.iter().copied().find(|dl| dl.target_station.to_lowercase().contains(towards_lower)))
 }
 
 
@@ -142,7 +298,20 @@ pub struct WienerLinienPlugin {
     interface: Weak<dyn RocketBotInterface>,
     config: RwLock<Config>,
     station_database: RwLock<StationDatabase>,
-    http_client: Mutex<reqwest::Client>,
+    provider: Box<dyn DepartureProvider>,
+
+    /// The most recent `{cpfx}dep` lookup result per user, keyed by user ID, used to resolve
+    /// `{cpfx}checkin LINE TOWARDS` to a specific stop and departure.
+    last_departures: RwLock<HashMap<String, (u32, String, Vec<DepartureLine>)>>,
+
+    /// Active check-ins, keyed by user ID.
+    checkins: RwLock<HashMap<String, CheckIn>>,
+
+    /// Generates the next [`CheckIn::generation`] value.
+    next_checkin_generation: AtomicU64,
+
+    /// Active channel subscriptions established via `{cpfx}depwatch`.
+    watches: RwLock<Vec<Watch>>,
 }
 impl WienerLinienPlugin {
     async fn ensure_station_database_current(&self, config: &Config) {
@@ -158,144 +327,20 @@ impl WienerLinienPlugin {
             // load a new database
         }
 
-        let stations = {
-            let client_guard = self.http_client.lock().await;
-            let request = client_guard.get(&config.stop_points_url);
-            let response = match request.send().await {
-                Ok(r) => r,
-                Err(e) => {
-                    error!("failed to send stations update request to {:?}: {}", config.stop_points_url, e);
-                    return;
-                },
-            };
-            let response_bytes = match response.bytes().await {
-                Ok(b) => b,
-                Err(e) => {
-                    error!("failed to obtain bytes of stations update to {:?}: {}", config.stop_points_url, e);
-                    return;
-                },
-            };
-            let response_reader = response_bytes.reader();
-            let mut response_decoder = csv::ReaderBuilder::new()
-                .delimiter(b';')
-                .quote(b'"')
-                .has_headers(true)
-                .from_reader(response_reader);
-
-            let mut stations = Vec::new();
-            for record_res in response_decoder.deserialize() {
-                let station: StoppingPoint = match record_res {
-                    Ok(r) => r,
-                    Err(e) => {
-                        error!("failed to obtain a station entry from {:?}: {}", config.stop_points_url, e);
-                        return;
-                    },
-                };
-                let station_name_lower = station.name.to_lowercase();
-                stations.push((station_name_lower, station));
-            }
-
-            stations
+        let stations = match self.provider.load_stations(config).await {
+            Some(s) => s,
+            None => return,
         };
 
         database_guard.stations = stations;
+        database_guard.rebuild_index();
     }
 
+    /// Delegates to the configured [`DepartureProvider`]; kept as a plugin-level method (rather
+    /// than calling `self.provider.fetch_departures` directly from every command handler) purely
+    /// so the provider field itself stays an implementation detail of `WienerLinienPlugin`.
     async fn get_departures(&self, config: &Config, station_id: u32, line_number: Option<&str>) -> Option<Vec<Vec<DepartureLine>>> {
-        let url = config.monitor_url_format
-            .replace("{stopId}", &station_id.to_string());
-
-        let client_guard = self.http_client.lock().await;
-        let request = client_guard.get(&url);
-        let response = match request.send().await {
-            Ok(r) => r,
-            Err(e) => {
-                error!("failed to send monitor request to {:?}: {}", url, e);
-                return None;
-            },
-        };
-        let response_bytes = match response.bytes().await {
-            Ok(b) => b,
-            Err(e) => {
-                error!("failed to obtain bytes of monitor {:?}: {}", url, e);
-                return None;
-            },
-        };
-        let response_reader = response_bytes.reader();
-        let monitor_wrapper: MonitorWrapper = match serde_json::from_reader(response_reader) {
-            Ok(mw) => mw,
-            Err(e) => {
-                error!("failed to parse monitor {:?}: {}", url, e);
-                return None;
-            },
-        };
-
-        let mut platform_to_deps: HashMap<Option<i64>, BTreeMap<(String, String), DepartureLine>> = HashMap::new();
-        for monitor in &monitor_wrapper.data.monitors {
-            let platform_number = monitor.location_stop.properties.attributes.rbl;
-            let dep_lines = platform_to_deps
-                .entry(platform_number)
-                .or_insert_with(|| BTreeMap::new());
-
-            for line in &monitor.lines {
-                if let Some(ln) = line_number {
-                    if line.name != ln {
-                        continue;
-                    }
-                }
-
-                for departure in &line.departure_data.departures {
-                    let countdown = match departure.departure_time.countdown {
-                        Some(cd) => cd,
-                        None => continue,
-                    };
-
-                    let (line_and_target, target_full, barrier_free, realtime, traffic_jam) = if let Some(vehicle) = &departure.vehicle {
-                        (
-                            (vehicle.name.clone(), vehicle.towards.to_lowercase()),
-                            vehicle.towards.clone(),
-                            vehicle.barrier_free,
-                            vehicle.realtime_supported,
-                            vehicle.traffic_jam,
-                        )
-                    } else {
-                        (
-                            (line.name.clone(), line.towards.to_lowercase()),
-                            line.towards.clone(),
-                            line.barrier_free,
-                            line.realtime_supported,
-                            line.traffic_jam,
-                        )
-                    };
-
-                    let dep_entry = dep_lines
-                        .entry(line_and_target.clone())
-                        .or_insert_with(|| DepartureLine::new(
-                            line_and_target.0,
-                            target_full,
-                            Vec::new(),
-                        ));
-                    dep_entry.departures.push(DepartureTimeEntry::new(
-                        countdown,
-                        barrier_free,
-                        realtime,
-                        traffic_jam,
-                    ))
-                }
-            }
-        }
-
-        let mut ret_monitors: Vec<Vec<DepartureLine>> = platform_to_deps.into_values()
-            .map(|deps| deps.into_values().collect())
-            .collect();
-        ret_monitors.sort_unstable_by_key(|rm: &Vec<DepartureLine>| {
-            let rm_vec: Vec<(String, String)> = rm.iter()
-                .map(|dl| (dl.line_name.clone(), dl.target_station.to_lowercase()))
-                .collect();
-            rm_vec
-        });
-
-        Some(ret_monitors)
+        self.provider.fetch_departures(config, station_id, line_number).await
     }
 
     async fn channel_command_dep(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
@@ -348,6 +393,15 @@ impl WienerLinienPlugin {
             },
         };
 
+        {
+            let flat_departures: Vec<DepartureLine> = departures.iter().flatten().cloned().collect();
+            let mut last_dep_guard = self.last_departures.write().await;
+            last_dep_guard.insert(
+                channel_message.message.sender.id.clone(),
+                (station.stop_id, station.name.clone(), flat_departures),
+            );
+        }
+
         let departures_string = if departures.len() == 0 {
             format!("No departures at *{}*", station.name)
         } else {
@@ -378,6 +432,10 @@ impl WienerLinienPlugin {
                         if !departure.realtime {
                             // not realtime: question mark
                             ds.push_str(" \u{2753}");
+                        } else if departure.delay_minutes != 0 {
+                            write!(&mut ds, " ({:+})", departure.delay_minutes).unwrap();
+                        } else {
+                            ds.push_str(" (on time)");
                         }
                     }
                 }
@@ -465,7 +523,452 @@ impl WienerLinienPlugin {
         }
     }
 
+    async fn channel_command_depcal(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        self.ensure_station_database_current(&config_guard).await;
+
+        let line = command.options.get("line")
+            .or_else(|| command.options.get("l"))
+            .map(|v| v.as_str().expect("line not a string").to_owned());
+        let count = command.options.get("count")
+            .or_else(|| command.options.get("n"))
+            .map(|v| v.as_i64().expect("--count value not an i64"))
+            .unwrap_or(DEFAULT_ICAL_DEPARTURE_COUNT)
+            .max(1) as usize;
+        let station_name_lower = command.rest.trim().to_lowercase();
+        let force_search = command.flags.contains("s") || command.flags.contains("search");
+
+        let station = {
+            let db_guard = self.station_database
+                .read().await;
+            match find_station(&*db_guard, &station_name_lower).best(!force_search) {
+                Some(bs) => bs.clone(),
+                None => {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Station not found.",
+                    ).await;
+                    return;
+                },
+            }
+        };
+
+        let departures_opt = self.get_departures(
+            &config_guard,
+            station.stop_id,
+            line.as_deref(),
+        ).await;
+        let departures = match departures_opt {
+            Some(d) => d,
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "Failed to obtain departures.",
+                ).await;
+                return;
+            },
+        };
+
+        let mut entries: Vec<(&DepartureLine, &DepartureTimeEntry)> = departures.iter()
+            .flatten()
+            .flat_map(|dl| dl.departures.iter().map(move |entry| (dl, entry)))
+            .collect();
+        entries.sort_unstable_by_key(|(_dl, entry)| entry.countdown);
+        entries.truncate(count);
+
+        if entries.len() == 0 {
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                "No departures to export.",
+            ).await;
+            return;
+        }
+
+        let now = Utc::now();
+        let dtstamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+        let mut ics = String::new();
+        ics.push_str("BEGIN:VCALENDAR\r\n");
+        ics.push_str("VERSION:2.0\r\n");
+        ics.push_str("PRODID:-//rocketbot//wienerlinien//EN\r\n");
+        for (i, (line, entry)) in entries.iter().enumerate() {
+            let departure_instant = now + chrono::Duration::minutes(entry.countdown as i64);
+            let dtstart = departure_instant.format("%Y%m%dT%H%M%SZ").to_string();
+            let dtend = (departure_instant + chrono::Duration::minutes(1)).format("%Y%m%dT%H%M%SZ").to_string();
+
+            ics.push_str("BEGIN:VEVENT\r\n");
+            write!(&mut ics, "UID:wienerlinien-{}-{}-{}@rocketbot\r\n", station.stop_id, dtstamp, i).unwrap();
+            write!(&mut ics, "DTSTAMP:{}\r\n", dtstamp).unwrap();
+            write!(&mut ics, "DTSTART:{}\r\n", dtstart).unwrap();
+            write!(&mut ics, "DTEND:{}\r\n", dtend).unwrap();
+            write!(&mut ics, "SUMMARY:{}\r\n", ics_escape_text(&format!("{} \u{2192} {}", line.line_name, line.target_station))).unwrap();
+            write!(&mut ics, "LOCATION:{}\r\n", ics_escape_text(&station.name)).unwrap();
+            write!(&mut ics, "X-WIENERLINIEN-DELAY-MINUTES:{}\r\n", entry.delay_minutes).unwrap();
+            write!(&mut ics, "X-WIENERLINIEN-ACCESSIBLE:{}\r\n", entry.accessible).unwrap();
+            ics.push_str("END:VEVENT\r\n");
+        }
+        ics.push_str("END:VCALENDAR\r\n");
+
+        let attachment = Attachment::new(
+            ics.into_bytes(),
+            "departures.ics".to_owned(),
+            "text/calendar".to_owned(),
+            None,
+        );
+        interface.send_channel_message_with_attachment(
+            &channel_message.channel.name,
+            OutgoingMessageWithAttachmentBuilder::new(attachment)
+                .build(),
+        ).await;
+    }
+
+    async fn channel_command_checkin(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let user_id = channel_message.message.sender.id.clone();
+        let line_name = &command.args[0];
+        let line_name_lower = line_name.to_lowercase();
+        let towards_lower = command.rest.trim().to_lowercase();
+        if towards_lower.len() == 0 {
+            send_channel_message!(
+                interface,
+                &channel_message.channel.name,
+                "Please specify both a line and a destination to track.",
+            ).await;
+            return;
+        }
+
+        let (stop_id, station_name, departures) = {
+            let last_dep_guard = self.last_departures.read().await;
+            match last_dep_guard.get(&user_id) {
+                Some((sid, name, deps)) => (*sid, name.clone(), deps.clone()),
+                None => {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "No recent departure lookup found; look up a station's departures first.",
+                    ).await;
+                    return;
+                },
+            }
+        };
+
+        let matching_line = match find_departure_line(&departures, &line_name_lower, &towards_lower) {
+            Some(dl) => dl.clone(),
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    &format!("No departure of line {} towards {} found at {}.", line_name, command.rest.trim(), station_name),
+                ).await;
+                return;
+            },
+        };
+        let next_departure = match matching_line.departures.first() {
+            Some(d) => d,
+            None => {
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "That line currently has no upcoming departures.",
+                ).await;
+                return;
+            },
+        };
+
+        let generation = self.next_checkin_generation.fetch_add(1, Ordering::Relaxed);
+        let check_in = CheckIn {
+            generation,
+            channel_name: channel_message.channel.name.clone(),
+            stop_id,
+            station_name: station_name.clone(),
+            line_name: matching_line.line_name.clone(),
+            towards: matching_line.target_station.clone(),
+            last_delay_minutes: next_departure.delay_minutes,
+        };
+
+        let response = format!(
+            "Tracking {} towards {} from {} (currently {} min{}).",
+            check_in.line_name,
+            check_in.towards,
+            station_name,
+            next_departure.countdown,
+            if next_departure.delay_minutes != 0 { format!(", {:+} min delay", next_departure.delay_minutes) } else { String::new() },
+        );
+
+        {
+            // check-and-insert happens under a single lock acquisition to avoid a race between
+            // two concurrent check-ins from the same user
+            let mut checkins_guard = self.checkins.write().await;
+            if checkins_guard.contains_key(&user_id) {
+                drop(checkins_guard);
+                send_channel_message!(
+                    interface,
+                    &channel_message.channel.name,
+                    "You are already tracking a departure. Check out first.",
+                ).await;
+                return;
+            }
+            checkins_guard.insert(user_id.clone(), check_in);
+        }
+
+        let poll_interval_seconds = self.config.read().await.checkin_poll_interval_seconds;
+        self.schedule_checkin_poll(&interface, &user_id, generation, poll_interval_seconds).await;
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
+    }
+
+    async fn channel_command_checkout(&self, channel_message: &ChannelMessage, _command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let user_id = channel_message.message.sender.id.clone();
+        let removed = {
+            let mut checkins_guard = self.checkins.write().await;
+            checkins_guard.remove(&user_id)
+        };
+
+        let response = match removed {
+            Some(check_in) => format!(
+                "Stopped tracking {} towards {}.",
+                check_in.line_name,
+                check_in.towards,
+            ),
+            None => "You are not tracking any departure.".to_owned(),
+        };
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
+    }
+
+    async fn channel_command_depwatch(&self, channel_message: &ChannelMessage, command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        self.ensure_station_database_current(&config_guard).await;
+
+        let line_filter = command.options.get("line")
+            .or_else(|| command.options.get("l"))
+            .map(|v| v.as_str().expect("line not a string").to_owned());
+        let station_name_lower = command.rest.trim().to_lowercase();
+        let force_search = command.flags.contains("s") || command.flags.contains("search");
+
+        let station = {
+            let db_guard = self.station_database
+                .read().await;
+            match find_station(&*db_guard, &station_name_lower).best(!force_search) {
+                Some(bs) => bs.clone(),
+                None => {
+                    send_channel_message!(
+                        interface,
+                        &channel_message.channel.name,
+                        "Station not found.",
+                    ).await;
+                    return;
+                },
+            }
+        };
+
+        let watch = Watch {
+            channel_name: channel_message.channel.name.clone(),
+            stop_id: station.stop_id,
+            station_name: station.name.clone(),
+            line_filter: line_filter.clone(),
+            last_alert_keys: BTreeSet::new(),
+        };
+
+        let is_first_watch = {
+            let mut watches_guard = self.watches.write().await;
+            let is_first_watch = watches_guard.is_empty();
+            watches_guard.push(watch);
+            is_first_watch
+        };
+
+        if is_first_watch {
+            self.schedule_watch_poll(&interface, config_guard.watch_poll_interval_seconds).await;
+        }
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &format!(
+                "Now watching *{}*{} for delays, traffic jams and imminent departures.",
+                station.name,
+                line_filter.map(|l| format!(" (line {})", l)).unwrap_or_default(),
+            ),
+        ).await;
+    }
+
+    async fn channel_command_unwatch(&self, channel_message: &ChannelMessage, _command: &CommandInstance) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let removed_count = {
+            let mut watches_guard = self.watches.write().await;
+            let count_before = watches_guard.len();
+            watches_guard.retain(|w| w.channel_name != channel_message.channel.name);
+            count_before - watches_guard.len()
+        };
+
+        let response = if removed_count > 0 {
+            format!("Stopped watching {} station{}.", removed_count, if removed_count == 1 { "" } else { "s" })
+        } else {
+            "This channel is not watching any stations.".to_owned()
+        };
+
+        send_channel_message!(
+            interface,
+            &channel_message.channel.name,
+            &response,
+        ).await;
+    }
+
+    /// Registers a timer that will cause a poll of all active channel watches via `timer_elapsed`
+    /// once `poll_interval_seconds` have elapsed.
+    async fn schedule_watch_poll(&self, interface: &std::sync::Arc<dyn RocketBotInterface>, poll_interval_seconds: u64) {
+        let next_poll = Utc::now() + chrono::Duration::seconds(poll_interval_seconds as i64);
+        let custom_data = serde_json::json!(["wienerlinien_watch_poll"]);
+        interface.register_timer(next_poll, custom_data).await;
+    }
+
+    /// Polls every distinct station currently subscribed to via `{cpfx}depwatch` and sends a
+    /// channel message for each condition (`traffic_jam`, `realtime_lost`, `imminent`) that has
+    /// newly appeared since the previous poll. Reschedules itself as long as at least one watch
+    /// remains.
+    async fn timer_elapsed_watch_poll(&self) {
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+
+        let watches_snapshot: Vec<Watch> = {
+            let watches_guard = self.watches.read().await;
+            watches_guard.clone()
+        };
+
+        let stop_ids: BTreeSet<u32> = watches_snapshot.iter()
+            .map(|w| w.stop_id)
+            .collect();
+
+        let mut stop_id_to_lines: HashMap<u32, Vec<DepartureLine>> = HashMap::new();
+        for stop_id in stop_ids {
+            let monitors = self.get_departures(&config_guard, stop_id, None).await;
+            let flat_lines: Vec<DepartureLine> = monitors
+                .map(|ms| ms.into_iter().flatten().collect())
+                .unwrap_or_default();
+            stop_id_to_lines.insert(stop_id, flat_lines);
+        }
+
+        for watch in &watches_snapshot {
+            let lines = match stop_id_to_lines.get(&watch.stop_id) {
+                Some(l) => l,
+                None => continue,
+            };
+
+            let mut current_alert_keys = BTreeSet::new();
+            for line in lines {
+                if let Some(filter) = &watch.line_filter {
+                    if line.line_name.to_lowercase() != filter.to_lowercase() {
+                        continue;
+                    }
+                }
+                let departure = match line.departures.first() {
+                    Some(d) => d,
+                    None => continue,
+                };
+
+                if departure.traffic_jam {
+                    current_alert_keys.insert((line.line_name.clone(), line.target_station.clone(), "traffic_jam".to_owned()));
+                }
+                if !departure.realtime {
+                    current_alert_keys.insert((line.line_name.clone(), line.target_station.clone(), "realtime_lost".to_owned()));
+                }
+                if departure.countdown <= config_guard.watch_imminent_threshold_minutes {
+                    current_alert_keys.insert((line.line_name.clone(), line.target_station.clone(), "imminent".to_owned()));
+                }
+            }
+
+            for (line_name, target_station, condition) in current_alert_keys.difference(&watch.last_alert_keys) {
+                let message = match condition.as_str() {
+                    "traffic_jam" => format!("{} towards {} at {} is stuck in a traffic jam.", line_name, target_station, watch.station_name),
+                    "realtime_lost" => format!("{} towards {} at {} has lost real-time tracking.", line_name, target_station, watch.station_name),
+                    "imminent" => format!("{} towards {} at {} is about to depart.", line_name, target_station, watch.station_name),
+                    _ => continue,
+                };
+                send_channel_message!(interface, &watch.channel_name, &message).await;
+            }
+
+            {
+                let mut watches_guard = self.watches.write().await;
+                if let Some(stored) = watches_guard.iter_mut().find(|w|
+                    w.channel_name == watch.channel_name
+                        && w.stop_id == watch.stop_id
+                        && w.line_filter == watch.line_filter
+                ) {
+                    stored.last_alert_keys = current_alert_keys;
+                }
+            }
+        }
+
+        let should_reschedule = !self.watches.read().await.is_empty();
+        if should_reschedule {
+            self.schedule_watch_poll(&interface, config_guard.watch_poll_interval_seconds).await;
+        }
+    }
+
+    /// Registers a timer that will cause a poll of the given user's active check-in via
+    /// `timer_elapsed` once `poll_interval_seconds` have elapsed. `generation` must match the
+    /// check-in's current [`CheckIn::generation`]; stale timers from a check-in the user has
+    /// since replaced are dropped by `timer_elapsed`.
+    async fn schedule_checkin_poll(&self, interface: &std::sync::Arc<dyn RocketBotInterface>, user_id: &str, generation: u64, poll_interval_seconds: u64) {
+        let next_poll = Utc::now() + chrono::Duration::seconds(poll_interval_seconds as i64);
+        let custom_data = serde_json::json!(["wienerlinien_checkin", user_id, generation]);
+        interface.register_timer(next_poll, custom_data).await;
+    }
+
+    /// Removes the user's check-in, but only if it is still the one identified by `generation`;
+    /// a mismatch means the user has checked out and/or back in again while we were polling, so
+    /// the entry we'd otherwise remove is no longer the one this poll was started for.
+    async fn remove_checkin_if_current(&self, user_id: &str, generation: u64) {
+        let mut checkins_guard = self.checkins.write().await;
+        if checkins_guard.get(user_id).map(|c| c.generation) == Some(generation) {
+            checkins_guard.remove(user_id);
+        }
+    }
+
     fn try_get_config(config: serde_json::Value) -> Result<Config, &'static str> {
+        let provider = config["provider"]
+            .as_str().ok_or("provider not a string")?
+            .to_owned();
         let stop_points_url = config["stop_points_url"]
             .as_str().ok_or("stop_points_url not a string")?
             .to_owned();
@@ -474,11 +977,21 @@ impl WienerLinienPlugin {
             .to_owned();
         let max_stations_age_min = config["max_stations_age_min"]
             .as_u64().ok_or("max_stations_age_min not a u64")?;
+        let checkin_poll_interval_seconds = config["checkin_poll_interval_seconds"]
+            .as_u64().ok_or("checkin_poll_interval_seconds not a u64")?;
+        let watch_poll_interval_seconds = config["watch_poll_interval_seconds"]
+            .as_u64().ok_or("watch_poll_interval_seconds not a u64")?;
+        let watch_imminent_threshold_minutes = config["watch_imminent_threshold_minutes"]
+            .as_u64().ok_or("watch_imminent_threshold_minutes not a u64")?;
 
         Ok(Config {
+            provider,
             stop_points_url,
             monitor_url_format,
             max_stations_age_min,
+            checkin_poll_interval_seconds,
+            watch_poll_interval_seconds,
+            watch_imminent_threshold_minutes,
         })
     }
 }
@@ -492,6 +1005,10 @@ impl RocketBotPlugin for WienerLinienPlugin {
 
         let config_object = Self::try_get_config(config)
             .expect("failed to load config");
+        let provider: Box<dyn DepartureProvider> = match config_object.provider.as_str() {
+            "wienerlinien" => Box::new(WienerLinienProvider::new(reqwest::Client::new())),
+            other => panic!("unknown wienerlinien provider {:?}", other),
+        };
         let config_lock = RwLock::new(
             "WienerLinienPlugin::config",
             config_object,
@@ -501,10 +1018,6 @@ impl RocketBotPlugin for WienerLinienPlugin {
             "WienerLinienPlugin::station_database",
             StationDatabase::default(),
         );
-        let http_client = Mutex::new(
-            "WienerLinienPlugin::client",
-            reqwest::Client::new(),
-        );
 
         my_interface.register_channel_command(
             &CommandDefinitionBuilder::new(
@@ -528,12 +1041,85 @@ impl RocketBotPlugin for WienerLinienPlugin {
             )
                 .build()
         ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "depcal",
+                "wienerlinien",
+                "{cpfx}depcal [-l LINE] [-n COUNT] STATION",
+                "Exports upcoming departures from a given station as an iCalendar (.ics) attachment.",
+            )
+                .add_flag("s")
+                .add_flag("search")
+                .add_option("l", CommandValueType::String)
+                .add_option("line", CommandValueType::String)
+                .add_option("n", CommandValueType::Integer)
+                .add_option("count", CommandValueType::Integer)
+                .build()
+        ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "checkin",
+                "wienerlinien",
+                "{cpfx}checkin LINE TOWARDS",
+                "Tracks a specific upcoming departure (as seen via {cpfx}dep) and reports on delay changes and departure.",
+            )
+                .arg_count(1)
+                .build()
+        ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "checkout",
+                "wienerlinien",
+                "{cpfx}checkout",
+                "Stops tracking the departure previously checked into with {cpfx}checkin.",
+            )
+                .build()
+        ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "depwatch",
+                "wienerlinien",
+                "{cpfx}depwatch [-l LINE] STATION",
+                "Subscribes this channel to delay, traffic-jam and imminent-departure alerts for a station.",
+            )
+                .add_flag("s")
+                .add_flag("search")
+                .add_option("l", CommandValueType::String)
+                .add_option("line", CommandValueType::String)
+                .build()
+        ).await;
+        my_interface.register_channel_command(
+            &CommandDefinitionBuilder::new(
+                "unwatch",
+                "wienerlinien",
+                "{cpfx}unwatch",
+                "Removes this channel's subscriptions previously set up with {cpfx}depwatch.",
+            )
+                .build()
+        ).await;
+
+        let last_departures = RwLock::new(
+            "WienerLinienPlugin::last_departures",
+            HashMap::new(),
+        );
+        let checkins = RwLock::new(
+            "WienerLinienPlugin::checkins",
+            HashMap::new(),
+        );
+        let watches = RwLock::new(
+            "WienerLinienPlugin::watches",
+            Vec::new(),
+        );
 
         Self {
             interface,
             config: config_lock,
             station_database,
-            http_client,
+            provider,
+            last_departures,
+            checkins,
+            next_checkin_generation: AtomicU64::new(0),
+            watches,
         }
     }
 
@@ -546,6 +1132,16 @@ impl RocketBotPlugin for WienerLinienPlugin {
             self.channel_command_dep(channel_message, command).await
         } else if command.name == "stations" {
             self.channel_command_stations(channel_message, command).await
+        } else if command.name == "depcal" {
+            self.channel_command_depcal(channel_message, command).await
+        } else if command.name == "checkin" {
+            self.channel_command_checkin(channel_message, command).await
+        } else if command.name == "checkout" {
+            self.channel_command_checkout(channel_message, command).await
+        } else if command.name == "depwatch" {
+            self.channel_command_depwatch(channel_message, command).await
+        } else if command.name == "unwatch" {
+            self.channel_command_unwatch(channel_message, command).await
         }
     }
 
@@ -554,11 +1150,127 @@ impl RocketBotPlugin for WienerLinienPlugin {
             Some(include_str!("../help/dep.md").to_owned())
         } else if command_name == "stations" {
             Some(include_str!("../help/stations.md").to_owned())
+        } else if command_name == "depcal" {
+            Some(include_str!("../help/depcal.md").to_owned())
+        } else if command_name == "checkin" {
+            Some(include_str!("../help/checkin.md").to_owned())
+        } else if command_name == "checkout" {
+            Some(include_str!("../help/checkout.md").to_owned())
+        } else if command_name == "depwatch" {
+            Some(include_str!("../help/depwatch.md").to_owned())
+        } else if command_name == "unwatch" {
+            Some(include_str!("../help/unwatch.md").to_owned())
         } else {
             None
         }
     }
 
+    async fn timer_elapsed(&self, custom_data: &serde_json::Value) {
+        if !custom_data.is_array() {
+            return;
+        }
+        if custom_data[0] == "wienerlinien_watch_poll" {
+            self.timer_elapsed_watch_poll().await;
+            return;
+        }
+        if custom_data[0] != "wienerlinien_checkin" {
+            return;
+        }
+        let user_id = match custom_data[1].as_str() {
+            Some(u) => u.to_owned(),
+            None => return,
+        };
+
+        let check_in = {
+            let checkins_guard = self.checkins.read().await;
+            match checkins_guard.get(&user_id) {
+                Some(c) => c.clone(),
+                None => return, // checked out in the meantime
+            }
+        };
+        if custom_data[2].as_u64() != Some(check_in.generation) {
+            // stale timer belonging to a check-in the user has since replaced
+            return;
+        }
+
+        let interface = match self.interface.upgrade() {
+            None => return,
+            Some(i) => i,
+        };
+
+        let config_guard = self.config.read().await;
+        // don't filter by check_in.line_name here: it is the display-resolved DepartureLine name
+        // (which may come from a vehicle designation), not the raw Line.name that get_departures'
+        // line_number parameter compares against
+        let monitors = self.get_departures(&config_guard, check_in.stop_id, None).await;
+        let flat_lines: Vec<DepartureLine> = monitors
+            .map(|ms| ms.into_iter().flatten().collect())
+            .unwrap_or_default();
+        let line_name_lower = check_in.line_name.to_lowercase();
+        let towards_lower = check_in.towards.to_lowercase();
+        let next_departure = find_departure_line(&flat_lines, &line_name_lower, &towards_lower)
+            .and_then(|dl| dl.departures.first());
+
+        match next_departure {
+            None => {
+                send_channel_message!(
+                    interface,
+                    &check_in.channel_name,
+                    &format!(
+                        "Lost track of {} towards {} (no longer listed); stopped tracking.",
+                        check_in.line_name,
+                        check_in.towards,
+                    ),
+                ).await;
+                self.remove_checkin_if_current(&user_id, check_in.generation).await;
+            },
+            Some(departure) if departure.countdown == 0 => {
+                send_channel_message!(
+                    interface,
+                    &check_in.channel_name,
+                    &format!(
+                        "{} towards {} has departed ({:+} min).",
+                        check_in.line_name,
+                        check_in.towards,
+                        departure.delay_minutes,
+                    ),
+                ).await;
+                self.remove_checkin_if_current(&user_id, check_in.generation).await;
+            },
+            Some(departure) => {
+                if (departure.delay_minutes - check_in.last_delay_minutes).abs() >= DELAY_CHANGE_THRESHOLD_MINUTES {
+                    send_channel_message!(
+                        interface,
+                        &check_in.channel_name,
+                        &format!(
+                            "{} towards {}: delay now {:+} min (was {:+} min), {} min to departure.",
+                            check_in.line_name,
+                            check_in.towards,
+                            departure.delay_minutes,
+                            check_in.last_delay_minutes,
+                            departure.countdown,
+                        ),
+                    ).await;
+                }
+
+                let still_current = {
+                    let mut checkins_guard = self.checkins.write().await;
+                    match checkins_guard.get_mut(&user_id) {
+                        Some(c) if c.generation == check_in.generation => {
+                            c.last_delay_minutes = departure.delay_minutes;
+                            true
+                        },
+                        _ => false, // checked out and/or replaced while we were polling
+                    }
+                };
+
+                if still_current {
+                    self.schedule_checkin_poll(&interface, &user_id, check_in.generation, config_guard.checkin_poll_interval_seconds).await;
+                }
+            },
+        }
+    }
+
     async fn configuration_updated(&self, new_config: serde_json::Value) -> bool {
         match Self::try_get_config(new_config) {
             Ok(c) => {
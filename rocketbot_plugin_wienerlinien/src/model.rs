@@ -104,6 +104,7 @@ pub(crate) struct DepartureTimeEntry {
     pub accessible: bool,
     pub realtime: bool,
     pub traffic_jam: bool,
+    pub delay_minutes: i64,
 }
 impl DepartureTimeEntry {
     pub fn new(
@@ -111,12 +112,14 @@ impl DepartureTimeEntry {
         accessible: bool,
         realtime: bool,
         traffic_jam: bool,
+        delay_minutes: i64,
     ) -> Self {
         Self {
             countdown,
             accessible,
             realtime,
             traffic_jam,
+            delay_minutes,
         }
     }
 }
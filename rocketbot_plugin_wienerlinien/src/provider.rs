@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use hyper::body::Buf;
+use log::error;
+
+use crate::Config;
+use crate::model::{DepartureLine, DepartureTimeEntry, MonitorWrapper, StoppingPoint};
+
+
+/// Supplies station metadata and live departure data to [`crate::WienerLinienPlugin`]. Introduced
+/// so that a second backend (e.g. a generic GTFS-Realtime TripUpdate feed or another city's
+/// monitor API) can drive the same `{cpfx}dep`/`{cpfx}stations`/`{cpfx}depwatch` command surface
+/// without forking the plugin. `find_station`, `BestStations` and the message formatting in
+/// [`crate::WienerLinienPlugin`] operate only on [`StoppingPoint`] and [`DepartureLine`] and are
+/// therefore already provider-agnostic.
+#[async_trait]
+pub(crate) trait DepartureProvider: Send + Sync {
+    /// Fetches the full list of known stations, each paired with its lowercased name for use by
+    /// `find_station`. Returns `None` if the station list could not be obtained.
+    async fn load_stations(&self, config: &Config) -> Option<Vec<(String, StoppingPoint)>>;
+
+    /// Fetches the current departures at `station_id`, grouped by platform and optionally
+    /// filtered to a single line. Returns `None` if the departures could not be obtained.
+    async fn fetch_departures(&self, config: &Config, station_id: u32, line_number: Option<&str>) -> Option<Vec<Vec<DepartureLine>>>;
+}
+
+
+/// Computes how many minutes late a departure is, by parsing `time_planned` and `time_real` as
+/// ISO-8601 timestamps and floor-dividing their difference in seconds by 60. Returns 0 if
+/// `time_real` is absent (no real-time data available yet) or if either timestamp fails to parse.
+fn compute_delay_minutes(time_planned: &str, time_real: Option<&str>) -> i64 {
+    let time_real = match time_real {
+        Some(tr) => tr,
+        None => return 0,
+    };
+
+    let planned = match chrono::DateTime::parse_from_str(time_planned, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        Ok(dt) => dt,
+        Err(e) => {
+            error!("failed to parse planned departure time {:?}: {}", time_planned, e);
+            return 0;
+        },
+    };
+    let real = match chrono::DateTime::parse_from_str(time_real, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        Ok(dt) => dt,
+        Err(e) => {
+            error!("failed to parse real departure time {:?}: {}", time_real, e);
+            return 0;
+        },
+    };
+
+    (real - planned).num_seconds().div_euclid(60)
+}
+
+
+/// The original Wiener Linien backend: a semicolon-delimited CSV stop list and the `OGD`
+/// real-time monitor's JSON `MonitorWrapper` shape.
+pub(crate) struct WienerLinienProvider {
+    http_client: reqwest::Client,
+}
+impl WienerLinienProvider {
+    pub fn new(http_client: reqwest::Client) -> Self {
+        Self {
+            http_client,
+        }
+    }
+}
+#[async_trait]
+impl DepartureProvider for WienerLinienProvider {
+    async fn load_stations(&self, config: &Config) -> Option<Vec<(String, StoppingPoint)>> {
+        let request = self.http_client.get(&config.stop_points_url);
+        let response = match request.send().await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to send stations update request to {:?}: {}", config.stop_points_url, e);
+                return None;
+            },
+        };
+        let response_bytes = match response.bytes().await {
+            Ok(b) => b,
+            Err(e) => {
+                error!("failed to obtain bytes of stations update to {:?}: {}", config.stop_points_url, e);
+                return None;
+            },
+        };
+        let response_reader = response_bytes.reader();
+        let mut response_decoder = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .quote(b'"')
+            .has_headers(true)
+            .from_reader(response_reader);
+
+        let mut stations = Vec::new();
+        for record_res in response_decoder.deserialize() {
+            let station: StoppingPoint = match record_res {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("failed to obtain a station entry from {:?}: {}", config.stop_points_url, e);
+                    return None;
+                },
+            };
+            let station_name_lower = station.name.to_lowercase();
+            stations.push((station_name_lower, station));
+        }
+
+        Some(stations)
+    }
+
+    async fn fetch_departures(&self, config: &Config, station_id: u32, line_number: Option<&str>) -> Option<Vec<Vec<DepartureLine>>> {
+        let url = config.monitor_url_format
+            .replace("{stopId}", &station_id.to_string());
+
+        let request = self.http_client.get(&url);
+        let response = match request.send().await {
+            Ok(r) => r,
+            Err(e) => {
+                error!("failed to send monitor request to {:?}: {}", url, e);
+                return None;
+            },
+        };
+        let response_bytes = match response.bytes().await {
+            Ok(b) => b,
+            Err(e) => {
+                error!("failed to obtain bytes of monitor {:?}: {}", url, e);
+                return None;
+            },
+        };
+        let response_reader = response_bytes.reader();
+        let monitor_wrapper: MonitorWrapper = match serde_json::from_reader(response_reader) {
+            Ok(mw) => mw,
+            Err(e) => {
+                error!("failed to parse monitor {:?}: {}", url, e);
+                return None;
+            },
+        };
+
+        let mut platform_to_deps: std::collections::HashMap<Option<i64>, std::collections::BTreeMap<(String, String), DepartureLine>> = std::collections::HashMap::new();
+        for monitor in &monitor_wrapper.data.monitors {
+            let platform_number = monitor.location_stop.properties.attributes.rbl;
+            let dep_lines = platform_to_deps
+                .entry(platform_number)
+                .or_insert_with(|| std::collections::BTreeMap::new());
+
+            for line in &monitor.lines {
+                if let Some(ln) = line_number {
+                    if line.name != ln {
+                        continue;
+                    }
+                }
+
+                for departure in &line.departure_data.departures {
+                    let countdown = match departure.departure_time.countdown {
+                        Some(cd) => cd,
+                        None => continue,
+                    };
+                    let delay_minutes = compute_delay_minutes(
+                        &departure.departure_time.time_planned,
+                        departure.departure_time.time_real.as_deref(),
+                    );
+
+                    let (line_and_target, target_full, barrier_free, realtime, traffic_jam) = if let Some(vehicle) = &departure.vehicle {
+                        (
+                            (vehicle.name.clone(), vehicle.towards.to_lowercase()),
+                            vehicle.towards.clone(),
+                            vehicle.barrier_free,
+                            vehicle.realtime_supported,
+                            vehicle.traffic_jam,
+                        )
+                    } else {
+                        (
+                            (line.name.clone(), line.towards.to_lowercase()),
+                            line.towards.clone(),
+                            line.barrier_free,
+                            line.realtime_supported,
+                            line.traffic_jam,
+                        )
+                    };
+
+                    let dep_entry = dep_lines
+                        .entry(line_and_target.clone())
+                        .or_insert_with(|| DepartureLine::new(
+                            line_and_target.0,
+                            target_full,
+                            Vec::new(),
+                        ));
+                    dep_entry.departures.push(DepartureTimeEntry::new(
+                        countdown,
+                        barrier_free,
+                        realtime,
+                        traffic_jam,
+                        delay_minutes,
+                    ))
+                }
+            }
+        }
+
+        let mut ret_monitors: Vec<Vec<DepartureLine>> = platform_to_deps.into_values()
+            .map(|deps| deps.into_values().collect())
+            .collect();
+        ret_monitors.sort_unstable_by_key(|rm: &Vec<DepartureLine>| {
+            let rm_vec: Vec<(String, String)> = rm.iter()
+                .map(|dl| (dl.line_name.clone(), dl.target_station.to_lowercase()))
+                .collect();
+            rm_vec
+        });
+
+        Some(ret_monitors)
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
-use crate::placeholders::Placeholder;
+use crate::placeholders::{Placeholder, TargetCase};
 
 
 static CASING_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
@@ -15,6 +15,9 @@ static LOOKUP_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
 static SHORTEN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
     "^\\$shorten\\$(?P<key>.+)\\$(?P<len>0|[1-9][0-9]*)$"
 ).expect("failed to compile shorten regex"));
+static CONVERT_CASE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(
+    "^\\$convertcase\\$(?P<key>[^\\$]+)\\$(?P<target_case>.+)$"
+).expect("failed to compile convertcase regex"));
 
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -34,6 +37,7 @@ pub enum CompilationError {
     CaseUnknownCapturingGroup(String),
     UnknownCapturingGroup(String),
     ShortenTooLong,
+    ConvertCaseUnknownTargetCase(String),
 }
 impl fmt::Display for CompilationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -50,6 +54,8 @@ impl fmt::Display for CompilationError {
                 => write!(f, "unknown capturing group named {:?}", s),
             CompilationError::ShortenTooLong
                 => write!(f, "shortening length too long"),
+            CompilationError::ConvertCaseUnknownTargetCase(s)
+                => write!(f, "unknown target case {:?}", s),
         }
     }
 }
@@ -210,6 +216,28 @@ fn process_named_group(group_name: &str, regex: &Regex, placeholders: &mut Vec<P
         return Ok(());
     }
 
+    if let Some(convert_case_match) = CONVERT_CASE_REGEX.captures(group_name) {
+        let key = convert_case_match.name("key").unwrap().as_str();
+        let target_case_str = convert_case_match.name("target_case").unwrap().as_str();
+
+        let target_case = TargetCase::try_from_str(target_case_str)
+            .ok_or_else(|| CompilationError::ConvertCaseUnknownTargetCase(target_case_str.to_owned()))?;
+
+        let any_such_named_capture = regex
+            .capture_names()
+            .filter_map(|cn| cn)
+            .any(|cn| cn == key);
+        if !any_such_named_capture {
+            return Err(CompilationError::UnknownCapturingGroup(key.to_owned()));
+        }
+
+        placeholders.push(Placeholder::ConvertCase(
+            key.to_owned(),
+            target_case,
+        ));
+        return Ok(());
+    }
+
     let any_such_named_capture = regex
         .capture_names()
         .filter_map(|cn| cn)
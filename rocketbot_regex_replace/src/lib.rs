@@ -194,6 +194,21 @@ mod tests {
         test_replacement("kiwIFRUIT", "(?i)(?P<kiw>kiw)(?P<i>i)", "${kiw}${$case$i$ifruit}", "kiwI", None);
     }
 
+    #[test]
+    fn convert_case_replacement() {
+        test_replacement("http_server", "(?i)(?P<id>.+)", "${$convertcase$id$snake}", "HTTPServer", None);
+        test_replacement("HTTP_SERVER", "(?i)(?P<id>.+)", "${$convertcase$id$SCREAMING_SNAKE}", "HTTPServer", None);
+        test_replacement("http-server", "(?i)(?P<id>.+)", "${$convertcase$id$kebab}", "HTTPServer", None);
+        test_replacement("httpServer", "(?i)(?P<id>.+)", "${$convertcase$id$camel}", "http_server", None);
+        test_replacement("HttpServer", "(?i)(?P<id>.+)", "${$convertcase$id$Pascal}", "http-server", None);
+        test_replacement("Http Server", "(?i)(?P<id>.+)", "${$convertcase$id$Title}", "HTTP_SERVER", None);
+        test_replacement("httpserver", "(?i)(?P<id>.+)", "${$convertcase$id$lower}", "Http-Server", None);
+        test_replacement("HTTPSERVER", "(?i)(?P<id>.+)", "${$convertcase$id$upper}", "http server", None);
+
+        // leading/trailing delimiters must not yield empty words
+        test_replacement("foo_bar", "(?i)(?P<id>.+)", "${$convertcase$id$snake}", "__foo-bar__", None);
+    }
+
     #[test]
     fn lookup_replacement() {
         let mut lookups = HashMap::new();
@@ -27,6 +27,35 @@ impl<'a> ReplacementState<'a> {
 }
 
 
+/// The case a group's words are recombined into by `Placeholder::ConvertCase`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum TargetCase {
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    Camel,
+    Pascal,
+    Title,
+    Lower,
+    Upper,
+}
+impl TargetCase {
+    pub fn try_from_str(s: &str) -> Option<Self> {
+        match s {
+            "snake" => Some(Self::Snake),
+            "SCREAMING_SNAKE" => Some(Self::ScreamingSnake),
+            "kebab" => Some(Self::Kebab),
+            "camel" => Some(Self::Camel),
+            "Pascal" => Some(Self::Pascal),
+            "Title" => Some(Self::Title),
+            "lower" => Some(Self::Lower),
+            "upper" => Some(Self::Upper),
+            _ => None,
+        }
+    }
+}
+
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub(crate) enum Placeholder {
     ConstantString(String),
@@ -41,6 +70,7 @@ pub(crate) enum Placeholder {
     CasingNamedMatchGroup(String, String),
     CasingNumberedMatchGroup(String, usize),
     Shorten(String, usize),
+    ConvertCase(String, TargetCase),
 }
 impl Placeholder {
     pub fn replace(&self, state: &ReplacementState) -> String {
@@ -69,6 +99,8 @@ impl Placeholder {
                 => case_string_numbered(string_to_case, *case_template_group, &state),
             Placeholder::Shorten(group_name, length)
                 => shorten(group_name, *length, &state),
+            Placeholder::ConvertCase(group_name, target_case)
+                => convert_case(group_name, *target_case, &state),
         }
     }
 }
@@ -180,3 +212,87 @@ fn shorten(group_name: &str, length: usize, state: &ReplacementState) -> String
     let match_str = state.regex_match.name(group_name).unwrap().as_str();
     match_str.chars().take(length).collect()
 }
+
+fn convert_case(group_name: &str, target_case: TargetCase, state: &ReplacementState) -> String {
+    let match_str = state.regex_match.name(group_name).unwrap().as_str();
+    let words = segment_words(match_str);
+    recombine_words(&words, target_case)
+}
+
+/// Splits `s` into lowercased words, emitting a boundary on a `_`/`-`/space delimiter (consumed,
+/// not kept), between a lowercase letter or digit and a following uppercase letter, before the
+/// last letter of an uppercase run that is followed by a lowercase letter (so `HTTPServer` becomes
+/// `HTTP` + `Server`), and between a letter and a digit.
+fn segment_words(s: &str) -> Vec<String> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut words = Vec::new();
+    let mut current = String::new();
+
+    for i in 0..chars.len() {
+        let c = chars[i];
+        if c == '_' || c == '-' || c == ' ' {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+
+        if !current.is_empty() {
+            let prev = chars[i - 1];
+            let boundary = if (prev.is_lowercase() || prev.is_ascii_digit()) && c.is_uppercase() {
+                // aA, 0A
+                true
+            } else if prev.is_uppercase() && c.is_uppercase() {
+                // an uppercase run followed by uppercase-then-lowercase, e.g. HTTPServer
+                i + 1 < chars.len() && chars[i + 1].is_lowercase()
+            } else if prev.is_ascii_alphanumeric() && c.is_ascii_alphanumeric() && prev.is_alphabetic() != c.is_alphabetic() {
+                // letter/digit transition
+                true
+            } else {
+                false
+            };
+
+            if boundary {
+                words.push(std::mem::take(&mut current));
+            }
+        }
+
+        for lc in c.to_lowercase() {
+            current.push(lc);
+        }
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+/// Uppercases the first character of `word` and lowercases the rest.
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => {
+            let mut capitalized: String = first.to_uppercase().collect();
+            capitalized.push_str(&chars.as_str().to_lowercase());
+            capitalized
+        },
+        None => String::new(),
+    }
+}
+
+fn recombine_words(words: &[String], target_case: TargetCase) -> String {
+    match target_case {
+        TargetCase::Snake => words.join("_"),
+        TargetCase::ScreamingSnake => words.iter().map(|w| w.to_uppercase()).collect::<Vec<_>>().join("_"),
+        TargetCase::Kebab => words.join("-"),
+        TargetCase::Title => words.iter().map(|w| capitalize(w)).collect::<Vec<_>>().join(" "),
+        TargetCase::Camel => words.iter().enumerate()
+            .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+            .collect(),
+        TargetCase::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+        TargetCase::Lower => words.concat(),
+        TargetCase::Upper => words.iter().map(|w| w.to_uppercase()).collect(),
+    }
+}
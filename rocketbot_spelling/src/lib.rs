@@ -1,4 +1,5 @@
 pub mod hunspell;
+pub mod symspell;
 
 
 use std::path::PathBuf;
@@ -7,6 +8,7 @@ use log::error;
 use serde::{Deserialize, Serialize};
 
 use crate::hunspell::HunspellDictionary;
+use crate::symspell::SymSpellDictionary;
 
 
 pub trait SpellingEngine : Sized {
@@ -126,3 +128,83 @@ impl SpellingEngine for HunspellEngine {
         all_suggestions
     }
 }
+
+
+fn default_max_edit_distance() -> usize { 2 }
+fn default_prefix_length() -> usize { 4 }
+fn default_max_suggestions() -> usize { 10 }
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+struct SymSpellEngineConfig {
+    dictionaries: Vec<SymSpellDictConfig>,
+    #[serde(default = "default_max_edit_distance")] max_edit_distance: usize,
+    #[serde(default = "default_prefix_length")] prefix_length: usize,
+    #[serde(default = "default_max_suggestions")] max_suggestions: usize,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+struct SymSpellDictConfig {
+    path: String,
+}
+
+
+/// A pure-Rust [`SpellingEngine`] backed by plain word-frequency dictionaries and the Symmetric
+/// Delete algorithm (see [`crate::symspell`]). Unlike [`HunspellEngine`], it requires no external
+/// `.aff`/`.dic` files or FFI calls, and its suggestion latency does not grow with dictionary size.
+pub struct SymSpellEngine {
+    dictionaries: Vec<SymSpellDictionary>,
+    max_suggestions: usize,
+}
+impl SpellingEngine for SymSpellEngine {
+    fn new(config: serde_json::Value) -> Option<Self> {
+        let config_object: SymSpellEngineConfig = match serde_json::from_value(config) {
+            Ok(co) => co,
+            Err(e) => {
+                error!("failed to parse config: {}", e);
+                return None;
+            },
+        };
+
+        let mut dictionaries = Vec::new();
+        for dict_config in &config_object.dictionaries {
+            let path = PathBuf::from(&dict_config.path);
+            let dictionary = match SymSpellDictionary::load(
+                &path,
+                config_object.max_edit_distance,
+                config_object.prefix_length,
+            ) {
+                Ok(d) => d,
+                Err(e) => {
+                    error!("failed to load SymSpell dictionary {:?}: {}", dict_config.path, e);
+                    return None;
+                },
+            };
+            dictionaries.push(dictionary);
+        }
+
+        Some(Self {
+            dictionaries,
+            max_suggestions: config_object.max_suggestions,
+        })
+    }
+
+    fn is_correct(&self, word: &str) -> bool {
+        for dict in &self.dictionaries {
+            if dict.is_correct(word) {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn suggest(&self, word: &str) -> Vec<String> {
+        let mut all_suggestions = Vec::new();
+
+        for dict in &self.dictionaries {
+            all_suggestions.append(&mut dict.suggest(word, self.max_suggestions));
+        }
+
+        all_suggestions
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use strsim::damerau_levenshtein;
+
+
+#[derive(Debug)]
+pub enum SymSpellError {
+    Io(io::Error),
+    MalformedLine(usize),
+}
+impl fmt::Display for SymSpellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e)
+                => write!(f, "failed to read SymSpell dictionary: {}", e),
+            Self::MalformedLine(line_no)
+                => write!(f, "malformed SymSpell dictionary line {} (expected \"word count\")", line_no),
+        }
+    }
+}
+impl std::error::Error for SymSpellError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::MalformedLine(_) => None,
+        }
+    }
+}
+
+
+/// A word loaded from a frequency dictionary, paired with its occurrence count, used to rank
+/// suggestions that are equally close to the query.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct WordFrequency {
+    word: String,
+    count: u64,
+}
+
+
+/// A word-frequency list indexed for fast fuzzy suggestion using the Symmetric Delete spelling
+/// correction algorithm: rather than comparing a query against every dictionary word, every
+/// dictionary word is pre-expanded (at load time) into every string obtainable by deleting up to
+/// `max_edit_distance` of its characters, each pointing back at the word(s) it came from. A query
+/// is then expanded the same way and its deletion-variants are looked up directly; only the
+/// (typically small) set of dictionary words sharing a deletion-variant with the query need to be
+/// verified with a true Damerau-Levenshtein comparison.
+pub struct SymSpellDictionary {
+    max_edit_distance: usize,
+    words: Vec<WordFrequency>,
+
+    /// Exact word (case-folded) to its index into `words`.
+    word_index: HashMap<String, usize>,
+
+    /// Deletion-variant (case-folded) to the indices into `words` of the dictionary word(s) it was
+    /// generated from.
+    deletes: HashMap<String, Vec<usize>>,
+}
+impl SymSpellDictionary {
+    /// Loads a plain-text dictionary of `word count` lines (one per line, whitespace-separated)
+    /// and builds the Symmetric Delete index. Words shorter than `prefix_length` are still looked
+    /// up directly but are excluded from deletion-variant generation, guarding memory use on
+    /// dictionaries containing many short words.
+    pub fn load(path: &Path, max_edit_distance: usize, prefix_length: usize) -> Result<Self, SymSpellError> {
+        let contents = fs::read_to_string(path)
+            .map_err(SymSpellError::Io)?;
+
+        let mut words = Vec::new();
+        for (line_no, line) in contents.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.len() == 0 {
+                continue;
+            }
+
+            let (word_part, count_part) = trimmed.rsplit_once(char::is_whitespace)
+                .ok_or_else(|| SymSpellError::MalformedLine(line_no + 1))?;
+            let count: u64 = count_part.trim().parse()
+                .map_err(|_| SymSpellError::MalformedLine(line_no + 1))?;
+
+            words.push(WordFrequency {
+                word: word_part.trim().to_lowercase(),
+                count,
+            });
+        }
+
+        let mut word_index: HashMap<String, usize> = HashMap::new();
+        let mut deletes: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, wf) in words.iter().enumerate() {
+            word_index.entry(wf.word.clone()).or_insert(index);
+
+            if wf.word.chars().count() < prefix_length {
+                continue;
+            }
+
+            for variant in deletion_variants(&wf.word, max_edit_distance) {
+                deletes.entry(variant)
+                    .or_insert_with(Vec::new)
+                    .push(index);
+            }
+        }
+
+        Ok(Self {
+            max_edit_distance,
+            words,
+            word_index,
+            deletes,
+        })
+    }
+
+    /// Checks whether `word` is an exact (case-folded) match for a dictionary entry, via a direct
+    /// hash lookup.
+    pub fn is_correct(&self, word: &str) -> bool {
+        self.word_index.contains_key(&word.to_lowercase())
+    }
+
+    /// Suggests up to `limit` dictionary words for `word`, sorted by ascending Damerau-Levenshtein
+    /// distance, then by descending frequency.
+    pub fn suggest(&self, word: &str, limit: usize) -> Vec<String> {
+        let lower = word.to_lowercase();
+
+        let mut candidate_indices: HashSet<usize> = HashSet::new();
+        if let Some(&index) = self.word_index.get(&lower) {
+            candidate_indices.insert(index);
+        }
+        if let Some(indices) = self.deletes.get(&lower) {
+            candidate_indices.extend(indices.iter().copied());
+        }
+        for variant in deletion_variants(&lower, self.max_edit_distance) {
+            if let Some(&index) = self.word_index.get(&variant) {
+                candidate_indices.insert(index);
+            }
+            if let Some(indices) = self.deletes.get(&variant) {
+                candidate_indices.extend(indices.iter().copied());
+            }
+        }
+
+        let mut scored: Vec<(usize, u64, &str)> = Vec::new();
+        for index in candidate_indices {
+            let candidate = &self.words[index];
+            let distance = damerau_levenshtein(&lower, &candidate.word);
+            if distance <= self.max_edit_distance {
+                scored.push((distance, candidate.count, candidate.word.as_str()));
+            }
+        }
+        scored.sort_unstable_by(|(dist_a, count_a, word_a), (dist_b, count_b, word_b)| {
+            dist_a.cmp(dist_b)
+                .then_with(|| count_b.cmp(count_a))
+                .then_with(|| word_a.cmp(word_b))
+        });
+        scored.truncate(limit);
+
+        scored.into_iter()
+            .map(|(_dist, _count, word)| word.to_owned())
+            .collect()
+    }
+}
+
+
+/// Generates every distinct string obtainable by deleting between one and `max_edit_distance`
+/// characters from `word` (in any combination), used both to index dictionary words and to expand
+/// a query into lookup keys for [`SymSpellDictionary::suggest`].
+fn deletion_variants(word: &str, max_edit_distance: usize) -> HashSet<String> {
+    let mut all = HashSet::new();
+    let mut frontier: HashSet<String> = HashSet::new();
+    frontier.insert(word.to_owned());
+
+    for _ in 0..max_edit_distance {
+        let mut next_frontier = HashSet::new();
+        for candidate in &frontier {
+            let chars: Vec<char> = candidate.chars().collect();
+            for skip_index in 0..chars.len() {
+                let variant: String = chars.iter().enumerate()
+                    .filter(|(i, _c)| *i != skip_index)
+                    .map(|(_i, c)| *c)
+                    .collect();
+                if all.insert(variant.clone()) {
+                    next_frontier.insert(variant);
+                }
+            }
+        }
+        if next_frontier.len() == 0 {
+            break;
+        }
+        frontier = next_frontier;
+    }
+
+    all
+}
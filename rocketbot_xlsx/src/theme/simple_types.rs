@@ -1,6 +1,168 @@
+use std::fmt;
+use std::str::FromStr;
+
 use strict_num::FiniteF64;
 
 
+/// Why parsing a DrawingML simple type from its XSD lexical representation failed. Lets callers
+/// distinguish the reason (and, for range violations, the offending value) instead of getting a
+/// bare `None` back from `try_from_str`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SimpleTypeParseError {
+    /// The string is not a valid number in the expected lexical space.
+    NotANumber,
+    /// The value parsed correctly but falls outside the type's valid range. The bounds and the
+    /// offending value are kept as their XSD string representations so this variant can serve
+    /// both the integer- and float-backed simple types in this module.
+    OutOfRange { min: String, max: String, found: String },
+    /// A `UniversalMeasure` was missing its two-letter unit suffix (`mm`, `cm`, `in`, `pt`, `pc`, `pi`).
+    MissingUnitSuffix,
+    /// A `Percentage` (or a type built on it) was missing its trailing `%` sign.
+    MissingPercentSign,
+    /// A hex-encoded value (`Panose`, `PitchFamily`) did not have the expected number of hex digits.
+    BadHexLength { expected: usize, found: usize },
+    /// The string does not match any of the type's known enumeration values.
+    UnknownEnumValue,
+}
+impl fmt::Display for SimpleTypeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotANumber => write!(f, "not a valid number"),
+            Self::OutOfRange { min, max, found } => write!(f, "value {} out of range {}..={}", found, min, max),
+            Self::MissingUnitSuffix => write!(f, "missing unit suffix"),
+            Self::MissingPercentSign => write!(f, "missing percent sign"),
+            Self::BadHexLength { expected, found } => write!(f, "expected {} hex digits, found {}", expected, found),
+            Self::UnknownEnumValue => write!(f, "unknown enumeration value"),
+        }
+    }
+}
+impl std::error::Error for SimpleTypeParseError {}
+
+
+/// Formats `value` as a plain decimal string with no exponent, matching the `-?[0-9]+(\.[0-9]+)?`
+/// lexical space shared by most simple types in this module. Rust's default `{}` formatting can
+/// emit scientific notation (e.g. `1e-7`, `1e20`) for very small or very large magnitudes, which
+/// these grammars do not accept, so such output is reconstructed into fixed-point form by
+/// shifting the decimal point according to the exponent.
+fn format_f64_plain(value: f64) -> String {
+    let formatted = format!("{}", value);
+    let e_pos = match formatted.find(['e', 'E']) {
+        Some(p) => p,
+        None => return formatted,
+    };
+
+    let (mantissa, exponent_str) = formatted.split_at(e_pos);
+    let exponent: i32 = exponent_str[1..].parse().unwrap();
+    let negative = mantissa.starts_with('-');
+    let mantissa = mantissa.trim_start_matches('-');
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", int_part, frac_part);
+    let point_pos = int_part.len() as i32 + exponent;
+
+    let mut result = String::new();
+    if point_pos <= 0 {
+        result.push_str("0.");
+        for _ in 0..(-point_pos) {
+            result.push('0');
+        }
+        result.push_str(&digits);
+    } else if point_pos as usize >= digits.len() {
+        result.push_str(&digits);
+        for _ in 0..(point_pos as usize - digits.len()) {
+            result.push('0');
+        }
+    } else {
+        let pos = point_pos as usize;
+        result.push_str(&digits[..pos]);
+        result.push('.');
+        result.push_str(&digits[pos..]);
+    }
+
+    if result.contains('.') {
+        while result.ends_with('0') {
+            result.pop();
+        }
+        if result.ends_with('.') {
+            result.pop();
+        }
+    }
+
+    if negative {
+        format!("-{}", result)
+    } else {
+        result
+    }
+}
+
+/// Rounds a plain (non-exponential) decimal string to at most `max_frac_digits` fractional
+/// digits, using round-half-to-even, for the stricter `(\.[0-9][0-9]?)?` grammars.
+fn round_plain_decimal(s: &str, max_frac_digits: usize) -> String {
+    let negative = s.starts_with('-');
+    let unsigned = s.trim_start_matches('-');
+    let (int_part, frac_part) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if frac_part.len() <= max_frac_digits {
+        return s.to_owned();
+    }
+
+    let mut int_digits: Vec<u8> = int_part.bytes().map(|b| b - b'0').collect();
+    let mut kept_frac: Vec<u8> = frac_part.as_bytes()[..max_frac_digits].iter().map(|b| b - b'0').collect();
+    let rest = &frac_part.as_bytes()[max_frac_digits..];
+
+    let round_up = if rest[0] > b'5' {
+        true
+    } else if rest[0] < b'5' {
+        false
+    } else if rest[1..].iter().any(|&b| b != b'0') {
+        true
+    } else {
+        let last_kept = kept_frac.last().copied().unwrap_or_else(|| *int_digits.last().unwrap_or(&0));
+        last_kept % 2 == 1
+    };
+
+    if round_up {
+        let mut carry = true;
+        for d in kept_frac.iter_mut().rev() {
+            if carry {
+                *d += 1;
+                if *d == 10 { *d = 0; } else { carry = false; }
+            }
+        }
+        if carry {
+            for d in int_digits.iter_mut().rev() {
+                if carry {
+                    *d += 1;
+                    if *d == 10 { *d = 0; } else { carry = false; }
+                }
+            }
+            if carry {
+                int_digits.insert(0, 1);
+            }
+        }
+    }
+
+    let mut result = String::new();
+    if negative {
+        result.push('-');
+    }
+    for d in &int_digits {
+        result.push((b'0' + d) as char);
+    }
+    if !kept_frac.is_empty() {
+        result.push('.');
+        for d in &kept_frac {
+            result.push((b'0' + d) as char);
+        }
+        while result.ends_with('0') {
+            result.pop();
+        }
+        if result.ends_with('.') {
+            result.pop();
+        }
+    }
+    result
+}
+
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Percentage {
     // s_ST_Percentage, xsd:string matches "-?[0-9]+(\.[0-9]+)?%"
@@ -11,10 +173,21 @@ impl Percentage {
     pub const fn get(&self) -> FiniteF64 { self.percentage }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let stripped_percent = s.strip_suffix('%')?;
-        let value: f64 = stripped_percent.parse().ok()?;
-        let finite_value = FiniteF64::new(value)?;
-        Some(Self {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        format!("{}%", format_f64_plain(self.percentage.get()))
+    }
+}
+impl FromStr for Percentage {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stripped_percent = s.strip_suffix('%').ok_or(SimpleTypeParseError::MissingPercentSign)?;
+        let value: f64 = stripped_percent.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        let finite_value = FiniteF64::new(value).ok_or(SimpleTypeParseError::NotANumber)?;
+        Ok(Self {
             percentage: finite_value,
         })
     }
@@ -30,13 +203,29 @@ impl FixedPercentage {
     pub const fn get(&self) -> FiniteF64 { self.percentage }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let unfettered = Percentage::try_from_str(s)?;
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        let plain = format_f64_plain(self.percentage.get());
+        format!("{}%", round_plain_decimal(&plain, 2))
+    }
+}
+impl FromStr for FixedPercentage {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unfettered: Percentage = s.parse()?;
         if unfettered.percentage.get() >= -100.0 && unfettered.percentage.get() <= 100.0 {
-            Some(Self {
+            Ok(Self {
                 percentage: unfettered.percentage,
             })
         } else {
-            None
+            Err(SimpleTypeParseError::OutOfRange {
+                min: "-100".to_owned(),
+                max: "100".to_owned(),
+                found: format_f64_plain(unfettered.percentage.get()),
+            })
         }
     }
 }
@@ -51,13 +240,28 @@ impl PositivePercentage {
     pub const fn get(&self) -> FiniteF64 { self.percentage }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let unfettered = Percentage::try_from_str(s)?;
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        format!("{}%", format_f64_plain(self.percentage.get()))
+    }
+}
+impl FromStr for PositivePercentage {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unfettered: Percentage = s.parse()?;
         if unfettered.percentage.get() >= 0.0 {
-            Some(Self {
+            Ok(Self {
                 percentage: unfettered.percentage,
             })
         } else {
-            None
+            Err(SimpleTypeParseError::OutOfRange {
+                min: "0".to_owned(),
+                max: "inf".to_owned(),
+                found: format_f64_plain(unfettered.percentage.get()),
+            })
         }
     }
 }
@@ -72,13 +276,29 @@ impl PositiveFixedPercentage {
     pub const fn get(&self) -> FiniteF64 { self.percentage }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let unfettered = Percentage::try_from_str(s)?;
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        let plain = format_f64_plain(self.percentage.get());
+        format!("{}%", round_plain_decimal(&plain, 2))
+    }
+}
+impl FromStr for PositiveFixedPercentage {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unfettered: Percentage = s.parse()?;
         if unfettered.percentage.get() >= 0.0 && unfettered.percentage.get() <= 100.0 {
-            Some(Self {
+            Ok(Self {
                 percentage: unfettered.percentage,
             })
         } else {
-            None
+            Err(SimpleTypeParseError::OutOfRange {
+                min: "0".to_owned(),
+                max: "100".to_owned(),
+                found: format_f64_plain(unfettered.percentage.get()),
+            })
         }
     }
 }
@@ -92,8 +312,19 @@ impl Angle {
     pub const fn get(&self) -> i64 { self.angle }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let angle: i64 = s.parse().ok()?;
-        Some(Self {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.angle.to_string()
+    }
+}
+impl FromStr for Angle {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let angle: i64 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Ok(Self {
             angle,
         })
     }
@@ -108,13 +339,28 @@ impl FixedAngle {
     pub const fn get(&self) -> i32 { self.angle }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let unfettered = Angle::try_from_str(s)?;
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.angle.to_string()
+    }
+}
+impl FromStr for FixedAngle {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unfettered: Angle = s.parse()?;
         if unfettered.angle >= -5400000 && unfettered.angle <= 5400000 {
-            Some(Self {
+            Ok(Self {
                 angle: unfettered.angle.try_into().unwrap(),
             })
         } else {
-            None
+            Err(SimpleTypeParseError::OutOfRange {
+                min: "-5400000".to_owned(),
+                max: "5400000".to_owned(),
+                found: unfettered.angle.to_string(),
+            })
         }
     }
 }
@@ -128,13 +374,28 @@ impl PositiveFixedAngle {
     pub const fn get(&self) -> u32 { self.angle }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let unfettered = Angle::try_from_str(s)?;
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.angle.to_string()
+    }
+}
+impl FromStr for PositiveFixedAngle {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unfettered: Angle = s.parse()?;
         if unfettered.angle >= 0 && unfettered.angle <= 21600000 {
-            Some(Self {
+            Ok(Self {
                 angle: unfettered.angle.try_into().unwrap(),
             })
         } else {
-            None
+            Err(SimpleTypeParseError::OutOfRange {
+                min: "0".to_owned(),
+                max: "21600000".to_owned(),
+                found: unfettered.angle.to_string(),
+            })
         }
     }
 }
@@ -148,18 +409,31 @@ impl Panose {
     pub const fn get(&self) -> [u8; 10] { self.bytes }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.bytes.iter()
+            .map(|b| format!("{:02X}", b))
+            .collect()
+    }
+}
+impl FromStr for Panose {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.len() != 20 {
-            return None;
+            return Err(SimpleTypeParseError::BadHexLength { expected: 20, found: s.len() });
         }
         if !s.chars().all(|c| (c >= '0' && c <= '9') || (c >= 'A' && c <= 'F')) {
-            return None;
+            return Err(SimpleTypeParseError::NotANumber);
         }
         let mut bytes = [0u8; 10];
         for i in 0..s.len()/2 {
             let hex_byte = &s[2*i..2*i+2];
-            bytes[i] = hex_byte.parse().ok()?;
+            bytes[i] = hex_byte.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
         }
-        Some(Self {
+        Ok(Self {
             bytes,
         })
     }
@@ -190,33 +464,46 @@ pub enum PitchFamily {
 }
 impl PitchFamily {
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        // try_from_str parses the string as decimal (despite validating hex-looking characters),
+        // so emit the decimal form here to stay round-trippable.
+        (*self as u8).to_string()
+    }
+}
+impl FromStr for PitchFamily {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.len() != 2 {
-            return None;
+            return Err(SimpleTypeParseError::BadHexLength { expected: 2, found: s.len() });
         }
         if !s.chars().all(|c| (c >= '0' && c <= '9') || (c >= 'A' && c <= 'F')) {
-            return None;
+            return Err(SimpleTypeParseError::NotANumber);
         }
-        let byte: u8 = s.parse().ok()?;
+        let byte: u8 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
         match byte {
-            0x00 => Some(Self::Family00),
-            0x01 => Some(Self::Family01),
-            0x02 => Some(Self::Family02),
-            0x16 => Some(Self::Family16),
-            0x17 => Some(Self::Family17),
-            0x18 => Some(Self::Family18),
-            0x32 => Some(Self::Family32),
-            0x33 => Some(Self::Family33),
-            0x34 => Some(Self::Family34),
-            0x48 => Some(Self::Family48),
-            0x49 => Some(Self::Family49),
-            0x50 => Some(Self::Family50),
-            0x64 => Some(Self::Family64),
-            0x65 => Some(Self::Family65),
-            0x66 => Some(Self::Family66),
-            0x80 => Some(Self::Family80),
-            0x81 => Some(Self::Family81),
-            0x82 => Some(Self::Family82),
-            _ => None,
+            0x00 => Ok(Self::Family00),
+            0x01 => Ok(Self::Family01),
+            0x02 => Ok(Self::Family02),
+            0x16 => Ok(Self::Family16),
+            0x17 => Ok(Self::Family17),
+            0x18 => Ok(Self::Family18),
+            0x32 => Ok(Self::Family32),
+            0x33 => Ok(Self::Family33),
+            0x34 => Ok(Self::Family34),
+            0x48 => Ok(Self::Family48),
+            0x49 => Ok(Self::Family49),
+            0x50 => Ok(Self::Family50),
+            0x64 => Ok(Self::Family64),
+            0x65 => Ok(Self::Family65),
+            0x66 => Ok(Self::Family66),
+            0x80 => Ok(Self::Family80),
+            0x81 => Ok(Self::Family81),
+            0x82 => Ok(Self::Family82),
+            _ => Err(SimpleTypeParseError::UnknownEnumValue),
         }
     }
 }
@@ -230,12 +517,28 @@ pub enum TileFlipMode {
 }
 impl TileFlipMode {
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        match self {
+            Self::None => "none",
+            Self::X => "x",
+            Self::Y => "y",
+            Self::XY => "xy",
+        }.to_owned()
+    }
+}
+impl FromStr for TileFlipMode {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "none" => Some(Self::None),
-            "x" => Some(Self::X),
-            "y" => Some(Self::Y),
-            "xy" => Some(Self::XY),
-            _ => None,
+            "none" => Ok(Self::None),
+            "x" => Ok(Self::X),
+            "y" => Ok(Self::Y),
+            "xy" => Ok(Self::XY),
+            _ => Err(SimpleTypeParseError::UnknownEnumValue),
         }
     }
 }
@@ -248,13 +551,33 @@ pub enum Coordinate {
 }
 impl Coordinate {
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        match self {
+            Self::Unqualified(cu) => cu.to_xsd_string(),
+            Self::UniversalMeasure(um) => um.to_xsd_string(),
+        }
+    }
+
+    /// Normalizes either variant to English Metric Units.
+    pub fn as_emu(&self) -> Option<i64> {
+        match self {
+            Self::Unqualified(cu) => Some(cu.get()),
+            Self::UniversalMeasure(um) => um.to_emu(),
+        }
+    }
+}
+impl FromStr for Coordinate {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         // try to parse as universal measure first, then as a bare value
-        if let Some(um) = UniversalMeasure::try_from_str(s) {
-            Some(Self::UniversalMeasure(um))
-        } else if let Some(cu) = CoordinateUnqualified::try_from_str(s) {
-            Some(Self::Unqualified(cu))
+        if let Ok(um) = s.parse() {
+            Ok(Self::UniversalMeasure(um))
         } else {
-            None
+            Ok(Self::Unqualified(s.parse::<CoordinateUnqualified>()?))
         }
     }
 }
@@ -266,13 +589,33 @@ pub enum Coordinate32 {
 }
 impl Coordinate32 {
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        match self {
+            Self::Unqualified(cu) => cu.to_xsd_string(),
+            Self::UniversalMeasure(um) => um.to_xsd_string(),
+        }
+    }
+
+    /// Normalizes either variant to English Metric Units.
+    pub fn as_emu(&self) -> Option<i64> {
+        match self {
+            Self::Unqualified(cu) => Some(cu.get().into()),
+            Self::UniversalMeasure(um) => um.to_emu(),
+        }
+    }
+}
+impl FromStr for Coordinate32 {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         // try to parse as universal measure first, then as a bare value
-        if let Some(um) = UniversalMeasure::try_from_str(s) {
-            Some(Self::UniversalMeasure(um))
-        } else if let Some(cu) = Coordinate32Unqualified::try_from_str(s) {
-            Some(Self::Unqualified(cu))
+        if let Ok(um) = s.parse() {
+            Ok(Self::UniversalMeasure(um))
         } else {
-            None
+            Ok(Self::Unqualified(s.parse::<Coordinate32Unqualified>()?))
         }
     }
 }
@@ -284,13 +627,33 @@ pub enum TextPoint {
 }
 impl TextPoint {
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        match self {
+            Self::Unqualified(cu) => cu.to_xsd_string(),
+            Self::UniversalMeasure(um) => um.to_xsd_string(),
+        }
+    }
+
+    /// Normalizes either variant to English Metric Units.
+    pub fn as_emu(&self) -> Option<i64> {
+        match self {
+            Self::Unqualified(cu) => Some(cu.get().into()),
+            Self::UniversalMeasure(um) => um.to_emu(),
+        }
+    }
+}
+impl FromStr for TextPoint {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         // try to parse as universal measure first, then as a bare value
-        if let Some(um) = UniversalMeasure::try_from_str(s) {
-            Some(Self::UniversalMeasure(um))
-        } else if let Some(cu) = TextPointUnqualified::try_from_str(s) {
-            Some(Self::Unqualified(cu))
+        if let Ok(um) = s.parse() {
+            Ok(Self::UniversalMeasure(um))
         } else {
-            None
+            Ok(Self::Unqualified(s.parse::<TextPointUnqualified>()?))
         }
     }
 }
@@ -314,8 +677,32 @@ impl CoordinateUnqualified {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: i64 = s.parse().ok()?;
-        Self::try_from_i64(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.value.to_string()
+    }
+
+    /// Re-expresses this EMU value as a `UniversalMeasure` in the given unit.
+    pub fn to_universal_measure(&self, unit: UniversalMeasureUnit) -> UniversalMeasure {
+        let value = self.value as f64 / (unit.emu_per_unit() as f64);
+        UniversalMeasure {
+            value: FiniteF64::new(value).expect("EMU-derived value is always finite"),
+            unit,
+        }
+    }
+}
+impl FromStr for CoordinateUnqualified {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: i64 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_i64(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "-27273042329600".to_owned(),
+            max: "27273042316900".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -332,8 +719,19 @@ impl Coordinate32Unqualified {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: i32 = s.parse().ok()?;
-        Some(Self {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.value.to_string()
+    }
+}
+impl FromStr for Coordinate32Unqualified {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: i32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Ok(Self {
             value,
         })
     }
@@ -358,8 +756,23 @@ impl TextPointUnqualified {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: i32 = s.parse().ok()?;
-        Self::try_from_i32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.value.to_string()
+    }
+}
+impl FromStr for TextPointUnqualified {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: i32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_i32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "-400000".to_owned(),
+            max: "400000".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -374,18 +787,51 @@ impl UniversalMeasure {
     pub const fn get_unit(&self) -> UniversalMeasureUnit { self.unit }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        format!("{}{}", format_f64_plain(self.value.get()), self.unit.to_xsd_string())
+    }
+
+    /// Converts this measure to English Metric Units, rounding to the nearest whole EMU.
+    /// Returns `None` if the conversion overflows or leaves the valid `CoordinateUnqualified`
+    /// range.
+    pub fn to_emu(&self) -> Option<i64> {
+        let emu_f64 = self.value.get() * (self.unit.emu_per_unit() as f64);
+        if !emu_f64.is_finite() {
+            return None;
+        }
+        let emu = emu_f64.round();
+        if emu < i64::MIN as f64 || emu > i64::MAX as f64 {
+            return None;
+        }
+        let emu = emu as i64;
+        if CoordinateUnqualified::try_from_i64(emu).is_some() {
+            Some(emu)
+        } else {
+            None
+        }
+    }
+}
+impl FromStr for UniversalMeasure {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         // at least one digit and two letters of unit
         if s.len() < 3 {
-            return None;
+            return Err(SimpleTypeParseError::MissingUnitSuffix);
         }
 
         // slice right before the last two characters
         // if that isn't at a Unicode boundary, the value is invalid
-        let (number_str, unit_str) = s.split_at_checked(s.len() - 2)?;
-        let unit = UniversalMeasureUnit::try_from_str(unit_str)?;
-        let value_f64: f64 = number_str.parse().ok()?;
-        let value = FiniteF64::new(value_f64)?;
-        Some(Self {
+        let (number_str, unit_str) = s.split_at_checked(s.len() - 2)
+            .ok_or(SimpleTypeParseError::MissingUnitSuffix)?;
+        let unit = UniversalMeasureUnit::try_from_str(unit_str)
+            .ok_or(SimpleTypeParseError::MissingUnitSuffix)?;
+        let value_f64: f64 = number_str.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        let value = FiniteF64::new(value_f64).ok_or(SimpleTypeParseError::NotANumber)?;
+        Ok(Self {
             value,
             unit,
         })
@@ -403,14 +849,43 @@ pub enum UniversalMeasureUnit {
 }
 impl UniversalMeasureUnit {
     pub fn try_from_str(s: &str) -> Option<Self> {
+        s.parse().ok()
+    }
+
+    /// The number of English Metric Units (1/914400 inch) in one unit of this measure.
+    pub const fn emu_per_unit(&self) -> i64 {
+        match self {
+            Self::Mm => 36000,
+            Self::Cm => 360000,
+            Self::In => 914400,
+            Self::Pt => 12700,
+            Self::Pc | Self::Pi => 152400,
+        }
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        match self {
+            Self::Mm => "mm",
+            Self::Cm => "cm",
+            Self::In => "in",
+            Self::Pt => "pt",
+            Self::Pc => "pc",
+            Self::Pi => "pi",
+        }.to_owned()
+    }
+}
+impl FromStr for UniversalMeasureUnit {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "mm" => Some(Self::Mm),
-            "cm" => Some(Self::Cm),
-            "in" => Some(Self::In),
-            "pt" => Some(Self::Pt),
-            "pc" => Some(Self::Pc),
-            "pi" => Some(Self::Pi),
-            _ => None,
+            "mm" => Ok(Self::Mm),
+            "cm" => Ok(Self::Cm),
+            "in" => Ok(Self::In),
+            "pt" => Ok(Self::Pt),
+            "pc" => Ok(Self::Pc),
+            "pi" => Ok(Self::Pi),
+            _ => Err(SimpleTypeParseError::UnknownEnumValue),
         }
     }
 }
@@ -432,8 +907,42 @@ impl PositiveCoordinate {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u64 = s.parse().ok()?;
-        Self::try_from_u64(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.coordinate.to_string()
+    }
+
+    /// Adds `other` to this coordinate, returning `None` if the sum would exceed
+    /// the `27273042316900` ceiling.
+    pub const fn checked_add(&self, other: Self) -> Option<Self> {
+        match self.coordinate.checked_add(other.coordinate) {
+            Some(sum) => Self::try_from_u64(sum),
+            None => None,
+        }
+    }
+
+    /// Adds `other` to this coordinate, clamping to the `27273042316900` ceiling on overflow.
+    pub const fn saturating_add(&self, other: Self) -> Self {
+        let sum = self.coordinate.saturating_add(other.coordinate);
+        if sum <= 27273042316900 {
+            Self { coordinate: sum }
+        } else {
+            Self { coordinate: 27273042316900 }
+        }
+    }
+}
+impl FromStr for PositiveCoordinate {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u64 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u64(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "0".to_owned(),
+            max: "27273042316900".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -450,8 +959,19 @@ impl PositiveCoordinate32 {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u32 = s.parse().ok()?;
-        Some(Self::from_u32(value))
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.coordinate.to_string()
+    }
+}
+impl FromStr for PositiveCoordinate32 {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Ok(Self::from_u32(value))
     }
 }
 
@@ -472,8 +992,23 @@ impl LineWidth {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u32 = s.parse().ok()?;
-        Self::try_from_u32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.width.to_string()
+    }
+}
+impl FromStr for LineWidth {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "0".to_owned(),
+            max: "20116800".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -494,8 +1029,23 @@ impl FovAngle {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u32 = s.parse().ok()?;
-        Self::try_from_u32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.angle.to_string()
+    }
+}
+impl FromStr for FovAngle {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "0".to_owned(),
+            max: "10800000".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -516,8 +1066,23 @@ impl TextColumnCount {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let count: u8 = s.parse().ok()?;
-        Self::try_from_u8(count)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.count.to_string()
+    }
+}
+impl FromStr for TextColumnCount {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let count: u8 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u8(count).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "1".to_owned(),
+            max: "16".to_owned(),
+            found: count.to_string(),
+        })
     }
 }
 
@@ -538,8 +1103,23 @@ impl TextMargin {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u32 = s.parse().ok()?;
-        Self::try_from_u32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.margin.to_string()
+    }
+}
+impl FromStr for TextMargin {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "0".to_owned(),
+            max: "51206400".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -560,8 +1140,23 @@ impl TextIndentLevelType {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u8 = s.parse().ok()?;
-        Self::try_from_u8(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.level.to_string()
+    }
+}
+impl FromStr for TextIndentLevelType {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u8 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u8(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "0".to_owned(),
+            max: "8".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -582,8 +1177,23 @@ impl TextIndent {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: i32 = s.parse().ok()?;
-        Self::try_from_i32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.level.to_string()
+    }
+}
+impl FromStr for TextIndent {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: i32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_i32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "-51206400".to_owned(),
+            max: "51206400".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -604,8 +1214,23 @@ impl TextSpacingPoint {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u32 = s.parse().ok()?;
-        Self::try_from_u32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.level.to_string()
+    }
+}
+impl FromStr for TextSpacingPoint {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "0".to_owned(),
+            max: "158400".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -626,8 +1251,23 @@ impl TextBulletSizePercent {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u16 = s.parse().ok()?;
-        Self::try_from_u16(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.size.to_string()
+    }
+}
+impl FromStr for TextBulletSizePercent {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u16 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u16(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "25".to_owned(),
+            max: "400".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -648,8 +1288,23 @@ impl TextNonNegativePoint {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u32 = s.parse().ok()?;
-        Self::try_from_u32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.size.to_string()
+    }
+}
+impl FromStr for TextNonNegativePoint {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "0".to_owned(),
+            max: "400000".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -670,8 +1325,23 @@ impl TextFontSize {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u32 = s.parse().ok()?;
-        Self::try_from_u32(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.size.to_string()
+    }
+}
+impl FromStr for TextFontSize {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u32 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u32(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "100".to_owned(),
+            max: "400000".to_owned(),
+            found: value.to_string(),
+        })
     }
 }
 
@@ -692,7 +1362,164 @@ impl TextBulletStartAtNumber {
     }
 
     pub fn try_from_str(s: &str) -> Option<Self> {
-        let value: u16 = s.parse().ok()?;
-        Self::try_from_u16(value)
+        s.parse().ok()
+    }
+
+    pub fn to_xsd_string(&self) -> String {
+        self.start_at.to_string()
+    }
+}
+impl FromStr for TextBulletStartAtNumber {
+    type Err = SimpleTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: u16 = s.parse().map_err(|_| SimpleTypeParseError::NotANumber)?;
+        Self::try_from_u16(value).ok_or_else(|| SimpleTypeParseError::OutOfRange {
+            min: "1".to_owned(),
+            max: "32767".to_owned(),
+            found: value.to_string(),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Angle, CoordinateUnqualified, FixedAngle, FixedPercentage, Panose, Percentage,
+        PositiveCoordinate, PositiveFixedAngle, PositiveFixedPercentage, PositivePercentage,
+        TextPointUnqualified, UniversalMeasure, UniversalMeasureUnit,
+    };
+
+    #[test]
+    fn test_percentage_round_trip() {
+        for s in ["0%", "100%", "-100%", "33.33%", "-0.001%"] {
+            let parsed = Percentage::try_from_str(s).unwrap();
+            let parsed_again = Percentage::try_from_str(&parsed.to_xsd_string()).unwrap();
+            assert_eq!(parsed, parsed_again);
+        }
+    }
+
+    #[test]
+    fn test_fixed_percentage_rounds_to_two_digits() {
+        // round-half-to-even: 12.345 is exactly halfway between 12.34 and 12.35, and 4 is even
+        let parsed = FixedPercentage::try_from_str("12.345%").unwrap();
+        assert_eq!(&parsed.to_xsd_string(), "12.34%");
+
+        let parsed = FixedPercentage::try_from_str("-99.995%").unwrap();
+        assert_eq!(&parsed.to_xsd_string(), "-100%");
+
+        // round-half-to-even: 12.125 rounds to 12.12, not 12.13
+        let parsed = FixedPercentage::try_from_str("12.125%").unwrap();
+        assert_eq!(&parsed.to_xsd_string(), "12.12%");
+    }
+
+    #[test]
+    fn test_positive_percentage_round_trip() {
+        let parsed = PositivePercentage::try_from_str("250.5%").unwrap();
+        let parsed_again = PositivePercentage::try_from_str(&parsed.to_xsd_string()).unwrap();
+        assert_eq!(parsed, parsed_again);
+    }
+
+    #[test]
+    fn test_positive_fixed_percentage_round_trip() {
+        let parsed = PositiveFixedPercentage::try_from_str("100%").unwrap();
+        let parsed_again = PositiveFixedPercentage::try_from_str(&parsed.to_xsd_string()).unwrap();
+        assert_eq!(parsed, parsed_again);
+    }
+
+    #[test]
+    fn test_angle_round_trip() {
+        for s in ["0", "-2147483648", "2147483647"] {
+            let parsed = Angle::try_from_str(s).unwrap();
+            assert_eq!(&parsed.to_xsd_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_fixed_angle_round_trip() {
+        for s in ["0", "-5400000", "5400000"] {
+            let parsed = FixedAngle::try_from_str(s).unwrap();
+            assert_eq!(&parsed.to_xsd_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_positive_fixed_angle_round_trip() {
+        for s in ["0", "21600000"] {
+            let parsed = PositiveFixedAngle::try_from_str(s).unwrap();
+            assert_eq!(&parsed.to_xsd_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_panose_round_trip() {
+        let s = "0123456789ABCDEF0123";
+        let parsed = Panose::try_from_str(s).unwrap();
+        assert_eq!(&parsed.to_xsd_string(), s);
+    }
+
+    #[test]
+    fn test_coordinate_unqualified_round_trip() {
+        for s in ["0", "-27273042329600", "27273042316900"] {
+            let parsed = CoordinateUnqualified::try_from_str(s).unwrap();
+            assert_eq!(&parsed.to_xsd_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_text_point_unqualified_round_trip() {
+        for s in ["-400000", "0", "400000"] {
+            let parsed = TextPointUnqualified::try_from_str(s).unwrap();
+            assert_eq!(&parsed.to_xsd_string(), s);
+        }
+    }
+
+    #[test]
+    fn test_universal_measure_round_trip() {
+        for s in ["12.7mm", "-0.5in", "1pt", "100pc"] {
+            let parsed = UniversalMeasure::try_from_str(s).unwrap();
+            let parsed_again = UniversalMeasure::try_from_str(&parsed.to_xsd_string()).unwrap();
+            assert_eq!(parsed, parsed_again);
+        }
+    }
+
+    #[test]
+    fn test_universal_measure_avoids_scientific_notation() {
+        let parsed = UniversalMeasure::try_from_str("0.0000001mm").unwrap();
+        assert!(!parsed.to_xsd_string().contains(['e', 'E']));
+    }
+
+    #[test]
+    fn test_universal_measure_to_emu() {
+        let one_inch = UniversalMeasure::try_from_str("1in").unwrap();
+        assert_eq!(one_inch.to_emu(), Some(914400));
+
+        let one_point = UniversalMeasure::try_from_str("1pt").unwrap();
+        assert_eq!(one_point.to_emu(), Some(12700));
+    }
+
+    #[test]
+    fn test_coordinate_unqualified_universal_measure_round_trip() {
+        let coordinate = CoordinateUnqualified::try_from_str("914400").unwrap();
+        let measure = coordinate.to_universal_measure(UniversalMeasureUnit::In);
+        assert_eq!(measure.to_emu(), Some(914400));
+    }
+
+    #[test]
+    fn test_positive_coordinate_checked_add() {
+        let max = PositiveCoordinate::try_from_str("27273042316900").unwrap();
+        let one = PositiveCoordinate::try_from_str("1").unwrap();
+        assert_eq!(max.checked_add(one), None);
+
+        let zero = PositiveCoordinate::try_from_str("0").unwrap();
+        assert_eq!(zero.checked_add(one), PositiveCoordinate::try_from_str("1"));
+    }
+
+    #[test]
+    fn test_positive_coordinate_saturating_add() {
+        let max = PositiveCoordinate::try_from_str("27273042316900").unwrap();
+        let one = PositiveCoordinate::try_from_str("1").unwrap();
+        assert_eq!(max.saturating_add(one), max);
     }
 }
@@ -0,0 +1,217 @@
+//! Session-based authentication for the bim web interface.
+//!
+//! Passwords are stored as Argon2id PHC strings in `web.users`; a successful `/auth/login`
+//! issues a random 256-bit token that is stored in `web.sessions` together with an expiry
+//! timestamp and handed to the browser as a cookie. [`authenticate_request`] is the guard that
+//! request handlers call to recover the session (if any) behind an incoming request.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use chrono::{DateTime, Duration, Utc};
+use form_urlencoded;
+use http_body_util::{BodyExt, Full};
+use hyper::{Method, Request, Response};
+use hyper::body::{Bytes, Incoming};
+use rand::{Rng, thread_rng};
+use tracing::error;
+
+use crate::{connect_to_db, get_query_pairs, return_400, return_401, return_405, return_500};
+
+
+/// How long a freshly issued session remains valid.
+const SESSION_LIFETIME_HOURS: i64 = 24 * 14;
+
+/// Name of the cookie carrying the session token.
+const SESSION_COOKIE_NAME: &str = "rocketbotweb_session";
+
+/// A PHC string with no corresponding account, verified against on an unknown username so that
+/// the time taken to reject a login does not reveal whether the username exists.
+const DUMMY_PHC_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$c29tZXNhbHRzb21lc2FsdA$YWJjZGVmZ2hpamtsbW5vcHFyc3R1dnd4eXo";
+
+
+/// The authenticated identity behind a request, as established by [`authenticate_request`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct Session {
+    pub username: String,
+    pub is_admin: bool,
+}
+
+
+fn extract_session_token(request: &Request<Incoming>) -> Option<String> {
+    let cookie_header = request.headers().get("Cookie")?.to_str().ok()?;
+    for cookie_pair in cookie_header.split(';') {
+        let (name, value) = cookie_pair.trim().split_once('=')?;
+        if name == SESSION_COOKIE_NAME {
+            return Some(value.to_owned());
+        }
+    }
+    None
+}
+
+/// Verifies `password` against the Argon2id PHC string `phc_hash`, recomputing the hash with the
+/// salt and parameters embedded in `phc_hash` and comparing it in constant time.
+fn verify_password(phc_hash: &str, password: &str) -> bool {
+    let parsed_hash = match PasswordHash::new(phc_hash) {
+        Ok(h) => h,
+        Err(e) => {
+            error!("failed to parse stored password hash: {}", e);
+            return false;
+        },
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok()
+}
+
+fn generate_session_token() -> String {
+    let mut rng = thread_rng();
+    let token_bytes: [u8; 32] = rng.gen();
+    token_bytes.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Looks up the session token carried in `request`'s `Cookie` header in `web.sessions` and
+/// returns the corresponding [`Session`] if the token exists and has not expired.
+///
+/// Returns `None` for a missing, unknown or expired token; callers should respond with
+/// [`crate::return_401`] in that case rather than proceeding.
+pub(crate) async fn authenticate_request(request: &Request<Incoming>) -> Option<Session> {
+    let token = extract_session_token(request)?;
+
+    let db_conn = connect_to_db().await?;
+    let row_res = db_conn.query_opt(
+        "
+            SELECT s.username, s.expires_at, u.is_admin
+            FROM web.sessions s
+            INNER JOIN web.users u ON u.username = s.username
+            WHERE s.token = $1
+        ",
+        &[&token],
+    ).await;
+    let row = match row_res {
+        Ok(Some(r)) => r,
+        Ok(None) => return None,
+        Err(e) => {
+            error!("failed to query session: {}", e);
+            return None;
+        },
+    };
+
+    let username: String = row.get(0);
+    let expires_at: DateTime<Utc> = row.get(1);
+    let is_admin: bool = row.get(2);
+
+    if expires_at <= Utc::now() {
+        return None;
+    }
+
+    Some(Session { username, is_admin })
+}
+
+
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+struct LoginForm {
+    pub username: String,
+    pub password: String,
+}
+
+fn parse_login_form(body: &[u8]) -> Option<LoginForm> {
+    let mut username = None;
+    let mut password = None;
+    for (key, value) in form_urlencoded::parse(body) {
+        match key.as_ref() {
+            "username" => username = Some(value.into_owned()),
+            "password" => password = Some(value.into_owned()),
+            _ => {},
+        }
+    }
+    Some(LoginForm {
+        username: username?,
+        password: password?,
+    })
+}
+
+pub(crate) async fn handle_auth_login(request: Request<Incoming>, is_tls: bool) -> Result<Response<Full<Bytes>>, Infallible> {
+    // owned, rather than borrowed from `request`, so that the request can still be consumed
+    // below in order to read its body
+    let query_pairs: HashMap<Cow<'static, str>, Cow<'static, str>> = get_query_pairs(&request)
+        .into_iter()
+        .map(|(k, v)| (Cow::Owned(k.into_owned()), Cow::Owned(v.into_owned())))
+        .collect();
+    if request.method() != Method::POST {
+        return return_405(&query_pairs).await;
+    }
+
+    let body_bytes = match request.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => {
+            error!("failed to read login request body: {}", e);
+            return return_500();
+        },
+    };
+    let login_form = match parse_login_form(&body_bytes) {
+        Some(lf) => lf,
+        None => return return_400("missing username or password", &query_pairs).await,
+    };
+
+    let db_conn = match connect_to_db().await {
+        Some(c) => c,
+        None => return return_500(),
+    };
+
+    // fetch the stored hash regardless of whether the user exists, and fall back to verifying
+    // against a dummy hash otherwise, so that an unknown username and a wrong password take the
+    // same amount of time and produce the same response
+    let row_res = db_conn.query_opt(
+        "SELECT argon2_phc_hash FROM web.users WHERE username = $1",
+        &[&login_form.username],
+    ).await;
+    let phc_hash_opt: Option<String> = match row_res {
+        Ok(row_opt) => row_opt.map(|row| row.get(0)),
+        Err(e) => {
+            error!("failed to query user: {}", e);
+            return return_500();
+        },
+    };
+    let phc_hash: &str = phc_hash_opt.as_deref().unwrap_or(DUMMY_PHC_HASH);
+    let password_ok = verify_password(phc_hash, &login_form.password) && phc_hash_opt.is_some();
+    if !password_ok {
+        return return_401(&query_pairs).await;
+    }
+
+    let token = generate_session_token();
+    let expires_at = Utc::now() + Duration::hours(SESSION_LIFETIME_HOURS);
+    let insert_res = db_conn.execute(
+        "INSERT INTO web.sessions (token, username, expires_at) VALUES ($1, $2, $3)",
+        &[&token, &login_form.username, &expires_at],
+    ).await;
+    if let Err(e) = insert_res {
+        error!("failed to insert session: {}", e);
+        return return_500();
+    }
+
+    // only mark the cookie Secure if this request actually arrived over TLS; a plain-HTTP
+    // client would never send a Secure cookie back, silently breaking login on deployments
+    // that haven't configured TLS
+    let secure_attr = if is_tls { "; Secure" } else { "" };
+    let cookie_header = format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict{}; Max-Age={}",
+        SESSION_COOKIE_NAME, token, secure_attr, SESSION_LIFETIME_HOURS * 3600,
+    );
+    let response_res = Response::builder()
+        .status(303)
+        .header("Set-Cookie", cookie_header)
+        .header("Location", "/bim-vehicle-status")
+        .body(Full::new(Bytes::new()));
+    match response_res {
+        Ok(r) => Ok(r),
+        Err(e) => {
+            error!("failed to assemble login redirect response: {}", e);
+            return_500()
+        },
+    }
+}
@@ -8,12 +8,15 @@ pub(crate) mod tables;
 pub(crate) mod top;
 
 
+use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 
+use chrono::NaiveDate;
 use form_urlencoded;
-use rocketbot_bim_common::{VehicleInfo, VehicleNumber};
+use rocketbot_bim_common::{CouplingMode, VehicleInfo, VehicleNumber};
 use serde::{Deserialize, Serialize};
+use tokio_postgres::types::ToSql;
 use tracing::{error, warn};
 
 use crate::{connect_to_db, get_bot_config};
@@ -45,6 +48,123 @@ impl Default for VehicleDatabaseExtract {
 }
 
 
+/// Additional ride-filtering criteria, parsed from query-string parameters (`company`, `line`,
+/// `weekday`, `hour`, `before`, `after`, `coupling`), shared by the read-only JSON statistics
+/// endpoints. Mirrors the `key:value` filter syntax accepted by the bim chat commands.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct StatsQueryFilter {
+    pub company: Option<String>,
+    pub line: Option<String>,
+    pub weekday: Option<i32>,
+    pub hour_range: Option<(i32, i32)>,
+    pub before: Option<NaiveDate>,
+    pub after: Option<NaiveDate>,
+    pub coupling_mode: Option<CouplingMode>,
+}
+impl StatsQueryFilter {
+    pub fn parse(query_pairs: &HashMap<Cow<str>, Cow<str>>) -> Result<Self, String> {
+        let company = query_pairs.get("company")
+            .map(|c| c.to_string())
+            .filter(|c| c.len() > 0);
+        let line = query_pairs.get("line")
+            .map(|l| l.to_string())
+            .filter(|l| l.len() > 0);
+        let weekday = match query_pairs.get("weekday") {
+            Some(w) if w.len() > 0 => Some(
+                parse_weekday(w).ok_or_else(|| format!("invalid weekday {:?}", w))?
+            ),
+            _ => None,
+        };
+        let hour_range = match query_pairs.get("hour") {
+            Some(h) if h.len() > 0 => Some(
+                parse_hour_range(h).ok_or_else(|| format!("invalid hour range {:?} (expected e.g. \"4-10\")", h))?
+            ),
+            _ => None,
+        };
+        let before = match query_pairs.get("before") {
+            Some(b) if b.len() > 0 => Some(
+                NaiveDate::parse_from_str(b, "%Y-%m-%d").map_err(|_| format!("invalid date {:?}", b))?
+            ),
+            _ => None,
+        };
+        let after = match query_pairs.get("after") {
+            Some(a) if a.len() > 0 => Some(
+                NaiveDate::parse_from_str(a, "%Y-%m-%d").map_err(|_| format!("invalid date {:?}", a))?
+            ),
+            _ => None,
+        };
+        let coupling_mode = match query_pairs.get("coupling") {
+            Some(c) if c.len() > 0 => Some(
+                CouplingMode::try_from_db_str(c).ok_or_else(|| format!("invalid coupling mode {:?}", c))?
+            ),
+            _ => None,
+        };
+
+        Ok(Self { company, line, weekday, hour_range, before, after, coupling_mode })
+    }
+
+    /// Appends this filter's conditions (each prefixed with `AND`) to `criteria`, pushing bound
+    /// parameters onto `params` and referencing the rides table under `alias`.
+    pub fn push_conditions<'p>(&'p self, alias: &str, criteria: &mut Vec<String>, params: &mut Vec<&'p (dyn ToSql + Sync)>) {
+        if let Some(company) = &self.company {
+            criteria.push(format!("AND LOWER({}.company) = LOWER(${})", alias, params.len() + 1));
+            params.push(company);
+        }
+        if let Some(line) = &self.line {
+            criteria.push(format!("AND LOWER({}.line) = LOWER(${})", alias, params.len() + 1));
+            params.push(line);
+        }
+        if let Some(weekday) = &self.weekday {
+            // already validated by `parse`, so it is safe to embed as a literal
+            criteria.push(format!("AND EXTRACT(DOW FROM {}.\"timestamp\") = {}", alias, weekday));
+        }
+        if let Some((from_hour, to_hour)) = &self.hour_range {
+            // both bounds were range-checked (0..=23) by `parse`
+            criteria.push(format!(
+                "AND EXTRACT(HOUR FROM {alias}.\"timestamp\") >= {from} AND EXTRACT(HOUR FROM {alias}.\"timestamp\") <= {to}",
+                alias = alias, from = from_hour, to = to_hour,
+            ));
+        }
+        if let Some(before) = &self.before {
+            criteria.push(format!("AND {}.\"timestamp\" < ${}", alias, params.len() + 1));
+            params.push(before);
+        }
+        if let Some(after) = &self.after {
+            criteria.push(format!("AND {}.\"timestamp\" >= ${}", alias, params.len() + 1));
+            params.push(after);
+        }
+        if let Some(coupling_mode) = &self.coupling_mode {
+            // already validated by `parse`, so it is safe to embed directly
+            criteria.push(format!("AND {}.coupling_mode = '{}'", alias, coupling_mode.as_db_str()));
+        }
+    }
+}
+
+fn parse_weekday(value: &str) -> Option<i32> {
+    // Postgres' EXTRACT(DOW) returns 0 (Sunday) through 6 (Saturday)
+    match value.to_lowercase().as_str() {
+        "sun" | "sunday" => Some(0),
+        "mon" | "monday" => Some(1),
+        "tue" | "tuesday" => Some(2),
+        "wed" | "wednesday" => Some(3),
+        "thu" | "thursday" => Some(4),
+        "fri" | "friday" => Some(5),
+        "sat" | "saturday" => Some(6),
+        _ => None,
+    }
+}
+
+fn parse_hour_range(value: &str) -> Option<(i32, i32)> {
+    let (from_str, to_str) = value.split_once('-')?;
+    let from_hour: i32 = from_str.parse().ok()?;
+    let to_hour: i32 = to_str.parse().ok()?;
+    if from_hour < 0 || from_hour > 23 || to_hour < 0 || to_hour > 23 {
+        return None;
+    }
+    Some((from_hour, to_hour))
+}
+
+
 fn append_to_query(query_string: &mut String, key: &str, value: &str) {
     if query_string.len() > 0 {
         query_string.push('&');
@@ -14,11 +14,13 @@ use tokio_postgres::types::ToSql;
 use tracing::{debug, error};
 
 use crate::{
-    get_query_pairs, get_query_pairs_multiset, render_response, return_400, return_405, return_500,
+    get_query_pairs, get_query_pairs_multiset, render_response, return_400, return_401, return_405,
+    return_500,
 };
+use crate::auth::authenticate_request;
 use crate::bim::{
     append_to_query, connect_to_db, obtain_bim_plugin_config, obtain_company_to_bim_database,
-    obtain_company_to_definition,
+    obtain_company_to_definition, StatsQueryFilter,
 };
 use crate::templating::filters;
 use crate::util::sort_as_text;
@@ -96,6 +98,12 @@ struct VehicleStatusSetupTemplate {
     pub companies: Vec<String>,
     pub default_company: Option<String>,
     pub riders: Vec<String>,
+
+    pub rider_offset: i64,
+    pub rider_limit: i64,
+    pub rider_prev_offset: Option<i64>,
+    pub rider_next_offset: Option<i64>,
+    pub total_rider_count: i64,
 }
 
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Template)]
@@ -133,6 +141,88 @@ enum LastRideState {
 }
 
 
+/// A page of the distinct-riders listing, as computed by [`query_rider_page`].
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct RiderPage {
+    pub riders: Vec<String>,
+    pub total_rider_count: i64,
+    pub prev_offset: Option<i64>,
+    pub next_offset: Option<i64>,
+}
+
+/// Queries a page of the `DISTINCT rider_username` values in `bim.rides`, applying `stats_filter`
+/// and an optional `rider_search` substring (case-insensitively) before paging with `offset` and
+/// `limit`.
+///
+/// Generic over [`tokio_postgres::GenericClient`] so it can run against either a plain connection
+/// or (in tests) a transaction that gets rolled back afterwards.
+async fn query_rider_page<C: tokio_postgres::GenericClient>(
+    db_conn: &C,
+    stats_filter: &StatsQueryFilter,
+    rider_search: Option<&str>,
+    offset: i64,
+    limit: i64,
+) -> Result<RiderPage, tokio_postgres::Error> {
+    let mut criteria = Vec::new();
+    let mut params: Vec<&(dyn ToSql + Sync)> = Vec::new();
+    if let Some(rider_search) = &rider_search {
+        criteria.push(format!("AND r.rider_username ILIKE ${}", params.len() + 1));
+        params.push(rider_search);
+    }
+    stats_filter.push_conditions("r", &mut criteria, &mut params);
+
+    let count_query = format!(
+        "
+            SELECT CAST(COUNT(DISTINCT r.rider_username) AS bigint)
+            FROM bim.rides r
+            WHERE 1=1
+            {}
+        ",
+        criteria.join(" "),
+    );
+    let count_row = db_conn.query_one(&count_query, &params).await?;
+    let total_rider_count: i64 = count_row.get(0);
+
+    params.push(&limit);
+    params.push(&offset);
+    let query = format!(
+        "
+            SELECT DISTINCT r.rider_username
+            FROM bim.rides r
+            WHERE 1=1
+            {}
+            ORDER BY r.rider_username
+            LIMIT ${} OFFSET ${}
+        ",
+        criteria.join(" "),
+        params.len() - 1,
+        params.len(),
+    );
+    let rows = db_conn.query(&query, &params).await?;
+
+    let mut riders: Vec<String> = Vec::with_capacity(rows.len());
+    for row in rows {
+        let rider: String = row.get(0);
+        riders.push(rider);
+    }
+    sort_as_text(&mut riders);
+
+    let prev_offset = if offset > 0 { Some((offset - limit).max(0)) } else { None };
+    let next_offset = if offset + limit < total_rider_count { Some(offset + limit) } else { None };
+
+    Ok(RiderPage { riders, total_rider_count, prev_offset, next_offset })
+}
+
+/// Extracts the configured default company from the bim plugin's `config.default_company` value.
+fn parse_default_company(config_value: &serde_json::Value) -> Result<Option<String>, String> {
+    match config_value {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(s) => Ok(Some(s.clone())),
+        other => Err(format!("default company has unexpected value {:?}", other)),
+    }
+}
+
+
 fn cows_to_owned_skip_empty<'a, 'b>(vals: Option<&'a Vec<Cow<'b, str>>>) -> Vec<String> {
     if let Some(some_vals) = vals {
         let mut ret = Vec::with_capacity(some_vals.len());
@@ -648,34 +738,62 @@ pub(crate) async fn handle_bim_vehicle_status(request: &Request<Incoming>) -> Re
             }
         },
         _ => {
-            // show setup page
+            // show setup page; this is where vehicle status mappings are configured, so require
+            // a valid session (no admin flag needed yet, but future write routes should check
+            // `session.is_admin`)
+            if authenticate_request(request).await.is_none() {
+                return return_401(&query_pairs).await;
+            }
+
             let plugin_config = match obtain_bim_plugin_config().await {
                 Some(p) => p,
                 None => return return_500(),
             };
-            let default_company = match &plugin_config["config"]["default_company"] {
-                serde_json::Value::Null => None,
-                serde_json::Value::String(s) => Some(s.clone()),
-                other => {
-                    error!("default company has unexpected value {:?}", other);
+            let default_company = match parse_default_company(&plugin_config["config"]["default_company"]) {
+                Ok(dc) => dc,
+                Err(e) => {
+                    error!("{}", e);
                     return return_500();
                 },
             };
 
-            let mut riders_set = HashSet::new();
-            let rows = match db_conn.query("SELECT DISTINCT rider_username FROM bim.rides", &[]).await {
-                Ok(r) => r,
+            let stats_filter = match StatsQueryFilter::parse(&query_pairs) {
+                Ok(f) => f,
+                Err(e) => return return_400(&e, &query_pairs).await,
+            };
+            let rider_offset: i64 = match query_pairs.get("offset") {
+                Some(o) if o.len() > 0 => match o.parse() {
+                    Ok(v) if v >= 0 => v,
+                    _ => return return_400("invalid offset", &query_pairs).await,
+                },
+                _ => 0,
+            };
+            const DEFAULT_RIDER_LIMIT: i64 = 100;
+            let rider_limit: i64 = match query_pairs.get("limit") {
+                Some(l) if l.len() > 0 => match l.parse() {
+                    Ok(v) if v > 0 => v,
+                    _ => return return_400("invalid limit", &query_pairs).await,
+                },
+                _ => DEFAULT_RIDER_LIMIT,
+            };
+            let rider_search = query_pairs.get("rider")
+                .map(|r| format!("%{}%", r))
+                .filter(|r| r.len() > 2);
+
+            let rider_page_res = query_rider_page(
+                &db_conn, &stats_filter, rider_search.as_deref(), rider_offset, rider_limit,
+            ).await;
+            let rider_page = match rider_page_res {
+                Ok(rp) => rp,
                 Err(e) => {
                     error!("error querying riders: {}", e);
                     return return_500();
                 },
             };
-            for row in rows {
-                let rider: String = row.get(0);
-                riders_set.insert(rider);
-            }
-            let mut riders: Vec<String> = riders_set.into_iter().collect();
-            sort_as_text(&mut riders);
+            let riders = rider_page.riders;
+            let total_rider_count = rider_page.total_rider_count;
+            let rider_prev_offset = rider_page.prev_offset;
+            let rider_next_offset = rider_page.next_offset;
 
             let companies_set: HashSet<String> = company_to_definition.keys()
                 .map(|k| k.clone())
@@ -687,6 +805,11 @@ pub(crate) async fn handle_bim_vehicle_status(request: &Request<Incoming>) -> Re
                 companies,
                 default_company,
                 riders,
+                rider_offset,
+                rider_limit,
+                rider_prev_offset,
+                rider_next_offset,
+                total_rider_count,
             };
             match render_response(&template, &query_pairs, 200, vec![]).await {
                 Some(r) => Ok(r),
@@ -695,3 +818,61 @@ pub(crate) async fn handle_bim_vehicle_status(request: &Request<Incoming>) -> Re
         },
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{with_rides_fixture, RideFixture};
+
+    #[test]
+    fn test_parse_default_company() {
+        assert_eq!(parse_default_company(&serde_json::Value::Null), Ok(None));
+        assert_eq!(
+            parse_default_company(&serde_json::Value::String("Wiener Linien".to_owned())),
+            Ok(Some("Wiener Linien".to_owned())),
+        );
+        assert!(parse_default_company(&serde_json::Value::Bool(true)).is_err());
+    }
+
+    // the riders query is run against a real (rolled-back) database transaction, so it is
+    // ignored by default; run with `cargo test -- --ignored` against a database configured via
+    // ROCKETBOTWEB_TEST_DB_CONN_STRING
+    #[tokio::test]
+    #[ignore]
+    async fn test_query_rider_page() {
+        let fixtures = [
+            RideFixture { company: "wien", rider_username: "alice", timestamp: Utc::now(), line: Some("1") },
+            RideFixture { company: "wien", rider_username: "bob", timestamp: Utc::now(), line: Some("2") },
+            RideFixture { company: "graz", rider_username: "carol", timestamp: Utc::now(), line: Some("3") },
+        ];
+
+        with_rides_fixture(&fixtures, |txn| Box::pin(async move {
+            let stats_filter = StatsQueryFilter::default();
+            let page = query_rider_page(txn, &stats_filter, None, 0, 100).await
+                .expect("failed to query rider page");
+            assert_eq!(page.riders, vec!["alice".to_owned(), "bob".to_owned(), "carol".to_owned()]);
+            assert_eq!(page.total_rider_count, 3);
+            assert_eq!(page.prev_offset, None);
+            assert_eq!(page.next_offset, None);
+
+            let first_page = query_rider_page(txn, &stats_filter, None, 0, 2).await
+                .expect("failed to query rider page");
+            assert_eq!(first_page.riders, vec!["alice".to_owned(), "bob".to_owned()]);
+            assert_eq!(first_page.next_offset, Some(2));
+
+            let wien_filter = StatsQueryFilter {
+                company: Some("wien".to_owned()),
+                ..Default::default()
+            };
+            let wien_page = query_rider_page(txn, &wien_filter, None, 0, 100).await
+                .expect("failed to query rider page");
+            assert_eq!(wien_page.riders, vec!["alice".to_owned(), "bob".to_owned()]);
+            assert_eq!(wien_page.total_rider_count, 2);
+
+            let search_page = query_rider_page(txn, &stats_filter, Some("%ob%"), 0, 100).await
+                .expect("failed to query rider page");
+            assert_eq!(search_page.riders, vec!["bob".to_owned()]);
+        })).await;
+    }
+}
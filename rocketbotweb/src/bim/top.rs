@@ -12,8 +12,8 @@ use serde::Serialize;
 use tokio_postgres::types::ToSql;
 use tracing::error;
 
-use crate::{get_query_pairs, render_response, return_405, return_500};
-use crate::bim::connect_to_db;
+use crate::{get_query_pairs, render_response, return_400, return_405, return_500};
+use crate::bim::{connect_to_db, StatsQueryFilter};
 use crate::templating::filters;
 
 
@@ -447,6 +447,10 @@ pub(crate) async fn handle_top_bim_lines(request: &Request<Incoming>) -> Result<
         .unwrap_or(10);
     let username_opt = query_pairs.get("username")
         .and_then(|u| if u.len() == 0 { None } else { Some(u) });
+    let stats_filter = match StatsQueryFilter::parse(&query_pairs) {
+        Ok(f) => f,
+        Err(e) => return return_400(&e, &query_pairs).await,
+    };
 
     let db_conn = match connect_to_db().await {
         Some(c) => c,
@@ -461,6 +465,7 @@ pub(crate) async fn handle_top_bim_lines(request: &Request<Incoming>) -> Result<
         ride_counts_criteria.push(format!("AND r.rider_username = ${}", query_params.len() + 1));
         query_params.push(username);
     }
+    stats_filter.push_conditions("r", &mut ride_counts_criteria, &mut query_params);
 
     // query rides
     let query = format!(
@@ -548,6 +553,10 @@ pub(crate) async fn handle_top_bim_days(request: &Request<Incoming>) -> Result<R
         .unwrap_or(10);
     let username_opt = query_pairs.get("username")
         .and_then(|u| if u.len() == 0 { None } else { Some(u) });
+    let stats_filter = match StatsQueryFilter::parse(&query_pairs) {
+        Ok(f) => f,
+        Err(e) => return return_400(&e, &query_pairs).await,
+    };
 
     let db_conn = match connect_to_db().await {
         Some(c) => c,
@@ -560,12 +569,14 @@ pub(crate) async fn handle_top_bim_days(request: &Request<Incoming>) -> Result<R
     query_params.push(&top_count);
 
     if let Some(username) = username_opt {
-        ride_counts_criteria.push(format!("r.rider_username = ${}", query_params.len() + 1));
+        ride_counts_criteria.push(format!("AND r.rider_username = ${}", query_params.len() + 1));
         query_params.push(username);
 
         main_criteria.push(format!("AND r.rider_username = ${}", query_params.len() + 1));
         query_params.push(username);
     }
+    stats_filter.push_conditions("r", &mut ride_counts_criteria, &mut query_params);
+    stats_filter.push_conditions("r", &mut main_criteria, &mut query_params);
 
     // query rides
     let query = format!(
@@ -573,7 +584,8 @@ pub(crate) async fn handle_top_bim_days(request: &Request<Incoming>) -> Result<R
             WITH ride_counts(ride_date, ride_count) AS (
                 SELECT bim.to_transport_date(r.\"timestamp\"), COUNT(*)
                 FROM bim.rides r
-                {} {}
+                WHERE 1=1
+                {}
                 GROUP BY bim.to_transport_date(r.\"timestamp\")
             ),
             top_ride_counts(ride_count) AS (
@@ -594,8 +606,7 @@ pub(crate) async fn handle_top_bim_days(request: &Request<Incoming>) -> Result<R
             {}
             GROUP BY rc.ride_date, r.rider_username, rc.ride_count
         ",
-        if ride_counts_criteria.len() > 0 { "WHERE" } else { "" },
-        ride_counts_criteria.join(" AND "),
+        ride_counts_criteria.join(" "),
         main_criteria.join(" "),
     );
     let ride_rows_res = db_conn.query(&query, &query_params).await;
@@ -646,6 +657,131 @@ pub(crate) async fn handle_top_bim_days(request: &Request<Incoming>) -> Result<R
     }
 }
 
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+struct RiderCountPart {
+    pub rider: String,
+    pub ride_count: i64,
+    pub vehicle_count: i64,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Template)]
+#[template(path = "top-riders.html")]
+struct TopRidersTemplate {
+    pub riders: Vec<RiderCountPart>,
+}
+
+pub(crate) async fn handle_top_riders(request: &Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+    let query_pairs = get_query_pairs(request);
+
+    if request.method() != Method::GET {
+        return return_405(&query_pairs).await;
+    }
+
+    let top_count: i64 = query_pairs.get("count")
+        .map(|c_str| c_str.parse().ok())
+        .flatten()
+        .filter(|tc| *tc > 0)
+        .unwrap_or(10);
+    let stats_filter = match StatsQueryFilter::parse(&query_pairs) {
+        Ok(f) => f,
+        Err(e) => return return_400(&e, &query_pairs).await,
+    };
+
+    let db_conn = match connect_to_db().await {
+        Some(c) => c,
+        None => return return_500(),
+    };
+
+    let mut ride_count_criteria = Vec::new();
+    let mut ride_count_params: Vec<&(dyn ToSql + Sync)> = Vec::new();
+    stats_filter.push_conditions("r", &mut ride_count_criteria, &mut ride_count_params);
+
+    let ride_count_query = format!(
+        "
+            SELECT r.rider_username, CAST(COUNT(*) AS bigint) ride_count
+            FROM bim.rides r
+            WHERE 1=1
+            {}
+            GROUP BY r.rider_username
+        ",
+        ride_count_criteria.join(" "),
+    );
+    let ride_rows_res = db_conn.query(&ride_count_query, &ride_count_params).await;
+    let ride_rows = match ride_rows_res {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("error querying rider ride counts: {}", e);
+            return return_500();
+        },
+    };
+
+    let mut rider_to_ride_and_vehicle_count: HashMap<String, (i64, i64)> = HashMap::new();
+    for ride_row in ride_rows {
+        let rider_username: String = ride_row.get(0);
+        let ride_count: i64 = ride_row.get(1);
+
+        rider_to_ride_and_vehicle_count
+            .entry(rider_username)
+            .or_insert((0, 0))
+            .0 += ride_count;
+    }
+
+    let mut vehicle_count_criteria = Vec::new();
+    let mut vehicle_count_params: Vec<&(dyn ToSql + Sync)> = Vec::new();
+    stats_filter.push_conditions("r", &mut vehicle_count_criteria, &mut vehicle_count_params);
+
+    let vehicle_count_query = format!(
+        "
+            SELECT i.rider_username, CAST(COUNT(*) AS bigint) vehicle_count
+            FROM (
+                SELECT DISTINCT r.rider_username, r.company, rv.vehicle_number
+                FROM bim.rides r
+                INNER JOIN bim.ride_vehicles rv ON rv.ride_id = r.id
+                WHERE rv.coupling_mode = 'R'
+                {}
+            ) i
+            GROUP BY i.rider_username
+        ",
+        vehicle_count_criteria.join(" "),
+    );
+    let vehicle_rows_res = db_conn.query(&vehicle_count_query, &vehicle_count_params).await;
+    let vehicle_rows = match vehicle_rows_res {
+        Ok(rs) => rs,
+        Err(e) => {
+            error!("error querying rider vehicle counts: {}", e);
+            return return_500();
+        },
+    };
+
+    for vehicle_row in vehicle_rows {
+        let rider_username: String = vehicle_row.get(0);
+        let vehicle_count: i64 = vehicle_row.get(1);
+
+        rider_to_ride_and_vehicle_count
+            .entry(rider_username)
+            .or_insert((0, 0))
+            .1 += vehicle_count;
+    }
+
+    let mut riders: Vec<RiderCountPart> = rider_to_ride_and_vehicle_count.iter()
+        .map(|(rider, (ride_count, vehicle_count))| RiderCountPart {
+            rider: rider.clone(),
+            ride_count: *ride_count,
+            vehicle_count: *vehicle_count,
+        })
+        .collect();
+    riders.sort_unstable_by(|a, b| b.ride_count.cmp(&a.ride_count).then_with(|| a.rider.cmp(&b.rider)));
+    riders.truncate(top_count.max(0) as usize);
+
+    let template = TopRidersTemplate {
+        riders,
+    };
+    match render_response(&template, &query_pairs, 200, vec![]).await {
+        Some(r) => Ok(r),
+        None => return_500(),
+    }
+}
+
 pub(crate) async fn handle_bim_last_riders(request: &Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
     let query_pairs = get_query_pairs(request);
 
@@ -11,6 +11,14 @@ pub struct WebConfig {
     pub bot_config_path: PathBuf,
     pub static_path: PathBuf,
     #[serde(default)] pub bim_odds_ends: Vec<BimOddEndConfig>,
+
+    /// Address to bind the TLS-terminated listener to. Required if `tls_cert_path` and
+    /// `tls_key_path` are set; the plain-HTTP listener on `listen` keeps running alongside it.
+    #[serde(default)] pub tls_listen: Option<SocketAddr>,
+    /// Path to the PEM-encoded TLS certificate (chain) to serve. Reloaded on SIGHUP.
+    #[serde(default)] pub tls_cert_path: Option<PathBuf>,
+    /// Path to the PEM-encoded TLS private key matching `tls_cert_path`. Reloaded on SIGHUP.
+    #[serde(default)] pub tls_key_path: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
@@ -1,10 +1,13 @@
 mod aliases;
+mod auth;
 mod bim;
 mod config;
 mod line_graph_drawing;
 mod quotes;
 mod templating;
+#[cfg(test)] mod test_support;
 mod thanks;
+mod tls;
 mod util;
 
 
@@ -15,6 +18,7 @@ use std::env;
 use std::ffi::OsString;
 use std::fs::File;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use askama::Template;
 use form_urlencoded;
@@ -29,12 +33,15 @@ use regex::Regex;
 use serde::{Serialize, Deserialize};
 use serde_json;
 use tokio::net::TcpListener;
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::sync::{RwLock, RwLockReadGuard};
 use tokio_postgres::{self, NoTls};
+use tokio_rustls::TlsAcceptor;
 use toml;
 use tracing::{debug, error};
 
 use crate::aliases::{handle_nicks_aliases, handle_plaintext_aliases_for_nick};
+use crate::auth::handle_auth_login;
 use crate::bim::achievements::handle_bim_achievements;
 use crate::bim::charts::{
     handle_bim_depot_last_rider_pie, handle_bim_first_rider_pie,
@@ -56,12 +63,13 @@ use crate::bim::tables::{
 };
 use crate::bim::top::{
     handle_bim_fixed_monopolies, handle_bim_last_riders, handle_explorer_bims, handle_top_bim_days,
-    handle_top_bim_lines, handle_top_bims, handle_wide_bims,
+    handle_top_bim_lines, handle_top_bims, handle_top_riders, handle_wide_bims,
 };
 use crate::config::WebConfig;
 use crate::quotes::{handle_quotes_votes, handle_top_quotes};
-use crate::templating::{Error400Template, Error404Template, Error405Template};
+use crate::templating::{Error400Template, Error401Template, Error404Template, Error405Template};
 use crate::thanks::handle_thanks;
+use crate::tls::TlsConfigHolder;
 
 
 pub(crate) static CONFIG: OnceCell<RwLock<WebConfig>> = OnceCell::new();
@@ -86,9 +94,33 @@ struct IndexTemplate;
 
 
 fn get_query_pairs<'a, T>(request: &'a Request<T>) -> HashMap<Cow<'a, str>, Cow<'a, str>> {
-    get_query_pairs_vec(request)
+    let mut pairs: HashMap<Cow<'a, str>, Cow<'a, str>> = get_query_pairs_vec(request)
         .into_iter()
-        .collect()
+        .collect();
+
+    // let an explicit Accept header pick JSON output even if the query string doesn't say so,
+    // without forcing every render_response call site to duplicate the negotiation logic
+    if !pairs.contains_key("format") && wants_json_response(request) {
+        pairs.insert(Cow::Borrowed("format"), Cow::Borrowed("json"));
+    }
+
+    pairs
+}
+
+/// Whether `request`'s `Accept` header names `application/json` as an acceptable response type.
+fn wants_json_response<T>(request: &Request<T>) -> bool {
+    let accept_value = match request.headers().get(hyper::header::ACCEPT) {
+        Some(a) => a,
+        None => return false,
+    };
+    let accept_str = match accept_value.to_str() {
+        Ok(s) => s,
+        Err(_) => return false,
+    };
+    accept_str
+        .split(',')
+        .map(|part| part.split(';').next().unwrap_or("").trim())
+        .any(|mime| mime == "application/json")
 }
 
 fn get_query_pairs_multiset<'a, T>(request: &'a Request<T>) -> HashMap<Cow<'a, str>, Vec<Cow<'a, str>>> {
@@ -238,6 +270,14 @@ async fn return_400(reason: &str, query_pairs: &HashMap<Cow<'_, str>, Cow<'_, st
     }
 }
 
+async fn return_401(query_pairs: &HashMap<Cow<'_, str>, Cow<'_, str>>) -> Result<Response<Full<Bytes>>, Infallible> {
+    let template = Error401Template;
+    match render_response(&template, query_pairs, 401, vec![]).await {
+        Some(r) => Ok(r),
+        None => return_500(),
+    }
+}
+
 async fn return_405(query_pairs: &HashMap<Cow<'_, str>, Cow<'_, str>>) -> Result<Response<Full<Bytes>>, Infallible> {
     let template = Error405Template {
         allowed_methods: vec!["GET".to_owned()],
@@ -325,7 +365,12 @@ async fn handle_static(request: &Request<Incoming>, caps: &regex::Captures<'_>)
 }
 
 
-async fn handle_request(request: Request<Incoming>) -> Result<Response<Full<Bytes>>, Infallible> {
+async fn handle_request(request: Request<Incoming>, is_tls: bool) -> Result<Response<Full<Bytes>>, Infallible> {
+    // handled separately because it needs to consume the request body
+    if request.uri().path() == "/auth/login" {
+        return handle_auth_login(request, is_tls).await;
+    }
+
     match request.uri().path() {
         "/" => handle_index(&request).await,
         "/topquotes" => handle_top_quotes(&request).await,
@@ -357,6 +402,7 @@ async fn handle_request(request: Request<Incoming>) -> Result<Response<Full<Byte
         "/bim-histogram-fixed-coupling" => handle_bim_histogram_fixed_coupling(&request).await,
         "/bim-global-stats" => handle_bim_global_stats(&request).await,
         "/top-bim-days" => handle_top_bim_days(&request).await,
+        "/top-riders" => handle_top_riders(&request).await,
         "/bim-vehicle-status" => handle_bim_vehicle_status(&request).await,
         "/bim-first-rider-pie" => handle_bim_first_rider_pie(&request).await,
         "/bim-type-histogram" => handle_bim_type_histogram(&request).await,
@@ -409,9 +455,66 @@ async fn main() {
             .expect("failed to parse config file")
     };
     let listen_address = config.listen.clone();
+    let tls_listen_address = config.tls_listen.clone();
+    let tls_holder = match (&config.tls_cert_path, &config.tls_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let holder = TlsConfigHolder::load(cert_path.clone(), key_path.clone())
+                .expect("failed to load initial TLS certificate");
+            Some(Arc::new(holder))
+        },
+        (None, None) => None,
+        _ => panic!("tls_cert_path and tls_key_path must either both be set or both be absent"),
+    };
     CONFIG.set(RwLock::new(config))
         .expect("failed to set initial config");
 
+    if let Some(holder) = tls_holder {
+        let tls_listen_address = tls_listen_address
+            .expect("tls_listen must be set if tls_cert_path and tls_key_path are set");
+
+        // reload the certificate from disk on SIGHUP so a renewed one takes effect without
+        // restarting the whole bot
+        {
+            let holder = Arc::clone(&holder);
+            tokio::task::spawn(async move {
+                let mut sighup = signal(SignalKind::hangup())
+                    .expect("failed to install SIGHUP handler");
+                loop {
+                    sighup.recv().await;
+                    holder.reload().await;
+                }
+            });
+        }
+
+        let tls_listener = TcpListener::bind(tls_listen_address).await
+            .expect("failed to create TLS TCP listener");
+        tokio::task::spawn(async move {
+            loop {
+                let (stream, remote_addr) = tls_listener.accept().await
+                    .expect("failed to accept incoming TLS connection");
+                let acceptor = TlsAcceptor::from(holder.current().await);
+                tokio::task::spawn(async move {
+                    let tls_stream = match acceptor.accept(stream).await {
+                        Ok(s) => s,
+                        Err(e) => {
+                            error!("TLS handshake with {} failed: {}", remote_addr, e);
+                            return;
+                        },
+                    };
+                    let io = TokioIo::new(tls_stream);
+                    let serve_result = Builder::new(TokioExecutor::new())
+                        .http1()
+                        .http2()
+                        .serve_connection(io, service_fn(|req| handle_request(req, true)))
+                        .await;
+                    if let Err(e) = serve_result {
+                        error!("error serving TLS connection from {}: {}", remote_addr, e);
+                    }
+                });
+            }
+        });
+    }
+
     let listener = TcpListener::bind(listen_address).await
         .expect("failed to create TCP listener");
     loop {
@@ -422,7 +525,7 @@ async fn main() {
             let serve_result = Builder::new(TokioExecutor::new())
                 .http1()
                 .http2()
-                .serve_connection(io, service_fn(handle_request))
+                .serve_connection(io, service_fn(|req| handle_request(req, false)))
                 .await;
             if let Err(e) = serve_result {
                 error!("error serving connection from {}: {}", remote_addr, e);
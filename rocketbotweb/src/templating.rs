@@ -92,39 +92,175 @@ fn get_f64_arg(args: &HashMap<String, JsonValue>, key: &str, func: &str) -> tera
     }
 }
 
+fn srgb_channel_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_channel_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Converts a gamma-encoded sRGB color to the OkLab color space, which is (unlike sRGB)
+/// perceptually uniform, making linear interpolation within it avoid muddy midpoints.
+fn srgb_to_oklab(rgb: (f64, f64, f64)) -> (f64, f64, f64) {
+    let r = srgb_channel_to_linear(rgb.0);
+    let g = srgb_channel_to_linear(rgb.1);
+    let b = srgb_channel_to_linear(rgb.2);
+
+    let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
+    let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
+    let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+
+    let l_ = l.cbrt();
+    let m_ = m.cbrt();
+    let s_ = s.cbrt();
+
+    (
+        0.2104542553 * l_ + 0.7936177850 * m_ - 0.0040720468 * s_,
+        1.9779984951 * l_ - 2.4285922050 * m_ + 0.4505937099 * s_,
+        0.0259040371 * l_ + 0.7827717662 * m_ - 0.8086757660 * s_,
+    )
+}
+
+/// Inverse of [`srgb_to_oklab`].
+fn oklab_to_srgb(lab: (f64, f64, f64)) -> (f64, f64, f64) {
+    let (l, a, b) = lab;
+
+    let l_ = l + 0.3963377774 * a + 0.2158037573 * b;
+    let m_ = l - 0.1055613458 * a - 0.0638541728 * b;
+    let s_ = l - 0.0894841775 * a - 1.2914855480 * b;
+
+    let l_cubed = l_ * l_ * l_;
+    let m_cubed = m_ * m_ * m_;
+    let s_cubed = s_ * s_ * s_;
+
+    let r = 4.0767416621 * l_cubed - 3.3077115913 * m_cubed + 0.2309699292 * s_cubed;
+    let g = -1.2684380046 * l_cubed + 2.6097574011 * m_cubed - 0.3413193965 * s_cubed;
+    let b = -0.0041960863 * l_cubed - 0.7034186147 * m_cubed + 1.7076147010 * s_cubed;
+
+    (
+        linear_channel_to_srgb(r),
+        linear_channel_to_srgb(g),
+        linear_channel_to_srgb(b),
+    )
+}
+
+fn lerp_color(from: (f64, f64, f64), to: (f64, f64, f64), pos: f64) -> (f64, f64, f64) {
+    (
+        from.0 + pos * (to.0 - from.0),
+        from.1 + pos * (to.1 - from.1),
+        from.2 + pos * (to.2 - from.2),
+    )
+}
+
+fn mix_color_in_space(from: (f64, f64, f64), to: (f64, f64, f64), pos: f64, space: &str) -> tera::Result<(f64, f64, f64)> {
+    match space {
+        "srgb" => Ok(lerp_color(from, to, pos)),
+        "oklab" => {
+            let mixed_lab = lerp_color(srgb_to_oklab(from), srgb_to_oklab(to), pos);
+            Ok(oklab_to_srgb(mixed_lab))
+        },
+        other => Err(tera::Error::msg(format!("unknown space {:?} in mix_color", other))),
+    }
+}
+
+/// A single entry of the optional `stops` argument to [`mix_color`]: a value and the color it
+/// maps to. Stops are expected to be sorted ascending by `value`.
+struct ColorStop {
+    value: f64,
+    color: (f64, f64, f64),
+}
+
+fn get_stops_arg(args: &HashMap<String, JsonValue>) -> tera::Result<Option<Vec<ColorStop>>> {
+    let stops_json = match args.get("stops") {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    let stops_array = match stops_json.as_array() {
+        Some(a) => a,
+        None => return Err(tera::Error::msg("stops in mix_color not an array")),
+    };
+
+    let mut stops = Vec::with_capacity(stops_array.len());
+    for stop_json in stops_array {
+        let value = stop_json.get("value")
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| tera::Error::msg("stop in mix_color missing numeric value"))?;
+        let color_str = stop_json.get("color")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| tera::Error::msg("stop in mix_color missing string color"))?;
+        let color = hex_color_to_color(color_str)
+            .ok_or_else(|| tera::Error::msg("invalid color in mix_color stop"))?;
+        stops.push(ColorStop { value, color });
+    }
+
+    if stops.len() < 2 {
+        return Err(tera::Error::msg("stops in mix_color must contain at least two entries"));
+    }
+
+    Ok(Some(stops))
+}
+
 fn mix_color(args: &HashMap<String, JsonValue>) -> tera::Result<JsonValue> {
-    let min_value = get_f64_arg(args, "min_value", "mix_color")?;
-    let max_value = get_f64_arg(args, "max_value", "mix_color")?;
     let value = get_f64_arg(args, "value", "mix_color")?;
+    let space = match args.get("space") {
+        Some(s) => match s.as_str() {
+            Some(s) => s.to_owned(),
+            None => return Err(tera::Error::msg("space in mix_color not a string")),
+        },
+        None => "srgb".to_owned(),
+    };
 
-    let min_color_str = get_str_arg(args, "min_color", "mix_color")?;
-    let max_color_str = get_str_arg(args, "max_color", "mix_color")?;
+    let stops = match get_stops_arg(args)? {
+        Some(stops) => stops,
+        None => {
+            let min_value = get_f64_arg(args, "min_value", "mix_color")?;
+            let max_value = get_f64_arg(args, "max_value", "mix_color")?;
+            let min_color_str = get_str_arg(args, "min_color", "mix_color")?;
+            let max_color_str = get_str_arg(args, "max_color", "mix_color")?;
 
-    let min_color = match hex_color_to_color(min_color_str) {
-        Some(mc) => mc,
-        None => return Err(tera::Error::msg("invalid min_color in mix_color")),
-    };
-    let max_color = match hex_color_to_color(max_color_str) {
-        Some(mc) => mc,
-        None => return Err(tera::Error::msg("invalid max_color in mix_color")),
+            let min_color = match hex_color_to_color(min_color_str) {
+                Some(mc) => mc,
+                None => return Err(tera::Error::msg("invalid min_color in mix_color")),
+            };
+            let max_color = match hex_color_to_color(max_color_str) {
+                Some(mc) => mc,
+                None => return Err(tera::Error::msg("invalid max_color in mix_color")),
+            };
+
+            vec![
+                ColorStop { value: min_value, color: min_color },
+                ColorStop { value: max_value, color: max_color },
+            ]
+        },
     };
 
-    if value < min_value {
-        return Ok(color_to_hex_color(min_color));
+    if value <= stops[0].value {
+        return Ok(color_to_hex_color(stops[0].color));
     }
-    if value > max_value {
-        return Ok(color_to_hex_color(max_color));
+    let last_index = stops.len() - 1;
+    if value >= stops[last_index].value {
+        return Ok(color_to_hex_color(stops[last_index].color));
     }
 
-    // lerp
-    let value_pos = (value - min_value) / (max_value - min_value);
-    let my_color = (
-        min_color.0 + value_pos * (max_color.0 - min_color.0),
-        min_color.1 + value_pos * (max_color.1 - min_color.1),
-        min_color.2 + value_pos * (max_color.2 - min_color.2),
-    );
+    for window in stops.windows(2) {
+        let (lower, upper) = (&window[0], &window[1]);
+        if value >= lower.value && value <= upper.value {
+            let value_pos = (value - lower.value) / (upper.value - lower.value);
+            let my_color = mix_color_in_space(lower.color, upper.color, value_pos, &space)?;
+            return Ok(color_to_hex_color(my_color));
+        }
+    }
 
-    Ok(color_to_hex_color(my_color))
+    Err(tera::Error::msg("value in mix_color does not fall within any pair of stops"))
 }
 
 pub(crate) fn augment_tera(tera: &mut Tera) {
@@ -11,6 +11,10 @@ pub(crate) struct Error400Template {
     pub reason: String,
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, Template)]
+#[template(path = "401.html")]
+pub(crate) struct Error401Template;
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize, Template)]
 #[template(path = "404.html")]
 pub(crate) struct Error404Template;
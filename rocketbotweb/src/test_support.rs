@@ -0,0 +1,71 @@
+//! Database fixtures for integration-style tests of the bim web handlers.
+//!
+//! [`with_rides_fixture`] opens a connection to the database named by the
+//! `ROCKETBOTWEB_TEST_DB_CONN_STRING` environment variable, inserts the given `bim.rides` rows
+//! inside a transaction, runs the caller's test body against that transaction, and always rolls
+//! it back afterwards so the database is left exactly as it was found. All such tests share one
+//! physical database, so they serialize behind [`DB_TEST_MUTEX`] to avoid observing each other's
+//! in-progress fixtures.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
+use tokio_postgres::NoTls;
+
+
+/// Serializes tests that touch the real database; fixtures from two concurrently running tests
+/// would otherwise be visible to each other's queries.
+pub(crate) static DB_TEST_MUTEX: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+
+/// A `bim.rides` row to insert as a fixture.
+pub(crate) struct RideFixture {
+    pub company: &'static str,
+    pub rider_username: &'static str,
+    pub timestamp: DateTime<Utc>,
+    pub line: Option<&'static str>,
+}
+
+/// Connects to the test database, inserts `rides` as `bim.rides` rows within a transaction, runs
+/// `body` against that transaction, then rolls it back so none of it is persisted.
+///
+/// Panics (failing the test) if `ROCKETBOTWEB_TEST_DB_CONN_STRING` is unset, or if the connection
+/// or any fixture insert fails.
+pub(crate) async fn with_rides_fixture<R>(
+    rides: &[RideFixture],
+    body: impl for<'c> FnOnce(&'c tokio_postgres::Transaction<'c>) -> Pin<Box<dyn Future<Output = R> + 'c>>,
+) -> R {
+    let _guard = DB_TEST_MUTEX.lock().await;
+
+    let conn_string = std::env::var("ROCKETBOTWEB_TEST_DB_CONN_STRING")
+        .expect("ROCKETBOTWEB_TEST_DB_CONN_STRING must be set to run database-backed tests");
+    let (mut client, connection) = tokio_postgres::connect(&conn_string, NoTls).await
+        .expect("failed to connect to test database");
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("test database connection error: {}", e);
+        }
+    });
+
+    let transaction = client.transaction().await
+        .expect("failed to begin test transaction");
+    for ride in rides {
+        transaction.execute(
+            "
+                INSERT INTO bim.rides (company, rider_username, \"timestamp\", line)
+                VALUES ($1, $2, $3, $4)
+            ",
+            &[&ride.company, &ride.rider_username, &ride.timestamp, &ride.line],
+        ).await.expect("failed to insert ride fixture");
+    }
+
+    let result = body(&transaction).await;
+
+    transaction.rollback().await
+        .expect("failed to roll back test transaction");
+
+    result
+}
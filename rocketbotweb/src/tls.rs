@@ -0,0 +1,71 @@
+//! TLS termination for the embedded bim web server, loaded from PEM files and hot-reloadable
+//! (see [`TlsConfigHolder::reload`], driven from `main.rs` in response to SIGHUP) so a renewed
+//! certificate takes effect without restarting the server.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use rustls::ServerConfig;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio::sync::RwLock;
+use tracing::{error, info};
+
+
+fn load_server_config(cert_path: &Path, key_path: &Path) -> Result<ServerConfig, String> {
+    let cert_file = File::open(cert_path)
+        .map_err(|e| format!("failed to open TLS certificate {:?}: {}", cert_path, e))?;
+    let certs: Vec<CertificateDer<'static>> = rustls_pemfile::certs(&mut BufReader::new(cert_file))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("failed to parse TLS certificate {:?}: {}", cert_path, e))?;
+
+    let key_file = File::open(key_path)
+        .map_err(|e| format!("failed to open TLS private key {:?}: {}", key_path, e))?;
+    let key: PrivateKeyDer<'static> = rustls_pemfile::private_key(&mut BufReader::new(key_file))
+        .map_err(|e| format!("failed to parse TLS private key {:?}: {}", key_path, e))?
+        .ok_or_else(|| format!("no private key found in {:?}", key_path))?;
+
+    ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("failed to build TLS server config from {:?} and {:?}: {}", cert_path, key_path, e))
+}
+
+
+/// Holds the currently active TLS server config for the TLS listener, swapped out in place when
+/// the certificate is reloaded.
+pub(crate) struct TlsConfigHolder {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: RwLock<Arc<ServerConfig>>,
+}
+impl TlsConfigHolder {
+    pub(crate) fn load(cert_path: PathBuf, key_path: PathBuf) -> Result<Self, String> {
+        let config = load_server_config(&cert_path, &key_path)?;
+        Ok(Self {
+            cert_path,
+            key_path,
+            current: RwLock::new(Arc::new(config)),
+        })
+    }
+
+    pub(crate) async fn current(&self) -> Arc<ServerConfig> {
+        self.current.read().await.clone()
+    }
+
+    /// Reloads the certificate and key from the paths this holder was created with, replacing
+    /// the active config on success. On failure, logs the error and keeps serving the
+    /// previously loaded certificate.
+    pub(crate) async fn reload(&self) {
+        match load_server_config(&self.cert_path, &self.key_path) {
+            Ok(new_config) => {
+                *self.current.write().await = Arc::new(new_config);
+                info!("reloaded TLS certificate from {:?}", self.cert_path);
+            },
+            Err(e) => {
+                error!("failed to reload TLS certificate, keeping previous one: {}", e);
+            },
+        }
+    }
+}